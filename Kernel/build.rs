@@ -0,0 +1,72 @@
+/*! Kernel build script
+ *
+ * Embeds the `Root/` sysroot of the repository into the kernel image, so
+ * the kernel could populate his root filesystem without any disk
+ */
+
+use std::{
+    env,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf
+    }
+};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let sysroot_dir = manifest_dir.join("../Root")
+                                  .canonicalize()
+                                  .expect("Missing Root/ sysroot directory");
+    let out_file = PathBuf::from(env::var("OUT_DIR").unwrap()).join("sysroot_entries.rs");
+
+    /* collect the entries, each directory precedes his children */
+    let mut sysroot_entries = Vec::new();
+    collect_sysroot_entries(&sysroot_dir, &sysroot_dir, &mut sysroot_entries)
+        .expect("Failed to walk the Root/ sysroot");
+
+    let mut out_content = String::from("&[\n");
+    for (vfs_path, host_path, is_dir) in sysroot_entries.iter() {
+        if *is_dir {
+            out_content.push_str(&format!("    SysRootEntry::Dir({:?}),\n", vfs_path));
+        } else {
+            out_content.push_str(&format!("    SysRootEntry::File({:?}, \
+                                           include_bytes!({:?})),\n",
+                                          vfs_path, host_path));
+        }
+    }
+    out_content.push_str("]\n");
+    fs::write(out_file, out_content).expect("Failed to write the sysroot entries");
+
+    println!("cargo:rerun-if-changed={}", sysroot_dir.display());
+    for (_, host_path, _) in sysroot_entries.iter() {
+        println!("cargo:rerun-if-changed={}", host_path.display());
+    }
+}
+
+/**
+ * Walks recursively the given directory collecting the VFS path, the host
+ * path and the type of each entry
+ */
+fn collect_sysroot_entries(sysroot_dir: &Path,
+                           dir_path: &Path,
+                           sysroot_entries: &mut Vec<(String, PathBuf, bool)>)
+                           -> io::Result<()> {
+    let mut dir_entries = fs::read_dir(dir_path)?.collect::<io::Result<Vec<_>>>()?;
+    dir_entries.sort_by_key(|dir_entry| dir_entry.file_name());
+
+    for dir_entry in dir_entries {
+        let host_path = dir_entry.path();
+        let vfs_path =
+            format!("/{}",
+                    host_path.strip_prefix(sysroot_dir).unwrap().to_string_lossy());
+        if dir_entry.file_type()?.is_dir() {
+            sysroot_entries.push((vfs_path, host_path.clone(), true));
+            collect_sysroot_entries(sysroot_dir, &host_path, sysroot_entries)?;
+        } else {
+            sysroot_entries.push((vfs_path, host_path, false));
+        }
+    }
+    Ok(())
+}
@@ -25,6 +25,7 @@ use crate::{
     },
     arch::interrupts::apic_manager::ApicManager,
     dbg_println,
+    dev::pci::PciEcamRegion,
     processor::{
         CpuCoreId,
         Processor
@@ -371,6 +372,23 @@ struct FixedAcpiDescTable {
     m_dsdt: u32
 }
 
+#[repr(C)]
+#[repr(packed)]
+struct McfgTableEntry {
+    m_header: SystemDescTable,
+    _reserved: u64
+}
+
+#[repr(C)]
+#[repr(packed)]
+struct McfgAllocationEntry {
+    m_base_addr: u64,
+    m_segment: u16,
+    m_start_bus: u8,
+    m_end_bus: u8,
+    _reserved: u32
+}
+
 #[repr(C)]
 #[repr(packed)]
 struct ApicTableEntry {
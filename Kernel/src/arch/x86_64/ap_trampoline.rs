@@ -0,0 +1,143 @@
+/*! x86_64 application processors bootstrap trampoline */
+
+use core::{
+    ptr,
+    ptr::{
+        read_volatile,
+        write_volatile
+    }
+};
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    vm::{
+        mem_manager::MemManager,
+        Page4KiB
+    }
+};
+
+extern "C" {
+    static ap_trampoline_begin: u8;
+    static ap_trampoline_args: u8;
+    static ap_trampoline_end: u8;
+}
+
+/**
+ * Real mode code which brings the APs into the higher half long mode.
+ *
+ * The code is copied below the first MiB, which is never given to the
+ * physical frames allocator, and identity mapped since the APs enable the
+ * paging while still executing it
+ */
+pub struct ApTrampoline {
+    m_args: *mut ApTrampolineArgs
+}
+
+impl ApTrampoline /* Constants */ {
+    /**
+     * Physical address where the trampoline code is copied, must be
+     * page aligned and below the first MiB
+     */
+    pub const PHYS_ADDR: usize = 0x8000;
+}
+
+impl ApTrampoline /* Constructors */ {
+    /**
+     * Identity maps the trampoline page into the kernel `PageDir` and
+     * copies the trampoline code into it
+     */
+    pub fn install() -> Self {
+        let (trampoline_begin, trampoline_size, args_offset) = unsafe {
+            let trampoline_begin = &ap_trampoline_begin as *const u8;
+            (trampoline_begin,
+             &ap_trampoline_end as *const u8 as usize - trampoline_begin as usize,
+             &ap_trampoline_args as *const u8 as usize - trampoline_begin as usize)
+        };
+        assert!(trampoline_size <= Page4KiB::SIZE,
+                "AP trampoline code exceeds the page size");
+
+        /* the APs execute the trampoline from the same address before and after
+         * the paging activation
+         */
+        let trampoline_virt_addr: VirtAddr = Self::PHYS_ADDR.into();
+        MemManager::instance().kernel_page_dir()
+                              .ensure_page_table_entry::<Page4KiB>(trampoline_virt_addr)
+                              .expect("Failed to map the AP trampoline")
+                              .set_phys_frame(Self::PHYS_ADDR.into())
+                              .set_present(true)
+                              .set_readable(true)
+                              .set_writeable(true)
+                              .set_user(false);
+
+        unsafe {
+            ptr::copy_nonoverlapping(trampoline_begin,
+                                     trampoline_virt_addr.as_ptr_mut(),
+                                     trampoline_size);
+        }
+
+        Self { m_args: trampoline_virt_addr.offset(args_offset).as_ptr_mut() }
+    }
+}
+
+impl ApTrampoline /* Methods */ {
+    /**
+     * Writes the arguments for the next AP which executes the trampoline
+     */
+    pub fn prepare(&self, page_dir_phys_frame: PhysAddr, ap_stack_bottom: VirtAddr) {
+        /* the page directory is loaded into <cr3> while in protected mode */
+        assert!(*page_dir_phys_frame <= u32::MAX as usize,
+                "Kernel page directory must be below 4GiB to start the APs");
+
+        unsafe {
+            write_volatile(self.m_args,
+                           ApTrampolineArgs { m_page_dir: *page_dir_phys_frame as u64,
+                                              m_stack_bottom: *ap_stack_bottom as u64,
+                                              m_is_started: 0 });
+        }
+    }
+
+    /**
+     * Removes the identity mapping of the trampoline page.
+     *
+     * Must be called only once all the started APs are executing the
+     * higher half code
+     */
+    pub fn uninstall(self) {
+        let trampoline_virt_addr: VirtAddr = Self::PHYS_ADDR.into();
+        MemManager::instance().kernel_page_dir()
+                              .ensure_page_table_entry::<Page4KiB>(trampoline_virt_addr)
+                              .expect("Failed to unmap the AP trampoline")
+                              .set_unused();
+    }
+}
+
+impl ApTrampoline /* Getters */ {
+    /**
+     * Returns the startup vector to send with the SIPI
+     */
+    pub fn startup_vector(&self) -> u8 {
+        (Self::PHYS_ADDR >> 12) as u8
+    }
+
+    /**
+     * Returns whether the last prepared AP consumed his arguments
+     */
+    pub fn is_ap_started(&self) -> bool {
+        unsafe { read_volatile(&(*self.m_args).m_is_started) != 0 }
+    }
+}
+
+/**
+ * Arguments area at the end of the trampoline code, keep in sync with
+ * `ap_trampoline_args` in `ap_trampoline.S`
+ */
+#[repr(C)]
+struct ApTrampolineArgs {
+    m_page_dir: u64,
+    m_stack_bottom: u64,
+    m_is_started: u64
+}
@@ -0,0 +1,1160 @@
+/*! x86_64 ATA/ATAPI implementation
+ *
+ * Drives the disks and the CD-ROMs attached to the IDE controllers found
+ * on the PCI bus, the data is transferred with the bus-master DMA when the
+ * IDE controller supports it, otherwise with the PIO.
+ *
+ * The commands are completed in polling, the interrupts of the drives are
+ * masked with the `nIEN` bit of the device control register
+ */
+
+use alloc::{
+    string::String,
+    sync::Arc
+};
+use core::{
+    convert::TryFrom,
+    hint::spin_loop,
+    ptr
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::device::{
+        DeviceId,
+        DeviceIdClass,
+        DeviceIdType
+    }
+};
+use bits::bit_flags::{
+    BitFlags,
+    TBitFlagsValues
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::phys_addr::PhysAddr,
+    arch::x86_64::io_port::IoPort,
+    dev::{
+        pci::{
+            PciFunction,
+            PciMatch,
+            TPciDriver
+        },
+        storage::{
+            StorageResult,
+            TStorageDevice
+        },
+        DevManager,
+        TDevice
+    },
+    vm::{
+        buddy_allocator::PhysZone,
+        mem_manager::MemManager
+    }
+};
+
+/**
+ * x86_64 `TStorageDevice` implementation for the ATA disks and the ATAPI
+ * CD-ROMs
+ */
+pub struct X64AtaDevice {
+    m_device_id: DeviceId,
+    m_channel: Arc<X64AtaChannel>,
+    m_drive: AtaDrive,
+    m_drive_info: SpinMutex<Option<AtaDriveInfo>>
+}
+
+impl X64AtaDevice /* Constructors */ {
+    /**
+     * Constructs an uninitialized `X64AtaDevice` for the given drive of
+     * the given `X64AtaChannel`
+     */
+    pub fn new(channel: Arc<X64AtaChannel>, drive: AtaDrive, serial_value: u32) -> Self {
+        Self { m_device_id: DeviceId::new(DeviceIdType::Block,
+                                          DeviceIdClass::Storage,
+                                          serial_value),
+               m_channel: channel,
+               m_drive: drive,
+               m_drive_info: SpinMutex::const_new(None) }
+    }
+}
+
+impl X64AtaDevice /* Privates */ {
+    /**
+     * Returns a copy of the `AtaDriveInfo` read by `init_hw()`
+     */
+    fn drive_info(&self) -> StorageResult<AtaDriveInfo> {
+        self.m_drive_info
+            .lock()
+            .clone()
+            .ok_or((OsErrorClass::ReferenceNotFound, Some("ATA drive not initialized")))
+    }
+
+    /**
+     * Transfers the given blocks, split into the biggest transfers
+     * accepted by the channel
+     */
+    fn transfer_blocks(&self,
+                       first_block: u64,
+                       mut buffer: AtaBuffer)
+                       -> StorageResult<()> {
+        let drive_info = self.drive_info()?;
+        if buffer.len() % drive_info.m_block_size != 0 {
+            return Err((OsErrorClass::InvalidArgument,
+                        Some("Buffer not multiple of the block size")));
+        }
+
+        let blocks_count = (buffer.len() / drive_info.m_block_size) as u64;
+        if first_block.checked_add(blocks_count)
+                      .map_or(true, |end_block| end_block > drive_info.m_blocks_count)
+        {
+            return Err((OsErrorClass::EndOfDataReached, Some("Block out of the media")));
+        }
+
+        let mut channel_regs = self.m_channel.m_regs.lock();
+        let transfer_blocks_max =
+            channel_regs.transfer_size_max(&drive_info) / drive_info.m_block_size;
+
+        let mut transferred_blocks = 0;
+        while transferred_blocks < blocks_count {
+            let transfer_blocks =
+                (blocks_count - transferred_blocks).min(transfer_blocks_max as u64)
+                as usize;
+            let chunk_start = transferred_blocks as usize * drive_info.m_block_size;
+            let chunk_end = chunk_start + transfer_blocks * drive_info.m_block_size;
+
+            let chunk_buffer = match &mut buffer {
+                AtaBuffer::Read(read_buffer) => {
+                    AtaBuffer::Read(&mut read_buffer[chunk_start..chunk_end])
+                },
+                AtaBuffer::Write(write_buffer) => {
+                    AtaBuffer::Write(&write_buffer[chunk_start..chunk_end])
+                },
+            };
+
+            let lba = first_block + transferred_blocks;
+            match (chunk_buffer, drive_info.m_kind) {
+                (chunk_buffer, AtaDriveKind::Ata) => {
+                    channel_regs.ata_transfer(self.m_drive,
+                                              &drive_info,
+                                              lba,
+                                              chunk_buffer)?
+                },
+                (AtaBuffer::Read(read_chunk), AtaDriveKind::Atapi) => {
+                    let read_packet =
+                        scsi_read10_packet(lba as u32, transfer_blocks as u16);
+                    channel_regs.atapi_packet(self.m_drive,
+                                              &drive_info,
+                                              &read_packet,
+                                              read_chunk)?
+                },
+                (AtaBuffer::Write(_), AtaDriveKind::Atapi) => {
+                    return Err((OsErrorClass::OperationNotEnabled,
+                                Some("Read-only ATAPI media")));
+                }
+            }
+            transferred_blocks += transfer_blocks as u64;
+        }
+
+        Ok(())
+    }
+}
+
+impl TDevice for X64AtaDevice {
+    fn device_id(&self) -> DeviceId {
+        self.m_device_id
+    }
+
+    fn device_name(&self) -> String {
+        let drive_prefix = match self.m_drive_info.lock().as_ref().map(|info| info.m_kind)
+        {
+            Some(AtaDriveKind::Atapi) => "cdrom",
+            _ => "ata"
+        };
+        format!("{}_{}", drive_prefix, self.m_device_id.serial_value())
+    }
+
+    fn init_hw(&self) -> bool {
+        let drive_info = self.m_channel.m_regs.lock().identify(self.m_drive);
+        match drive_info {
+            Some(drive_info) => {
+                *self.m_drive_info.lock() = Some(drive_info);
+                true
+            },
+            None => false
+        }
+    }
+
+    fn as_storage(&self) -> Option<&dyn TStorageDevice> {
+        Some(self)
+    }
+}
+
+impl TStorageDevice for X64AtaDevice {
+    fn block_size(&self) -> usize {
+        self.drive_info().map_or(0, |drive_info| drive_info.m_block_size)
+    }
+
+    fn blocks_count(&self) -> u64 {
+        self.drive_info().map_or(0, |drive_info| drive_info.m_blocks_count)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.drive_info()
+            .map_or(true, |drive_info| drive_info.m_kind == AtaDriveKind::Atapi)
+    }
+
+    fn read_blocks(&self, first_block: u64, buffer: &mut [u8]) -> StorageResult<()> {
+        self.transfer_blocks(first_block, AtaBuffer::Read(buffer))
+    }
+
+    fn write_blocks(&self, first_block: u64, buffer: &[u8]) -> StorageResult<()> {
+        self.transfer_blocks(first_block, AtaBuffer::Write(buffer))
+    }
+
+    fn flush(&self) -> StorageResult<()> {
+        let drive_info = self.drive_info()?;
+        if drive_info.m_kind == AtaDriveKind::Atapi {
+            return Ok(());
+        }
+        self.m_channel.m_regs.lock().flush_cache(self.m_drive, &drive_info)
+    }
+}
+
+/**
+ * IDE channel shared by the master and the slave drives
+ */
+pub struct X64AtaChannel {
+    m_regs: SpinMutex<AtaChannelRegs>
+}
+
+impl X64AtaChannel /* Constants */ {
+    const PRIMARY_IO_BASE: u16 = 0x1F0;
+    const PRIMARY_CTRL_BASE: u16 = 0x3F6;
+    const SECONDARY_IO_BASE: u16 = 0x170;
+    const SECONDARY_CTRL_BASE: u16 = 0x376;
+    const SECONDARY_BUS_MASTER_OFFSET: u16 = 8;
+}
+
+impl X64AtaChannel /* Constructors */ {
+    /**
+     * Constructs the primary `X64AtaChannel`, the bus-master DMA is used
+     * when the I/O base of the IDE controller's bus-master is given
+     */
+    pub fn new_primary(bus_master_base: Option<u16>) -> Self {
+        Self::new(Self::PRIMARY_IO_BASE, Self::PRIMARY_CTRL_BASE, bus_master_base)
+    }
+
+    /**
+     * Constructs the secondary `X64AtaChannel`, the bus-master DMA is used
+     * when the I/O base of the IDE controller's bus-master is given
+     */
+    pub fn new_secondary(bus_master_base: Option<u16>) -> Self {
+        Self::new(Self::SECONDARY_IO_BASE,
+                  Self::SECONDARY_CTRL_BASE,
+                  bus_master_base.map(|bus_master_base| {
+                                     bus_master_base + Self::SECONDARY_BUS_MASTER_OFFSET
+                                 }))
+    }
+
+    /**
+     * Constructs an `X64AtaChannel` with the given I/O bases, used for the
+     * channels of the IDE controllers in native mode
+     */
+    pub fn new(io_base: u16, ctrl_base: u16, bus_master_base: Option<u16>) -> Self {
+        let regs = AtaChannelRegs { m_data: IoPort::new(io_base),
+                                    m_error_features: IoPort::new(io_base + 1),
+                                    m_sector_count: IoPort::new(io_base + 2),
+                                    m_lba_low: IoPort::new(io_base + 3),
+                                    m_lba_mid: IoPort::new(io_base + 4),
+                                    m_lba_high: IoPort::new(io_base + 5),
+                                    m_drive_select: IoPort::new(io_base + 6),
+                                    m_status_command: IoPort::new(io_base + 7),
+                                    m_alt_status_ctrl: IoPort::new(ctrl_base),
+                                    m_bus_master:
+                                        bus_master_base.and_then(AtaBusMaster::new),
+                                    m_selected: None };
+
+        /* the commands are completed in polling */
+        unsafe {
+            regs.m_alt_status_ctrl.write(AtaChannelRegs::CTRL_INTR_DISABLED);
+        }
+        Self { m_regs: SpinMutex::const_new(regs) }
+    }
+}
+
+/**
+ * `TPciDriver` for the IDE controllers, registers an `X64AtaDevice` for
+ * each drive found into the two channels
+ */
+pub struct X64AtaPciDriver;
+
+impl X64AtaPciDriver /* Constants */ {
+    const PCI_MATCHES: [PciMatch; 1] = [PciMatch::by_class(0x01, 0x01)];
+
+    const PROG_IF_PRIMARY_NATIVE: u8 = 1 << 0;
+    const PROG_IF_SECONDARY_NATIVE: u8 = 1 << 2;
+}
+
+impl X64AtaPciDriver /* Privates */ {
+    /**
+     * Constructs the `X64AtaChannel` described by the I/O BARs starting
+     * from the given index, used when the channel is in native mode
+     */
+    fn native_channel(pci_function: &PciFunction,
+                      bar_index: usize,
+                      bus_master_base: Option<u16>)
+                      -> Option<X64AtaChannel> {
+        let io_base = pci_function.bar(bar_index)?.io_port()?;
+        let ctrl_base = pci_function.bar(bar_index + 1)?.io_port()?;
+
+        /* the alternate status register is the third of the control block */
+        Some(X64AtaChannel::new(io_base, ctrl_base + 2, bus_master_base))
+    }
+}
+
+impl TPciDriver for X64AtaPciDriver {
+    fn driver_name(&self) -> &'static str {
+        "ata"
+    }
+
+    fn pci_matches(&self) -> &[PciMatch] {
+        &Self::PCI_MATCHES
+    }
+
+    fn probe(&self, pci_function: &Arc<PciFunction>) -> bool {
+        /* BAR4 is the bus-master I/O space, shared by the two channels */
+        let bus_master_base =
+            pci_function.bar(4).and_then(|bar| bar.io_port()).filter(|port| *port != 0);
+
+        pci_function.enable_decoding();
+        if bus_master_base.is_some() {
+            pci_function.enable_bus_master();
+        }
+
+        /* the channels in compatibility mode use the legacy I/O ports */
+        let prog_if = pci_function.prog_if();
+        let primary_channel = if prog_if & Self::PROG_IF_PRIMARY_NATIVE != 0 {
+            Self::native_channel(pci_function, 0, bus_master_base)
+        } else {
+            Some(X64AtaChannel::new_primary(bus_master_base))
+        };
+        let secondary_channel = if prog_if & Self::PROG_IF_SECONDARY_NATIVE != 0 {
+            let bus_master_offset = X64AtaChannel::SECONDARY_BUS_MASTER_OFFSET;
+            let secondary_bus_master_base =
+                bus_master_base.map(|base| base + bus_master_offset);
+            Self::native_channel(pci_function, 2, secondary_bus_master_base)
+        } else {
+            Some(X64AtaChannel::new_secondary(bus_master_base))
+        };
+
+        let dev_manager = DevManager::instance();
+        for ata_channel in primary_channel.into_iter().chain(secondary_channel) {
+            let ata_channel = Arc::new(ata_channel);
+            for ata_drive in [AtaDrive::Master, AtaDrive::Slave].iter() {
+                let serial_value = dev_manager.next_serial_value(DeviceIdClass::Storage);
+                dev_manager.register_device(X64AtaDevice::new(ata_channel.clone(),
+                                                              *ata_drive,
+                                                              serial_value));
+            }
+        }
+        true
+    }
+}
+
+/**
+ * Lists the drives of an IDE channel
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum AtaDrive {
+    Master,
+    Slave
+}
+
+impl AtaDrive /* Getters */ {
+    /**
+     * Returns the drive selection bit of the drive/head register
+     */
+    fn select_bit(&self) -> u8 {
+        match self {
+            Self::Master => 0,
+            Self::Slave => 1 << 4
+        }
+    }
+}
+
+/**
+ * Lists the kinds of the attached drives
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+enum AtaDriveKind {
+    Ata,
+    Atapi
+}
+
+/**
+ * Informations collected with the `IDENTIFY` commands
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+struct AtaDriveInfo {
+    m_kind: AtaDriveKind,
+    m_block_size: usize,
+    m_blocks_count: u64,
+    m_supports_lba48: bool,
+    m_supports_dma: bool
+}
+
+/**
+ * Buffer of a transfer, with the direction
+ */
+enum AtaBuffer<'a> {
+    Read(&'a mut [u8]),
+    Write(&'a [u8])
+}
+
+impl<'a> AtaBuffer<'a> /* Getters */ {
+    /**
+     * Returns the size in bytes of the buffer
+     */
+    fn len(&self) -> usize {
+        match self {
+            Self::Read(read_buffer) => read_buffer.len(),
+            Self::Write(write_buffer) => write_buffer.len()
+        }
+    }
+}
+
+/**
+ * Registers of an IDE channel, accessed only with the channel lock
+ */
+struct AtaChannelRegs {
+    m_data: IoPort<u16>,
+    m_error_features: IoPort<u8>,
+    m_sector_count: IoPort<u8>,
+    m_lba_low: IoPort<u8>,
+    m_lba_mid: IoPort<u8>,
+    m_lba_high: IoPort<u8>,
+    m_drive_select: IoPort<u8>,
+    m_status_command: IoPort<u8>,
+    m_alt_status_ctrl: IoPort<u8>,
+    m_bus_master: Option<AtaBusMaster>,
+    m_selected: Option<AtaDrive>
+}
+
+impl AtaChannelRegs /* Constants */ {
+    /**
+     * Maximum amount of status polls before to give up a command
+     */
+    const POLLS_MAX: usize = 10_000_000;
+
+    const SECTOR_SIZE: usize = 512;
+    const ATAPI_SECTOR_SIZE: usize = 2048;
+    const PIO_SECTORS_MAX: usize = 128;
+
+    const CTRL_INTR_DISABLED: u8 = 1 << 1;
+    const DRIVE_SELECT_BASE: u8 = 0xA0;
+    const DRIVE_SELECT_LBA: u8 = 1 << 6;
+
+    const ATAPI_SIGNATURE: (u8, u8) = (0x14, 0xEB);
+    const SATAPI_SIGNATURE: (u8, u8) = (0x69, 0x96);
+
+    const CMD_READ_PIO: u8 = 0x20;
+    const CMD_READ_PIO_EXT: u8 = 0x24;
+    const CMD_READ_DMA: u8 = 0xC8;
+    const CMD_READ_DMA_EXT: u8 = 0x25;
+    const CMD_WRITE_PIO: u8 = 0x30;
+    const CMD_WRITE_PIO_EXT: u8 = 0x34;
+    const CMD_WRITE_DMA: u8 = 0xCA;
+    const CMD_WRITE_DMA_EXT: u8 = 0x35;
+    const CMD_FLUSH_CACHE: u8 = 0xE7;
+    const CMD_FLUSH_CACHE_EXT: u8 = 0xEA;
+    const CMD_PACKET: u8 = 0xA0;
+    const CMD_IDENTIFY_PACKET: u8 = 0xA1;
+    const CMD_IDENTIFY: u8 = 0xEC;
+
+    const PACKET_FEATURE_DMA: u8 = 1 << 0;
+    const SCSI_READ_CAPACITY: u8 = 0x25;
+}
+
+impl AtaChannelRegs /* Methods */ {
+    /**
+     * Identifies the given drive, `None` is returned when nothing is
+     * attached
+     */
+    fn identify(&mut self, drive: AtaDrive) -> Option<AtaDriveInfo> {
+        self.select_drive(drive, 0);
+
+        /* the floating bus reads as <0xFF> */
+        if self.status().raw_bits() == 0xFF {
+            return None;
+        }
+
+        unsafe {
+            self.m_sector_count.write(0);
+            self.m_lba_low.write(0);
+            self.m_lba_mid.write(0);
+            self.m_lba_high.write(0);
+            self.m_status_command.write(Self::CMD_IDENTIFY);
+        }
+        if self.status().raw_bits() == 0 {
+            return None;
+        }
+
+        /* the packet devices abort the command and leave their signature */
+        let mut identify_data = [0; 256];
+        let kind = match self.wait_data_request() {
+            Ok(_) => AtaDriveKind::Ata,
+            Err(_) => {
+                let signature =
+                    unsafe { (self.m_lba_mid.read(), self.m_lba_high.read()) };
+                if signature != Self::ATAPI_SIGNATURE
+                   && signature != Self::SATAPI_SIGNATURE
+                {
+                    return None;
+                }
+
+                unsafe {
+                    self.m_status_command.write(Self::CMD_IDENTIFY_PACKET);
+                }
+                self.wait_data_request().ok()?;
+                AtaDriveKind::Atapi
+            }
+        };
+        for identify_word in identify_data.iter_mut() {
+            *identify_word = unsafe { self.m_data.read() };
+        }
+
+        /* DMA is usable only with both the drive and the controller support */
+        let supports_dma =
+            identify_data[49] & (1 << 8) != 0 && self.m_bus_master.is_some();
+        let mut drive_info = AtaDriveInfo { m_kind: kind,
+                                            m_block_size: Self::SECTOR_SIZE,
+                                            m_blocks_count: 0,
+                                            m_supports_lba48: false,
+                                            m_supports_dma: supports_dma };
+        match kind {
+            AtaDriveKind::Ata => {
+                drive_info.m_supports_lba48 = identify_data[83] & (1 << 10) != 0;
+                drive_info.m_blocks_count = if drive_info.m_supports_lba48 {
+                    (0..4).fold(0, |blocks_count, word_index| {
+                              blocks_count
+                              | (identify_data[100 + word_index] as u64)
+                                << (word_index * 16)
+                          })
+                } else {
+                    identify_data[60] as u64 | (identify_data[61] as u64) << 16
+                };
+            },
+            AtaDriveKind::Atapi => {
+                /* the media could be missing, the CD-ROM is read-only anyway */
+                drive_info.m_block_size = Self::ATAPI_SECTOR_SIZE;
+                drive_info.m_blocks_count =
+                    self.read_capacity(drive, &drive_info).unwrap_or(0);
+            }
+        }
+        Some(drive_info)
+    }
+
+    /**
+     * Reads or writes the given sectors of an ATA disk
+     */
+    fn ata_transfer(&mut self,
+                    drive: AtaDrive,
+                    drive_info: &AtaDriveInfo,
+                    lba: u64,
+                    buffer: AtaBuffer)
+                    -> StorageResult<()> {
+        let sectors_count = buffer.len() / Self::SECTOR_SIZE;
+        let is_write = matches!(buffer, AtaBuffer::Write(_));
+        let use_dma = drive_info.m_supports_dma;
+
+        let command = match (is_write, use_dma, drive_info.m_supports_lba48) {
+            (false, false, false) => Self::CMD_READ_PIO,
+            (false, false, true) => Self::CMD_READ_PIO_EXT,
+            (false, true, false) => Self::CMD_READ_DMA,
+            (false, true, true) => Self::CMD_READ_DMA_EXT,
+            (true, false, false) => Self::CMD_WRITE_PIO,
+            (true, false, true) => Self::CMD_WRITE_PIO_EXT,
+            (true, true, false) => Self::CMD_WRITE_DMA,
+            (true, true, true) => Self::CMD_WRITE_DMA_EXT
+        };
+        if !drive_info.m_supports_lba48 && lba + sectors_count as u64 > 1 << 28 {
+            return Err((OsErrorClass::LimitOverflow, Some("LBA28 limit overflowed")));
+        }
+
+        /* the DMA is prepared before to issue the command */
+        if use_dma {
+            self.bus_master().prepare(&buffer);
+        }
+        self.write_lba(drive, drive_info, lba, sectors_count);
+        unsafe {
+            self.m_status_command.write(command);
+        }
+
+        if use_dma {
+            self.complete_dma(buffer)
+        } else {
+            match buffer {
+                AtaBuffer::Read(read_buffer) => {
+                    for sector in read_buffer.chunks_mut(Self::SECTOR_SIZE) {
+                        self.wait_data_request()?;
+                        self.read_pio_data(sector);
+                    }
+                },
+                AtaBuffer::Write(write_buffer) => {
+                    for sector in write_buffer.chunks(Self::SECTOR_SIZE) {
+                        self.wait_data_request()?;
+                        self.write_pio_data(sector);
+                    }
+                    self.wait_not_busy()?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /**
+     * Sends the given SCSI packet to an ATAPI drive and reads his
+     * response into the given buffer
+     */
+    fn atapi_packet(&mut self,
+                    drive: AtaDrive,
+                    drive_info: &AtaDriveInfo,
+                    packet: &[u8; 12],
+                    buffer: &mut [u8])
+                    -> StorageResult<()> {
+        let use_dma = drive_info.m_supports_dma
+                      && buffer.len() % Self::ATAPI_SECTOR_SIZE == 0
+                      && !buffer.is_empty();
+        self.select_drive(drive, 0);
+        self.wait_not_busy()?;
+
+        /* the byte count limit is the size of each PIO data request */
+        if use_dma {
+            self.bus_master().prepare(&AtaBuffer::Read(buffer));
+        }
+        unsafe {
+            self.m_error_features.write(if use_dma {
+                                            Self::PACKET_FEATURE_DMA
+                                        } else {
+                                            0
+                                        });
+            self.m_lba_mid.write(Self::ATAPI_SECTOR_SIZE as u8);
+            self.m_lba_high.write((Self::ATAPI_SECTOR_SIZE >> 8) as u8);
+            self.m_status_command.write(Self::CMD_PACKET);
+        }
+
+        self.wait_data_request()?;
+        for packet_word in packet.chunks(2) {
+            unsafe {
+                self.m_data.write(u16::from_le_bytes([packet_word[0], packet_word[1]]));
+            }
+        }
+
+        if use_dma {
+            return self.complete_dma(AtaBuffer::Read(buffer));
+        }
+
+        /* each data request transfers the byte count given by the drive */
+        let mut read_bytes = 0;
+        while read_bytes < buffer.len() {
+            self.wait_data_request()?;
+            let byte_count = unsafe {
+                self.m_lba_mid.read() as usize | (self.m_lba_high.read() as usize) << 8
+            };
+            let chunk_end = (read_bytes + byte_count).min(buffer.len());
+            self.read_pio_data(&mut buffer[read_bytes..chunk_end]);
+
+            /* discard what doesn't fit into the buffer */
+            for _ in (chunk_end - read_bytes..byte_count).step_by(2) {
+                unsafe {
+                    self.m_data.read();
+                }
+            }
+            read_bytes = chunk_end;
+        }
+        self.wait_not_busy().map(|_| ())
+    }
+
+    /**
+     * Writes back the volatile cache of an ATA disk
+     */
+    fn flush_cache(&mut self,
+                   drive: AtaDrive,
+                   drive_info: &AtaDriveInfo)
+                   -> StorageResult<()> {
+        self.select_drive(drive, 0);
+        unsafe {
+            self.m_status_command.write(if drive_info.m_supports_lba48 {
+                                            Self::CMD_FLUSH_CACHE_EXT
+                                        } else {
+                                            Self::CMD_FLUSH_CACHE
+                                        });
+        }
+        self.wait_not_busy().map(|_| ())
+    }
+}
+
+impl AtaChannelRegs /* Getters */ {
+    /**
+     * Returns the biggest transfer in bytes accepted for the given drive
+     */
+    fn transfer_size_max(&self, drive_info: &AtaDriveInfo) -> usize {
+        if drive_info.m_supports_dma {
+            AtaBusMaster::BUFFER_SIZE
+        } else {
+            Self::PIO_SECTORS_MAX * Self::SECTOR_SIZE
+        }
+    }
+}
+
+impl AtaChannelRegs /* Privates */ {
+    /**
+     * Selects the given drive with the given high LBA bits and waits the
+     * selection delay
+     */
+    fn select_drive(&mut self, drive: AtaDrive, lba_high_bits: u8) {
+        unsafe {
+            self.m_drive_select.write(Self::DRIVE_SELECT_BASE
+                                      | Self::DRIVE_SELECT_LBA
+                                      | drive.select_bit()
+                                      | (lba_high_bits & 0x0F));
+        }
+
+        /* each read of the alternate status takes ~100ns */
+        if self.m_selected != Some(drive) {
+            for _ in 0..4 {
+                self.alt_status();
+            }
+            self.m_selected = Some(drive);
+        }
+    }
+
+    /**
+     * Selects the drive and writes the LBA and the sectors count
+     * registers, with the 48 bits protocol when supported
+     */
+    fn write_lba(&mut self,
+                 drive: AtaDrive,
+                 drive_info: &AtaDriveInfo,
+                 lba: u64,
+                 sectors_count: usize) {
+        let lba_bytes = lba.to_le_bytes();
+        if drive_info.m_supports_lba48 {
+            self.select_drive(drive, 0);
+            unsafe {
+                /* the high order bytes come first */
+                self.m_sector_count.write((sectors_count >> 8) as u8);
+                self.m_lba_low.write(lba_bytes[3]);
+                self.m_lba_mid.write(lba_bytes[4]);
+                self.m_lba_high.write(lba_bytes[5]);
+                self.m_sector_count.write(sectors_count as u8);
+                self.m_lba_low.write(lba_bytes[0]);
+                self.m_lba_mid.write(lba_bytes[1]);
+                self.m_lba_high.write(lba_bytes[2]);
+            }
+        } else {
+            self.select_drive(drive, lba_bytes[3]);
+            unsafe {
+                self.m_sector_count.write(sectors_count as u8);
+                self.m_lba_low.write(lba_bytes[0]);
+                self.m_lba_mid.write(lba_bytes[1]);
+                self.m_lba_high.write(lba_bytes[2]);
+            }
+        }
+    }
+
+    /**
+     * Reads the capacity of the media of an ATAPI drive
+     */
+    fn read_capacity(&mut self,
+                     drive: AtaDrive,
+                     drive_info: &AtaDriveInfo)
+                     -> StorageResult<u64> {
+        let mut read_capacity_packet = [0; 12];
+        read_capacity_packet[0] = Self::SCSI_READ_CAPACITY;
+
+        /* PIO only, the response is smaller than a sector */
+        let pio_drive_info = AtaDriveInfo { m_supports_dma: false,
+                                            ..drive_info.clone() };
+        let mut capacity_data = [0; 8];
+
+        /* the first command after the media change reports the unit
+         * attention */
+        let mut result = Err((OsErrorClass::NoDataAvailable, Some("No ATAPI media")));
+        for _ in 0..3 {
+            result = self.atapi_packet(drive,
+                                       &pio_drive_info,
+                                       &read_capacity_packet,
+                                       &mut capacity_data);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result?;
+
+        let last_block = u32::from_be_bytes([capacity_data[0],
+                                             capacity_data[1],
+                                             capacity_data[2],
+                                             capacity_data[3]]);
+        Ok(last_block as u64 + 1)
+    }
+
+    /**
+     * Starts the prepared DMA transfer and waits his completion, the read
+     * data is copied into the given buffer
+     */
+    fn complete_dma(&mut self, buffer: AtaBuffer) -> StorageResult<()> {
+        self.bus_master().start(matches!(buffer, AtaBuffer::Read(_)));
+
+        let mut polls = 0;
+        loop {
+            let bus_master_status = self.bus_master().status();
+            if bus_master_status.is_enabled(BusMasterStatusBits::Errored) {
+                self.bus_master().stop();
+                return Err((OsErrorClass::Unknown, Some("ATA DMA transfer failed")));
+            } else if bus_master_status.is_disabled(BusMasterStatusBits::Active)
+                      && self.alt_status().is_disabled(AtaStatusBits::Busy)
+            {
+                break;
+            }
+
+            polls += 1;
+            if polls >= Self::POLLS_MAX {
+                self.bus_master().stop();
+                return Err((OsErrorClass::InterruptedOperation,
+                            Some("ATA DMA timeout")));
+            }
+            spin_loop();
+        }
+        self.bus_master().stop();
+
+        let status = self.status();
+        if status.is_enabled(AtaStatusBits::Errored)
+           || status.is_enabled(AtaStatusBits::DriveFault)
+        {
+            return Err((OsErrorClass::Unknown, Some("ATA command failed")));
+        }
+        if let AtaBuffer::Read(read_buffer) = buffer {
+            self.bus_master().copy_to(read_buffer);
+        }
+        Ok(())
+    }
+
+    /**
+     * Waits until the drive is ready to transfer the data with the PIO
+     */
+    fn wait_data_request(&mut self) -> StorageResult<()> {
+        let status = self.wait_not_busy()?;
+        if status.is_enabled(AtaStatusBits::DataRequest) {
+            Ok(())
+        } else {
+            Err((OsErrorClass::NoDataAvailable, Some("ATA drive has no data")))
+        }
+    }
+
+    /**
+     * Waits until the drive is no more busy and returns his status, the
+     * errors reported by the drive are returned as `Err`
+     */
+    fn wait_not_busy(&mut self) -> StorageResult<BitFlags<u8, AtaStatusBits>> {
+        self.alt_status();
+        for _ in 0..Self::POLLS_MAX {
+            let status = self.status();
+            if status.is_disabled(AtaStatusBits::Busy) {
+                if status.is_enabled(AtaStatusBits::Errored)
+                   || status.is_enabled(AtaStatusBits::DriveFault)
+                {
+                    return Err((OsErrorClass::Unknown, Some("ATA command failed")));
+                }
+                return Ok(status);
+            }
+            spin_loop();
+        }
+        Err((OsErrorClass::InterruptedOperation, Some("ATA drive timeout")))
+    }
+
+    /**
+     * Reads the given buffer with the data register
+     */
+    fn read_pio_data(&self, buffer: &mut [u8]) {
+        for data_word in buffer.chunks_mut(2) {
+            let raw_word = unsafe { self.m_data.read() }.to_le_bytes();
+            data_word.copy_from_slice(&raw_word[..data_word.len()]);
+        }
+    }
+
+    /**
+     * Writes the given buffer with the data register
+     */
+    fn write_pio_data(&self, buffer: &[u8]) {
+        for data_word in buffer.chunks(2) {
+            unsafe {
+                self.m_data.write(u16::from_le_bytes([data_word[0], data_word[1]]));
+            }
+        }
+    }
+
+    /**
+     * Returns the status of the selected drive, acknowledging his
+     * interrupt
+     */
+    fn status(&self) -> BitFlags<u8, AtaStatusBits> {
+        BitFlags::from_raw_truncate(unsafe { self.m_status_command.read() })
+    }
+
+    /**
+     * Returns the status of the selected drive without side effects
+     */
+    fn alt_status(&self) -> BitFlags<u8, AtaStatusBits> {
+        BitFlags::from_raw_truncate(unsafe { self.m_alt_status_ctrl.read() })
+    }
+
+    /**
+     * Returns the `AtaBusMaster`, called only when the DMA is supported
+     */
+    fn bus_master(&self) -> &AtaBusMaster {
+        self.m_bus_master.as_ref().unwrap()
+    }
+}
+
+/**
+ * Bus-master DMA engine of an IDE channel, transfers the data through a
+ * physical bounce frame described by a single-entry PRD table
+ */
+struct AtaBusMaster {
+    m_command: IoPort<u8>,
+    m_status: IoPort<u8>,
+    m_prd_table_addr: IoPort<u32>,
+    m_prd_table_frame: PhysAddr,
+    m_buffer_frame: PhysAddr
+}
+
+impl AtaBusMaster /* Constants */ {
+    /**
+     * Size of the bounce frame, which is the biggest DMA transfer
+     */
+    const BUFFER_SIZE: usize = 4096;
+
+    const CMD_START: u8 = 1 << 0;
+    const CMD_READ: u8 = 1 << 3;
+    const PRD_END_OF_TABLE: u32 = 1 << 31;
+}
+
+impl AtaBusMaster /* Constructors */ {
+    /**
+     * Constructs the `AtaBusMaster` at the given I/O base
+     */
+    fn new(io_base: u16) -> Option<Self> {
+        /* the PRD table and the frames must be addressable with 32 bits */
+        let mem_manager = MemManager::instance();
+        let prd_table_frame =
+            mem_manager.allocate_kernel_phys_frames(0, PhysZone::Dma32)?;
+        let buffer_frame =
+            match mem_manager.allocate_kernel_phys_frames(0, PhysZone::Dma32) {
+                Some(buffer_frame) => buffer_frame,
+                None => {
+                    mem_manager.free_kernel_phys_frame(prd_table_frame);
+                    return None;
+                }
+            };
+
+        let bus_master = Self { m_command: IoPort::new(io_base),
+                                m_status: IoPort::new(io_base + 2),
+                                m_prd_table_addr: IoPort::new(io_base + 4),
+                                m_prd_table_frame: prd_table_frame,
+                                m_buffer_frame: buffer_frame };
+        bus_master.stop();
+        Some(bus_master)
+    }
+}
+
+impl AtaBusMaster /* Methods */ {
+    /**
+     * Fills the PRD table for the given buffer, the data to write is
+     * copied into the bounce frame
+     */
+    fn prepare(&self, buffer: &AtaBuffer) {
+        if let AtaBuffer::Write(write_buffer) = buffer {
+            unsafe {
+                ptr::copy_nonoverlapping(write_buffer.as_ptr(),
+                                         self.frame_ptr(self.m_buffer_frame),
+                                         write_buffer.len());
+            }
+        }
+
+        /* the byte count <0> means 64KiB, never used with a single frame */
+        let prd_entry =
+            [*self.m_buffer_frame as u32, buffer.len() as u32 | Self::PRD_END_OF_TABLE];
+        unsafe {
+            ptr::copy_nonoverlapping(prd_entry.as_ptr() as *const u8,
+                                     self.frame_ptr(self.m_prd_table_frame),
+                                     8);
+
+            self.m_prd_table_addr.write(*self.m_prd_table_frame as u32);
+
+            /* clear the error and the interrupt bits writing them */
+            let mut bus_master_status = self.status();
+            bus_master_status.set_enabled(BusMasterStatusBits::Errored)
+                             .set_enabled(BusMasterStatusBits::Interrupt);
+            self.m_status.write(bus_master_status.raw_bits());
+        }
+    }
+
+    /**
+     * Starts the prepared transfer, `is_read` is from the drive to the
+     * memory
+     */
+    fn start(&self, is_read: bool) {
+        let direction = if is_read {
+            Self::CMD_READ
+        } else {
+            0
+        };
+        unsafe {
+            self.m_command.write(direction | Self::CMD_START);
+        }
+    }
+
+    /**
+     * Stops the engine
+     */
+    fn stop(&self) {
+        unsafe {
+            self.m_command.write(0);
+        }
+    }
+
+    /**
+     * Copies the data read from the bounce frame into the given buffer
+     */
+    fn copy_to(&self, buffer: &mut [u8]) {
+        unsafe {
+            ptr::copy_nonoverlapping(self.frame_ptr(self.m_buffer_frame),
+                                     buffer.as_mut_ptr(),
+                                     buffer.len());
+        }
+    }
+}
+
+impl AtaBusMaster /* Getters */ {
+    /**
+     * Returns the status of the engine
+     */
+    fn status(&self) -> BitFlags<u8, BusMasterStatusBits> {
+        BitFlags::from_raw_truncate(unsafe { self.m_status.read() })
+    }
+}
+
+impl AtaBusMaster /* Privates */ {
+    /**
+     * Returns the pointer to the given frame into the physical memory
+     * mapping
+     */
+    fn frame_ptr(&self, phys_frame: PhysAddr) -> *mut u8 {
+        MemManager::instance().layout_manager()
+                              .phys_addr_to_virt_addr(phys_frame)
+                              .as_ptr_mut()
+    }
+}
+
+impl Drop for AtaBusMaster {
+    fn drop(&mut self) {
+        MemManager::instance().free_kernel_phys_frame(self.m_prd_table_frame);
+        MemManager::instance().free_kernel_phys_frame(self.m_buffer_frame);
+    }
+}
+
+/**
+ * Returns the SCSI `READ(10)` packet for the given blocks
+ */
+fn scsi_read10_packet(lba: u32, blocks_count: u16) -> [u8; 12] {
+    let lba_bytes = lba.to_be_bytes();
+    let count_bytes = blocks_count.to_be_bytes();
+    [0x28,
+     0,
+     lba_bytes[0],
+     lba_bytes[1],
+     lba_bytes[2],
+     lba_bytes[3],
+     0,
+     count_bytes[0],
+     count_bytes[1],
+     0,
+     0,
+     0]
+}
+
+/**
+ * Status register flags
+ */
+#[repr(usize)]
+#[derive(Copy, Clone)]
+enum AtaStatusBits {
+    Errored,
+    DataRequest = 3,
+    DriveFault  = 5,
+    Busy        = 7
+}
+
+impl Into<usize> for AtaStatusBits {
+    fn into(self) -> usize {
+        self as usize
+    }
+}
+
+impl TryFrom<usize> for AtaStatusBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Errored),
+            3 => Ok(Self::DataRequest),
+            5 => Ok(Self::DriveFault),
+            7 => Ok(Self::Busy),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for AtaStatusBits {
+}
+
+/**
+ * Bus-master status register flags
+ */
+#[repr(usize)]
+#[derive(Copy, Clone)]
+enum BusMasterStatusBits {
+    Active,
+    Errored,
+    Interrupt
+}
+
+impl Into<usize> for BusMasterStatusBits {
+    fn into(self) -> usize {
+        self as usize
+    }
+}
+
+impl TryFrom<usize> for BusMasterStatusBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Active),
+            1 => Ok(Self::Errored),
+            2 => Ok(Self::Interrupt),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for BusMasterStatusBits {
+}
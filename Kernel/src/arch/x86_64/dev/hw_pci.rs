@@ -0,0 +1,101 @@
+/*! x86_64 PCI configuration space implementation */
+
+use alloc::{
+    vec,
+    vec::Vec
+};
+use core::ops::RangeInclusive;
+
+use sync::SpinMutex;
+
+use crate::{
+    arch::x86_64::io_port::IoPort,
+    dev::pci::{
+        PciAddress,
+        TPciConfigSpace
+    }
+};
+
+/**
+ * x86_64 `TPciConfigSpace` implementation based on the legacy I/O ports
+ * mechanism, used when the ACPI doesn't describe the ECAM regions.
+ *
+ * Reaches only the segment group `0`
+ */
+pub struct X64PortPciConfigSpace {
+    m_ports: SpinMutex<X64PciConfigPorts>
+}
+
+impl X64PortPciConfigSpace /* Constants */ {
+    const CONFIG_ADDRESS: u16 = 0xCF8;
+    const CONFIG_DATA: u16 = 0xCFC;
+    const CONFIG_ENABLE: u32 = 1 << 31;
+}
+
+impl X64PortPciConfigSpace /* Constructors */ {
+    /**
+     * Constructs a `X64PortPciConfigSpace`
+     */
+    pub fn new() -> Self {
+        let config_ports =
+            X64PciConfigPorts { m_config_address: IoPort::new(Self::CONFIG_ADDRESS),
+                                m_config_data: IoPort::new(Self::CONFIG_DATA) };
+
+        Self { m_ports: SpinMutex::const_new(config_ports) }
+    }
+}
+
+impl X64PortPciConfigSpace /* Privates */ {
+    /**
+     * Returns the value to write into the address port to select the given
+     * register
+     */
+    fn config_address(pci_address: PciAddress, offset: u16) -> u32 {
+        Self::CONFIG_ENABLE
+        | (pci_address.bus() as u32) << 16
+        | (pci_address.slot() as u32) << 11
+        | (pci_address.function() as u32) << 8
+        | (offset as u32 & 0xFC)
+    }
+}
+
+impl TPciConfigSpace for X64PortPciConfigSpace {
+    fn read_u32(&self, pci_address: PciAddress, offset: u16) -> u32 {
+        /* only the first 256 bytes are reachable with this mechanism */
+        if pci_address.segment() != 0 || offset >= 0x100 {
+            return u32::MAX;
+        }
+
+        let config_ports = self.m_ports.lock();
+        unsafe {
+            config_ports.m_config_address
+                        .write(Self::config_address(pci_address, offset));
+            config_ports.m_config_data.read()
+        }
+    }
+
+    fn write_u32(&self, pci_address: PciAddress, offset: u16, value: u32) {
+        if pci_address.segment() != 0 || offset >= 0x100 {
+            return;
+        }
+
+        let config_ports = self.m_ports.lock();
+        unsafe {
+            config_ports.m_config_address
+                        .write(Self::config_address(pci_address, offset));
+            config_ports.m_config_data.write(value);
+        }
+    }
+
+    fn bus_ranges(&self) -> Vec<(u16, RangeInclusive<u8>)> {
+        vec![(0, 0..=u8::MAX)]
+    }
+}
+
+/**
+ * Couple of I/O ports which must be accessed atomically
+ */
+struct X64PciConfigPorts {
+    m_config_address: IoPort<u32>,
+    m_config_data: IoPort<u32>
+}
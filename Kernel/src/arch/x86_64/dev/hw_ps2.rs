@@ -0,0 +1,405 @@
+/*! x86_64 i8042 PS/2 controller implementation
+ *
+ * The controller is initialized in polling, then the bytes sent by the
+ * keyboard and the mouse are received with the IRQ 1 and the IRQ 12
+ */
+
+use alloc::sync::Arc;
+use core::hint::spin_loop;
+
+use sync::SpinMutex;
+
+use crate::{
+    arch::x86_64::{
+        interrupts::apic_manager::ApicManager,
+        io_port::IoPort
+    },
+    dev::{
+        input::InputEventQueue,
+        ps2::{
+            Ps2KeyboardDecoder,
+            Ps2MouseDecoder,
+            ScancodeSet
+        }
+    }
+};
+
+/* <None> until <X64Ps2Controller::init_instance()> is called */
+static mut SM_PS2_CONTROLLER: Option<X64Ps2Controller> = None;
+
+/**
+ * x86_64 i8042 PS/2 controller, routes the bytes received from his
+ * devices to their `InputEventQueue`s
+ */
+pub struct X64Ps2Controller {
+    m_data: IoPort<u8>,
+    m_status_command: IoPort<u8>,
+    m_keyboard: Option<Ps2Channel<Ps2KeyboardDecoder>>,
+    m_mouse: Option<Ps2Channel<Ps2MouseDecoder>>
+}
+
+impl X64Ps2Controller /* Constants */ {
+    const DATA_PORT: u16 = 0x60;
+    const STATUS_COMMAND_PORT: u16 = 0x64;
+
+    const KEYBOARD_IRQ: u8 = 1;
+    const MOUSE_IRQ: u8 = 12;
+
+    const STATUS_OUTPUT_FULL: u8 = 1 << 0;
+    const STATUS_INPUT_FULL: u8 = 1 << 1;
+    const STATUS_MOUSE_OUTPUT: u8 = 1 << 5;
+
+    const CONFIG_KEYBOARD_IRQ: u8 = 1 << 0;
+    const CONFIG_MOUSE_IRQ: u8 = 1 << 1;
+    const CONFIG_MOUSE_CLOCK_OFF: u8 = 1 << 5;
+    const CONFIG_TRANSLATION: u8 = 1 << 6;
+
+    const CMD_READ_CONFIG: u8 = 0x20;
+    const CMD_WRITE_CONFIG: u8 = 0x60;
+    const CMD_DISABLE_MOUSE: u8 = 0xa7;
+    const CMD_ENABLE_MOUSE: u8 = 0xa8;
+    const CMD_TEST_MOUSE: u8 = 0xa9;
+    const CMD_SELF_TEST: u8 = 0xaa;
+    const CMD_TEST_KEYBOARD: u8 = 0xab;
+    const CMD_DISABLE_KEYBOARD: u8 = 0xad;
+    const CMD_ENABLE_KEYBOARD: u8 = 0xae;
+    const CMD_WRITE_MOUSE: u8 = 0xd4;
+
+    const SELF_TEST_PASSED: u8 = 0x55;
+    const PORT_TEST_PASSED: u8 = 0x00;
+
+    const DEV_SET_SAMPLE_RATE: u8 = 0xf3;
+    const DEV_GET_ID: u8 = 0xf2;
+    const DEV_ENABLE_REPORTING: u8 = 0xf4;
+    const DEV_SET_DEFAULTS: u8 = 0xf6;
+    const DEV_RESET: u8 = 0xff;
+    const DEV_ACK: u8 = 0xfa;
+    const DEV_RESET_PASSED: u8 = 0xaa;
+
+    /* the sample rates sequence which enables the wheel of the mice which
+     * have it, they then answer with <MOUSE_ID_WHEEL>
+     */
+    const MOUSE_WHEEL_SEQUENCE: [u8; 3] = [200, 100, 80];
+    const MOUSE_ID_WHEEL: u8 = 0x03;
+
+    /* the devices could take hundreds of milliseconds to reset */
+    const POLLS_MAX: usize = 1_000_000;
+}
+
+impl X64Ps2Controller /* Constructors */ {
+    /**
+     * Initializes the controller and his devices, then stores the global
+     * instance.
+     *
+     * Returns whether at least one device is available
+     */
+    pub fn init_instance() -> bool {
+        let mut ps2_controller = Self { m_data: IoPort::new(Self::DATA_PORT),
+                                        m_status_command:
+                                            IoPort::new(Self::STATUS_COMMAND_PORT),
+                                        m_keyboard: None,
+                                        m_mouse: None };
+
+        if ps2_controller.init_hw().is_some()
+           && (ps2_controller.m_keyboard.is_some() || ps2_controller.m_mouse.is_some())
+        {
+            unsafe {
+                SM_PS2_CONTROLLER = Some(ps2_controller);
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl X64Ps2Controller /* Methods */ {
+    /**
+     * Enables the interrupts of the available devices into the controller
+     * and into the I/O APIC
+     */
+    pub fn enable_irqs(&self) {
+        let mut config = match self.read_config() {
+            Some(config) => config,
+            None => return
+        };
+
+        let apic_manager = ApicManager::instance_mut();
+        for (irq, config_bit, is_available) in
+            [(Self::KEYBOARD_IRQ, Self::CONFIG_KEYBOARD_IRQ, self.m_keyboard.is_some()),
+             (Self::MOUSE_IRQ, Self::CONFIG_MOUSE_IRQ, self.m_mouse.is_some())].iter()
+        {
+            if !is_available {
+                continue;
+            }
+            config |= config_bit;
+
+            /* the ISA interrupts are edge triggered and active high, the GSI
+             * is kept since it could be overridden by the ACPI
+             */
+            let gsi = apic_manager.irq_to_gsi(*irq);
+            apic_manager.configure_irq(*irq, gsi, true, true, true);
+            apic_manager.enable_irq(*irq);
+        }
+        self.write_config(config);
+    }
+
+    /**
+     * Moves the bytes received by the controller into the decoders of the
+     * devices which sent them.
+     *
+     * Called by the interrupt handler of the IRQ 1 and the IRQ 12
+     */
+    pub fn on_interrupt(&self) {
+        loop {
+            let status = unsafe { self.m_status_command.read() };
+            if status & Self::STATUS_OUTPUT_FULL == 0 {
+                break;
+            }
+
+            let data_byte = unsafe { self.m_data.read() };
+            if status & Self::STATUS_MOUSE_OUTPUT != 0 {
+                if let Some(mouse) = self.m_mouse.as_ref() {
+                    let events = &mouse.m_events;
+                    mouse.m_decoder
+                         .lock()
+                         .decode(data_byte, |input_event| events.push(input_event));
+                }
+            } else if let Some(keyboard) = self.m_keyboard.as_ref() {
+                if let Some(input_event) = keyboard.m_decoder.lock().decode(data_byte) {
+                    keyboard.m_events.push(input_event);
+                }
+            }
+        }
+    }
+}
+
+impl X64Ps2Controller /* Getters */ {
+    /**
+     * Returns the global `X64Ps2Controller` instance, if initialized
+     */
+    pub fn try_instance() -> Option<&'static Self> {
+        unsafe { SM_PS2_CONTROLLER.as_ref() }
+    }
+
+    /**
+     * Returns the `InputEventQueue` of the keyboard, if available
+     */
+    pub fn keyboard_events(&self) -> Option<Arc<InputEventQueue>> {
+        self.m_keyboard.as_ref().map(|keyboard| keyboard.m_events.clone())
+    }
+
+    /**
+     * Returns the `InputEventQueue` of the mouse, if available
+     */
+    pub fn mouse_events(&self) -> Option<Arc<InputEventQueue>> {
+        self.m_mouse.as_ref().map(|mouse| mouse.m_events.clone())
+    }
+}
+
+impl X64Ps2Controller /* Privates */ {
+    /**
+     * Tests the controller and initializes the devices attached to his
+     * ports, the available ones are stored into `m_keyboard` and
+     * `m_mouse`
+     */
+    fn init_hw(&mut self) -> Option<()> {
+        /* disable the devices and drop the bytes which they already sent */
+        self.write_command(Self::CMD_DISABLE_KEYBOARD)?;
+        self.write_command(Self::CMD_DISABLE_MOUSE)?;
+        self.flush_output();
+
+        /* keep the interrupts disabled until the devices are ready */
+        let config =
+            self.read_config()? & !(Self::CONFIG_KEYBOARD_IRQ | Self::CONFIG_MOUSE_IRQ);
+        self.write_config(config)?;
+
+        /* the self test could reset the configuration */
+        self.write_command(Self::CMD_SELF_TEST)?;
+        if self.read_data()? != Self::SELF_TEST_PASSED {
+            return None;
+        }
+        self.write_config(config)?;
+
+        /* the mouse port exists when his clock could be enabled */
+        self.write_command(Self::CMD_ENABLE_MOUSE)?;
+        let has_mouse_port = self.read_config()? & Self::CONFIG_MOUSE_CLOCK_OFF == 0;
+        self.write_command(Self::CMD_DISABLE_MOUSE)?;
+
+        if self.test_port(Self::CMD_TEST_KEYBOARD) {
+            self.write_command(Self::CMD_ENABLE_KEYBOARD)?;
+            if self.init_keyboard().is_some() {
+                /* with the translation the controller converts the set 2 */
+                let scancode_set = if config & Self::CONFIG_TRANSLATION != 0 {
+                    ScancodeSet::Set1
+                } else {
+                    ScancodeSet::Set2
+                };
+                self.m_keyboard =
+                    Some(Ps2Channel::new(Ps2KeyboardDecoder::new(scancode_set)));
+            }
+        }
+        if has_mouse_port && self.test_port(Self::CMD_TEST_MOUSE) {
+            self.write_command(Self::CMD_ENABLE_MOUSE)?;
+            if let Some(has_wheel) = self.init_mouse() {
+                self.m_mouse = Some(Ps2Channel::new(Ps2MouseDecoder::new(has_wheel)));
+            }
+        }
+        Some(())
+    }
+
+    /**
+     * Resets the keyboard and enables his scanning
+     */
+    fn init_keyboard(&self) -> Option<()> {
+        self.send_to_device(false, Self::DEV_RESET)?;
+        if self.read_data()? != Self::DEV_RESET_PASSED {
+            return None;
+        }
+        self.send_to_device(false, Self::DEV_ENABLE_REPORTING)
+    }
+
+    /**
+     * Resets the mouse, enables his wheel if he has one and enables his
+     * data reporting.
+     *
+     * Returns whether the mouse has the wheel
+     */
+    fn init_mouse(&self) -> Option<bool> {
+        self.send_to_device(true, Self::DEV_RESET)?;
+        if self.read_data()? != Self::DEV_RESET_PASSED {
+            return None;
+        }
+        /* the mouse sends his identifier after the reset */
+        self.read_data()?;
+        self.send_to_device(true, Self::DEV_SET_DEFAULTS)?;
+
+        for sample_rate in Self::MOUSE_WHEEL_SEQUENCE.iter() {
+            self.send_to_device(true, Self::DEV_SET_SAMPLE_RATE)?;
+            self.send_to_device(true, *sample_rate)?;
+        }
+        self.send_to_device(true, Self::DEV_GET_ID)?;
+        let has_wheel = self.read_data()? == Self::MOUSE_ID_WHEEL;
+
+        self.send_to_device(true, Self::DEV_ENABLE_REPORTING)?;
+        Some(has_wheel)
+    }
+
+    /**
+     * Executes the given port test command and returns whether it passed
+     */
+    fn test_port(&self, test_command: u8) -> bool {
+        self.write_command(test_command).is_some()
+        && self.read_data() == Some(Self::PORT_TEST_PASSED)
+    }
+
+    /**
+     * Sends the given byte to the keyboard or to the mouse and waits for
+     * his acknowledge
+     */
+    fn send_to_device(&self, to_mouse: bool, device_byte: u8) -> Option<()> {
+        if to_mouse {
+            self.write_command(Self::CMD_WRITE_MOUSE)?;
+        }
+        self.write_data(device_byte)?;
+
+        if self.read_data()? == Self::DEV_ACK {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Returns the configuration byte of the controller
+     */
+    fn read_config(&self) -> Option<u8> {
+        self.write_command(Self::CMD_READ_CONFIG)?;
+        self.read_data()
+    }
+
+    /**
+     * Writes the configuration byte of the controller
+     */
+    fn write_config(&self, config: u8) -> Option<()> {
+        self.write_command(Self::CMD_WRITE_CONFIG)?;
+        self.write_data(config)
+    }
+
+    /**
+     * Writes the given command to the controller
+     */
+    fn write_command(&self, command: u8) -> Option<()> {
+        self.wait_status(Self::STATUS_INPUT_FULL, false)?;
+        unsafe {
+            self.m_status_command.write(command);
+        }
+        Some(())
+    }
+
+    /**
+     * Writes the given byte to the data port
+     */
+    fn write_data(&self, data_byte: u8) -> Option<()> {
+        self.wait_status(Self::STATUS_INPUT_FULL, false)?;
+        unsafe {
+            self.m_data.write(data_byte);
+        }
+        Some(())
+    }
+
+    /**
+     * Reads the next byte from the data port
+     */
+    fn read_data(&self) -> Option<u8> {
+        self.wait_status(Self::STATUS_OUTPUT_FULL, true)?;
+        Some(unsafe { self.m_data.read() })
+    }
+
+    /**
+     * Drops the bytes pending into the output buffer
+     */
+    fn flush_output(&self) {
+        for _ in 0..Self::POLLS_MAX {
+            if unsafe { self.m_status_command.read() } & Self::STATUS_OUTPUT_FULL == 0 {
+                break;
+            }
+            unsafe {
+                self.m_data.read();
+            }
+        }
+    }
+
+    /**
+     * Waits until the given status bit reaches the given value, `None` is
+     * returned on timeout
+     */
+    fn wait_status(&self, status_bit: u8, is_set: bool) -> Option<()> {
+        for _ in 0..Self::POLLS_MAX {
+            if (unsafe { self.m_status_command.read() } & status_bit != 0) == is_set {
+                return Some(());
+            }
+            spin_loop();
+        }
+        None
+    }
+}
+
+/**
+ * Device attached to a port of the `X64Ps2Controller`
+ */
+struct Ps2Channel<D> {
+    m_decoder: SpinMutex<D>,
+    m_events: Arc<InputEventQueue>
+}
+
+impl<D> Ps2Channel<D> /* Constructors */ {
+    /**
+     * Constructs a `Ps2Channel` with the given decoder and an empty
+     * `InputEventQueue`
+     */
+    fn new(decoder: D) -> Self {
+        Self { m_decoder: SpinMutex::const_new(decoder),
+               m_events: Arc::new(InputEventQueue::new()) }
+    }
+}
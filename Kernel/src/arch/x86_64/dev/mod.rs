@@ -1,16 +1,38 @@
 /*! x86_64 device drivers implementations */
 
+use alloc::sync::Arc;
+
+use api_data::object::device::DeviceIdClass;
+
 use crate::{
-    arch::x86_64::dev::{
-        hw_random::{
-            rdrand::X64RdRandRandom,
-            rdtsc::X64RdTscRandom
+    arch::x86_64::{
+        acpi_manager::AcpiManager,
+        dev::{
+            hw_ata::X64AtaPciDriver,
+            hw_pci::X64PortPciConfigSpace,
+            hw_ps2::X64Ps2Controller,
+            hw_random::{
+                rdrand::X64RdRandRandom,
+                rdtsc::X64RdTscRandom
+            },
+            hw_uart::X64Serial16550Uart
+        }
+    },
+    dbg_println,
+    dev::{
+        pci::{
+            PciEcamConfigSpace,
+            TPciConfigSpace
         },
-        hw_uart::X64Serial16550Uart
+        ps2::Ps2InputDevice,
+        DevManager
     },
-    dev::DevManager
+    DbgLevel
 };
 
+pub mod hw_ata;
+pub mod hw_pci;
+pub mod hw_ps2;
 pub mod hw_random;
 pub mod hw_uart;
 
@@ -44,4 +66,47 @@ impl DevManager /* Methods */ {
         assert!(self.register_device(X64Serial16550Uart::new_com4()),
                 "Failed to register Serial COM4 driver");
     }
+
+    /**
+     * Returns the best `TPciConfigSpace` available, the ECAM described by
+     * the ACPI when present, otherwise the legacy I/O ports
+     */
+    pub fn pci_config_space(&self) -> Arc<dyn TPciConfigSpace> {
+        let ecam_regions = AcpiManager::try_instance().map(AcpiManager::pci_ecam_regions)
+                                                      .unwrap_or_default();
+        match PciEcamConfigSpace::new(ecam_regions) {
+            Some(ecam_config_space) => Arc::new(ecam_config_space),
+            None => Arc::new(X64PortPciConfigSpace::new())
+        }
+    }
+
+    /**
+     * Registers the `TPciDriver`s of the architecture
+     */
+    pub fn register_pci_drivers(&self) {
+        self.register_pci_driver(X64AtaPciDriver);
+    }
+
+    /**
+     * Registers the keyboard and the mouse attached to the PS/2
+     * controller, then enables their interrupts
+     */
+    pub fn register_input_devices(&self) {
+        if !X64Ps2Controller::init_instance() {
+            dbg_println!(DbgLevel::Warn, "No PS/2 devices available");
+            return;
+        }
+
+        let ps2_controller = X64Ps2Controller::try_instance().unwrap();
+        if let Some(keyboard_events) = ps2_controller.keyboard_events() {
+            let serial_value = self.next_serial_value(DeviceIdClass::Terminal);
+            self.register_device(Ps2InputDevice::new_keyboard(keyboard_events,
+                                                              serial_value));
+        }
+        if let Some(mouse_events) = ps2_controller.mouse_events() {
+            let serial_value = self.next_serial_value(DeviceIdClass::Terminal);
+            self.register_device(Ps2InputDevice::new_mouse(mouse_events, serial_value));
+        }
+        ps2_controller.enable_irqs();
+    }
 }
@@ -8,7 +8,9 @@ use multiboot2::{
     BootInformation,
     BootLoaderNameTag,
     CommandLineTag,
-    MemoryMapTag
+    FramebufferType,
+    MemoryMapTag,
+    ModuleTag
 };
 
 use helps::dbg::C_MIB;
@@ -18,7 +20,12 @@ use crate::{
         phys_addr::PhysAddr,
         TAddress
     },
-    boot_info::THwBootInfo
+    boot_info::{
+        BootFramebuffer,
+        BootModule,
+        FbColorField,
+        THwBootInfo
+    }
 };
 
 /**
@@ -73,6 +80,40 @@ impl THwBootInfo for HwBootInfo {
             })
             .expect("Bootloader doesn't provide memory areas")
     }
+
+    fn boot_modules(&self) -> Vec<BootModule> {
+        self.m_multiboot_ptr
+            .module_tags()
+            .map(|module_tag: &ModuleTag| {
+                let start_phys_addr: PhysAddr =
+                    (module_tag.start_address() as usize).into();
+                let end_phys_addr: PhysAddr = (module_tag.end_address() as usize).into();
+
+                BootModule::new(start_phys_addr..end_phys_addr, module_tag.cmdline())
+            })
+            .collect()
+    }
+
+    fn framebuffer(&self) -> Option<BootFramebuffer> {
+        let framebuffer_tag = self.m_multiboot_ptr.framebuffer_tag()?;
+
+        /* the indexed and the EGA text framebuffers are not supported */
+        match framebuffer_tag.buffer_type {
+            FramebufferType::RGB { red,
+                                   green,
+                                   blue } => {
+                Some(BootFramebuffer::new((framebuffer_tag.address as usize).into(),
+                                          framebuffer_tag.width as usize,
+                                          framebuffer_tag.height as usize,
+                                          framebuffer_tag.pitch as usize,
+                                          framebuffer_tag.bpp as usize,
+                                          FbColorField::new(red.position, red.size),
+                                          FbColorField::new(green.position, green.size),
+                                          FbColorField::new(blue.position, blue.size)))
+            },
+            _ => None
+        }
+    }
 }
 
 impl From<*const u8> for HwBootInfo {
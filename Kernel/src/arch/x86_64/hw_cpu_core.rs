@@ -2,6 +2,7 @@
 
 use core::{
     arch::x86_64::_rdtsc,
+    hint::spin_loop,
     ptr::read_volatile
 };
 
@@ -14,6 +15,7 @@ use crate::{
     },
     arch::x86_64::{
         acpi_manager::AcpiManager,
+        ap_trampoline::ApTrampoline,
         global_desc_table::{
             GlobalDescTable,
             Segment,
@@ -33,15 +35,22 @@ use crate::{
     dbg_println,
     processor::{
         CpuCoreId,
+        Processor,
         THwCpuCore
-    }
+    },
+    vm::mem_manager::MemManager
 };
 
 const C_DOUBLE_FAULT_STACK: usize = 4096;
 const C_DOUBLE_FAULT_STACK_INDEX: usize = 0;
 
+pub const C_PAGE_FAULT_INTR: u32 = 14;
 pub const C_IRQ_MASTER_BASE: u32 = 0x20;
 pub const C_IRQ_SLAVE_BASE: u32 = 0x28;
+pub const C_LAPIC_TIMER_INTR: u32 = C_IRQ_MASTER_BASE + 18;
+pub const C_PS2_KEYBOARD_INTR: u32 = C_IRQ_MASTER_BASE + 1;
+pub const C_PS2_MOUSE_INTR: u32 = C_IRQ_MASTER_BASE + 12;
+pub const C_TLB_SHOOTDOWN_INTR: u32 = 0xf0;
 
 /**
  * x86_64 `HwCpuBase` implementation
@@ -51,7 +60,8 @@ pub struct HwCpuCore {
     m_global_desc_table: GlobalDescTable,
     m_task_state_segment: TaskStateSegment,
     m_intr_desc_table: IntrDescTable,
-    m_double_fault_stack: [u8; C_DOUBLE_FAULT_STACK]
+    m_double_fault_stack: [u8; C_DOUBLE_FAULT_STACK],
+    m_ap_trampoline: Option<ApTrampoline>
 }
 
 impl HwCpuCore /* Privates */ {
@@ -184,6 +194,26 @@ impl HwCpuCore /* Privates */ {
              (lapic_counter - left_lapic_count) * pic_time * ApicManager::TIMER_DIVIDER)
         }
     }
+
+    /**
+     * Busy waits until `condition` returns `true` or the given amount of
+     * microseconds is elapsed.
+     *
+     * Returns the last value returned by `condition`
+     */
+    fn wait_until<F>(micros: u64, condition: F) -> bool
+        where F: Fn() -> bool {
+        let tsc_ticks = Processor::instance().cores_max_frequency() / 1_000_000 * micros;
+
+        let begin = unsafe { _rdtsc() };
+        while unsafe { _rdtsc() } - begin < tsc_ticks {
+            if condition() {
+                return true;
+            }
+            spin_loop();
+        }
+        condition()
+    }
 }
 
 impl THwCpuCore for HwCpuCore {
@@ -192,7 +222,8 @@ impl THwCpuCore for HwCpuCore {
                m_global_desc_table: GlobalDescTable::new(),
                m_task_state_segment: TaskStateSegment::new(),
                m_intr_desc_table: IntrDescTable::new(),
-               m_double_fault_stack: [0; C_DOUBLE_FAULT_STACK] }
+               m_double_fault_stack: [0; C_DOUBLE_FAULT_STACK],
+               m_ap_trampoline: None }
     }
 
     fn init(&mut self) {
@@ -223,9 +254,9 @@ impl THwCpuCore for HwCpuCore {
             dbg_println!(DbgLevel::Trace, "Discovering AP CPUs...");
             AcpiManager::instance().register_ap_cpus();
 
-            /* redirect keyboard IRQ */
-            dbg_println!(DbgLevel::Trace, "Using I/O APIC For Interrupts...");
-            ApicManager::instance().enable_irq(1);
+            /* NOTE the IRQs of the devices are redirected to the I/O APIC by
+             * their drivers
+             */
         }
 
         /* enable the LAPIC for this CPU */
@@ -266,6 +297,12 @@ impl THwCpuCore for HwCpuCore {
         }
     }
 
+    fn do_wait_for_interrupt(&self) {
+        unsafe {
+            asm!("sti; hlt", options(nomem, nostack));
+        }
+    }
+
     fn do_enable_interrupts(&self) {
         unsafe {
             asm!("sti", options(nomem, nostack));
@@ -278,6 +315,61 @@ impl THwCpuCore for HwCpuCore {
         }
     }
 
+    fn set_kern_stack_bottom(&mut self, kern_stack_bottom: VirtAddr) {
+        /* interrupts from the user-space use the ring0 stack of the TSS */
+        self.m_task_state_segment.m_stacks_per_privilege[0] = kern_stack_bottom;
+
+        /* <syscall_entry> reads the stack from the IA32_SYSENTER_ESP MSR */
+        unsafe {
+            MsRegister::new_sysenter_esp().write(*kern_stack_bottom as u64);
+        }
+    }
+
+    fn set_tls_ptr(&mut self, tls_ptr: VirtAddr) {
+        /* the x86_64 ABI reaches the thread control block through <fs> */
+        unsafe {
+            MsRegister::new_fs_base().write(*tls_ptr as u64);
+        }
+    }
+
+    fn start_ap(&mut self, ap_cpu_core_id: CpuCoreId, ap_stack_bottom: VirtAddr) -> bool {
+        const C_INIT_DELAY_MICROS: u64 = 10_000;
+        const C_FIRST_SIPI_DELAY_MICROS: u64 = 1_000;
+        const C_SECOND_SIPI_DELAY_MICROS: u64 = 100_000;
+
+        /* the trampoline is installed once and kept until all the APs start */
+        let ap_trampoline =
+            self.m_ap_trampoline.get_or_insert_with(ApTrampoline::install);
+        ap_trampoline.prepare(MemManager::instance().kernel_page_dir().root_phys_frame(),
+                              ap_stack_bottom);
+
+        /* INIT-SIPI-SIPI sequence, the second SIPI is sent only if the AP
+         * doesn't reach the trampoline with the first one
+         */
+        let local_apic = ApicManager::instance().local_apic();
+        local_apic.send_init_ipi(ap_cpu_core_id);
+        Self::wait_until(C_INIT_DELAY_MICROS, || false);
+
+        local_apic.send_startup_ipi(ap_cpu_core_id, ap_trampoline.startup_vector());
+        if Self::wait_until(C_FIRST_SIPI_DELAY_MICROS, || ap_trampoline.is_ap_started()) {
+            return true;
+        }
+
+        local_apic.send_startup_ipi(ap_cpu_core_id, ap_trampoline.startup_vector());
+        Self::wait_until(C_SECOND_SIPI_DELAY_MICROS, || ap_trampoline.is_ap_started())
+    }
+
+    fn end_aps_startup(&mut self) {
+        if let Some(ap_trampoline) = self.m_ap_trampoline.take() {
+            ap_trampoline.uninstall();
+        }
+    }
+
+    fn send_tlb_shootdown_ipi(&self, cpu_core_id: CpuCoreId) {
+        ApicManager::instance().local_apic()
+                               .send_fixed_ipi(cpu_core_id, C_TLB_SHOOTDOWN_INTR);
+    }
+
     fn calculate_speed(&self) -> (u64, u64) {
         const C_MEASURE_COUNT: usize = 5;
         const C_REQUIRED_MATCHES: usize = 3;
@@ -350,4 +442,8 @@ impl THwCpuCore for HwCpuCore {
         }
         rflags.bit_at(9)
     }
+
+    fn is_ap(&self) -> bool {
+        self.m_is_ap
+    }
 }
@@ -3,6 +3,7 @@
 use alloc::vec::Vec;
 use core::{
     arch::x86_64::__cpuid,
+    hint::spin_loop,
     ops::Range,
     ptr::{
         read_volatile,
@@ -19,13 +20,17 @@ use crate::{
         TAddress
     },
     arch::x86_64::{
-        hw_cpu_core::C_IRQ_MASTER_BASE,
+        hw_cpu_core::{
+            C_IRQ_MASTER_BASE,
+            C_LAPIC_TIMER_INTR
+        },
         ms_register::MsRegister
     },
     processor::{
         CpuCoreId,
         Processor
     },
+    task::scheduler::Scheduler,
     vm::{
         mem_manager::MemManager,
         Page4KiB
@@ -310,10 +315,10 @@ impl LocalApic /* Methods */ {
     pub fn enable_timer(&self) {
         let timer_counter = Processor::instance().cores_bus_frequency()
                             / ApicManager::TIMER_DIVIDER
-                            / 200;
+                            / Scheduler::TICKS_PER_SECOND;
         self.write_timer_counter(timer_counter as u32);
         self.write_local_vector(LapicRegister::LocalVecTableTimer,
-                                C_IRQ_MASTER_BASE + 18,
+                                C_LAPIC_TIMER_INTR,
                                 DELIVERY_MODE_NORMAL,
                                 INTERRUPT_MASK_DISABLE,
                                 MODE_PERIODIC);
@@ -328,6 +333,33 @@ impl LocalApic /* Methods */ {
     pub fn read_timer_counter(&self) -> u32 {
         unsafe { self.read(LapicRegister::TimerCurrentCounter) }
     }
+
+    /**
+     * Sends the INIT inter-processor interrupt to the given `CpuCore`
+     */
+    pub fn send_init_ipi(&self, cpu_core_id: CpuCoreId) {
+        self.send_ipi(cpu_core_id, DELIVERY_MODE_INIT | LEVEL_ASSERT | TRIGGER_MODE_EDGE);
+    }
+
+    /**
+     * Sends the STARTUP inter-processor interrupt to the given `CpuCore`,
+     * which starts to execute the code at `startup_vector * 4KiB`
+     */
+    pub fn send_startup_ipi(&self, cpu_core_id: CpuCoreId, startup_vector: u8) {
+        self.send_ipi(cpu_core_id,
+                      DELIVERY_MODE_INTER_PROCESSOR
+                      | LEVEL_ASSERT
+                      | startup_vector as u32);
+    }
+
+    /**
+     * Sends to the given `CpuCore` the inter-processor interrupt which
+     * raises the given interrupt vector
+     */
+    pub fn send_fixed_ipi(&self, cpu_core_id: CpuCoreId, vector: u32) {
+        self.send_ipi(cpu_core_id,
+                      DELIVERY_MODE_NORMAL | LEVEL_ASSERT | TRIGGER_MODE_EDGE | vector);
+    }
 }
 
 impl LocalApic /* Getters */ {
@@ -335,7 +367,8 @@ impl LocalApic /* Getters */ {
      * Returns the hardware `CpuId`
      */
     pub fn cpu_id(&self) -> CpuCoreId {
-        unsafe { self.read(LapicRegister::CoreId) as CpuCoreId }
+        /* the id is stored into the highest byte of the register */
+        unsafe { (self.read(LapicRegister::CoreId) >> 24) as CpuCoreId }
     }
 
     /**
@@ -347,6 +380,25 @@ impl LocalApic /* Getters */ {
 }
 
 impl LocalApic /* Privates */ {
+    /**
+     * Sends an inter-processor interrupt with the given command to the
+     * given `CpuCore` and waits for his delivery
+     */
+    fn send_ipi(&self, cpu_core_id: CpuCoreId, command: u32) {
+        unsafe {
+            /* the interrupt is sent when the low part is written */
+            self.write(LapicRegister::IntrCommandHigh, (cpu_core_id as u32) << 24);
+            self.write(LapicRegister::IntrCommandLow,
+                       command | DESTINATION_MODE_PHYSICAL);
+
+            /* wait for the delivery */
+            while self.read(LapicRegister::IntrCommandLow) & DELIVERY_STATUS_PENDING != 0
+            {
+                spin_loop();
+            }
+        }
+    }
+
     /**
      * Writes the local vector using the given setting
      */
@@ -512,8 +564,8 @@ const DELIVERY_STATUS_PENDING: u32 = 1 << 12;
 const POLARITY_HIGH_ACTIVE: u32 = 0 << 13;
 const POLARITY_LOW_ACTIVE: u32 = 1 << 13;
 
-const LEVEL_ASSERT: u32 = 0 << 14;
-const LEVEL_DE_ASSERT: u32 = 1 << 14;
+const LEVEL_DE_ASSERT: u32 = 0 << 14;
+const LEVEL_ASSERT: u32 = 1 << 14;
 
 const TRIGGER_MODE_EDGE: u32 = 0 << 15;
 const TRIGGER_MODE_LEVEL: u32 = 1 << 15;
@@ -1,6 +1,50 @@
 /*! x86_64 interrupt handler */
 
-use crate::arch::interrupts::intr_stack_frame::IntrStackFrame;
+use api_data::{
+    error::{
+        class::OsErrorClass,
+        OsError
+    },
+    sys::fn_path::KernFnPath,
+    task::exit_status::TaskExitStatus
+};
+use bits::bit_fields::TBitFields;
+
+use crate::{
+    addr::virt_addr::VirtAddr,
+    arch::{
+        dev::hw_ps2::X64Ps2Controller,
+        hw_cpu_core::{
+            C_LAPIC_TIMER_INTR,
+            C_PAGE_FAULT_INTR,
+            C_PS2_KEYBOARD_INTR,
+            C_PS2_MOUSE_INTR,
+            C_TLB_SHOOTDOWN_INTR
+        },
+        interrupts::{
+            apic_manager::ApicManager,
+            intr_stack_frame::IntrStackFrame
+        }
+    },
+    dbg_print::DbgLevel,
+    dbg_println,
+    net::NetStack,
+    processor::Processor,
+    sys::{
+        is_user_copy_instr,
+        KernFnTable
+    },
+    task::scheduler::Scheduler,
+    vm::{
+        layout_manager::LayoutManager,
+        user_space::VmAccess
+    }
+};
+
+/* page fault error code bits */
+const C_PAGE_FAULT_WRITE_BIT: usize = 1;
+const C_PAGE_FAULT_USER_MODE_BIT: usize = 2;
+const C_PAGE_FAULT_INSTR_FETCH_BIT: usize = 4;
 
 extern "C" {
     pub fn syscall_entry();
@@ -8,10 +52,105 @@ extern "C" {
 
 #[no_mangle]
 extern "C" fn interrupt_handler(intr_stack_frame: &mut IntrStackFrame) {
-    panic!("Interrupt occurred\n{:?}", intr_stack_frame);
+    match intr_stack_frame.intr_num() as u32 {
+        C_LAPIC_TIMER_INTR => {
+            /* notify the LAPIC before the switch, since the next thread could
+             * not return here for a while
+             */
+            ApicManager::instance().local_apic().end_of_interrupt();
+            if let Some(net_stack) = NetStack::try_instance() {
+                net_stack.on_timer_tick();
+            }
+            Scheduler::instance().on_timer_tick();
+        },
+        C_PS2_KEYBOARD_INTR | C_PS2_MOUSE_INTR => {
+            if let Some(ps2_controller) = X64Ps2Controller::try_instance() {
+                ps2_controller.on_interrupt();
+            }
+            ApicManager::instance().local_apic().end_of_interrupt();
+        },
+        C_TLB_SHOOTDOWN_INTR => {
+            Processor::instance().this_core().serve_tlb_shootdown();
+            ApicManager::instance().local_apic().end_of_interrupt();
+        },
+        C_PAGE_FAULT_INTR => on_page_fault(intr_stack_frame),
+        _ => panic!("Interrupt occurred\n{:?}", intr_stack_frame)
+    }
 }
 
 #[no_mangle]
 extern "C" fn syscall_handler(intr_stack_frame: &mut IntrStackFrame) {
-    panic!("Interrupt occurred\n{:?}", intr_stack_frame);
+    /* the user-space puts into rax the pointer to the <SysCallPayload> */
+    KernFnTable::instance().dispatch(intr_stack_frame.rax());
+}
+
+/**
+ * Resolves the page fault against the `UserSpace` of the current
+ * `Process`, the `Thread` which performed an invalid access is terminated
+ * with the `OsError` which describes it
+ */
+fn on_page_fault(intr_stack_frame: &IntrStackFrame) {
+    let fault_virt_addr = page_fault_addr();
+    let error_code = intr_stack_frame.error_code();
+    let vm_access = if error_code.bit_at(C_PAGE_FAULT_INSTR_FETCH_BIT) {
+        VmAccess::Execute
+    } else if error_code.bit_at(C_PAGE_FAULT_WRITE_BIT) {
+        VmAccess::Write
+    } else {
+        VmAccess::Read
+    };
+
+    /* the kernel faults into the user-space only copying the values of the
+     * system calls, which are resolved as the user faults
+     */
+    let os_error = {
+        let current_thread = Processor::instance().this_core().current_thread();
+        let user_space = current_thread.proc().user_space();
+        let is_user_addr = fault_virt_addr < LayoutManager::user_space_range().end
+                           && !user_space.is_kernel();
+        if !error_code.bit_at(C_PAGE_FAULT_USER_MODE_BIT)
+           && !(is_user_addr && is_user_copy_instr(intr_stack_frame.instr_ptr()))
+        {
+            panic!("Kernel page fault accessing {}\n{:?}",
+                   fault_virt_addr, intr_stack_frame);
+        }
+
+        let resolve_result = if is_user_addr {
+            user_space.resolve_page_fault(fault_virt_addr, vm_access)
+        } else {
+            Err((OsErrorClass::InvalidMemoryAccess, Some("Access to kernel memory")))
+        };
+        match resolve_result {
+            Ok(_) => return,
+            Err((error_class, error_msg)) => {
+                dbg_println!(DbgLevel::Warn,
+                             "Thread {} killed: {:?} of {} at ip {:#018x}",
+                             current_thread.id(),
+                             vm_access,
+                             fault_virt_addr,
+                             intr_stack_frame.instr_ptr());
+
+                OsError::new(error_class,
+                             KernFnPath::Invalid,
+                             None,
+                             current_thread.proc().id(),
+                             current_thread.id(),
+                             error_msg)
+            }
+        }
+    };
+
+    /* the thread reference is dropped above, since this never returns */
+    Scheduler::instance().exit_current_thread(TaskExitStatus::WithError(os_error))
+}
+
+/**
+ * Returns the `VirtAddr` which caused the last page fault
+ */
+fn page_fault_addr() -> VirtAddr {
+    let cr2_value: usize;
+    unsafe {
+        asm!("mov {}, cr2", out(reg) cr2_value, options(nomem, nostack, preserves_flags));
+    }
+    VirtAddr::from(cr2_value)
 }
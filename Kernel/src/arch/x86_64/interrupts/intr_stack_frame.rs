@@ -42,6 +42,37 @@ pub struct IntrStackFrame {
 }
 
 impl IntrStackFrame /* Getters */ {
+    /**
+     * Returns the value of the `rax` register at the interruption time.
+     *
+     * On `syscall` this is the pointer to the user `SysCallPayload`
+     */
+    pub fn rax(&self) -> usize {
+        self.m_rax
+    }
+
+    /**
+     * Returns the number of the thrown interrupt
+     */
+    pub fn intr_num(&self) -> usize {
+        self.m_intr_num
+    }
+
+    /**
+     * Returns the error code pushed by the CPU for the exceptions which
+     * have one, zero otherwise
+     */
+    pub fn error_code(&self) -> usize {
+        self.m_error_code
+    }
+
+    /**
+     * Returns the address of the interrupted instruction
+     */
+    pub fn instr_ptr(&self) -> usize {
+        self.m_rip
+    }
+
     pub fn is_from_user_space(&self) -> bool {
         self.m_intr_num == 0 || { self.m_rflags }.bit_at(9)
     }
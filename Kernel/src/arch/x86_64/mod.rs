@@ -2,6 +2,7 @@
 
 pub mod acpi_manager;
 pub mod addr;
+pub mod ap_trampoline;
 pub mod desc_table;
 pub mod dev;
 pub mod global_desc_table;
@@ -15,3 +16,4 @@ pub mod task;
 pub mod vm;
 
 global_asm!(include_str!("kernel_start.S"), options(att_syntax));
+global_asm!(include_str!("ap_trampoline.S"), options(att_syntax));
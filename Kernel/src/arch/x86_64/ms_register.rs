@@ -20,6 +20,14 @@ impl MsRegister /* Constructors */ {
         Self::new(0xc0000084)
     }
 
+    pub const fn new_sysenter_esp() -> Self {
+        Self::new(0x175)
+    }
+
+    pub const fn new_fs_base() -> Self {
+        Self::new(0xc0000100)
+    }
+
     /**
      * Constructs a `MsRegister` with the given value
      */
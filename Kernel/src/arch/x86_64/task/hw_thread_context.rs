@@ -0,0 +1,107 @@
+/*! x86_64 thread context */
+
+use core::{
+    cell::UnsafeCell,
+    mem::size_of
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    arch::x86_64::{
+        desc_table::CpuRingMode,
+        global_desc_table::SegmentSelector
+    },
+    task::thread::{
+        KernThreadEntry,
+        THwThreadContext
+    }
+};
+
+extern "C" {
+    fn hw_switch_context(prev_stack_ptr: *mut usize, next_stack_ptr: usize);
+    fn hw_thread_start();
+}
+
+/**
+ * x86_64 `HwThreadContext` implementation.
+ *
+ * The registers of the suspended thread are stored on his kernel stack by
+ * `hw_switch_context()`, so here is kept only the stack pointer
+ */
+pub struct HwThreadContext {
+    m_stack_ptr: UnsafeCell<usize>
+}
+
+impl THwThreadContext for HwThreadContext {
+    fn new_empty() -> Self {
+        Self { m_stack_ptr: UnsafeCell::new(0) }
+    }
+
+    fn new_kernel(kern_stack_bottom: VirtAddr,
+                  thread_entry: KernThreadEntry,
+                  thread_arg: usize)
+                  -> Self {
+        assert!(kern_stack_bottom.is_aligned(16usize),
+                "Kernel stack must be 16 bytes aligned");
+
+        /* prepare the frame expected by <hw_switch_context()> */
+        let initial_frame = [0,                        /* r15 */
+                             0,                        /* r14 */
+                             thread_arg,               /* r13 */
+                             thread_entry as usize,    /* r12 */
+                             0,                        /* rbx */
+                             0,                        /* rbp */
+                             hw_thread_start as usize  /* return address */];
+
+        /* after the <ret> the stack pointer is the 16 bytes aligned bottom, as
+         * expected by the System V ABI before the <call>
+         */
+        let stack_ptr = *kern_stack_bottom - size_of::<[usize; 7]>();
+        unsafe {
+            *(stack_ptr as *mut [usize; 7]) = initial_frame;
+        }
+
+        Self { m_stack_ptr: UnsafeCell::new(stack_ptr) }
+    }
+
+    unsafe fn switch_to(&self, next_context: &Self) {
+        hw_switch_context(self.m_stack_ptr.get(), *next_context.m_stack_ptr.get());
+    }
+
+    unsafe fn enter_user_space(entry_point: VirtAddr,
+                               stack_ptr: VirtAddr,
+                               args: [usize; 2])
+                               -> ! {
+        let user_code_selector =
+            SegmentSelector::new(SegmentSelector::C_INDEX_USER_CODE, CpuRingMode::Ring3);
+        let user_data_selector =
+            SegmentSelector::new(SegmentSelector::C_INDEX_USER_DATA, CpuRingMode::Ring3);
+
+        /* build the interrupt return frame which lands into the user-space
+         * with the interrupts enabled (<rflags> = 0x202)
+         */
+        asm!("mov ds, {data_sel:x}",
+             "mov es, {data_sel:x}",
+             "push {data_sel}",
+             "push {stack_ptr}",
+             "push 0x202",
+             "push {code_sel}",
+             "push {entry_point}",
+             "xor rbp, rbp",
+             "iretq",
+             data_sel = in(reg) user_data_selector.as_raw(),
+             code_sel = in(reg) user_code_selector.as_raw(),
+             stack_ptr = in(reg) *stack_ptr,
+             entry_point = in(reg) *entry_point,
+             in("rdi") args[0],
+             in("rsi") args[1],
+             options(noreturn));
+    }
+}
+
+unsafe impl Sync for HwThreadContext {
+    /* the context is touched only by the CPU which runs the thread */
+}
@@ -1,3 +1,6 @@
 /*! x86_64 tasking management */
 
+pub mod hw_thread_context;
 pub mod task_state_segment;
+
+global_asm!(include_str!("context_switch.S"), options(att_syntax));
@@ -24,7 +24,9 @@ static mut SM_BOOT_INFORMATION: Option<BootInfo> = None;
 pub struct BootInfo {
     m_boot_loader_name: String,
     m_cmd_line_args_buf: String,
-    m_boot_mem_areas: Vec<Range<PhysAddr>>
+    m_boot_mem_areas: Vec<Range<PhysAddr>>,
+    m_boot_modules: Vec<BootModule>,
+    m_framebuffer: Option<BootFramebuffer>
 }
 
 impl BootInfo /* Constructors */ {
@@ -49,7 +51,9 @@ impl BootInfo /* Constructors */ {
                                 String::from(hw_boot_info.boot_loader_name()),
                             m_cmd_line_args_buf:
                                 String::from(hw_boot_info.cmd_line_args()),
-                            m_boot_mem_areas: hw_boot_info.phys_mem_ranges() });
+                            m_boot_mem_areas: hw_boot_info.phys_mem_ranges(),
+                            m_boot_modules: hw_boot_info.boot_modules(),
+                            m_framebuffer: hw_boot_info.framebuffer() });
         }
     }
 }
@@ -146,6 +150,209 @@ impl BootInfo /* Getters */ {
     pub fn phys_mem_ranges(&self) -> &Vec<Range<PhysAddr>> {
         &self.m_boot_mem_areas
     }
+
+    /**
+     * Returns the `BootModule`s loaded by the bootloader
+     */
+    pub fn boot_modules(&self) -> &Vec<BootModule> {
+        &self.m_boot_modules
+    }
+
+    /**
+     * Returns the linear `BootFramebuffer` set up by the bootloader
+     */
+    pub fn framebuffer(&self) -> Option<&BootFramebuffer> {
+        self.m_framebuffer.as_ref()
+    }
+}
+
+/**
+ * File loaded into the physical memory by the bootloader together with the
+ * kernel (i.e the initial ramdisk)
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BootModule {
+    m_phys_range: Range<PhysAddr>,
+    m_cmd_line: String
+}
+
+impl BootModule /* Constructors */ {
+    /**
+     * Constructs a `BootModule` from the given parameters
+     */
+    pub fn new(phys_range: Range<PhysAddr>, cmd_line: &str) -> Self {
+        Self { m_phys_range: phys_range,
+               m_cmd_line: String::from(cmd_line) }
+    }
+}
+
+impl BootModule /* Getters */ {
+    /**
+     * Returns the physical memory `Range` which stores the module
+     */
+    pub fn phys_range(&self) -> &Range<PhysAddr> {
+        &self.m_phys_range
+    }
+
+    /**
+     * Returns the command-line string given to the module
+     */
+    pub fn cmd_line(&self) -> &str {
+        self.m_cmd_line.as_str()
+    }
+}
+
+/**
+ * Direct color linear framebuffer set up by the bootloader.
+ *
+ * Each pixel is stored in `bpp()` bits, the color components are placed
+ * according to their `FbColorField`s
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BootFramebuffer {
+    m_phys_addr: PhysAddr,
+    m_width: usize,
+    m_height: usize,
+    m_pitch: usize,
+    m_bpp: usize,
+    m_red_field: FbColorField,
+    m_green_field: FbColorField,
+    m_blue_field: FbColorField
+}
+
+impl BootFramebuffer /* Constructors */ {
+    /**
+     * Constructs a `BootFramebuffer` from the given parameters
+     */
+    pub fn new(phys_addr: PhysAddr,
+               width: usize,
+               height: usize,
+               pitch: usize,
+               bpp: usize,
+               red_field: FbColorField,
+               green_field: FbColorField,
+               blue_field: FbColorField)
+               -> Self {
+        Self { m_phys_addr: phys_addr,
+               m_width: width,
+               m_height: height,
+               m_pitch: pitch,
+               m_bpp: bpp,
+               m_red_field: red_field,
+               m_green_field: green_field,
+               m_blue_field: blue_field }
+    }
+}
+
+impl BootFramebuffer /* Getters */ {
+    /**
+     * Returns the physical address of the first pixel
+     */
+    pub fn phys_addr(&self) -> PhysAddr {
+        self.m_phys_addr
+    }
+
+    /**
+     * Returns the amount of pixels of each row
+     */
+    pub fn width(&self) -> usize {
+        self.m_width
+    }
+
+    /**
+     * Returns the amount of rows
+     */
+    pub fn height(&self) -> usize {
+        self.m_height
+    }
+
+    /**
+     * Returns the amount of bytes between the beginning of two rows
+     */
+    pub fn pitch(&self) -> usize {
+        self.m_pitch
+    }
+
+    /**
+     * Returns the amount of bits of each pixel
+     */
+    pub fn bpp(&self) -> usize {
+        self.m_bpp
+    }
+
+    /**
+     * Returns the `FbColorField` of the red component
+     */
+    pub fn red_field(&self) -> FbColorField {
+        self.m_red_field
+    }
+
+    /**
+     * Returns the `FbColorField` of the green component
+     */
+    pub fn green_field(&self) -> FbColorField {
+        self.m_green_field
+    }
+
+    /**
+     * Returns the `FbColorField` of the blue component
+     */
+    pub fn blue_field(&self) -> FbColorField {
+        self.m_blue_field
+    }
+}
+
+/**
+ * Position of a color component into the pixels of a `BootFramebuffer`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct FbColorField {
+    m_position: u8,
+    m_size: u8
+}
+
+impl FbColorField /* Constructors */ {
+    /**
+     * Constructs a `FbColorField` from the given parameters
+     */
+    pub const fn new(position: u8, size: u8) -> Self {
+        Self { m_position: position,
+               m_size: size }
+    }
+}
+
+impl FbColorField /* Methods */ {
+    /**
+     * Returns the given 8 bits component scaled and shifted into his
+     * pixel bits
+     */
+    pub fn encode(&self, component: u8) -> u32 {
+        let scaled_component = if self.m_size < 8 {
+            (component >> (8 - self.m_size)) as u32
+        } else {
+            component as u32
+        };
+        scaled_component << self.m_position
+    }
+}
+
+impl FbColorField /* Getters */ {
+    /**
+     * Returns the index of the lowest bit of the component
+     */
+    pub fn position(&self) -> u8 {
+        self.m_position
+    }
+
+    /**
+     * Returns the amount of bits of the component
+     */
+    pub fn size(&self) -> u8 {
+        self.m_size
+    }
 }
 
 /**
@@ -167,4 +374,15 @@ pub trait THwBootInfo: From<*const u8> {
      * Returns a filled `BootMemAreas`
      */
     fn phys_mem_ranges(&self) -> Vec<Range<PhysAddr>>;
+
+    /**
+     * Returns the `BootModule`s loaded by the bootloader
+     */
+    fn boot_modules(&self) -> Vec<BootModule>;
+
+    /**
+     * Returns the direct color `BootFramebuffer`, if the bootloader set up
+     * one
+     */
+    fn framebuffer(&self) -> Option<BootFramebuffer>;
 }
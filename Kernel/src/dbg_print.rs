@@ -1,6 +1,10 @@
 /*! debug printing support */
 
-use alloc::sync::Arc;
+use alloc::{
+    boxed::Box,
+    sync::Arc,
+    vec::Vec
+};
 use core::{
     convert::TryFrom,
     fmt,
@@ -19,7 +23,8 @@ use crate::{
         uart::TUartDevice,
         DevManager,
         TDevice
-    }
+    },
+    fb_console::FbConsole
 };
 
 /* Vt100 color codes */
@@ -29,8 +34,8 @@ const C_VT100_YELLOW: usize = 33;
 const C_VT100_MAGENTA: usize = 35;
 const C_VT100_WHITE: usize = 37;
 
-/* output device for <dbg_println()> */
-static mut SM_DBG_WRITER: Option<DbgWriter> = None;
+/* output devices for <dbg_println()> */
+static mut SM_DBG_WRITERS: Vec<DbgWriter> = Vec::new();
 
 /* verbosity of the <dbg_println()> */
 static mut SM_DBG_MAX_LEVEL: DbgLevel = DbgLevel::Info;
@@ -136,7 +141,7 @@ pub fn dbg_print_init() {
         };
 
         unsafe {
-            SM_DBG_WRITER = Some(DbgWriter::new(uart_device_driver));
+            SM_DBG_WRITERS.push(DbgWriter::new_uart(uart_device_driver));
         }
     } else {
         panic!("Missing UART device driver")
@@ -160,6 +165,27 @@ pub fn dbg_print_init() {
     }
 }
 
+/**
+ * Adds a `FbConsole` on the first framebuffer device to the debug outputs.
+ *
+ * Returns whether a framebuffer device is available
+ */
+pub fn dbg_print_init_fb_console() -> bool {
+    if let Some(fb_device_drivers) =
+        DevManager::instance().enumerate_by_class(DeviceIdClass::Framebuffer)
+    {
+        /* the console lives until the shutdown, like the UART device */
+        let fb_console =
+            Box::leak(Box::new(FbConsole::new(fb_device_drivers[0].clone())));
+        unsafe {
+            SM_DBG_WRITERS.push(DbgWriter::FbConsole(fb_console));
+        }
+        true
+    } else {
+        false
+    }
+}
+
 /**
  * Returns the global `DbgLevel`
  */
@@ -192,44 +218,49 @@ pub fn dbg_print_set_max_level(dbg_level: DbgLevel) -> DbgLevel {
 }
 
 /**
- * Performs the output to the selected debug device
+ * Performs the output to all the selected debug devices
  */
 pub fn dbg_do_print(args: fmt::Arguments<'_>, dbg_level: DbgLevel, module_path: &str) {
-    write!(unsafe { SM_DBG_WRITER.as_mut().expect("Missing UART device") },
-           "[\x1b[0;{}m{}\x1b[0m <> \x1b[0;{}m{: <26}\x1b[0m] \x1b[0;{}m{}\x1b[0m\n",
-           dbg_level.as_vt100_color(),
-           dbg_level,
-           C_VT100_MAGENTA,
-           module_path,
-           dbg_level.as_vt100_color(),
-           args).expect("Failed to print to UART debug device");
+    for dbg_writer in unsafe { SM_DBG_WRITERS.iter_mut() } {
+        write!(dbg_writer,
+               "[\x1b[0;{}m{}\x1b[0m <> \x1b[0;{}m{: <26}\x1b[0m] \x1b[0;{}m{}\x1b[0m\n",
+               dbg_level.as_vt100_color(),
+               dbg_level,
+               C_VT100_MAGENTA,
+               module_path,
+               dbg_level.as_vt100_color(),
+               args).expect("Failed to print to debug device");
+    }
 }
 
 /**
- * Implements `fmt::Write` for `TUartDevice`
+ * Implements `fmt::Write` for the debug output devices
  */
-pub struct DbgWriter {
-    m_uart_device: &'static dyn TUartDevice
+pub enum DbgWriter {
+    Uart(&'static dyn TUartDevice),
+    FbConsole(&'static FbConsole)
 }
 
 impl DbgWriter /* Constructors */ {
     /**
      * Constructs a `DbgWriter` which leaks a reference to the given device
      */
-    fn new(device_driver: Arc<dyn TDevice>) -> Self {
+    fn new_uart(device_driver: Arc<dyn TDevice>) -> Self {
         let device_driver = Arc::clone(&device_driver);
         let leaked_device_driver_ptr = Arc::as_ptr(&device_driver);
         mem::forget(device_driver);
 
-        Self { m_uart_device:
-                   unsafe { &*leaked_device_driver_ptr }.as_uart()
+        Self::Uart(unsafe { &*leaked_device_driver_ptr }.as_uart()
                                                         .expect("Wrong UART device \
-                                                                 selected") }
+                                                                 selected"))
     }
 }
 
 impl fmt::Write for DbgWriter {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.m_uart_device.write_str(s)
+        match self {
+            Self::Uart(uart_device) => uart_device.write_str(s),
+            Self::FbConsole(fb_console) => fb_console.write_str(s)
+        }
     }
 }
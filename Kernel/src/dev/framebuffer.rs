@@ -0,0 +1,208 @@
+/*! Linear framebuffer */
+
+use alloc::{
+    format,
+    string::String
+};
+use core::{
+    ops::Range,
+    ptr
+};
+
+use api_data::object::device::{
+    DeviceId,
+    DeviceIdClass,
+    DeviceIdType
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    boot_info::BootFramebuffer,
+    dev::TDevice,
+    vm::mem_manager::MemManager
+};
+
+/**
+ * Pixel addressable screen device driver interface.
+ *
+ * The colors are the raw pixel values returned by `rgb_color()`
+ */
+pub trait TFramebufferDevice: TDevice {
+    /**
+     * Returns the amount of pixels of each row
+     */
+    fn width(&self) -> usize;
+
+    /**
+     * Returns the amount of rows
+     */
+    fn height(&self) -> usize;
+
+    /**
+     * Returns the amount of bytes between the beginning of two rows
+     */
+    fn pitch(&self) -> usize;
+
+    /**
+     * Returns the amount of bits of each pixel
+     */
+    fn bpp(&self) -> usize;
+
+    /**
+     * Returns the pixel value of the given 8 bits components
+     */
+    fn rgb_color(&self, red: u8, green: u8, blue: u8) -> u32;
+
+    /**
+     * Writes the given pixel value, the pixels out of the screen are
+     * ignored
+     */
+    fn put_pixel(&self, x: usize, y: usize, color: u32);
+
+    /**
+     * Fills the given rectangle with the given pixel value, the rectangle
+     * is clipped to the screen
+     */
+    fn fill_rect(&self, x: usize, y: usize, width: usize, height: usize, color: u32);
+}
+
+/**
+ * Direct color linear framebuffer set up by the bootloader
+ */
+pub struct LinearFramebuffer {
+    m_device_id: DeviceId,
+    m_boot_framebuffer: BootFramebuffer,
+    m_virt_addr: SpinMutex<VirtAddr>
+}
+
+impl LinearFramebuffer /* Constructors */ {
+    /**
+     * Constructs a `LinearFramebuffer` which drives the given
+     * `BootFramebuffer`
+     */
+    pub fn new(boot_framebuffer: BootFramebuffer, serial_value: u32) -> Self {
+        Self { m_device_id: DeviceId::new(DeviceIdType::Character,
+                                          DeviceIdClass::Framebuffer,
+                                          serial_value),
+               m_boot_framebuffer: boot_framebuffer,
+               m_virt_addr: SpinMutex::const_new(VirtAddr::null()) }
+    }
+}
+
+impl LinearFramebuffer /* Privates */ {
+    /**
+     * Returns the size in bytes of the framebuffer memory
+     */
+    fn mem_size(&self) -> usize {
+        self.m_boot_framebuffer.pitch() * self.m_boot_framebuffer.height()
+    }
+
+    /**
+     * Writes the given pixel value at the given offset of the mapped
+     * framebuffer
+     */
+    unsafe fn write_pixel(&self, virt_addr: VirtAddr, byte_offset: usize, color: u32) {
+        let pixel_ptr = virt_addr.offset(byte_offset).as_ptr_mut::<u8>();
+        match self.m_boot_framebuffer.bpp() {
+            32 => ptr::write_volatile(pixel_ptr as *mut u32, color),
+            24 => {
+                for (index, color_byte) in color.to_le_bytes()[..3].iter().enumerate() {
+                    ptr::write_volatile(pixel_ptr.add(index), *color_byte);
+                }
+            },
+            _ => ptr::write_volatile(pixel_ptr as *mut u16, color as u16)
+        }
+    }
+}
+
+impl TFramebufferDevice for LinearFramebuffer {
+    fn width(&self) -> usize {
+        self.m_boot_framebuffer.width()
+    }
+
+    fn height(&self) -> usize {
+        self.m_boot_framebuffer.height()
+    }
+
+    fn pitch(&self) -> usize {
+        self.m_boot_framebuffer.pitch()
+    }
+
+    fn bpp(&self) -> usize {
+        self.m_boot_framebuffer.bpp()
+    }
+
+    fn rgb_color(&self, red: u8, green: u8, blue: u8) -> u32 {
+        self.m_boot_framebuffer.red_field().encode(red)
+        | self.m_boot_framebuffer.green_field().encode(green)
+        | self.m_boot_framebuffer.blue_field().encode(blue)
+    }
+
+    fn put_pixel(&self, x: usize, y: usize, color: u32) {
+        self.fill_rect(x, y, 1, 1, color);
+    }
+
+    fn fill_rect(&self, x: usize, y: usize, width: usize, height: usize, color: u32) {
+        let x_end = x.saturating_add(width).min(self.width());
+        let y_end = y.saturating_add(height).min(self.height());
+        let bytes_per_pixel = (self.bpp() + 7) / 8;
+
+        /* the lock serializes the writers of the same pixels */
+        let virt_addr = self.m_virt_addr.lock();
+        if virt_addr.is_null() {
+            return;
+        }
+        for row in y..y_end {
+            for column in x..x_end {
+                unsafe {
+                    self.write_pixel(*virt_addr,
+                                     row * self.pitch() + column * bytes_per_pixel,
+                                     color);
+                }
+            }
+        }
+    }
+}
+
+impl TDevice for LinearFramebuffer {
+    fn device_id(&self) -> DeviceId {
+        self.m_device_id
+    }
+
+    fn device_name(&self) -> String {
+        format!("fb_{}", self.m_device_id.serial_value())
+    }
+
+    fn init_hw(&self) -> bool {
+        /* only the 15/16, 24 and 32 bits pixels are drawable */
+        if !matches!(self.m_boot_framebuffer.bpp(), 15 | 16 | 24 | 32)
+           || self.m_boot_framebuffer.pitch() * 8
+              < self.m_boot_framebuffer.width() * self.m_boot_framebuffer.bpp()
+        {
+            return false;
+        }
+
+        match MemManager::instance().map_kernel_mmio(self.m_boot_framebuffer.phys_addr(),
+                                                     self.mem_size())
+        {
+            Some(virt_addr) => {
+                *self.m_virt_addr.lock() = virt_addr;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn mappable_phys_range(&self) -> Option<Range<PhysAddr>> {
+        Some(self.m_boot_framebuffer.phys_addr().as_range(self.mem_size()))
+    }
+
+    fn as_framebuffer(&self) -> Option<&dyn TFramebufferDevice> {
+        Some(self)
+    }
+}
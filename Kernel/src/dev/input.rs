@@ -0,0 +1,75 @@
+/*! Input devices */
+
+use alloc::collections::VecDeque;
+
+use api_data::object::input::InputEvent;
+use sync::SpinMutex;
+
+use crate::dev::TDevice;
+
+/**
+ * Input device driver interface, like keyboards and mice
+ */
+pub trait TInputDevice: TDevice {
+    /**
+     * Dequeues the oldest pending `InputEvent`
+     */
+    fn next_event(&self) -> Option<InputEvent>;
+
+    /**
+     * Returns whether there are pending `InputEvent`s to read
+     */
+    fn has_events(&self) -> bool;
+}
+
+/**
+ * Bounded FIFO of `InputEvent`s shared between the interrupt handler
+ * which produces them and the device driver which reads them.
+ *
+ * When the queue is full the oldest events are dropped
+ */
+pub struct InputEventQueue {
+    m_events: SpinMutex<VecDeque<InputEvent>>
+}
+
+impl InputEventQueue /* Constants */ {
+    pub const CAPACITY: usize = 256;
+}
+
+impl InputEventQueue /* Constructors */ {
+    /**
+     * Constructs an empty `InputEventQueue`
+     */
+    pub fn new() -> Self {
+        Self { m_events: SpinMutex::const_new(VecDeque::with_capacity(Self::CAPACITY)) }
+    }
+}
+
+impl InputEventQueue /* Methods */ {
+    /**
+     * Enqueues the given `InputEvent`
+     */
+    pub fn push(&self, input_event: InputEvent) {
+        let mut events = self.m_events.lock();
+        if events.len() >= Self::CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(input_event);
+    }
+
+    /**
+     * Dequeues the oldest `InputEvent`
+     */
+    pub fn pop(&self) -> Option<InputEvent> {
+        self.m_events.lock().pop_front()
+    }
+}
+
+impl InputEventQueue /* Getters */ {
+    /**
+     * Returns whether the queue is empty
+     */
+    pub fn is_empty(&self) -> bool {
+        self.m_events.lock().is_empty()
+    }
+}
@@ -6,6 +6,7 @@ use alloc::{
     sync::Arc,
     vec::Vec
 };
+use core::ops::Range;
 
 use api_data::object::device::{
     DeviceId,
@@ -13,23 +14,56 @@ use api_data::object::device::{
 };
 use sync::SpinRwLock;
 
-use crate::dev::{
-    random::TRandomDevice,
-    uart::TUartDevice
+use crate::{
+    addr::phys_addr::PhysAddr,
+    boot_info::BootInfo,
+    dbg_println,
+    dev::{
+        framebuffer::{
+            LinearFramebuffer,
+            TFramebufferDevice
+        },
+        input::TInputDevice,
+        net::TNetDevice,
+        pci::{
+            enumerate_pci_functions,
+            PciFunction,
+            TPciDriver
+        },
+        random::TRandomDevice,
+        storage::TStorageDevice,
+        uart::TUartDevice,
+        virtio::{
+            blk::VirtioBlkPciDriver,
+            net::VirtioNetPciDriver
+        }
+    },
+    DbgLevel
 };
 
+pub mod framebuffer;
+pub mod input;
+pub mod net;
+pub mod pci;
+pub mod ps2;
 pub mod random;
+pub mod storage;
 pub mod uart;
+pub mod virtio;
 
 /* <None> until <DevManager::early_init()> is called */
 static mut SM_DEV_MANAGER: DevManager =
-    DevManager { m_devices: SpinRwLock::const_new(BTreeMap::new()) };
+    DevManager { m_devices: SpinRwLock::const_new(BTreeMap::new()),
+                 m_pci_functions: SpinRwLock::const_new(Vec::new()),
+                 m_pci_drivers: SpinRwLock::const_new(Vec::new()) };
 
 /**
  * Kernel centralized `TDevice` driver manager
  */
 pub struct DevManager {
-    m_devices: SpinRwLock<BTreeMap<DeviceId, Arc<dyn TDevice>>>
+    m_devices: SpinRwLock<BTreeMap<DeviceId, Arc<dyn TDevice>>>,
+    m_pci_functions: SpinRwLock<Vec<Arc<PciFunction>>>,
+    m_pci_drivers: SpinRwLock<Vec<Arc<dyn TPciDriver>>>
 }
 
 impl DevManager /* Constructors */ {
@@ -87,6 +121,85 @@ impl DevManager /* Methods */ {
     pub fn unregister_device(&self, device_id: DeviceId) -> Option<Arc<dyn TDevice>> {
         self.m_devices.write().remove(&device_id)
     }
+
+    /**
+     * Enumerates the functions attached to the PCI bus and registers the
+     * `TPciDriver`s which drive them
+     */
+    pub fn init_pci_bus(&self) {
+        /* NOTE <DevManager::pci_config_space()> and
+         * <DevManager::register_pci_drivers()> are implemented into
+         * Kernel/arch/<arch_name>/dev
+         */
+        let pci_functions = enumerate_pci_functions(self.pci_config_space());
+        for pci_function in pci_functions.iter() {
+            dbg_println!(DbgLevel::Debug,
+                         "PCI {}: {:04x}:{:04x}, class: {:02x}:{:02x}",
+                         pci_function.address(),
+                         pci_function.vendor_id(),
+                         pci_function.device_id(),
+                         pci_function.class(),
+                         pci_function.subclass());
+        }
+        *self.m_pci_functions.write() = pci_functions;
+
+        /* the drivers of the architecture independent devices */
+        self.register_pci_driver(VirtioBlkPciDriver);
+        self.register_pci_driver(VirtioNetPciDriver);
+        self.register_pci_drivers();
+    }
+
+    /**
+     * Registers the linear framebuffer set up by the bootloader, if any.
+     *
+     * Returns whether the `LinearFramebuffer` was registered
+     */
+    pub fn init_framebuffer(&self) -> bool {
+        match BootInfo::instance().framebuffer() {
+            Some(boot_framebuffer) => {
+                let serial_value = self.next_serial_value(DeviceIdClass::Framebuffer);
+                self.register_device(LinearFramebuffer::new(boot_framebuffer.clone(),
+                                                            serial_value))
+            },
+            None => false
+        }
+    }
+
+    /**
+     * Registers a `TPciDriver` and offers it the unclaimed PCI functions
+     * which satisfy his `PciMatch`es.
+     *
+     * Returns the amount of functions claimed by the driver
+     */
+    pub fn register_pci_driver<T>(&self, pci_driver: T) -> usize
+        where T: TPciDriver + 'static {
+        let pci_driver = Arc::new(pci_driver);
+        self.m_pci_drivers.write().push(pci_driver.clone());
+
+        /* the locks are not held while the driver probes the functions */
+        let mut claimed_count = 0;
+        for pci_function in self.pci_functions() {
+            let is_matching =
+                pci_driver.pci_matches()
+                          .iter()
+                          .any(|pci_match| pci_match.matches(&pci_function));
+            if !is_matching || !pci_function.claim() {
+                continue;
+            }
+
+            if pci_driver.probe(&pci_function) {
+                dbg_println!(DbgLevel::Info,
+                             "PCI {} claimed by {}",
+                             pci_function.address(),
+                             pci_driver.driver_name());
+                claimed_count += 1;
+            } else {
+                /* give the function back to the other drivers */
+                pci_function.unclaim();
+            }
+        }
+        claimed_count
+    }
 }
 
 impl DevManager /* Getters */ {
@@ -104,6 +217,45 @@ impl DevManager /* Getters */ {
         self.m_devices.read().get(&device_id).map(|device| device.clone())
     }
 
+    /**
+     * Returns all the registered device drivers ordered by `DeviceId`
+     */
+    pub fn devices(&self) -> Vec<Arc<dyn TDevice>> {
+        self.m_devices.read().values().cloned().collect()
+    }
+
+    /**
+     * Returns the device driver with the given `TDevice::device_name()`
+     */
+    pub fn device_by_name(&self, device_name: &str) -> Option<Arc<dyn TDevice>> {
+        self.m_devices
+            .read()
+            .values()
+            .find(|device_driver| device_driver.device_name() == device_name)
+            .cloned()
+    }
+
+    /**
+     * Returns the functions found on the PCI bus by `init_pci_bus()`
+     */
+    pub fn pci_functions(&self) -> Vec<Arc<PciFunction>> {
+        self.m_pci_functions.read().clone()
+    }
+
+    /**
+     * Returns the first serial value not used by the registered device
+     * drivers with the given `DeviceIdClass`
+     */
+    pub fn next_serial_value(&self, device_class: DeviceIdClass) -> u32 {
+        self.m_devices
+            .read()
+            .keys()
+            .filter(|device_id| device_id.device_class() == device_class)
+            .map(|device_id| device_id.serial_value() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
     /**
      * Returns a `Vec` of device drivers with the same `DeviceIdClass`
      */
@@ -144,9 +296,10 @@ impl DevManager /* Getters */ {
 }
 
 /**
- * Base interface for all the device drivers
+ * Base interface for all the device drivers, which are shared among all
+ * the CPUs
  */
-pub trait TDevice {
+pub trait TDevice: Send + Sync {
     /**
      * Returns the `DeviceId` of this device driver
      */
@@ -163,6 +316,14 @@ pub trait TDevice {
      */
     fn init_hw(&self) -> bool;
 
+    /**
+     * Returns the physical memory of the device which could be mapped into
+     * the user-space by `Device::map_to_memory()`
+     */
+    fn mappable_phys_range(&self) -> Option<Range<PhysAddr>> {
+        None
+    }
+
     /**
      * Downcast this `TDevice` to a `TRandomDevice`
      */
@@ -176,6 +337,34 @@ pub trait TDevice {
     fn as_uart(&self) -> Option<&dyn TUartDevice> {
         None
     }
+
+    /**
+     * Downcast this `TDevice` to a `TStorageDevice`
+     */
+    fn as_storage(&self) -> Option<&dyn TStorageDevice> {
+        None
+    }
+
+    /**
+     * Downcast this `TDevice` to a `TNetDevice`
+     */
+    fn as_net(&self) -> Option<&dyn TNetDevice> {
+        None
+    }
+
+    /**
+     * Downcast this `TDevice` to a `TFramebufferDevice`
+     */
+    fn as_framebuffer(&self) -> Option<&dyn TFramebufferDevice> {
+        None
+    }
+
+    /**
+     * Downcast this `TDevice` to a `TInputDevice`
+     */
+    fn as_input(&self) -> Option<&dyn TInputDevice> {
+        None
+    }
 }
 
 impl TDevice for Arc<dyn TDevice> {
@@ -191,6 +380,10 @@ impl TDevice for Arc<dyn TDevice> {
         (**self).init_hw()
     }
 
+    fn mappable_phys_range(&self) -> Option<Range<PhysAddr>> {
+        (**self).mappable_phys_range()
+    }
+
     fn as_random(&self) -> Option<&dyn TRandomDevice> {
         (**self).as_random()
     }
@@ -198,4 +391,20 @@ impl TDevice for Arc<dyn TDevice> {
     fn as_uart(&self) -> Option<&dyn TUartDevice> {
         (**self).as_uart()
     }
+
+    fn as_storage(&self) -> Option<&dyn TStorageDevice> {
+        (**self).as_storage()
+    }
+
+    fn as_net(&self) -> Option<&dyn TNetDevice> {
+        (**self).as_net()
+    }
+
+    fn as_framebuffer(&self) -> Option<&dyn TFramebufferDevice> {
+        (**self).as_framebuffer()
+    }
+
+    fn as_input(&self) -> Option<&dyn TInputDevice> {
+        (**self).as_input()
+    }
 }
@@ -0,0 +1,41 @@
+/*! Kernel network devices support */
+
+use api_data::error::class::OsErrorClass;
+
+use crate::dev::TDevice;
+
+/**
+ * Result of the `TNetDevice` operations
+ */
+pub type NetDevResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Network interface driver interface, sends and receives ethernet frames
+ */
+pub trait TNetDevice: TDevice {
+    /**
+     * Returns the hardware address of the interface
+     */
+    fn mac_addr(&self) -> [u8; 6];
+
+    /**
+     * Returns the maximum size of the payload of the ethernet frames
+     */
+    fn mtu(&self) -> usize;
+
+    /**
+     * Transmits the given ethernet frame, which must not contain the frame
+     * check sequence.
+     *
+     * NOTE: the implementation is responsible of thread-synchronization
+     */
+    fn send_frame(&self, frame: &[u8]) -> NetDevResult<()>;
+
+    /**
+     * Copies the next received ethernet frame into the given buffer and
+     * returns his size, `None` is returned when no frames are pending.
+     *
+     * NOTE: the implementation is responsible of thread-synchronization
+     */
+    fn recv_frame(&self, buffer: &mut [u8]) -> Option<usize>;
+}
@@ -0,0 +1,804 @@
+/*! PCI bus support */
+
+use alloc::{
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    fmt,
+    ops::RangeInclusive,
+    ptr::{
+        read_volatile,
+        write_volatile
+    },
+    sync::atomic::{
+        AtomicBool,
+        Ordering
+    }
+};
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    vm::mem_manager::MemManager
+};
+
+/**
+ * Location of a PCI function into the configuration space
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq)]
+#[derive(PartialOrd, Ord)]
+pub struct PciAddress {
+    m_segment: u16,
+    m_bus: u8,
+    m_slot: u8,
+    m_function: u8
+}
+
+impl PciAddress /* Constructors */ {
+    /**
+     * Constructs a `PciAddress` from the given parameters
+     */
+    pub const fn new(segment: u16, bus: u8, slot: u8, function: u8) -> Self {
+        Self { m_segment: segment,
+               m_bus: bus,
+               m_slot: slot,
+               m_function: function }
+    }
+}
+
+impl PciAddress /* Getters */ {
+    /**
+     * Returns the segment group (always `0` without ECAM)
+     */
+    pub fn segment(&self) -> u16 {
+        self.m_segment
+    }
+
+    /**
+     * Returns the bus number
+     */
+    pub fn bus(&self) -> u8 {
+        self.m_bus
+    }
+
+    /**
+     * Returns the slot number into the bus
+     */
+    pub fn slot(&self) -> u8 {
+        self.m_slot
+    }
+
+    /**
+     * Returns the function number into the slot
+     */
+    pub fn function(&self) -> u8 {
+        self.m_function
+    }
+}
+
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,
+               "{:04x}:{:02x}:{:02x}.{}",
+               self.m_segment, self.m_bus, self.m_slot, self.m_function)
+    }
+}
+
+/**
+ * Configuration space access mechanism interface
+ */
+pub trait TPciConfigSpace: Send + Sync {
+    /**
+     * Reads the 32 bits register at the given aligned offset of the
+     * configuration space of the given function
+     */
+    fn read_u32(&self, pci_address: PciAddress, offset: u16) -> u32;
+
+    /**
+     * Writes the 32 bits register at the given aligned offset of the
+     * configuration space of the given function
+     */
+    fn write_u32(&self, pci_address: PciAddress, offset: u16, value: u32);
+
+    /**
+     * Returns the segment groups with the range of the buses accessible
+     * with this mechanism
+     */
+    fn bus_ranges(&self) -> Vec<(u16, RangeInclusive<u8>)>;
+}
+
+/**
+ * Memory mapped configuration space region described by the ACPI MCFG
+ * table
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PciEcamRegion {
+    m_phys_addr: PhysAddr,
+    m_segment: u16,
+    m_buses: RangeInclusive<u8>
+}
+
+impl PciEcamRegion /* Constructors */ {
+    /**
+     * Constructs a `PciEcamRegion` for the given segment group and buses,
+     * the given `PhysAddr` is the base of the bus `0`
+     */
+    pub fn new(phys_addr: PhysAddr, segment: u16, buses: RangeInclusive<u8>) -> Self {
+        Self { m_phys_addr: phys_addr,
+               m_segment: segment,
+               m_buses: buses }
+    }
+}
+
+/**
+ * `TPciConfigSpace` implementation based on the PCI express Enhanced
+ * Configuration Access Mechanism.
+ *
+ * Each function has 4KiB of configuration space mapped into the memory
+ */
+pub struct PciEcamConfigSpace {
+    m_regions: Vec<(PciEcamRegion, VirtAddr)>
+}
+
+impl PciEcamConfigSpace /* Constants */ {
+    const FUNCTION_SPACE_SIZE: usize = 4096;
+    const BUS_SPACE_SIZE: usize = Self::FUNCTION_SPACE_SIZE * 8 * 32;
+}
+
+impl PciEcamConfigSpace /* Constructors */ {
+    /**
+     * Constructs a `PciEcamConfigSpace` mapping the given regions, `None`
+     * is returned when no region could be mapped
+     */
+    pub fn new(ecam_regions: Vec<PciEcamRegion>) -> Option<Self> {
+        let mut mapped_regions = Vec::with_capacity(ecam_regions.len());
+        for ecam_region in ecam_regions {
+            let first_bus = *ecam_region.m_buses.start() as usize;
+            let buses_count = *ecam_region.m_buses.end() as usize - first_bus + 1;
+
+            /* map only the buses into the range */
+            let bus_phys_addr =
+                ecam_region.m_phys_addr.offset(first_bus * Self::BUS_SPACE_SIZE);
+            if let Some(virt_addr) =
+                MemManager::instance().map_kernel_mmio(bus_phys_addr,
+                                                       buses_count * Self::BUS_SPACE_SIZE)
+            {
+                mapped_regions.push((ecam_region, virt_addr));
+            }
+        }
+
+        if mapped_regions.is_empty() {
+            None
+        } else {
+            Some(Self { m_regions: mapped_regions })
+        }
+    }
+}
+
+impl PciEcamConfigSpace /* Privates */ {
+    /**
+     * Returns the pointer to the register of the given function
+     */
+    fn register_ptr(&self, pci_address: PciAddress, offset: u16) -> Option<*mut u32> {
+        let (ecam_region, virt_addr) =
+            self.m_regions.iter().find(|(ecam_region, _)| {
+                                      ecam_region.m_segment == pci_address.segment()
+                                      && ecam_region.m_buses.contains(&pci_address.bus())
+                                  })?;
+
+        let bus_index = (pci_address.bus() - *ecam_region.m_buses.start()) as usize;
+        let function_index =
+            pci_address.slot() as usize * 8 + pci_address.function() as usize;
+        let register_offset = bus_index * Self::BUS_SPACE_SIZE
+                              + function_index * Self::FUNCTION_SPACE_SIZE
+                              + (offset as usize & !0x3);
+        Some(virt_addr.offset(register_offset).as_ptr_mut())
+    }
+}
+
+impl TPciConfigSpace for PciEcamConfigSpace {
+    fn read_u32(&self, pci_address: PciAddress, offset: u16) -> u32 {
+        match self.register_ptr(pci_address, offset) {
+            Some(register_ptr) => unsafe { read_volatile(register_ptr) },
+            None => u32::MAX
+        }
+    }
+
+    fn write_u32(&self, pci_address: PciAddress, offset: u16, value: u32) {
+        if let Some(register_ptr) = self.register_ptr(pci_address, offset) {
+            unsafe {
+                write_volatile(register_ptr, value);
+            }
+        }
+    }
+
+    fn bus_ranges(&self) -> Vec<(u16, RangeInclusive<u8>)> {
+        self.m_regions
+            .iter()
+            .map(|(ecam_region, _)| (ecam_region.m_segment, ecam_region.m_buses.clone()))
+            .collect()
+    }
+}
+
+/**
+ * Base Address Register of a `PciFunction`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct PciBar {
+    m_kind: PciBarKind,
+    m_base: usize,
+    m_size: usize,
+    m_is_prefetchable: bool
+}
+
+impl PciBar /* Getters */ {
+    /**
+     * Returns the address space decoded by this BAR
+     */
+    pub fn kind(&self) -> PciBarKind {
+        self.m_kind
+    }
+
+    /**
+     * Returns the first I/O port for the `PciBarKind::Io` BARs
+     */
+    pub fn io_port(&self) -> Option<u16> {
+        match self.m_kind {
+            PciBarKind::Io => Some(self.m_base as u16),
+            PciBarKind::Memory => None
+        }
+    }
+
+    /**
+     * Returns the physical base address for the `PciBarKind::Memory` BARs
+     */
+    pub fn phys_addr(&self) -> Option<PhysAddr> {
+        match self.m_kind {
+            PciBarKind::Io => None,
+            PciBarKind::Memory => Some(self.m_base.into())
+        }
+    }
+
+    /**
+     * Returns the size in bytes of the decoded space
+     */
+    pub fn size(&self) -> usize {
+        self.m_size
+    }
+
+    /**
+     * Returns whether the reads of the memory have no side effects
+     */
+    pub fn is_prefetchable(&self) -> bool {
+        self.m_is_prefetchable
+    }
+}
+
+/**
+ * Lists the address spaces decodable by a `PciBar`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq)]
+pub enum PciBarKind {
+    Io,
+    Memory
+}
+
+/**
+ * Entry of the capabilities list of a `PciFunction`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct PciCapability {
+    m_id: u8,
+    m_offset: u16
+}
+
+impl PciCapability /* Constants */ {
+    pub const ID_MSI: u8 = 0x05;
+    pub const ID_VENDOR_SPECIFIC: u8 = 0x09;
+    pub const ID_PCI_EXPRESS: u8 = 0x10;
+    pub const ID_MSI_X: u8 = 0x11;
+}
+
+impl PciCapability /* Getters */ {
+    /**
+     * Returns the capability identifier
+     */
+    pub fn id(&self) -> u8 {
+        self.m_id
+    }
+
+    /**
+     * Returns the offset of the capability into the configuration space
+     */
+    pub fn offset(&self) -> u16 {
+        self.m_offset
+    }
+}
+
+/**
+ * Function of a PCI device discovered by `enumerate_pci_functions()`.
+ *
+ * Exposes the identifiers, the decoded BARs and the capabilities, the
+ * configuration space remains accessible to the driver which claims it
+ */
+pub struct PciFunction {
+    m_address: PciAddress,
+    m_config_space: Arc<dyn TPciConfigSpace>,
+    m_vendor_id: u16,
+    m_device_id: u16,
+    m_class: u8,
+    m_subclass: u8,
+    m_prog_if: u8,
+    m_revision: u8,
+    m_irq_line: u8,
+    m_bars: [Option<PciBar>; 6],
+    m_capabilities: Vec<PciCapability>,
+    m_is_claimed: AtomicBool
+}
+
+impl PciFunction /* Constants */ {
+    const REG_VENDOR_DEVICE: u16 = 0x00;
+    const REG_COMMAND_STATUS: u16 = 0x04;
+    const REG_CLASS_REVISION: u16 = 0x08;
+    const REG_HEADER_TYPE: u16 = 0x0C;
+    const REG_BAR0: u16 = 0x10;
+    const REG_CAPABILITIES_PTR: u16 = 0x34;
+    const REG_IRQ: u16 = 0x3C;
+
+    const CMD_IO_SPACE: u16 = 1 << 0;
+    const CMD_MEMORY_SPACE: u16 = 1 << 1;
+    const CMD_BUS_MASTER: u16 = 1 << 2;
+    const CMD_INTR_DISABLE: u16 = 1 << 10;
+    const STATUS_CAPABILITIES: u32 = 1 << 20;
+
+    const HEADER_TYPE_GENERIC: u8 = 0x00;
+    const HEADER_MULTI_FUNCTION: u8 = 0x80;
+}
+
+impl PciFunction /* Constructors */ {
+    /**
+     * Reads the function at the given `PciAddress`, `None` is returned when
+     * it doesn't exists
+     */
+    fn probe(config_space: Arc<dyn TPciConfigSpace>,
+             address: PciAddress)
+             -> Option<Self> {
+        let vendor_device = config_space.read_u32(address, Self::REG_VENDOR_DEVICE);
+        if vendor_device & 0xFFFF == 0xFFFF {
+            return None;
+        }
+
+        let class_revision = config_space.read_u32(address, Self::REG_CLASS_REVISION);
+        let irq = config_space.read_u32(address, Self::REG_IRQ);
+        let mut pci_function = Self { m_address: address,
+                                      m_config_space: config_space,
+                                      m_vendor_id: vendor_device as u16,
+                                      m_device_id: (vendor_device >> 16) as u16,
+                                      m_class: (class_revision >> 24) as u8,
+                                      m_subclass: (class_revision >> 16) as u8,
+                                      m_prog_if: (class_revision >> 8) as u8,
+                                      m_revision: class_revision as u8,
+                                      m_irq_line: irq as u8,
+                                      m_bars: [None; 6],
+                                      m_capabilities: Vec::new(),
+                                      m_is_claimed: AtomicBool::new(false) };
+
+        /* the bridges have a different header without the BARs 2..5 */
+        if pci_function.header_type() & !Self::HEADER_MULTI_FUNCTION
+           == Self::HEADER_TYPE_GENERIC
+        {
+            pci_function.m_bars = pci_function.decode_bars();
+        }
+        pci_function.m_capabilities = pci_function.walk_capabilities();
+        Some(pci_function)
+    }
+}
+
+impl PciFunction /* Methods */ {
+    /**
+     * Reads the 8 bits register at the given offset
+     */
+    pub fn read_u8(&self, offset: u16) -> u8 {
+        (self.read_u32(offset) >> ((offset & 0x3) * 8)) as u8
+    }
+
+    /**
+     * Reads the 16 bits register at the given offset
+     */
+    pub fn read_u16(&self, offset: u16) -> u16 {
+        (self.read_u32(offset) >> ((offset & 0x2) * 8)) as u16
+    }
+
+    /**
+     * Reads the 32 bits register at the given offset
+     */
+    pub fn read_u32(&self, offset: u16) -> u32 {
+        self.m_config_space.read_u32(self.m_address, offset & !0x3)
+    }
+
+    /**
+     * Writes the 16 bits register at the given offset preserving the
+     * other half of the 32 bits register
+     */
+    pub fn write_u16(&self, offset: u16, value: u16) {
+        let shift = (offset & 0x2) * 8;
+        let register = self.read_u32(offset) & !(0xFFFF << shift);
+        self.write_u32(offset, register | (value as u32) << shift);
+    }
+
+    /**
+     * Writes the 32 bits register at the given offset
+     */
+    pub fn write_u32(&self, offset: u16, value: u32) {
+        self.m_config_space.write_u32(self.m_address, offset & !0x3, value)
+    }
+
+    /**
+     * Enables the decoding of the I/O and memory BARs
+     */
+    pub fn enable_decoding(&self) {
+        self.update_command(Self::CMD_IO_SPACE | Self::CMD_MEMORY_SPACE, true);
+    }
+
+    /**
+     * Enables the DMA initiated by the function
+     */
+    pub fn enable_bus_master(&self) {
+        self.update_command(Self::CMD_BUS_MASTER, true);
+    }
+
+    /**
+     * Enables or disables the legacy INTx interrupts
+     */
+    pub fn set_intx_enabled(&self, enabled: bool) {
+        self.update_command(Self::CMD_INTR_DISABLE, !enabled);
+    }
+
+    /**
+     * Marks this function as owned by a driver, returns `false` when it
+     * was already claimed
+     */
+    pub fn claim(&self) -> bool {
+        !self.m_is_claimed.swap(true, Ordering::SeqCst)
+    }
+
+    /**
+     * Gives back this function, called when the driver's probe fails
+     */
+    pub fn unclaim(&self) {
+        self.m_is_claimed.store(false, Ordering::SeqCst);
+    }
+}
+
+impl PciFunction /* Getters */ {
+    /**
+     * Returns the `PciAddress` of this function
+     */
+    pub fn address(&self) -> PciAddress {
+        self.m_address
+    }
+
+    /**
+     * Returns the vendor identifier
+     */
+    pub fn vendor_id(&self) -> u16 {
+        self.m_vendor_id
+    }
+
+    /**
+     * Returns the device identifier
+     */
+    pub fn device_id(&self) -> u16 {
+        self.m_device_id
+    }
+
+    /**
+     * Returns the base class code
+     */
+    pub fn class(&self) -> u8 {
+        self.m_class
+    }
+
+    /**
+     * Returns the sub-class code
+     */
+    pub fn subclass(&self) -> u8 {
+        self.m_subclass
+    }
+
+    /**
+     * Returns the programming interface code
+     */
+    pub fn prog_if(&self) -> u8 {
+        self.m_prog_if
+    }
+
+    /**
+     * Returns the revision identifier
+     */
+    pub fn revision(&self) -> u8 {
+        self.m_revision
+    }
+
+    /**
+     * Returns the legacy interrupt line routed by the firmware
+     */
+    pub fn irq_line(&self) -> u8 {
+        self.m_irq_line
+    }
+
+    /**
+     * Returns the header type, the bit 7 marks the multi-function devices
+     */
+    pub fn header_type(&self) -> u8 {
+        self.read_u8(Self::REG_HEADER_TYPE + 2)
+    }
+
+    /**
+     * Returns the decoded BAR at the given index
+     */
+    pub fn bar(&self, index: usize) -> Option<PciBar> {
+        self.m_bars.get(index).copied().flatten()
+    }
+
+    /**
+     * Returns the capabilities list
+     */
+    pub fn capabilities(&self) -> &[PciCapability] {
+        self.m_capabilities.as_slice()
+    }
+
+    /**
+     * Returns the capabilities with the given identifier
+     */
+    pub fn capabilities_by_id(&self,
+                              id: u8)
+                              -> impl Iterator<Item = &PciCapability> + '_ {
+        self.m_capabilities.iter().filter(move |capability| capability.id() == id)
+    }
+
+    /**
+     * Returns whether this function is owned by a driver
+     */
+    pub fn is_claimed(&self) -> bool {
+        self.m_is_claimed.load(Ordering::SeqCst)
+    }
+}
+
+impl PciFunction /* Privates */ {
+    /**
+     * Enables or disables the given bits of the command register
+     */
+    fn update_command(&self, command_bits: u16, enabled: bool) {
+        let command = self.read_u16(Self::REG_COMMAND_STATUS);
+        let command = if enabled {
+            command | command_bits
+        } else {
+            command & !command_bits
+        };
+        self.write_u16(Self::REG_COMMAND_STATUS, command);
+    }
+
+    /**
+     * Reads the base addresses and the sizes of the six BARs
+     */
+    fn decode_bars(&self) -> [Option<PciBar>; 6] {
+        /* the decoding is disabled while the sizes are probed */
+        let command = self.read_u16(Self::REG_COMMAND_STATUS);
+        self.write_u16(Self::REG_COMMAND_STATUS,
+                       command & !(Self::CMD_IO_SPACE | Self::CMD_MEMORY_SPACE));
+
+        let mut bars = [None; 6];
+        let mut bar_index = 0;
+        while bar_index < bars.len() {
+            let bar_offset = Self::REG_BAR0 + bar_index as u16 * 4;
+            let bar_value = self.read_u32(bar_offset);
+            let size_mask = self.size_mask(bar_offset);
+
+            if bar_value & 0x1 != 0 {
+                /* I/O space BAR, only the low 16 bits are decoded */
+                let size_mask = size_mask & !0x3 & 0xFFFF;
+                if size_mask != 0 {
+                    bars[bar_index] =
+                        Some(PciBar { m_kind: PciBarKind::Io,
+                                      m_base: (bar_value & !0x3) as usize,
+                                      m_size: (!size_mask & 0xFFFF) as usize + 1,
+                                      m_is_prefetchable: false });
+                }
+            } else {
+                let is_64bit = (bar_value >> 1) & 0x3 == 0x2;
+                let mut phys_addr = (bar_value & !0xF) as u64;
+                let mut size_mask = (size_mask & !0xF) as u64;
+                if is_64bit && bar_index + 1 < bars.len() {
+                    let high_offset = bar_offset + 4;
+                    phys_addr |= (self.read_u32(high_offset) as u64) << 32;
+                    size_mask |= (self.size_mask(high_offset) as u64) << 32;
+                } else {
+                    size_mask |= 0xFFFF_FFFF_0000_0000;
+                }
+
+                if size_mask & 0xFFFF_FFFF != 0 {
+                    bars[bar_index] =
+                        Some(PciBar { m_kind: PciBarKind::Memory,
+                                      m_base: phys_addr as usize,
+                                      m_size: (!size_mask + 1) as usize,
+                                      m_is_prefetchable: bar_value & 0x8 != 0 });
+                }
+
+                /* the 64 bits BARs use two slots */
+                if is_64bit {
+                    bar_index += 1;
+                }
+            }
+            bar_index += 1;
+        }
+
+        self.write_u16(Self::REG_COMMAND_STATUS, command);
+        bars
+    }
+
+    /**
+     * Returns the writable bits of the BAR at the given offset, which is
+     * restored after the probe
+     */
+    fn size_mask(&self, bar_offset: u16) -> u32 {
+        let bar_value = self.read_u32(bar_offset);
+        self.write_u32(bar_offset, u32::MAX);
+        let size_mask = self.read_u32(bar_offset);
+        self.write_u32(bar_offset, bar_value);
+        size_mask
+    }
+
+    /**
+     * Walks the capabilities list
+     */
+    fn walk_capabilities(&self) -> Vec<PciCapability> {
+        let mut capabilities = Vec::new();
+        if self.read_u32(Self::REG_COMMAND_STATUS) & Self::STATUS_CAPABILITIES == 0 {
+            return capabilities;
+        }
+
+        /* the list is limited to catch the loops of the broken devices */
+        let mut cap_offset = (self.read_u8(Self::REG_CAPABILITIES_PTR) & !0x3) as u16;
+        while cap_offset >= 0x40 && capabilities.len() < 48 {
+            let cap_header = self.read_u16(cap_offset);
+            capabilities.push(PciCapability { m_id: cap_header as u8,
+                                              m_offset: cap_offset });
+            cap_offset = ((cap_header >> 8) as u8 & !0x3) as u16;
+        }
+        capabilities
+    }
+}
+
+/**
+ * Identifiers which a `TPciDriver` accepts, the `None` fields match
+ * everything
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct PciMatch {
+    m_vendor_id: Option<u16>,
+    m_device_id: Option<u16>,
+    m_class: Option<u8>,
+    m_subclass: Option<u8>
+}
+
+impl PciMatch /* Constructors */ {
+    /**
+     * Constructs a `PciMatch` for the given vendor and device identifiers
+     */
+    pub const fn by_ids(vendor_id: u16, device_id: u16) -> Self {
+        Self { m_vendor_id: Some(vendor_id),
+               m_device_id: Some(device_id),
+               m_class: None,
+               m_subclass: None }
+    }
+
+    /**
+     * Constructs a `PciMatch` for all the devices of the given vendor
+     */
+    pub const fn by_vendor(vendor_id: u16) -> Self {
+        Self { m_vendor_id: Some(vendor_id),
+               m_device_id: None,
+               m_class: None,
+               m_subclass: None }
+    }
+
+    /**
+     * Constructs a `PciMatch` for the given class and sub-class codes
+     */
+    pub const fn by_class(class: u8, subclass: u8) -> Self {
+        Self { m_vendor_id: None,
+               m_device_id: None,
+               m_class: Some(class),
+               m_subclass: Some(subclass) }
+    }
+}
+
+impl PciMatch /* Methods */ {
+    /**
+     * Returns whether the given `PciFunction` satisfies this `PciMatch`
+     */
+    pub fn matches(&self, pci_function: &PciFunction) -> bool {
+        self.m_vendor_id.map_or(true, |vendor_id| vendor_id == pci_function.vendor_id())
+        && self.m_device_id
+               .map_or(true, |device_id| device_id == pci_function.device_id())
+        && self.m_class.map_or(true, |class| class == pci_function.class())
+        && self.m_subclass.map_or(true, |subclass| subclass == pci_function.subclass())
+    }
+}
+
+/**
+ * PCI device driver interface.
+ *
+ * The `DevManager` offers each unclaimed `PciFunction` which satisfies one
+ * of the `PciMatch`es to `probe()`
+ */
+pub trait TPciDriver: Send + Sync {
+    /**
+     * Returns the name of the driver
+     */
+    fn driver_name(&self) -> &'static str;
+
+    /**
+     * Returns the identifiers of the functions accepted
+     */
+    fn pci_matches(&self) -> &[PciMatch];
+
+    /**
+     * Initializes the given `PciFunction` and registers his `TDevice`s into
+     * the `DevManager`.
+     *
+     * Returns whether the function is now owned by this driver
+     */
+    fn probe(&self, pci_function: &Arc<PciFunction>) -> bool;
+}
+
+/**
+ * Scans all the buses accessible with the given `TPciConfigSpace` and
+ * returns the existing functions
+ */
+pub fn enumerate_pci_functions(config_space: Arc<dyn TPciConfigSpace>)
+                               -> Vec<Arc<PciFunction>> {
+    let mut pci_functions = Vec::new();
+    for (segment, buses) in config_space.bus_ranges() {
+        for bus in buses {
+            for slot in 0..32 {
+                let pci_address = PciAddress::new(segment, bus, slot, 0);
+                let first_function =
+                    match PciFunction::probe(config_space.clone(), pci_address) {
+                        Some(first_function) => first_function,
+                        None => continue
+                    };
+
+                /* the other functions exist only with the multi-function bit */
+                let is_multi_function = first_function.header_type()
+                                        & PciFunction::HEADER_MULTI_FUNCTION
+                                        != 0;
+                pci_functions.push(Arc::new(first_function));
+                if is_multi_function {
+                    for function in 1..8 {
+                        let pci_address = PciAddress::new(segment, bus, slot, function);
+                        if let Some(pci_function) =
+                            PciFunction::probe(config_space.clone(), pci_address)
+                        {
+                            pci_functions.push(Arc::new(pci_function));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pci_functions
+}
@@ -0,0 +1,351 @@
+/*! PS/2 keyboard and mouse support
+ *
+ * Decodes the bytes sent by the PS/2 devices into `InputEvent`s, the
+ * controller which receives them is driven by the architecture
+ */
+
+use alloc::{
+    format,
+    string::String,
+    sync::Arc
+};
+use core::{
+    convert::TryFrom,
+    mem
+};
+
+use api_data::object::{
+    device::{
+        DeviceId,
+        DeviceIdClass,
+        DeviceIdType
+    },
+    input::{
+        InputEvent,
+        KeyCode,
+        MouseButton
+    }
+};
+
+use crate::dev::{
+    input::{
+        InputEventQueue,
+        TInputDevice
+    },
+    TDevice
+};
+
+/* scancode set 2 to scancode set 1 translation, as done by the i8042 */
+const C_SET2_TO_SET1: [u8; 0x84] =
+    [0x00, 0x43, 0x41, 0x3f, 0x3d, 0x3b, 0x3c, 0x58, 0x64, 0x44, 0x42, 0x40, 0x3e, 0x0f,
+     0x29, 0x59, 0x65, 0x38, 0x2a, 0x70, 0x1d, 0x10, 0x02, 0x5a, 0x66, 0x71, 0x2c, 0x1f,
+     0x1e, 0x11, 0x03, 0x5b, 0x67, 0x2e, 0x2d, 0x20, 0x12, 0x05, 0x04, 0x5c, 0x68, 0x39,
+     0x2f, 0x21, 0x14, 0x13, 0x06, 0x5d, 0x69, 0x31, 0x30, 0x23, 0x22, 0x15, 0x07, 0x5e,
+     0x6a, 0x72, 0x32, 0x24, 0x16, 0x08, 0x09, 0x5f, 0x6b, 0x33, 0x25, 0x17, 0x18, 0x0b,
+     0x0a, 0x60, 0x6c, 0x34, 0x35, 0x26, 0x27, 0x19, 0x0c, 0x61, 0x6d, 0x73, 0x28, 0x74,
+     0x1a, 0x0d, 0x62, 0x6e, 0x3a, 0x36, 0x1c, 0x1b, 0x75, 0x2b, 0x63, 0x76, 0x55, 0x56,
+     0x77, 0x78, 0x79, 0x7a, 0x0e, 0x7b, 0x7c, 0x4f, 0x7d, 0x4b, 0x47, 0x7e, 0x7f, 0x6f,
+     0x52, 0x53, 0x50, 0x4c, 0x4d, 0x48, 0x01, 0x45, 0x57, 0x4e, 0x51, 0x4a, 0x37, 0x49,
+     0x46, 0x54, 0x00, 0x00, 0x00, 0x41];
+
+/**
+ * Lists the scancode sets sent by the PS/2 keyboards
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum ScancodeSet {
+    /**
+     * XT scancodes, the release codes have the bit 7 set.
+     *
+     * Received when the controller translates the keyboard scancodes
+     */
+    Set1,
+
+    /**
+     * AT scancodes, the release codes are prefixed by 0xf0
+     */
+    Set2
+}
+
+/**
+ * Translates the scancodes sent by a PS/2 keyboard into
+ * `InputEvent::Key`s
+ */
+pub struct Ps2KeyboardDecoder {
+    m_scancode_set: ScancodeSet,
+    m_extended: bool,
+    m_released: bool,
+    m_pause_bytes: usize
+}
+
+impl Ps2KeyboardDecoder /* Constants */ {
+    const EXTENDED_PREFIX: u8 = 0xe0;
+    const PAUSE_PREFIX: u8 = 0xe1;
+    const SET2_RELEASE_PREFIX: u8 = 0xf0;
+    const SET1_RELEASE_BIT: u8 = 0x80;
+    const KEY_CODE_EXTENDED_BIT: u8 = 0x80;
+
+    /* amount of bytes which follow the pause prefix */
+    const SET1_PAUSE_BYTES: usize = 5;
+    const SET2_PAUSE_BYTES: usize = 7;
+
+    /* fake shifts sent with the 0xe0 prefix around some extended keys */
+    const SET1_LEFT_SHIFT: u8 = 0x2a;
+    const SET1_RIGHT_SHIFT: u8 = 0x36;
+}
+
+impl Ps2KeyboardDecoder /* Constructors */ {
+    /**
+     * Constructs a `Ps2KeyboardDecoder` for the given `ScancodeSet`
+     */
+    pub const fn new(scancode_set: ScancodeSet) -> Self {
+        Self { m_scancode_set: scancode_set,
+               m_extended: false,
+               m_released: false,
+               m_pause_bytes: 0 }
+    }
+}
+
+impl Ps2KeyboardDecoder /* Methods */ {
+    /**
+     * Consumes the given scancode byte and returns the `InputEvent` which
+     * it completes, if any
+     */
+    pub fn decode(&mut self, scancode: u8) -> Option<InputEvent> {
+        /* the pause key has no release code, it is released with the last
+         * byte of his sequence
+         */
+        if self.m_pause_bytes > 0 {
+            self.m_pause_bytes -= 1;
+            return if self.m_pause_bytes == 0 {
+                Some(InputEvent::Key(KeyCode::Pause, false))
+            } else {
+                None
+            };
+        }
+
+        match scancode {
+            Self::EXTENDED_PREFIX => {
+                self.m_extended = true;
+                None
+            },
+            Self::PAUSE_PREFIX => {
+                self.m_pause_bytes = match self.m_scancode_set {
+                    ScancodeSet::Set1 => Self::SET1_PAUSE_BYTES,
+                    ScancodeSet::Set2 => Self::SET2_PAUSE_BYTES
+                };
+                Some(InputEvent::Key(KeyCode::Pause, true))
+            },
+            Self::SET2_RELEASE_PREFIX if self.m_scancode_set == ScancodeSet::Set2 => {
+                self.m_released = true;
+                None
+            },
+            _ => {
+                let extended = mem::replace(&mut self.m_extended, false);
+                let released = mem::replace(&mut self.m_released, false);
+
+                let (set1_code, pressed) = match self.m_scancode_set {
+                    ScancodeSet::Set1 => (scancode & !Self::SET1_RELEASE_BIT,
+                                          scancode & Self::SET1_RELEASE_BIT == 0),
+                    ScancodeSet::Set2 => {
+                        (*C_SET2_TO_SET1.get(scancode as usize)?, !released)
+                    },
+                };
+
+                /* the fake shifts are not real key events */
+                if extended
+                   && (set1_code == Self::SET1_LEFT_SHIFT
+                       || set1_code == Self::SET1_RIGHT_SHIFT)
+                {
+                    return None;
+                }
+
+                let raw_key_code = if extended {
+                    set1_code | Self::KEY_CODE_EXTENDED_BIT
+                } else {
+                    set1_code
+                };
+                KeyCode::try_from(raw_key_code).ok().map(|key_code| {
+                                                        InputEvent::Key(key_code, pressed)
+                                                    })
+            }
+        }
+    }
+}
+
+/**
+ * Assembles the packets sent by a PS/2 mouse and translates them into
+ * `InputEvent`s
+ */
+pub struct Ps2MouseDecoder {
+    m_packet: [u8; 4],
+    m_packet_size: usize,
+    m_received: usize,
+    m_buttons: u8
+}
+
+impl Ps2MouseDecoder /* Constants */ {
+    const FLAG_ALWAYS_ONE: u8 = 1 << 3;
+    const FLAG_X_SIGN: u8 = 1 << 4;
+    const FLAG_Y_SIGN: u8 = 1 << 5;
+    const FLAG_X_OVERFLOW: u8 = 1 << 6;
+    const FLAG_Y_OVERFLOW: u8 = 1 << 7;
+
+    const BUTTONS_MASK: u8 = 0x07;
+    const BUTTONS: [(u8, MouseButton); 3] = [(1 << 0, MouseButton::Left),
+                                             (1 << 1, MouseButton::Right),
+                                             (1 << 2, MouseButton::Middle)];
+}
+
+impl Ps2MouseDecoder /* Constructors */ {
+    /**
+     * Constructs a `Ps2MouseDecoder` for a mouse which sends 3 bytes
+     * packets, or 4 bytes packets when it has the wheel
+     */
+    pub const fn new(has_wheel: bool) -> Self {
+        Self { m_packet: [0; 4],
+               m_packet_size: if has_wheel {
+                   4
+               } else {
+                   3
+               },
+               m_received: 0,
+               m_buttons: 0 }
+    }
+}
+
+impl Ps2MouseDecoder /* Methods */ {
+    /**
+     * Consumes the given packet byte and gives to `emit_fn` the
+     * `InputEvent`s of the packet which it completes
+     */
+    pub fn decode<F>(&mut self, packet_byte: u8, mut emit_fn: F)
+        where F: FnMut(InputEvent) {
+        /* the first byte is discarded when it is not a valid flags byte,
+         * this resynchronizes the stream after a lost byte
+         */
+        if self.m_received == 0 && packet_byte & Self::FLAG_ALWAYS_ONE == 0 {
+            return;
+        }
+        self.m_packet[self.m_received] = packet_byte;
+        self.m_received += 1;
+        if self.m_received < self.m_packet_size {
+            return;
+        }
+        self.m_received = 0;
+
+        /* the movements of the overflowed packets are meaningless */
+        let flags = self.m_packet[0];
+        if flags & (Self::FLAG_X_OVERFLOW | Self::FLAG_Y_OVERFLOW) == 0 {
+            let delta_x =
+                Self::signed_delta(self.m_packet[1], flags & Self::FLAG_X_SIGN != 0);
+            let delta_y =
+                Self::signed_delta(self.m_packet[2], flags & Self::FLAG_Y_SIGN != 0);
+
+            /* the mouse counts the vertical movements upward */
+            if delta_x != 0 || delta_y != 0 {
+                emit_fn(InputEvent::MouseMove(delta_x, -delta_y));
+            }
+        }
+
+        for (button_bit, mouse_button) in Self::BUTTONS.iter() {
+            if (flags ^ self.m_buttons) & button_bit != 0 {
+                emit_fn(InputEvent::MouseButton(*mouse_button, flags & button_bit != 0));
+            }
+        }
+        self.m_buttons = flags & Self::BUTTONS_MASK;
+
+        /* the wheel movement is a 4 bits signed value */
+        if self.m_packet_size == 4 {
+            let wheel_delta = ((self.m_packet[3] << 4) as i8) >> 4;
+            if wheel_delta != 0 {
+                emit_fn(InputEvent::MouseWheel(wheel_delta));
+            }
+        }
+    }
+}
+
+impl Ps2MouseDecoder /* Privates */ {
+    /**
+     * Returns the 9 bits movement composed by the given byte and sign bit
+     */
+    fn signed_delta(delta_byte: u8, is_negative: bool) -> i16 {
+        if is_negative {
+            delta_byte as i16 - 0x100
+        } else {
+            delta_byte as i16
+        }
+    }
+}
+
+/**
+ * `TInputDevice` which exposes the `InputEvent`s of a PS/2 keyboard or
+ * mouse, they are enqueued by the interrupt handler of the controller
+ */
+pub struct Ps2InputDevice {
+    m_device_id: DeviceId,
+    m_name_prefix: &'static str,
+    m_events: Arc<InputEventQueue>
+}
+
+impl Ps2InputDevice /* Constructors */ {
+    /**
+     * Constructs a `Ps2InputDevice` for a keyboard which reads the given
+     * `InputEventQueue`
+     */
+    pub fn new_keyboard(events: Arc<InputEventQueue>, serial_value: u32) -> Self {
+        Self::new(events, "kbd", serial_value)
+    }
+
+    /**
+     * Constructs a `Ps2InputDevice` for a mouse which reads the given
+     * `InputEventQueue`
+     */
+    pub fn new_mouse(events: Arc<InputEventQueue>, serial_value: u32) -> Self {
+        Self::new(events, "mouse", serial_value)
+    }
+
+    /**
+     * Constructs a `Ps2InputDevice` with the given name prefix
+     */
+    fn new(events: Arc<InputEventQueue>,
+           name_prefix: &'static str,
+           serial_value: u32)
+           -> Self {
+        Self { m_device_id: DeviceId::new(DeviceIdType::Character,
+                                          DeviceIdClass::Terminal,
+                                          serial_value),
+               m_name_prefix: name_prefix,
+               m_events: events }
+    }
+}
+
+impl TInputDevice for Ps2InputDevice {
+    fn next_event(&self) -> Option<InputEvent> {
+        self.m_events.pop()
+    }
+
+    fn has_events(&self) -> bool {
+        !self.m_events.is_empty()
+    }
+}
+
+impl TDevice for Ps2InputDevice {
+    fn device_id(&self) -> DeviceId {
+        self.m_device_id
+    }
+
+    fn device_name(&self) -> String {
+        format!("{}_{}", self.m_name_prefix, self.m_device_id.serial_value())
+    }
+
+    fn init_hw(&self) -> bool {
+        /* the controller is initialized by the architecture before */
+        true
+    }
+
+    fn as_input(&self) -> Option<&dyn TInputDevice> {
+        Some(self)
+    }
+}
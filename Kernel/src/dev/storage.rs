@@ -0,0 +1,118 @@
+/*! Kernel storage support */
+
+use alloc::sync::Arc;
+
+use api_data::error::class::OsErrorClass;
+use meetix_fs::{
+    dev::TBlockDevice,
+    MxFsResult
+};
+
+use crate::dev::TDevice;
+
+/**
+ * Result of the `TStorageDevice` operations
+ */
+pub type StorageResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Storage device driver interface, reads and writes contiguous blocks of
+ * the same size
+ */
+pub trait TStorageDevice: TDevice {
+    /**
+     * Returns the size in bytes of each block of the media
+     */
+    fn block_size(&self) -> usize;
+
+    /**
+     * Returns the amount of blocks of the media
+     */
+    fn blocks_count(&self) -> u64;
+
+    /**
+     * Returns whether the media refuses the writes (i.e. a CD-ROM)
+     */
+    fn is_read_only(&self) -> bool;
+
+    /**
+     * Reads the blocks starting from the given one into the buffer, which
+     * must be a multiple of the `block_size()`
+     */
+    fn read_blocks(&self, first_block: u64, buffer: &mut [u8]) -> StorageResult<()>;
+
+    /**
+     * Writes the blocks starting from the given one with the buffer, which
+     * must be a multiple of the `block_size()`
+     */
+    fn write_blocks(&self, first_block: u64, buffer: &[u8]) -> StorageResult<()>;
+
+    /**
+     * Makes persistent the blocks written until now.
+     *
+     * NOTE: the implementation is responsible of thread-synchronization
+     */
+    fn flush(&self) -> StorageResult<()>;
+}
+
+/**
+ * `TBlockDevice` which reads and writes the blocks of a registered
+ * `TStorageDevice`, used to mount the filesystems stored into the disks
+ */
+pub struct StorageBlockDevice {
+    m_device: Arc<dyn TDevice>,
+    m_writeable: bool
+}
+
+impl StorageBlockDevice /* Constructors */ {
+    /**
+     * Constructs a `StorageBlockDevice` for the given device driver, `None`
+     * is returned when it is not a `TStorageDevice`.
+     *
+     * The writes are refused when `writeable` is `false`
+     */
+    pub fn new(device: Arc<dyn TDevice>, writeable: bool) -> Option<Self> {
+        device.as_storage()?;
+        Some(Self { m_device: device,
+                    m_writeable: writeable })
+    }
+}
+
+impl StorageBlockDevice /* Privates */ {
+    /**
+     * Returns the `TStorageDevice` wrapped
+     */
+    fn storage(&self) -> &dyn TStorageDevice {
+        self.m_device.as_storage().unwrap()
+    }
+}
+
+impl TBlockDevice for StorageBlockDevice {
+    fn block_size(&self) -> usize {
+        self.storage().block_size()
+    }
+
+    fn blocks_count(&self) -> u64 {
+        self.storage().blocks_count()
+    }
+
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()> {
+        self.storage().read_blocks(first_block, buffer)
+    }
+
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()> {
+        if !self.m_writeable {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Device not opened for write")));
+        }
+        self.storage().write_blocks(first_block, buffer)
+    }
+
+    fn flush(&mut self) -> MxFsResult<()> {
+        if self.m_writeable {
+            self.storage().flush()
+        } else {
+            Ok(())
+        }
+    }
+}
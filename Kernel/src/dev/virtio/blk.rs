@@ -0,0 +1,500 @@
+/*! Virtio block device driver */
+
+use alloc::{
+    string::String,
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    hint::spin_loop,
+    ptr
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::device::{
+        DeviceId,
+        DeviceIdClass,
+        DeviceIdType
+    }
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        TAddress
+    },
+    dev::{
+        pci::{
+            PciFunction,
+            PciMatch,
+            TPciDriver
+        },
+        storage::{
+            StorageResult,
+            TStorageDevice
+        },
+        virtio::{
+            VirtioPciTransport,
+            Virtqueue,
+            VirtqueueBuffer,
+            VIRTIO_PCI_VENDOR_ID
+        },
+        DevManager,
+        TDevice
+    },
+    vm::{
+        mem_manager::MemManager,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * `TStorageDevice` implementation for the virtio block devices.
+ *
+ * The transfers are split into batches of requests, each one with his own
+ * bounce frame, which are submitted to the device with a single
+ * notification and completed in polling
+ */
+pub struct VirtioBlkDevice {
+    m_device_id: DeviceId,
+    m_transport: VirtioPciTransport,
+    m_state: SpinMutex<Option<VirtioBlkState>>
+}
+
+impl VirtioBlkDevice /* Constants */ {
+    const FEATURE_RO: u64 = 1 << 5;
+    const FEATURE_BLK_SIZE: u64 = 1 << 6;
+    const FEATURE_FLUSH: u64 = 1 << 9;
+
+    const CFG_CAPACITY: usize = 0x00;
+    const CFG_BLK_SIZE: usize = 0x14;
+}
+
+impl VirtioBlkDevice /* Constructors */ {
+    /**
+     * Constructs an uninitialized `VirtioBlkDevice` which uses the given
+     * `VirtioPciTransport`
+     */
+    pub fn new(transport: VirtioPciTransport, serial_value: u32) -> Self {
+        Self { m_device_id: DeviceId::new(DeviceIdType::Block,
+                                          DeviceIdClass::Storage,
+                                          serial_value),
+               m_transport: transport,
+               m_state: SpinMutex::const_new(None) }
+    }
+}
+
+impl VirtioBlkDevice /* Privates */ {
+    /**
+     * Negotiates the features and configures the request queue, the device
+     * is marked as failed by the caller on `None`
+     */
+    fn init_state(&self) -> Option<VirtioBlkState> {
+        let driver_features =
+            Self::FEATURE_RO | Self::FEATURE_BLK_SIZE | Self::FEATURE_FLUSH;
+        let features = self.m_transport.negotiate_features(driver_features)?;
+
+        /* the capacity is always expressed in 512 bytes sectors */
+        let sectors_count = self.m_transport.device_cfg_read::<u64>(Self::CFG_CAPACITY);
+        let block_size = if features & Self::FEATURE_BLK_SIZE != 0 {
+            self.m_transport.device_cfg_read::<u32>(Self::CFG_BLK_SIZE) as usize
+        } else {
+            VirtioBlkState::SECTOR_SIZE
+        };
+
+        /* each block must fit into a bounce frame */
+        if !block_size.is_power_of_two()
+           || block_size < VirtioBlkState::SECTOR_SIZE
+           || block_size > Page4KiB::SIZE
+        {
+            return None;
+        }
+
+        let virtqueue = self.m_transport.setup_queue(0)?;
+        let state = VirtioBlkState::new(virtqueue,
+                                        sectors_count,
+                                        block_size,
+                                        features & Self::FEATURE_RO != 0,
+                                        features & Self::FEATURE_FLUSH != 0)?;
+
+        self.m_transport.set_driver_ok();
+        Some(state)
+    }
+
+    /**
+     * Executes the given closure with the `VirtioBlkState` initialized by
+     * `init_hw()`
+     */
+    fn with_state<F, R>(&self, state_fn: F) -> StorageResult<R>
+        where F: FnOnce(&mut VirtioBlkState) -> StorageResult<R> {
+        match self.m_state.lock().as_mut() {
+            Some(state) => state_fn(state),
+            None => {
+                Err((OsErrorClass::ReferenceNotFound, Some("virtio-blk not initialized")))
+            },
+        }
+    }
+}
+
+impl TDevice for VirtioBlkDevice {
+    fn device_id(&self) -> DeviceId {
+        self.m_device_id
+    }
+
+    fn device_name(&self) -> String {
+        format!("vblk_{}", self.m_device_id.serial_value())
+    }
+
+    fn init_hw(&self) -> bool {
+        match self.init_state() {
+            Some(state) => {
+                *self.m_state.lock() = Some(state);
+                true
+            },
+            None => {
+                self.m_transport.set_failed();
+                false
+            }
+        }
+    }
+
+    fn as_storage(&self) -> Option<&dyn TStorageDevice> {
+        Some(self)
+    }
+}
+
+impl TStorageDevice for VirtioBlkDevice {
+    fn block_size(&self) -> usize {
+        self.with_state(|state| Ok(state.m_block_size)).unwrap_or(0)
+    }
+
+    fn blocks_count(&self) -> u64 {
+        self.with_state(|state| Ok(state.blocks_count())).unwrap_or(0)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.with_state(|state| Ok(state.m_is_read_only)).unwrap_or(true)
+    }
+
+    fn read_blocks(&self, first_block: u64, buffer: &mut [u8]) -> StorageResult<()> {
+        self.with_state(|state| {
+                let mut first_sector = state.first_sector(first_block, buffer.len())?;
+                for chunk in buffer.chunks_mut(VirtioBlkState::BATCH_SIZE) {
+                    state.submit_batch(VirtioBlkState::REQ_TYPE_IN,
+                                       first_sector,
+                                       chunk.len())?;
+                    state.copy_from_frames(chunk);
+                    first_sector += (chunk.len() / VirtioBlkState::SECTOR_SIZE) as u64;
+                }
+                Ok(())
+            })
+    }
+
+    fn write_blocks(&self, first_block: u64, buffer: &[u8]) -> StorageResult<()> {
+        self.with_state(|state| {
+                if state.m_is_read_only {
+                    return Err((OsErrorClass::OperationNotEnabled,
+                                Some("Read-only virtio-blk")));
+                }
+
+                let mut first_sector = state.first_sector(first_block, buffer.len())?;
+                for chunk in buffer.chunks(VirtioBlkState::BATCH_SIZE) {
+                    state.copy_into_frames(chunk);
+                    state.submit_batch(VirtioBlkState::REQ_TYPE_OUT,
+                                       first_sector,
+                                       chunk.len())?;
+                    first_sector += (chunk.len() / VirtioBlkState::SECTOR_SIZE) as u64;
+                }
+                Ok(())
+            })
+    }
+
+    fn flush(&self) -> StorageResult<()> {
+        self.with_state(|state| {
+                if state.m_has_flush {
+                    state.submit_batch(VirtioBlkState::REQ_TYPE_FLUSH, 0, 0)
+                } else {
+                    Ok(())
+                }
+            })
+    }
+}
+
+/**
+ * `TPciDriver` for the virtio block devices, both the transitional and the
+ * modern ones are driven with the virtio 1.x interface
+ */
+pub struct VirtioBlkPciDriver;
+
+impl VirtioBlkPciDriver /* Constants */ {
+    const PCI_MATCHES: [PciMatch; 2] = [PciMatch::by_ids(VIRTIO_PCI_VENDOR_ID, 0x1001),
+                                        PciMatch::by_ids(VIRTIO_PCI_VENDOR_ID, 0x1042)];
+}
+
+impl TPciDriver for VirtioBlkPciDriver {
+    fn driver_name(&self) -> &'static str {
+        "virtio-blk"
+    }
+
+    fn pci_matches(&self) -> &[PciMatch] {
+        &Self::PCI_MATCHES
+    }
+
+    fn probe(&self, pci_function: &Arc<PciFunction>) -> bool {
+        pci_function.enable_decoding();
+        pci_function.enable_bus_master();
+
+        let transport = match VirtioPciTransport::new(pci_function) {
+            Some(transport) => transport,
+            None => return false
+        };
+
+        let dev_manager = DevManager::instance();
+        let serial_value = dev_manager.next_serial_value(DeviceIdClass::Storage);
+        dev_manager.register_device(VirtioBlkDevice::new(transport, serial_value))
+    }
+}
+
+/**
+ * Request queue of a `VirtioBlkDevice` with the frames of the requests
+ */
+struct VirtioBlkState {
+    m_virtqueue: Virtqueue,
+    m_header_frame: PhysAddr,
+    m_data_frames: Vec<PhysAddr>,
+    m_sectors_count: u64,
+    m_block_size: usize,
+    m_is_read_only: bool,
+    m_has_flush: bool
+}
+
+impl VirtioBlkState /* Constants */ {
+    /**
+     * Amount of requests submitted with a single notification, each one
+     * uses three descriptors
+     */
+    const BATCH_REQUESTS: usize = 16;
+    const BATCH_SIZE: usize = Self::BATCH_REQUESTS * Page4KiB::SIZE;
+
+    const SECTOR_SIZE: usize = 512;
+    const HEADER_SIZE: usize = 16;
+    const HEADER_SLOT_SIZE: usize = 32;
+
+    const REQ_TYPE_IN: u32 = 0;
+    const REQ_TYPE_OUT: u32 = 1;
+    const REQ_TYPE_FLUSH: u32 = 4;
+
+    const REQ_STATUS_OK: u8 = 0;
+    const REQ_STATUS_UNSUPPORTED: u8 = 2;
+    const REQ_STATUS_PENDING: u8 = 0xFF;
+
+    const POLLS_MAX: usize = 10_000_000;
+}
+
+impl VirtioBlkState /* Constructors */ {
+    /**
+     * Constructs a `VirtioBlkState` allocating the frames of the requests
+     */
+    fn new(virtqueue: Virtqueue,
+           sectors_count: u64,
+           block_size: usize,
+           is_read_only: bool,
+           has_flush: bool)
+           -> Option<Self> {
+        let mem_manager = MemManager::instance();
+
+        /* the <Drop> implementation releases the frames on failure */
+        let mut state = Self { m_virtqueue: virtqueue,
+                               m_header_frame:
+                                   mem_manager.allocate_kernel_phys_frame()?,
+                               m_data_frames: Vec::with_capacity(Self::BATCH_REQUESTS),
+                               m_sectors_count: sectors_count,
+                               m_block_size: block_size,
+                               m_is_read_only: is_read_only,
+                               m_has_flush: has_flush };
+        for _ in 0..Self::BATCH_REQUESTS {
+            state.m_data_frames.push(mem_manager.allocate_kernel_phys_frame()?);
+        }
+
+        /* the queue must contain a whole batch */
+        if state.m_virtqueue.size() as usize >= Self::BATCH_REQUESTS * 3 {
+            Some(state)
+        } else {
+            None
+        }
+    }
+}
+
+impl VirtioBlkState /* Methods */ {
+    /**
+     * Submits a request for each frame needed by the given amount of
+     * bytes, starting from the given sector, and waits the completion of
+     * all of them.
+     *
+     * The `REQ_TYPE_FLUSH` is submitted as a single request without data
+     */
+    fn submit_batch(&mut self,
+                    request_type: u32,
+                    first_sector: u64,
+                    data_len: usize)
+                    -> StorageResult<()> {
+        let requests_count = if request_type == Self::REQ_TYPE_FLUSH {
+            1
+        } else {
+            (data_len + Page4KiB::SIZE - 1) / Page4KiB::SIZE
+        };
+
+        for request_index in 0..requests_count {
+            let header_addr =
+                self.m_header_frame.offset(request_index * Self::HEADER_SLOT_SIZE);
+            let status_addr = header_addr.offset(Self::HEADER_SIZE);
+            let sector_offset =
+                (request_index * Page4KiB::SIZE / Self::SECTOR_SIZE) as u64;
+
+            /* the header contains the type, a reserved field and the sector */
+            unsafe {
+                let header_ptr = self.frame_ptr(header_addr);
+                ptr::write_volatile(header_ptr as *mut u32, request_type);
+                ptr::write_volatile((header_ptr as *mut u32).add(1), 0);
+                ptr::write_volatile((header_ptr as *mut u64).add(1),
+                                    first_sector + sector_offset);
+                ptr::write_volatile(self.frame_ptr(status_addr),
+                                    Self::REQ_STATUS_PENDING);
+            }
+
+            let header_buffer =
+                VirtqueueBuffer::new_readable(header_addr, Self::HEADER_SIZE);
+            let status_buffer = VirtqueueBuffer::new_writable(status_addr, 1);
+            let head_desc = if request_type == Self::REQ_TYPE_FLUSH {
+                self.m_virtqueue.push_chain(&[header_buffer, status_buffer])
+            } else {
+                let data_frame = self.m_data_frames[request_index];
+                let request_len =
+                    (data_len - request_index * Page4KiB::SIZE).min(Page4KiB::SIZE);
+                let data_buffer = if request_type == Self::REQ_TYPE_IN {
+                    VirtqueueBuffer::new_writable(data_frame, request_len)
+                } else {
+                    VirtqueueBuffer::new_readable(data_frame, request_len)
+                };
+                self.m_virtqueue.push_chain(&[header_buffer, data_buffer, status_buffer])
+            };
+
+            if head_desc.is_none() {
+                return Err((OsErrorClass::LimitReached, Some("virtio-blk queue full")));
+            }
+        }
+        self.m_virtqueue.notify();
+
+        /* wait all the requests of the batch */
+        let mut completed_count = 0;
+        let mut polls = 0;
+        while completed_count < requests_count {
+            if self.m_virtqueue.pop_used().is_some() {
+                completed_count += 1;
+            } else if polls >= Self::POLLS_MAX {
+                return Err((OsErrorClass::InterruptedOperation,
+                            Some("virtio-blk request timeout")));
+            } else {
+                polls += 1;
+                spin_loop();
+            }
+        }
+
+        for request_index in 0..requests_count {
+            let status_addr =
+                self.m_header_frame
+                    .offset(request_index * Self::HEADER_SLOT_SIZE + Self::HEADER_SIZE);
+            match unsafe { ptr::read_volatile(self.frame_ptr(status_addr)) } {
+                Self::REQ_STATUS_OK => continue,
+                Self::REQ_STATUS_UNSUPPORTED => {
+                    return Err((OsErrorClass::OperationNotEnabled,
+                                Some("virtio-blk request unsupported")));
+                },
+                _ => return Err((OsErrorClass::Unknown, Some("virtio-blk I/O error")))
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Copies the given data into the data frames
+     */
+    fn copy_into_frames(&self, buffer: &[u8]) {
+        for (data_frame, chunk) in
+            self.m_data_frames.iter().zip(buffer.chunks(Page4KiB::SIZE))
+        {
+            unsafe {
+                ptr::copy_nonoverlapping(chunk.as_ptr(),
+                                         self.frame_ptr(*data_frame),
+                                         chunk.len());
+            }
+        }
+    }
+
+    /**
+     * Copies the data frames into the given buffer
+     */
+    fn copy_from_frames(&self, buffer: &mut [u8]) {
+        for (data_frame, chunk) in
+            self.m_data_frames.iter().zip(buffer.chunks_mut(Page4KiB::SIZE))
+        {
+            unsafe {
+                ptr::copy_nonoverlapping(self.frame_ptr(*data_frame),
+                                         chunk.as_mut_ptr(),
+                                         chunk.len());
+            }
+        }
+    }
+}
+
+impl VirtioBlkState /* Getters */ {
+    /**
+     * Returns the amount of blocks of the media
+     */
+    fn blocks_count(&self) -> u64 {
+        self.m_sectors_count * Self::SECTOR_SIZE as u64 / self.m_block_size as u64
+    }
+
+    /**
+     * Validates the given transfer and returns his first sector
+     */
+    fn first_sector(&self, first_block: u64, buffer_len: usize) -> StorageResult<u64> {
+        if buffer_len % self.m_block_size != 0 {
+            return Err((OsErrorClass::InvalidArgument,
+                        Some("Buffer not multiple of the block size")));
+        }
+
+        let blocks_count = (buffer_len / self.m_block_size) as u64;
+        if first_block.checked_add(blocks_count)
+                      .map_or(true, |end_block| end_block > self.blocks_count())
+        {
+            return Err((OsErrorClass::EndOfDataReached, Some("Block out of the media")));
+        }
+        Ok(first_block * (self.m_block_size / Self::SECTOR_SIZE) as u64)
+    }
+}
+
+impl VirtioBlkState /* Privates */ {
+    /**
+     * Returns the pointer to the given physical address into the physical
+     * memory mapping
+     */
+    fn frame_ptr(&self, phys_addr: PhysAddr) -> *mut u8 {
+        MemManager::instance().layout_manager()
+                              .phys_addr_to_virt_addr(phys_addr)
+                              .as_ptr_mut()
+    }
+}
+
+impl Drop for VirtioBlkState {
+    fn drop(&mut self) {
+        let mem_manager = MemManager::instance();
+        mem_manager.free_kernel_phys_frame(self.m_header_frame);
+        for data_frame in self.m_data_frames.iter() {
+            mem_manager.free_kernel_phys_frame(*data_frame);
+        }
+    }
+}
@@ -0,0 +1,584 @@
+/*! Virtio devices support
+ *
+ * Implements the virtio 1.x PCI transport and the split virtqueues, the
+ * device drivers are into the sub-modules
+ */
+
+use alloc::vec::Vec;
+use core::{
+    hint::spin_loop,
+    mem::size_of,
+    ptr,
+    ptr::{
+        read_volatile,
+        write_volatile
+    },
+    sync::atomic::{
+        fence,
+        Ordering
+    }
+};
+
+use helps::align::align_up;
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    dev::pci::{
+        PciCapability,
+        PciFunction
+    },
+    vm::{
+        mem_manager::MemManager,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+pub mod blk;
+pub mod net;
+
+/**
+ * PCI vendor identifier of the virtio devices
+ */
+pub const VIRTIO_PCI_VENDOR_ID: u16 = 0x1AF4;
+
+/**
+ * Feature bit which marks the devices compliant to the virtio 1.x
+ */
+pub const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+/**
+ * Virtio 1.x PCI transport.
+ *
+ * Accesses the common, notification and device specific configuration
+ * structures described by the vendor specific capabilities of the
+ * `PciFunction`
+ */
+pub struct VirtioPciTransport {
+    m_common_cfg: VirtAddr,
+    m_notify_cfg: VirtAddr,
+    m_notify_off_multiplier: u32,
+    m_device_cfg: VirtAddr
+}
+
+impl VirtioPciTransport /* Constants */ {
+    const CAP_CFG_TYPE_COMMON: u8 = 1;
+    const CAP_CFG_TYPE_NOTIFY: u8 = 2;
+    const CAP_CFG_TYPE_DEVICE: u8 = 4;
+
+    const COMMON_DEVICE_FEATURE_SELECT: usize = 0x00;
+    const COMMON_DEVICE_FEATURE: usize = 0x04;
+    const COMMON_DRIVER_FEATURE_SELECT: usize = 0x08;
+    const COMMON_DRIVER_FEATURE: usize = 0x0C;
+    const COMMON_DEVICE_STATUS: usize = 0x14;
+    const COMMON_CONFIG_GENERATION: usize = 0x15;
+    const COMMON_QUEUE_SELECT: usize = 0x16;
+    const COMMON_QUEUE_SIZE: usize = 0x18;
+    const COMMON_QUEUE_ENABLE: usize = 0x1C;
+    const COMMON_QUEUE_NOTIFY_OFF: usize = 0x1E;
+    const COMMON_QUEUE_DESC: usize = 0x20;
+    const COMMON_QUEUE_DRIVER: usize = 0x28;
+    const COMMON_QUEUE_DEVICE: usize = 0x30;
+
+    const STATUS_ACKNOWLEDGE: u8 = 1 << 0;
+    const STATUS_DRIVER: u8 = 1 << 1;
+    const STATUS_DRIVER_OK: u8 = 1 << 2;
+    const STATUS_FEATURES_OK: u8 = 1 << 3;
+    const STATUS_FAILED: u8 = 1 << 7;
+
+    const RESET_POLLS_MAX: usize = 1_000_000;
+}
+
+impl VirtioPciTransport /* Constructors */ {
+    /**
+     * Constructs a `VirtioPciTransport` mapping the configuration
+     * structures of the given `PciFunction`, `None` is returned for the
+     * legacy only devices
+     */
+    pub fn new(pci_function: &PciFunction) -> Option<Self> {
+        let mut common_cfg = None;
+        let mut notify_cfg = None;
+        let mut device_cfg = None;
+        let mut notify_off_multiplier = 0;
+
+        for capability in
+            pci_function.capabilities_by_id(PciCapability::ID_VENDOR_SPECIFIC)
+        {
+            let cap_offset = capability.offset();
+            let cfg_type = pci_function.read_u8(cap_offset + 3);
+            let cfg_slot = match cfg_type {
+                Self::CAP_CFG_TYPE_COMMON => &mut common_cfg,
+                Self::CAP_CFG_TYPE_NOTIFY => &mut notify_cfg,
+                Self::CAP_CFG_TYPE_DEVICE => &mut device_cfg,
+                _ => continue
+            };
+
+            /* the first usable capability of each type is the preferred one */
+            if cfg_slot.is_some() {
+                continue;
+            }
+
+            let bar_index = pci_function.read_u8(cap_offset + 4) as usize;
+            let cfg_phys_addr = match pci_function.bar(bar_index)
+                                                  .and_then(|bar| bar.phys_addr())
+            {
+                Some(bar_phys_addr) => {
+                    bar_phys_addr.offset(pci_function.read_u32(cap_offset + 8) as usize)
+                },
+                None => continue
+            };
+            let cfg_len = pci_function.read_u32(cap_offset + 12) as usize;
+
+            *cfg_slot = MemManager::instance().map_kernel_mmio(cfg_phys_addr, cfg_len);
+            if cfg_type == Self::CAP_CFG_TYPE_NOTIFY {
+                notify_off_multiplier = pci_function.read_u32(cap_offset + 16);
+            }
+        }
+
+        Some(Self { m_common_cfg: common_cfg?,
+                    m_notify_cfg: notify_cfg?,
+                    m_notify_off_multiplier: notify_off_multiplier,
+                    m_device_cfg: device_cfg? })
+    }
+}
+
+impl VirtioPciTransport /* Methods */ {
+    /**
+     * Resets the device and negotiates the features, the accepted ones are
+     * the given `driver_features` offered by the device.
+     *
+     * Returns the accepted features or `None` when the device refuses them
+     */
+    pub fn negotiate_features(&self, driver_features: u64) -> Option<u64> {
+        if !self.reset() {
+            return None;
+        }
+        self.add_status(Self::STATUS_ACKNOWLEDGE | Self::STATUS_DRIVER);
+
+        let device_features = self.read_features();
+        let features = device_features & (driver_features | VIRTIO_F_VERSION_1);
+        if features & VIRTIO_F_VERSION_1 == 0 {
+            self.set_failed();
+            return None;
+        }
+
+        self.write_features(features);
+        self.add_status(Self::STATUS_FEATURES_OK);
+
+        /* the device clears <FEATURES_OK> when doesn't support the subset */
+        if self.status() & Self::STATUS_FEATURES_OK == 0 {
+            self.set_failed();
+            None
+        } else {
+            Some(features)
+        }
+    }
+
+    /**
+     * Configures and enables the virtqueue at the given index, the size is
+     * truncated to `Virtqueue::SIZE_MAX`
+     */
+    pub fn setup_queue(&self, queue_index: u16) -> Option<Virtqueue> {
+        self.common_write::<u16>(Self::COMMON_QUEUE_SELECT, queue_index);
+
+        let queue_size = self.common_read::<u16>(Self::COMMON_QUEUE_SIZE);
+        if queue_size == 0 {
+            return None;
+        }
+
+        let notify_off = self.common_read::<u16>(Self::COMMON_QUEUE_NOTIFY_OFF) as usize;
+        let notify_addr =
+            self.m_notify_cfg.offset(notify_off * self.m_notify_off_multiplier as usize);
+        let virtqueue = Virtqueue::new(queue_index,
+                                       queue_size.min(Virtqueue::SIZE_MAX),
+                                       notify_addr)?;
+
+        self.common_write::<u16>(Self::COMMON_QUEUE_SIZE, virtqueue.m_size);
+        self.common_write_addr(Self::COMMON_QUEUE_DESC, virtqueue.desc_phys_addr());
+        self.common_write_addr(Self::COMMON_QUEUE_DRIVER, virtqueue.avail_phys_addr());
+        self.common_write_addr(Self::COMMON_QUEUE_DEVICE, virtqueue.used_phys_addr());
+        self.common_write::<u16>(Self::COMMON_QUEUE_ENABLE, 1);
+        Some(virtqueue)
+    }
+
+    /**
+     * Tells to the device that the driver is ready to use it
+     */
+    pub fn set_driver_ok(&self) {
+        self.add_status(Self::STATUS_DRIVER_OK);
+    }
+
+    /**
+     * Tells to the device that the driver gave up
+     */
+    pub fn set_failed(&self) {
+        self.add_status(Self::STATUS_FAILED);
+    }
+
+    /**
+     * Reads the device specific configuration field at the given offset,
+     * the read is retried when the device changes it meanwhile
+     */
+    pub fn device_cfg_read<T: Copy>(&self, offset: usize) -> T {
+        loop {
+            let generation = self.common_read::<u8>(Self::COMMON_CONFIG_GENERATION);
+            let value =
+                unsafe { read_volatile(self.m_device_cfg.offset(offset).as_ptr::<T>()) };
+            if generation == self.common_read::<u8>(Self::COMMON_CONFIG_GENERATION) {
+                return value;
+            }
+        }
+    }
+}
+
+impl VirtioPciTransport /* Privates */ {
+    /**
+     * Writes `0` into the device status and waits the device reset
+     */
+    fn reset(&self) -> bool {
+        self.common_write::<u8>(Self::COMMON_DEVICE_STATUS, 0);
+        for _ in 0..Self::RESET_POLLS_MAX {
+            if self.status() == 0 {
+                return true;
+            }
+            spin_loop();
+        }
+        false
+    }
+
+    /**
+     * Returns the 64 bits offered by the device
+     */
+    fn read_features(&self) -> u64 {
+        self.common_write::<u32>(Self::COMMON_DEVICE_FEATURE_SELECT, 0);
+        let low_features = self.common_read::<u32>(Self::COMMON_DEVICE_FEATURE) as u64;
+        self.common_write::<u32>(Self::COMMON_DEVICE_FEATURE_SELECT, 1);
+        let high_features = self.common_read::<u32>(Self::COMMON_DEVICE_FEATURE) as u64;
+        high_features << 32 | low_features
+    }
+
+    /**
+     * Writes the 64 bits accepted by the driver
+     */
+    fn write_features(&self, features: u64) {
+        self.common_write::<u32>(Self::COMMON_DRIVER_FEATURE_SELECT, 0);
+        self.common_write::<u32>(Self::COMMON_DRIVER_FEATURE, features as u32);
+        self.common_write::<u32>(Self::COMMON_DRIVER_FEATURE_SELECT, 1);
+        self.common_write::<u32>(Self::COMMON_DRIVER_FEATURE, (features >> 32) as u32);
+    }
+
+    /**
+     * Returns the device status
+     */
+    fn status(&self) -> u8 {
+        self.common_read::<u8>(Self::COMMON_DEVICE_STATUS)
+    }
+
+    /**
+     * Enables the given bits of the device status
+     */
+    fn add_status(&self, status_bits: u8) {
+        self.common_write::<u8>(Self::COMMON_DEVICE_STATUS, self.status() | status_bits);
+    }
+
+    /**
+     * Writes the 64 bits address field of the common configuration at the
+     * given offset, as two 32 bits halves
+     */
+    fn common_write_addr(&self, offset: usize, phys_addr: PhysAddr) {
+        self.common_write::<u32>(offset, *phys_addr as u32);
+        self.common_write::<u32>(offset + 4, (*phys_addr as u64 >> 32) as u32);
+    }
+
+    /**
+     * Reads the field of the common configuration at the given offset
+     */
+    fn common_read<T: Copy>(&self, offset: usize) -> T {
+        unsafe { read_volatile(self.m_common_cfg.offset(offset).as_ptr::<T>()) }
+    }
+
+    /**
+     * Writes the field of the common configuration at the given offset
+     */
+    fn common_write<T: Copy>(&self, offset: usize, value: T) {
+        unsafe {
+            write_volatile(self.m_common_cfg.offset(offset).as_ptr_mut::<T>(), value);
+        }
+    }
+}
+
+/**
+ * Split virtqueue.
+ *
+ * The descriptors table, the available and the used rings are stored into
+ * a single physical frame, the completions are collected in polling with
+ * `pop_used()`
+ */
+pub struct Virtqueue {
+    m_index: u16,
+    m_size: u16,
+    m_ring_frame: PhysAddr,
+    m_notify_addr: VirtAddr,
+    m_free_descs: Vec<u16>,
+    m_avail_idx: u16,
+    m_last_used_idx: u16
+}
+
+impl Virtqueue /* Constants */ {
+    /**
+     * Maximum amount of descriptors, which makes the rings fit into a
+     * single 4KiB frame
+     */
+    pub const SIZE_MAX: u16 = 128;
+
+    const DESC_F_NEXT: u16 = 1 << 0;
+    const DESC_F_WRITE: u16 = 1 << 1;
+}
+
+impl Virtqueue /* Constructors */ {
+    /**
+     * Constructs a `Virtqueue` with the given amount of descriptors,
+     * notified writing at the given `VirtAddr`
+     */
+    fn new(index: u16, size: u16, notify_addr: VirtAddr) -> Option<Self> {
+        let ring_frame = MemManager::instance().allocate_kernel_phys_frame()?;
+        let virtqueue = Self { m_index: index,
+                               m_size: size,
+                               m_ring_frame: ring_frame,
+                               m_notify_addr: notify_addr,
+                               m_free_descs: (0..size).rev().collect(),
+                               m_avail_idx: 0,
+                               m_last_used_idx: 0 };
+
+        /* the device expects zeroed rings */
+        unsafe {
+            ptr::write_bytes(virtqueue.ring_ptr::<u8>(0), 0, Page4KiB::SIZE);
+        }
+        Some(virtqueue)
+    }
+}
+
+impl Virtqueue /* Methods */ {
+    /**
+     * Makes available to the device a chain of descriptors for the given
+     * buffers, the device is not notified until `notify()` is called.
+     *
+     * Returns the head descriptor index, or `None` when the free
+     * descriptors are not enough
+     */
+    pub fn push_chain(&mut self, buffers: &[VirtqueueBuffer]) -> Option<u16> {
+        if buffers.is_empty() || buffers.len() > self.m_free_descs.len() {
+            return None;
+        }
+
+        /* fill the chain from the tail to know the next of each descriptor */
+        let mut next_desc = None;
+        for buffer in buffers.iter().rev() {
+            let desc_index = self.m_free_descs.pop().unwrap();
+
+            let mut flags = 0;
+            if buffer.m_device_writable {
+                flags |= Self::DESC_F_WRITE;
+            }
+            if next_desc.is_some() {
+                flags |= Self::DESC_F_NEXT;
+            }
+
+            let virtq_desc = VirtqDesc { m_addr: *buffer.m_phys_addr as u64,
+                                         m_len: buffer.m_len as u32,
+                                         m_flags: flags,
+                                         m_next: next_desc.unwrap_or(0) };
+            unsafe {
+                write_volatile(self.ring_ptr::<VirtqDesc>(desc_index as usize
+                                                          * size_of::<VirtqDesc>()),
+                               virtq_desc);
+            }
+            next_desc = Some(desc_index);
+        }
+
+        /* publish the head into the available ring, then the new index */
+        let head_desc = next_desc.unwrap();
+        let ring_slot = (self.m_avail_idx % self.m_size) as usize;
+        unsafe {
+            write_volatile(self.ring_ptr::<u16>(self.avail_offset() + 4 + ring_slot * 2),
+                           head_desc);
+            fence(Ordering::SeqCst);
+
+            self.m_avail_idx = self.m_avail_idx.wrapping_add(1);
+            write_volatile(self.ring_ptr::<u16>(self.avail_offset() + 2),
+                           self.m_avail_idx);
+        }
+        Some(head_desc)
+    }
+
+    /**
+     * Notifies the device about the chains pushed until now
+     */
+    pub fn notify(&self) {
+        fence(Ordering::SeqCst);
+        unsafe {
+            write_volatile(self.m_notify_addr.as_ptr_mut::<u16>(), self.m_index);
+        }
+    }
+
+    /**
+     * Collects the next chain completed by the device, his descriptors are
+     * released.
+     *
+     * Returns the head descriptor index with the amount of bytes written
+     * by the device
+     */
+    pub fn pop_used(&mut self) -> Option<(u16, usize)> {
+        let used_idx =
+            unsafe { read_volatile(self.ring_ptr::<u16>(self.used_offset() + 2)) };
+        if used_idx == self.m_last_used_idx {
+            return None;
+        }
+        fence(Ordering::SeqCst);
+
+        /* each used element is composed by the head index and the length */
+        let ring_slot = (self.m_last_used_idx % self.m_size) as usize;
+        let used_elem_offset = self.used_offset() + 4 + ring_slot * 8;
+        let (head_desc, written_len) = unsafe {
+            (read_volatile(self.ring_ptr::<u32>(used_elem_offset)) as u16,
+             read_volatile(self.ring_ptr::<u32>(used_elem_offset + 4)) as usize)
+        };
+        self.m_last_used_idx = self.m_last_used_idx.wrapping_add(1);
+
+        /* give back the descriptors of the chain */
+        let mut desc_index = head_desc;
+        loop {
+            let virtq_desc = unsafe {
+                read_volatile(self.ring_ptr::<VirtqDesc>(desc_index as usize
+                                                         * size_of::<VirtqDesc>()))
+            };
+            self.m_free_descs.push(desc_index);
+
+            if virtq_desc.m_flags & Self::DESC_F_NEXT == 0 {
+                break;
+            }
+            desc_index = virtq_desc.m_next;
+        }
+        Some((head_desc, written_len))
+    }
+}
+
+impl Virtqueue /* Getters */ {
+    /**
+     * Returns the amount of descriptors
+     */
+    pub fn size(&self) -> u16 {
+        self.m_size
+    }
+
+    /**
+     * Returns the amount of descriptors not used by pending chains
+     */
+    pub fn free_descs_count(&self) -> usize {
+        self.m_free_descs.len()
+    }
+}
+
+impl Virtqueue /* Privates */ {
+    /**
+     * Returns the offset of the available ring, which follows the
+     * descriptors table
+     */
+    fn avail_offset(&self) -> usize {
+        self.m_size as usize * size_of::<VirtqDesc>()
+    }
+
+    /**
+     * Returns the offset of the used ring, which follows the available
+     * ring aligned to 4 bytes
+     */
+    fn used_offset(&self) -> usize {
+        align_up(self.avail_offset() + 6 + self.m_size as usize * 2, 4)
+    }
+
+    /**
+     * Returns the physical address of the descriptors table
+     */
+    fn desc_phys_addr(&self) -> PhysAddr {
+        self.m_ring_frame
+    }
+
+    /**
+     * Returns the physical address of the available ring
+     */
+    fn avail_phys_addr(&self) -> PhysAddr {
+        self.m_ring_frame.offset(self.avail_offset())
+    }
+
+    /**
+     * Returns the physical address of the used ring
+     */
+    fn used_phys_addr(&self) -> PhysAddr {
+        self.m_ring_frame.offset(self.used_offset())
+    }
+
+    /**
+     * Returns the pointer at the given offset of the rings frame into the
+     * physical memory mapping
+     */
+    fn ring_ptr<T>(&self, offset: usize) -> *mut T {
+        MemManager::instance().layout_manager()
+                              .phys_addr_to_virt_addr(self.m_ring_frame)
+                              .offset(offset)
+                              .as_ptr_mut()
+    }
+}
+
+impl Drop for Virtqueue {
+    fn drop(&mut self) {
+        MemManager::instance().free_kernel_phys_frame(self.m_ring_frame);
+    }
+}
+
+/**
+ * Physically contiguous buffer referenced by a descriptor of a
+ * `Virtqueue`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct VirtqueueBuffer {
+    m_phys_addr: PhysAddr,
+    m_len: usize,
+    m_device_writable: bool
+}
+
+impl VirtqueueBuffer /* Constructors */ {
+    /**
+     * Constructs a `VirtqueueBuffer` which the device only reads
+     */
+    pub fn new_readable(phys_addr: PhysAddr, len: usize) -> Self {
+        Self { m_phys_addr: phys_addr,
+               m_len: len,
+               m_device_writable: false }
+    }
+
+    /**
+     * Constructs a `VirtqueueBuffer` which the device fills
+     */
+    pub fn new_writable(phys_addr: PhysAddr, len: usize) -> Self {
+        Self { m_phys_addr: phys_addr,
+               m_len: len,
+               m_device_writable: true }
+    }
+}
+
+/**
+ * Entry of the descriptors table
+ */
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct VirtqDesc {
+    m_addr: u64,
+    m_len: u32,
+    m_flags: u16,
+    m_next: u16
+}
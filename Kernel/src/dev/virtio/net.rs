@@ -0,0 +1,388 @@
+/*! Virtio network device driver */
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    hint::spin_loop,
+    ptr
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::device::{
+        DeviceId,
+        DeviceIdClass,
+        DeviceIdType
+    }
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::phys_addr::PhysAddr,
+    dev::{
+        net::{
+            NetDevResult,
+            TNetDevice
+        },
+        pci::{
+            PciFunction,
+            PciMatch,
+            TPciDriver
+        },
+        virtio::{
+            VirtioPciTransport,
+            Virtqueue,
+            VirtqueueBuffer,
+            VIRTIO_PCI_VENDOR_ID
+        },
+        DevManager,
+        TDevice
+    },
+    vm::{
+        mem_manager::MemManager,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * `TNetDevice` implementation for the virtio network devices.
+ *
+ * The receive queue is kept filled with frame sized buffers, the frames
+ * are transmitted through bounce frames and both the queues are
+ * completed in polling
+ */
+pub struct VirtioNetDevice {
+    m_device_id: DeviceId,
+    m_transport: VirtioPciTransport,
+    m_state: SpinMutex<Option<VirtioNetState>>
+}
+
+impl VirtioNetDevice /* Constants */ {
+    const FEATURE_MAC: u64 = 1 << 5;
+
+    const CFG_MAC: usize = 0x00;
+
+    const RX_QUEUE_INDEX: u16 = 0;
+    const TX_QUEUE_INDEX: u16 = 1;
+
+    const MTU: usize = 1500;
+}
+
+impl VirtioNetDevice /* Constructors */ {
+    /**
+     * Constructs an uninitialized `VirtioNetDevice` which uses the given
+     * `VirtioPciTransport`
+     */
+    pub fn new(transport: VirtioPciTransport, serial_value: u32) -> Self {
+        Self { m_device_id: DeviceId::new(DeviceIdType::Character,
+                                          DeviceIdClass::Network,
+                                          serial_value),
+               m_transport: transport,
+               m_state: SpinMutex::const_new(None) }
+    }
+}
+
+impl VirtioNetDevice /* Privates */ {
+    /**
+     * Negotiates the features and configures the queues, the device is
+     * marked as failed by the caller on `None`
+     */
+    fn init_state(&self) -> Option<VirtioNetState> {
+        let features = self.m_transport.negotiate_features(Self::FEATURE_MAC)?;
+
+        /* without an assigned address use a locally administered one */
+        let mac_addr = if features & Self::FEATURE_MAC != 0 {
+            let mut mac_addr = [0; 6];
+            for (i, mac_byte) in mac_addr.iter_mut().enumerate() {
+                *mac_byte = self.m_transport.device_cfg_read::<u8>(Self::CFG_MAC + i);
+            }
+            mac_addr
+        } else {
+            let serial_byte = self.m_device_id.serial_value() as u8;
+            [0x52, 0x54, 0x00, 0x12, 0x34, 0x56u8.wrapping_add(serial_byte)]
+        };
+
+        let rx_queue = self.m_transport.setup_queue(Self::RX_QUEUE_INDEX)?;
+        let tx_queue = self.m_transport.setup_queue(Self::TX_QUEUE_INDEX)?;
+        let state = VirtioNetState::new(rx_queue, tx_queue, mac_addr)?;
+
+        self.m_transport.set_driver_ok();
+        Some(state)
+    }
+
+    /**
+     * Executes the given closure with the `VirtioNetState` initialized by
+     * `init_hw()`
+     */
+    fn with_state<F, R>(&self, state_fn: F) -> NetDevResult<R>
+        where F: FnOnce(&mut VirtioNetState) -> NetDevResult<R> {
+        match self.m_state.lock().as_mut() {
+            Some(state) => state_fn(state),
+            None => {
+                Err((OsErrorClass::ReferenceNotFound, Some("virtio-net not initialized")))
+            },
+        }
+    }
+}
+
+impl TDevice for VirtioNetDevice {
+    fn device_id(&self) -> DeviceId {
+        self.m_device_id
+    }
+
+    fn device_name(&self) -> String {
+        format!("net_{}", self.m_device_id.serial_value())
+    }
+
+    fn init_hw(&self) -> bool {
+        match self.init_state() {
+            Some(state) => {
+                *self.m_state.lock() = Some(state);
+                true
+            },
+            None => {
+                self.m_transport.set_failed();
+                false
+            }
+        }
+    }
+
+    fn as_net(&self) -> Option<&dyn TNetDevice> {
+        Some(self)
+    }
+}
+
+impl TNetDevice for VirtioNetDevice {
+    fn mac_addr(&self) -> [u8; 6] {
+        self.with_state(|state| Ok(state.m_mac_addr)).unwrap_or([0; 6])
+    }
+
+    fn mtu(&self) -> usize {
+        Self::MTU
+    }
+
+    fn send_frame(&self, frame: &[u8]) -> NetDevResult<()> {
+        self.with_state(|state| state.send_frame(frame))
+    }
+
+    fn recv_frame(&self, buffer: &mut [u8]) -> Option<usize> {
+        self.with_state(|state| Ok(state.recv_frame(buffer))).ok().flatten()
+    }
+}
+
+/**
+ * `TPciDriver` for the virtio network devices, both the transitional and
+ * the modern ones are driven with the virtio 1.x interface
+ */
+pub struct VirtioNetPciDriver;
+
+impl VirtioNetPciDriver /* Constants */ {
+    const PCI_MATCHES: [PciMatch; 2] = [PciMatch::by_ids(VIRTIO_PCI_VENDOR_ID, 0x1000),
+                                        PciMatch::by_ids(VIRTIO_PCI_VENDOR_ID, 0x1041)];
+}
+
+impl TPciDriver for VirtioNetPciDriver {
+    fn driver_name(&self) -> &'static str {
+        "virtio-net"
+    }
+
+    fn pci_matches(&self) -> &[PciMatch] {
+        &Self::PCI_MATCHES
+    }
+
+    fn probe(&self, pci_function: &Arc<PciFunction>) -> bool {
+        pci_function.enable_decoding();
+        pci_function.enable_bus_master();
+
+        let transport = match VirtioPciTransport::new(pci_function) {
+            Some(transport) => transport,
+            None => return false
+        };
+
+        let dev_manager = DevManager::instance();
+        let serial_value = dev_manager.next_serial_value(DeviceIdClass::Network);
+        dev_manager.register_device(VirtioNetDevice::new(transport, serial_value))
+    }
+}
+
+/**
+ * Queues of a `VirtioNetDevice` with the frames of the buffers
+ */
+struct VirtioNetState {
+    m_rx_queue: Virtqueue,
+    m_tx_queue: Virtqueue,
+    m_rx_pending: BTreeMap<u16, PhysAddr>,
+    m_tx_pending: BTreeMap<u16, PhysAddr>,
+    m_tx_free_frames: Vec<PhysAddr>,
+    m_mac_addr: [u8; 6]
+}
+
+impl VirtioNetState /* Constants */ {
+    const RX_BUFFERS: usize = 32;
+    const TX_BUFFERS: usize = 16;
+
+    /**
+     * Size of the `virtio_net_hdr` which precedes each frame, with the
+     * virtio 1.x it always contains the `num_buffers` field
+     */
+    const HEADER_SIZE: usize = 12;
+
+    const POLLS_MAX: usize = 1_000_000;
+}
+
+impl VirtioNetState /* Constructors */ {
+    /**
+     * Constructs a `VirtioNetState` allocating the frames of the buffers,
+     * the receive ones are given to the device
+     */
+    fn new(rx_queue: Virtqueue, tx_queue: Virtqueue, mac_addr: [u8; 6]) -> Option<Self> {
+        let mem_manager = MemManager::instance();
+
+        /* the <Drop> implementation releases the frames on failure */
+        let mut state = Self { m_rx_queue: rx_queue,
+                               m_tx_queue: tx_queue,
+                               m_rx_pending: BTreeMap::new(),
+                               m_tx_pending: BTreeMap::new(),
+                               m_tx_free_frames: Vec::with_capacity(Self::TX_BUFFERS),
+                               m_mac_addr: mac_addr };
+        for _ in 0..Self::TX_BUFFERS {
+            state.m_tx_free_frames.push(mem_manager.allocate_kernel_phys_frame()?);
+        }
+        for _ in 0..Self::RX_BUFFERS.min(state.m_rx_queue.size() as usize) {
+            let rx_frame = mem_manager.allocate_kernel_phys_frame()?;
+            if !state.post_rx_frame(rx_frame) {
+                mem_manager.free_kernel_phys_frame(rx_frame);
+                return None;
+            }
+        }
+        state.m_rx_queue.notify();
+        Some(state)
+    }
+}
+
+impl VirtioNetState /* Methods */ {
+    /**
+     * Copies the given frame into a free bounce frame and gives it to the
+     * device
+     */
+    fn send_frame(&mut self, frame: &[u8]) -> NetDevResult<()> {
+        if frame.len() + Self::HEADER_SIZE > Page4KiB::SIZE {
+            return Err((OsErrorClass::InvalidArgument, Some("Frame too big")));
+        }
+
+        /* wait the device to release a bounce frame */
+        let mut polls = 0;
+        let tx_frame = loop {
+            self.reclaim_tx_frames();
+            if let Some(tx_frame) = self.m_tx_free_frames.pop() {
+                break tx_frame;
+            } else if polls >= Self::POLLS_MAX {
+                return Err((OsErrorClass::LimitReached, Some("virtio-net queue full")));
+            }
+            polls += 1;
+            spin_loop();
+        };
+
+        /* no offloads are negotiated, so the header is all zero */
+        unsafe {
+            let frame_ptr = Self::frame_ptr(tx_frame);
+            ptr::write_bytes(frame_ptr, 0, Self::HEADER_SIZE);
+            ptr::copy_nonoverlapping(frame.as_ptr(),
+                                     frame_ptr.add(Self::HEADER_SIZE),
+                                     frame.len());
+        }
+
+        let tx_buffer =
+            VirtqueueBuffer::new_readable(tx_frame, Self::HEADER_SIZE + frame.len());
+        match self.m_tx_queue.push_chain(&[tx_buffer]) {
+            Some(head_desc) => {
+                self.m_tx_pending.insert(head_desc, tx_frame);
+                self.m_tx_queue.notify();
+                Ok(())
+            },
+            None => {
+                self.m_tx_free_frames.push(tx_frame);
+                Err((OsErrorClass::LimitReached, Some("virtio-net queue full")))
+            }
+        }
+    }
+
+    /**
+     * Copies the next frame filled by the device into the given buffer,
+     * the receive buffer is given back to the device
+     */
+    fn recv_frame(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        let (head_desc, written_len) = self.m_rx_queue.pop_used()?;
+        let rx_frame = self.m_rx_pending.remove(&head_desc)?;
+
+        /* the frames bigger than the buffer are truncated */
+        let frame_len = written_len.saturating_sub(Self::HEADER_SIZE).min(buffer.len());
+        unsafe {
+            ptr::copy_nonoverlapping(Self::frame_ptr(rx_frame).add(Self::HEADER_SIZE),
+                                     buffer.as_mut_ptr(),
+                                     frame_len);
+        }
+
+        if self.post_rx_frame(rx_frame) {
+            self.m_rx_queue.notify();
+        } else {
+            MemManager::instance().free_kernel_phys_frame(rx_frame);
+        }
+        Some(frame_len)
+    }
+}
+
+impl VirtioNetState /* Privates */ {
+    /**
+     * Makes the given frame available to the device as receive buffer
+     */
+    fn post_rx_frame(&mut self, rx_frame: PhysAddr) -> bool {
+        let rx_buffer = VirtqueueBuffer::new_writable(rx_frame, Page4KiB::SIZE);
+        match self.m_rx_queue.push_chain(&[rx_buffer]) {
+            Some(head_desc) => {
+                self.m_rx_pending.insert(head_desc, rx_frame);
+                true
+            },
+            None => false
+        }
+    }
+
+    /**
+     * Collects the bounce frames already transmitted by the device
+     */
+    fn reclaim_tx_frames(&mut self) {
+        while let Some((head_desc, _)) = self.m_tx_queue.pop_used() {
+            if let Some(tx_frame) = self.m_tx_pending.remove(&head_desc) {
+                self.m_tx_free_frames.push(tx_frame);
+            }
+        }
+    }
+
+    /**
+     * Returns the pointer to the given physical frame into the physical
+     * memory mapping
+     */
+    fn frame_ptr(phys_frame: PhysAddr) -> *mut u8 {
+        MemManager::instance().layout_manager()
+                              .phys_addr_to_virt_addr(phys_frame)
+                              .as_ptr_mut()
+    }
+}
+
+impl Drop for VirtioNetState {
+    fn drop(&mut self) {
+        let mem_manager = MemManager::instance();
+        for phys_frame in self.m_rx_pending
+                              .values()
+                              .chain(self.m_tx_pending.values())
+                              .chain(self.m_tx_free_frames.iter())
+        {
+            mem_manager.free_kernel_phys_frame(*phys_frame);
+        }
+    }
+}
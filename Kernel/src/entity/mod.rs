@@ -0,0 +1,22 @@
+/*! Kernel OS entities management */
+
+use api_data::entity::{
+    types::OsEntityType,
+    OsEntityId
+};
+
+/**
+ * Common interface implemented by all the kernel OS entities which could
+ * be referenced by the user-space through `OsEntityHandle`s
+ */
+pub trait TOsEntity: Send + Sync {
+    /**
+     * Returns the unique `OsEntityId` of this entity
+     */
+    fn os_entity_id(&self) -> OsEntityId;
+
+    /**
+     * Returns the `OsEntityType` of this entity
+     */
+    fn os_entity_type(&self) -> OsEntityType;
+}
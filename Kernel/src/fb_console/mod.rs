@@ -0,0 +1,365 @@
+/*! Framebuffer text console */
+
+use alloc::{
+    sync::Arc,
+    vec,
+    vec::Vec
+};
+use core::fmt;
+
+use sync::SpinMutex;
+
+use crate::{
+    dev::{
+        framebuffer::TFramebufferDevice,
+        TDevice
+    },
+    fb_console::font::{
+        glyph_of,
+        C_GLYPH_HEIGHT,
+        C_GLYPH_WIDTH
+    }
+};
+
+pub mod font;
+
+/* each glyph row is drawn twice to obtain 8x16 characters */
+const C_ROW_SCALE: usize = 2;
+const C_CELL_WIDTH: usize = C_GLYPH_WIDTH;
+const C_CELL_HEIGHT: usize = C_GLYPH_HEIGHT * C_ROW_SCALE;
+
+/* VT100 palette, the second half contains the bright variants */
+const C_PALETTE_RGB: [(u8, u8, u8); 16] = [(0, 0, 0),
+                                           (205, 0, 0),
+                                           (0, 205, 0),
+                                           (205, 205, 0),
+                                           (0, 0, 238),
+                                           (205, 0, 205),
+                                           (0, 205, 205),
+                                           (229, 229, 229),
+                                           (127, 127, 127),
+                                           (255, 0, 0),
+                                           (0, 255, 0),
+                                           (255, 255, 0),
+                                           (92, 92, 255),
+                                           (255, 0, 255),
+                                           (0, 255, 255),
+                                           (255, 255, 255)];
+const C_BRIGHT_OFFSET: u8 = 8;
+const C_DEFAULT_FG: u8 = 7;
+const C_DEFAULT_BG: u8 = 0;
+
+/* amount of numeric parameters kept for each escape sequence */
+const C_ESC_PARAMS_MAX: usize = 8;
+
+/* columns between the tabulation stops */
+const C_TAB_SIZE: usize = 8;
+
+/**
+ * Text console which draws the characters on a `TFramebufferDevice`.
+ *
+ * The VT100 Select Graphic Rendition sequences (<ESC>[...m) change the
+ * colors of the following characters, the other sequences are ignored
+ */
+pub struct FbConsole {
+    m_device: Arc<dyn TDevice>,
+    m_palette: [u32; 16],
+    m_inner: SpinMutex<FbConsoleInner>
+}
+
+impl FbConsole /* Constructors */ {
+    /**
+     * Constructs a cleared `FbConsole` which covers the entire screen of
+     * the given framebuffer device
+     */
+    pub fn new(device: Arc<dyn TDevice>) -> Self {
+        let framebuffer =
+            device.as_framebuffer().expect("FbConsole requires a framebuffer device");
+
+        let mut palette = [0; 16];
+        for (raw_color, (red, green, blue)) in
+            palette.iter_mut().zip(C_PALETTE_RGB.iter())
+        {
+            *raw_color = framebuffer.rgb_color(*red, *green, *blue);
+        }
+
+        let columns = framebuffer.width() / C_CELL_WIDTH;
+        let rows = framebuffer.height() / C_CELL_HEIGHT;
+        framebuffer.fill_rect(0,
+                              0,
+                              framebuffer.width(),
+                              framebuffer.height(),
+                              palette[C_DEFAULT_BG as usize]);
+
+        Self { m_device: device,
+               m_palette: palette,
+               m_inner: SpinMutex::const_new(FbConsoleInner::new(columns, rows)) }
+    }
+}
+
+impl FbConsole /* Methods */ {
+    /**
+     * Writes the given `&str` interpreting the VT100 escape sequences
+     */
+    pub fn write_str(&self, str: &str) -> fmt::Result {
+        let mut inner = self.m_inner.lock();
+        if inner.m_columns == 0 || inner.m_rows == 0 {
+            return Err(fmt::Error);
+        }
+
+        for str_char in str.chars() {
+            /* the font contains only the ASCII characters */
+            let ascii_char = if str_char.is_ascii() {
+                str_char as u8
+            } else {
+                b'?'
+            };
+            self.process_char(&mut inner, ascii_char);
+        }
+        Ok(())
+    }
+}
+
+impl FbConsole /* Privates */ {
+    /**
+     * Advances the escape sequences parser or draws the given character
+     */
+    fn process_char(&self, inner: &mut FbConsoleInner, ascii_char: u8) {
+        match inner.m_esc_state {
+            EscState::Ground => {
+                match ascii_char {
+                    0x1b => inner.m_esc_state = EscState::Escape,
+                    b'\n' => self.new_line(inner),
+                    b'\r' => inner.m_cursor_column = 0,
+                    b'\t' => {
+                        let tab_stop =
+                            (inner.m_cursor_column / C_TAB_SIZE + 1) * C_TAB_SIZE;
+                        inner.m_cursor_column = tab_stop.min(inner.m_columns);
+                    },
+                    0x08 => {
+                        inner.m_cursor_column = inner.m_cursor_column.saturating_sub(1)
+                    },
+                    /* the other control characters are not drawable */
+                    0x00..=0x1f | 0x7f => {},
+                    _ => self.put_char(inner, ascii_char)
+                }
+            },
+            EscState::Escape => {
+                if ascii_char == b'[' {
+                    inner.m_esc_params = [0; C_ESC_PARAMS_MAX];
+                    inner.m_esc_params_count = 0;
+                    inner.m_esc_state = EscState::Csi;
+                } else {
+                    inner.m_esc_state = EscState::Ground;
+                }
+            },
+            EscState::Csi => {
+                match ascii_char {
+                    b'0'..=b'9' => {
+                        /* the first digit opens the parameter */
+                        if inner.m_esc_params_count == 0 {
+                            inner.m_esc_params_count = 1;
+                        }
+                        if inner.m_esc_params_count <= C_ESC_PARAMS_MAX {
+                            let esc_param =
+                                &mut inner.m_esc_params[inner.m_esc_params_count - 1];
+                            *esc_param =
+                                esc_param.saturating_mul(10)
+                                         .saturating_add((ascii_char - b'0') as usize);
+                        }
+                    },
+                    b';' => {
+                        inner.m_esc_params_count = (inner.m_esc_params_count + 1).max(2)
+                    },
+                    b'm' => {
+                        inner.select_graphic_rendition();
+                        inner.m_esc_state = EscState::Ground;
+                    },
+                    /* the other final bytes end the unsupported sequences */
+                    0x40..=0x7e => inner.m_esc_state = EscState::Ground,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /**
+     * Draws the given character at the cursor position and advances the
+     * cursor, wrapping the lines too long
+     */
+    fn put_char(&self, inner: &mut FbConsoleInner, ascii_char: u8) {
+        if inner.m_cursor_column >= inner.m_columns {
+            self.new_line(inner);
+        }
+
+        let cell = FbCell { m_char: ascii_char,
+                            m_fg_color: inner.m_fg_color,
+                            m_bg_color: inner.m_bg_color };
+        let cell_index = inner.m_cursor_row * inner.m_columns + inner.m_cursor_column;
+        inner.m_cells[cell_index] = cell;
+        self.draw_cell(inner.m_cursor_column, inner.m_cursor_row, cell);
+
+        inner.m_cursor_column += 1;
+    }
+
+    /**
+     * Moves the cursor to the beginning of the next line, scrolling the
+     * screen when the cursor is on the last line
+     */
+    fn new_line(&self, inner: &mut FbConsoleInner) {
+        inner.m_cursor_column = 0;
+        if inner.m_cursor_row + 1 < inner.m_rows {
+            inner.m_cursor_row += 1;
+            return;
+        }
+
+        /* drawing the entire screen is slow, so only the cells which change
+         * are drawn again
+         */
+        let columns = inner.m_columns;
+        for cell_index in 0..inner.m_cells.len() {
+            let new_cell = inner.m_cells
+                                .get(cell_index + columns)
+                                .copied()
+                                .unwrap_or_else(FbCell::blank);
+            if inner.m_cells[cell_index] != new_cell {
+                inner.m_cells[cell_index] = new_cell;
+                self.draw_cell(cell_index % columns, cell_index / columns, new_cell);
+            }
+        }
+    }
+
+    /**
+     * Draws the given `FbCell` at the given text position
+     */
+    fn draw_cell(&self, column: usize, row: usize, cell: FbCell) {
+        let framebuffer = self.framebuffer();
+        let fg_color = self.m_palette[cell.m_fg_color as usize];
+        let bg_color = self.m_palette[cell.m_bg_color as usize];
+
+        let x = column * C_CELL_WIDTH;
+        let y = row * C_CELL_HEIGHT;
+        for (glyph_row_index, glyph_row) in glyph_of(cell.m_char).iter().enumerate() {
+            for glyph_column in 0..C_GLYPH_WIDTH {
+                let color = if glyph_row & (1 << glyph_column) != 0 {
+                    fg_color
+                } else {
+                    bg_color
+                };
+                framebuffer.fill_rect(x + glyph_column,
+                                      y + glyph_row_index * C_ROW_SCALE,
+                                      1,
+                                      C_ROW_SCALE,
+                                      color);
+            }
+        }
+    }
+
+    /**
+     * Returns the `TFramebufferDevice` interface of the device
+     */
+    fn framebuffer(&self) -> &dyn TFramebufferDevice {
+        self.m_device.as_framebuffer().expect("FbConsole requires a framebuffer device")
+    }
+}
+
+/**
+ * Mutable state of the `FbConsole`
+ */
+struct FbConsoleInner {
+    m_cells: Vec<FbCell>,
+    m_columns: usize,
+    m_rows: usize,
+    m_cursor_column: usize,
+    m_cursor_row: usize,
+    m_fg_color: u8,
+    m_bg_color: u8,
+    m_esc_state: EscState,
+    m_esc_params: [usize; C_ESC_PARAMS_MAX],
+    m_esc_params_count: usize
+}
+
+impl FbConsoleInner /* Constructors */ {
+    /**
+     * Constructs a blank `FbConsoleInner` with the given text size
+     */
+    fn new(columns: usize, rows: usize) -> Self {
+        Self { m_cells: vec![FbCell::blank(); columns * rows],
+               m_columns: columns,
+               m_rows: rows,
+               m_cursor_column: 0,
+               m_cursor_row: 0,
+               m_fg_color: C_DEFAULT_FG,
+               m_bg_color: C_DEFAULT_BG,
+               m_esc_state: EscState::Ground,
+               m_esc_params: [0; C_ESC_PARAMS_MAX],
+               m_esc_params_count: 0 }
+    }
+}
+
+impl FbConsoleInner /* Methods */ {
+    /**
+     * Applies the collected parameters of a <ESC>[...m sequence to the
+     * current colors
+     */
+    fn select_graphic_rendition(&mut self) {
+        /* <ESC>[m is the same of <ESC>[0m */
+        let esc_params_count = self.m_esc_params_count.max(1).min(C_ESC_PARAMS_MAX);
+        for esc_param_index in 0..esc_params_count {
+            match self.m_esc_params[esc_param_index] {
+                0 => {
+                    self.m_fg_color = C_DEFAULT_FG;
+                    self.m_bg_color = C_DEFAULT_BG;
+                },
+                1 => self.m_fg_color |= C_BRIGHT_OFFSET,
+                22 => self.m_fg_color &= !C_BRIGHT_OFFSET,
+                esc_param @ 30..=37 => {
+                    self.m_fg_color =
+                        (self.m_fg_color & C_BRIGHT_OFFSET) | (esc_param - 30) as u8
+                },
+                39 => self.m_fg_color = C_DEFAULT_FG,
+                esc_param @ 40..=47 => self.m_bg_color = (esc_param - 40) as u8,
+                49 => self.m_bg_color = C_DEFAULT_BG,
+                esc_param @ 90..=97 => {
+                    self.m_fg_color = (esc_param - 90) as u8 + C_BRIGHT_OFFSET
+                },
+                esc_param @ 100..=107 => {
+                    self.m_bg_color = (esc_param - 100) as u8 + C_BRIGHT_OFFSET
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+/**
+ * Character with his colors, as palette indexes
+ */
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+struct FbCell {
+    m_char: u8,
+    m_fg_color: u8,
+    m_bg_color: u8
+}
+
+impl FbCell /* Constructors */ {
+    /**
+     * Constructs an empty `FbCell` with the default colors
+     */
+    fn blank() -> Self {
+        Self { m_char: b' ',
+               m_fg_color: C_DEFAULT_FG,
+               m_bg_color: C_DEFAULT_BG }
+    }
+}
+
+/**
+ * Enumerates the states of the escape sequences parser
+ */
+#[derive(Copy, Clone)]
+enum EscState {
+    Ground,
+    Escape,
+    Csi
+}
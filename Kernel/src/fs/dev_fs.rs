@@ -0,0 +1,245 @@
+/*! Device drivers filesystem */
+
+use alloc::sync::Arc;
+
+use core::sync::atomic::{
+    AtomicU32,
+    Ordering
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        dir::DirEntry,
+        grants::RawObjGrants,
+        info::RawObjInfo,
+        types::ObjType
+    },
+    path::PathExistsState,
+    task::modes::FsMountMode
+};
+use meetix_fs::volume::default_prot_grants;
+
+use crate::{
+    dev::{
+        DevManager,
+        TDevice
+    },
+    fs::vfs::{
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        path::parse_str_path,
+        Vfs,
+        VfsResult
+    },
+    task::{
+        process::Process,
+        scheduler::Scheduler
+    }
+};
+
+/* serial value of the <DeviceId> of the next <DevFs> */
+static SM_NEXT_DEV_FS_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * `FsType::Devices` `TFileSystem`.
+ *
+ * Exposes a flat directory with a `ObjType::Device` node for each device
+ * driver registered into the `DevManager`, named with his
+ * `TDevice::device_name()`.
+ *
+ * The content is never cached, so the drivers registered after the mount
+ * are listed too
+ */
+pub struct DevFs {
+    m_root_node: Arc<DevFsRootNode>
+}
+
+impl DevFs /* Constants */ {
+    /**
+     * Directory where the kernel mounts his `DevFs`
+     */
+    pub const MNT_PATH: &'static str = "/Devices";
+}
+
+impl DevFs /* Constructors */ {
+    /**
+     * Constructs a `DevFs` with his own `DeviceId`
+     */
+    pub fn new() -> Self {
+        let device_id = DeviceId::new(DeviceIdType::Character,
+                                      DeviceIdClass::Memory,
+                                      SM_NEXT_DEV_FS_SERIAL.fetch_add(1,
+                                                                      Ordering::SeqCst));
+        let root_node = DevFsRootNode { m_device_id: device_id,
+                                        m_mount_inst: Scheduler::instance().uptime() };
+
+        Self { m_root_node: Arc::new(root_node) }
+    }
+}
+
+impl TFileSystem for DevFs {
+    fn name(&self) -> &'static str {
+        "devfs"
+    }
+
+    fn root_node(&self) -> Arc<dyn TVfsNode> {
+        self.m_root_node.clone()
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/**
+ * Root directory of a `DevFs`
+ */
+struct DevFsRootNode {
+    m_device_id: DeviceId,
+    m_mount_inst: RawInstant
+}
+
+impl TVfsNode for DevFsRootNode {
+    fn obj_type(&self) -> ObjType {
+        ObjType::Dir
+    }
+
+    fn node_id(&self) -> u64 {
+        0
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        dev_fs_obj_info(ObjType::Dir,
+                        self.m_device_id,
+                        self.node_id(),
+                        name,
+                        0,
+                        0,
+                        self.m_mount_inst)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        let device = DevManager::instance().device_by_name(name)
+                                           .ok_or((OsErrorClass::ReferenceNotFound,
+                                                   Some("No such device")))?;
+        Ok(Arc::new(DevFsNode { m_device: device,
+                                m_mount_inst: self.m_mount_inst }))
+    }
+
+    fn create_child(&self,
+                    _name: &str,
+                    _obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only devfs")))
+    }
+
+    fn remove_child(&self, _name: &str) -> VfsResult<()> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only devfs")))
+    }
+
+    fn child_at(&self, index: usize) -> VfsResult<Option<DirEntry>> {
+        Ok(DevManager::instance().devices()
+                                 .get(index)
+                                 .map(|device| {
+                                     DirEntry::new(&device.device_name(), ObjType::Device)
+                                 }))
+    }
+}
+
+/**
+ * `ObjType::Device` node of a `DevFs`, references the device driver
+ */
+pub struct DevFsNode {
+    m_device: Arc<dyn TDevice>,
+    m_mount_inst: RawInstant
+}
+
+impl TVfsNode for DevFsNode {
+    fn obj_type(&self) -> ObjType {
+        ObjType::Device
+    }
+
+    fn node_id(&self) -> u64 {
+        /* the root directory is the <0> */
+        Into::<usize>::into(self.m_device.device_id()) as u64 + 1
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        /* only the storage devices have a known size */
+        let (block_size, blocks_count) = match self.m_device.as_storage() {
+            Some(storage_device) => {
+                (storage_device.block_size(), storage_device.blocks_count() as usize)
+            },
+            None => (1, 0)
+        };
+
+        dev_fs_obj_info(ObjType::Device,
+                        self.m_device.device_id(),
+                        self.node_id(),
+                        name,
+                        block_size,
+                        blocks_count,
+                        self.m_mount_inst)
+    }
+
+    fn device(&self) -> Option<Arc<dyn TDevice>> {
+        Some(self.m_device.clone())
+    }
+}
+
+/**
+ * Mounts a new `DevFs` at `DevFs::MNT_PATH`, which is created when the
+ * sysroot doesn't contain it
+ */
+pub fn mount_dev_fs(kern_proc: &Process) -> VfsResult<()> {
+    let mnt_path = parse_str_path(DevFs::MNT_PATH)?;
+    if !matches!(Vfs::instance().path_exists(kern_proc, &mnt_path),
+                 PathExistsState::Exists(ObjType::Dir))
+    {
+        Vfs::instance().create(kern_proc, &mnt_path, ObjType::Dir)?;
+    }
+    Vfs::instance().mount(kern_proc,
+                          Arc::new(DevFs::new()),
+                          &mnt_path,
+                          FsMountMode::OsGlobal)
+}
+
+/**
+ * Returns the `RawObjInfo` of a `DevFs` node, which is owned by the kernel
+ * and never modified after the mount
+ */
+fn dev_fs_obj_info(obj_type: ObjType,
+                   device_id: DeviceId,
+                   node_id: u64,
+                   name: Option<&str>,
+                   block_size: usize,
+                   blocks_count: usize,
+                   mount_inst: RawInstant)
+                   -> RawObjInfo {
+    RawObjInfo::new(obj_type,
+                    0,
+                    device_id,
+                    node_id,
+                    name,
+                    1,
+                    block_size,
+                    blocks_count,
+                    block_size * blocks_count,
+                    0,
+                    0,
+                    RawObjGrants::from_raw_truncate(default_prot_grants() as usize),
+                    mount_inst,
+                    Scheduler::instance().uptime(),
+                    mount_inst,
+                    Scheduler::instance().uptime(),
+                    mount_inst)
+}
@@ -0,0 +1,355 @@
+/*! FAT filesystem driver */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+
+use core::sync::atomic::{
+    AtomicU32,
+    Ordering
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        dir::DirEntry,
+        grants::{
+            ObjGrantsBits,
+            RawObjGrants
+        },
+        info::RawObjInfo,
+        types::ObjType
+    }
+};
+use fat_fs::{
+    dir_entry::FatAttrBits,
+    time::FatTimestamp,
+    volume::{
+        DirLocation,
+        EntryLocation,
+        FatDirRecord,
+        FatVolume
+    }
+};
+use meetix_fs::{
+    dev::TBlockDevice,
+    volume::default_prot_grants
+};
+use sync::SpinMutex;
+
+use crate::{
+    fs::vfs::{
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        VfsResult
+    },
+    task::scheduler::Scheduler
+};
+
+/* serial value of the <DeviceId> of the next <FatFs> */
+static SM_NEXT_FAT_FS_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * Block device accepted by the `FatFs`
+ */
+pub type FatFsDevice = Box<dyn TBlockDevice + Send>;
+
+/**
+ * `FsType::FatX` `TFileSystem`.
+ *
+ * Wraps the `fat_fs::volume::FatVolume` of a block device formatted with
+ * FAT12, FAT16 or FAT32, the long names (VFAT) are supported.
+ *
+ * The FAT has no owners and no grants, so all the nodes have the default
+ * grants, without the data write when the read-only attribute is set
+ */
+pub struct FatFs {
+    m_root_node: Arc<FatFsNode>
+}
+
+impl FatFs /* Constructors */ {
+    /**
+     * Mounts the filesystem stored into the given block device
+     */
+    pub fn mount(device: FatFsDevice) -> VfsResult<Self> {
+        let volume = FatVolume::mount(device, now)?;
+        let device_id = DeviceId::new(DeviceIdType::Block,
+                                      DeviceIdClass::Storage,
+                                      SM_NEXT_FAT_FS_SERIAL.fetch_add(1,
+                                                                      Ordering::SeqCst));
+
+        let root_dir = volume.root_dir();
+        let shared = Arc::new(FatFsShared { m_device_id: device_id,
+                                            m_volume: SpinMutex::const_new(volume) });
+        Ok(Self { m_root_node: Arc::new(FatFsNode { m_shared: shared,
+                                                    m_location: None,
+                                                    m_dir: Some(root_dir),
+                                                    m_obj_type: ObjType::Dir }) })
+    }
+}
+
+impl FatFs /* Methods */ {
+    /**
+     * Writes back the cached FAT sector and the free clusters hints
+     */
+    pub fn sync(&self) -> VfsResult<()> {
+        self.m_root_node.m_shared.m_volume.lock().sync()
+    }
+}
+
+impl FatFs /* Getters */ {
+    /**
+     * Returns the `DeviceId` of this `FatFs`
+     */
+    pub fn device_id(&self) -> DeviceId {
+        self.m_root_node.m_shared.m_device_id
+    }
+}
+
+impl TFileSystem for FatFs {
+    fn name(&self) -> &'static str {
+        "fatfs"
+    }
+
+    fn root_node(&self) -> Arc<dyn TVfsNode> {
+        self.m_root_node.clone()
+    }
+}
+
+/**
+ * Data shared among all the `FatFsNode`s of the same `FatFs`
+ */
+struct FatFsShared {
+    m_device_id: DeviceId,
+    m_volume: SpinMutex<FatVolume<FatFsDevice>>
+}
+
+impl Drop for FatFsShared {
+    fn drop(&mut self) {
+        /* the last node is gone, nobody could report the failure */
+        let _ = self.m_volume.lock().sync();
+    }
+}
+
+/**
+ * Directory or file of a `FatFs`, references his directory entry
+ */
+pub struct FatFsNode {
+    m_shared: Arc<FatFsShared>,
+    m_location: Option<EntryLocation>,
+    m_dir: Option<DirLocation>,
+    m_obj_type: ObjType
+}
+
+impl FatFsNode /* Privates */ {
+    /**
+     * Constructs the `FatFsNode` of the given `FatDirRecord`
+     */
+    fn child_node(&self, dir_record: &FatDirRecord) -> Arc<dyn TVfsNode> {
+        let (dir, obj_type) = if dir_record.is_dir() {
+            (Some(dir_record.content_location()), ObjType::Dir)
+        } else {
+            (None, ObjType::File)
+        };
+
+        Arc::new(Self { m_shared: self.m_shared.clone(),
+                        m_location: Some(dir_record.location()),
+                        m_dir: dir,
+                        m_obj_type: obj_type })
+    }
+
+    /**
+     * Returns the `DirLocation` of the content of this directory
+     */
+    fn dir_location(&self) -> VfsResult<DirLocation> {
+        self.m_dir.ok_or((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+    }
+
+    /**
+     * Returns the `EntryLocation` of this file
+     */
+    fn file_location(&self) -> VfsResult<EntryLocation> {
+        match self.m_location {
+            Some(location) if self.m_obj_type == ObjType::File => Ok(location),
+            _ => Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+        }
+    }
+}
+
+impl TVfsNode for FatFsNode {
+    fn obj_type(&self) -> ObjType {
+        self.m_obj_type
+    }
+
+    fn node_id(&self) -> u64 {
+        self.m_location.map_or(0, |location| location.node_id())
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        let mut volume = self.m_shared.m_volume.lock();
+        let cluster_size = volume.cluster_size();
+
+        /* the root directory has no entry, so no timestamps */
+        let short_entry = match self.m_location {
+            Some(location) => {
+                /* the node could have been removed by someone else meanwhile */
+                match volume.load_entry(location) {
+                    Ok(short_entry) => Some(short_entry),
+                    Err(_) => return RawObjInfo::default()
+                }
+            },
+            None => None
+        };
+
+        let mut prot_grants =
+            RawObjGrants::from_raw_truncate(default_prot_grants() as usize);
+        let (data_size, creat_inst, access_inst, modify_inst) = match &short_entry {
+            Some(short_entry) => {
+                if short_entry.attrs().is_enabled(FatAttrBits::ReadOnly) {
+                    prot_grants.set_disabled(ObjGrantsBits::UserCanWriteData);
+                }
+                (short_entry.file_size() as usize,
+                 short_entry.creat().to_instant(),
+                 short_entry.access().to_instant(),
+                 short_entry.modify().to_instant())
+            },
+            None => {
+                (0, RawInstant::default(), RawInstant::default(), RawInstant::default())
+            },
+        };
+
+        RawObjInfo::new(self.m_obj_type,
+                        0,
+                        self.m_shared.m_device_id,
+                        self.node_id(),
+                        name,
+                        1,
+                        cluster_size,
+                        (data_size + cluster_size - 1) / cluster_size,
+                        data_size,
+                        0,
+                        0,
+                        prot_grants,
+                        creat_inst,
+                        access_inst,
+                        modify_inst,
+                        now(),
+                        modify_inst)
+    }
+
+    fn update_obj_info(&self, raw_obj_info: &RawObjInfo) -> VfsResult<()> {
+        /* the root directory has nothing to store */
+        let location = match self.m_location {
+            Some(location) => location,
+            None => return Ok(())
+        };
+
+        let mut volume = self.m_shared.m_volume.lock();
+        let mut short_entry = volume.load_entry(location)?;
+
+        let mut attrs = short_entry.attrs();
+        attrs.set(FatAttrBits::ReadOnly,
+                  !raw_obj_info.prot_grants()
+                               .is_enabled(ObjGrantsBits::UserCanWriteData));
+        short_entry.set_attrs(attrs);
+        short_entry.set_creat(FatTimestamp::from_instant(raw_obj_info.creat_inst()));
+        short_entry
+            .set_access(FatTimestamp::from_instant(raw_obj_info.last_data_access_inst()));
+        short_entry
+            .set_modify(FatTimestamp::from_instant(raw_obj_info.last_data_modify_inst()));
+        volume.store_entry(location, &short_entry)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        let dir = self.dir_location()?;
+
+        let dir_record = self.m_shared
+                             .m_volume
+                             .lock()
+                             .lookup(dir, name)?
+                             .ok_or((OsErrorClass::ReferenceNotFound,
+                                     Some("No such name in directory")))?;
+        Ok(self.child_node(&dir_record))
+    }
+
+    fn create_child(&self,
+                    name: &str,
+                    obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        let dir = self.dir_location()?;
+        if !matches!(obj_type, ObjType::Dir | ObjType::File) {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Object type not storable into a fatfs")));
+        }
+
+        let dir_record = self.m_shared
+                             .m_volume
+                             .lock()
+                             .create_node(dir, name, obj_type == ObjType::Dir)?;
+        Ok(self.child_node(&dir_record))
+    }
+
+    fn remove_child(&self, name: &str) -> VfsResult<()> {
+        let dir = self.dir_location()?;
+        self.m_shared.m_volume.lock().remove_node(dir, name)
+    }
+
+    fn child_at(&self, index: usize) -> VfsResult<Option<DirEntry>> {
+        let dir = self.dir_location()?;
+
+        /* the FAT directories have no index, so they are read each time */
+        let dir_records = self.m_shared.m_volume.lock().dir_records(dir)?;
+        Ok(dir_records.get(index).map(|dir_record| {
+                                     let obj_type = if dir_record.is_dir() {
+                                         ObjType::Dir
+                                     } else {
+                                         ObjType::File
+                                     };
+                                     DirEntry::new(dir_record.name(), obj_type)
+                                 }))
+    }
+
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> VfsResult<usize> {
+        let location = self.file_location()?;
+        self.m_shared.m_volume.lock().read_data(location, offset, buffer)
+    }
+
+    fn write_at(&self, offset: usize, buffer: &[u8]) -> VfsResult<usize> {
+        let location = self.file_location()?;
+        self.m_shared.m_volume.lock().write_data(location, offset, buffer)
+    }
+
+    fn data_size(&self) -> usize {
+        let location = match self.file_location() {
+            Ok(location) => location,
+            Err(_) => return 0
+        };
+
+        self.m_shared
+            .m_volume
+            .lock()
+            .load_entry(location)
+            .map_or(0, |short_entry| short_entry.file_size() as usize)
+    }
+
+    fn set_data_size(&self, data_size: usize) -> VfsResult<()> {
+        let location = self.file_location()?;
+        self.m_shared.m_volume.lock().set_data_size(location, data_size)
+    }
+}
+
+/**
+ * Returns the current `RawInstant` used for the timestamps
+ */
+fn now() -> RawInstant {
+    Scheduler::instance().uptime()
+}
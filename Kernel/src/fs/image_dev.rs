@@ -0,0 +1,70 @@
+/*! Filesystem images as block devices */
+
+use alloc::sync::Arc;
+
+use api_data::error::class::OsErrorClass;
+use meetix_fs::{
+    dev::TBlockDevice,
+    MxFsResult
+};
+
+use crate::fs::vfs::node::TVfsNode;
+
+/**
+ * `TBlockDevice` which stores his blocks into the data of a file node, used
+ * to mount the filesystem images stored into another filesystem
+ */
+pub struct ImageBlockDevice {
+    m_node: Arc<dyn TVfsNode>,
+    m_writeable: bool
+}
+
+impl ImageBlockDevice /* Constants */ {
+    /**
+     * Size of the sector of the disks which the images come from
+     */
+    pub const BLOCK_SIZE: usize = 512;
+}
+
+impl ImageBlockDevice /* Constructors */ {
+    /**
+     * Constructs an `ImageBlockDevice` for the given file node, the writes
+     * are refused when `writeable` is `false`
+     */
+    pub fn new(node: Arc<dyn TVfsNode>, writeable: bool) -> Self {
+        Self { m_node: node,
+               m_writeable: writeable }
+    }
+}
+
+impl TBlockDevice for ImageBlockDevice {
+    fn block_size(&self) -> usize {
+        Self::BLOCK_SIZE
+    }
+
+    fn blocks_count(&self) -> u64 {
+        (self.m_node.data_size() / Self::BLOCK_SIZE) as u64
+    }
+
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()> {
+        let offset = first_block as usize * Self::BLOCK_SIZE;
+        if self.m_node.read_at(offset, buffer)? != buffer.len() {
+            return Err((OsErrorClass::EndOfDataReached, Some("Block out of the image")));
+        }
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()> {
+        if !self.m_writeable {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Image not opened for write")));
+        }
+
+        /* the images never grow */
+        let offset = first_block as usize * Self::BLOCK_SIZE;
+        if offset + buffer.len() > self.m_node.data_size() {
+            return Err((OsErrorClass::EndOfDataReached, Some("Block out of the image")));
+        }
+        self.m_node.write_at(offset, buffer).map(|_| ())
+    }
+}
@@ -0,0 +1,386 @@
+/*! Initial ramdisk unpacking */
+
+use alloc::string::String;
+use core::{
+    slice,
+    str
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        grants::{
+            ObjGrantsBits,
+            RawObjGrants
+        },
+        types::ObjType
+    },
+    path::{
+        PathComponent,
+        PathExistsState
+    }
+};
+
+use helps::align::align_up;
+
+use crate::{
+    addr::TAddress,
+    boot_info::BootInfo,
+    dbg_print::DbgLevel,
+    dbg_println,
+    fs::vfs::{
+        path::parse_str_path,
+        Vfs,
+        VfsEntry,
+        VfsResult
+    },
+    task::process::Process,
+    vm::{
+        mem_manager::MemManager,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+const CPIO_NEWC_MAGIC: &[u8] = b"070701";
+const CPIO_NEWC_HEADER_SIZE: usize = 110;
+const CPIO_NEWC_TRAILER: &str = "TRAILER!!!";
+
+const USTAR_MAGIC: &[u8] = b"ustar";
+const USTAR_MAGIC_OFFSET: usize = 257;
+const USTAR_BLOCK_SIZE: usize = 512;
+
+/* unix file mode bits used by both the archive formats */
+const UNIX_MODE_TYPE_MASK: u32 = 0o170000;
+const UNIX_MODE_DIR: u32 = 0o040000;
+const UNIX_MODE_FILE: u32 = 0o100000;
+const UNIX_MODE_LINK: u32 = 0o120000;
+
+/**
+ * Unpacks into the root filesystem each bootloader module which is a
+ * supported archive, then releases the physical frames of all the modules
+ */
+pub fn unpack_boot_modules(kern_proc: &Process) {
+    let boot_modules = BootInfo::instance().boot_modules();
+
+    for boot_module in boot_modules.iter() {
+        let module_phys_range = boot_module.phys_range();
+        let module_data = unsafe {
+            let module_virt_addr =
+                MemManager::instance().layout_manager()
+                                      .phys_addr_to_virt_addr(module_phys_range.start);
+            slice::from_raw_parts(*module_virt_addr as *const u8,
+                                  *module_phys_range.end - *module_phys_range.start)
+        };
+
+        match unpack_initrd(kern_proc, module_data) {
+            Ok(entries_count) => {
+                dbg_println!(DbgLevel::Info,
+                             "Unpacked {} entries from initrd '{}'",
+                             entries_count,
+                             boot_module.cmd_line())
+            },
+            Err((error_class, error_msg)) => {
+                dbg_println!(DbgLevel::Warn,
+                             "Skipped boot module '{}': {} ({})",
+                             boot_module.cmd_line(),
+                             error_class,
+                             error_msg.unwrap_or(""))
+            }
+        }
+    }
+
+    /* the content is now stored into the filesystem, release the frames */
+    for boot_module in boot_modules.iter() {
+        let module_phys_range = boot_module.phys_range();
+        let module_frames_range =
+            module_phys_range.start.align_down(Page4KiB::SIZE)..module_phys_range.end;
+        for phys_frame in module_frames_range.step_by(Page4KiB::SIZE) {
+            MemManager::instance().free_kernel_phys_frame(phys_frame);
+        }
+    }
+}
+
+/**
+ * Unpacks the given `cpio` (newc) or `ustar` archive into the root
+ * filesystem and returns the amount of entries unpacked.
+ *
+ * Already existing files are overwritten
+ */
+pub fn unpack_initrd(kern_proc: &Process, initrd_data: &[u8]) -> VfsResult<usize> {
+    let mut entries_count = 0;
+    let mut unpack_entry = |initrd_entry: InitRdEntry<'_>| -> VfsResult<()> {
+        initrd_entry.unpack(kern_proc)?;
+        entries_count += 1;
+        Ok(())
+    };
+
+    if initrd_data.starts_with(CPIO_NEWC_MAGIC) {
+        walk_cpio_newc(initrd_data, &mut unpack_entry)?;
+    } else if initrd_data.get(USTAR_MAGIC_OFFSET..USTAR_MAGIC_OFFSET + USTAR_MAGIC.len())
+              == Some(USTAR_MAGIC)
+    {
+        walk_ustar(initrd_data, &mut unpack_entry)?;
+    } else {
+        return Err((OsErrorClass::TypesNotMatch, Some("Unknown initrd archive format")));
+    }
+    Ok(entries_count)
+}
+
+/**
+ * Entry of the initial ramdisk archive with his absolute path
+ */
+enum InitRdEntry<'a> {
+    Dir(String, u32),
+    File(String, u32, &'a [u8]),
+    Link(String, &'a str)
+}
+
+impl<'a> InitRdEntry<'a> /* Methods */ {
+    /**
+     * Creates the entry into the `Vfs`, creating the missing parent
+     * directories too
+     */
+    fn unpack(&self, kern_proc: &Process) -> VfsResult<()> {
+        match self {
+            Self::Dir(str_path, unix_mode) => {
+                let vfs_entry = ensure_node(kern_proc, str_path, ObjType::Dir)?;
+                apply_unix_mode(&vfs_entry, *unix_mode)
+            },
+            Self::File(str_path, unix_mode, data) => {
+                let vfs_entry = ensure_node(kern_proc, str_path, ObjType::File)?;
+                vfs_entry.node().set_data_size(0)?;
+                if vfs_entry.node().write_at(0, data)? != data.len() {
+                    return Err((OsErrorClass::NotEnoughMemory,
+                                Some("Short write unpacking the initrd")));
+                }
+                apply_unix_mode(&vfs_entry, *unix_mode)
+            },
+            Self::Link(str_path, str_target) => {
+                let vfs_entry = ensure_node(kern_proc, str_path, ObjType::Link)?;
+                vfs_entry.node().set_link_target(&parse_str_path(str_target)?)
+            }
+        }
+    }
+}
+
+/**
+ * Returns the node at the given path, created with the given `ObjType`
+ * when not exists
+ */
+fn ensure_node(kern_proc: &Process,
+               str_path: &str,
+               obj_type: ObjType)
+               -> VfsResult<VfsEntry> {
+    let path_components = parse_str_path(str_path)?;
+
+    /* create the parent directories not listed before their children */
+    for parent_len in 2..path_components.len() {
+        let parent_path = &path_components[..parent_len];
+        if !matches!(Vfs::instance().path_exists(kern_proc, parent_path),
+                     PathExistsState::Exists(_))
+        {
+            Vfs::instance().create(kern_proc, parent_path, ObjType::Dir)?;
+        }
+    }
+
+    /* links are not followed, an archive could overwrite the link itself */
+    match Vfs::instance().resolve(kern_proc, &path_components, false) {
+        Ok(vfs_entry) if vfs_entry.node().obj_type() == obj_type => Ok(vfs_entry),
+        Ok(_) => {
+            Err((OsErrorClass::TypesNotMatch, Some("Initrd entry type not matches")))
+        },
+        Err(_) => Vfs::instance().create(kern_proc, &path_components, obj_type)
+    }
+}
+
+/**
+ * Translates the unix permission bits into the `RawObjGrants` of the node
+ */
+fn apply_unix_mode(vfs_entry: &VfsEntry, unix_mode: u32) -> VfsResult<()> {
+    let mut prot_grants = RawObjGrants::new_zero();
+    for (shift, grant_bits) in [(6,
+                                 [ObjGrantsBits::UserCanOpenIt,
+                                  ObjGrantsBits::UserCanSeeIt,
+                                  ObjGrantsBits::UserCanReadInfo,
+                                  ObjGrantsBits::UserCanReadData,
+                                  ObjGrantsBits::UserCanWriteData,
+                                  ObjGrantsBits::UserCanExecTraversData]),
+                                (3,
+                                 [ObjGrantsBits::GroupCanOpenIt,
+                                  ObjGrantsBits::GroupCanSeeIt,
+                                  ObjGrantsBits::GroupCanReadInfo,
+                                  ObjGrantsBits::GroupCanReadData,
+                                  ObjGrantsBits::GroupCanWriteData,
+                                  ObjGrantsBits::GroupCanExecTraversData]),
+                                (0,
+                                 [ObjGrantsBits::OtherCanOpenIt,
+                                  ObjGrantsBits::OtherCanSeeIt,
+                                  ObjGrantsBits::OtherCanReadInfo,
+                                  ObjGrantsBits::OtherCanReadData,
+                                  ObjGrantsBits::OtherCanWriteData,
+                                  ObjGrantsBits::OtherCanExecTraversData])].iter()
+    {
+        let [open_bit, see_bit, read_info_bit, read_bit, write_bit, exec_bit] =
+            *grant_bits;
+        let rwx_bits = (unix_mode >> shift) & 0o7;
+
+        /* the nodes are always visible, <rwx> decides the remaining grants */
+        prot_grants.set_enabled(open_bit).set_enabled(see_bit).set_enabled(read_info_bit);
+        prot_grants.set(read_bit, rwx_bits & 0o4 != 0)
+                   .set(write_bit, rwx_bits & 0o2 != 0)
+                   .set(exec_bit, rwx_bits & 0o1 != 0);
+    }
+
+    /* the owner can always update the metadata */
+    prot_grants.set_enabled(ObjGrantsBits::UserCanWriteInfo);
+
+    let mut raw_obj_info = vfs_entry.node().obj_info(None);
+    *raw_obj_info.prot_grants_mut() = prot_grants;
+    vfs_entry.node().update_obj_info(&raw_obj_info)
+}
+
+/**
+ * Walks the `cpio` newc archive giving each entry to the given closure
+ */
+fn walk_cpio_newc<'a, F>(initrd_data: &'a [u8], mut entry_fn: F) -> VfsResult<()>
+    where F: FnMut(InitRdEntry<'a>) -> VfsResult<()> {
+    let mut offset = 0;
+    loop {
+        let header = archive_slice(initrd_data, offset, CPIO_NEWC_HEADER_SIZE)?;
+        if !header.starts_with(CPIO_NEWC_MAGIC) {
+            return Err((OsErrorClass::InvalidArgument, Some("Malformed cpio header")));
+        }
+
+        let unix_mode = parse_number(&header[14..22], 16)? as u32;
+        let data_size = parse_number(&header[54..62], 16)?;
+        let name_size = parse_number(&header[94..102], 16)?;
+
+        /* the name is NULL terminated and padded with the header to 4 bytes */
+        let name_offset = offset + CPIO_NEWC_HEADER_SIZE;
+        let name = parse_str(archive_slice(initrd_data, name_offset, name_size)?)?;
+        let data_offset = align_up(name_offset + name_size, 4);
+        let data = archive_slice(initrd_data, data_offset, data_size)?;
+        offset = align_up(data_offset + data_size, 4);
+
+        if name == CPIO_NEWC_TRAILER {
+            return Ok(());
+        }
+        if let Some(str_path) = normalize_path(name) {
+            let initrd_entry = match unix_mode & UNIX_MODE_TYPE_MASK {
+                UNIX_MODE_DIR => Some(InitRdEntry::Dir(str_path, unix_mode)),
+                UNIX_MODE_FILE => Some(InitRdEntry::File(str_path, unix_mode, data)),
+                UNIX_MODE_LINK => Some(InitRdEntry::Link(str_path, parse_str(data)?)),
+                _ => None
+            };
+            if let Some(initrd_entry) = initrd_entry {
+                entry_fn(initrd_entry)?;
+            }
+        }
+    }
+}
+
+/**
+ * Walks the `ustar` archive giving each entry to the given closure
+ */
+fn walk_ustar<'a, F>(initrd_data: &'a [u8], mut entry_fn: F) -> VfsResult<()>
+    where F: FnMut(InitRdEntry<'a>) -> VfsResult<()> {
+    let mut offset = 0;
+    while offset + USTAR_BLOCK_SIZE <= initrd_data.len() {
+        let header = archive_slice(initrd_data, offset, USTAR_BLOCK_SIZE)?;
+
+        /* the archive ends with zero filled blocks */
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        } else if &header[USTAR_MAGIC_OFFSET..USTAR_MAGIC_OFFSET + USTAR_MAGIC.len()]
+                  != USTAR_MAGIC
+        {
+            return Err((OsErrorClass::InvalidArgument, Some("Malformed ustar header")));
+        }
+
+        let unix_mode = parse_number(&header[100..108], 8)? as u32;
+        let data_size = parse_number(&header[124..136], 8)?;
+        let type_flag = header[156];
+        let data = archive_slice(initrd_data, offset + USTAR_BLOCK_SIZE, data_size)?;
+        offset += USTAR_BLOCK_SIZE + align_up(data_size, USTAR_BLOCK_SIZE);
+
+        /* the prefix field extends the name for the long paths */
+        let name = parse_str(&header[..100])?;
+        let prefix = parse_str(&header[345..500])?;
+        let full_name = if prefix.is_empty() {
+            String::from(name)
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        if let Some(str_path) = normalize_path(&full_name) {
+            let initrd_entry = match type_flag {
+                b'0' | b'\0' => Some(InitRdEntry::File(str_path, unix_mode, data)),
+                b'5' => Some(InitRdEntry::Dir(str_path, unix_mode)),
+                b'2' => Some(InitRdEntry::Link(str_path, parse_str(&header[157..257])?)),
+                _ => None
+            };
+            if let Some(initrd_entry) = initrd_entry {
+                entry_fn(initrd_entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Returns the bounds checked sub-slice of the archive
+ */
+fn archive_slice(initrd_data: &[u8], offset: usize, size: usize) -> VfsResult<&[u8]> {
+    offset.checked_add(size)
+          .and_then(|end_offset| initrd_data.get(offset..end_offset))
+          .ok_or((OsErrorClass::EndOfDataReached, Some("Truncated initrd archive")))
+}
+
+/**
+ * Parses the ASCII number of the archive header with the given radix
+ */
+fn parse_number(raw_field: &[u8], radix: u32) -> VfsResult<usize> {
+    let str_field = parse_str(raw_field)?.trim();
+    if str_field.is_empty() {
+        Ok(0)
+    } else {
+        usize::from_str_radix(str_field, radix).map_err(|_| {
+                                                   (OsErrorClass::InvalidArgument,
+                                                    Some("Malformed initrd number"))
+                                               })
+    }
+}
+
+/**
+ * Returns the UTF-8 string of the field until the first NULL byte
+ */
+fn parse_str(raw_field: &[u8]) -> VfsResult<&str> {
+    let str_len = raw_field.iter().position(|byte| *byte == 0).unwrap_or(raw_field.len());
+    str::from_utf8(&raw_field[..str_len]).map_err(|_| {
+                                             (OsErrorClass::InvalidArgument,
+                                              Some("Non UTF-8 initrd string"))
+                                         })
+}
+
+/**
+ * Returns the absolute path of the archive name, which could be relative
+ * to the archive root, `None` for the archive root itself
+ */
+fn normalize_path(name: &str) -> Option<String> {
+    let mut str_path = String::new();
+    for str_component in name.split(PathComponent::SEPARATOR).filter(|str_component| {
+                                                                 !str_component.is_empty()
+                                 && *str_component != PathComponent::SELF_LINK
+                                                             })
+    {
+        str_path.push_str(PathComponent::SEPARATOR);
+        str_path.push_str(str_component);
+    }
+
+    if str_path.is_empty() {
+        None
+    } else {
+        Some(str_path)
+    }
+}
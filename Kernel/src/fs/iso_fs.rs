@@ -0,0 +1,308 @@
+/*! ISO9660 filesystem driver */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc,
+    vec::Vec
+};
+
+use core::sync::atomic::{
+    AtomicU32,
+    Ordering
+};
+
+use api_data::{
+    entity::OsEntityId,
+    error::class::OsErrorClass,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        dir::DirEntry,
+        grants::{
+            ObjGrantsBits,
+            RawObjGrants
+        },
+        info::RawObjInfo,
+        types::ObjType
+    },
+    path::{
+        PathComponent,
+        PathExistsState
+    },
+    task::modes::FsMountMode
+};
+use iso_fs::{
+    rock_ridge::RockRidgeInfo,
+    volume::{
+        IsoDirRecord,
+        IsoNodeType,
+        IsoVolume
+    }
+};
+use meetix_fs::{
+    dev::TBlockDevice,
+    volume::default_prot_grants
+};
+use sync::SpinMutex;
+
+use crate::{
+    fs::vfs::{
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        path::parse_str_path,
+        Vfs,
+        VfsResult
+    },
+    task::{
+        process::Process,
+        scheduler::Scheduler
+    }
+};
+
+/* serial value of the <DeviceId> of the next <IsoFs> */
+static SM_NEXT_ISO_FS_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * Block device accepted by the `IsoFs`
+ */
+pub type IsoFsDevice = Box<dyn TBlockDevice + Send>;
+
+/**
+ * `FsType::CdROM` `TFileSystem`.
+ *
+ * Wraps the read-only `iso_fs::volume::IsoVolume` of a CD-ROM or of an ISO
+ * image, the Rock Ridge names, attributes and symbolic links are preferred
+ * to the Joliet names.
+ *
+ * All the nodes have the default grants without the writes, the owners
+ * are given only by Rock Ridge
+ */
+pub struct IsoFs {
+    m_root_node: Arc<IsoFsNode>
+}
+
+impl IsoFs /* Constants */ {
+    /**
+     * Directory where the CD-ROM which booted the kernel is mounted
+     */
+    pub const BOOT_CD_MNT_PATH: &'static str = "/MeetiX/BootCD";
+}
+
+impl IsoFs /* Constructors */ {
+    /**
+     * Mounts the filesystem stored into the given block device
+     */
+    pub fn mount(device: IsoFsDevice) -> VfsResult<Self> {
+        let volume = IsoVolume::mount(device)?;
+        let device_id = DeviceId::new(DeviceIdType::Block,
+                                      DeviceIdClass::Storage,
+                                      SM_NEXT_ISO_FS_SERIAL.fetch_add(1,
+                                                                      Ordering::SeqCst));
+
+        let root_record = volume.root().clone();
+        let shared = Arc::new(IsoFsShared { m_device_id: device_id,
+                                            m_volume: SpinMutex::const_new(volume) });
+        Ok(Self { m_root_node: Arc::new(IsoFsNode::new(shared, root_record)) })
+    }
+}
+
+impl IsoFs /* Getters */ {
+    /**
+     * Returns the `DeviceId` of this `IsoFs`
+     */
+    pub fn device_id(&self) -> DeviceId {
+        self.m_root_node.m_shared.m_device_id
+    }
+}
+
+impl TFileSystem for IsoFs {
+    fn name(&self) -> &'static str {
+        "isofs"
+    }
+
+    fn root_node(&self) -> Arc<dyn TVfsNode> {
+        self.m_root_node.clone()
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/**
+ * Data shared among all the `IsoFsNode`s of the same `IsoFs`
+ */
+struct IsoFsShared {
+    m_device_id: DeviceId,
+    m_volume: SpinMutex<IsoVolume<IsoFsDevice>>
+}
+
+/**
+ * Directory, file or symbolic link of an `IsoFs`, keeps his resolved
+ * directory record since the volume never changes
+ */
+pub struct IsoFsNode {
+    m_shared: Arc<IsoFsShared>,
+    m_record: IsoDirRecord,
+    m_obj_type: ObjType
+}
+
+impl IsoFsNode /* Constructors */ {
+    /**
+     * Constructs the `IsoFsNode` of the given `IsoDirRecord`
+     */
+    fn new(shared: Arc<IsoFsShared>, dir_record: IsoDirRecord) -> Self {
+        Self { m_shared: shared,
+               m_obj_type: iso_obj_type(dir_record.node_type()),
+               m_record: dir_record }
+    }
+}
+
+impl TVfsNode for IsoFsNode {
+    fn obj_type(&self) -> ObjType {
+        self.m_obj_type
+    }
+
+    fn node_id(&self) -> u64 {
+        self.m_record.node_id()
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        let block_size = self.m_shared.m_volume.lock().block_size();
+
+        /* nobody could write the volume */
+        let mut prot_grants =
+            RawObjGrants::from_raw_truncate(default_prot_grants() as usize);
+        for write_grant in [ObjGrantsBits::UserCanWriteData,
+                            ObjGrantsBits::UserCanWriteInfo,
+                            ObjGrantsBits::GroupCanWriteData,
+                            ObjGrantsBits::GroupCanWriteInfo,
+                            ObjGrantsBits::OtherCanWriteData,
+                            ObjGrantsBits::OtherCanWriteInfo].iter()
+        {
+            prot_grants.set_disabled(*write_grant);
+        }
+
+        /* the plain ISO9660 and Joliet records have only the recording date */
+        let recording_inst = self.m_record.recording_inst();
+        let rock_ridge = self.m_record.rock_ridge();
+        let creat_inst =
+            rock_ridge.and_then(RockRidgeInfo::creat_inst).unwrap_or(recording_inst);
+        let access_inst =
+            rock_ridge.and_then(RockRidgeInfo::access_inst).unwrap_or(recording_inst);
+        let modify_inst =
+            rock_ridge.and_then(RockRidgeInfo::modify_inst).unwrap_or(recording_inst);
+
+        /* the owners are known only with Rock Ridge */
+        let os_user = rock_ridge.and_then(RockRidgeInfo::uid).unwrap_or(0) as OsEntityId;
+        let os_group = rock_ridge.and_then(RockRidgeInfo::gid).unwrap_or(0) as OsEntityId;
+
+        let data_size = self.m_record.data_size() as usize;
+        RawObjInfo::new(self.m_obj_type,
+                        0,
+                        self.m_shared.m_device_id,
+                        self.node_id(),
+                        name,
+                        rock_ridge.and_then(RockRidgeInfo::links).unwrap_or(1),
+                        block_size,
+                        (data_size + block_size - 1) / block_size,
+                        data_size,
+                        os_user,
+                        os_group,
+                        prot_grants,
+                        creat_inst,
+                        access_inst,
+                        modify_inst,
+                        Scheduler::instance().uptime(),
+                        modify_inst)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        let dir_record = self.m_shared
+                             .m_volume
+                             .lock()
+                             .lookup(&self.m_record, name)?
+                             .ok_or((OsErrorClass::ReferenceNotFound,
+                                     Some("No such name in directory")))?;
+        Ok(Arc::new(Self::new(self.m_shared.clone(), dir_record)))
+    }
+
+    fn create_child(&self,
+                    _name: &str,
+                    _obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only isofs")))
+    }
+
+    fn remove_child(&self, _name: &str) -> VfsResult<()> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only isofs")))
+    }
+
+    fn child_at(&self, index: usize) -> VfsResult<Option<DirEntry>> {
+        /* the directories are read each time, nothing is cached */
+        let dir_records = self.m_shared.m_volume.lock().dir_records(&self.m_record)?;
+        Ok(dir_records.get(index).map(|dir_record| {
+                                     DirEntry::new(dir_record.name(),
+                                                   iso_obj_type(dir_record.node_type()))
+                                 }))
+    }
+
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> VfsResult<usize> {
+        self.m_shared.m_volume.lock().read_data(&self.m_record, offset, buffer)
+    }
+
+    fn write_at(&self, _offset: usize, _buffer: &[u8]) -> VfsResult<usize> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only isofs")))
+    }
+
+    fn data_size(&self) -> usize {
+        if self.m_obj_type == ObjType::File {
+            self.m_record.data_size() as usize
+        } else {
+            0
+        }
+    }
+
+    fn set_data_size(&self, _data_size: usize) -> VfsResult<()> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only isofs")))
+    }
+
+    fn link_target(&self) -> VfsResult<Vec<PathComponent>> {
+        let str_target = self.m_record
+                             .link_target()
+                             .ok_or((OsErrorClass::TypesNotMatch, Some("Not a link")))?;
+        parse_str_path(str_target)
+    }
+}
+
+/**
+ * Mounts the CD-ROM which booted the kernel at `IsoFs::BOOT_CD_MNT_PATH`,
+ * which is created when the sysroot doesn't contain it
+ */
+pub fn mount_boot_cd(kern_proc: &Process, device: IsoFsDevice) -> VfsResult<()> {
+    let iso_fs = IsoFs::mount(device)?;
+
+    let mnt_path = parse_str_path(IsoFs::BOOT_CD_MNT_PATH)?;
+    if !matches!(Vfs::instance().path_exists(kern_proc, &mnt_path),
+                 PathExistsState::Exists(ObjType::Dir))
+    {
+        Vfs::instance().create(kern_proc, &mnt_path, ObjType::Dir)?;
+    }
+    Vfs::instance().mount(kern_proc, Arc::new(iso_fs), &mnt_path, FsMountMode::OsGlobal)
+}
+
+/**
+ * Returns the `ObjType` of the given `IsoNodeType`
+ */
+fn iso_obj_type(node_type: IsoNodeType) -> ObjType {
+    match node_type {
+        IsoNodeType::Dir => ObjType::Dir,
+        IsoNodeType::File => ObjType::File,
+        IsoNodeType::Link => ObjType::Link
+    }
+}
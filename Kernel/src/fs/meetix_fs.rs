@@ -0,0 +1,339 @@
+/*! MeetiX filesystem driver */
+
+use alloc::{
+    boxed::Box,
+    string::String,
+    sync::Arc,
+    vec::Vec
+};
+
+use core::sync::atomic::{
+    AtomicU32,
+    Ordering
+};
+
+use api_data::{
+    entity::OsEntityId,
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        dir::DirEntry,
+        grants::RawObjGrants,
+        info::RawObjInfo,
+        types::ObjType
+    },
+    path::PathComponent
+};
+use meetix_fs::{
+    dev::TBlockDevice,
+    layout::{
+        Inode,
+        InodeInstant,
+        NodeType
+    },
+    volume::{
+        default_prot_grants,
+        FormatOptions,
+        Volume
+    }
+};
+use sync::SpinMutex;
+
+use crate::{
+    fs::vfs::{
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        path::parse_str_path,
+        VfsResult
+    },
+    task::scheduler::Scheduler
+};
+
+/* serial value of the <DeviceId> of the next <MeetiXFs> */
+static SM_NEXT_MEETIX_FS_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * Block device accepted by the `MeetiXFs`
+ */
+pub type MeetiXFsDevice = Box<dyn TBlockDevice + Send>;
+
+/**
+ * `FsType::MeetiX` `TFileSystem`.
+ *
+ * Wraps the `meetix_fs::volume::Volume` of a block device, the metadata
+ * of the nodes is never cached, so each operation reads it from the device
+ */
+pub struct MeetiXFs {
+    m_root_node: Arc<MeetiXFsNode>
+}
+
+impl MeetiXFs /* Constructors */ {
+    /**
+     * Mounts the filesystem stored into the given block device
+     */
+    pub fn mount(device: MeetiXFsDevice) -> VfsResult<Self> {
+        Ok(Self::new(Volume::mount(device, now)?))
+    }
+
+    /**
+     * Formats the given block device with an empty filesystem and mounts it
+     */
+    pub fn format(device: MeetiXFsDevice,
+                  format_options: &FormatOptions)
+                  -> VfsResult<Self> {
+        Ok(Self::new(Volume::format(device, format_options, now)?))
+    }
+
+    /**
+     * Constructs the `MeetiXFs` for the given mounted `Volume`
+     */
+    fn new(volume: Volume<MeetiXFsDevice>) -> Self {
+        let device_id =
+            DeviceId::new(DeviceIdType::Block,
+                          DeviceIdClass::Storage,
+                          SM_NEXT_MEETIX_FS_SERIAL.fetch_add(1, Ordering::SeqCst));
+        let shared = Arc::new(MeetiXFsShared { m_device_id: device_id,
+                                               m_volume: SpinMutex::const_new(volume) });
+
+        Self { m_root_node: Arc::new(MeetiXFsNode { m_shared: shared,
+                                                    m_inode_id: Inode::ROOT_ID,
+                                                    m_obj_type: ObjType::Dir }) }
+    }
+}
+
+impl MeetiXFs /* Methods */ {
+    /**
+     * Writes back the cached blocks bitmap and the superblock
+     */
+    pub fn sync(&self) -> VfsResult<()> {
+        self.m_root_node.m_shared.m_volume.lock().sync()
+    }
+}
+
+impl MeetiXFs /* Getters */ {
+    /**
+     * Returns the `DeviceId` of this `MeetiXFs`
+     */
+    pub fn device_id(&self) -> DeviceId {
+        self.m_root_node.m_shared.m_device_id
+    }
+
+    /**
+     * Returns whether the filesystem was cleanly unmounted before this
+     * mount
+     */
+    pub fn was_clean(&self) -> bool {
+        self.m_root_node.m_shared.m_volume.lock().was_clean()
+    }
+}
+
+impl TFileSystem for MeetiXFs {
+    fn name(&self) -> &'static str {
+        "meetixfs"
+    }
+
+    fn root_node(&self) -> Arc<dyn TVfsNode> {
+        self.m_root_node.clone()
+    }
+}
+
+/**
+ * Data shared among all the `MeetiXFsNode`s of the same `MeetiXFs`
+ */
+struct MeetiXFsShared {
+    m_device_id: DeviceId,
+    m_volume: SpinMutex<Volume<MeetiXFsDevice>>
+}
+
+/**
+ * Directory, file or link of a `MeetiXFs`, references his inode
+ */
+pub struct MeetiXFsNode {
+    m_shared: Arc<MeetiXFsShared>,
+    m_inode_id: u64,
+    m_obj_type: ObjType
+}
+
+impl MeetiXFsNode /* Privates */ {
+    /**
+     * Constructs the `MeetiXFsNode` of the given inode
+     */
+    fn child_node(&self, inode_id: u64, node_type: NodeType) -> Arc<dyn TVfsNode> {
+        Arc::new(Self { m_shared: self.m_shared.clone(),
+                        m_inode_id: inode_id,
+                        m_obj_type: node_type.obj_type() })
+    }
+
+    /**
+     * Fails when this node is not of the given `ObjType`
+     */
+    fn ensure_obj_type(&self, obj_type: ObjType, err_msg: &'static str) -> VfsResult<()> {
+        if self.m_obj_type == obj_type {
+            Ok(())
+        } else {
+            Err((OsErrorClass::TypesNotMatch, Some(err_msg)))
+        }
+    }
+}
+
+impl TVfsNode for MeetiXFsNode {
+    fn obj_type(&self) -> ObjType {
+        self.m_obj_type
+    }
+
+    fn node_id(&self) -> u64 {
+        self.m_inode_id
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        let mut volume = self.m_shared.m_volume.lock();
+        let block_size = volume.block_size();
+
+        /* the node could have been removed by someone else meanwhile */
+        let inode = match volume.load_inode(self.m_inode_id) {
+            Ok(inode) => inode,
+            Err(_) => return RawObjInfo::default()
+        };
+        let data_blocks_used =
+            inode.extents().iter().map(|extent| extent.blocks_count() as usize).sum();
+
+        RawObjInfo::new(self.m_obj_type,
+                        0,
+                        self.m_shared.m_device_id,
+                        self.m_inode_id,
+                        name,
+                        inode.links() as u32,
+                        block_size,
+                        data_blocks_used,
+                        inode.data_size() as usize,
+                        inode.os_user() as OsEntityId,
+                        inode.os_group() as OsEntityId,
+                        RawObjGrants::from_raw_truncate(inode.prot_grants() as usize),
+                        inode.instant(InodeInstant::Creat),
+                        inode.instant(InodeInstant::DataAccess),
+                        inode.instant(InodeInstant::DataModify),
+                        now(),
+                        inode.instant(InodeInstant::InfoModify))
+    }
+
+    fn update_obj_info(&self, raw_obj_info: &RawObjInfo) -> VfsResult<()> {
+        let mut volume = self.m_shared.m_volume.lock();
+
+        let mut inode = volume.load_inode(self.m_inode_id)?;
+        inode.set_prot_grants(raw_obj_info.prot_grants().raw_bits() as u32);
+        inode.set_instant(InodeInstant::Creat, raw_obj_info.creat_inst());
+        inode.set_instant(InodeInstant::DataAccess, raw_obj_info.last_data_access_inst());
+        inode.set_instant(InodeInstant::DataModify, raw_obj_info.last_data_modify_inst());
+        inode.set_instant(InodeInstant::InfoAccess, raw_obj_info.last_info_access_inst());
+        inode.set_instant(InodeInstant::InfoModify, now());
+        volume.store_inode(&mut inode)
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        self.ensure_obj_type(ObjType::Dir, "Not a directory")?;
+
+        let dir_record = self.m_shared
+                             .m_volume
+                             .lock()
+                             .lookup(self.m_inode_id, name)?
+                             .ok_or((OsErrorClass::ReferenceNotFound,
+                                     Some("No such name in directory")))?;
+        Ok(self.child_node(dir_record.inode_id(), dir_record.node_type()))
+    }
+
+    fn create_child(&self,
+                    name: &str,
+                    obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        self.ensure_obj_type(ObjType::Dir, "Not a directory")?;
+
+        let node_type =
+            NodeType::from_obj_type(obj_type).ok_or((OsErrorClass::TypesNotMatch,
+                                                     Some("Object type not storable \
+                                                           into a meetixfs")))?;
+        let inode_id =
+            self.m_shared
+                .m_volume
+                .lock()
+                .create_node(self.m_inode_id, name, node_type, default_prot_grants())?;
+        Ok(self.child_node(inode_id, node_type))
+    }
+
+    fn remove_child(&self, name: &str) -> VfsResult<()> {
+        self.ensure_obj_type(ObjType::Dir, "Not a directory")?;
+        self.m_shared.m_volume.lock().remove_node(self.m_inode_id, name)
+    }
+
+    fn child_at(&self, index: usize) -> VfsResult<Option<DirEntry>> {
+        self.ensure_obj_type(ObjType::Dir, "Not a directory")?;
+
+        let dir_record =
+            self.m_shared.m_volume.lock().dir_record_at(self.m_inode_id, index)?;
+        Ok(dir_record.map(|dir_record| {
+                         DirEntry::new(dir_record.name(),
+                                       dir_record.node_type().obj_type())
+                     }))
+    }
+
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> VfsResult<usize> {
+        self.ensure_obj_type(ObjType::File, "Not a file")?;
+        self.m_shared.m_volume.lock().read_data(self.m_inode_id, offset, buffer)
+    }
+
+    fn write_at(&self, offset: usize, buffer: &[u8]) -> VfsResult<usize> {
+        self.ensure_obj_type(ObjType::File, "Not a file")?;
+        self.m_shared.m_volume.lock().write_data(self.m_inode_id, offset, buffer)
+    }
+
+    fn data_size(&self) -> usize {
+        if self.m_obj_type != ObjType::File {
+            return 0;
+        }
+
+        self.m_shared
+            .m_volume
+            .lock()
+            .load_inode(self.m_inode_id)
+            .map_or(0, |inode| inode.data_size() as usize)
+    }
+
+    fn set_data_size(&self, data_size: usize) -> VfsResult<()> {
+        self.ensure_obj_type(ObjType::File, "Not a file")?;
+        self.m_shared.m_volume.lock().set_data_size(self.m_inode_id, data_size)
+    }
+
+    fn link_target(&self) -> VfsResult<Vec<PathComponent>> {
+        self.ensure_obj_type(ObjType::Link, "Not a link")?;
+
+        let str_target = self.m_shared.m_volume.lock().read_link(self.m_inode_id)?;
+        parse_str_path(&str_target)
+    }
+
+    fn set_link_target(&self, target: &[PathComponent]) -> VfsResult<()> {
+        self.ensure_obj_type(ObjType::Link, "Not a link")?;
+
+        /* the target is stored as string, like the paths given by the user */
+        let mut str_target = String::new();
+        for (index, path_component) in target.iter().enumerate() {
+            if index > 0 && !target[index - 1].is_root() {
+                str_target.push_str(PathComponent::SEPARATOR);
+            }
+            str_target.push_str(&path_component.as_string());
+        }
+        self.m_shared.m_volume.lock().write_link(self.m_inode_id, &str_target)
+    }
+}
+
+/**
+ * Returns the current `RawInstant` used for the timestamps
+ */
+fn now() -> RawInstant {
+    Scheduler::instance().uptime()
+}
@@ -0,0 +1,11 @@
+/*! Kernel filesystems management */
+
+pub mod dev_fs;
+pub mod fat_fs;
+pub mod image_dev;
+pub mod initrd;
+pub mod iso_fs;
+pub mod meetix_fs;
+pub mod sysroot;
+pub mod tmpfs;
+pub mod vfs;
@@ -0,0 +1,48 @@
+/*! Sysroot embedded into the kernel image */
+
+use api_data::object::types::ObjType;
+
+use crate::{
+    fs::vfs::{
+        path::parse_str_path,
+        Vfs,
+        VfsResult
+    },
+    task::process::Process
+};
+
+/* generated by the kernel <build.rs> walking the <Root/> directory */
+const SYSROOT_ENTRIES: &[SysRootEntry] =
+    include!(concat!(env!("OUT_DIR"), "/sysroot_entries.rs"));
+
+/**
+ * Directory or file of the `Root/` sysroot embedded at build time
+ */
+enum SysRootEntry {
+    Dir(&'static str),
+    File(&'static str, &'static [u8])
+}
+
+/**
+ * Creates into the root filesystem the tree of the embedded sysroot, which
+ * gives a writable `/Users` tree even without any disk
+ */
+pub fn load_embedded_sysroot(kern_proc: &Process) -> VfsResult<()> {
+    /* the directories always precede their children */
+    for sysroot_entry in SYSROOT_ENTRIES.iter() {
+        match sysroot_entry {
+            SysRootEntry::Dir(str_path) => {
+                Vfs::instance().create(kern_proc,
+                                       &parse_str_path(str_path)?,
+                                       ObjType::Dir)?;
+            },
+            SysRootEntry::File(str_path, data) => {
+                let file_entry = Vfs::instance().create(kern_proc,
+                                                        &parse_str_path(str_path)?,
+                                                        ObjType::File)?;
+                file_entry.node().write_at(0, data)?;
+            }
+        }
+    }
+    Ok(())
+}
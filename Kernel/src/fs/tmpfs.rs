@@ -0,0 +1,429 @@
+/*! In-memory temporary filesystem */
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::Arc,
+    vec::Vec
+};
+
+use core::{
+    cmp::min,
+    mem::size_of,
+    sync::atomic::{
+        AtomicU32,
+        AtomicU64,
+        Ordering
+    }
+};
+
+use api_data::{
+    entity::OsEntityId,
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        dir::DirEntry,
+        grants::{
+            ObjGrantsBits,
+            RawObjGrants
+        },
+        info::RawObjInfo,
+        types::ObjType
+    },
+    path::PathComponent
+};
+use sync::{
+    SpinMutex,
+    SpinRwLock
+};
+
+use crate::{
+    fs::vfs::{
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        VfsResult
+    },
+    task::scheduler::Scheduler
+};
+
+/* serial value of the <DeviceId> of the next <TmpFs> */
+static SM_NEXT_TMP_FS_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * RAM-backed `TFileSystem`.
+ *
+ * Each instance is a `DeviceIdClass::Memory` device and his content is
+ * lost when the last reference to it is dropped
+ */
+pub struct TmpFs {
+    m_root_node: Arc<TmpFsNode>
+}
+
+impl TmpFs /* Constants */ {
+    /**
+     * Allocation unit reported into the `RawObjInfo` of the nodes
+     */
+    pub const DATA_BLOCK_SIZE: usize = 512;
+}
+
+impl TmpFs /* Constructors */ {
+    /**
+     * Constructs an empty `TmpFs` with his own `DeviceId`
+     */
+    pub fn new() -> Self {
+        let device_id = DeviceId::new(DeviceIdType::Block,
+                                      DeviceIdClass::Memory,
+                                      SM_NEXT_TMP_FS_SERIAL.fetch_add(1,
+                                                                      Ordering::SeqCst));
+        let shared = Arc::new(TmpFsShared { m_device_id: device_id,
+                                            m_next_node_id: AtomicU64::new(0) });
+
+        Self { m_root_node: TmpFsNode::new(shared, ObjType::Dir) }
+    }
+}
+
+impl TmpFs /* Getters */ {
+    /**
+     * Returns the `DeviceId` of this `TmpFs`
+     */
+    pub fn device_id(&self) -> DeviceId {
+        self.m_root_node.m_shared.m_device_id
+    }
+}
+
+impl TFileSystem for TmpFs {
+    fn name(&self) -> &'static str {
+        "tmpfs"
+    }
+
+    fn root_node(&self) -> Arc<dyn TVfsNode> {
+        self.m_root_node.clone()
+    }
+}
+
+/**
+ * Data shared among all the `TmpFsNode`s of the same `TmpFs`
+ */
+struct TmpFsShared {
+    m_device_id: DeviceId,
+    m_next_node_id: AtomicU64
+}
+
+/**
+ * Directory, file or link of a `TmpFs`
+ */
+pub struct TmpFsNode {
+    m_shared: Arc<TmpFsShared>,
+    m_node_id: u64,
+    m_obj_type: ObjType,
+    m_metadata: SpinMutex<TmpFsMetadata>,
+    m_content: SpinRwLock<TmpFsContent>
+}
+
+impl TmpFsNode /* Constructors */ {
+    /**
+     * Constructs an empty `TmpFsNode` of the given `ObjType` with the
+     * default grants
+     */
+    fn new(shared: Arc<TmpFsShared>, obj_type: ObjType) -> Arc<Self> {
+        let content = match obj_type {
+            ObjType::Dir => TmpFsContent::Dir(BTreeMap::new()),
+            ObjType::Link => TmpFsContent::Link(Vec::new()),
+            _ => TmpFsContent::File(Vec::new())
+        };
+        let node_id = shared.m_next_node_id.fetch_add(1, Ordering::SeqCst);
+
+        Arc::new(Self { m_shared: shared,
+                        m_node_id: node_id,
+                        m_obj_type: obj_type,
+                        m_metadata: SpinMutex::const_new(TmpFsMetadata::new(now())),
+                        m_content: SpinRwLock::const_new(content) })
+    }
+}
+
+impl TVfsNode for TmpFsNode {
+    fn obj_type(&self) -> ObjType {
+        self.m_obj_type
+    }
+
+    fn node_id(&self) -> u64 {
+        self.m_node_id
+    }
+
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo {
+        /* directories and links account a <DirEntry> for each name */
+        let data_bytes_used = match &*self.m_content.read() {
+            TmpFsContent::Dir(children) => children.len() * size_of::<DirEntry>(),
+            TmpFsContent::File(data) => data.len(),
+            TmpFsContent::Link(target) => target.len() * size_of::<DirEntry>()
+        };
+
+        let mut metadata = self.m_metadata.lock();
+        metadata.m_last_info_access_inst = now();
+        RawObjInfo::new(self.m_obj_type,
+                        0,
+                        self.m_shared.m_device_id,
+                        self.m_node_id,
+                        name,
+                        1,
+                        TmpFs::DATA_BLOCK_SIZE,
+                        (data_bytes_used + TmpFs::DATA_BLOCK_SIZE - 1)
+                        / TmpFs::DATA_BLOCK_SIZE,
+                        data_bytes_used,
+                        metadata.m_os_user_id,
+                        metadata.m_os_group_id,
+                        metadata.m_prot_grants,
+                        metadata.m_creat_inst,
+                        metadata.m_last_data_access_inst,
+                        metadata.m_last_data_modify_inst,
+                        metadata.m_last_info_access_inst,
+                        metadata.m_last_info_modify_inst)
+    }
+
+    fn update_obj_info(&self, raw_obj_info: &RawObjInfo) -> VfsResult<()> {
+        let mut metadata = self.m_metadata.lock();
+        metadata.m_prot_grants = *raw_obj_info.prot_grants();
+        metadata.m_creat_inst = raw_obj_info.creat_inst();
+        metadata.m_last_data_access_inst = raw_obj_info.last_data_access_inst();
+        metadata.m_last_data_modify_inst = raw_obj_info.last_data_modify_inst();
+        metadata.m_last_info_access_inst = raw_obj_info.last_info_access_inst();
+        metadata.m_last_info_modify_inst = now();
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        match &*self.m_content.read() {
+            TmpFsContent::Dir(children) => {
+                let child_node = children.get(name)
+                                         .ok_or((OsErrorClass::ReferenceNotFound,
+                                                 Some("No such name in directory")))?;
+                Ok(child_node.clone())
+            },
+            _ => Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+        }
+    }
+
+    fn create_child(&self,
+                    name: &str,
+                    obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        if !matches!(obj_type, ObjType::Dir | ObjType::File | ObjType::Link) {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Object type not storable into a tmpfs")));
+        }
+
+        match &mut *self.m_content.write() {
+            TmpFsContent::Dir(children) => {
+                if children.contains_key(name) {
+                    return Err((OsErrorClass::IdentifierNotAvailable,
+                                Some("Name already in use")));
+                }
+
+                let child_node = Self::new(self.m_shared.clone(), obj_type);
+                children.insert(String::from(name), child_node.clone());
+                self.m_metadata.lock().mark_data_modified();
+                Ok(child_node)
+            },
+            _ => Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+        }
+    }
+
+    fn remove_child(&self, name: &str) -> VfsResult<()> {
+        match &mut *self.m_content.write() {
+            TmpFsContent::Dir(children) => {
+                let child_node = children.get(name)
+                                         .ok_or((OsErrorClass::ReferenceNotFound,
+                                                 Some("No such name in directory")))?;
+                if let TmpFsContent::Dir(grandchildren) = &*child_node.m_content.read() {
+                    if !grandchildren.is_empty() {
+                        return Err((OsErrorClass::OperationNotEnabled,
+                                    Some("Directory not empty")));
+                    }
+                }
+
+                children.remove(name);
+                self.m_metadata.lock().mark_data_modified();
+                Ok(())
+            },
+            _ => Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+        }
+    }
+
+    fn child_at(&self, index: usize) -> VfsResult<Option<DirEntry>> {
+        let dir_entry = match &*self.m_content.read() {
+            TmpFsContent::Dir(children) => {
+                children.iter().nth(index).map(|(name, child_node)| {
+                                              DirEntry::new(name, child_node.m_obj_type)
+                                          })
+            },
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+        };
+
+        self.m_metadata.lock().m_last_data_access_inst = now();
+        Ok(dir_entry)
+    }
+
+    fn read_at(&self, offset: usize, buffer: &mut [u8]) -> VfsResult<usize> {
+        let read_bytes = match &*self.m_content.read() {
+            TmpFsContent::File(data) => {
+                /* reading past the end is not an error, simply reads nothing */
+                let offset = min(offset, data.len());
+                let read_bytes = min(buffer.len(), data.len() - offset);
+
+                buffer[..read_bytes].copy_from_slice(&data[offset..offset + read_bytes]);
+                read_bytes
+            },
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+        };
+
+        self.m_metadata.lock().m_last_data_access_inst = now();
+        Ok(read_bytes)
+    }
+
+    fn write_at(&self, offset: usize, buffer: &[u8]) -> VfsResult<usize> {
+        match &mut *self.m_content.write() {
+            TmpFsContent::File(data) => {
+                let write_end =
+                    offset.checked_add(buffer.len())
+                          .ok_or((OsErrorClass::LimitOverflow, Some("File too big")))?;
+
+                /* the hole between the end and the offset is zero filled */
+                if write_end > data.len() {
+                    data.resize(write_end, 0);
+                }
+                data[offset..write_end].copy_from_slice(buffer);
+            },
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+        }
+
+        self.m_metadata.lock().mark_data_modified();
+        Ok(buffer.len())
+    }
+
+    fn data_size(&self) -> usize {
+        match &*self.m_content.read() {
+            TmpFsContent::File(data) => data.len(),
+            _ => 0
+        }
+    }
+
+    fn set_data_size(&self, data_size: usize) -> VfsResult<()> {
+        match &mut *self.m_content.write() {
+            TmpFsContent::File(data) => data.resize(data_size, 0),
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+        }
+
+        self.m_metadata.lock().mark_data_modified();
+        Ok(())
+    }
+
+    fn link_target(&self) -> VfsResult<Vec<PathComponent>> {
+        let link_target = match &*self.m_content.read() {
+            TmpFsContent::Link(target) => target.clone(),
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a link")))
+        };
+
+        self.m_metadata.lock().m_last_data_access_inst = now();
+        Ok(link_target)
+    }
+
+    fn set_link_target(&self, target: &[PathComponent]) -> VfsResult<()> {
+        match &mut *self.m_content.write() {
+            TmpFsContent::Link(link_target) => *link_target = Vec::from(target),
+            _ => return Err((OsErrorClass::TypesNotMatch, Some("Not a link")))
+        }
+
+        self.m_metadata.lock().mark_data_modified();
+        Ok(())
+    }
+}
+
+/**
+ * Ownership, grants and timestamps of a `TmpFsNode`
+ */
+struct TmpFsMetadata {
+    m_os_user_id: OsEntityId,
+    m_os_group_id: OsEntityId,
+    m_prot_grants: RawObjGrants,
+    m_creat_inst: RawInstant,
+    m_last_data_access_inst: RawInstant,
+    m_last_data_modify_inst: RawInstant,
+    m_last_info_access_inst: RawInstant,
+    m_last_info_modify_inst: RawInstant
+}
+
+impl TmpFsMetadata /* Constructors */ {
+    /**
+     * Constructs the `TmpFsMetadata` of a node created at the given
+     * `RawInstant`.
+     *
+     * The nodes are owned by the kernel, which grants everything to the
+     * owner and only the read and the traversal to the others
+     */
+    fn new(creat_inst: RawInstant) -> Self {
+        let mut prot_grants = RawObjGrants::new_zero();
+        for grant_bit in [ObjGrantsBits::UserCanOpenIt,
+                          ObjGrantsBits::UserCanReadData,
+                          ObjGrantsBits::UserCanWriteData,
+                          ObjGrantsBits::UserCanExecTraversData,
+                          ObjGrantsBits::UserCanReadInfo,
+                          ObjGrantsBits::UserCanWriteInfo,
+                          ObjGrantsBits::UserCanSeeIt,
+                          ObjGrantsBits::GroupCanOpenIt,
+                          ObjGrantsBits::GroupCanReadData,
+                          ObjGrantsBits::GroupCanExecTraversData,
+                          ObjGrantsBits::GroupCanReadInfo,
+                          ObjGrantsBits::GroupCanSeeIt,
+                          ObjGrantsBits::OtherCanOpenIt,
+                          ObjGrantsBits::OtherCanReadData,
+                          ObjGrantsBits::OtherCanExecTraversData,
+                          ObjGrantsBits::OtherCanReadInfo,
+                          ObjGrantsBits::OtherCanSeeIt].iter()
+        {
+            prot_grants.set_enabled(*grant_bit);
+        }
+
+        Self { m_os_user_id: 0,
+               m_os_group_id: 0,
+               m_prot_grants: prot_grants,
+               m_creat_inst: creat_inst,
+               m_last_data_access_inst: creat_inst,
+               m_last_data_modify_inst: creat_inst,
+               m_last_info_access_inst: creat_inst,
+               m_last_info_modify_inst: creat_inst }
+    }
+}
+
+impl TmpFsMetadata /* Methods */ {
+    /**
+     * Updates the timestamps after a modification of the data
+     */
+    fn mark_data_modified(&mut self) {
+        let now = now();
+        self.m_last_data_access_inst = now;
+        self.m_last_data_modify_inst = now;
+    }
+}
+
+/**
+ * Data of a `TmpFsNode` according to his `ObjType`
+ */
+enum TmpFsContent {
+    Dir(BTreeMap<String, Arc<TmpFsNode>>),
+    File(Vec<u8>),
+    Link(Vec<PathComponent>)
+}
+
+/**
+ * Returns the current `RawInstant` used for the timestamps
+ */
+fn now() -> RawInstant {
+    Scheduler::instance().uptime()
+}
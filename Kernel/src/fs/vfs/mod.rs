@@ -0,0 +1,515 @@
+/*! Kernel Virtual File System */
+
+use alloc::{
+    collections::VecDeque,
+    sync::Arc,
+    vec::Vec
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::types::ObjType,
+    path::{
+        PathComponent,
+        PathExistsState
+    },
+    task::modes::FsMountMode
+};
+use sync::SpinRwLock;
+
+use crate::{
+    fs::vfs::{
+        mount::Mount,
+        node::{
+            TFileSystem,
+            TVfsNode
+        },
+        path::{
+            validate_name,
+            VfsPath
+        }
+    },
+    task::process::Process
+};
+
+pub mod mount;
+pub mod node;
+pub mod path;
+
+/* <None> until <Vfs::init_instance()> is called */
+static mut SM_VFS: Option<Vfs> = None;
+
+/**
+ * On failure the VFS returns the `OsErrorClass` and an optional message,
+ * as the kernel functions do
+ */
+pub type VfsResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Result of `Vfs::walk()`, on failure carries the index of the last
+ * existing component too
+ */
+type WalkResult = Result<VfsEntry, (Option<usize>, (OsErrorClass, Option<&'static str>))>;
+
+/**
+ * Unifies all the mounted `TFileSystem`s into a single tree.
+ *
+ * The paths are resolved starting from the root or from the working
+ * directory of the `Process`, crossing only the `Mount`s visible by it
+ */
+pub struct Vfs {
+    m_mounts: SpinRwLock<Vec<Arc<Mount>>>
+}
+
+impl Vfs /* Constants */ {
+    /**
+     * Maximum amount of links followed while resolving a single path
+     */
+    pub const LINKS_FOLLOWED_MAX: usize = 16;
+
+    /**
+     * Size of the buffer used to copy the files
+     */
+    const COPY_CHUNK_SIZE: usize = 4096;
+}
+
+impl Vfs /* Constructors */ {
+    /**
+     * Initializes the global `Vfs` instance without any `Mount`
+     */
+    pub fn init_instance() {
+        unsafe {
+            SM_VFS = Some(Self { m_mounts: SpinRwLock::const_new(Vec::new()) });
+        }
+    }
+}
+
+impl Vfs /* Methods */ {
+    /**
+     * Mounts the given `TFileSystem` at the given directory, which must be
+     * empty.
+     *
+     * The first `Mount` must be the root one
+     */
+    pub fn mount(&self,
+                 proc: &Process,
+                 file_system: Arc<dyn TFileSystem>,
+                 mnt_path: &[PathComponent],
+                 mnt_mode: FsMountMode)
+                 -> VfsResult<()> {
+        if !self.has_root_mount() {
+            if !matches!(mnt_path, [PathComponent::Root])
+               || mnt_mode != FsMountMode::OsGlobal
+            {
+                return Err((OsErrorClass::OperationNotEnabled,
+                            Some("The root filesystem must be mounted first")));
+            }
+
+            let root_mount = Mount::new(VfsPath::root(), file_system, mnt_mode, proc);
+            self.m_mounts.write().push(Arc::new(root_mount));
+            return Ok(());
+        }
+
+        let mnt_point = self.resolve(proc, mnt_path, true)?;
+        if mnt_point.node().obj_type() != ObjType::Dir {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Mount point is not a directory")));
+        } else if mnt_point.node().child_at(0)?.is_some() {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Mount point is not empty")));
+        }
+
+        let mut mounts = self.m_mounts.write();
+        if mounts.iter()
+                 .any(|mount| {
+                     mount.mnt_path() == mnt_point.path() && mount.is_visible_by(proc)
+                 })
+        {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Mount point already in use")));
+        }
+        mounts.push(Arc::new(Mount::new(mnt_point.m_path, file_system, mnt_mode, proc)));
+        Ok(())
+    }
+
+    /**
+     * Removes the `Mount` at the given directory, only the `Process` which
+     * performed the mount could remove it
+     */
+    pub fn unmount(&self, proc: &Process, mnt_path: &[PathComponent]) -> VfsResult<()> {
+        let mnt_point = self.resolve(proc, mnt_path, true)?;
+        if mnt_point.path().is_root() {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("The root filesystem cannot be unmounted")));
+        }
+
+        let mut mounts = self.m_mounts.write();
+        let mount_index =
+            mounts.iter()
+                  .rposition(|mount| {
+                      mount.mnt_path() == mnt_point.path() && mount.is_visible_by(proc)
+                  })
+                  .ok_or((OsErrorClass::ReferenceNotFound, Some("Not a mount point")))?;
+        if mounts[mount_index].owner_proc_id() != proc.id() {
+            return Err((OsErrorClass::NotEnoughGrants,
+                        Some("Mount performed by another process")));
+        }
+
+        mounts.remove(mount_index);
+        Ok(())
+    }
+
+    /**
+     * Resolves the given path into the `VfsEntry` of the referenced node.
+     *
+     * When `follow_last_link` is `false` and the last component is a link,
+     * the link itself is returned
+     */
+    pub fn resolve(&self,
+                   proc: &Process,
+                   path: &[PathComponent],
+                   follow_last_link: bool)
+                   -> VfsResult<VfsEntry> {
+        if path.is_empty() {
+            return Err((OsErrorClass::InvalidArgument, Some("Empty VFS path")));
+        }
+        self.walk(proc, path, follow_last_link).map_err(|(_, error)| error)
+    }
+
+    /**
+     * Returns the `PathExistsState` of the given path
+     */
+    pub fn path_exists(&self, proc: &Process, path: &[PathComponent]) -> PathExistsState {
+        if path.is_empty() {
+            return PathExistsState::EmptyPath;
+        }
+
+        match self.walk(proc, path, true) {
+            Ok(vfs_entry) => PathExistsState::Exists(vfs_entry.node().obj_type()),
+            Err((Some(last_existing_index), _)) => {
+                PathExistsState::ExistsUntil(last_existing_index)
+            },
+            Err((None, _)) => PathExistsState::NotExists
+        }
+    }
+
+    /**
+     * Creates a new empty node of the given `ObjType` at the given path,
+     * the parent directory must already exist
+     */
+    pub fn create(&self,
+                  proc: &Process,
+                  path: &[PathComponent],
+                  obj_type: ObjType)
+                  -> VfsResult<VfsEntry> {
+        let (parent_entry, name) = self.resolve_parent(proc, path)?;
+        if self.is_mount_point_visible_by(proc, &parent_entry.m_path, name) {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Name already in use")));
+        }
+
+        /* check the VFS limits before touching the filesystem */
+        let mut vfs_path = parent_entry.path().clone();
+        vfs_path.push_name(name)?;
+
+        let node = parent_entry.node().create_child(name, obj_type)?;
+        Ok(VfsEntry { m_path: vfs_path,
+                      m_node: node })
+    }
+
+    /**
+     * Removes the name of the node at the given path, links are not
+     * followed
+     */
+    pub fn remove(&self, proc: &Process, path: &[PathComponent]) -> VfsResult<()> {
+        let (parent_entry, name) = self.resolve_parent(proc, path)?;
+        if self.is_mount_point_visible_by(proc, &parent_entry.m_path, name) {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Cannot remove a mount point")));
+        }
+
+        parent_entry.node().remove_child(name)
+    }
+
+    /**
+     * Copies the given file into a new file with the same name into the
+     * given directory
+     */
+    pub fn copy_file(&self,
+                     proc: &Process,
+                     file_entry: &VfsEntry,
+                     dest_dir_entry: &VfsEntry)
+                     -> VfsResult<VfsEntry> {
+        if file_entry.node().obj_type() != ObjType::File {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        }
+
+        /* a file is never the root, so it always has a name */
+        let mut dest_path = dest_dir_entry.path().to_path_components();
+        dest_path.push(PathComponent::from(file_entry.path().last_name().unwrap()));
+        let dest_entry = self.create(proc, &dest_path, ObjType::File)?;
+
+        /* copy the data chunk by chunk, the partial copy is removed */
+        let mut chunk_buffer = vec![0; Self::COPY_CHUNK_SIZE];
+        let mut offset = 0;
+        loop {
+            let read_result = file_entry.node().read_at(offset, &mut chunk_buffer);
+            let copy_result = read_result.and_then(|read_bytes| {
+                                             let chunk = &chunk_buffer[..read_bytes];
+                                             dest_entry.node().write_at(offset, chunk)
+                                         });
+            match copy_result {
+                Ok(0) => break,
+                Ok(copied_bytes) => offset += copied_bytes,
+                Err(error) => {
+                    let _ = self.remove(proc, &dest_path);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(dest_entry)
+    }
+
+    /**
+     * Moves the given file into the given directory.
+     *
+     * The file is copied and his old name removed, so it is allowed across
+     * different `TFileSystem`s too
+     */
+    pub fn move_file(&self,
+                     proc: &Process,
+                     file_entry: &VfsEntry,
+                     dest_dir_entry: &VfsEntry)
+                     -> VfsResult<VfsEntry> {
+        let dest_entry = self.copy_file(proc, file_entry, dest_dir_entry)?;
+        if let Err(error) = self.remove(proc, &file_entry.path().to_path_components()) {
+            let _ = self.remove(proc, &dest_entry.path().to_path_components());
+            return Err(error);
+        }
+
+        /* the moved file keeps his grants and timestamps */
+        let _ = dest_entry.node().update_obj_info(&file_entry.node().obj_info(None));
+        Ok(dest_entry)
+    }
+
+    /**
+     * Changes the working directory of the given `Process`
+     */
+    pub fn change_cwd(&self, proc: &Process, path: &[PathComponent]) -> VfsResult<()> {
+        let vfs_entry = self.resolve(proc, path, true)?;
+        if vfs_entry.node().obj_type() != ObjType::Dir {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a directory")));
+        }
+
+        *proc.cwd().lock() = vfs_entry.m_path;
+        Ok(())
+    }
+}
+
+impl Vfs /* Getters */ {
+    /**
+     * Returns the global `Vfs` instance
+     */
+    pub fn instance() -> &'static Self {
+        unsafe {
+            SM_VFS.as_ref().expect("Called Vfs::instance() before Vfs::init_instance()")
+        }
+    }
+
+    /**
+     * Returns whether the root filesystem is mounted
+     */
+    pub fn has_root_mount(&self) -> bool {
+        self.m_mounts.read().iter().any(|mount| mount.mnt_path().is_root())
+    }
+}
+
+impl Vfs /* Privates */ {
+    /**
+     * Resolves the parent directory of the last component of the given
+     * path, which must be a name
+     */
+    fn resolve_parent<'a>(&self,
+                          proc: &Process,
+                          path: &'a [PathComponent])
+                          -> VfsResult<(VfsEntry, &'a str)> {
+        let (name, parent_path) = match path.split_last() {
+            Some((PathComponent::ObjectName(name), parent_path)) => (name, parent_path),
+            _ => return Err((OsErrorClass::InvalidArgument, Some("Path without a name")))
+        };
+        validate_name(name)?;
+
+        /* a single name is relative to the working directory */
+        let parent_entry = if parent_path.is_empty() {
+            self.resolve(proc, &[PathComponent::SelfLink], true)?
+        } else {
+            self.resolve(proc, parent_path, true)?
+        };
+        if parent_entry.node().obj_type() != ObjType::Dir {
+            return Err((OsErrorClass::TypesNotMatch, Some("Parent is not a directory")));
+        }
+
+        Ok((parent_entry, name.as_str()))
+    }
+
+    /**
+     * Returns whether the given name of the given directory is covered by a
+     * `Mount` visible by the `Process`
+     */
+    fn is_mount_point_visible_by(&self,
+                                 proc: &Process,
+                                 dir_path: &VfsPath,
+                                 name: &str)
+                                 -> bool {
+        let mut child_path = dir_path.clone();
+        child_path.push_name(name).is_ok()
+        && self.m_mounts
+               .read()
+               .iter()
+               .any(|mount| *mount.mnt_path() == child_path && mount.is_visible_by(proc))
+    }
+
+    /**
+     * Walks the given path component by component.
+     *
+     * On failure returns the index of the last component of the given path
+     * which exists, if any
+     */
+    fn walk(&self,
+            proc: &Process,
+            path: &[PathComponent],
+            follow_last_link: bool)
+            -> WalkResult {
+        /* snapshot the visible mounts, the last mounted covers the previous */
+        let visible_mounts: Vec<Arc<Mount>> =
+            self.m_mounts
+                .read()
+                .iter()
+                .filter(|mount| mount.is_visible_by(proc))
+                .cloned()
+                .collect();
+        let mount_at = |vfs_path: &VfsPath| {
+            visible_mounts.iter().rev().find(|mount| mount.mnt_path() == vfs_path)
+        };
+
+        let root_node = match mount_at(&VfsPath::root()) {
+            Some(root_mount) => root_mount.file_system().root_node(),
+            None => {
+                return Err((None,
+                            (OsErrorClass::ReferenceNotFound,
+                             Some("No root filesystem"))))
+            },
+        };
+
+        /* each queued component remembers his index into the given path, the
+         * components which come from the links have no index
+         */
+        let mut components: VecDeque<(PathComponent, Option<usize>)> =
+            path.iter()
+                .enumerate()
+                .map(|(index, path_component)| (path_component.clone(), Some(index)))
+                .collect();
+
+        /* relative paths starts from the working directory */
+        if !path[0].is_root() {
+            let cwd = proc.cwd().lock().clone();
+            for name in cwd.names().iter().rev() {
+                components.push_front((PathComponent::ObjectName(name.clone()), None));
+            }
+        }
+
+        let mut nodes_stack = vec![root_node];
+        let mut vfs_path = VfsPath::root();
+        let mut last_existing_index = None;
+        let mut links_followed = 0;
+        while let Some((path_component, path_index)) = components.pop_front() {
+            match path_component {
+                PathComponent::Root => {
+                    nodes_stack.truncate(1);
+                    vfs_path = VfsPath::root();
+                },
+                PathComponent::SelfLink => { /* nothing to do */ },
+                PathComponent::ParentLink => {
+                    /* the parent of the root is the root itself */
+                    if nodes_stack.len() > 1 {
+                        nodes_stack.pop();
+                        vfs_path.pop_name();
+                    }
+                },
+                PathComponent::ObjectName(name) => {
+                    let dir_node = nodes_stack.last().unwrap();
+                    if dir_node.obj_type() != ObjType::Dir {
+                        return Err((last_existing_index,
+                                    (OsErrorClass::TypesNotMatch,
+                                     Some("Not a directory"))));
+                    }
+
+                    let mut node =
+                        dir_node.lookup(&name)
+                                .map_err(|error| (last_existing_index, error))?;
+                    vfs_path.push_name(&name)
+                            .map_err(|error| (last_existing_index, error))?;
+
+                    /* cross into the filesystem mounted here */
+                    if let Some(mount) = mount_at(&vfs_path) {
+                        node = mount.file_system().root_node();
+                    }
+
+                    /* the links are substituted by their target */
+                    if node.obj_type() == ObjType::Link
+                       && (follow_last_link || !components.is_empty())
+                    {
+                        links_followed += 1;
+                        if links_followed > Self::LINKS_FOLLOWED_MAX {
+                            return Err((last_existing_index,
+                                        (OsErrorClass::LimitReached,
+                                         Some("Too many links followed"))));
+                        }
+
+                        let link_target =
+                            node.link_target()
+                                .map_err(|error| (last_existing_index, error))?;
+                        vfs_path.pop_name();
+                        for target_component in link_target.into_iter().rev() {
+                            components.push_front((target_component, None));
+                        }
+                    } else {
+                        nodes_stack.push(node);
+                    }
+                }
+            }
+
+            if path_index.is_some() {
+                last_existing_index = path_index;
+            }
+        }
+
+        Ok(VfsEntry { m_path: vfs_path,
+                      m_node: nodes_stack.pop().unwrap() })
+    }
+}
+
+/**
+ * Node resolved by the `Vfs` with his canonical `VfsPath`
+ */
+#[derive(Clone)]
+pub struct VfsEntry {
+    m_path: VfsPath,
+    m_node: Arc<dyn TVfsNode>
+}
+
+impl VfsEntry /* Getters */ {
+    /**
+     * Returns the canonical `VfsPath` of the node
+     */
+    pub fn path(&self) -> &VfsPath {
+        &self.m_path
+    }
+
+    /**
+     * Returns the resolved node
+     */
+    pub fn node(&self) -> &Arc<dyn TVfsNode> {
+        &self.m_node
+    }
+}
@@ -0,0 +1,93 @@
+/*! VFS mount points */
+
+use alloc::sync::Arc;
+
+use api_data::task::{
+    modes::FsMountMode,
+    TaskId
+};
+
+use crate::{
+    fs::vfs::{
+        node::TFileSystem,
+        path::VfsPath
+    },
+    task::process::Process
+};
+
+/**
+ * Filesystem instance connected to a directory of the `Vfs`.
+ *
+ * The `FsMountMode` restricts the `Process`es which could see it, the
+ * others continue to see the covered directory
+ */
+pub struct Mount {
+    m_mnt_path: VfsPath,
+    m_file_system: Arc<dyn TFileSystem>,
+    m_mnt_mode: FsMountMode,
+    m_owner_proc_id: TaskId,
+    m_owner_session_id: TaskId
+}
+
+impl Mount /* Constructors */ {
+    /**
+     * Constructs a `Mount` performed by the given `Process`
+     */
+    pub fn new(mnt_path: VfsPath,
+               file_system: Arc<dyn TFileSystem>,
+               mnt_mode: FsMountMode,
+               owner_proc: &Process)
+               -> Self {
+        Self { m_mnt_path: mnt_path,
+               m_file_system: file_system,
+               m_mnt_mode: mnt_mode,
+               m_owner_proc_id: owner_proc.id(),
+               m_owner_session_id: owner_proc.session_id() }
+    }
+}
+
+impl Mount /* Methods */ {
+    /**
+     * Returns whether the given `Process` could see this `Mount`
+     */
+    pub fn is_visible_by(&self, proc: &Process) -> bool {
+        match self.m_mnt_mode {
+            FsMountMode::OsGlobal => true,
+            FsMountMode::SessionGlobal => proc.session_id() == self.m_owner_session_id,
+            FsMountMode::ChildInheritable => {
+                proc.is_or_descends_from(self.m_owner_proc_id)
+            },
+            FsMountMode::PrivateToProc => proc.id() == self.m_owner_proc_id
+        }
+    }
+}
+
+impl Mount /* Getters */ {
+    /**
+     * Returns the `VfsPath` of the covered directory
+     */
+    pub fn mnt_path(&self) -> &VfsPath {
+        &self.m_mnt_path
+    }
+
+    /**
+     * Returns the mounted `TFileSystem`
+     */
+    pub fn file_system(&self) -> &Arc<dyn TFileSystem> {
+        &self.m_file_system
+    }
+
+    /**
+     * Returns the `FsMountMode` given at mount time
+     */
+    pub fn mnt_mode(&self) -> FsMountMode {
+        self.m_mnt_mode
+    }
+
+    /**
+     * Returns the `TaskId` of the `Process` which performed the mount
+     */
+    pub fn owner_proc_id(&self) -> TaskId {
+        self.m_owner_proc_id
+    }
+}
@@ -0,0 +1,163 @@
+/*! VFS filesystem interfaces */
+
+use alloc::{
+    sync::Arc,
+    vec::Vec
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        dir::DirEntry,
+        info::RawObjInfo,
+        types::ObjType
+    },
+    path::PathComponent
+};
+
+use crate::{
+    dev::TDevice,
+    fs::vfs::VfsResult
+};
+
+/**
+ * Interface implemented by each filesystem instance which could be
+ * mounted into the `Vfs`
+ */
+pub trait TFileSystem: Send + Sync {
+    /**
+     * Returns the name of the filesystem driver
+     */
+    fn name(&self) -> &'static str;
+
+    /**
+     * Returns the root directory of this filesystem instance
+     */
+    fn root_node(&self) -> Arc<dyn TVfsNode>;
+
+    /**
+     * Returns whether the filesystem refuses any modification
+     */
+    fn is_read_only(&self) -> bool {
+        false
+    }
+}
+
+/**
+ * Interface implemented by each node (directory, file or link) of a
+ * `TFileSystem`.
+ *
+ * The default implementations refuse the operation, so each node type
+ * implements only what it supports
+ */
+pub trait TVfsNode: Send + Sync {
+    /**
+     * Returns the `ObjType` of this node
+     */
+    fn obj_type(&self) -> ObjType;
+
+    /**
+     * Returns the identifier of this node, unique into his filesystem
+     */
+    fn node_id(&self) -> u64;
+
+    /**
+     * Returns the `RawObjInfo` metadata of this node, the name is given by
+     * the caller since it belongs to the parent directory
+     */
+    fn obj_info(&self, name: Option<&str>) -> RawObjInfo;
+
+    /**
+     * Updates the grants and the timestamps of this node with the given
+     * `RawObjInfo`
+     */
+    fn update_obj_info(&self, _raw_obj_info: &RawObjInfo) -> VfsResult<()> {
+        Err((OsErrorClass::OperationNotEnabled, Some("Read-only metadata")))
+    }
+
+    /**
+     * Returns the child with the given name of this directory
+     */
+    fn lookup(&self, _name: &str) -> VfsResult<Arc<dyn TVfsNode>> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+    }
+
+    /**
+     * Creates into this directory a new empty child of the given
+     * `ObjType`
+     */
+    fn create_child(&self,
+                    _name: &str,
+                    _obj_type: ObjType)
+                    -> VfsResult<Arc<dyn TVfsNode>> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+    }
+
+    /**
+     * Removes the child with the given name from this directory
+     */
+    fn remove_child(&self, _name: &str) -> VfsResult<()> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+    }
+
+    /**
+     * Returns the `DirEntry` of the child at the given position of this
+     * directory, `None` when the children are finished
+     */
+    fn child_at(&self, _index: usize) -> VfsResult<Option<DirEntry>> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a directory")))
+    }
+
+    /**
+     * Reads the data of this file starting from the given offset.
+     *
+     * Returns the amount of bytes read
+     */
+    fn read_at(&self, _offset: usize, _buffer: &mut [u8]) -> VfsResult<usize> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+    }
+
+    /**
+     * Writes the data of this file starting from the given offset.
+     *
+     * Returns the amount of bytes written
+     */
+    fn write_at(&self, _offset: usize, _buffer: &[u8]) -> VfsResult<usize> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+    }
+
+    /**
+     * Returns the size in bytes of the data of this file
+     */
+    fn data_size(&self) -> usize {
+        0
+    }
+
+    /**
+     * Truncates or extends with zeroes the data of this file
+     */
+    fn set_data_size(&self, _data_size: usize) -> VfsResult<()> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a file")))
+    }
+
+    /**
+     * Returns the path referenced by this link
+     */
+    fn link_target(&self) -> VfsResult<Vec<PathComponent>> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a link")))
+    }
+
+    /**
+     * Binds this link to the given path
+     */
+    fn set_link_target(&self, _target: &[PathComponent]) -> VfsResult<()> {
+        Err((OsErrorClass::TypesNotMatch, Some("Not a link")))
+    }
+
+    /**
+     * Returns the device driver referenced by this device node
+     */
+    fn device(&self) -> Option<Arc<dyn TDevice>> {
+        None
+    }
+}
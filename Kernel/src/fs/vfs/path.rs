@@ -0,0 +1,190 @@
+/*! VFS canonical paths */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+
+use core::fmt;
+
+use api_data::{
+    error::class::OsErrorClass,
+    limit::{
+        VFS_NAME_LEN_MAX,
+        VFS_PATH_LEN_MAX
+    },
+    path::PathComponent
+};
+
+use crate::fs::vfs::VfsResult;
+
+/**
+ * Absolute path without links, self-links and parent-links.
+ *
+ * It is the path followed by the `Vfs` to reach a node from the root,
+ * mount points included
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Eq, PartialEq)]
+pub struct VfsPath {
+    m_names: Vec<String>,
+    m_str_len: usize
+}
+
+impl VfsPath /* Constructors */ {
+    /**
+     * Constructs the `VfsPath` of the root directory
+     */
+    pub const fn root() -> Self {
+        Self { m_names: Vec::new(),
+               m_str_len: PathComponent::SEPARATOR.len() }
+    }
+}
+
+impl VfsPath /* Methods */ {
+    /**
+     * Appends the given name, which is validated against the VFS limits
+     */
+    pub fn push_name(&mut self, name: &str) -> VfsResult<()> {
+        validate_name(name)?;
+
+        /* the root separator is shared with the first name */
+        let str_len = if self.is_root() {
+            self.m_str_len + name.len()
+        } else {
+            self.m_str_len + PathComponent::SEPARATOR.len() + name.len()
+        };
+        if str_len > VFS_PATH_LEN_MAX {
+            return Err((OsErrorClass::LimitOverflow, Some("VFS path too long")));
+        }
+
+        self.m_names.push(String::from(name));
+        self.m_str_len = str_len;
+        Ok(())
+    }
+
+    /**
+     * Removes the last name, the root has no names to remove
+     */
+    pub fn pop_name(&mut self) -> Option<String> {
+        let name = self.m_names.pop()?;
+        if self.is_root() {
+            self.m_str_len = PathComponent::SEPARATOR.len();
+        } else {
+            self.m_str_len -= PathComponent::SEPARATOR.len() + name.len();
+        }
+        Some(name)
+    }
+}
+
+impl VfsPath /* Getters */ {
+    /**
+     * Returns the names from the root to the referenced node
+     */
+    pub fn names(&self) -> &[String] {
+        self.m_names.as_slice()
+    }
+
+    /**
+     * Returns the last name, `None` for the root
+     */
+    pub fn last_name(&self) -> Option<&str> {
+        self.m_names.last().map(|name| name.as_str())
+    }
+
+    /**
+     * Returns the length in bytes of the `String` representation
+     */
+    pub fn len(&self) -> usize {
+        self.m_str_len
+    }
+
+    /**
+     * Returns whether this is the `VfsPath` of the root directory
+     */
+    pub fn is_root(&self) -> bool {
+        self.m_names.is_empty()
+    }
+
+    /**
+     * Returns whether `self` is `other` or one of his descendants
+     */
+    pub fn starts_with(&self, other: &Self) -> bool {
+        self.m_names.starts_with(&other.m_names)
+    }
+
+    /**
+     * Returns the absolute `PathComponent`s which reach the same node
+     */
+    pub fn to_path_components(&self) -> Vec<PathComponent> {
+        let mut path_components = vec![PathComponent::Root];
+        path_components.extend(self.m_names
+                                   .iter()
+                                   .map(|name| PathComponent::ObjectName(name.clone())));
+        path_components
+    }
+}
+
+impl Default for VfsPath {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
+impl fmt::Display for VfsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            write!(f, "{}", PathComponent::SEPARATOR)
+        } else {
+            for name in self.m_names.iter() {
+                write!(f, "{}{}", PathComponent::SEPARATOR, name)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/**
+ * Splits the given string path into `PathComponent`s, as the userspace
+ * `Path` does.
+ *
+ * Fails when the path or one of his names exceeds the VFS limits
+ */
+pub fn parse_str_path(str_path: &str) -> VfsResult<Vec<PathComponent>> {
+    if str_path.len() > VFS_PATH_LEN_MAX {
+        return Err((OsErrorClass::LimitOverflow, Some("VFS path too long")));
+    }
+
+    let mut path_components = Vec::new();
+    if str_path.starts_with(PathComponent::SEPARATOR) {
+        path_components.push(PathComponent::Root);
+    }
+    for str_path_component in str_path.split(PathComponent::SEPARATOR)
+                                      .filter(|str_component| !str_component.is_empty())
+    {
+        if str_path_component.len() > VFS_NAME_LEN_MAX {
+            return Err((OsErrorClass::LimitOverflow, Some("VFS name too long")));
+        }
+        path_components.push(PathComponent::from(str_path_component));
+    }
+    Ok(path_components)
+}
+
+/**
+ * Validates the given name of a filesystem node
+ */
+pub fn validate_name(name: &str) -> VfsResult<()> {
+    if name.is_empty()
+       || name.contains(PathComponent::SEPARATOR)
+       || name == PathComponent::SELF_LINK
+       || name == PathComponent::PARENT_LINK
+       || name.contains('\0')
+    {
+        Err((OsErrorClass::InvalidArgument, Some("Invalid VFS name")))
+    } else if name.len() > VFS_NAME_LEN_MAX {
+        Err((OsErrorClass::LimitOverflow, Some("VFS name too long")))
+    } else {
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ use crate::{
     dbg_print::DbgLevel,
     dbg_println,
     vm::{
+        mem_manager::MemManager,
         Page4KiB,
         TPageSize
     }
@@ -70,8 +71,10 @@ fn kernel_heap_mem_supplier(requested_size: usize) -> Option<(NonNull<u8>, usize
         if C_ETERNAL_POOL_SIZE / Page4KiB::SIZE
            < SM_ETERNAL_POOL_USED_PAGES + requested_pages
         {
-            None /* TODO allocate a new kernel region
-                  *      MemManager::instance().allocate_kernel_region(...) */
+            /* the regions are available after the MemManager init */
+            let region_virt_addr =
+                MemManager::try_instance()?.allocate_kernel_region(requested_pages)?;
+            Some((NonNull::new_unchecked(region_virt_addr.as_ptr_mut()), requested_size))
         } else {
             let eternal_pool_used_size = SM_ETERNAL_POOL_USED_PAGES * Page4KiB::SIZE;
 
@@ -20,18 +20,48 @@
 #[macro_use]
 extern crate alloc;
 
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+
+use api_data::{
+    object::device::DeviceIdClass,
+    path::PathComponent,
+    task::modes::FsMountMode
+};
 use symbols::code_symbols::CodeSymbols;
 
 use crate::{
     boot_info::BootInfo,
     dbg_print::{
         dbg_print_init,
+        dbg_print_init_fb_console,
         DbgLevel
     },
-    dev::DevManager,
+    dev::{
+        storage::StorageBlockDevice,
+        DevManager
+    },
+    fs::{
+        dev_fs::mount_dev_fs,
+        initrd::unpack_boot_modules,
+        iso_fs::mount_boot_cd,
+        sysroot::load_embedded_sysroot,
+        tmpfs::TmpFs,
+        vfs::Vfs
+    },
     heap::kernel_heap_init_eternal_pool,
+    net::{
+        NetConfig,
+        NetStack
+    },
     processor::Processor,
-    task::scheduler::Scheduler,
+    sys::KernFnTable,
+    task::{
+        loader::spawn_init_proc,
+        scheduler::Scheduler
+    },
     version::KERNEL_VERSION,
     vm::mem_manager::MemManager
 };
@@ -41,9 +71,15 @@ mod arch;
 mod boot_info;
 mod dbg_print;
 mod dev;
+mod entity;
+mod fb_console;
+mod fs;
 mod heap;
+mod net;
+mod object;
 mod panic;
 mod processor;
+mod sys;
 mod task;
 mod version;
 mod vm;
@@ -87,22 +123,108 @@ pub extern "C" fn bsp_rust_start(raw_boot_info_ptr: *const u8) -> ! {
     dbg_println!(DbgLevel::Info, "Initializing Memory Management...");
     MemManager::init_instance();
 
+    /* the framebuffer is mapped into the kernel regions */
+    dbg_println!(DbgLevel::Info, "Initializing Framebuffer...");
+    if DevManager::instance().init_framebuffer() {
+        /* from now the debug messages are printed on the screen too */
+        dbg_print_init_fb_console();
+    } else {
+        dbg_println!(DbgLevel::Warn, "No linear framebuffer available");
+    }
+
     /* initialize the interrupts for this CPU */
     dbg_println!(DbgLevel::Info, "Initializing Interrupts Management...");
     Processor::instance_mut().init_interrupts_for_bsp();
 
-    /* starting Symmetric Multi Processor */
+    /* the input devices need the interrupts to be initialized */
+    dbg_println!(DbgLevel::Info, "Initializing Input Devices...");
+    DevManager::instance().register_input_devices();
+
+    /* initialize the task scheduler */
+    dbg_println!(DbgLevel::Info, "Initializing Task Scheduler...");
+    Scheduler::init_instance();
+
+    /* initialize the virtual filesystem, still without mounts */
+    dbg_println!(DbgLevel::Info, "Initializing Virtual File System...");
+    Vfs::init_instance();
+
+    /* mount as root a tmpfs populated with the embedded sysroot */
+    dbg_println!(DbgLevel::Info, "Mounting Root Temporary File System...");
+    {
+        let kern_proc = Scheduler::instance().kern_proc();
+        Vfs::instance().mount(kern_proc,
+                              Arc::new(TmpFs::new()),
+                              &[PathComponent::Root],
+                              FsMountMode::OsGlobal)
+                       .expect("Failed to mount the root tmpfs");
+        load_embedded_sysroot(kern_proc).expect("Failed to load the embedded sysroot");
+
+        /* the initrd modules given by the bootloader extend the sysroot */
+        unpack_boot_modules(kern_proc);
+    }
+
+    /* enumerate the PCI bus, the matching drivers register their devices */
+    dbg_println!(DbgLevel::Info, "Initializing PCI Bus...");
+    DevManager::instance().init_pci_bus();
+
+    /* expose all the drivers into /Devices and mount the boot CD-ROM */
+    dbg_println!(DbgLevel::Info, "Initializing Storage Devices...");
+    {
+        let kern_proc = Scheduler::instance().kern_proc();
+        mount_dev_fs(kern_proc).expect("Failed to mount the devfs");
+
+        /* the first CD-ROM which contains an ISO9660 volume is the boot one */
+        let storage_devices =
+            DevManager::instance().enumerate_by_class(DeviceIdClass::Storage)
+                                  .unwrap_or_default();
+        for storage_device in storage_devices {
+            if let Some(block_device) = StorageBlockDevice::new(storage_device, false) {
+                if mount_boot_cd(kern_proc, Box::new(block_device)).is_ok() {
+                    dbg_println!(DbgLevel::Info, "Mounted Boot CD-ROM");
+                    break;
+                }
+            }
+        }
+    }
+
+    /* the static configuration is the one of the QEMU user-mode network */
+    dbg_println!(DbgLevel::Info, "Initializing Network Stack...");
+    if !NetStack::init_instance(NetConfig::new([10, 0, 2, 15],
+                                               [255, 255, 255, 0],
+                                               [10, 0, 2, 2]))
+    {
+        dbg_println!(DbgLevel::Warn, "No network devices available");
+    }
+
+    /* initialize the kernel routines callable from the user-space */
+    dbg_println!(DbgLevel::Info, "Initializing Kernel Function Calls...");
+    KernFnTable::init_instance();
+
+    /* starting Symmetric Multi Processor, the APs join the scheduler */
     if Processor::instance().cores_count() > 1 {
         dbg_println!(DbgLevel::Info,
                      "Starting Other {} SMP APs (total cores count: {})...",
                      Processor::instance().cores_count() - 1,
                      Processor::instance().cores_count());
-        //Processor::instance().start_smp();
+        Processor::instance_mut().start_smp();
+        dbg_println!(DbgLevel::Info,
+                     "Online SMP Cores: {}/{}",
+                     Processor::instance().online_cores_count(),
+                     Processor::instance().cores_count());
     }
 
-    /* initialize the task scheduler */
-    dbg_println!(DbgLevel::Info, "Initializing Task Scheduler...");
-    Scheduler::init_instance();
+    /* the first user process, which spawns the others */
+    dbg_println!(DbgLevel::Info, "Spawning Init Process...");
+    match spawn_init_proc() {
+        Ok(init_proc) => {
+            dbg_println!(DbgLevel::Info, "Init Process Spawned (id: {})", init_proc.id())
+        },
+        Err((_, err_msg)) => {
+            dbg_println!(DbgLevel::Warn,
+                         "Failed to spawn the init process: {}",
+                         err_msg.unwrap_or("unknown"))
+        }
+    }
 
     /* FIXME debug printing to remove */
     {
@@ -134,18 +256,28 @@ pub extern "C" fn bsp_rust_start(raw_boot_info_ptr: *const u8) -> ! {
         dbg_println!(DbgLevel::Trace,
                      "Interrupts are enabled: {}",
                      Processor::instance().this_core().are_interrupts_enabled());
-        unsafe {
-            asm!("int 33");
-        }
     }
 
-    panic!("TODO implement the remaining code");
+    /* become the idle thread of the BSP and start scheduling */
+    dbg_println!(DbgLevel::Info, "Starting Task Scheduling...");
+    Scheduler::instance().run_this_core()
 }
 
-pub extern "C" fn ap_rust_start() {
+/**
+ * Rust entry-point for the APs.
+ *
+ * Here is where each AP starts his execution when
+ * `Kernel/arch/<arch_name>/ap_trampoline.S` transfers the control to the
+ * Rust code, on the stack allocated by `Processor::start_smp()`
+ */
+#[no_mangle]
+pub extern "C" fn ap_rust_start() -> ! {
     /* initialize the CPU management for this AP */
     dbg_println!(DbgLevel::Info,
                  "Initializing AP{}...",
                  Processor::instance().this_core().id());
     Processor::instance_mut().init_this_ap();
+
+    /* become the idle thread of this AP and start scheduling */
+    Scheduler::instance().run_this_core()
 }
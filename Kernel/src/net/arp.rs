@@ -0,0 +1,112 @@
+/*! Address Resolution Protocol */
+
+use alloc::vec::Vec;
+
+use crate::net::{
+    ether::{
+        MacAddr,
+        ETHER_TYPE_IPV4
+    },
+    ipv4::Ipv4Addr,
+    read_be_u16
+};
+
+pub const ARP_OPER_REQUEST: u16 = 1;
+pub const ARP_OPER_REPLY: u16 = 2;
+
+/**
+ * Size of the ARP packets which map IPv4 addresses to ethernet ones
+ */
+pub const ARP_PACKET_SIZE: usize = 28;
+
+const ARP_HTYPE_ETHERNET: u16 = 1;
+
+/**
+ * Received ARP packet which maps an IPv4 address to an ethernet one
+ */
+pub struct ArpPacket {
+    m_oper: u16,
+    m_sender_mac: MacAddr,
+    m_sender_ip: Ipv4Addr,
+    m_target_ip: Ipv4Addr
+}
+
+impl ArpPacket /* Constructors */ {
+    /**
+     * Parses the given payload, `None` is returned when it doesn't map
+     * IPv4 addresses to ethernet ones
+     */
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        if payload.len() < ARP_PACKET_SIZE
+           || read_be_u16(payload, 0) != ARP_HTYPE_ETHERNET
+           || read_be_u16(payload, 2) != ETHER_TYPE_IPV4
+           || payload[4] != 6
+           || payload[5] != 4
+        {
+            return None;
+        }
+
+        let mut sender_mac = [0; 6];
+        let mut sender_ip = [0; 4];
+        let mut target_ip = [0; 4];
+        sender_mac.copy_from_slice(&payload[8..14]);
+        sender_ip.copy_from_slice(&payload[14..18]);
+        target_ip.copy_from_slice(&payload[24..28]);
+
+        Some(Self { m_oper: read_be_u16(payload, 6),
+                    m_sender_mac: sender_mac,
+                    m_sender_ip: sender_ip,
+                    m_target_ip: target_ip })
+    }
+}
+
+impl ArpPacket /* Getters */ {
+    /**
+     * Returns the operation, `ARP_OPER_REQUEST` or `ARP_OPER_REPLY`
+     */
+    pub fn oper(&self) -> u16 {
+        self.m_oper
+    }
+
+    /**
+     * Returns the hardware address of the sender
+     */
+    pub fn sender_mac(&self) -> MacAddr {
+        self.m_sender_mac
+    }
+
+    /**
+     * Returns the IPv4 address of the sender
+     */
+    pub fn sender_ip(&self) -> Ipv4Addr {
+        self.m_sender_ip
+    }
+
+    /**
+     * Returns the IPv4 address to resolve
+     */
+    pub fn target_ip(&self) -> Ipv4Addr {
+        self.m_target_ip
+    }
+}
+
+/**
+ * Returns a new ARP packet with the given operation and addresses
+ */
+pub fn build_arp_packet(oper: u16,
+                        sender_mac: MacAddr,
+                        sender_ip: Ipv4Addr,
+                        target_mac: MacAddr,
+                        target_ip: Ipv4Addr)
+                        -> Vec<u8> {
+    let mut packet = Vec::with_capacity(ARP_PACKET_SIZE);
+    packet.extend_from_slice(&ARP_HTYPE_ETHERNET.to_be_bytes());
+    packet.extend_from_slice(&ETHER_TYPE_IPV4.to_be_bytes());
+    packet.extend_from_slice(&[6, 4]);
+    packet.extend_from_slice(&oper.to_be_bytes());
+    packet.extend_from_slice(&sender_mac);
+    packet.extend_from_slice(&sender_ip);
+    packet.extend_from_slice(&target_mac);
+    packet.extend_from_slice(&target_ip);
+    packet
+}
@@ -0,0 +1,101 @@
+/*! Ethernet frames */
+
+use alloc::vec::Vec;
+
+use crate::net::read_be_u16;
+
+/**
+ * Hardware address of a network interface
+ */
+pub type MacAddr = [u8; 6];
+
+/**
+ * Destination of the frames received by all the interfaces
+ */
+pub const MAC_BROADCAST: MacAddr = [0xFF; 6];
+
+pub const ETHER_TYPE_IPV4: u16 = 0x0800;
+pub const ETHER_TYPE_ARP: u16 = 0x0806;
+
+/**
+ * Size of the header, the optional 802.1Q tag is not supported
+ */
+pub const ETHER_HEADER_SIZE: usize = 14;
+
+/**
+ * Received ethernet frame, borrows the receive buffer
+ */
+pub struct EtherFrame<'a> {
+    m_dst_mac: MacAddr,
+    m_src_mac: MacAddr,
+    m_ether_type: u16,
+    m_payload: &'a [u8]
+}
+
+impl<'a> EtherFrame<'a> /* Constructors */ {
+    /**
+     * Parses the given frame, `None` is returned when it is truncated
+     */
+    pub fn parse(frame: &'a [u8]) -> Option<Self> {
+        if frame.len() < ETHER_HEADER_SIZE {
+            return None;
+        }
+
+        let mut dst_mac = [0; 6];
+        let mut src_mac = [0; 6];
+        dst_mac.copy_from_slice(&frame[0..6]);
+        src_mac.copy_from_slice(&frame[6..12]);
+
+        Some(Self { m_dst_mac: dst_mac,
+                    m_src_mac: src_mac,
+                    m_ether_type: read_be_u16(frame, 12),
+                    m_payload: &frame[ETHER_HEADER_SIZE..] })
+    }
+}
+
+impl<'a> EtherFrame<'a> /* Getters */ {
+    /**
+     * Returns the hardware address of the receiver
+     */
+    pub fn dst_mac(&self) -> MacAddr {
+        self.m_dst_mac
+    }
+
+    /**
+     * Returns the hardware address of the sender
+     */
+    pub fn src_mac(&self) -> MacAddr {
+        self.m_src_mac
+    }
+
+    /**
+     * Returns the protocol of the payload
+     */
+    pub fn ether_type(&self) -> u16 {
+        self.m_ether_type
+    }
+
+    /**
+     * Returns the payload, which could contain the padding of the short
+     * frames
+     */
+    pub fn payload(&self) -> &'a [u8] {
+        self.m_payload
+    }
+}
+
+/**
+ * Returns a new ethernet frame which encapsulates the given payload
+ */
+pub fn build_ether_frame(dst_mac: MacAddr,
+                         src_mac: MacAddr,
+                         ether_type: u16,
+                         payload: &[u8])
+                         -> Vec<u8> {
+    let mut frame = Vec::with_capacity(ETHER_HEADER_SIZE + payload.len());
+    frame.extend_from_slice(&dst_mac);
+    frame.extend_from_slice(&src_mac);
+    frame.extend_from_slice(&ether_type.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
@@ -0,0 +1,34 @@
+/*! Internet Control Message Protocol */
+
+use alloc::vec::Vec;
+
+use crate::net::ipv4::ipv4_checksum;
+
+const ICMP_TYPE_ECHO_REPLY: u8 = 0;
+const ICMP_TYPE_ECHO_REQUEST: u8 = 8;
+
+const ICMP_HEADER_SIZE: usize = 8;
+
+/**
+ * Returns the echo reply for the given ICMP message, `None` is returned
+ * when it is not a valid echo request.
+ *
+ * The other messages are ignored
+ */
+pub fn icmp_echo_reply(message: &[u8]) -> Option<Vec<u8>> {
+    if message.len() < ICMP_HEADER_SIZE
+       || message[0] != ICMP_TYPE_ECHO_REQUEST
+       || ipv4_checksum(message, 0) != 0
+    {
+        return None;
+    }
+
+    /* the identifier, the sequence and the data are echoed back */
+    let mut reply = message.to_vec();
+    reply[0] = ICMP_TYPE_ECHO_REPLY;
+    reply[2..4].copy_from_slice(&[0, 0]);
+
+    let checksum = ipv4_checksum(&reply, 0);
+    reply[2..4].copy_from_slice(&checksum.to_be_bytes());
+    Some(reply)
+}
@@ -0,0 +1,214 @@
+/*! Internet Protocol version 4 */
+
+use alloc::vec::Vec;
+
+use crate::net::read_be_u16;
+
+/**
+ * Octets of an IPv4 address
+ */
+pub type Ipv4Addr = [u8; 4];
+
+/**
+ * Destination of the packets received by all the hosts of the network
+ */
+pub const IPV4_BROADCAST: Ipv4Addr = [0xFF; 4];
+
+pub const IPV4_PROTOCOL_ICMP: u8 = 1;
+pub const IPV4_PROTOCOL_TCP: u8 = 6;
+pub const IPV4_PROTOCOL_UDP: u8 = 17;
+
+/**
+ * Size of the header without options, which is the one sent
+ */
+pub const IPV4_HEADER_SIZE: usize = 20;
+
+const IPV4_TTL: u8 = 64;
+const IPV4_FLAG_DONT_FRAGMENT: u16 = 1 << 14;
+const IPV4_FLAG_MORE_FRAGMENTS: u16 = 1 << 13;
+const IPV4_FRAGMENT_OFFSET_MASK: u16 = 0x1FFF;
+
+/**
+ * Received IPv4 packet, borrows the receive buffer.
+ *
+ * The fragmented packets are not supported
+ */
+pub struct Ipv4Packet<'a> {
+    m_src_addr: Ipv4Addr,
+    m_dst_addr: Ipv4Addr,
+    m_protocol: u8,
+    m_payload: &'a [u8]
+}
+
+impl<'a> Ipv4Packet<'a> /* Constructors */ {
+    /**
+     * Parses and validates the given packet, `None` is returned when it is
+     * malformed or fragmented
+     */
+    pub fn parse(packet: &'a [u8]) -> Option<Self> {
+        if packet.len() < IPV4_HEADER_SIZE || packet[0] >> 4 != 4 {
+            return None;
+        }
+
+        /* the ethernet padding follows the total length */
+        let header_size = (packet[0] & 0xF) as usize * 4;
+        let total_len = read_be_u16(packet, 2) as usize;
+        if header_size < IPV4_HEADER_SIZE
+           || total_len < header_size
+           || total_len > packet.len()
+           || ipv4_checksum(&packet[..header_size], 0) != 0
+        {
+            return None;
+        }
+
+        let fragment_bits = read_be_u16(packet, 6);
+        if fragment_bits & (IPV4_FLAG_MORE_FRAGMENTS | IPV4_FRAGMENT_OFFSET_MASK) != 0 {
+            return None;
+        }
+
+        let mut src_addr = [0; 4];
+        let mut dst_addr = [0; 4];
+        src_addr.copy_from_slice(&packet[12..16]);
+        dst_addr.copy_from_slice(&packet[16..20]);
+
+        Some(Self { m_src_addr: src_addr,
+                    m_dst_addr: dst_addr,
+                    m_protocol: packet[9],
+                    m_payload: &packet[header_size..total_len] })
+    }
+}
+
+impl<'a> Ipv4Packet<'a> /* Getters */ {
+    /**
+     * Returns the address of the sender
+     */
+    pub fn src_addr(&self) -> Ipv4Addr {
+        self.m_src_addr
+    }
+
+    /**
+     * Returns the address of the receiver
+     */
+    pub fn dst_addr(&self) -> Ipv4Addr {
+        self.m_dst_addr
+    }
+
+    /**
+     * Returns the protocol of the payload
+     */
+    pub fn protocol(&self) -> u8 {
+        self.m_protocol
+    }
+
+    /**
+     * Returns the payload without the padding
+     */
+    pub fn payload(&self) -> &'a [u8] {
+        self.m_payload
+    }
+}
+
+/**
+ * Transport payload waiting to be encapsulated into an IPv4 packet and
+ * sent
+ */
+pub struct Ipv4Outgoing {
+    m_dst_addr: Ipv4Addr,
+    m_protocol: u8,
+    m_payload: Vec<u8>
+}
+
+impl Ipv4Outgoing /* Constructors */ {
+    /**
+     * Constructs an `Ipv4Outgoing` from the given parameters
+     */
+    pub fn new(dst_addr: Ipv4Addr, protocol: u8, payload: Vec<u8>) -> Self {
+        Self { m_dst_addr: dst_addr,
+               m_protocol: protocol,
+               m_payload: payload }
+    }
+}
+
+impl Ipv4Outgoing /* Getters */ {
+    /**
+     * Returns the address of the receiver
+     */
+    pub fn dst_addr(&self) -> Ipv4Addr {
+        self.m_dst_addr
+    }
+
+    /**
+     * Returns the protocol of the payload
+     */
+    pub fn protocol(&self) -> u8 {
+        self.m_protocol
+    }
+
+    /**
+     * Returns the transport payload
+     */
+    pub fn payload(&self) -> &[u8] {
+        &self.m_payload
+    }
+}
+
+/**
+ * Returns a new not fragmentable IPv4 packet which encapsulates the given
+ * payload
+ */
+pub fn build_ipv4_packet(src_addr: Ipv4Addr,
+                         dst_addr: Ipv4Addr,
+                         protocol: u8,
+                         identification: u16,
+                         payload: &[u8])
+                         -> Vec<u8> {
+    let total_len = (IPV4_HEADER_SIZE + payload.len()) as u16;
+
+    let mut packet = Vec::with_capacity(total_len as usize);
+    packet.extend_from_slice(&[0x45, 0]);
+    packet.extend_from_slice(&total_len.to_be_bytes());
+    packet.extend_from_slice(&identification.to_be_bytes());
+    packet.extend_from_slice(&IPV4_FLAG_DONT_FRAGMENT.to_be_bytes());
+    packet.extend_from_slice(&[IPV4_TTL, protocol, 0, 0]);
+    packet.extend_from_slice(&src_addr);
+    packet.extend_from_slice(&dst_addr);
+
+    let checksum = ipv4_checksum(&packet, 0);
+    packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/**
+ * Returns the internet checksum of the given data, which continues the
+ * given partial sum.
+ *
+ * The checksum of data which already contains his checksum is `0`
+ */
+pub fn ipv4_checksum(data: &[u8], partial_sum: u32) -> u16 {
+    let mut sum = data.chunks(2).fold(partial_sum, |sum, word| {
+                                    let high = (word[0] as u32) << 8;
+                                    let low = word.get(1).copied().unwrap_or(0) as u32;
+                                    sum.wrapping_add(high | low)
+                                });
+
+    /* fold the carries into the lower 16 bits */
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/**
+ * Returns the partial sum of the pseudo-header which the TCP and UDP
+ * checksums cover
+ */
+pub fn pseudo_header_sum(src_addr: Ipv4Addr,
+                         dst_addr: Ipv4Addr,
+                         protocol: u8,
+                         len: usize)
+                         -> u32 {
+    let addr_sum =
+        |addr: Ipv4Addr| read_be_u16(&addr, 0) as u32 + read_be_u16(&addr, 2) as u32;
+    addr_sum(src_addr) + addr_sum(dst_addr) + protocol as u32 + len as u32
+}
@@ -0,0 +1,1204 @@
+/*! Kernel network stack
+ *
+ * Minimal IPv4 stack with ARP, ICMP echo, UDP and TCP which drives the
+ * first registered `TNetDevice`.
+ *
+ * The received frames are processed by a kernel thread woken up by the
+ * timer, so no protocol code runs into the interrupt handlers, while the
+ * socket operations park their threads until the socket is ready
+ */
+
+use alloc::{
+    collections::BTreeMap,
+    sync::Arc,
+    vec::Vec
+};
+use core::mem;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass
+        },
+        modes::ObjRecvMode,
+        socket::{
+            SocketAddr,
+            SocketType
+        }
+    }
+};
+use sync::SpinMutex;
+
+use crate::{
+    dbg_println,
+    dev::{
+        net::TNetDevice,
+        DevManager,
+        TDevice
+    },
+    net::{
+        arp::{
+            build_arp_packet,
+            ArpPacket,
+            ARP_OPER_REPLY,
+            ARP_OPER_REQUEST
+        },
+        ether::{
+            build_ether_frame,
+            EtherFrame,
+            MacAddr,
+            ETHER_HEADER_SIZE,
+            ETHER_TYPE_ARP,
+            ETHER_TYPE_IPV4,
+            MAC_BROADCAST
+        },
+        icmp::icmp_echo_reply,
+        ipv4::{
+            build_ipv4_packet,
+            Ipv4Addr,
+            Ipv4Outgoing,
+            Ipv4Packet,
+            IPV4_BROADCAST,
+            IPV4_HEADER_SIZE,
+            IPV4_PROTOCOL_ICMP,
+            IPV4_PROTOCOL_TCP,
+            IPV4_PROTOCOL_UDP
+        },
+        tcp::{
+            tcp_reset_reply,
+            TcpSegment,
+            TcpSocket,
+            TcpState
+        },
+        udp::{
+            UdpDatagram,
+            UdpSocket,
+            UDP_HEADER_SIZE
+        }
+    },
+    processor::Processor,
+    task::{
+        scheduler::{
+            SchedPolicy,
+            SchedPrio,
+            Scheduler
+        },
+        thread::Thread
+    },
+    DbgLevel
+};
+
+pub mod arp;
+pub mod ether;
+pub mod icmp;
+pub mod ipv4;
+pub mod tcp;
+pub mod udp;
+
+/* <None> until <NetStack::init_instance()> finds a network device */
+static mut SM_NET_STACK: Option<NetStack> = None;
+
+/**
+ * Result of the `NetStack` operations
+ */
+pub type NetResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Static IPv4 configuration of the network interface
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct NetConfig {
+    m_ip_addr: Ipv4Addr,
+    m_netmask: Ipv4Addr,
+    m_gateway: Ipv4Addr
+}
+
+impl NetConfig /* Constructors */ {
+    /**
+     * Constructs a `NetConfig` from the given parameters
+     */
+    pub const fn new(ip_addr: Ipv4Addr, netmask: Ipv4Addr, gateway: Ipv4Addr) -> Self {
+        Self { m_ip_addr: ip_addr,
+               m_netmask: netmask,
+               m_gateway: gateway }
+    }
+}
+
+impl NetConfig /* Getters */ {
+    /**
+     * Returns the address of the interface
+     */
+    pub fn ip_addr(&self) -> Ipv4Addr {
+        self.m_ip_addr
+    }
+
+    /**
+     * Returns the mask of the local network
+     */
+    pub fn netmask(&self) -> Ipv4Addr {
+        self.m_netmask
+    }
+
+    /**
+     * Returns the router of the packets outside the local network
+     */
+    pub fn gateway(&self) -> Ipv4Addr {
+        self.m_gateway
+    }
+
+    /**
+     * Returns the host which receives the frames for the given address,
+     * which is the gateway for the addresses outside the local network
+     */
+    pub fn next_hop(&self, dst_addr: Ipv4Addr) -> Ipv4Addr {
+        let is_local = (0..4).all(|i| {
+                                 dst_addr[i] & self.m_netmask[i]
+                                 == self.m_ip_addr[i] & self.m_netmask[i]
+                             });
+        if is_local {
+            dst_addr
+        } else {
+            self.m_gateway
+        }
+    }
+}
+
+/**
+ * Kernel network stack, the sockets are referenced by identifiers
+ */
+pub struct NetStack {
+    m_device: Arc<dyn TDevice>,
+    m_poller_thread: Arc<Thread>,
+    m_state: SpinMutex<NetStackState>
+}
+
+impl NetStack /* Constructors */ {
+    /**
+     * Initializes the global `NetStack` instance with the first registered
+     * `TNetDevice` and the given `NetConfig`.
+     *
+     * Returns `false` when there are no network devices
+     */
+    pub fn init_instance(net_config: NetConfig) -> bool {
+        let net_device =
+            DevManager::instance().enumerate_by_class(DeviceIdClass::Network)
+                                  .unwrap_or_default()
+                                  .into_iter()
+                                  .find(|device| device.as_net().is_some());
+        let net_device = match net_device {
+            Some(net_device) => net_device,
+            None => return false
+        };
+
+        let mac_addr = net_device.as_net().unwrap().mac_addr();
+        let poller_thread = Thread::new_kernel(Scheduler::instance().kern_proc().clone(),
+                                               Self::poller_thread_entry,
+                                               0,
+                                               SchedPolicy::RoundRobin(SchedPrio::High));
+        unsafe {
+            SM_NET_STACK =
+                Some(Self { m_device: net_device,
+                            m_poller_thread: poller_thread.clone(),
+                            m_state:
+                                SpinMutex::const_new(NetStackState::new(net_config,
+                                                                        mac_addr)) });
+        }
+
+        /* the time-sharing class always admits the threads */
+        Scheduler::instance().add_thread(poller_thread);
+        true
+    }
+}
+
+impl NetStack /* Methods */ {
+    /**
+     * Opens a new unbound socket of the given `SocketType` and returns his
+     * identifier
+     */
+    pub fn open_socket(&self, socket_type: SocketType) -> NetResult<usize> {
+        self.with_state(|state| {
+                if state.m_sockets.len() >= NetStackState::SOCKETS_MAX {
+                    return Err((OsErrorClass::LimitReached, Some("Too many sockets")));
+                }
+
+                let socket_id = state.m_next_socket_id;
+                state.m_next_socket_id += 1;
+
+                let net_socket = match socket_type {
+                    SocketType::Udp => NetSocket::Udp(UdpSocket::new()),
+                    SocketType::Tcp => NetSocket::Tcp(TcpSocket::new())
+                };
+                state.m_sockets.insert(socket_id, net_socket);
+                Ok(socket_id)
+            })
+    }
+
+    /**
+     * Closes the given socket, the TCP connections are gracefully closed in
+     * background
+     */
+    pub fn close_socket(&self, socket_id: usize) {
+        let now = Scheduler::instance().ticks();
+        self.with_state(|state| state.close_socket(socket_id, now));
+    }
+
+    /**
+     * Binds the given socket to the given local `SocketAddr`, the port `0`
+     * selects a free one
+     */
+    pub fn bind(&self, socket_id: usize, local_addr: SocketAddr) -> NetResult<()> {
+        self.with_state(|state| {
+                if !local_addr.is_unspecified()
+                   && local_addr.ip_addr() != state.m_config.ip_addr()
+                {
+                    return Err((OsErrorClass::InvalidArgument,
+                                Some("Address not local")));
+                }
+                state.bind(socket_id, local_addr.port())
+            })
+    }
+
+    /**
+     * Connects the given socket to the given remote `SocketAddr`.
+     *
+     * The UDP sockets only remember it as default destination, the TCP ones
+     * wait the end of the three-way handshake
+     */
+    pub fn connect(&self, socket_id: usize, remote_addr: SocketAddr) -> NetResult<()> {
+        if remote_addr.is_unspecified() || remote_addr.port() == 0 {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid remote address")));
+        }
+
+        let now = Scheduler::instance().ticks();
+        let is_tcp = self.with_state(|state| {
+                             state.ensure_bound(socket_id)?;
+
+                             let iss = NetStackState::new_iss();
+                             match state.m_sockets.get_mut(&socket_id) {
+                                 Some(NetSocket::Udp(udp_socket)) => {
+                                     udp_socket.set_remote_addr(remote_addr);
+                                     Ok(false)
+                                 },
+                                 Some(NetSocket::Tcp(tcp_socket)) => {
+                                     if tcp_socket.state() != TcpState::Closed
+                                        || tcp_socket.is_reset()
+                                     {
+                                         return Err((OsErrorClass::InvalidArgument,
+                                                     Some("Socket already used")));
+                                     }
+                                     tcp_socket.connect(remote_addr,
+                                                        iss,
+                                                        now,
+                                                        &mut state.m_outbox);
+                                     Ok(true)
+                                 },
+                                 None => Err(NetStackState::SOCKET_NOT_FOUND)
+                             }
+                         })?;
+        if !is_tcp {
+            return Ok(());
+        }
+
+        self.wait_for(socket_id, ObjRecvMode::Sync, |state| {
+                let tcp_socket = match state.tcp_socket_mut(socket_id) {
+                    Ok(tcp_socket) => tcp_socket,
+                    Err(err) => return Some(Err(err))
+                };
+
+                match tcp_socket.state() {
+                    TcpState::SynSent => None,
+                    TcpState::Closed => Some(Err((OsErrorClass::InterruptedOperation,
+                                                  Some("Connection refused")))),
+                    _ => Some(Ok(()))
+                }
+            })
+    }
+
+    /**
+     * Puts the given bound TCP socket into the listening state
+     */
+    pub fn listen(&self, socket_id: usize, backlog: usize) -> NetResult<()> {
+        self.with_state(|state| {
+                let tcp_socket = state.tcp_socket_mut(socket_id)?;
+                if tcp_socket.local_addr().port() == 0 {
+                    return Err((OsErrorClass::InvalidArgument,
+                                Some("Socket not bound")));
+                } else if tcp_socket.state() != TcpState::Closed {
+                    return Err((OsErrorClass::InvalidArgument,
+                                Some("Socket already used")));
+                }
+
+                tcp_socket.listen(backlog.min(NetStackState::BACKLOG_MAX));
+                Ok(())
+            })
+    }
+
+    /**
+     * Waits, according to the `ObjRecvMode`, an incoming connection of the
+     * given listening socket.
+     *
+     * Returns the identifier of the connected socket with the remote
+     * `SocketAddr`
+     */
+    pub fn accept(&self,
+                  socket_id: usize,
+                  recv_mode: ObjRecvMode)
+                  -> NetResult<(usize, SocketAddr)> {
+        self.wait_for(socket_id, recv_mode, |state| {
+                match state.tcp_socket_mut(socket_id) {
+                    Ok(tcp_socket) if tcp_socket.state() == TcpState::Listen => {},
+                    Ok(_) => {
+                        return Some(Err((OsErrorClass::InvalidArgument,
+                                         Some("Socket not listening"))))
+                    },
+                    Err(err) => return Some(Err(err))
+                }
+
+                /* the connections are accepted in arrival order */
+                state.m_sockets.iter_mut().find_map(|(conn_id, net_socket)| {
+                                              match net_socket {
+                         NetSocket::Tcp(tcp_socket)
+                             if tcp_socket.listener_id() == Some(socket_id)
+                                && tcp_socket.is_acceptable() =>
+                         {
+                             tcp_socket.set_accepted();
+                             Some(Ok((*conn_id, tcp_socket.remote_addr())))
+                         },
+                         _ => None
+                     }
+                                          })
+            })
+    }
+
+    /**
+     * Sends the given data to the connected remote end-point.
+     *
+     * Returns the amount of bytes sent, the TCP sockets wait to have space
+     * into the transmit buffer
+     */
+    pub fn send(&self, socket_id: usize, data: &[u8]) -> NetResult<usize> {
+        let udp_remote_addr =
+            self.with_state(|state| match state.m_sockets.get(&socket_id) {
+                    Some(NetSocket::Udp(udp_socket)) => {
+                        udp_socket.remote_addr()
+                                  .map(Some)
+                                  .ok_or((OsErrorClass::InvalidArgument,
+                                          Some("Socket not connected")))
+                    },
+                    Some(NetSocket::Tcp(_)) => Ok(None),
+                    None => Err(NetStackState::SOCKET_NOT_FOUND)
+                })?;
+        if let Some(remote_addr) = udp_remote_addr {
+            return self.send_to(socket_id, data, remote_addr);
+        }
+
+        let now = Scheduler::instance().ticks();
+        self.wait_for(socket_id, ObjRecvMode::Sync, |state| {
+                let tcp_socket = match state.tcp_socket_mut(socket_id) {
+                    Ok(tcp_socket) => tcp_socket,
+                    Err(err) => return Some(Err(err))
+                };
+
+                if tcp_socket.is_reset() {
+                    Some(Err((OsErrorClass::InterruptedOperation,
+                              Some("Connection reset"))))
+                } else if !matches!(tcp_socket.state(),
+                                    TcpState::Established | TcpState::CloseWait)
+                {
+                    Some(Err((OsErrorClass::OperationNotEnabled,
+                              Some("Socket not connected"))))
+                } else if data.is_empty() || tcp_socket.has_tx_space() {
+                    Some(Ok(tcp_socket.send(data, now, &mut state.m_outbox)))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /**
+     * Waits, according to the `ObjRecvMode`, data from the connected remote
+     * end-point and moves it into the given buffer.
+     *
+     * Returns the amount of bytes received, `0` when the peer closed the
+     * connection
+     */
+    pub fn recv(&self,
+                socket_id: usize,
+                buffer: &mut [u8],
+                recv_mode: ObjRecvMode)
+                -> NetResult<usize> {
+        let is_udp = self.with_state(|state| match state.m_sockets.get(&socket_id) {
+                             Some(net_socket) => Ok(net_socket.is_udp()),
+                             None => Err(NetStackState::SOCKET_NOT_FOUND)
+                         })?;
+        if is_udp {
+            return self.recv_from(socket_id, buffer, recv_mode)
+                       .map(|(recv_len, _)| recv_len);
+        }
+
+        self.wait_for(socket_id, recv_mode, |state| {
+                let tcp_socket = match state.tcp_socket_mut(socket_id) {
+                    Ok(tcp_socket) => tcp_socket,
+                    Err(err) => return Some(Err(err))
+                };
+
+                if tcp_socket.has_rx_data() {
+                    Some(Ok(tcp_socket.recv(buffer, &mut state.m_outbox)))
+                } else if tcp_socket.is_reset() {
+                    Some(Err((OsErrorClass::InterruptedOperation,
+                              Some("Connection reset"))))
+                } else if tcp_socket.is_fin_received() {
+                    Some(Ok(0))
+                } else if !tcp_socket.is_synchronized() {
+                    Some(Err((OsErrorClass::OperationNotEnabled,
+                              Some("Socket not connected"))))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /**
+     * Sends the given data as a single datagram to the given remote
+     * `SocketAddr`, the socket is bound to a free port when unbound
+     */
+    pub fn send_to(&self,
+                   socket_id: usize,
+                   data: &[u8],
+                   remote_addr: SocketAddr)
+                   -> NetResult<usize> {
+        let payload_max = self.net_device().mtu() - IPV4_HEADER_SIZE - UDP_HEADER_SIZE;
+        if data.len() > payload_max {
+            return Err((OsErrorClass::LimitOverflow, Some("Datagram too big")));
+        } else if remote_addr.port() == 0 {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid remote address")));
+        }
+
+        self.with_state(|state| {
+                state.ensure_bound(socket_id)?;
+
+                let udp_socket = state.udp_socket_mut(socket_id)?;
+                let datagram = udp_socket.build_datagram(remote_addr, data);
+                state.m_outbox.push(Ipv4Outgoing::new(remote_addr.ip_addr(),
+                                                      IPV4_PROTOCOL_UDP,
+                                                      datagram));
+                Ok(data.len())
+            })
+    }
+
+    /**
+     * Waits, according to the `ObjRecvMode`, the next datagram and copies
+     * it into the given buffer.
+     *
+     * Returns the size of the datagram with the sender `SocketAddr`
+     */
+    pub fn recv_from(&self,
+                     socket_id: usize,
+                     buffer: &mut [u8],
+                     recv_mode: ObjRecvMode)
+                     -> NetResult<(usize, SocketAddr)> {
+        self.wait_for(socket_id, recv_mode, |state| {
+                let udp_socket = match state.udp_socket_mut(socket_id) {
+                    Ok(udp_socket) => udp_socket,
+                    Err(err) => return Some(Err(err))
+                };
+                udp_socket.pop_datagram(buffer).map(Ok)
+            })
+    }
+
+    /**
+     * Processes the frames received by the `TNetDevice` and the expired
+     * TCP timers
+     */
+    pub fn poll(&self) {
+        let net_device = self.net_device();
+        let now = Scheduler::instance().ticks();
+
+        self.with_state(|state| {
+                let mut frame_buffer = [0; NetStackState::FRAME_SIZE_MAX];
+                for _ in 0..NetStackState::RX_FRAMES_PER_POLL {
+                    match net_device.recv_frame(&mut frame_buffer) {
+                        Some(frame_len) => {
+                            state.handle_frame(&frame_buffer[..frame_len], now)
+                        },
+                        None => break
+                    }
+                }
+                state.on_timer(now);
+            });
+    }
+
+    /**
+     * Called on each timer tick to wake up the polling kernel thread
+     */
+    pub fn on_timer_tick(&self) {
+        /* the ticks of the other CpuCores would only repeat the wake up */
+        if Processor::instance().this_core().is_bsp() {
+            Scheduler::instance().wake_up_thread(self.m_poller_thread.clone());
+        }
+    }
+}
+
+impl NetStack /* Getters */ {
+    /**
+     * Returns the global `NetStack` instance, `None` when there are no
+     * network devices
+     */
+    pub fn try_instance() -> Option<&'static Self> {
+        unsafe { SM_NET_STACK.as_ref() }
+    }
+
+    /**
+     * Returns the `DeviceId` of the driven `TNetDevice`
+     */
+    pub fn device_id(&self) -> DeviceId {
+        self.m_device.device_id()
+    }
+
+    /**
+     * Returns the `SocketType` of the given socket
+     */
+    pub fn socket_type(&self, socket_id: usize) -> Option<SocketType> {
+        self.m_state.lock().m_sockets.get(&socket_id).map(|net_socket| {
+                                                         if net_socket.is_udp() {
+                                                             SocketType::Udp
+                                                         } else {
+                                                             SocketType::Tcp
+                                                         }
+                                                     })
+    }
+}
+
+impl NetStack /* Privates */ {
+    /**
+     * Entry-point of the kernel thread which polls the `TNetDevice` on
+     * each timer tick
+     */
+    fn poller_thread_entry(_thread_arg: usize) {
+        let net_stack = Self::try_instance().unwrap();
+        let this_core = Processor::instance().this_core();
+        loop {
+            /* the system calls take the state lock with the interrupts
+             * disabled, so it is never held by a preempted thread
+             */
+            this_core.disable_interrupts();
+            net_stack.poll();
+
+            /* parked until the next <NetStack::on_timer_tick()> */
+            net_stack.m_poller_thread.mark_as_blocked();
+            Scheduler::instance().schedule();
+        }
+    }
+
+    /**
+     * Executes the given closure with the `NetStackState`, then sends the
+     * frames produced and wakes up the threads waiting the sockets made
+     * ready without holding the lock
+     */
+    fn with_state<F, R>(&self, state_fn: F) -> R
+        where F: FnOnce(&mut NetStackState) -> R {
+        let (result, tx_frames, ready_waiters) = {
+            let mut state = self.m_state.lock();
+            let result = state_fn(&mut *state);
+            state.flush_outbox();
+            (result, mem::take(&mut state.m_tx_frames), state.take_ready_waiters())
+        };
+
+        for waiter_thread in ready_waiters {
+            Scheduler::instance().wake_up_thread(waiter_thread);
+        }
+
+        let net_device = self.net_device();
+        for tx_frame in tx_frames {
+            if let Err((_, message)) = net_device.send_frame(&tx_frame) {
+                dbg_println!(DbgLevel::Warn,
+                             "Failed to send a frame: {}",
+                             message.unwrap_or("unknown error"));
+            }
+        }
+        result
+    }
+
+    /**
+     * Retries the given closure, each time the given socket is made ready,
+     * until it returns a result.
+     *
+     * With `ObjRecvMode::Poll` the closure is tried only once
+     */
+    fn wait_for<F, R>(&self,
+                      socket_id: usize,
+                      recv_mode: ObjRecvMode,
+                      mut try_fn: F)
+                      -> NetResult<R>
+        where F: FnMut(&mut NetStackState) -> Option<NetResult<R>> {
+        let current_thread = Processor::instance().this_core().current_thread();
+        loop {
+            let result = self.with_state(|state| {
+                                 let result = try_fn(state);
+                                 if result.is_none() && recv_mode != ObjRecvMode::Poll {
+                                     /* blocked under the waker lock */
+                                     current_thread.mark_as_blocked();
+                                     state.m_waiters
+                                          .entry(socket_id)
+                                          .or_default()
+                                          .push(current_thread.clone());
+                                 }
+                                 result
+                             });
+            if let Some(result) = result {
+                return result;
+            } else if recv_mode == ObjRecvMode::Poll {
+                return Err((OsErrorClass::NoDataAvailable, None));
+            }
+
+            /* parked until the socket is made ready */
+            Scheduler::instance().schedule();
+        }
+    }
+
+    /**
+     * Returns the `TNetDevice` wrapped
+     */
+    fn net_device(&self) -> &dyn TNetDevice {
+        self.m_device.as_net().unwrap()
+    }
+}
+
+/**
+ * Socket of the `NetStack`
+ */
+enum NetSocket {
+    Udp(UdpSocket),
+    Tcp(TcpSocket)
+}
+
+impl NetSocket /* Getters */ {
+    /**
+     * Returns whether this is a `SocketType::Udp` socket
+     */
+    fn is_udp(&self) -> bool {
+        matches!(self, Self::Udp(_))
+    }
+
+    /**
+     * Returns the local end-point
+     */
+    fn local_addr(&self) -> SocketAddr {
+        match self {
+            Self::Udp(udp_socket) => udp_socket.local_addr(),
+            Self::Tcp(tcp_socket) => tcp_socket.local_addr()
+        }
+    }
+}
+
+/**
+ * Protocols state of the `NetStack`
+ */
+struct NetStackState {
+    m_config: NetConfig,
+    m_mac_addr: MacAddr,
+    m_arp_cache: BTreeMap<Ipv4Addr, MacAddr>,
+    m_arp_pending: Vec<(Ipv4Addr, Vec<u8>)>,
+    m_sockets: BTreeMap<usize, NetSocket>,
+    m_orphan_ids: Vec<usize>,
+    m_waiters: BTreeMap<usize, Vec<Arc<Thread>>>,
+    m_ready_ids: Vec<usize>,
+    m_outbox: Vec<Ipv4Outgoing>,
+    m_tx_frames: Vec<Vec<u8>>,
+    m_next_socket_id: usize,
+    m_next_port: u16,
+    m_next_ipv4_id: u16
+}
+
+impl NetStackState /* Constants */ {
+    const SOCKETS_MAX: usize = 256;
+    const BACKLOG_MAX: usize = 32;
+    const ARP_PENDING_MAX: usize = 32;
+
+    /* the frames are received without the frame check sequence */
+    const FRAME_SIZE_MAX: usize = ETHER_HEADER_SIZE + 1500;
+    const RX_FRAMES_PER_POLL: usize = 64;
+
+    const EPHEMERAL_PORTS_FIRST: u16 = 49152;
+
+    const SOCKET_NOT_FOUND: (OsErrorClass, Option<&'static str>) =
+        (OsErrorClass::ReferenceNotFound, Some("Socket not found"));
+}
+
+impl NetStackState /* Constructors */ {
+    /**
+     * Constructs an empty `NetStackState`
+     */
+    fn new(config: NetConfig, mac_addr: MacAddr) -> Self {
+        Self { m_config: config,
+               m_mac_addr: mac_addr,
+               m_arp_cache: BTreeMap::new(),
+               m_arp_pending: Vec::new(),
+               m_sockets: BTreeMap::new(),
+               m_orphan_ids: Vec::new(),
+               m_waiters: BTreeMap::new(),
+               m_ready_ids: Vec::new(),
+               m_outbox: Vec::new(),
+               m_tx_frames: Vec::new(),
+               m_next_socket_id: 0,
+               m_next_port: Self::EPHEMERAL_PORTS_FIRST,
+               m_next_ipv4_id: 0 }
+    }
+}
+
+impl NetStackState /* Methods */ {
+    /**
+     * Binds the given socket to the given port, `0` selects a free one
+     */
+    fn bind(&mut self, socket_id: usize, port: u16) -> NetResult<()> {
+        let net_socket = self.m_sockets.get(&socket_id).ok_or(Self::SOCKET_NOT_FOUND)?;
+        if net_socket.local_addr().port() != 0 {
+            return Err((OsErrorClass::InvalidArgument, Some("Socket already bound")));
+        }
+
+        let is_udp = net_socket.is_udp();
+        let port = if port == 0 {
+            self.free_port(is_udp)?
+        } else if self.is_port_used(is_udp, port) {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Port already used")));
+        } else {
+            port
+        };
+
+        let local_addr = SocketAddr::new(self.m_config.ip_addr(), port);
+        match self.m_sockets.get_mut(&socket_id) {
+            Some(NetSocket::Udp(udp_socket)) => udp_socket.set_local_addr(local_addr),
+            Some(NetSocket::Tcp(tcp_socket)) => tcp_socket.set_local_addr(local_addr),
+            None => return Err(Self::SOCKET_NOT_FOUND)
+        }
+        Ok(())
+    }
+
+    /**
+     * Binds the given socket to a free port when still unbound
+     */
+    fn ensure_bound(&mut self, socket_id: usize) -> NetResult<()> {
+        let net_socket = self.m_sockets.get(&socket_id).ok_or(Self::SOCKET_NOT_FOUND)?;
+        if net_socket.local_addr().port() == 0 {
+            self.bind(socket_id, 0)
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+     * Closes the given socket, the TCP connections remain until the end of
+     * the closing handshake
+     */
+    fn close_socket(&mut self, socket_id: usize, now: u64) {
+        /* the threads still waiting the socket find it closed */
+        self.m_ready_ids.push(socket_id);
+
+        let tcp_socket = match self.m_sockets.get_mut(&socket_id) {
+            Some(NetSocket::Tcp(tcp_socket)) => tcp_socket,
+            Some(NetSocket::Udp(_)) => {
+                self.m_sockets.remove(&socket_id);
+                return;
+            },
+            None => return
+        };
+
+        /* the connections not accepted yet are reset with the listener */
+        if tcp_socket.state() == TcpState::Listen {
+            self.m_sockets.remove(&socket_id);
+            for net_socket in self.m_sockets.values_mut() {
+                if let NetSocket::Tcp(tcp_socket) = net_socket {
+                    if tcp_socket.listener_id() == Some(socket_id) {
+                        tcp_socket.abort(&mut self.m_outbox);
+                    }
+                }
+            }
+            return;
+        }
+
+        tcp_socket.close(now, &mut self.m_outbox);
+        if tcp_socket.state() == TcpState::Closed {
+            self.m_sockets.remove(&socket_id);
+        } else {
+            self.m_orphan_ids.push(socket_id);
+        }
+    }
+
+    /**
+     * Processes the given received ethernet frame
+     */
+    fn handle_frame(&mut self, frame: &[u8], now: u64) {
+        let ether_frame = match EtherFrame::parse(frame) {
+            Some(ether_frame) => ether_frame,
+            None => return
+        };
+        if ether_frame.dst_mac() != self.m_mac_addr
+           && ether_frame.dst_mac() != MAC_BROADCAST
+        {
+            return;
+        }
+
+        match ether_frame.ether_type() {
+            ETHER_TYPE_ARP => self.handle_arp(ether_frame.payload()),
+            ETHER_TYPE_IPV4 => self.handle_ipv4(ether_frame.payload(), now),
+            _ => { /* unsupported protocol */ }
+        }
+    }
+
+    /**
+     * Learns the address of the sender and answers to the requests for our
+     * address
+     */
+    fn handle_arp(&mut self, payload: &[u8]) {
+        let arp_packet = match ArpPacket::parse(payload) {
+            Some(arp_packet) => arp_packet,
+            None => return
+        };
+
+        let sender_ip = arp_packet.sender_ip();
+        if sender_ip != [0; 4] {
+            self.m_arp_cache.insert(sender_ip, arp_packet.sender_mac());
+
+            /* send the packets which were waiting for this address */
+            let (ready_packets, waiting_packets): (Vec<_>, Vec<_>) =
+                mem::take(&mut self.m_arp_pending).into_iter()
+                                                  .partition(|(next_hop, _)| {
+                                                      *next_hop == sender_ip
+                                                  });
+            self.m_arp_pending = waiting_packets;
+            for (_, ipv4_packet) in ready_packets {
+                self.push_frame(arp_packet.sender_mac(), ETHER_TYPE_IPV4, &ipv4_packet);
+            }
+        }
+
+        if arp_packet.oper() == ARP_OPER_REQUEST
+           && arp_packet.target_ip() == self.m_config.ip_addr()
+        {
+            let arp_reply = build_arp_packet(ARP_OPER_REPLY,
+                                             self.m_mac_addr,
+                                             self.m_config.ip_addr(),
+                                             arp_packet.sender_mac(),
+                                             sender_ip);
+            self.push_frame(arp_packet.sender_mac(), ETHER_TYPE_ARP, &arp_reply);
+        }
+    }
+
+    /**
+     * Dispatches the given IPv4 packet to his protocol
+     */
+    fn handle_ipv4(&mut self, payload: &[u8], now: u64) {
+        let ipv4_packet = match Ipv4Packet::parse(payload) {
+            Some(ipv4_packet) => ipv4_packet,
+            None => return
+        };
+        if ipv4_packet.dst_addr() != self.m_config.ip_addr()
+           && ipv4_packet.dst_addr() != IPV4_BROADCAST
+        {
+            return;
+        }
+
+        match ipv4_packet.protocol() {
+            IPV4_PROTOCOL_ICMP => {
+                if let Some(echo_reply) = icmp_echo_reply(ipv4_packet.payload()) {
+                    self.m_outbox.push(Ipv4Outgoing::new(ipv4_packet.src_addr(),
+                                                         IPV4_PROTOCOL_ICMP,
+                                                         echo_reply));
+                }
+            },
+            IPV4_PROTOCOL_UDP => self.handle_udp(&ipv4_packet),
+            IPV4_PROTOCOL_TCP => self.handle_tcp(&ipv4_packet, now),
+            _ => { /* unsupported protocol */ }
+        }
+    }
+
+    /**
+     * Queues the given UDP datagram into the socket bound to his port
+     */
+    fn handle_udp(&mut self, ipv4_packet: &Ipv4Packet) {
+        let datagram = match UdpDatagram::parse(ipv4_packet.src_addr(),
+                                                ipv4_packet.dst_addr(),
+                                                ipv4_packet.payload())
+        {
+            Some(datagram) => datagram,
+            None => return
+        };
+
+        let src_addr = SocketAddr::new(ipv4_packet.src_addr(), datagram.src_port());
+        for (socket_id, net_socket) in self.m_sockets.iter_mut() {
+            if let NetSocket::Udp(udp_socket) = net_socket {
+                if udp_socket.local_addr().port() == datagram.dst_port() {
+                    udp_socket.push_datagram(src_addr, datagram.payload());
+                    self.m_ready_ids.push(*socket_id);
+                    return;
+                }
+            }
+        }
+    }
+
+    /**
+     * Gives the given TCP segment to his connection, or creates a new one
+     * when it is a SYN for a listening socket
+     */
+    fn handle_tcp(&mut self, ipv4_packet: &Ipv4Packet, now: u64) {
+        let segment = match TcpSegment::parse(ipv4_packet.src_addr(),
+                                              ipv4_packet.dst_addr(),
+                                              ipv4_packet.payload())
+        {
+            Some(segment) => segment,
+            None => return
+        };
+        let local_addr = SocketAddr::new(self.m_config.ip_addr(), segment.dst_port());
+        let remote_addr = SocketAddr::new(ipv4_packet.src_addr(), segment.src_port());
+
+        /* the established connections have the precedence on the listeners */
+        let mut listener = None;
+        for (socket_id, net_socket) in self.m_sockets.iter_mut() {
+            let tcp_socket = match net_socket {
+                NetSocket::Tcp(tcp_socket) => tcp_socket,
+                NetSocket::Udp(_) => continue
+            };
+            if tcp_socket.local_addr().port() != local_addr.port() {
+                continue;
+            }
+
+            match tcp_socket.state() {
+                TcpState::Listen => listener = Some((*socket_id, tcp_socket.backlog())),
+                TcpState::Closed => continue,
+                _ if tcp_socket.remote_addr() == remote_addr => {
+                    tcp_socket.on_segment(&segment, now, &mut self.m_outbox);
+
+                    /* the listener could have a new connection to accept */
+                    self.m_ready_ids.push(*socket_id);
+                    self.m_ready_ids.extend(tcp_socket.listener_id());
+                    return;
+                },
+                _ => continue
+            }
+        }
+
+        match listener {
+            Some((listener_id, backlog)) if segment.is_syn() => {
+                let pending_count = self.m_sockets
+                                        .values()
+                                        .filter(|net_socket| match net_socket {
+                                            NetSocket::Tcp(tcp_socket) => {
+                                                tcp_socket.listener_id()
+                                                == Some(listener_id)
+                                            },
+                                            NetSocket::Udp(_) => false
+                                        })
+                                        .count();
+
+                /* the SYNs beyond the backlog are dropped, the peer retries */
+                if pending_count < backlog && self.m_sockets.len() < Self::SOCKETS_MAX {
+                    let tcp_socket = TcpSocket::new_accepted(local_addr,
+                                                             remote_addr,
+                                                             &segment,
+                                                             listener_id,
+                                                             Self::new_iss(),
+                                                             now,
+                                                             &mut self.m_outbox);
+                    self.m_sockets
+                        .insert(self.m_next_socket_id, NetSocket::Tcp(tcp_socket));
+                    self.m_next_socket_id += 1;
+                }
+            },
+            _ => {
+                if let Some(reset_reply) =
+                    tcp_reset_reply(local_addr, remote_addr, &segment)
+                {
+                    self.m_outbox.push(reset_reply);
+                }
+            }
+        }
+    }
+
+    /**
+     * Runs the TCP timers and releases the connections closed which are
+     * not referenced anymore
+     */
+    fn on_timer(&mut self, now: u64) {
+        for (socket_id, net_socket) in self.m_sockets.iter_mut() {
+            if let NetSocket::Tcp(tcp_socket) = net_socket {
+                /* the expired retransmissions could give up the connection */
+                let prev_state = tcp_socket.state();
+                tcp_socket.on_timer(now, &mut self.m_outbox);
+                if tcp_socket.state() != prev_state {
+                    self.m_ready_ids.push(*socket_id);
+                }
+            }
+        }
+
+        let orphan_ids = &self.m_orphan_ids;
+        self.m_sockets.retain(|socket_id, net_socket| match net_socket {
+                          NetSocket::Tcp(tcp_socket) => {
+                              let is_unreferenced = orphan_ids.contains(socket_id)
+                                                    || tcp_socket.listener_id().is_some();
+                              !(is_unreferenced && tcp_socket.state() == TcpState::Closed)
+                          },
+                          NetSocket::Udp(_) => true
+                      });
+
+        let sockets = &self.m_sockets;
+        self.m_orphan_ids.retain(|socket_id| sockets.contains_key(socket_id));
+    }
+
+    /**
+     * Encapsulates into IPv4 packets the transport payloads produced and
+     * queues their frames
+     */
+    fn flush_outbox(&mut self) {
+        for outgoing in mem::take(&mut self.m_outbox) {
+            let ipv4_packet = build_ipv4_packet(self.m_config.ip_addr(),
+                                                outgoing.dst_addr(),
+                                                outgoing.protocol(),
+                                                self.m_next_ipv4_id,
+                                                outgoing.payload());
+            self.m_next_ipv4_id = self.m_next_ipv4_id.wrapping_add(1);
+
+            if outgoing.dst_addr() == IPV4_BROADCAST {
+                self.push_frame(MAC_BROADCAST, ETHER_TYPE_IPV4, &ipv4_packet);
+                continue;
+            }
+
+            /* the packets wait the resolution of the next hop address */
+            let next_hop = self.m_config.next_hop(outgoing.dst_addr());
+            match self.m_arp_cache.get(&next_hop).copied() {
+                Some(next_hop_mac) => {
+                    self.push_frame(next_hop_mac, ETHER_TYPE_IPV4, &ipv4_packet)
+                },
+                None => {
+                    if self.m_arp_pending.len() < Self::ARP_PENDING_MAX {
+                        self.m_arp_pending.push((next_hop, ipv4_packet));
+                    }
+
+                    let arp_request = build_arp_packet(ARP_OPER_REQUEST,
+                                                       self.m_mac_addr,
+                                                       self.m_config.ip_addr(),
+                                                       [0; 6],
+                                                       next_hop);
+                    self.push_frame(MAC_BROADCAST, ETHER_TYPE_ARP, &arp_request);
+                }
+            }
+        }
+    }
+
+    /**
+     * Takes the threads waiting the sockets made ready since the last call
+     */
+    fn take_ready_waiters(&mut self) -> Vec<Arc<Thread>> {
+        let mut ready_waiters = Vec::new();
+        for socket_id in mem::take(&mut self.m_ready_ids) {
+            if let Some(waiters) = self.m_waiters.remove(&socket_id) {
+                ready_waiters.extend(waiters);
+            }
+        }
+        ready_waiters
+    }
+}
+
+impl NetStackState /* Getters */ {
+    /**
+     * Returns the `UdpSocket` with the given identifier
+     */
+    fn udp_socket_mut(&mut self, socket_id: usize) -> NetResult<&mut UdpSocket> {
+        match self.m_sockets.get_mut(&socket_id) {
+            Some(NetSocket::Udp(udp_socket)) => Ok(udp_socket),
+            Some(NetSocket::Tcp(_)) => {
+                Err((OsErrorClass::OperationNotEnabled, Some("Not an UDP socket")))
+            },
+            None => Err(Self::SOCKET_NOT_FOUND)
+        }
+    }
+
+    /**
+     * Returns the `TcpSocket` with the given identifier
+     */
+    fn tcp_socket_mut(&mut self, socket_id: usize) -> NetResult<&mut TcpSocket> {
+        match self.m_sockets.get_mut(&socket_id) {
+            Some(NetSocket::Tcp(tcp_socket)) => Ok(tcp_socket),
+            Some(NetSocket::Udp(_)) => {
+                Err((OsErrorClass::OperationNotEnabled, Some("Not a TCP socket")))
+            },
+            None => Err(Self::SOCKET_NOT_FOUND)
+        }
+    }
+
+    /**
+     * Returns whether a socket of the same protocol is bound to the given
+     * port
+     */
+    fn is_port_used(&self, is_udp: bool, port: u16) -> bool {
+        self.m_sockets
+            .values()
+            .any(|net_socket| {
+                net_socket.is_udp() == is_udp && net_socket.local_addr().port() == port
+            })
+    }
+
+    /**
+     * Returns the next ephemeral port not used by the sockets of the same
+     * protocol
+     */
+    fn free_port(&mut self, is_udp: bool) -> NetResult<u16> {
+        for _ in Self::EPHEMERAL_PORTS_FIRST..=u16::MAX {
+            let port = self.m_next_port;
+            self.m_next_port = if port == u16::MAX {
+                Self::EPHEMERAL_PORTS_FIRST
+            } else {
+                port + 1
+            };
+
+            if !self.is_port_used(is_udp, port) {
+                return Ok(port);
+            }
+        }
+        Err((OsErrorClass::LimitReached, Some("No free ports")))
+    }
+
+    /**
+     * Returns a new initial sequence number for a TCP connection, from the
+     * random device when available
+     */
+    fn new_iss() -> u32 {
+        DevManager::instance().device_by_class(DeviceIdClass::Random)
+                              .and_then(|device| {
+                                  device.as_random().map(|random| random.random_u32())
+                              })
+                              .unwrap_or_else(|| {
+                                  (Scheduler::instance().ticks() as u32)
+                                      .wrapping_mul(2_654_435_761)
+                              })
+    }
+}
+
+impl NetStackState /* Privates */ {
+    /**
+     * Queues a new ethernet frame for the given receiver
+     */
+    fn push_frame(&mut self, dst_mac: MacAddr, ether_type: u16, payload: &[u8]) {
+        self.m_tx_frames
+            .push(build_ether_frame(dst_mac, self.m_mac_addr, ether_type, payload));
+    }
+}
+
+/**
+ * Reads the big-endian `u16` at the given offset of the given bytes
+ */
+fn read_be_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+/**
+ * Reads the big-endian `u32` at the given offset of the given bytes
+ */
+fn read_be_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([bytes[offset],
+                        bytes[offset + 1],
+                        bytes[offset + 2],
+                        bytes[offset + 3]])
+}
@@ -0,0 +1,896 @@
+/*! Transmission Control Protocol */
+
+use alloc::{
+    collections::VecDeque,
+    vec::Vec
+};
+
+use api_data::object::socket::SocketAddr;
+
+use crate::{
+    net::{
+        ipv4::{
+            ipv4_checksum,
+            pseudo_header_sum,
+            Ipv4Addr,
+            Ipv4Outgoing,
+            IPV4_PROTOCOL_TCP
+        },
+        read_be_u16,
+        read_be_u32
+    },
+    task::scheduler::Scheduler
+};
+
+pub const TCP_HEADER_SIZE: usize = 20;
+
+const TCP_FLAG_FIN: u8 = 1 << 0;
+const TCP_FLAG_SYN: u8 = 1 << 1;
+const TCP_FLAG_RST: u8 = 1 << 2;
+const TCP_FLAG_PSH: u8 = 1 << 3;
+const TCP_FLAG_ACK: u8 = 1 << 4;
+
+const TCP_OPTION_END: u8 = 0;
+const TCP_OPTION_NOP: u8 = 1;
+const TCP_OPTION_MSS: u8 = 2;
+
+/**
+ * Lists the states of the TCP connections
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum TcpState {
+    Closed,
+    Listen,
+    SynSent,
+    SynReceived,
+    Established,
+    FinWait1,
+    FinWait2,
+    CloseWait,
+    Closing,
+    LastAck,
+    TimeWait
+}
+
+/**
+ * Received TCP segment, borrows the receive buffer
+ */
+pub struct TcpSegment<'a> {
+    m_src_port: u16,
+    m_dst_port: u16,
+    m_seq: u32,
+    m_ack: u32,
+    m_flags: u8,
+    m_window: u16,
+    m_mss: Option<u16>,
+    m_payload: &'a [u8]
+}
+
+impl<'a> TcpSegment<'a> /* Constructors */ {
+    /**
+     * Parses and validates the given segment sent between the given
+     * addresses, `None` is returned when it is malformed
+     */
+    pub fn parse(src_addr: Ipv4Addr,
+                 dst_addr: Ipv4Addr,
+                 segment: &'a [u8])
+                 -> Option<Self> {
+        if segment.len() < TCP_HEADER_SIZE {
+            return None;
+        }
+
+        let header_size = (segment[12] >> 4) as usize * 4;
+        let sum = pseudo_header_sum(src_addr, dst_addr, IPV4_PROTOCOL_TCP, segment.len());
+        if header_size < TCP_HEADER_SIZE
+           || header_size > segment.len()
+           || ipv4_checksum(segment, sum) != 0
+        {
+            return None;
+        }
+
+        Some(Self { m_src_port: read_be_u16(segment, 0),
+                    m_dst_port: read_be_u16(segment, 2),
+                    m_seq: read_be_u32(segment, 4),
+                    m_ack: read_be_u32(segment, 8),
+                    m_flags: segment[13],
+                    m_window: read_be_u16(segment, 14),
+                    m_mss: Self::parse_mss(&segment[TCP_HEADER_SIZE..header_size]),
+                    m_payload: &segment[header_size..] })
+    }
+}
+
+impl<'a> TcpSegment<'a> /* Getters */ {
+    /**
+     * Returns the port of the sender
+     */
+    pub fn src_port(&self) -> u16 {
+        self.m_src_port
+    }
+
+    /**
+     * Returns the port of the receiver
+     */
+    pub fn dst_port(&self) -> u16 {
+        self.m_dst_port
+    }
+
+    /**
+     * Returns whether the segment opens a connection
+     */
+    pub fn is_syn(&self) -> bool {
+        self.has_flags(TCP_FLAG_SYN) && !self.has_flags(TCP_FLAG_ACK | TCP_FLAG_RST)
+    }
+
+    /**
+     * Returns whether the segment resets the connection
+     */
+    pub fn is_rst(&self) -> bool {
+        self.has_flags(TCP_FLAG_RST)
+    }
+}
+
+impl<'a> TcpSegment<'a> /* Privates */ {
+    /**
+     * Returns whether all the given flags are enabled
+     */
+    fn has_flags(&self, flags: u8) -> bool {
+        self.m_flags & flags == flags
+    }
+
+    /**
+     * Returns the amount of sequence numbers occupied by the segment
+     */
+    fn seq_len(&self) -> u32 {
+        let mut seq_len = self.m_payload.len() as u32;
+        if self.has_flags(TCP_FLAG_SYN) {
+            seq_len += 1;
+        }
+        if self.has_flags(TCP_FLAG_FIN) {
+            seq_len += 1;
+        }
+        seq_len
+    }
+
+    /**
+     * Returns the maximum segment size announced into the given options
+     */
+    fn parse_mss(options: &[u8]) -> Option<u16> {
+        let mut offset = 0;
+        while offset < options.len() {
+            match options[offset] {
+                TCP_OPTION_END => break,
+                TCP_OPTION_NOP => offset += 1,
+                option_kind => {
+                    let option_len = *options.get(offset + 1)? as usize;
+                    if option_len < 2 || offset + option_len > options.len() {
+                        return None;
+                    }
+                    if option_kind == TCP_OPTION_MSS && option_len == 4 {
+                        return Some(read_be_u16(options, offset + 2));
+                    }
+                    offset += option_len;
+                }
+            }
+        }
+        None
+    }
+}
+
+/**
+ * State of a `SocketType::Tcp` socket.
+ *
+ * The data sent and not acknowledged yet is kept into the transmit
+ * buffer, which is sent again from the first unacknowledged byte when
+ * the retransmission timer expires
+ */
+pub struct TcpSocket {
+    m_state: TcpState,
+    m_local_addr: SocketAddr,
+    m_remote_addr: SocketAddr,
+    m_listener_id: Option<usize>,
+    m_backlog: usize,
+    m_snd_una: u32,
+    m_snd_nxt: u32,
+    m_snd_wnd: u32,
+    m_snd_mss: usize,
+    m_rcv_nxt: u32,
+    m_tx_buffer: VecDeque<u8>,
+    m_rx_buffer: VecDeque<u8>,
+    m_is_fin_queued: bool,
+    m_is_fin_received: bool,
+    m_is_reset: bool,
+    m_retransmit_at: Option<u64>,
+    m_rto: u64,
+    m_retries: usize,
+    m_time_wait_end: u64
+}
+
+impl TcpSocket /* Constants */ {
+    /**
+     * Size of both the transmit and the receive buffers
+     */
+    const BUFFER_SIZE: usize = 16 * 1024;
+
+    /**
+     * Maximum segment size announced, fits into the ethernet MTU
+     */
+    const RCV_MSS: u16 = 1460;
+
+    /**
+     * Maximum segment size used when the peer doesn't announce it
+     */
+    const DEFAULT_SND_MSS: usize = 536;
+
+    const RTO_INITIAL: u64 = Scheduler::TICKS_PER_SECOND;
+    const RTO_MAX: u64 = Scheduler::TICKS_PER_SECOND * 30;
+    const RETRIES_MAX: usize = 8;
+    const TIME_WAIT: u64 = Scheduler::TICKS_PER_SECOND * 2;
+}
+
+impl TcpSocket /* Constructors */ {
+    /**
+     * Constructs a closed and unbound `TcpSocket`
+     */
+    pub fn new() -> Self {
+        Self { m_state: TcpState::Closed,
+               m_local_addr: SocketAddr::default(),
+               m_remote_addr: SocketAddr::default(),
+               m_listener_id: None,
+               m_backlog: 0,
+               m_snd_una: 0,
+               m_snd_nxt: 0,
+               m_snd_wnd: 0,
+               m_snd_mss: Self::DEFAULT_SND_MSS,
+               m_rcv_nxt: 0,
+               m_tx_buffer: VecDeque::new(),
+               m_rx_buffer: VecDeque::new(),
+               m_is_fin_queued: false,
+               m_is_fin_received: false,
+               m_is_reset: false,
+               m_retransmit_at: None,
+               m_rto: Self::RTO_INITIAL,
+               m_retries: 0,
+               m_time_wait_end: 0 }
+    }
+
+    /**
+     * Constructs the `TcpSocket` of a connection requested by the given
+     * SYN segment to a listening `TcpSocket`, the SYN-ACK is sent
+     * immediately
+     */
+    pub fn new_accepted(local_addr: SocketAddr,
+                        remote_addr: SocketAddr,
+                        syn_segment: &TcpSegment,
+                        listener_id: usize,
+                        iss: u32,
+                        now: u64,
+                        outbox: &mut Vec<Ipv4Outgoing>)
+                        -> Self {
+        let mut tcp_socket = Self::new();
+        tcp_socket.m_state = TcpState::SynReceived;
+        tcp_socket.m_local_addr = local_addr;
+        tcp_socket.m_remote_addr = remote_addr;
+        tcp_socket.m_listener_id = Some(listener_id);
+        tcp_socket.m_rcv_nxt = syn_segment.m_seq.wrapping_add(1);
+        tcp_socket.m_snd_wnd = syn_segment.m_window as u32;
+        tcp_socket.set_snd_mss(syn_segment.m_mss);
+
+        tcp_socket.m_snd_una = iss;
+        tcp_socket.m_snd_nxt = iss.wrapping_add(1);
+        tcp_socket.send_segment(iss, TCP_FLAG_SYN | TCP_FLAG_ACK, &[], outbox);
+        tcp_socket.m_retransmit_at = Some(now + tcp_socket.m_rto);
+        tcp_socket
+    }
+}
+
+impl TcpSocket /* Methods */ {
+    /**
+     * Starts the three-way handshake with the given remote end-point
+     */
+    pub fn connect(&mut self,
+                   remote_addr: SocketAddr,
+                   iss: u32,
+                   now: u64,
+                   outbox: &mut Vec<Ipv4Outgoing>) {
+        self.m_state = TcpState::SynSent;
+        self.m_remote_addr = remote_addr;
+        self.m_snd_una = iss;
+        self.m_snd_nxt = iss.wrapping_add(1);
+        self.send_segment(iss, TCP_FLAG_SYN, &[], outbox);
+        self.m_retransmit_at = Some(now + self.m_rto);
+    }
+
+    /**
+     * Puts the `TcpSocket` into the `TcpState::Listen`, up to `backlog`
+     * connections could wait to be accepted
+     */
+    pub fn listen(&mut self, backlog: usize) {
+        self.m_state = TcpState::Listen;
+        self.m_backlog = backlog.max(1);
+    }
+
+    /**
+     * Appends to the transmit buffer the part of the given data which fits
+     * into it and sends it when the peer window allows it.
+     *
+     * Returns the amount of bytes appended
+     */
+    pub fn send(&mut self,
+                data: &[u8],
+                now: u64,
+                outbox: &mut Vec<Ipv4Outgoing>)
+                -> usize {
+        let free_space = Self::BUFFER_SIZE - self.m_tx_buffer.len();
+        let append_len = data.len().min(free_space);
+        self.m_tx_buffer.extend(data[..append_len].iter());
+
+        self.output(now, outbox);
+        append_len
+    }
+
+    /**
+     * Moves into the given buffer the received data and returns the amount
+     * of bytes moved.
+     *
+     * The peer is notified when the receive window re-opens
+     */
+    pub fn recv(&mut self, buffer: &mut [u8], outbox: &mut Vec<Ipv4Outgoing>) -> usize {
+        let was_window_small = self.rcv_wnd() < Self::RCV_MSS as usize;
+
+        let recv_len = buffer.len().min(self.m_rx_buffer.len());
+        for (byte, rx_byte) in buffer.iter_mut().zip(self.m_rx_buffer.drain(..recv_len)) {
+            *byte = rx_byte;
+        }
+
+        if recv_len > 0 && was_window_small && self.is_synchronized() {
+            self.send_segment(self.m_snd_nxt, TCP_FLAG_ACK, &[], outbox);
+        }
+        recv_len
+    }
+
+    /**
+     * Closes the local side of the connection, the FIN is sent after the
+     * data of the transmit buffer
+     */
+    pub fn close(&mut self, now: u64, outbox: &mut Vec<Ipv4Outgoing>) {
+        match self.m_state {
+            TcpState::Closed | TcpState::Listen | TcpState::SynSent => {
+                self.m_state = TcpState::Closed;
+            },
+            TcpState::SynReceived | TcpState::Established | TcpState::CloseWait => {
+                self.m_is_fin_queued = true;
+                self.output(now, outbox);
+            },
+            _ => { /* the FIN is already sent */ }
+        }
+    }
+
+    /**
+     * Resets the connection and sends the RST to the peer
+     */
+    pub fn abort(&mut self, outbox: &mut Vec<Ipv4Outgoing>) {
+        if self.is_synchronized() || self.m_state == TcpState::SynReceived {
+            self.send_segment(self.m_snd_nxt, TCP_FLAG_RST | TCP_FLAG_ACK, &[], outbox);
+        }
+        self.m_state = TcpState::Closed;
+        self.m_retransmit_at = None;
+    }
+
+    /**
+     * Processes the given segment received from the remote end-point of
+     * this connection
+     */
+    pub fn on_segment(&mut self,
+                      segment: &TcpSegment,
+                      now: u64,
+                      outbox: &mut Vec<Ipv4Outgoing>) {
+        match self.m_state {
+            TcpState::Closed | TcpState::Listen => return,
+            TcpState::SynSent => return self.on_syn_sent_segment(segment, now, outbox),
+            _ => { /* the connection is synchronized */ }
+        }
+
+        /* the RST is trusted only at the expected sequence number */
+        if segment.has_flags(TCP_FLAG_RST) {
+            if segment.m_seq == self.m_rcv_nxt {
+                self.m_is_reset = true;
+                self.m_state = TcpState::Closed;
+                self.m_retransmit_at = None;
+            }
+            return;
+        }
+
+        /* a retransmitted SYN means that our ACK or SYN-ACK was lost */
+        if segment.has_flags(TCP_FLAG_SYN) {
+            if self.m_state == TcpState::SynReceived {
+                self.send_segment(self.m_snd_una,
+                                  TCP_FLAG_SYN | TCP_FLAG_ACK,
+                                  &[],
+                                  outbox);
+            } else {
+                self.send_segment(self.m_snd_nxt, TCP_FLAG_ACK, &[], outbox);
+            }
+            return;
+        }
+        if !segment.has_flags(TCP_FLAG_ACK) {
+            return;
+        }
+
+        if self.m_state == TcpState::SynReceived {
+            if segment.m_ack != self.m_snd_nxt {
+                self.send_segment(segment.m_ack, TCP_FLAG_RST, &[], outbox);
+                return;
+            }
+            self.m_state = TcpState::Established;
+            self.m_snd_una = segment.m_ack;
+            self.m_retransmit_at = None;
+            self.m_retries = 0;
+        } else {
+            self.on_ack(segment.m_ack, now);
+        }
+        self.m_snd_wnd = segment.m_window as u32;
+
+        /* in-order data only, the others are dropped and acknowledged */
+        let mut must_ack = false;
+        if !segment.m_payload.is_empty() {
+            must_ack = true;
+            if segment.m_seq == self.m_rcv_nxt && self.can_receive() {
+                let accept_len = segment.m_payload.len().min(self.rcv_wnd());
+                self.m_rx_buffer.extend(segment.m_payload[..accept_len].iter());
+                self.m_rcv_nxt = self.m_rcv_nxt.wrapping_add(accept_len as u32);
+            }
+        }
+
+        /* the FIN is accepted only after all the data which precedes it */
+        let fin_seq = segment.m_seq.wrapping_add(segment.m_payload.len() as u32);
+        if segment.has_flags(TCP_FLAG_FIN)
+           && fin_seq == self.m_rcv_nxt
+           && !self.m_is_fin_received
+        {
+            self.m_rcv_nxt = self.m_rcv_nxt.wrapping_add(1);
+            self.m_is_fin_received = true;
+            must_ack = true;
+
+            match self.m_state {
+                TcpState::Established => self.m_state = TcpState::CloseWait,
+                TcpState::FinWait1 => self.m_state = TcpState::Closing,
+                TcpState::FinWait2 => self.enter_time_wait(now),
+                _ => { /* already closing */ }
+            }
+        }
+
+        if must_ack {
+            self.send_segment(self.m_snd_nxt, TCP_FLAG_ACK, &[], outbox);
+        }
+        self.output(now, outbox);
+    }
+
+    /**
+     * Retransmits the unacknowledged segments when the timer expires and
+     * closes the connections at the end of the `TcpState::TimeWait`
+     */
+    pub fn on_timer(&mut self, now: u64, outbox: &mut Vec<Ipv4Outgoing>) {
+        if self.m_state == TcpState::TimeWait && now >= self.m_time_wait_end {
+            self.m_state = TcpState::Closed;
+            return;
+        }
+
+        match self.m_retransmit_at {
+            Some(retransmit_at) if now >= retransmit_at => {},
+            _ => return
+        }
+        if self.m_snd_una == self.m_snd_nxt {
+            self.m_retransmit_at = None;
+            return;
+        }
+
+        /* the peer is considered unreachable after too many retries */
+        self.m_retries += 1;
+        if self.m_retries > Self::RETRIES_MAX {
+            self.abort(outbox);
+            self.m_is_reset = true;
+            return;
+        }
+        self.m_rto = (self.m_rto * 2).min(Self::RTO_MAX);
+
+        match self.m_state {
+            TcpState::SynSent => {
+                self.send_segment(self.m_snd_una, TCP_FLAG_SYN, &[], outbox)
+            },
+            TcpState::SynReceived => self.send_segment(self.m_snd_una,
+                                                       TCP_FLAG_SYN | TCP_FLAG_ACK,
+                                                       &[],
+                                                       outbox),
+            _ => {
+                /* go back to the first unacknowledged byte */
+                self.m_snd_nxt = self.m_snd_una;
+                self.output(now, outbox);
+            }
+        }
+        self.m_retransmit_at = Some(now + self.m_rto);
+    }
+}
+
+impl TcpSocket /* Getters */ {
+    /**
+     * Returns the current `TcpState`
+     */
+    pub fn state(&self) -> TcpState {
+        self.m_state
+    }
+
+    /**
+     * Returns the local end-point, the port is `0` when unbound
+     */
+    pub fn local_addr(&self) -> SocketAddr {
+        self.m_local_addr
+    }
+
+    /**
+     * Returns the remote end-point
+     */
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.m_remote_addr
+    }
+
+    /**
+     * Returns the identifier of the listening socket which will accept
+     * this connection
+     */
+    pub fn listener_id(&self) -> Option<usize> {
+        self.m_listener_id
+    }
+
+    /**
+     * Returns the maximum amount of connections waiting to be accepted
+     */
+    pub fn backlog(&self) -> usize {
+        self.m_backlog
+    }
+
+    /**
+     * Returns whether the connection is completed and waits to be accepted
+     */
+    pub fn is_acceptable(&self) -> bool {
+        self.m_listener_id.is_some() && self.is_synchronized()
+    }
+
+    /**
+     * Returns whether the connection is established
+     */
+    pub fn is_synchronized(&self) -> bool {
+        !matches!(self.m_state,
+                  TcpState::Closed
+                  | TcpState::Listen
+                  | TcpState::SynSent
+                  | TcpState::SynReceived)
+    }
+
+    /**
+     * Returns whether the connection was reset by the peer or timed out
+     */
+    pub fn is_reset(&self) -> bool {
+        self.m_is_reset
+    }
+
+    /**
+     * Returns whether the peer will not send more data
+     */
+    pub fn is_fin_received(&self) -> bool {
+        self.m_is_fin_received
+    }
+
+    /**
+     * Returns whether data is waiting to be received
+     */
+    pub fn has_rx_data(&self) -> bool {
+        !self.m_rx_buffer.is_empty()
+    }
+
+    /**
+     * Returns whether the transmit buffer could accept more data
+     */
+    pub fn has_tx_space(&self) -> bool {
+        self.m_tx_buffer.len() < Self::BUFFER_SIZE
+    }
+}
+
+impl TcpSocket /* Setters */ {
+    /**
+     * Binds the `TcpSocket` to the given local end-point
+     */
+    pub fn set_local_addr(&mut self, local_addr: SocketAddr) {
+        self.m_local_addr = local_addr;
+    }
+
+    /**
+     * Detaches the connection from the listening socket, once accepted
+     */
+    pub fn set_accepted(&mut self) {
+        self.m_listener_id = None;
+    }
+}
+
+impl TcpSocket /* Privates */ {
+    /**
+     * Completes the three-way handshake started by `connect()`
+     */
+    fn on_syn_sent_segment(&mut self,
+                           segment: &TcpSegment,
+                           now: u64,
+                           outbox: &mut Vec<Ipv4Outgoing>) {
+        let is_ack_valid = segment.m_ack == self.m_snd_nxt;
+        if segment.has_flags(TCP_FLAG_ACK) && !is_ack_valid {
+            if !segment.has_flags(TCP_FLAG_RST) {
+                self.send_segment(segment.m_ack, TCP_FLAG_RST, &[], outbox);
+            }
+            return;
+        }
+
+        if segment.has_flags(TCP_FLAG_RST) {
+            /* the connection is refused */
+            if is_ack_valid {
+                self.m_is_reset = true;
+                self.m_state = TcpState::Closed;
+                self.m_retransmit_at = None;
+            }
+        } else if segment.has_flags(TCP_FLAG_SYN | TCP_FLAG_ACK) {
+            self.m_state = TcpState::Established;
+            self.m_rcv_nxt = segment.m_seq.wrapping_add(1);
+            self.m_snd_una = segment.m_ack;
+            self.m_snd_wnd = segment.m_window as u32;
+            self.set_snd_mss(segment.m_mss);
+            self.m_retransmit_at = None;
+            self.m_retries = 0;
+            self.m_rto = Self::RTO_INITIAL;
+
+            self.send_segment(self.m_snd_nxt, TCP_FLAG_ACK, &[], outbox);
+            self.output(now, outbox);
+        }
+    }
+
+    /**
+     * Releases the acknowledged data and moves the states which wait the
+     * ACK of our FIN
+     */
+    fn on_ack(&mut self, ack: u32, now: u64) {
+        let acked_len = ack.wrapping_sub(self.m_snd_una) as usize;
+        let in_flight_len = self.m_snd_nxt.wrapping_sub(self.m_snd_una) as usize;
+        if acked_len == 0 || acked_len > in_flight_len {
+            return;
+        }
+
+        /* the FIN occupies a sequence number after the data */
+        let acked_data_len = acked_len.min(self.m_tx_buffer.len());
+        let is_fin_acked = acked_len > self.m_tx_buffer.len() && self.is_fin_sent();
+        self.m_tx_buffer.drain(..acked_data_len);
+        self.m_snd_una = ack;
+
+        self.m_retries = 0;
+        self.m_rto = Self::RTO_INITIAL;
+        self.m_retransmit_at = if self.m_snd_una == self.m_snd_nxt {
+            None
+        } else {
+            Some(now + self.m_rto)
+        };
+
+        if is_fin_acked {
+            match self.m_state {
+                TcpState::FinWait1 => self.m_state = TcpState::FinWait2,
+                TcpState::Closing => self.enter_time_wait(now),
+                TcpState::LastAck => self.m_state = TcpState::Closed,
+                _ => { /* the FIN is not in flight */ }
+            }
+        }
+    }
+
+    /**
+     * Sends the data of the transmit buffer allowed by the peer window and
+     * the FIN when the local side is closed
+     */
+    fn output(&mut self, now: u64, outbox: &mut Vec<Ipv4Outgoing>) {
+        if !self.is_synchronized() || self.m_state == TcpState::TimeWait {
+            return;
+        }
+
+        /* a zero window is probed with a single byte */
+        let window = (self.m_snd_wnd as usize).max(1);
+        let tx_len = self.m_tx_buffer.len();
+        let mut in_flight_len = self.m_snd_nxt.wrapping_sub(self.m_snd_una) as usize;
+        let mut sent_len = in_flight_len.min(tx_len);
+        let was_idle = in_flight_len == 0;
+
+        while sent_len < tx_len && in_flight_len < window {
+            let segment_len =
+                self.m_snd_mss.min(tx_len - sent_len).min(window - in_flight_len);
+            let data: Vec<u8> = self.m_tx_buffer
+                                    .range(sent_len..sent_len + segment_len)
+                                    .copied()
+                                    .collect();
+
+            let seq = self.m_snd_una.wrapping_add(sent_len as u32);
+            self.send_segment(seq, TCP_FLAG_ACK | TCP_FLAG_PSH, &data, outbox);
+
+            sent_len += segment_len;
+            in_flight_len += segment_len;
+            self.m_snd_nxt = self.m_snd_una.wrapping_add(in_flight_len as u32);
+        }
+
+        /* the FIN follows the last byte of data */
+        let can_send_fin = matches!(self.m_state,
+                                    TcpState::Established
+                                    | TcpState::CloseWait
+                                    | TcpState::FinWait1
+                                    | TcpState::Closing
+                                    | TcpState::LastAck);
+        if self.m_is_fin_queued && can_send_fin && in_flight_len == tx_len {
+            self.send_segment(self.m_snd_nxt, TCP_FLAG_FIN | TCP_FLAG_ACK, &[], outbox);
+            self.m_snd_nxt = self.m_snd_nxt.wrapping_add(1);
+            in_flight_len += 1;
+
+            match self.m_state {
+                TcpState::Established => self.m_state = TcpState::FinWait1,
+                TcpState::CloseWait => self.m_state = TcpState::LastAck,
+                _ => { /* FIN retransmitted */ }
+            }
+        }
+
+        if was_idle && in_flight_len > 0 && self.m_retransmit_at.is_none() {
+            self.m_retransmit_at = Some(now + self.m_rto);
+        }
+    }
+
+    /**
+     * Builds and queues a segment for the remote end-point, the ACK field
+     * and the window always describe the receive side
+     */
+    fn send_segment(&self,
+                    seq: u32,
+                    flags: u8,
+                    payload: &[u8],
+                    outbox: &mut Vec<Ipv4Outgoing>) {
+        /* the SYNs announce the maximum segment size */
+        let mss_bytes = Self::RCV_MSS.to_be_bytes();
+        let mss_option = [TCP_OPTION_MSS, 4, mss_bytes[0], mss_bytes[1]];
+        let options: &[u8] = if flags & TCP_FLAG_SYN != 0 {
+            &mss_option
+        } else {
+            &[]
+        };
+
+        let ack = if flags & TCP_FLAG_ACK != 0 {
+            self.m_rcv_nxt
+        } else {
+            0
+        };
+        let window = self.rcv_wnd().min(u16::MAX as usize) as u16;
+
+        let segment = build_tcp_segment(self.m_local_addr,
+                                        self.m_remote_addr,
+                                        seq,
+                                        ack,
+                                        flags,
+                                        window,
+                                        options,
+                                        payload);
+        outbox.push(Ipv4Outgoing::new(self.m_remote_addr.ip_addr(),
+                                      IPV4_PROTOCOL_TCP,
+                                      segment));
+    }
+
+    /**
+     * Returns whether our FIN is sent and not acknowledged yet
+     */
+    fn is_fin_sent(&self) -> bool {
+        let in_flight_len = self.m_snd_nxt.wrapping_sub(self.m_snd_una) as usize;
+        matches!(self.m_state, TcpState::FinWait1 | TcpState::Closing | TcpState::LastAck)
+        && in_flight_len == self.m_tx_buffer.len() + 1
+    }
+
+    /**
+     * Returns whether the state accepts data from the peer
+     */
+    fn can_receive(&self) -> bool {
+        matches!(self.m_state,
+                 TcpState::Established | TcpState::FinWait1 | TcpState::FinWait2)
+    }
+
+    /**
+     * Returns the free space of the receive buffer
+     */
+    fn rcv_wnd(&self) -> usize {
+        Self::BUFFER_SIZE - self.m_rx_buffer.len()
+    }
+
+    /**
+     * Updates the maximum size of the segments sent with the one
+     * announced by the peer
+     */
+    fn set_snd_mss(&mut self, peer_mss: Option<u16>) {
+        self.m_snd_mss = peer_mss.map_or(Self::DEFAULT_SND_MSS, |peer_mss| {
+                                     (peer_mss as usize).min(Self::RCV_MSS as usize)
+                                                        .max(1)
+                                 });
+    }
+
+    /**
+     * Moves into the `TcpState::TimeWait`, which waits the retransmitted
+     * FINs of the peer
+     */
+    fn enter_time_wait(&mut self, now: u64) {
+        self.m_state = TcpState::TimeWait;
+        self.m_time_wait_end = now + Self::TIME_WAIT;
+        self.m_retransmit_at = None;
+    }
+}
+
+/**
+ * Returns the RST which answers to the given segment sent to a port
+ * without connections, `None` is returned for the RSTs
+ */
+pub fn tcp_reset_reply(local_addr: SocketAddr,
+                       remote_addr: SocketAddr,
+                       segment: &TcpSegment)
+                       -> Option<Ipv4Outgoing> {
+    if segment.has_flags(TCP_FLAG_RST) {
+        return None;
+    }
+
+    let reply = if segment.has_flags(TCP_FLAG_ACK) {
+        build_tcp_segment(local_addr,
+                          remote_addr,
+                          segment.m_ack,
+                          0,
+                          TCP_FLAG_RST,
+                          0,
+                          &[],
+                          &[])
+    } else {
+        build_tcp_segment(local_addr,
+                          remote_addr,
+                          0,
+                          segment.m_seq.wrapping_add(segment.seq_len()),
+                          TCP_FLAG_RST | TCP_FLAG_ACK,
+                          0,
+                          &[],
+                          &[])
+    };
+    Some(Ipv4Outgoing::new(remote_addr.ip_addr(), IPV4_PROTOCOL_TCP, reply))
+}
+
+/**
+ * Returns a new segment with the given fields, options and payload
+ */
+fn build_tcp_segment(local_addr: SocketAddr,
+                     remote_addr: SocketAddr,
+                     seq: u32,
+                     ack: u32,
+                     flags: u8,
+                     window: u16,
+                     options: &[u8],
+                     payload: &[u8])
+                     -> Vec<u8> {
+    let header_size = TCP_HEADER_SIZE + options.len();
+    let segment_len = header_size + payload.len();
+
+    let mut segment = Vec::with_capacity(segment_len);
+    segment.extend_from_slice(&local_addr.port().to_be_bytes());
+    segment.extend_from_slice(&remote_addr.port().to_be_bytes());
+    segment.extend_from_slice(&seq.to_be_bytes());
+    segment.extend_from_slice(&ack.to_be_bytes());
+    segment.extend_from_slice(&[(header_size / 4) as u8 * 16, flags]);
+    segment.extend_from_slice(&window.to_be_bytes());
+    segment.extend_from_slice(&[0, 0, 0, 0]);
+    segment.extend_from_slice(options);
+    segment.extend_from_slice(payload);
+
+    let sum = pseudo_header_sum(local_addr.ip_addr(),
+                                remote_addr.ip_addr(),
+                                IPV4_PROTOCOL_TCP,
+                                segment_len);
+    let checksum = ipv4_checksum(&segment, sum);
+    segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+    segment
+}
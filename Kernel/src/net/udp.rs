@@ -0,0 +1,206 @@
+/*! User Datagram Protocol */
+
+use alloc::{
+    collections::VecDeque,
+    vec::Vec
+};
+
+use api_data::object::socket::SocketAddr;
+
+use crate::net::{
+    ipv4::{
+        ipv4_checksum,
+        pseudo_header_sum,
+        Ipv4Addr,
+        IPV4_PROTOCOL_UDP
+    },
+    read_be_u16
+};
+
+pub const UDP_HEADER_SIZE: usize = 8;
+
+/**
+ * Received UDP datagram, borrows the receive buffer
+ */
+pub struct UdpDatagram<'a> {
+    m_src_port: u16,
+    m_dst_port: u16,
+    m_payload: &'a [u8]
+}
+
+impl<'a> UdpDatagram<'a> /* Constructors */ {
+    /**
+     * Parses and validates the given datagram sent between the given
+     * addresses, `None` is returned when it is malformed
+     */
+    pub fn parse(src_addr: Ipv4Addr,
+                 dst_addr: Ipv4Addr,
+                 datagram: &'a [u8])
+                 -> Option<Self> {
+        if datagram.len() < UDP_HEADER_SIZE {
+            return None;
+        }
+
+        let udp_len = read_be_u16(datagram, 4) as usize;
+        if udp_len < UDP_HEADER_SIZE || udp_len > datagram.len() {
+            return None;
+        }
+
+        /* the checksum is optional, a zero checksum means not computed */
+        let datagram = &datagram[..udp_len];
+        let sum = pseudo_header_sum(src_addr, dst_addr, IPV4_PROTOCOL_UDP, udp_len);
+        if read_be_u16(datagram, 6) != 0 && ipv4_checksum(datagram, sum) != 0 {
+            return None;
+        }
+
+        Some(Self { m_src_port: read_be_u16(datagram, 0),
+                    m_dst_port: read_be_u16(datagram, 2),
+                    m_payload: &datagram[UDP_HEADER_SIZE..] })
+    }
+}
+
+impl<'a> UdpDatagram<'a> /* Getters */ {
+    /**
+     * Returns the port of the sender
+     */
+    pub fn src_port(&self) -> u16 {
+        self.m_src_port
+    }
+
+    /**
+     * Returns the port of the receiver
+     */
+    pub fn dst_port(&self) -> u16 {
+        self.m_dst_port
+    }
+
+    /**
+     * Returns the data of the datagram
+     */
+    pub fn payload(&self) -> &'a [u8] {
+        self.m_payload
+    }
+}
+
+/**
+ * State of a `SocketType::Udp` socket
+ */
+pub struct UdpSocket {
+    m_local_addr: SocketAddr,
+    m_remote_addr: Option<SocketAddr>,
+    m_rx_queue: VecDeque<(SocketAddr, Vec<u8>)>
+}
+
+impl UdpSocket /* Constants */ {
+    /**
+     * Maximum amount of datagrams queued for the receive, the others are
+     * dropped
+     */
+    const RX_QUEUE_MAX: usize = 32;
+}
+
+impl UdpSocket /* Constructors */ {
+    /**
+     * Constructs an unbound `UdpSocket`
+     */
+    pub fn new() -> Self {
+        Self { m_local_addr: SocketAddr::default(),
+               m_remote_addr: None,
+               m_rx_queue: VecDeque::new() }
+    }
+}
+
+impl UdpSocket /* Methods */ {
+    /**
+     * Queues the given datagram received from the given `SocketAddr`.
+     *
+     * When connected only the datagrams of the remote end-point are
+     * accepted
+     */
+    pub fn push_datagram(&mut self, src_addr: SocketAddr, payload: &[u8]) {
+        let is_accepted =
+            self.m_remote_addr.map_or(true, |remote_addr| remote_addr == src_addr);
+        if is_accepted && self.m_rx_queue.len() < Self::RX_QUEUE_MAX {
+            self.m_rx_queue.push_back((src_addr, payload.to_vec()));
+        }
+    }
+
+    /**
+     * Copies the next queued datagram into the given buffer and returns
+     * his size with the sender, the bytes which don't fit are discarded
+     */
+    pub fn pop_datagram(&mut self, buffer: &mut [u8]) -> Option<(usize, SocketAddr)> {
+        let (src_addr, payload) = self.m_rx_queue.pop_front()?;
+
+        let copy_len = payload.len().min(buffer.len());
+        buffer[..copy_len].copy_from_slice(&payload[..copy_len]);
+        Some((copy_len, src_addr))
+    }
+
+    /**
+     * Returns a new datagram for the given `SocketAddr`
+     */
+    pub fn build_datagram(&self, dst_addr: SocketAddr, payload: &[u8]) -> Vec<u8> {
+        let udp_len = UDP_HEADER_SIZE + payload.len();
+
+        let mut datagram = Vec::with_capacity(udp_len);
+        datagram.extend_from_slice(&self.m_local_addr.port().to_be_bytes());
+        datagram.extend_from_slice(&dst_addr.port().to_be_bytes());
+        datagram.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend_from_slice(payload);
+
+        /* a computed zero checksum is sent as all ones */
+        let sum = pseudo_header_sum(self.m_local_addr.ip_addr(),
+                                    dst_addr.ip_addr(),
+                                    IPV4_PROTOCOL_UDP,
+                                    udp_len);
+        let checksum = match ipv4_checksum(&datagram, sum) {
+            0 => 0xFFFF,
+            checksum => checksum
+        };
+        datagram[6..8].copy_from_slice(&checksum.to_be_bytes());
+        datagram
+    }
+}
+
+impl UdpSocket /* Getters */ {
+    /**
+     * Returns the local end-point, the port is `0` when unbound
+     */
+    pub fn local_addr(&self) -> SocketAddr {
+        self.m_local_addr
+    }
+
+    /**
+     * Returns the remote end-point given to `connect()`
+     */
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.m_remote_addr
+    }
+
+    /**
+     * Returns whether datagrams are waiting to be received
+     */
+    pub fn has_datagrams(&self) -> bool {
+        !self.m_rx_queue.is_empty()
+    }
+}
+
+impl UdpSocket /* Setters */ {
+    /**
+     * Binds the `UdpSocket` to the given local end-point
+     */
+    pub fn set_local_addr(&mut self, local_addr: SocketAddr) {
+        self.m_local_addr = local_addr;
+    }
+
+    /**
+     * Sets the default destination, the datagrams of the other end-points
+     * are discarded
+     */
+    pub fn set_remote_addr(&mut self, remote_addr: SocketAddr) {
+        self.m_remote_addr = Some(remote_addr);
+        self.m_rx_queue.retain(|(src_addr, _)| *src_addr == remote_addr);
+    }
+}
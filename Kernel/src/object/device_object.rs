@@ -0,0 +1,229 @@
+/*! Device driver backed kernel objects */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+use core::{
+    mem,
+    ptr
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        config::{
+            ObjConfigBits,
+            ObjConfigFlags
+        },
+        info::RawObjInfo,
+        input::InputEvent,
+        modes::SeekMode,
+        types::ObjType
+    }
+};
+use meetix_fs::dev::TBlockDevice;
+use sync::SpinMutex;
+
+use crate::{
+    dev::{
+        input::TInputDevice,
+        storage::{
+            StorageBlockDevice,
+            StorageResult,
+            TStorageDevice
+        },
+        TDevice
+    },
+    fs::vfs::VfsEntry,
+    object::TObject,
+    processor::Processor
+};
+
+/**
+ * Opened `Device` of the `Vfs`.
+ *
+ * The data is read and written directly with the device driver, for the
+ * storage devices the position is the index of the next block, while the
+ * input devices are read as a stream of `InputEvent`s
+ */
+pub struct DeviceObject {
+    m_vfs_entry: VfsEntry,
+    m_device: Arc<dyn TDevice>,
+    m_config_flags: ObjConfigFlags,
+    m_pos: SpinMutex<u64>
+}
+
+impl DeviceObject /* Constructors */ {
+    /**
+     * Constructs a `DeviceObject` for the given device driver opened with
+     * the given `ObjConfigFlags`
+     */
+    pub fn new(vfs_entry: VfsEntry,
+               device: Arc<dyn TDevice>,
+               config_flags: ObjConfigFlags)
+               -> Self {
+        Self { m_vfs_entry: vfs_entry,
+               m_device: device,
+               m_config_flags: config_flags,
+               m_pos: SpinMutex::const_new(0) }
+    }
+}
+
+impl DeviceObject /* Methods */ {
+    /**
+     * Reads the blocks from the current position, which is advanced by the
+     * amount of blocks read, or the pending `InputEvent`s of the input
+     * devices.
+     *
+     * Returns the amount of bytes read
+     */
+    pub fn read_data(&self, buffer: &mut [u8]) -> StorageResult<usize> {
+        if !self.m_config_flags.is_enabled(ObjConfigBits::Read) {
+            return Err((OsErrorClass::OperationNotEnabled, Some("Not opened for read")));
+        }
+        if let Some(input_device) = self.m_device.as_input() {
+            return Self::read_input_events(input_device, buffer);
+        }
+
+        let storage_device = self.storage()?;
+        let mut pos = self.m_pos.lock();
+        storage_device.read_blocks(*pos, buffer)?;
+        *pos += (buffer.len() / storage_device.block_size()) as u64;
+        Ok(buffer.len())
+    }
+
+    /**
+     * Writes the blocks from the current position, which is advanced by
+     * the amount of blocks written.
+     *
+     * Returns the amount of bytes written
+     */
+    pub fn write_data(&self, buffer: &[u8]) -> StorageResult<usize> {
+        if !self.m_config_flags.is_enabled(ObjConfigBits::Write) {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Not opened for write")));
+        }
+
+        let storage_device = self.storage()?;
+        if storage_device.is_read_only() {
+            return Err((OsErrorClass::OperationNotEnabled, Some("Read-only device")));
+        }
+
+        let mut pos = self.m_pos.lock();
+        storage_device.write_blocks(*pos, buffer)?;
+        *pos += (buffer.len() / storage_device.block_size()) as u64;
+        Ok(buffer.len())
+    }
+
+    /**
+     * Moves the position according to the given `SeekMode`, expressed in
+     * blocks, and returns the new one
+     */
+    pub fn set_pos(&self, seek_mode: SeekMode) -> StorageResult<usize> {
+        let storage_device = self.storage()?;
+
+        let mut pos = self.m_pos.lock();
+        let new_pos = match seek_mode {
+            SeekMode::Absolute(offset) => Some(offset as u64),
+            SeekMode::Relative(offset) => {
+                if offset < 0 {
+                    pos.checked_sub(offset.unsigned_abs() as u64)
+                } else {
+                    pos.checked_add(offset as u64)
+                }
+            },
+            SeekMode::End => Some(storage_device.blocks_count())
+        };
+
+        *pos =
+            new_pos.filter(|new_pos| *new_pos <= storage_device.blocks_count())
+                   .ok_or((OsErrorClass::InvalidArgument,
+                           Some("Position out of range")))?;
+        Ok(*pos as usize)
+    }
+}
+
+impl DeviceObject /* Getters */ {
+    /**
+     * Returns the referenced device driver
+     */
+    pub fn device(&self) -> &Arc<dyn TDevice> {
+        &self.m_device
+    }
+}
+
+impl DeviceObject /* Privates */ {
+    /**
+     * Waits until the given `TInputDevice` has pending `InputEvent`s, then
+     * copies into the buffer as many events as it could contain.
+     *
+     * Returns the amount of bytes read
+     */
+    fn read_input_events(input_device: &dyn TInputDevice,
+                         buffer: &mut [u8])
+                         -> StorageResult<usize> {
+        let event_size = mem::size_of::<InputEvent>();
+        if buffer.len() < event_size {
+            return Err((OsErrorClass::InvalidArgument,
+                        Some("Buffer smaller than an InputEvent")));
+        }
+
+        /* sleep until the next interrupt, which could be the device one */
+        while !input_device.has_events() {
+            let this_core = Processor::instance().this_core();
+            this_core.wait_for_interrupt();
+            this_core.disable_interrupts();
+        }
+
+        let mut read_bytes = 0;
+        for event_slot in buffer.chunks_exact_mut(event_size) {
+            match input_device.next_event() {
+                Some(input_event) => {
+                    /* the user buffer could be not aligned for the events */
+                    unsafe {
+                        ptr::write_unaligned(event_slot.as_mut_ptr() as *mut InputEvent,
+                                             input_event);
+                    }
+                    read_bytes += event_size;
+                },
+                None => break
+            }
+        }
+        Ok(read_bytes)
+    }
+
+    /**
+     * Returns the `TStorageDevice` of the referenced device driver, the
+     * other drivers are not accessible as stream of blocks
+     */
+    fn storage(&self) -> StorageResult<&dyn TStorageDevice> {
+        self.m_device
+            .as_storage()
+            .ok_or((OsErrorClass::OperationNotEnabled, Some("Not a storage device")))
+    }
+}
+
+impl TObject for DeviceObject {
+    fn obj_type(&self) -> ObjType {
+        ObjType::Device
+    }
+
+    fn obj_info(&self) -> RawObjInfo {
+        self.m_vfs_entry.node().obj_info(self.m_vfs_entry.path().last_name())
+    }
+
+    fn into_device_object(self: Arc<Self>) -> Option<Arc<DeviceObject>> {
+        Some(self)
+    }
+
+    fn into_block_device(self: Arc<Self>) -> Option<Box<dyn TBlockDevice + Send>> {
+        if !self.m_config_flags.is_enabled(ObjConfigBits::Read) {
+            return None;
+        }
+
+        let writeable = self.m_config_flags.is_enabled(ObjConfigBits::Write);
+        StorageBlockDevice::new(self.m_device.clone(), writeable)
+            .map(|block_device| Box::new(block_device) as Box<dyn TBlockDevice + Send>)
+    }
+}
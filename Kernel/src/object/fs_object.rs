@@ -0,0 +1,198 @@
+/*! Filesystem backed kernel objects */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        config::{
+            ObjConfigBits,
+            ObjConfigFlags
+        },
+        dir::DirEntry,
+        info::RawObjInfo,
+        modes::SeekMode,
+        types::ObjType
+    }
+};
+use meetix_fs::dev::TBlockDevice;
+use sync::SpinMutex;
+
+use crate::{
+    fs::{
+        image_dev::ImageBlockDevice,
+        vfs::{
+            VfsEntry,
+            VfsResult
+        }
+    },
+    object::TObject
+};
+
+/**
+ * Opened `File`, `Dir` or `Link` of the `Vfs`.
+ *
+ * For `File`s the position is the data offset, for `Dir`s is the index of
+ * the next child returned
+ */
+pub struct FsObject {
+    m_vfs_entry: SpinMutex<VfsEntry>,
+    m_config_flags: ObjConfigFlags,
+    m_pos: SpinMutex<usize>
+}
+
+impl FsObject /* Constructors */ {
+    /**
+     * Constructs a `FsObject` opened with the given `ObjConfigFlags`
+     */
+    pub fn new(vfs_entry: VfsEntry, config_flags: ObjConfigFlags) -> Self {
+        Self { m_vfs_entry: SpinMutex::const_new(vfs_entry),
+               m_config_flags: config_flags,
+               m_pos: SpinMutex::const_new(0) }
+    }
+}
+
+impl FsObject /* Methods */ {
+    /**
+     * Reads the data from the current position, which is advanced by the
+     * amount of bytes read
+     */
+    pub fn read_data(&self, buffer: &mut [u8]) -> VfsResult<usize> {
+        if !self.m_config_flags.is_enabled(ObjConfigBits::Read) {
+            return Err((OsErrorClass::OperationNotEnabled, Some("Not opened for read")));
+        }
+
+        let node = self.vfs_entry().node().clone();
+        let mut pos = self.m_pos.lock();
+        let read_bytes = node.read_at(*pos, buffer)?;
+        *pos += read_bytes;
+        Ok(read_bytes)
+    }
+
+    /**
+     * Writes the data from the current position, which is advanced by the
+     * amount of bytes written
+     */
+    pub fn write_data(&self, buffer: &[u8]) -> VfsResult<usize> {
+        if !self.m_config_flags.is_enabled(ObjConfigBits::Write) {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Not opened for write")));
+        }
+
+        let node = self.vfs_entry().node().clone();
+        let mut pos = self.m_pos.lock();
+        let written_bytes = node.write_at(*pos, buffer)?;
+        *pos += written_bytes;
+        Ok(written_bytes)
+    }
+
+    /**
+     * Returns the next `DirEntry` of the opened `Dir`
+     */
+    pub fn next_child(&self) -> VfsResult<DirEntry> {
+        let node = self.vfs_entry().node().clone();
+        let mut pos = self.m_pos.lock();
+        let dir_entry =
+            node.child_at(*pos)?
+                .ok_or((OsErrorClass::ReferenceNotFound, Some("No more children")))?;
+        *pos += 1;
+        Ok(dir_entry)
+    }
+
+    /**
+     * Moves the position according to the given `SeekMode` and returns the
+     * new one
+     */
+    pub fn set_pos(&self, seek_mode: SeekMode) -> VfsResult<usize> {
+        let mut pos = self.m_pos.lock();
+        let new_pos = match seek_mode {
+            SeekMode::Absolute(offset) => Some(offset),
+            SeekMode::Relative(offset) => {
+                if offset < 0 {
+                    pos.checked_sub(offset.unsigned_abs())
+                } else {
+                    pos.checked_add(offset as usize)
+                }
+            },
+            SeekMode::End => Some(self.end_pos()?)
+        };
+
+        *pos = new_pos.ok_or((OsErrorClass::InvalidArgument,
+                              Some("Position out of range")))?;
+        Ok(*pos)
+    }
+}
+
+impl FsObject /* Getters */ {
+    /**
+     * Returns a copy of the referenced `VfsEntry`
+     */
+    pub fn vfs_entry(&self) -> VfsEntry {
+        self.m_vfs_entry.lock().clone()
+    }
+
+    /**
+     * Returns the `ObjConfigFlags` given at open time
+     */
+    pub fn config_flags(&self) -> ObjConfigFlags {
+        self.m_config_flags
+    }
+}
+
+impl FsObject /* Setters */ {
+    /**
+     * Replaces the referenced `VfsEntry`, used when the node is moved
+     */
+    pub fn set_vfs_entry(&self, vfs_entry: VfsEntry) {
+        *self.m_vfs_entry.lock() = vfs_entry;
+    }
+}
+
+impl FsObject /* Privates */ {
+    /**
+     * Returns the position of the end of the data
+     */
+    fn end_pos(&self) -> VfsResult<usize> {
+        let node = self.vfs_entry().node().clone();
+        if node.obj_type() == ObjType::Dir {
+            let mut children_count = 0;
+            while node.child_at(children_count)?.is_some() {
+                children_count += 1;
+            }
+            Ok(children_count)
+        } else {
+            Ok(node.data_size())
+        }
+    }
+}
+
+impl TObject for FsObject {
+    fn obj_type(&self) -> ObjType {
+        self.vfs_entry().node().obj_type()
+    }
+
+    fn obj_info(&self) -> RawObjInfo {
+        let vfs_entry = self.vfs_entry();
+        vfs_entry.node().obj_info(vfs_entry.path().last_name())
+    }
+
+    fn into_fs_object(self: Arc<Self>) -> Option<Arc<FsObject>> {
+        Some(self)
+    }
+
+    fn into_block_device(self: Arc<Self>) -> Option<Box<dyn TBlockDevice + Send>> {
+        /* only the opened files could contain a filesystem image */
+        let node = self.vfs_entry().node().clone();
+        if node.obj_type() != ObjType::File
+           || !self.m_config_flags.is_enabled(ObjConfigBits::Read)
+        {
+            return None;
+        }
+
+        let writeable = self.m_config_flags.is_enabled(ObjConfigBits::Write);
+        Some(Box::new(ImageBlockDevice::new(node, writeable)))
+    }
+}
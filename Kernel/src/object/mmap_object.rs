@@ -0,0 +1,357 @@
+/*! Memory mapping kernel objects */
+
+use alloc::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        Weak
+    },
+    vec::Vec
+};
+use core::ops::Range;
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::{
+        device::{
+            DeviceId,
+            DeviceIdClass,
+            DeviceIdType
+        },
+        grants::RawObjGrants,
+        info::RawObjInfo,
+        modes::MMapPtrMode,
+        types::ObjType
+    },
+    task::TaskId
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    dbg_print::DbgLevel,
+    dbg_println,
+    dev::TDevice,
+    fs::vfs::node::TVfsNode,
+    object::TObject,
+    task::{
+        process::Process,
+        scheduler::Scheduler
+    },
+    vm::{
+        mem_object::MemObject,
+        user_space::{
+            UserSpaceResult,
+            VmProtBits,
+            VmProtFlags
+        },
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * On failure the `MMapObject` returns the `OsErrorClass` and an optional
+ * message, as the kernel functions do
+ */
+pub type MMapResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Lists the memories which could be mapped by a `MMapObject`
+ */
+enum MMapSource {
+    /**
+     * Anonymous or file-backed `MemObject`, whose frames are shared by all
+     * the `Process`es which map it
+     */
+    Memory(Arc<MemObject>),
+
+    /**
+     * Physical memory of a device driver, mapped uncacheable
+     */
+    Device(Arc<dyn TDevice>, Range<PhysAddr>)
+}
+
+/**
+ * Memory mappable into the user-space.
+ *
+ * The memory is mapped into each `Process` which asks his pointer, even
+ * when the handle was received from another `Process`, so all of them
+ * share the same frames. The mappings remain until the `Process` drops
+ * his last handle or exits
+ */
+pub struct MMapObject {
+    m_source: MMapSource,
+    m_device_id: DeviceId,
+    m_is_writeable: bool,
+    m_map_hint: Option<VirtAddr>,
+    m_creat_inst: RawInstant,
+    m_proc_mappings: SpinMutex<BTreeMap<TaskId, (Weak<Process>, VirtAddr)>>
+}
+
+impl MMapObject /* Constructors */ {
+    /**
+     * Constructs a `MMapObject` of the given size filled with zeroes
+     */
+    pub fn new_anonymous(size: usize) -> Self {
+        Self::new(MMapSource::Memory(Arc::new(MemObject::new_anonymous(size))),
+                  DeviceId::new(DeviceIdType::Block, DeviceIdClass::Memory, 0),
+                  true,
+                  None)
+    }
+
+    /**
+     * Constructs a `MMapObject` for the data of the given file node which
+     * begins at the given offset.
+     *
+     * The written pages are given back to the file when `keep_file_sync`
+     * is `true`, otherwise they remain private to the `MMapObject`
+     */
+    pub fn new_file(file_node: Arc<dyn TVfsNode>,
+                    file_offset: usize,
+                    size: usize,
+                    keep_file_sync: bool,
+                    is_writeable: bool,
+                    map_hint: Option<VirtAddr>)
+                    -> Self {
+        /* the file MMaps are recognized by the storage device class */
+        let device_id = DeviceId::new(DeviceIdType::Block, DeviceIdClass::Storage, 0);
+        let mem_object =
+            MemObject::new_file(size, file_node, file_offset, keep_file_sync);
+
+        Self::new(MMapSource::Memory(Arc::new(mem_object)),
+                  device_id,
+                  is_writeable,
+                  map_hint)
+    }
+
+    /**
+     * Constructs a `MMapObject` for the given physical memory of the given
+     * device driver.
+     *
+     * The mappings are placed at the given `VirtAddr` when it is free
+     */
+    pub fn new_device(device: Arc<dyn TDevice>,
+                      phys_range: Range<PhysAddr>,
+                      map_hint: Option<VirtAddr>)
+                      -> Self {
+        let device_id = device.device_id();
+        Self::new(MMapSource::Device(device, phys_range), device_id, true, map_hint)
+    }
+
+    /**
+     * Constructs a `MMapObject` for the given `MMapSource`
+     */
+    fn new(source: MMapSource,
+           device_id: DeviceId,
+           is_writeable: bool,
+           map_hint: Option<VirtAddr>)
+           -> Self {
+        Self { m_source: source,
+               m_device_id: device_id,
+               m_is_writeable: is_writeable,
+               m_map_hint: map_hint,
+               m_creat_inst: Scheduler::instance().uptime(),
+               m_proc_mappings: SpinMutex::const_new(BTreeMap::new()) }
+    }
+}
+
+impl MMapObject /* Methods */ {
+    /**
+     * Returns the `VirtAddr` of the memory into the address space of the
+     * given `Process`, which is mapped at the first request.
+     *
+     * The memory is mapped read-only until it is requested with
+     * `MMapPtrMode::ForWrite`
+     */
+    pub fn map_into(&self,
+                    proc: &Arc<Process>,
+                    ptr_mode: MMapPtrMode)
+                    -> MMapResult<VirtAddr> {
+        if ptr_mode == MMapPtrMode::ForWrite && !self.m_is_writeable {
+            return Err((OsErrorClass::OperationNotEnabled, Some("MMap not writeable")));
+        }
+
+        let user_space = proc.user_space();
+        let mut proc_mappings = self.m_proc_mappings.lock();
+        if let Some((_, virt_addr)) = proc_mappings.get(&proc.id()) {
+            let virt_addr = *virt_addr;
+
+            /* the protection change flushes the TLB of the other CpuCores,
+             * which could spin on the lock with the interrupts disabled
+             */
+            drop(proc_mappings);
+            if let MMapSource::Memory(mem_object) = &self.m_source {
+                let is_writeable =
+                    user_space.vm_area_at(virt_addr)
+                              .map_or(false, |vm_area| {
+                                  vm_area.prot_flags().is_enabled(VmProtBits::Writeable)
+                              });
+                if ptr_mode == MMapPtrMode::ForWrite && !is_writeable {
+                    let virt_end =
+                        virt_addr.offset(mem_object.pages_count() * Page4KiB::SIZE);
+                    user_space.protect(virt_addr..virt_end,
+                                       Self::mem_prot_flags(MMapPtrMode::ForWrite))?;
+                }
+            }
+            return Ok(virt_addr);
+        }
+
+        let virt_addr = match &self.m_source {
+            MMapSource::Memory(mem_object) => {
+                let prot_flags = Self::mem_prot_flags(ptr_mode);
+                self.map_with_hint(|virt_start| {
+                        user_space.map_shared(virt_start, mem_object.clone(), prot_flags)
+                    })?
+            },
+            MMapSource::Device(_, phys_range) => {
+                /* the device memory could not begin at a page boundary */
+                let phys_start = phys_range.start.align_down(Page4KiB::SIZE);
+                let phys_end = phys_range.end.align_up(Page4KiB::SIZE);
+
+                let virt_start = self.map_with_hint(|virt_start| {
+                                         user_space.map_device(virt_start,
+                                                               phys_start..phys_end)
+                                     })?;
+                virt_start.offset(*phys_range.start - *phys_start)
+            }
+        };
+        proc_mappings.insert(proc.id(), (Arc::downgrade(proc), virt_addr));
+        Ok(virt_addr)
+    }
+
+    /**
+     * Releases the pointer obtained with `MMapObject::map_into()`, the
+     * pages of the file synced memory written since the previous call are
+     * given back to the file
+     */
+    pub fn drop_ptr(&self) -> MMapResult<()> {
+        let mem_object = match &self.m_source {
+            MMapSource::Memory(mem_object) if mem_object.is_file_synced() => mem_object,
+            _ => return Ok(())
+        };
+
+        /* the written pages become read-only again into all the mappings
+         * before being written, so the next writes mark them again
+         */
+        let dirty_pages = mem_object.take_dirty_pages();
+        let proc_mappings: Vec<_> =
+            self.m_proc_mappings
+                .lock()
+                .values()
+                .filter_map(|(proc, virt_addr)| {
+                    proc.upgrade().map(|proc| (proc, *virt_addr))
+                })
+                .collect();
+
+        /* the lock is released before, since the remapping flushes the TLB
+         * of the other CpuCores
+         */
+        for (proc, virt_addr) in proc_mappings {
+            let virt_end = virt_addr.offset(mem_object.pages_count() * Page4KiB::SIZE);
+            proc.user_space().track_clean_pages(virt_addr..virt_end)?;
+        }
+
+        let write_result = mem_object.write_back(&dirty_pages);
+        if write_result.is_err() {
+            /* the pages are written again by the next call */
+            for page_index in dirty_pages {
+                mem_object.mark_dirty(page_index);
+            }
+        }
+        write_result
+    }
+}
+
+impl MMapObject /* Getters */ {
+    /**
+     * Returns the size in bytes of the mapped memory
+     */
+    pub fn size(&self) -> usize {
+        match &self.m_source {
+            MMapSource::Memory(mem_object) => mem_object.size(),
+            MMapSource::Device(_, phys_range) => *phys_range.end - *phys_range.start
+        }
+    }
+}
+
+impl MMapObject /* Privates */ {
+    /**
+     * Maps the memory at the hint given at creation time, or where the
+     * `UserSpace` places it when the hint is missing or not free
+     */
+    fn map_with_hint<F>(&self, map_fn: F) -> UserSpaceResult<VirtAddr>
+        where F: Fn(Option<VirtAddr>) -> UserSpaceResult<VirtAddr> {
+        self.m_map_hint
+            .and_then(|map_hint| map_fn(Some(map_hint)).ok())
+            .map_or_else(|| map_fn(None), Ok)
+    }
+
+    /**
+     * Returns the `VmProtFlags` of the `MMapSource::Memory` mappings
+     * requested with the given `MMapPtrMode`
+     */
+    fn mem_prot_flags(ptr_mode: MMapPtrMode) -> VmProtFlags {
+        match ptr_mode {
+            MMapPtrMode::ForRead => VmProtFlags::new_zero() | VmProtBits::Readable,
+            MMapPtrMode::ForWrite => {
+                VmProtFlags::new_zero() | VmProtBits::Readable | VmProtBits::Writeable
+            },
+        }
+    }
+}
+
+impl TObject for MMapObject {
+    fn obj_type(&self) -> ObjType {
+        ObjType::MMap
+    }
+
+    fn obj_info(&self) -> RawObjInfo {
+        RawObjInfo::new(ObjType::MMap,
+                        0,
+                        self.m_device_id,
+                        0,
+                        None,
+                        1,
+                        Page4KiB::SIZE,
+                        (self.size() + Page4KiB::SIZE - 1) / Page4KiB::SIZE,
+                        self.size(),
+                        0,
+                        0,
+                        RawObjGrants::new_zero(),
+                        self.m_creat_inst,
+                        Scheduler::instance().uptime(),
+                        Scheduler::instance().uptime(),
+                        self.m_creat_inst,
+                        self.m_creat_inst)
+    }
+
+    fn into_mmap_object(self: Arc<Self>) -> Option<Arc<MMapObject>> {
+        Some(self)
+    }
+
+    fn release_from_proc(&self, proc: &Process) {
+        /* the unmap flushes the TLB of the other CpuCores before releasing
+         * the frames, so it must not be performed holding the lock
+         */
+        let proc_mapping = self.m_proc_mappings.lock().remove(&proc.id());
+        if let Some((_, virt_addr)) = proc_mapping {
+            /* the device memory could not begin at a page boundary */
+            let virt_start = virt_addr.align_down(Page4KiB::SIZE);
+            let virt_end = virt_addr.offset(self.size()).align_up(Page4KiB::SIZE);
+
+            /* the frames are freed by the last UserSpace which unmaps them */
+            if let Err((_, err_msg)) = proc.user_space().unmap(virt_start..virt_end) {
+                dbg_println!(DbgLevel::Warn,
+                             "MMapObject: failed to unmap from process {}: {}",
+                             proc.id(),
+                             err_msg.unwrap_or("unknown"));
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+/*! Kernel objects management */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+
+use api_data::object::{
+    info::RawObjInfo,
+    types::ObjType
+};
+use meetix_fs::dev::TBlockDevice;
+
+use crate::{
+    object::{
+        device_object::DeviceObject,
+        fs_object::FsObject,
+        mmap_object::MMapObject,
+        socket_object::SocketObject
+    },
+    task::process::Process
+};
+
+pub mod device_object;
+pub mod fs_object;
+pub mod mmap_object;
+pub mod socket_object;
+
+/**
+ * Common interface implemented by all the kernel objects which could be
+ * referenced by the user-space through `ObjHandle`s
+ */
+pub trait TObject: Send + Sync {
+    /**
+     * Returns the `ObjType` of this object
+     */
+    fn obj_type(&self) -> ObjType;
+
+    /**
+     * Returns the `RawObjInfo` metadata of this object
+     */
+    fn obj_info(&self) -> RawObjInfo;
+
+    /**
+     * Returns this object as `FsObject` if it is backed by the `Vfs`
+     */
+    fn into_fs_object(self: Arc<Self>) -> Option<Arc<FsObject>> {
+        None
+    }
+
+    /**
+     * Returns this object as `DeviceObject` if it references a device
+     * driver
+     */
+    fn into_device_object(self: Arc<Self>) -> Option<Arc<DeviceObject>> {
+        None
+    }
+
+    /**
+     * Returns this object as `MMapObject` if it maps memory into the
+     * user-space
+     */
+    fn into_mmap_object(self: Arc<Self>) -> Option<Arc<MMapObject>> {
+        None
+    }
+
+    /**
+     * Returns this object as `SocketObject` if it references a socket of
+     * the `NetStack`
+     */
+    fn into_socket_object(self: Arc<Self>) -> Option<Arc<SocketObject>> {
+        None
+    }
+
+    /**
+     * Returns the `TBlockDevice` which reads/writes the data of this
+     * object, used as source of the mounted filesystems
+     */
+    fn into_block_device(self: Arc<Self>) -> Option<Box<dyn TBlockDevice + Send>> {
+        None
+    }
+
+    /**
+     * Releases the resources given to the given `Process`, which no longer
+     * references this object.
+     *
+     * Called when the `Process` drops his last handle or exits
+     */
+    fn release_from_proc(&self, _proc: &Process) {
+        /* nothing to release by default */
+    }
+}
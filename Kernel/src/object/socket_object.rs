@@ -0,0 +1,98 @@
+/*! Network socket kernel objects */
+
+use alloc::sync::Arc;
+
+use api_data::{
+    instant::RawInstant,
+    object::{
+        grants::RawObjGrants,
+        info::RawObjInfo,
+        socket::SocketType,
+        types::ObjType
+    }
+};
+
+use crate::{
+    net::NetStack,
+    object::TObject,
+    task::scheduler::Scheduler
+};
+
+/**
+ * Socket of the `NetStack`, which is closed when the last `ObjHandle` is
+ * dropped
+ */
+pub struct SocketObject {
+    m_socket_id: usize,
+    m_socket_type: SocketType,
+    m_creat_inst: RawInstant
+}
+
+impl SocketObject /* Constructors */ {
+    /**
+     * Constructs a `SocketObject` which owns the given socket of the
+     * `NetStack`
+     */
+    pub fn new(socket_id: usize, socket_type: SocketType) -> Self {
+        Self { m_socket_id: socket_id,
+               m_socket_type: socket_type,
+               m_creat_inst: Scheduler::instance().uptime() }
+    }
+}
+
+impl SocketObject /* Getters */ {
+    /**
+     * Returns the identifier of the socket into the `NetStack`
+     */
+    pub fn socket_id(&self) -> usize {
+        self.m_socket_id
+    }
+
+    /**
+     * Returns the `SocketType` of the socket
+     */
+    pub fn socket_type(&self) -> SocketType {
+        self.m_socket_type
+    }
+}
+
+impl TObject for SocketObject {
+    fn obj_type(&self) -> ObjType {
+        ObjType::Socket
+    }
+
+    fn obj_info(&self) -> RawObjInfo {
+        let net_stack = NetStack::try_instance().expect("Socket without NetStack");
+
+        /* the sockets have no name and no storage */
+        RawObjInfo::new(ObjType::Socket,
+                        0,
+                        net_stack.device_id(),
+                        self.m_socket_id as u64,
+                        None,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        0,
+                        RawObjGrants::new_zero(),
+                        self.m_creat_inst,
+                        Scheduler::instance().uptime(),
+                        Scheduler::instance().uptime(),
+                        self.m_creat_inst,
+                        self.m_creat_inst)
+    }
+
+    fn into_socket_object(self: Arc<Self>) -> Option<Arc<SocketObject>> {
+        Some(self)
+    }
+}
+
+impl Drop for SocketObject {
+    fn drop(&mut self) {
+        if let Some(net_stack) = NetStack::try_instance() {
+            net_stack.close_socket(self.m_socket_id);
+        }
+    }
+}
@@ -1,13 +1,34 @@
 /*! Kernel CPU management */
 
 use alloc::{
+    boxed::Box,
     collections::BTreeMap,
-    sync::Arc
+    sync::Arc,
+    vec::Vec
+};
+
+use core::{
+    hint::spin_loop,
+    sync::atomic::{
+        AtomicBool,
+        AtomicUsize,
+        Ordering
+    }
 };
 
 use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
     arch::hw_cpu_core::HwCpuCore,
-    task::thread::Thread
+    dbg_print::DbgLevel,
+    dbg_println,
+    task::{
+        process::Process,
+        thread::Thread
+    },
+    vm::page_dir::PageDir
 };
 
 /* <None> until <Processor::init_instance()> is called */
@@ -23,10 +44,20 @@ pub type CpuCoreId = usize;
  */
 pub struct Processor {
     m_cores_map: BTreeMap<CpuCoreId, CpuCore>,
+    m_online_cores_count: AtomicUsize,
+    m_tlb_shootdown_lock: AtomicBool,
     m_cores_max_frequency: u64,
     m_cores_bus_frequency: u64
 }
 
+impl Processor /* Constants */ {
+    /**
+     * Size of the initial stack of each AP, which becomes the stack of his
+     * idle `Thread`
+     */
+    pub const AP_STACK_SIZE: usize = 64 * 1024;
+}
+
 impl Processor /* Constructors */ {
     /**
      * Initializes the global `SM_PROCESSOR` instance
@@ -38,6 +69,8 @@ impl Processor /* Constructors */ {
         /* initialize the global instance */
         unsafe {
             SM_PROCESSOR = Some(Self { m_cores_map: BTreeMap::new(),
+                                       m_online_cores_count: AtomicUsize::new(1),
+                                       m_tlb_shootdown_lock: AtomicBool::new(false),
                                        m_cores_max_frequency: 0,
                                        m_cores_bus_frequency: 0 });
         }
@@ -58,8 +91,12 @@ impl Processor /* Methods */ {
     pub fn register_cpu_core(&mut self, cpu_core_id: CpuCoreId, is_ap: bool) {
         self.m_cores_map.insert(cpu_core_id,
                                 CpuCore { m_hw_cpu: HwCpuCore::new(is_ap),
+                                          m_is_online: AtomicBool::new(!is_ap),
+                                          m_tlb_shootdown_root: AtomicUsize::new(0),
+                                          m_no_preempt_count: AtomicUsize::new(0),
                                           m_current_thread: None,
-                                          m_idle_thread: None });
+                                          m_idle_thread: None,
+                                          m_prev_thread: None });
     }
 
     /**
@@ -68,6 +105,10 @@ impl Processor /* Methods */ {
     pub fn init_this_ap(&mut self) {
         self.this_core_mut().m_hw_cpu.init();
         self.this_core().m_hw_cpu.init_interrupts();
+        self.this_core().m_is_online.store(true, Ordering::SeqCst);
+
+        /* let the BSP start the next AP */
+        self.m_online_cores_count.fetch_add(1, Ordering::SeqCst);
     }
 
     /**
@@ -91,10 +132,60 @@ impl Processor /* Methods */ {
     }
 
     /**
-     * Starts the Symmetric Multi Processor
+     * Starts the Symmetric Multi Processor.
+     *
+     * The APs are started one at time, each one is waited until completes
+     * his initialization with `init_this_ap()`
+     */
+    pub fn start_smp(&mut self) {
+        let ap_cpu_core_ids: Vec<_> =
+            self.m_cores_map
+                .iter()
+                .filter(|(_, cpu_core)| cpu_core.m_hw_cpu.is_ap())
+                .map(|(&cpu_core_id, _)| cpu_core_id)
+                .collect();
+
+        for ap_cpu_core_id in ap_cpu_core_ids {
+            /* never released, the AP adopts it for his idle thread */
+            let ap_stack_bottom = {
+                let ap_stack =
+                    Box::leak(vec![0u8; Self::AP_STACK_SIZE].into_boxed_slice());
+                let ap_stack_virt_addr: VirtAddr = ap_stack.as_ptr().into();
+
+                ap_stack_virt_addr.offset(Self::AP_STACK_SIZE).align_down(16usize)
+            };
+
+            let online_cores_count = self.online_cores_count();
+            if !self.this_core_mut().m_hw_cpu.start_ap(ap_cpu_core_id, ap_stack_bottom) {
+                dbg_println!(DbgLevel::Warn,
+                             "AP CPU Core {} doesn't respond to startup",
+                             ap_cpu_core_id);
+                continue;
+            }
+
+            while self.online_cores_count() == online_cores_count {
+                spin_loop();
+            }
+        }
+
+        /* all the started APs are now executing the kernel code */
+        self.this_core_mut().m_hw_cpu.end_aps_startup();
+    }
+
+    /**
+     * Flushes the TLB entries of the given `PageDir` from the other online
+     * `CpuCore`s and waits for their acknowledgement, so the frames which
+     * were mapped could be released or shared safely.
+     *
+     * The entries of this `CpuCore` are invalidated by the
+     * `PageTableMapping`s when dropped
      */
-    pub fn start_smp(&self) {
-        todo!()
+    pub fn shootdown_tlb(&self, page_dir: &PageDir) {
+        if self.online_cores_count() == 1 {
+            return;
+        }
+
+        self.this_core().without_interrupts(|| self.broadcast_tlb_shootdown(page_dir));
     }
 }
 
@@ -156,6 +247,13 @@ impl Processor /* Getters */ {
         self.m_cores_map.len()
     }
 
+    /**
+     * Returns the amount of cores which completed their initialization
+     */
+    pub fn online_cores_count(&self) -> usize {
+        self.m_online_cores_count.load(Ordering::SeqCst)
+    }
+
     /**
      * Returns the maximum frequency in Hz
      */
@@ -171,13 +269,63 @@ impl Processor /* Getters */ {
     }
 }
 
+impl Processor /* Privates */ {
+    /**
+     * Sends the TLB shootdown request for the given `PageDir` to the other
+     * online `CpuCore`s and waits until all of them serve it
+     */
+    fn broadcast_tlb_shootdown(&self, page_dir: &PageDir) {
+        let this_core = self.this_core();
+
+        /* one shootdown at time, the waiting cores serve the requests of
+         * the others, which otherwise would wait them forever
+         */
+        while self.m_tlb_shootdown_lock
+                  .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                  .is_err()
+        {
+            this_core.serve_tlb_shootdown();
+            spin_loop();
+        }
+
+        let this_core_id = HwCpuCore::this_id();
+        let target_cores: Vec<_> = self.m_cores_map
+                                       .iter()
+                                       .filter(|(cpu_core_id, cpu_core)| {
+                                           **cpu_core_id != this_core_id
+                                           && cpu_core.m_is_online.load(Ordering::SeqCst)
+                                       })
+                                       .collect();
+        for (cpu_core_id, cpu_core) in target_cores.iter() {
+            cpu_core.m_tlb_shootdown_root
+                    .store(*page_dir.root_phys_frame(), Ordering::SeqCst);
+            this_core.m_hw_cpu.send_tlb_shootdown_ipi(**cpu_core_id);
+        }
+
+        /* wait for the acknowledgement of all the targets */
+        while target_cores.iter()
+                          .any(|(_, cpu_core)| {
+                              cpu_core.m_tlb_shootdown_root.load(Ordering::SeqCst) != 0
+                          })
+        {
+            spin_loop();
+        }
+
+        self.m_tlb_shootdown_lock.store(false, Ordering::Release);
+    }
+}
+
 /**
  * Per-CPU Core structure
  */
 pub struct CpuCore {
     m_hw_cpu: HwCpuCore,
+    m_is_online: AtomicBool,
+    m_tlb_shootdown_root: AtomicUsize,
+    m_no_preempt_count: AtomicUsize,
     m_current_thread: Option<Arc<Thread>>,
-    m_idle_thread: Option<Arc<Thread>>
+    m_idle_thread: Option<Arc<Thread>>,
+    m_prev_thread: Option<Arc<Thread>>
 }
 
 impl CpuCore /* Methods */ {
@@ -212,6 +360,28 @@ impl CpuCore /* Methods */ {
         }
     }
 
+    /**
+     * Executes `f` with the interrupts enabled for this `Cpu` but without
+     * preemption, so the current `Thread` never leaves this `Cpu` while
+     * holding the spin locks taken by `f`
+     */
+    pub fn without_preemption<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R {
+        self.m_no_preempt_count.fetch_add(1, Ordering::SeqCst);
+        let was_enabled = self.are_interrupts_enabled();
+        if !was_enabled {
+            self.enable_interrupts();
+        }
+
+        let result = f();
+
+        if !was_enabled {
+            self.disable_interrupts();
+        }
+        self.m_no_preempt_count.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
     /**
      * Halts this CPU
      */
@@ -220,6 +390,42 @@ impl CpuCore /* Methods */ {
             self.m_hw_cpu.do_halt();
         }
     }
+
+    /**
+     * Flushes the TLB entries requested by `Processor::shootdown_tlb()`,
+     * if any, then acknowledges the request
+     */
+    pub fn serve_tlb_shootdown(&self) {
+        let root_phys_frame = self.m_tlb_shootdown_root.load(Ordering::SeqCst);
+        if root_phys_frame == 0 {
+            return;
+        }
+
+        /* reloading the PageDir flushes all his non-global entries, the
+         * switched out PageDirs have no entries
+         */
+        let current_page_dir = PageDir::current();
+        if *current_page_dir.root_phys_frame() == root_phys_frame {
+            unsafe {
+                current_page_dir.activate();
+            }
+        }
+        self.m_tlb_shootdown_root.store(0, Ordering::SeqCst);
+    }
+
+    /**
+     * Enables the interrupts and suspends this CPU until the next one
+     */
+    pub fn wait_for_interrupt(&self) {
+        self.m_hw_cpu.do_wait_for_interrupt();
+    }
+
+    /**
+     * Takes the `Thread` which was running before the last context switch
+     */
+    pub fn take_prev_thread(&mut self) -> Option<Arc<Thread>> {
+        self.m_prev_thread.take()
+    }
 }
 
 impl CpuCore /* Getters */ {
@@ -237,6 +443,28 @@ impl CpuCore /* Getters */ {
         self.m_hw_cpu.are_interrupts_enabled()
     }
 
+    /**
+     * Returns whether this CPU Core is the bootstrap processor
+     */
+    pub fn is_bsp(&self) -> bool {
+        !self.m_hw_cpu.is_ap()
+    }
+
+    /**
+     * Returns whether the current `Thread` can be preempted, which is not
+     * the case inside `CpuCore::without_preemption()`
+     */
+    pub fn is_preemptible(&self) -> bool {
+        self.m_no_preempt_count.load(Ordering::SeqCst) == 0
+    }
+
+    /**
+     * Returns whether this CPU Core is executing a `Thread`
+     */
+    pub fn has_current_thread(&self) -> bool {
+        self.m_current_thread.is_some()
+    }
+
     /**
      * Returns the current `Thread` for this CPU Core
      */
@@ -247,6 +475,17 @@ impl CpuCore /* Getters */ {
             .clone()
     }
 
+    /**
+     * Returns the `Process` of the current `Thread` for this CPU Core
+     */
+    pub fn current_proc(&self) -> Arc<Process> {
+        self.m_current_thread
+            .as_ref()
+            .expect("Requested current_proc to the CpuCore but is None")
+            .proc()
+            .clone()
+    }
+
     /**
      * Returns the idle `Thread` for this CPU Core
      */
@@ -272,6 +511,28 @@ impl CpuCore /* Setters */ {
     pub fn set_idle_thread(&mut self, idle_thread: Arc<Thread>) {
         self.m_idle_thread = Some(idle_thread);
     }
+
+    /**
+     * Sets the `Thread` which is being switched out from this CPU Core
+     */
+    pub fn set_prev_thread(&mut self, prev_thread: Arc<Thread>) {
+        self.m_prev_thread = Some(prev_thread);
+    }
+
+    /**
+     * Sets the kernel stack used when this CPU Core enters the kernel from
+     * the user-space
+     */
+    pub fn set_kern_stack_bottom(&mut self, kern_stack_bottom: VirtAddr) {
+        self.m_hw_cpu.set_kern_stack_bottom(kern_stack_bottom);
+    }
+
+    /**
+     * Sets the thread local storage pointer of the current `Thread`
+     */
+    pub fn set_tls_ptr(&mut self, tls_ptr: VirtAddr) {
+        self.m_hw_cpu.set_tls_ptr(tls_ptr);
+    }
 }
 
 /**
@@ -303,6 +564,11 @@ pub trait THwCpuCore {
      */
     fn do_halt(&self);
 
+    /**
+     * Enables the interrupts and halts this `HwCpu` until the next one
+     */
+    fn do_wait_for_interrupt(&self);
+
     /**
      * Enable hardware interrupts for this `Cpu`
      */
@@ -313,6 +579,37 @@ pub trait THwCpuCore {
      */
     fn do_disable_interrupts(&self);
 
+    /**
+     * Sets the kernel stack to use when the `HwCpu` switches from the
+     * user-space to the kernel
+     */
+    fn set_kern_stack_bottom(&mut self, kern_stack_bottom: VirtAddr);
+
+    /**
+     * Sets the thread local storage pointer used by the user-space code
+     */
+    fn set_tls_ptr(&mut self, tls_ptr: VirtAddr);
+
+    /**
+     * Starts the AP `HwCpu` with the given id, which executes
+     * `ap_rust_start()` on the given stack.
+     *
+     * Returns whether the AP acknowledged the startup
+     */
+    fn start_ap(&mut self, ap_cpu_core_id: CpuCoreId, ap_stack_bottom: VirtAddr) -> bool;
+
+    /**
+     * Releases what was needed by `start_ap()`, called once all the started
+     * APs are online
+     */
+    fn end_aps_startup(&mut self);
+
+    /**
+     * Sends to the `HwCpu` with the given id the inter-processor interrupt
+     * which makes it call `CpuCore::serve_tlb_shootdown()`
+     */
+    fn send_tlb_shootdown_ipi(&self, cpu_core_id: CpuCoreId);
+
     /**
      * Returns the best approximation of the cores and the bus speed in Hz
      */
@@ -332,4 +629,9 @@ pub trait THwCpuCore {
      * Returns whether this `Cpu` have hardware interrupts enabled
      */
     fn are_interrupts_enabled(&self) -> bool;
+
+    /**
+     * Returns whether this `HwCpu` is an application processor
+     */
+    fn is_ap(&self) -> bool;
 }
@@ -0,0 +1,127 @@
+/*! `Device` kernel routines */
+
+use alloc::sync::Arc;
+use core::ptr::NonNull;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::modes::SeekMode,
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    }
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    dev::{
+        storage::StorageResult,
+        TDevice
+    },
+    object::{
+        device_object::DeviceObject,
+        mmap_object::MMapObject
+    },
+    sys::{
+        object::{
+            add_object,
+            object_by_handle
+        },
+        user_slice,
+        user_slice_mut,
+        user_value,
+        KernFnResult
+    },
+    vm::{
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * Reads the `Device` blocks from the current position into the user
+ * buffer and returns the amount of bytes read
+ */
+pub fn device_read(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice_mut(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let device_object = device_object_by_handle(syscall_payload.raw_handle())?;
+
+    device_object.read_data(buffer)
+}
+
+/**
+ * Writes the user buffer into the `Device` blocks from the current
+ * position and returns the amount of bytes written
+ */
+pub fn device_write(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let device_object = device_object_by_handle(syscall_payload.raw_handle())?;
+
+    device_object.write_data(buffer)
+}
+
+/**
+ * Moves the `Device` position according to the user `SeekMode`, expressed
+ * in blocks, and returns the new position
+ */
+pub fn device_set_pos(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let seek_mode = user_value::<SeekMode>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SeekMode pointer")))?;
+    let device_object = device_object_by_handle(syscall_payload.raw_handle())?;
+
+    device_object.set_pos(seek_mode)
+}
+
+/**
+ * Creates a `MMap` of the `Device` memory which begins at the given page
+ * aligned offset and returns his handle.
+ *
+ * The first argument points to the optional user address where map it
+ */
+pub fn device_map_to_mem(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let map_hint = user_value::<Option<NonNull<()>>>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid address pointer")))?;
+    let from_off = syscall_payload.raw_arg(1);
+    let mmap_size = syscall_payload.raw_arg(2);
+    let device_object = device_object_by_handle(syscall_payload.raw_handle())?;
+
+    let phys_range = device_object.device()
+                                  .mappable_phys_range()
+                                  .ok_or((OsErrorClass::OperationNotEnabled,
+                                          Some("Device not mappable")))?;
+    let range_size = *phys_range.end - *phys_range.start;
+    if mmap_size == 0
+       || from_off % Page4KiB::SIZE != 0
+       || from_off >= range_size
+       || mmap_size > range_size - from_off
+    {
+        return Err((OsErrorClass::InvalidArgument,
+                    Some("Range out of the device memory")));
+    }
+
+    let map_hint = map_hint.map(|map_ptr| VirtAddr::from(map_ptr.as_ptr()));
+    if map_hint.map_or(false, |map_hint| !map_hint.is_aligned(Page4KiB::SIZE)) {
+        return Err((OsErrorClass::InvalidArgument, Some("Unaligned map address")));
+    }
+
+    let mmap_start = phys_range.start.offset(from_off);
+    add_object(Arc::new(MMapObject::new_device(device_object.device().clone(),
+                                               mmap_start..mmap_start.offset(mmap_size),
+                                               map_hint)))
+}
+
+/**
+ * Returns the `DeviceObject` referenced by the given `RawKernHandle` of
+ * the caller's `HandleTable`
+ */
+fn device_object_by_handle(raw_handle: Option<RawKernHandle>)
+                           -> StorageResult<Arc<DeviceObject>> {
+    object_by_handle(raw_handle)?.into_device_object()
+                                 .ok_or((OsErrorClass::TypesNotMatch,
+                                         Some("Not a device")))
+}
@@ -0,0 +1,49 @@
+/*! `Dir` kernel routines */
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        dir::DirEntry,
+        modes::SeekMode,
+        types::ObjType
+    },
+    sys::SysCallPayload
+};
+
+use crate::sys::{
+    is_user_value_writeable,
+    object::fs_object_by_handle,
+    user_value,
+    user_write,
+    KernFnResult
+};
+
+/**
+ * Writes into the user `DirEntry` the next child of the `Dir`
+ */
+pub fn dir_next_child(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let dir_entry_ptr = syscall_payload.raw_arg(0).into();
+    if !is_user_value_writeable::<DirEntry>(dir_entry_ptr) {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid DirEntry pointer")));
+    }
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    user_write(dir_entry_ptr, fs_object.next_child()?)
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid DirEntry pointer")))
+        .map(|_| 0)
+}
+
+/**
+ * Moves the `Dir` position according to the user `SeekMode` and returns
+ * the new position
+ */
+pub fn dir_set_pos(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let seek_mode = user_value::<SeekMode>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SeekMode pointer")))?;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    if fs_object.vfs_entry().node().obj_type() != ObjType::Dir {
+        return Err((OsErrorClass::TypesNotMatch, Some("Not a directory")));
+    }
+    fs_object.set_pos(seek_mode)
+}
@@ -0,0 +1,156 @@
+/*! `File` kernel routines */
+
+use alloc::sync::Arc;
+use core::ptr::NonNull;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        config::ObjConfigBits,
+        modes::SeekMode,
+        types::ObjType
+    },
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    }
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    fs::vfs::Vfs,
+    object::mmap_object::MMapObject,
+    processor::Processor,
+    sys::{
+        object::{
+            add_fs_object,
+            add_object,
+            fs_object_by_handle
+        },
+        user_slice,
+        user_slice_mut,
+        user_value,
+        KernFnResult
+    },
+    vm::{
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * Reads the `File` data from the current position into the user buffer
+ * and returns the amount of bytes read
+ */
+pub fn file_read_data(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice_mut(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    fs_object.read_data(buffer)
+}
+
+/**
+ * Writes the user buffer into the `File` data from the current position
+ * and returns the amount of bytes written
+ */
+pub fn file_write_data(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    fs_object.write_data(buffer)
+}
+
+/**
+ * Copies the `File` into the `Dir` referenced by the first argument and
+ * returns the `RawKernHandle` of the copy
+ */
+pub fn file_copy(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+    let dest_dir_object =
+        fs_object_by_handle(Some(syscall_payload.raw_arg(0) as RawKernHandle))?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let copy_entry = Vfs::instance().copy_file(&current_proc,
+                                               &fs_object.vfs_entry(),
+                                               &dest_dir_object.vfs_entry())?;
+    add_fs_object(copy_entry, fs_object.config_flags())
+}
+
+/**
+ * Moves the `File` into the `Dir` referenced by the first argument, the
+ * handle continues to reference the moved `File`
+ */
+pub fn file_move(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+    let dest_dir_object =
+        fs_object_by_handle(Some(syscall_payload.raw_arg(0) as RawKernHandle))?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let moved_entry = Vfs::instance().move_file(&current_proc,
+                                                &fs_object.vfs_entry(),
+                                                &dest_dir_object.vfs_entry())?;
+    fs_object.set_vfs_entry(moved_entry);
+    Ok(0)
+}
+
+/**
+ * Moves the `File` position according to the user `SeekMode` and returns
+ * the new position
+ */
+pub fn file_set_pos(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let seek_mode = user_value::<SeekMode>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SeekMode pointer")))?;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    if fs_object.vfs_entry().node().obj_type() != ObjType::File {
+        return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+    }
+    fs_object.set_pos(seek_mode)
+}
+
+/**
+ * Creates a `MMap` of the `File` data which begins at the given page
+ * aligned offset and returns his handle.
+ *
+ * The first argument points to the optional user address where map it,
+ * while the last one tells whether the written pages are given back to
+ * the `File`
+ */
+pub fn file_map_to_mem(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let map_hint = user_value::<Option<NonNull<()>>>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid address pointer")))?;
+    let from_off = syscall_payload.raw_arg(1);
+    let mmap_size = syscall_payload.raw_arg(2);
+    let keep_file_sync = syscall_payload.raw_arg(3) != 0;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    let config_flags = fs_object.config_flags();
+    if !config_flags.is_enabled(ObjConfigBits::Read) {
+        return Err((OsErrorClass::OperationNotEnabled, Some("Not opened for read")));
+    }
+
+    let vfs_entry = fs_object.vfs_entry();
+    if vfs_entry.node().obj_type() != ObjType::File {
+        return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+    } else if mmap_size == 0 || from_off % Page4KiB::SIZE != 0 {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid file range")));
+    }
+
+    let map_hint = map_hint.map(|map_ptr| VirtAddr::from(map_ptr.as_ptr()));
+    if map_hint.map_or(false, |map_hint| !map_hint.is_aligned(Page4KiB::SIZE)) {
+        return Err((OsErrorClass::InvalidArgument, Some("Unaligned map address")));
+    }
+
+    /* the MMap has the same permissions of the File */
+    add_object(Arc::new(MMapObject::new_file(vfs_entry.node().clone(),
+                                             from_off,
+                                             mmap_size,
+                                             keep_file_sync,
+                                             config_flags.is_enabled(ObjConfigBits::Write),
+                                             map_hint)))
+}
@@ -0,0 +1,24 @@
+/*! `Instant` kernel routines */
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    sys::SysCallPayload
+};
+
+use crate::{
+    sys::{
+        user_write,
+        KernFnResult
+    },
+    task::scheduler::Scheduler
+};
+
+/**
+ * Writes into the user `RawInstant` the time elapsed since the boot
+ */
+pub fn instant_now(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    user_write::<RawInstant>(syscall_payload.raw_arg(0).into(), Scheduler::instance().uptime())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid RawInstant pointer")))
+        .map(|_| 0)
+}
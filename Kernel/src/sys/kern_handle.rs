@@ -0,0 +1,77 @@
+/*! `KernHandle` kernel routines */
+
+use api_data::{
+    error::class::OsErrorClass,
+    sys::SysCallPayload
+};
+
+use crate::{
+    processor::Processor,
+    sys::KernFnResult,
+    task::handle_table::KernHandleRef
+};
+
+/**
+ * Returns `1` if the `SysCallPayload::raw_handle()` references an opened
+ * kernel resource into the caller's `HandleTable`, `0` otherwise
+ */
+pub fn kern_handle_is_valid(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    if let Some(raw_handle) = syscall_payload.raw_handle() {
+        let current_proc = Processor::instance().this_core().current_proc();
+        let is_valid = current_proc.handle_table().lock().is_valid(raw_handle);
+        Ok(is_valid as usize)
+    } else {
+        Ok(0)
+    }
+}
+
+/**
+ * Clones the `SysCallPayload::raw_handle()` into a new slot of the
+ * caller's `HandleTable` and returns the new `RawKernHandle`
+ */
+pub fn kern_handle_clone(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_handle = syscall_payload.raw_handle()
+                                    .ok_or((OsErrorClass::InvalidHandleReference, None))?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let clone_result = current_proc.handle_table().lock().clone_handle(raw_handle);
+    clone_result.map(|cloned_raw_handle| cloned_raw_handle as usize)
+                .map_err(|error_class| (error_class, Some("Failed to clone the handle")))
+}
+
+/**
+ * Removes the `SysCallPayload::raw_handle()` from the caller's
+ * `HandleTable`, the referenced resource is released with his last
+ * reference.
+ *
+ * When the caller drops his last handle to an object, the object
+ * releases what it gave to him, like the `MMap` mappings
+ */
+pub fn kern_handle_drop(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_handle = syscall_payload.raw_handle()
+                                    .ok_or((OsErrorClass::InvalidHandleReference, None))?;
+
+    /* the removed reference is dropped outside the lock, since dropping a
+     * kernel resource could require to lock again the <HandleTable>
+     */
+    let current_proc = Processor::instance().this_core().current_proc();
+    let (handle_ref, is_last_ref) = {
+        let mut handle_table = current_proc.handle_table().lock();
+        let handle_ref =
+            handle_table.remove(raw_handle)
+                        .map_err(|error_class| {
+                            (error_class, Some("Failed to drop the handle"))
+                        })?;
+
+        let is_last_ref = match &handle_ref {
+            KernHandleRef::Object(object) => !handle_table.is_object_referenced(object),
+            _ => false
+        };
+        (handle_ref, is_last_ref)
+    };
+
+    if let (KernHandleRef::Object(object), true) = (&handle_ref, is_last_ref) {
+        object.release_from_proc(&current_proc);
+    }
+    Ok(0)
+}
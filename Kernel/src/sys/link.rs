@@ -0,0 +1,43 @@
+/*! `Link` kernel routines */
+
+use api_data::sys::{
+    RawKernHandle,
+    SysCallPayload
+};
+
+use crate::{
+    fs::vfs::Vfs,
+    processor::Processor,
+    sys::{
+        object::{
+            add_fs_object,
+            fs_object_by_handle
+        },
+        KernFnResult
+    }
+};
+
+/**
+ * Resolves the `Link` and returns the `RawKernHandle` of the referenced
+ * `Object`, opened with the same flags of the `Link`
+ */
+pub fn link_deref(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let link_path = fs_object.vfs_entry().path().to_path_components();
+    let target_entry = Vfs::instance().resolve(&current_proc, &link_path, true)?;
+    add_fs_object(target_entry, fs_object.config_flags())
+}
+
+/**
+ * Binds the `Link` to the named `Object` referenced by the first argument
+ */
+pub fn link_bind_to(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+    let target_object =
+        fs_object_by_handle(Some(syscall_payload.raw_arg(0) as RawKernHandle))?;
+
+    let target_path = target_object.vfs_entry().path().to_path_components();
+    fs_object.vfs_entry().node().set_link_target(&target_path).map(|_| 0)
+}
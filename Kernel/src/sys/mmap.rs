@@ -0,0 +1,68 @@
+/*! `MMap` kernel routines */
+
+use alloc::sync::Arc;
+
+use core::convert::TryFrom;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::modes::MMapPtrMode,
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    }
+};
+
+use crate::{
+    object::mmap_object::{
+        MMapObject,
+        MMapResult
+    },
+    processor::Processor,
+    sys::{
+        object::object_by_handle,
+        user_write,
+        KernFnResult
+    }
+};
+
+/**
+ * Maps the `MMap` into the caller's address space, writes his size and
+ * returns the pointer to his memory.
+ *
+ * The memory is shared without locking, the `MMapPtrMode::ForWrite`
+ * requests make the mapping writeable
+ */
+pub fn mmap_get_ptr(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let ptr_mode = MMapPtrMode::try_from(syscall_payload.raw_arg(0)).map_err(|_| {
+                       (OsErrorClass::InvalidArgument, Some("Invalid MMapPtrMode"))
+                   })?;
+    let mmap_object = mmap_object_by_handle(syscall_payload.raw_handle())?;
+
+    /* written before the mapping, which is not undone on failure */
+    user_write(syscall_payload.raw_arg(1).into(), mmap_object.size())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid size pointer")))?;
+
+    let current_thread = Processor::instance().this_core().current_thread();
+    let virt_addr = mmap_object.map_into(current_thread.proc(), ptr_mode)?;
+    Ok(*virt_addr)
+}
+
+/**
+ * Releases the pointer obtained with `mmap_get_ptr()`, the memory remains
+ * mapped for the next requests while the written file pages are given
+ * back to the file
+ */
+pub fn mmap_drop_ptr(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    mmap_object_by_handle(syscall_payload.raw_handle())?.drop_ptr().map(|_| 0)
+}
+
+/**
+ * Returns the `MMapObject` referenced by the given `RawKernHandle` of the
+ * caller's `HandleTable`
+ */
+fn mmap_object_by_handle(raw_handle: Option<RawKernHandle>)
+                         -> MMapResult<Arc<MMapObject>> {
+    object_by_handle(raw_handle)?.into_mmap_object()
+                                 .ok_or((OsErrorClass::TypesNotMatch, Some("Not a MMap")))
+}
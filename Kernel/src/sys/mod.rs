@@ -0,0 +1,552 @@
+/*! Kernel function calls dispatcher */
+
+use alloc::vec::Vec;
+
+use core::mem::{
+    align_of,
+    size_of,
+    MaybeUninit
+};
+
+use api_data::{
+    error::{
+        class::OsErrorClass,
+        OsError
+    },
+    sys::{
+        codes::{
+            KernDeviceFnId,
+            KernDirFnId,
+            KernFileFnId,
+            KernHandleFnId,
+            KernInstantFnId,
+            KernLinkFnId,
+            KernMMapFnId,
+            KernObjConfigFnId,
+            KernObjectFnId,
+            KernPathFnId,
+            KernProcFnId,
+            KernSocketFnId,
+            KernTaskConfigFnId
+        },
+        fn_path::KernFnPath,
+        SysCallPayload,
+        TFromSysCallPtr
+    }
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    dbg_print::DbgLevel,
+    dbg_println,
+    processor::Processor,
+    sys::{
+        device::{
+            device_map_to_mem,
+            device_read,
+            device_set_pos,
+            device_write
+        },
+        dir::{
+            dir_next_child,
+            dir_set_pos
+        },
+        file::{
+            file_copy,
+            file_map_to_mem,
+            file_move,
+            file_read_data,
+            file_set_pos,
+            file_write_data
+        },
+        instant::instant_now,
+        kern_handle::{
+            kern_handle_clone,
+            kern_handle_drop,
+            kern_handle_is_valid
+        },
+        link::{
+            link_bind_to,
+            link_deref
+        },
+        mmap::{
+            mmap_drop_ptr,
+            mmap_get_ptr
+        },
+        object::{
+            obj_config_apply,
+            object_drop_name,
+            object_info,
+            object_recv,
+            object_send,
+            object_update_info
+        },
+        path::path_exists,
+        proc::{
+            proc_mount,
+            proc_unmount
+        },
+        socket::{
+            socket_accept,
+            socket_bind,
+            socket_connect,
+            socket_listen,
+            socket_open,
+            socket_recv,
+            socket_recv_from,
+            socket_send,
+            socket_send_to
+        },
+        task::task_config_apply
+    },
+    vm::{
+        layout_manager::LayoutManager,
+        user_space::VmAccess,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+pub mod device;
+pub mod dir;
+pub mod file;
+pub mod instant;
+pub mod kern_handle;
+pub mod link;
+pub mod mmap;
+pub mod object;
+pub mod path;
+pub mod proc;
+pub mod socket;
+pub mod task;
+
+extern "C" {
+    static __user_copy_begin: usize;
+    static __user_copy_end: usize;
+}
+
+/* <None> until <KernFnTable::init_instance()> is called */
+static mut SM_KERN_FN_TABLE: Option<KernFnTable> = None;
+
+/**
+ * Value returned by each `KernRoutine`.
+ *
+ * On failure the routine returns only the `OsErrorClass` and an optional
+ * message, the remaining `OsError` fields are filled by the dispatcher
+ */
+pub type KernFnResult = Result<usize, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Kernel routine callable from the user-space through `SysCallPayload`
+ */
+pub type KernRoutine = fn(&mut SysCallPayload) -> KernFnResult;
+
+/**
+ * Kernel routines table.
+ *
+ * The primary key is the `KernFnPath::raw_fn_class()`, the secondary key
+ * is the `KernFnPath::raw_fn_id()`
+ */
+pub struct KernFnTable {
+    m_routines: Vec<Vec<Option<KernRoutine>>>
+}
+
+impl KernFnTable /* Constructors */ {
+    /**
+     * Initializes the global `KernFnTable` instance
+     */
+    pub fn init_instance() {
+        unsafe {
+            SM_KERN_FN_TABLE = Some(Self { m_routines: Vec::new() });
+        }
+
+        /* register the kernel routines */
+        let kern_fn_table = Self::instance_mut();
+        kern_fn_table.register_routine(KernFnPath::KernHandle(KernHandleFnId::IsValid),
+                                       kern_handle_is_valid);
+        kern_fn_table.register_routine(KernFnPath::KernHandle(KernHandleFnId::Clone),
+                                       kern_handle_clone);
+        kern_fn_table.register_routine(KernFnPath::KernHandle(KernHandleFnId::Drop),
+                                       kern_handle_drop);
+        kern_fn_table.register_routine(KernFnPath::ObjConfig(KernObjConfigFnId::ApplyConfig),
+                                       obj_config_apply);
+        kern_fn_table.register_routine(KernFnPath::TaskConfig(KernTaskConfigFnId::ApplyConfig),
+                                       task_config_apply);
+        kern_fn_table.register_routine(KernFnPath::Object(KernObjectFnId::DropName),
+                                       object_drop_name);
+        kern_fn_table.register_routine(KernFnPath::Object(KernObjectFnId::Info),
+                                       object_info);
+        kern_fn_table.register_routine(KernFnPath::Object(KernObjectFnId::UpdateInfo),
+                                       object_update_info);
+        kern_fn_table.register_routine(KernFnPath::Object(KernObjectFnId::Send),
+                                       object_send);
+        kern_fn_table.register_routine(KernFnPath::Object(KernObjectFnId::Recv),
+                                       object_recv);
+        kern_fn_table.register_routine(KernFnPath::Device(KernDeviceFnId::Read),
+                                       device_read);
+        kern_fn_table.register_routine(KernFnPath::Device(KernDeviceFnId::Write),
+                                       device_write);
+        kern_fn_table.register_routine(KernFnPath::Device(KernDeviceFnId::SetPos),
+                                       device_set_pos);
+        kern_fn_table.register_routine(KernFnPath::Device(KernDeviceFnId::MapToMem),
+                                       device_map_to_mem);
+        kern_fn_table.register_routine(KernFnPath::Dir(KernDirFnId::NextChild),
+                                       dir_next_child);
+        kern_fn_table.register_routine(KernFnPath::Dir(KernDirFnId::SetPos), dir_set_pos);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::ReadData),
+                                       file_read_data);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::WriteData),
+                                       file_write_data);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::Copy), file_copy);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::Move), file_move);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::SetPos),
+                                       file_set_pos);
+        kern_fn_table.register_routine(KernFnPath::File(KernFileFnId::MapToMem),
+                                       file_map_to_mem);
+        kern_fn_table.register_routine(KernFnPath::Link(KernLinkFnId::Deref), link_deref);
+        kern_fn_table.register_routine(KernFnPath::Link(KernLinkFnId::BindTo),
+                                       link_bind_to);
+        kern_fn_table.register_routine(KernFnPath::MMap(KernMMapFnId::GetPtr),
+                                       mmap_get_ptr);
+        kern_fn_table.register_routine(KernFnPath::MMap(KernMMapFnId::DropPtr),
+                                       mmap_drop_ptr);
+        kern_fn_table.register_routine(KernFnPath::Instant(KernInstantFnId::Now),
+                                       instant_now);
+        kern_fn_table.register_routine(KernFnPath::Path(KernPathFnId::Exists),
+                                       path_exists);
+        kern_fn_table.register_routine(KernFnPath::Proc(KernProcFnId::Mount), proc_mount);
+        kern_fn_table.register_routine(KernFnPath::Proc(KernProcFnId::UnMount),
+                                       proc_unmount);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Open),
+                                       socket_open);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Bind),
+                                       socket_bind);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Connect),
+                                       socket_connect);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Listen),
+                                       socket_listen);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Accept),
+                                       socket_accept);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Send),
+                                       socket_send);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::Recv),
+                                       socket_recv);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::SendTo),
+                                       socket_send_to);
+        kern_fn_table.register_routine(KernFnPath::Socket(KernSocketFnId::RecvFrom),
+                                       socket_recv_from);
+    }
+}
+
+impl KernFnTable /* Methods */ {
+    /**
+     * Registers the given `KernRoutine` for the given `KernFnPath`
+     */
+    pub fn register_routine(&mut self, kern_fn_path: KernFnPath, routine: KernRoutine) {
+        assert_ne!(kern_fn_path.raw_fn_class(),
+                   u16::MAX,
+                   "Tried to register a routine for an invalid KernFnPath");
+
+        let raw_fn_class = kern_fn_path.raw_fn_class() as usize;
+        let raw_fn_id = kern_fn_path.raw_fn_id() as usize;
+
+        /* grow the tables to contain the new routine */
+        if self.m_routines.len() <= raw_fn_class {
+            self.m_routines.resize(raw_fn_class + 1, Vec::new());
+        }
+        let class_routines = &mut self.m_routines[raw_fn_class];
+        if class_routines.len() <= raw_fn_id {
+            class_routines.resize(raw_fn_id + 1, None);
+        }
+
+        assert!(class_routines[raw_fn_id].replace(routine).is_none(),
+                "Registered twice a routine for {}",
+                kern_fn_path);
+    }
+
+    /**
+     * Validates the user `SysCallPayload` pointer and executes the
+     * requested `KernRoutine`.
+     *
+     * The result of the routine is written back into the payload, as the
+     * `OsError` when the routine fails
+     */
+    pub fn dispatch(&self, raw_payload_ptr: usize) {
+        let mut syscall_payload = match Self::payload_from_user(raw_payload_ptr.into()) {
+            Some(syscall_payload) => syscall_payload,
+            None => {
+                /* there is no way to give back an error to the caller */
+                dbg_println!(DbgLevel::Warn,
+                             "Discarded kernel call with invalid payload: {:#018x}",
+                             raw_payload_ptr);
+                return;
+            }
+        };
+
+        let kern_fn_path = syscall_payload.kern_fn_path();
+        let kern_fn_result = match self.routine_by_fn_path(kern_fn_path) {
+            Some(routine) => routine(&mut syscall_payload),
+            None => Err((OsErrorClass::InvalidArgument, Some("Unknown kernel function")))
+        };
+
+        match kern_fn_result {
+            Ok(result) => syscall_payload.set_result(result),
+            Err((error_class, message)) => {
+                let raw_handle = syscall_payload.raw_handle();
+                let (proc_id, thread_id) = {
+                    let current_thread =
+                        Processor::instance().this_core().current_thread();
+                    (current_thread.proc().id(), current_thread.id())
+                };
+
+                *syscall_payload.error_mut() = OsError::new(error_class,
+                                                            kern_fn_path,
+                                                            raw_handle,
+                                                            proc_id,
+                                                            thread_id,
+                                                            message);
+            }
+        }
+
+        /* the routine could have unmapped the payload meanwhile */
+        if user_write(raw_payload_ptr.into(), syscall_payload).is_none() {
+            dbg_println!(DbgLevel::Warn,
+                         "Discarded result of kernel call with invalid payload: \
+                          {:#018x}",
+                         raw_payload_ptr);
+        }
+    }
+}
+
+impl KernFnTable /* Getters */ {
+    /**
+     * Returns the global `KernFnTable` instance
+     */
+    pub fn instance() -> &'static Self {
+        unsafe {
+            SM_KERN_FN_TABLE.as_ref().expect("Called KernFnTable::instance() before \
+                                              KernFnTable::init_instance()")
+        }
+    }
+
+    /**
+     * Returns the global `KernFnTable` mutable instance
+     */
+    pub fn instance_mut() -> &'static mut Self {
+        unsafe {
+            SM_KERN_FN_TABLE.as_mut().expect("Called KernFnTable::instance_mut() before \
+                                              KernFnTable::init_instance()")
+        }
+    }
+
+    /**
+     * Returns the registered `KernRoutine` for the given `KernFnPath`
+     */
+    pub fn routine_by_fn_path(&self, kern_fn_path: KernFnPath) -> Option<KernRoutine> {
+        self.m_routines
+            .get(kern_fn_path.raw_fn_class() as usize)?
+            .get(kern_fn_path.raw_fn_id() as usize)
+            .copied()
+            .flatten()
+    }
+}
+
+impl KernFnTable /* Privates */ {
+    /**
+     * Returns the copy of the user `SysCallPayload` only if the given
+     * `VirtAddr` references writeable user memory, where the results are
+     * written back
+     */
+    fn payload_from_user(payload_virt_addr: VirtAddr) -> Option<SysCallPayload> {
+        if is_user_value_writeable::<SysCallPayload>(payload_virt_addr) {
+            user_value(payload_virt_addr)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * Returns the user-space slice of `len` bytes at the given `VirtAddr` only
+ * if it is entirely mapped as user memory into the caller's address space
+ */
+pub fn user_slice(virt_addr: VirtAddr, len: usize) -> Option<&'static [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if is_user_range_accessible(virt_addr, len, false) {
+        Some(unsafe { core::slice::from_raw_parts(virt_addr.as_ptr(), len) })
+    } else {
+        None
+    }
+}
+
+/**
+ * Returns the mutable user-space slice of `len` bytes at the given
+ * `VirtAddr` only if it is entirely mapped as writeable user memory into
+ * the caller's address space
+ */
+pub fn user_slice_mut(virt_addr: VirtAddr, len: usize) -> Option<&'static mut [u8]> {
+    if len == 0 {
+        Some(&mut [])
+    } else if is_user_range_accessible(virt_addr, len, true) {
+        Some(unsafe { core::slice::from_raw_parts_mut(virt_addr.as_ptr_mut(), len) })
+    } else {
+        None
+    }
+}
+
+/**
+ * Returns the copy of the user-space `T` at the given `VirtAddr` only if it
+ * is well aligned, mapped as user memory into the caller's address space
+ * and his bytes are a valid `T`.
+ *
+ * The bytes are checked after the copy, so the other threads of the caller
+ * can't change them once validated
+ */
+pub fn user_value<T>(virt_addr: VirtAddr) -> Option<T>
+    where T: TFromSysCallPtr {
+    if !is_user_value_accessible::<T>(virt_addr, false) {
+        return None;
+    }
+
+    let mut raw_value = MaybeUninit::<T>::uninit();
+    unsafe {
+        copy_from_user(raw_value.as_mut_ptr() as *mut u8,
+                       virt_addr.as_ptr(),
+                       size_of::<T>());
+        if T::is_valid_raw(raw_value.as_ptr()) {
+            Some(raw_value.assume_init())
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * Writes the copy of the given `T` at the user-space `VirtAddr` only if it
+ * is well aligned and mapped as writeable user memory into the caller's
+ * address space
+ */
+pub fn user_write<T>(virt_addr: VirtAddr, value: T) -> Option<()>
+    where T: Copy {
+    if !is_user_value_writeable::<T>(virt_addr) {
+        return None;
+    }
+
+    unsafe {
+        copy_to_user(virt_addr.as_ptr_mut(),
+                     &value as *const T as *const u8,
+                     size_of::<T>());
+    }
+    Some(())
+}
+
+/**
+ * Returns whether `user_write()` could write a `T` at the given
+ * `VirtAddr`.
+ *
+ * Used by the routines to reject the invalid output pointers before doing
+ * something which can't be undone
+ */
+pub fn is_user_value_writeable<T>(virt_addr: VirtAddr) -> bool {
+    is_user_value_accessible::<T>(virt_addr, true)
+}
+
+/**
+ * Returns whether the given instruction address belongs to the routines
+ * which copy the values from/to the user-space, the only kernel code
+ * allowed to fault into the user-space
+ */
+pub fn is_user_copy_instr(instr_ptr: usize) -> bool {
+    let user_copy_range = unsafe {
+        &__user_copy_begin as *const _ as usize..&__user_copy_end as *const _ as usize
+    };
+    user_copy_range.contains(&instr_ptr)
+}
+
+/**
+ * Returns whether the given `VirtAddr` is well aligned for `T` and
+ * references user memory accessible by the caller
+ */
+fn is_user_value_accessible<T>(virt_addr: VirtAddr, need_writeable: bool) -> bool {
+    !virt_addr.is_null()
+    && virt_addr.is_aligned(align_of::<T>())
+    && is_user_range_accessible(virt_addr, size_of::<T>(), need_writeable)
+}
+
+/**
+ * Copies byte per byte `size` bytes from the user-space `src_ptr`.
+ *
+ * The bytes are read as volatile, the other threads of the caller could
+ * write them meanwhile
+ */
+#[inline(never)]
+#[link_section = ".text.user_copy"]
+unsafe fn copy_from_user(dst_ptr: *mut u8, src_ptr: *const u8, size: usize) {
+    for i in 0..size {
+        dst_ptr.add(i).write(src_ptr.add(i).read_volatile());
+    }
+}
+
+/**
+ * Copies byte per byte `size` bytes to the user-space `dst_ptr`
+ */
+#[inline(never)]
+#[link_section = ".text.user_copy"]
+unsafe fn copy_to_user(dst_ptr: *mut u8, src_ptr: *const u8, size: usize) {
+    for i in 0..size {
+        dst_ptr.add(i).write_volatile(src_ptr.add(i).read());
+    }
+}
+
+/**
+ * Returns whether the given range is entirely inside the user-space and
+ * accessible by the caller.
+ *
+ * The pages not yet mapped and the copy-on-write ones are resolved against
+ * the `VmArea`s of the caller, as the page faults would do
+ */
+fn is_user_range_accessible(virt_addr: VirtAddr,
+                            size: usize,
+                            need_writeable: bool)
+                            -> bool {
+    /* the range must not overlap the kernel space */
+    let user_space_range = LayoutManager::user_space_range();
+    let range_end = match virt_addr.checked_add(size) {
+        Some(range_end) => range_end,
+        None => return false
+    };
+    if virt_addr < user_space_range.start || range_end > *user_space_range.end {
+        return false;
+    }
+
+    let vm_access = if need_writeable {
+        VmAccess::Write
+    } else {
+        VmAccess::Read
+    };
+
+    /* check all the pages touched by the range */
+    let current_thread = Processor::instance().this_core().current_thread();
+    let user_space = current_thread.proc().user_space();
+    let mut page_virt_addr = virt_addr.align_down(Page4KiB::SIZE);
+    while *page_virt_addr < range_end {
+        let is_mapped_accessible = user_space.page_dir()
+                                             .mapped_page_table_entry(page_virt_addr)
+                                             .map_or(false, |page_table_entry| {
+                                                 page_table_entry.is_user()
+                                                 && (!need_writeable
+                                                     || page_table_entry.is_writeable())
+                                             });
+        if !is_mapped_accessible
+           && user_space.resolve_page_fault(page_virt_addr, vm_access).is_err()
+        {
+            return false;
+        }
+        page_virt_addr = page_virt_addr.offset(Page4KiB::SIZE);
+    }
+    true
+}
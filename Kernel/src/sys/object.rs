@@ -0,0 +1,271 @@
+/*! `Object` and `ObjConfig` kernel routines */
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        config::{
+            ObjConfigBits,
+            ObjConfigFlags,
+            RawObjConfig
+        },
+        info::RawObjInfo,
+        modes::ObjRecvMode,
+        types::ObjType
+    },
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    }
+};
+
+use crate::{
+    fs::vfs::{
+        Vfs,
+        VfsEntry,
+        VfsResult
+    },
+    object::{
+        device_object::DeviceObject,
+        fs_object::FsObject,
+        mmap_object::MMapObject,
+        TObject
+    },
+    processor::Processor,
+    sys::{
+        path::user_path_components,
+        user_value,
+        user_write,
+        KernFnResult
+    },
+    task::{
+        handle_table::KernHandleRef,
+        scheduler::Scheduler
+    }
+};
+
+/**
+ * Opens, or creates when `ObjConfigBits::Creat` is enabled, the `Object`
+ * described by the user `RawObjConfig` and returns his new
+ * `RawKernHandle`.
+ *
+ * The `MMap`s have no path, they are only created with the given size
+ */
+pub fn obj_config_apply(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_obj_config = user_value::<RawObjConfig>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid RawObjConfig pointer")))?;
+
+    let obj_type = raw_obj_config.obj_type();
+    if obj_type == ObjType::MMap {
+        if !raw_obj_config.flags().is_enabled(ObjConfigBits::Creat) {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("MMaps could only be created")));
+        }
+
+        let data_size = raw_obj_config.data_size()
+                                      .filter(|data_size| *data_size > 0)
+                                      .ok_or((OsErrorClass::InvalidArgument,
+                                              Some("MMaps must have a size")))?;
+        return add_object(Arc::new(MMapObject::new_anonymous(data_size)));
+    }
+    if !matches!(obj_type, ObjType::Device | ObjType::Dir | ObjType::File | ObjType::Link)
+    {
+        return Err((OsErrorClass::OperationNotEnabled,
+                    Some("Object type not supported")));
+    }
+    let raw_path = raw_obj_config.path()
+                                 .ok_or((OsErrorClass::InvalidArgument,
+                                         Some("Filesystem objects must have a path")))?;
+    let path_components = user_path_components(raw_path)?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    if obj_type == ObjType::Device {
+        /* the device nodes are published only by the drivers */
+        if raw_obj_config.flags().is_enabled(ObjConfigBits::Creat) {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Devices could not be created")));
+        }
+
+        let vfs_entry = Vfs::instance().resolve(&current_proc, &path_components, true)?;
+        let device =
+            vfs_entry.node().device().ok_or((OsErrorClass::TypesNotMatch,
+                                              Some("Object type not matches")))?;
+        return add_object(Arc::new(DeviceObject::new(vfs_entry,
+                                                     device,
+                                                     *raw_obj_config.flags())));
+    }
+
+    let vfs_entry = if raw_obj_config.flags().is_enabled(ObjConfigBits::Creat) {
+        let vfs_entry =
+            Vfs::instance().create(&current_proc, &path_components, obj_type)?;
+
+        /* the grants not given by the user are the filesystem's defaults */
+        if raw_obj_config.grants().raw_bits() != 0 {
+            let mut raw_obj_info = vfs_entry.node().obj_info(None);
+            *raw_obj_info.prot_grants_mut() = *raw_obj_config.grants();
+            vfs_entry.node().update_obj_info(&raw_obj_info)?;
+        }
+        vfs_entry
+    } else {
+        /* the links are opened, not followed */
+        let vfs_entry = Vfs::instance().resolve(&current_proc,
+                                                &path_components,
+                                                obj_type != ObjType::Link)?;
+        if vfs_entry.node().obj_type() != obj_type {
+            return Err((OsErrorClass::TypesNotMatch, Some("Object type not matches")));
+        }
+        vfs_entry
+    };
+
+    if let Some(data_size) = raw_obj_config.data_size() {
+        vfs_entry.node().set_data_size(data_size)?;
+    }
+
+    add_fs_object(vfs_entry, *raw_obj_config.flags())
+}
+
+/**
+ * Removes the name of the `Object` referenced by the
+ * `SysCallPayload::raw_handle()`, the data is released with the last
+ * handle
+ */
+pub fn object_drop_name(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let path_components = fs_object.vfs_entry().path().to_path_components();
+    Vfs::instance().remove(&current_proc, &path_components).map(|_| 0)
+}
+
+/**
+ * Writes into the user `RawObjInfo` the metadata of the `Object`
+ * referenced by the `SysCallPayload::raw_handle()`
+ */
+pub fn object_info(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let object = object_by_handle(syscall_payload.raw_handle())?;
+
+    user_write::<RawObjInfo>(syscall_payload.raw_arg(0).into(), object.obj_info())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid RawObjInfo pointer")))
+        .map(|_| 0)
+}
+
+/**
+ * Updates the metadata of the `Object` referenced by the
+ * `SysCallPayload::raw_handle()` with the user `RawObjInfo`
+ */
+pub fn object_update_info(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_obj_info = user_value::<RawObjInfo>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid RawObjInfo pointer")))?;
+    let fs_object = fs_object_by_handle(syscall_payload.raw_handle())?;
+
+    fs_object.vfs_entry().node().update_obj_info(&raw_obj_info).map(|_| 0)
+}
+
+/**
+ * Sends the `Object` referenced by the `SysCallPayload::raw_handle()` to
+ * the `Proc` or the `Thread` referenced by the first argument.
+ *
+ * The receiving `Process` shares the same kernel object, so the `MMap`s
+ * are mapped to the same frames
+ */
+pub fn object_send(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let object = object_by_handle(syscall_payload.raw_handle())?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let recv_handle_ref = current_proc.handle_table()
+                                      .lock()
+                                      .get(syscall_payload.raw_arg(0) as RawKernHandle)
+                                      .cloned();
+    let recv_proc = match recv_handle_ref {
+        Some(KernHandleRef::Proc(proc)) => proc,
+        Some(KernHandleRef::Thread(thread)) => thread.proc().clone(),
+        _ => return Err((OsErrorClass::InvalidHandleReference, Some("Not a task")))
+    };
+
+    recv_proc.send_object(object)
+             .map(|_| 0)
+             .map_err(|error_class| (error_class, Some("Receiving queue full")))
+}
+
+/**
+ * Receives the first `Object` of the `ObjType` given as first argument
+ * from the caller's receiving queue and returns his new `RawKernHandle`.
+ *
+ * With `ObjRecvMode::Sync` the caller waits until an `Object` arrives
+ */
+pub fn object_recv(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let obj_type = ObjType::try_from(syscall_payload.raw_arg(0)).map_err(|_| {
+                       (OsErrorClass::InvalidArgument, Some("Invalid ObjType"))
+                   })?;
+    let recv_mode = ObjRecvMode::try_from(syscall_payload.raw_arg(1)).map_err(|_| {
+                        (OsErrorClass::InvalidArgument, Some("Invalid ObjRecvMode"))
+                    })?;
+
+    let current_thread = Processor::instance().this_core().current_thread();
+    let current_proc = current_thread.proc();
+    if recv_mode == ObjRecvMode::Poll {
+        return match current_proc.recv_object(obj_type) {
+            Some(object) => add_object(object),
+            None => Err((OsErrorClass::NoDataAvailable, None))
+        };
+    }
+
+    loop {
+        let recv_object = current_proc.recv_object_or_block(obj_type, &current_thread);
+        if let Some(object) = recv_object {
+            return add_object(object);
+        }
+
+        /* parked until the next <Process::send_object()> */
+        Scheduler::instance().schedule();
+    }
+}
+
+/**
+ * Returns the `TObject` referenced by the given `RawKernHandle` of the
+ * caller's `HandleTable`
+ */
+pub fn object_by_handle(raw_handle: Option<RawKernHandle>)
+                        -> VfsResult<Arc<dyn TObject>> {
+    let raw_handle = raw_handle.ok_or((OsErrorClass::InvalidHandleReference, None))?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let handle_ref = current_proc.handle_table().lock().get(raw_handle).cloned();
+    match handle_ref {
+        Some(KernHandleRef::Object(object)) => Ok(object),
+        _ => Err((OsErrorClass::InvalidHandleReference, None))
+    }
+}
+
+/**
+ * Returns the `FsObject` referenced by the given `RawKernHandle` of the
+ * caller's `HandleTable`
+ */
+pub fn fs_object_by_handle(raw_handle: Option<RawKernHandle>)
+                           -> VfsResult<Arc<FsObject>> {
+    object_by_handle(raw_handle)?.into_fs_object()
+                                 .ok_or((OsErrorClass::TypesNotMatch,
+                                         Some("Not a filesystem object")))
+}
+
+/**
+ * Stores into the caller's `HandleTable` a new `FsObject` for the given
+ * `VfsEntry` and returns his `RawKernHandle`
+ */
+pub fn add_fs_object(vfs_entry: VfsEntry, config_flags: ObjConfigFlags) -> KernFnResult {
+    add_object(Arc::new(FsObject::new(vfs_entry, config_flags)))
+}
+
+/**
+ * Stores into the caller's `HandleTable` the given `TObject` and returns
+ * his `RawKernHandle`
+ */
+pub fn add_object(object: Arc<dyn TObject>) -> KernFnResult {
+    let current_proc = Processor::instance().this_core().current_proc();
+    let mut handle_table = current_proc.handle_table().lock();
+    handle_table.add(KernHandleRef::Object(object))
+                .map(|raw_handle| raw_handle as usize)
+                .map_err(|error_class| (error_class, Some("Failed to store the handle")))
+}
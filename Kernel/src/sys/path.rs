@@ -0,0 +1,108 @@
+/*! `Path` kernel routines */
+
+use alloc::vec::Vec;
+
+use core::{
+    mem::{
+        align_of,
+        size_of
+    },
+    str
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    limit::VFS_PATH_LEN_MAX,
+    path::{
+        PathComponent,
+        PathExistsState
+    },
+    sys::SysCallPayload
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    fs::vfs::{
+        path::{
+            parse_str_path,
+            validate_name
+        },
+        Vfs,
+        VfsResult
+    },
+    processor::Processor,
+    sys::{
+        user_slice,
+        user_write,
+        KernFnResult
+    }
+};
+
+/**
+ * Writes into the user `PathExistsState` the existence state of the given
+ * string path, relative paths are resolved from the caller's working
+ * directory
+ */
+pub fn path_exists(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_str_path = user_slice(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid path buffer")))?;
+
+    let str_path = str::from_utf8(raw_str_path).map_err(|_| {
+                                                   (OsErrorClass::InvalidArgument,
+                                                    Some("Path is not UTF-8"))
+                                               })?;
+    let path_components = parse_str_path(str_path)?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let exists_state = Vfs::instance().path_exists(&current_proc, &path_components);
+
+    user_write::<PathExistsState>(syscall_payload.raw_arg(2).into(), exists_state)
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid PathExistsState pointer")))
+        .map(|_| 0)
+}
+
+/**
+ * Copies into the kernel the user-space `PathComponent`s of the given
+ * slice, which is validated with his names
+ */
+pub fn user_path_components(raw_path: &[PathComponent]) -> VfsResult<Vec<PathComponent>> {
+    /* each name takes at least one byte and one separator */
+    if raw_path.len() > VFS_PATH_LEN_MAX {
+        return Err((OsErrorClass::LimitOverflow, Some("VFS path too long")));
+    }
+
+    let raw_path_addr = VirtAddr::from(raw_path.as_ptr() as usize);
+    if !raw_path_addr.is_aligned(align_of::<PathComponent>())
+       || user_slice(raw_path_addr, raw_path.len() * size_of::<PathComponent>()).is_none()
+    {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid path buffer")));
+    }
+
+    raw_path.iter()
+            .map(|path_component| {
+                if let PathComponent::ObjectName(raw_name) = path_component {
+                    user_name(raw_name.as_ptr() as usize, raw_name.len())
+                } else {
+                    Ok(path_component.clone())
+                }
+            })
+            .collect()
+}
+
+/**
+ * Copies into the kernel the user-space name at the given address
+ */
+fn user_name(raw_name_ptr: usize, raw_name_len: usize) -> VfsResult<PathComponent> {
+    let raw_name = user_slice(raw_name_ptr.into(), raw_name_len)
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid name buffer")))?;
+
+    let name = str::from_utf8(raw_name).map_err(|_| {
+                                           (OsErrorClass::InvalidArgument,
+                                            Some("Name is not UTF-8"))
+                                       })?;
+    validate_name(name)?;
+    Ok(PathComponent::from(name))
+}
@@ -0,0 +1,135 @@
+/*! `Proc` kernel routines */
+
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
+
+use core::convert::TryFrom;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::grants::RawObjGrants,
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    },
+    task::{
+        fs_types::FsType,
+        modes::FsMountMode
+    }
+};
+use meetix_fs::dev::TBlockDevice;
+
+use crate::{
+    fs::{
+        dev_fs::DevFs,
+        fat_fs::FatFs,
+        iso_fs::IsoFs,
+        meetix_fs::MeetiXFs,
+        vfs::{
+            node::TFileSystem,
+            Vfs,
+            VfsResult
+        }
+    },
+    processor::Processor,
+    sys::{
+        object::fs_object_by_handle,
+        KernFnResult
+    },
+    task::handle_table::KernHandleRef
+};
+
+/**
+ * Mounts a new filesystem of the given `FsType` at the `Dir` referenced by
+ * the fourth argument.
+ *
+ * The filesystems stored into a media read it from the object referenced
+ * by the third argument, which is valid only when the second one is
+ * `true`
+ */
+pub fn proc_mount(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let fs_type = match FsType::try_from(syscall_payload.raw_arg(0)) {
+        Ok(fs_type) => fs_type,
+        Err(_) => return Err((OsErrorClass::InvalidArgument, Some("Invalid FsType")))
+    };
+    let mnt_mode = match FsMountMode::try_from(syscall_payload.raw_arg(5)) {
+        Ok(mnt_mode) => mnt_mode,
+        Err(_) => {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid FsMountMode")))
+        },
+    };
+    let mnt_point =
+        fs_object_by_handle(Some(syscall_payload.raw_arg(3) as RawKernHandle))?;
+
+    let device = if syscall_payload.raw_arg(1) != 0 {
+        Some(block_device_by_handle(syscall_payload.raw_arg(2) as RawKernHandle)?)
+    } else {
+        None
+    };
+    let file_system = file_system_by_type(fs_type, device)?;
+
+    /* the grants not given by the user are the filesystem's defaults */
+    let mnt_grants = RawObjGrants::from_raw_truncate(syscall_payload.raw_arg(4));
+    if mnt_grants.raw_bits() != 0 {
+        let root_node = file_system.root_node();
+        let mut raw_obj_info = root_node.obj_info(None);
+        *raw_obj_info.prot_grants_mut() = mnt_grants;
+        root_node.update_obj_info(&raw_obj_info)?;
+    }
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let mnt_path = mnt_point.vfs_entry().path().to_path_components();
+    Vfs::instance().mount(&current_proc, file_system, &mnt_path, mnt_mode).map(|_| 0)
+}
+
+/**
+ * Unmounts the filesystem mounted at the `Dir` referenced by the first
+ * argument
+ */
+pub fn proc_unmount(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let mnt_point =
+        fs_object_by_handle(Some(syscall_payload.raw_arg(0) as RawKernHandle))?;
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let mnt_path = mnt_point.vfs_entry().path().to_path_components();
+    Vfs::instance().unmount(&current_proc, &mnt_path).map(|_| 0)
+}
+
+/**
+ * Returns the `TBlockDevice` of the object referenced by the given
+ * `RawKernHandle` of the caller's `HandleTable`
+ */
+fn block_device_by_handle(raw_handle: RawKernHandle)
+                          -> VfsResult<Box<dyn TBlockDevice + Send>> {
+    let current_proc = Processor::instance().this_core().current_proc();
+    let handle_ref = current_proc.handle_table().lock().get(raw_handle).cloned();
+    match handle_ref {
+        Some(KernHandleRef::Object(object)) => {
+            object.into_block_device()
+                  .ok_or((OsErrorClass::TypesNotMatch, Some("Not a block device")))
+        },
+        _ => Err((OsErrorClass::InvalidHandleReference, None))
+    }
+}
+
+/**
+ * Constructs the `TFileSystem` for the given `FsType`
+ */
+fn file_system_by_type(fs_type: FsType,
+                       device: Option<Box<dyn TBlockDevice + Send>>)
+                       -> VfsResult<Arc<dyn TFileSystem>> {
+    let needs_device =
+        || (OsErrorClass::InvalidArgument, Some("The filesystem needs a block device"));
+
+    match fs_type {
+        FsType::FatX => Ok(Arc::new(FatFs::mount(device.ok_or_else(needs_device)?)?)),
+        FsType::CdROM => Ok(Arc::new(IsoFs::mount(device.ok_or_else(needs_device)?)?)),
+        FsType::MeetiX => {
+            Ok(Arc::new(MeetiXFs::mount(device.ok_or_else(needs_device)?)?))
+        },
+        FsType::Devices => Ok(Arc::new(DevFs::new())),
+        _ => Err((OsErrorClass::OperationNotEnabled, Some("Filesystem not supported")))
+    }
+}
@@ -0,0 +1,202 @@
+/*! `Socket` kernel routines */
+
+use alloc::sync::Arc;
+
+use core::convert::TryFrom;
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::{
+        modes::ObjRecvMode,
+        socket::{
+            SocketAddr,
+            SocketType
+        }
+    },
+    sys::{
+        RawKernHandle,
+        SysCallPayload
+    }
+};
+
+use crate::{
+    net::{
+        NetResult,
+        NetStack
+    },
+    object::socket_object::SocketObject,
+    sys::{
+        is_user_value_writeable,
+        object::{
+            add_object,
+            object_by_handle
+        },
+        user_slice,
+        user_slice_mut,
+        user_value,
+        user_write,
+        KernFnResult
+    }
+};
+
+/**
+ * Opens a new unbound `Socket` of the given `SocketType` and returns his
+ * handle
+ */
+pub fn socket_open(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let socket_type = match SocketType::try_from(syscall_payload.raw_arg(0)) {
+        Ok(socket_type) => socket_type,
+        Err(_) => return Err((OsErrorClass::InvalidArgument, Some("Invalid SocketType")))
+    };
+
+    let socket_id = net_stack()?.open_socket(socket_type)?;
+    add_object(Arc::new(SocketObject::new(socket_id, socket_type)))
+}
+
+/**
+ * Binds the `Socket` to the user `SocketAddr`
+ */
+pub fn socket_bind(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let local_addr = user_value::<SocketAddr>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SocketAddr pointer")))?;
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.bind(socket_object.socket_id(), local_addr).map(|_| 0)
+}
+
+/**
+ * Connects the `Socket` to the user remote `SocketAddr`, waits the
+ * establishment of the TCP connections
+ */
+pub fn socket_connect(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let remote_addr = user_value::<SocketAddr>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SocketAddr pointer")))?;
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.connect(socket_object.socket_id(), remote_addr).map(|_| 0)
+}
+
+/**
+ * Puts the `Socket` into the listening state with the given backlog
+ */
+pub fn socket_listen(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.listen(socket_object.socket_id(), syscall_payload.raw_arg(0)).map(|_| 0)
+}
+
+/**
+ * Accepts an incoming connection according to the given `ObjRecvMode`,
+ * writes the remote `SocketAddr` and returns the handle of the connected
+ * `Socket`
+ */
+pub fn socket_accept(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let recv_mode = recv_mode_by_raw(syscall_payload.raw_arg(0))?;
+    let remote_addr_ptr = syscall_payload.raw_arg(1).into();
+    if !is_user_value_writeable::<SocketAddr>(remote_addr_ptr) {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid SocketAddr pointer")));
+    }
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    let (conn_id, conn_remote_addr) =
+        net_stack()?.accept(socket_object.socket_id(), recv_mode)?;
+    let raw_conn_handle =
+        add_object(Arc::new(SocketObject::new(conn_id, SocketType::Tcp)))?;
+
+    /* the connection is given anyway, the address is only informative */
+    let _ = user_write(remote_addr_ptr, conn_remote_addr);
+    Ok(raw_conn_handle)
+}
+
+/**
+ * Sends the user buffer to the connected end-point and returns the amount
+ * of bytes sent
+ */
+pub fn socket_send(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.send(socket_object.socket_id(), buffer)
+}
+
+/**
+ * Receives, according to the given `ObjRecvMode`, the data of the
+ * connected end-point into the user buffer and returns the amount of bytes
+ * received
+ */
+pub fn socket_recv(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let recv_mode = recv_mode_by_raw(syscall_payload.raw_arg(0))?;
+    let buffer = user_slice_mut(syscall_payload.raw_arg(1).into(), syscall_payload.raw_arg(2))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.recv(socket_object.socket_id(), buffer, recv_mode)
+}
+
+/**
+ * Sends the user buffer as datagram to the user `SocketAddr` and returns
+ * the amount of bytes sent
+ */
+pub fn socket_send_to(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let buffer = user_slice(syscall_payload.raw_arg(0).into(), syscall_payload.raw_arg(1))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let remote_addr = user_value::<SocketAddr>(syscall_payload.raw_arg(2).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid SocketAddr pointer")))?;
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    net_stack()?.send_to(socket_object.socket_id(), buffer, remote_addr)
+}
+
+/**
+ * Receives, according to the given `ObjRecvMode`, the next datagram into
+ * the user buffer, writes the sender `SocketAddr` and returns the size of
+ * the datagram
+ */
+pub fn socket_recv_from(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let recv_mode = recv_mode_by_raw(syscall_payload.raw_arg(0))?;
+    let buffer = user_slice_mut(syscall_payload.raw_arg(1).into(), syscall_payload.raw_arg(2))
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid data buffer")))?;
+    let src_addr_ptr = syscall_payload.raw_arg(3).into();
+    if !is_user_value_writeable::<SocketAddr>(src_addr_ptr) {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid SocketAddr pointer")));
+    }
+    let socket_object = socket_object_by_handle(syscall_payload.raw_handle())?;
+
+    let (recv_len, datagram_src_addr) =
+        net_stack()?.recv_from(socket_object.socket_id(), buffer, recv_mode)?;
+
+    /* the datagram is consumed anyway, the address is only informative */
+    let _ = user_write(src_addr_ptr, datagram_src_addr);
+    Ok(recv_len)
+}
+
+/**
+ * Returns the `SocketObject` referenced by the given `RawKernHandle` of
+ * the caller's `HandleTable`
+ */
+fn socket_object_by_handle(raw_handle: Option<RawKernHandle>)
+                           -> NetResult<Arc<SocketObject>> {
+    object_by_handle(raw_handle)?.into_socket_object()
+                                 .ok_or((OsErrorClass::TypesNotMatch,
+                                         Some("Not a socket")))
+}
+
+/**
+ * Returns the global `NetStack`, which is not available without network
+ * devices
+ */
+fn net_stack() -> NetResult<&'static NetStack> {
+    NetStack::try_instance().ok_or((OsErrorClass::OperationNotEnabled,
+                                    Some("Network not available")))
+}
+
+/**
+ * Converts the given raw value into an `ObjRecvMode`
+ */
+fn recv_mode_by_raw(raw_recv_mode: usize) -> NetResult<ObjRecvMode> {
+    ObjRecvMode::try_from(raw_recv_mode).map_err(|_| {
+                                            (OsErrorClass::InvalidArgument,
+                                             Some("Invalid ObjRecvMode"))
+                                        })
+}
@@ -0,0 +1,126 @@
+/*! `Task` and `TaskConfig` kernel routines */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+
+use core::{
+    mem::{
+        align_of,
+        size_of
+    },
+    str
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    limit::PROC_ARG_COUNT_MAX,
+    sys::SysCallPayload,
+    task::{
+        config::{
+            RawTaskConfig,
+            TaskConfigBits
+        },
+        types::TaskType
+    }
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    processor::Processor,
+    sys::{
+        object::fs_object_by_handle,
+        user_slice,
+        user_value,
+        KernFnResult
+    },
+    task::{
+        handle_table::KernHandleRef,
+        loader::{
+            spawn_proc,
+            LoaderResult
+        },
+        scheduler::SchedPolicy
+    }
+};
+
+/**
+ * Spawns the new `Process` described by the user `RawTaskConfig` from
+ * his `RawTaskConfig::file_to_exec()` and returns his new
+ * `RawKernHandle`
+ */
+pub fn task_config_apply(syscall_payload: &mut SysCallPayload) -> KernFnResult {
+    let raw_task_config = user_value::<RawTaskConfig>(syscall_payload.raw_arg(0).into())
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid RawTaskConfig pointer")))?;
+
+    if raw_task_config.task_type() != TaskType::Proc {
+        return Err((OsErrorClass::OperationNotEnabled, Some("Task type not supported")));
+    } else if !raw_task_config.flags().is_enabled(TaskConfigBits::IsSpawn) {
+        return Err((OsErrorClass::OperationNotEnabled,
+                    Some("Processes could only be spawned")));
+    }
+
+    let sched_policy = SchedPolicy::from_task_config(&raw_task_config)
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid real-time parameters")))?;
+
+    let file_object = fs_object_by_handle(Some(raw_task_config.file_to_exec()))?;
+    let cmdline_args = user_cmdline_args(raw_task_config.cmdline_args())?;
+    let cmdline_args: Vec<_> = cmdline_args.iter().map(String::as_str).collect();
+
+    let current_proc = Processor::instance().this_core().current_proc();
+    let proc = spawn_proc(current_proc.clone(),
+                          file_object.vfs_entry().node(),
+                          &cmdline_args,
+                          sched_policy)?;
+
+    let mut handle_table = current_proc.handle_table().lock();
+    handle_table.add(KernHandleRef::Proc(proc))
+                .map(|raw_handle| raw_handle as usize)
+                .map_err(|error_class| (error_class, Some("Failed to store the handle")))
+}
+
+/**
+ * Copies into the kernel the user-space command line arguments, which are
+ * validated with their strings
+ */
+fn user_cmdline_args(raw_cmdline_args: Option<&[&str]>) -> LoaderResult<Vec<String>> {
+    let raw_cmdline_args = match raw_cmdline_args {
+        Some(raw_cmdline_args) => raw_cmdline_args,
+        None => return Ok(Vec::new())
+    };
+    if raw_cmdline_args.len() > PROC_ARG_COUNT_MAX {
+        return Err((OsErrorClass::LimitOverflow, Some("Too many process arguments")));
+    }
+
+    let raw_args_addr = VirtAddr::from(raw_cmdline_args.as_ptr() as usize);
+    if !raw_args_addr.is_aligned(align_of::<&str>())
+       || user_slice(raw_args_addr, raw_cmdline_args.len() * size_of::<&str>()).is_none()
+    {
+        return Err((OsErrorClass::InvalidArgument, Some("Invalid arguments buffer")));
+    }
+
+    raw_cmdline_args.iter()
+                    .map(|raw_cmdline_arg| {
+                        user_cmdline_arg(raw_cmdline_arg.as_ptr() as usize,
+                                         raw_cmdline_arg.len())
+                    })
+                    .collect()
+}
+
+/**
+ * Copies into the kernel the user-space argument at the given address
+ */
+fn user_cmdline_arg(raw_arg_ptr: usize, raw_arg_len: usize) -> LoaderResult<String> {
+    let raw_arg = user_slice(raw_arg_ptr.into(), raw_arg_len)
+        .ok_or((OsErrorClass::InvalidArgument, Some("Invalid argument buffer")))?;
+
+    let cmdline_arg = str::from_utf8(raw_arg).map_err(|_| {
+                                                 (OsErrorClass::InvalidArgument,
+                                                  Some("Argument is not UTF-8"))
+                                             })?;
+    Ok(String::from(cmdline_arg))
+}
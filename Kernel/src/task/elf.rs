@@ -0,0 +1,260 @@
+/*! ELF64 executables parser */
+
+use core::{
+    mem::size_of,
+    ops::Range,
+    ptr::read_unaligned
+};
+
+use crate::addr::virt_addr::VirtAddr;
+
+/**
+ * Validated ELF64 executable image
+ */
+pub struct ElfFile<'a> {
+    m_image: &'a [u8],
+    m_header: ElfHeader
+}
+
+impl<'a> ElfFile<'a> /* Constants */ {
+    const MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+    const CLASS_64: u8 = 2;
+    const DATA_LITTLE_ENDIAN: u8 = 1;
+    const TYPE_EXECUTABLE: u16 = 2;
+
+    #[cfg(target_arch = "x86_64")]
+    const MACHINE: u16 = 62;
+    #[cfg(target_arch = "aarch64")]
+    const MACHINE: u16 = 183;
+    #[cfg(target_arch = "riscv")]
+    const MACHINE: u16 = 243;
+}
+
+impl<'a> ElfFile<'a> /* Constructors */ {
+    /**
+     * Validates the given image as a static ELF64 executable for the
+     * running architecture
+     */
+    pub fn parse(image: &'a [u8]) -> Result<Self, &'static str> {
+        if image.len() < size_of::<ElfHeader>() {
+            return Err("ELF image too small");
+        }
+
+        let header = unsafe { read_unaligned(image.as_ptr() as *const ElfHeader) };
+        if header.m_ident[..4] != Self::MAGIC {
+            return Err("Not an ELF image");
+        } else if header.m_ident[4] != Self::CLASS_64
+                  || header.m_ident[5] != Self::DATA_LITTLE_ENDIAN
+        {
+            return Err("Not a little endian ELF64 image");
+        } else if header.m_type != Self::TYPE_EXECUTABLE {
+            return Err("Not an ELF executable");
+        } else if header.m_machine != Self::MACHINE {
+            return Err("ELF executable for another architecture");
+        } else if header.m_prog_hdr_size as usize != size_of::<ElfProgHeader>() {
+            return Err("Unexpected ELF program header size");
+        }
+
+        /* all the program headers must be inside the image */
+        let prog_hdrs_end = (header.m_prog_hdr_count as usize)
+            .checked_mul(size_of::<ElfProgHeader>())
+            .and_then(|prog_hdrs_size| {
+                prog_hdrs_size.checked_add(header.m_prog_hdr_offset as usize)
+            });
+        if !matches!(prog_hdrs_end, Some(prog_hdrs_end) if prog_hdrs_end <= image.len()) {
+            return Err("ELF program headers out of image");
+        }
+
+        let elf_file = Self { m_image: image,
+                              m_header: header };
+
+        /* validate the segments too, so the loader can trust them */
+        for prog_header in elf_file.prog_headers() {
+            match prog_header.segment_type() {
+                ElfSegmentType::Dynamic | ElfSegmentType::Interp => {
+                    return Err("Dynamically linked ELF executables are not supported")
+                },
+                ElfSegmentType::Load | ElfSegmentType::Tls => {
+                    if prog_header.m_file_size > prog_header.m_mem_size
+                       || elf_file.segment_data(&prog_header).is_none()
+                    {
+                        return Err("ELF segment out of image");
+                    }
+                },
+                _ => { /* ignored segment */ }
+            }
+        }
+        Ok(elf_file)
+    }
+}
+
+impl<'a> ElfFile<'a> /* Getters */ {
+    /**
+     * Returns the `VirtAddr` of the entry point
+     */
+    pub fn entry_point(&self) -> VirtAddr {
+        (self.m_header.m_entry as usize).into()
+    }
+
+    /**
+     * Returns an iterator over the `ElfProgHeader`s
+     */
+    pub fn prog_headers(&self) -> impl Iterator<Item = ElfProgHeader> + '_ {
+        let prog_hdrs_ptr = unsafe {
+            self.m_image.as_ptr().add(self.m_header.m_prog_hdr_offset as usize)
+                as *const ElfProgHeader
+        };
+
+        (0..self.m_header.m_prog_hdr_count as usize)
+            .map(move |index| unsafe { read_unaligned(prog_hdrs_ptr.add(index)) })
+    }
+
+    /**
+     * Returns the `ElfProgHeader` of the `ElfSegmentType::Tls` segment
+     */
+    pub fn tls_prog_header(&self) -> Option<ElfProgHeader> {
+        self.prog_headers()
+            .find(|prog_header| prog_header.segment_type() == ElfSegmentType::Tls)
+    }
+
+    /**
+     * Returns the bytes of the segment stored into the image
+     */
+    pub fn segment_data(&self, prog_header: &ElfProgHeader) -> Option<&'a [u8]> {
+        let data_begin = prog_header.m_offset as usize;
+        let data_end = data_begin.checked_add(prog_header.m_file_size as usize)?;
+
+        self.m_image.get(data_begin..data_end)
+    }
+}
+
+/**
+ * ELF64 program header, describes a segment of the executable
+ */
+#[repr(C)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct ElfProgHeader {
+    m_type: u32,
+    m_flags: u32,
+    m_offset: u64,
+    m_virt_addr: u64,
+    m_phys_addr: u64,
+    m_file_size: u64,
+    m_mem_size: u64,
+    m_align: u64
+}
+
+impl ElfProgHeader /* Constants */ {
+    const FLAG_EXECUTABLE: u32 = 1 << 0;
+    const FLAG_WRITEABLE: u32 = 1 << 1;
+    const FLAG_READABLE: u32 = 1 << 2;
+}
+
+impl ElfProgHeader /* Getters */ {
+    /**
+     * Returns the `ElfSegmentType`
+     */
+    pub fn segment_type(&self) -> ElfSegmentType {
+        match self.m_type {
+            0 => ElfSegmentType::Null,
+            1 => ElfSegmentType::Load,
+            2 => ElfSegmentType::Dynamic,
+            3 => ElfSegmentType::Interp,
+            4 => ElfSegmentType::Note,
+            6 => ElfSegmentType::ProgHeaders,
+            7 => ElfSegmentType::Tls,
+            _ => ElfSegmentType::Other
+        }
+    }
+
+    /**
+     * Returns the virtual memory range occupied once loaded
+     */
+    pub fn virt_range(&self) -> Range<VirtAddr> {
+        let virt_addr: VirtAddr = (self.m_virt_addr as usize).into();
+        virt_addr..(self.m_virt_addr.saturating_add(self.m_mem_size) as usize).into()
+    }
+
+    /**
+     * Returns the size in bytes of the data stored into the image
+     */
+    pub fn file_size(&self) -> usize {
+        self.m_file_size as usize
+    }
+
+    /**
+     * Returns the size in bytes once loaded, the bytes after the
+     * `file_size()` are zeroed
+     */
+    pub fn mem_size(&self) -> usize {
+        self.m_mem_size as usize
+    }
+
+    /**
+     * Returns the alignment requested by the segment
+     */
+    pub fn align(&self) -> usize {
+        self.m_align.max(1) as usize
+    }
+
+    /**
+     * Returns whether the segment must be readable
+     */
+    pub fn is_readable(&self) -> bool {
+        self.m_flags & Self::FLAG_READABLE != 0
+    }
+
+    /**
+     * Returns whether the segment must be writeable
+     */
+    pub fn is_writeable(&self) -> bool {
+        self.m_flags & Self::FLAG_WRITEABLE != 0
+    }
+
+    /**
+     * Returns whether the segment must be executable
+     */
+    pub fn is_executable(&self) -> bool {
+        self.m_flags & Self::FLAG_EXECUTABLE != 0
+    }
+}
+
+/**
+ * Lists the ELF segment types known by the loader
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum ElfSegmentType {
+    Null,
+    Load,
+    Dynamic,
+    Interp,
+    Note,
+    ProgHeaders,
+    Tls,
+    Other
+}
+
+/**
+ * ELF64 file header
+ */
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct ElfHeader {
+    m_ident: [u8; 16],
+    m_type: u16,
+    m_machine: u16,
+    m_version: u32,
+    m_entry: u64,
+    m_prog_hdr_offset: u64,
+    m_sect_hdr_offset: u64,
+    m_flags: u32,
+    m_header_size: u16,
+    m_prog_hdr_size: u16,
+    m_prog_hdr_count: u16,
+    m_sect_hdr_size: u16,
+    m_sect_hdr_count: u16,
+    m_sect_names_index: u16
+}
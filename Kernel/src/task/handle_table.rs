@@ -0,0 +1,164 @@
+/*! Per-process kernel handles table */
+
+use alloc::{
+    sync::Arc,
+    vec::Vec
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    limit::OBJ_OPENED_COUNT_MAX,
+    sys::{
+        RawKernHandle,
+        INVALID_KERN_HANDLE
+    }
+};
+
+use crate::{
+    entity::TOsEntity,
+    object::TObject,
+    task::{
+        process::Process,
+        thread::Thread
+    }
+};
+
+/**
+ * Typed reference to a kernel resource stored into the `HandleTable`
+ */
+#[derive(Clone)]
+pub enum KernHandleRef {
+    Object(Arc<dyn TObject>),
+    Proc(Arc<Process>),
+    Thread(Arc<Thread>),
+    OsEntity(Arc<dyn TOsEntity>)
+}
+
+/**
+ * Maps the `RawKernHandle`s given to the user-space to the referenced
+ * kernel resources.
+ *
+ * Each `Process` owns his own `HandleTable`, which can contain at most
+ * `OBJ_OPENED_COUNT_MAX` opened handles
+ */
+pub struct HandleTable {
+    m_handles: Vec<Option<KernHandleRef>>,
+    m_opened_count: usize
+}
+
+impl HandleTable /* Constructors */ {
+    /**
+     * Constructs an empty `HandleTable`
+     */
+    pub const fn new() -> Self {
+        Self { m_handles: Vec::new(),
+               m_opened_count: 0 }
+    }
+}
+
+impl HandleTable /* Methods */ {
+    /**
+     * Stores the given `KernHandleRef` and returns his `RawKernHandle`
+     */
+    pub fn add(&mut self,
+               handle_ref: KernHandleRef)
+               -> Result<RawKernHandle, OsErrorClass> {
+        if self.m_opened_count >= OBJ_OPENED_COUNT_MAX {
+            return Err(OsErrorClass::LimitReached);
+        }
+
+        /* re-use the first free slot, otherwise append a new one */
+        let handle_index =
+            if let Some(free_index) = self.m_handles.iter().position(Option::is_none) {
+                self.m_handles[free_index] = Some(handle_ref);
+                free_index
+            } else {
+                self.m_handles.push(Some(handle_ref));
+                self.m_handles.len() - 1
+            };
+
+        self.m_opened_count += 1;
+        Ok(handle_index as RawKernHandle)
+    }
+
+    /**
+     * Stores a copy of the `KernHandleRef` referenced by `raw_handle` and
+     * returns the new `RawKernHandle`
+     */
+    pub fn clone_handle(&mut self,
+                        raw_handle: RawKernHandle)
+                        -> Result<RawKernHandle, OsErrorClass> {
+        let cloned_handle_ref =
+            self.get(raw_handle).ok_or(OsErrorClass::InvalidHandleReference)?.clone();
+
+        self.add(cloned_handle_ref)
+    }
+
+    /**
+     * Removes and returns the `KernHandleRef` referenced by `raw_handle`
+     */
+    pub fn remove(&mut self,
+                  raw_handle: RawKernHandle)
+                  -> Result<KernHandleRef, OsErrorClass> {
+        let handle_ref = self.m_handles
+                             .get_mut(raw_handle as usize)
+                             .and_then(Option::take)
+                             .ok_or(OsErrorClass::InvalidHandleReference)?;
+
+        /* release the trailing free slots */
+        while let Some(None) = self.m_handles.last() {
+            self.m_handles.pop();
+        }
+
+        self.m_opened_count -= 1;
+        Ok(handle_ref)
+    }
+}
+
+impl HandleTable /* Getters */ {
+    /**
+     * Returns the `KernHandleRef` referenced by `raw_handle`
+     */
+    pub fn get(&self, raw_handle: RawKernHandle) -> Option<&KernHandleRef> {
+        if raw_handle != INVALID_KERN_HANDLE {
+            self.m_handles.get(raw_handle as usize)?.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Returns whether `raw_handle` references an opened kernel resource
+     */
+    pub fn is_valid(&self, raw_handle: RawKernHandle) -> bool {
+        self.get(raw_handle).is_some()
+    }
+
+    /**
+     * Returns whether any handle references the given `TObject`
+     */
+    pub fn is_object_referenced(&self, object: &Arc<dyn TObject>) -> bool {
+        /* compare only the data, the vtables could be duplicated */
+        self.objects().any(|handle_object| {
+                          Arc::as_ptr(handle_object) as *const u8
+                          == Arc::as_ptr(object) as *const u8
+                      })
+    }
+
+    /**
+     * Returns an `Iterator` over the referenced `TObject`s
+     */
+    pub fn objects(&self) -> impl Iterator<Item = &Arc<dyn TObject>> {
+        self.m_handles.iter().filter_map(|handle_ref| match handle_ref {
+                                 Some(KernHandleRef::Object(object)) => Some(object),
+                                 _ => None
+                             })
+    }
+
+    /**
+     * Returns the amount of opened handles
+     */
+    pub fn opened_count(&self) -> usize {
+        self.m_opened_count
+    }
+}
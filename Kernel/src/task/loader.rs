@@ -0,0 +1,395 @@
+/*! User processes ELF loader */
+
+use alloc::{
+    sync::Arc,
+    vec::Vec
+};
+
+use core::{
+    cmp::min,
+    mem::size_of,
+    ptr
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    limit::{
+        PROC_ARG_COUNT_MAX,
+        PROC_ARG_LEN_MAX
+    }
+};
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    fs::vfs::{
+        node::TVfsNode,
+        path::parse_str_path,
+        Vfs
+    },
+    task::{
+        elf::{
+            ElfFile,
+            ElfSegmentType
+        },
+        process::Process,
+        scheduler::{
+            SchedPolicy,
+            Scheduler
+        },
+        thread::{
+            Thread,
+            UserThreadStart
+        }
+    },
+    vm::{
+        layout_manager::LayoutManager,
+        mem_manager::MemManager,
+        user_space::{
+            UserSpace,
+            VmProtBits,
+            VmProtFlags
+        },
+        Page4KiB
+    }
+};
+
+/**
+ * On failure the loader returns the `OsErrorClass` and an optional
+ * message, as the kernel functions do
+ */
+pub type LoaderResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * Path of the executable of the first user `Process`
+ */
+pub const INIT_PROC_PATH: &str = "/Bins/example";
+
+/**
+ * Loads a static ELF64 executable into a new user `Process`.
+ *
+ * The main `Thread` starts from the ELF entry point, which is called as
+ * `_start(argc, argv)`, the C-like signature expected by `LibRt`
+ */
+pub struct ProcLoader<'a> {
+    m_elf_file: ElfFile<'a>,
+    m_cmdline_args: &'a [&'a str]
+}
+
+impl<'a> ProcLoader<'a> /* Constants */ {
+    /**
+     * Size of the user stack of the main `Thread`
+     */
+    pub const USER_STACK_SIZE: usize = 64 * 1024;
+}
+
+impl<'a> ProcLoader<'a> /* Constructors */ {
+    /**
+     * Validates the given ELF image and the command line arguments
+     */
+    pub fn new(elf_image: &'a [u8], cmdline_args: &'a [&'a str]) -> LoaderResult<Self> {
+        if cmdline_args.len() > PROC_ARG_COUNT_MAX {
+            return Err((OsErrorClass::LimitOverflow,
+                        Some("Too many process arguments")));
+        } else if cmdline_args.iter()
+                              .any(|cmdline_arg| cmdline_arg.len() > PROC_ARG_LEN_MAX)
+        {
+            return Err((OsErrorClass::LimitOverflow, Some("Process argument too long")));
+        }
+
+        let elf_file = ElfFile::parse(elf_image).map_err(|err_msg| {
+                                                    (OsErrorClass::InvalidArgument,
+                                                     Some(err_msg))
+                                                })?;
+        Ok(Self { m_elf_file: elf_file,
+                  m_cmdline_args: cmdline_args })
+    }
+}
+
+impl<'a> ProcLoader<'a> /* Methods */ {
+    /**
+     * Creates the new `Process` with his main `Thread` already given to
+     * the `Scheduler`
+     */
+    pub fn load(&self,
+                parent_proc: Arc<Process>,
+                sched_policy: SchedPolicy)
+                -> LoaderResult<Arc<Process>> {
+        let user_space = UserSpace::new().ok_or((OsErrorClass::NotEnoughMemory, None))?;
+
+        /* map and fill the segments and the thread local storage */
+        let image_end = self.load_segments(&user_space)?;
+        let tls_ptr = self.load_tls(&user_space, image_end)?;
+
+        /* the user stack is placed at the end of the user-space */
+        let (argc, argv_ptr, stack_ptr) = self.load_user_stack(&user_space)?;
+
+        let user_start = UserThreadStart::new(self.m_elf_file.entry_point(),
+                                              stack_ptr,
+                                              [argc, *argv_ptr],
+                                              tls_ptr);
+
+        let proc = Process::new(Some(parent_proc), user_space);
+        let main_thread = Thread::new_user(proc.clone(), user_start, sched_policy);
+        if !Scheduler::instance().add_thread(main_thread.clone()) {
+            proc.remove_thread(&main_thread);
+            return Err((OsErrorClass::LimitReached,
+                        Some("Scheduling class rejected the thread")));
+        }
+        Ok(proc)
+    }
+}
+
+impl<'a> ProcLoader<'a> /* Privates */ {
+    /**
+     * Commits the `ElfSegmentType::Load` segments with the requested
+     * permissions and copies their data.
+     *
+     * Returns the page aligned end of the loaded image
+     */
+    fn load_segments(&self, user_space: &UserSpace) -> LoaderResult<VirtAddr> {
+        let mut image_end = VirtAddr::null();
+        for prog_header in
+            self.m_elf_file
+                .prog_headers()
+                .filter(|prog_header| prog_header.segment_type() == ElfSegmentType::Load)
+        {
+            let virt_range = prog_header.virt_range();
+            if !Self::is_user_range_free(virt_range.start, virt_range.end) {
+                return Err((OsErrorClass::InvalidArgument,
+                            Some("ELF segment out of user-space")));
+            }
+
+            Self::map_user_range(user_space,
+                                 virt_range.start,
+                                 virt_range.end,
+                                 prog_header.is_writeable(),
+                                 prog_header.is_executable())?;
+
+            /* the bytes after the file data are already zeroed */
+            let segment_data = self.m_elf_file.segment_data(&prog_header).unwrap();
+            Self::write_user_bytes(user_space, virt_range.start, segment_data);
+
+            let segment_end = virt_range.end.align_up(Page4KiB::SIZE);
+            if segment_end > image_end {
+                image_end = segment_end;
+            }
+        }
+
+        if image_end.is_null() {
+            Err((OsErrorClass::InvalidArgument, Some("ELF executable without segments")))
+        } else {
+            Ok(image_end)
+        }
+    }
+
+    /**
+     * Commits and initializes the thread local storage of the main `Thread`
+     * after the loaded image, if the executable have one.
+     *
+     * The x86_64 layout (variant II) is used: the thread pointer points to
+     * the end of the TLS block and stores his own address
+     */
+    fn load_tls(&self,
+                user_space: &UserSpace,
+                image_end: VirtAddr)
+                -> LoaderResult<VirtAddr> {
+        let tls_prog_header = match self.m_elf_file.tls_prog_header() {
+            Some(tls_prog_header) => tls_prog_header,
+            None => return Ok(VirtAddr::null())
+        };
+
+        let tls_align = tls_prog_header.align().max(size_of::<usize>());
+        if !tls_align.is_power_of_two() {
+            return Err((OsErrorClass::InvalidArgument, Some("Bad ELF TLS alignment")));
+        }
+
+        let tls_block_begin = image_end.align_up(tls_align.max(Page4KiB::SIZE));
+        let tls_ptr =
+            tls_block_begin.offset(Self::align_up(tls_prog_header.mem_size(), tls_align));
+        let tls_end = tls_ptr.offset(size_of::<usize>());
+        if !Self::is_user_range_free(tls_block_begin, tls_end) {
+            return Err((OsErrorClass::NotEnoughMemory,
+                        Some("ELF TLS out of user-space")));
+        }
+
+        Self::map_user_range(user_space, tls_block_begin, tls_end, true, false)?;
+
+        /* copy the initialization image and store the self pointer */
+        let tls_data = self.m_elf_file.segment_data(&tls_prog_header).unwrap();
+        Self::write_user_bytes(user_space, tls_block_begin, tls_data);
+        Self::write_user_bytes(user_space, tls_ptr, &(*tls_ptr).to_ne_bytes());
+
+        Ok(tls_ptr)
+    }
+
+    /**
+     * Commits the user stack and pushes on it the command line arguments.
+     *
+     * Returns `argc`, `argv` and the stack pointer for the entry point
+     */
+    fn load_user_stack(&self,
+                       user_space: &UserSpace)
+                       -> LoaderResult<(usize, VirtAddr, VirtAddr)> {
+        let stack_bottom = LayoutManager::user_space_range().end;
+        let stack_begin: VirtAddr = (*stack_bottom - Self::USER_STACK_SIZE).into();
+
+        Self::map_user_range(user_space, stack_begin, stack_bottom, true, false)?;
+
+        /* copy the null terminated strings at the bottom of the stack */
+        let mut stack_cursor = *stack_bottom;
+        let mut argv = Vec::with_capacity(self.m_cmdline_args.len() + 1);
+        for cmdline_arg in self.m_cmdline_args.iter() {
+            stack_cursor -= cmdline_arg.len() + 1;
+
+            Self::write_user_bytes(user_space,
+                                   stack_cursor.into(),
+                                   cmdline_arg.as_bytes());
+            Self::write_user_bytes(user_space,
+                                   (stack_cursor + cmdline_arg.len()).into(),
+                                   &[0]);
+            argv.push(stack_cursor);
+        }
+        argv.push(0);
+
+        /* then the null terminated <argv> array, 16 bytes aligned as
+         * required by the System V ABI
+         */
+        let argv_ptr: VirtAddr =
+            VirtAddr::from(stack_cursor - argv.len() * size_of::<usize>()).align_down(16usize);
+        for (index, arg_ptr) in argv.iter().enumerate() {
+            Self::write_user_bytes(user_space,
+                                   argv_ptr.offset(index * size_of::<usize>()),
+                                   &arg_ptr.to_ne_bytes());
+        }
+
+        /* the entry point is entered as it were called, so a null return
+         * address is pushed below the aligned <argv>
+         */
+        let stack_ptr: VirtAddr = (*argv_ptr - size_of::<usize>()).into();
+        Self::write_user_bytes(user_space, stack_ptr, &0usize.to_ne_bytes());
+
+        Ok((self.m_cmdline_args.len(), argv_ptr, stack_ptr))
+    }
+
+    /**
+     * Returns whether the given range lies into the user-space, below the
+     * user stack of the main `Thread`
+     */
+    fn is_user_range_free(range_begin: VirtAddr, range_end: VirtAddr) -> bool {
+        let user_space_range = LayoutManager::user_space_range();
+        let stack_begin = *user_space_range.end - Self::USER_STACK_SIZE;
+
+        range_begin <= range_end
+        && range_begin >= user_space_range.start
+        && *range_end <= stack_begin
+    }
+
+    /**
+     * Reserves, commits and maps the pages of the given range, since
+     * segments could share a page, the first one when already committed is
+     * extended with the requested permissions
+     */
+    fn map_user_range(user_space: &UserSpace,
+                      range_begin: VirtAddr,
+                      range_end: VirtAddr,
+                      is_writeable: bool,
+                      is_executable: bool)
+                      -> LoaderResult<()> {
+        let mut prot_flags = VmProtFlags::new_zero() | VmProtBits::Readable;
+        prot_flags.set(VmProtBits::Writeable, is_writeable)
+                  .set(VmProtBits::Executable, is_executable);
+
+        let mut commit_begin = range_begin.align_down(Page4KiB::SIZE);
+        let commit_end = range_end.align_up(Page4KiB::SIZE);
+        if let Some(vm_area) = user_space.vm_area_at(commit_begin) {
+            let shared_page_end = commit_begin.offset(Page4KiB::SIZE);
+            user_space.commit(commit_begin..shared_page_end,
+                              vm_area.prot_flags() | prot_flags)?;
+            commit_begin = shared_page_end;
+        }
+
+        if commit_begin < commit_end {
+            user_space.reserve(Some(commit_begin), *commit_end - *commit_begin)?;
+            user_space.commit(commit_begin..commit_end, prot_flags)?;
+        }
+
+        /* the loader writes the pages through the physical memory mapping,
+         * so they must be mapped before the first access of the process
+         */
+        user_space.populate(range_begin.align_down(Page4KiB::SIZE)..commit_end)
+    }
+
+    /**
+     * Copies the given bytes into the already committed user memory,
+     * passing through the physical memory mapping, since the `PageDir` of
+     * the `UserSpace` is not the active one
+     */
+    fn write_user_bytes(user_space: &UserSpace, virt_addr: VirtAddr, bytes: &[u8]) {
+        let layout_manager = MemManager::instance().layout_manager();
+        let page_dir = user_space.page_dir();
+
+        let mut bytes_written = 0;
+        while bytes_written < bytes.len() {
+            let dest_virt_addr = virt_addr.offset(bytes_written);
+            let page_offset = *dest_virt_addr % Page4KiB::SIZE;
+            let chunk_size =
+                min(Page4KiB::SIZE - page_offset, bytes.len() - bytes_written);
+
+            let phys_frame =
+                page_dir.mapped_page_table_entry(dest_virt_addr)
+                        .and_then(|page_table_entry| page_table_entry.phys_frame())
+                        .expect("Writing into unmapped user memory");
+            let dest_ptr = layout_manager.phys_addr_to_virt_addr(phys_frame)
+                                         .offset(page_offset)
+                                         .as_ptr_mut::<u8>();
+            unsafe {
+                ptr::copy_nonoverlapping(bytes[bytes_written..].as_ptr(),
+                                         dest_ptr,
+                                         chunk_size);
+            }
+
+            bytes_written += chunk_size;
+        }
+    }
+
+    /**
+     * Aligns up the given size to the given power of two alignment
+     */
+    fn align_up(size: usize, align: usize) -> usize {
+        (size + align - 1) & !(align - 1)
+    }
+}
+
+/**
+ * Reads the given executable file and loads it into a new child `Process`
+ * of the given one
+ */
+pub fn spawn_proc(parent_proc: Arc<Process>,
+                  file_node: &Arc<dyn TVfsNode>,
+                  cmdline_args: &[&str],
+                  sched_policy: SchedPolicy)
+                  -> LoaderResult<Arc<Process>> {
+    let mut elf_image = vec![0; file_node.data_size()];
+    let read_size = file_node.read_at(0, &mut elf_image)?;
+    elf_image.truncate(read_size);
+
+    ProcLoader::new(&elf_image, cmdline_args)?.load(parent_proc, sched_policy)
+}
+
+/**
+ * Spawns the first user `Process` as child of the kernel one, from the
+ * executable at `INIT_PROC_PATH`
+ */
+pub fn spawn_init_proc() -> LoaderResult<Arc<Process>> {
+    let kern_proc = Scheduler::instance().kern_proc();
+    let vfs_entry =
+        Vfs::instance().resolve(kern_proc, &parse_str_path(INIT_PROC_PATH)?, true)?;
+
+    spawn_proc(kern_proc.clone(),
+               vfs_entry.node(),
+               &[INIT_PROC_PATH],
+               SchedPolicy::default())
+}
@@ -1,5 +1,25 @@
 /*! Kernel multitasking management */
 
+use core::sync::atomic::{
+    AtomicU64,
+    Ordering
+};
+
+use api_data::task::TaskId;
+
+pub mod elf;
+pub mod handle_table;
+pub mod loader;
 pub mod process;
 pub mod scheduler;
 pub mod thread;
+
+/* next <TaskId> given to a <Process> or a <Thread> */
+static SM_NEXT_TASK_ID: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Returns a new system wide unique `TaskId`
+ */
+pub fn alloc_task_id() -> TaskId {
+    SM_NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst)
+}
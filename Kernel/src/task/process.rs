@@ -1,16 +1,232 @@
 /*! Process management */
 
 use alloc::{
+    collections::VecDeque,
     sync::Arc,
     vec::Vec
 };
+use core::mem;
 
-use api_data::task::TaskId;
+use api_data::{
+    error::class::OsErrorClass,
+    limit::OBJ_OPENED_COUNT_MAX,
+    object::types::ObjType,
+    task::TaskId
+};
+use sync::SpinMutex;
 
-use crate::task::thread::Thread;
+use crate::{
+    fs::vfs::path::VfsPath,
+    object::TObject,
+    task::{
+        alloc_task_id,
+        handle_table::HandleTable,
+        scheduler::Scheduler,
+        thread::Thread
+    },
+    vm::{
+        page_dir::PageDir,
+        user_space::UserSpace
+    }
+};
 
 pub struct Process {
     m_id: TaskId,
-    m_parent_proc: Arc<Process>,
-    m_threads: Vec<Arc<Thread>>
+    m_parent_proc: Option<Arc<Process>>,
+    m_session_id: TaskId,
+    m_user_space: UserSpace,
+    m_cwd: SpinMutex<VfsPath>,
+    m_threads: SpinMutex<Vec<Arc<Thread>>>,
+    m_handle_table: SpinMutex<HandleTable>,
+    m_recv_objects: SpinMutex<VecDeque<Arc<dyn TObject>>>,
+    m_recv_waiters: SpinMutex<Vec<Arc<Thread>>>
+}
+
+impl Process /* Constructors */ {
+    /**
+     * Constructs a new `Process` without `Thread`s which address space is
+     * described by the given `UserSpace`.
+     *
+     * Only the kernel `Process` have no parent, his children start a new
+     * session, while the others join the session and inherit the working
+     * directory of the parent
+     */
+    pub fn new(parent_proc: Option<Arc<Process>>, user_space: UserSpace) -> Arc<Self> {
+        let proc_id = alloc_task_id();
+        let (session_id, cwd) = match parent_proc.as_ref() {
+            Some(parent_proc) if parent_proc.parent_proc().is_some() => {
+                (parent_proc.session_id(), parent_proc.cwd().lock().clone())
+            },
+            _ => (proc_id, VfsPath::root())
+        };
+
+        Arc::new(Self { m_id: proc_id,
+                        m_parent_proc: parent_proc,
+                        m_session_id: session_id,
+                        m_user_space: user_space,
+                        m_cwd: SpinMutex::const_new(cwd),
+                        m_threads: SpinMutex::const_new(Vec::new()),
+                        m_handle_table: SpinMutex::const_new(HandleTable::new()),
+                        m_recv_objects: SpinMutex::const_new(VecDeque::new()),
+                        m_recv_waiters: SpinMutex::const_new(Vec::new()) })
+    }
+}
+
+impl Process /* Methods */ {
+    /**
+     * Adds the given `Thread` to the ones owned by this `Process`
+     */
+    pub fn add_thread(&self, thread: Arc<Thread>) {
+        self.m_threads.lock().push(thread);
+    }
+
+    /**
+     * Removes the given `Thread` from the ones owned by this `Process`
+     */
+    pub fn remove_thread(&self, thread: &Arc<Thread>) {
+        self.m_threads.lock().retain(|proc_thread| !Arc::ptr_eq(proc_thread, thread));
+    }
+
+    /**
+     * Enqueues the given `TObject` into the receiving queue, the object
+     * is referenced by the queue until received
+     */
+    pub fn send_object(&self, object: Arc<dyn TObject>) -> Result<(), OsErrorClass> {
+        let recv_waiters = {
+            let mut recv_objects = self.m_recv_objects.lock();
+            if recv_objects.len() >= OBJ_OPENED_COUNT_MAX {
+                return Err(OsErrorClass::LimitReached);
+            }
+
+            recv_objects.push_back(object);
+            mem::take(&mut *self.m_recv_waiters.lock())
+        };
+
+        /* each waiter checks again whether the object is of his type */
+        for thread in recv_waiters {
+            Scheduler::instance().wake_up_thread(thread);
+        }
+        Ok(())
+    }
+
+    /**
+     * Removes from the receiving queue the first `TObject` of the given
+     * `ObjType`, the others remain in the sending order
+     */
+    pub fn recv_object(&self, obj_type: ObjType) -> Option<Arc<dyn TObject>> {
+        let mut recv_objects = self.m_recv_objects.lock();
+        let object_index =
+            recv_objects.iter().position(|object| object.obj_type() == obj_type)?;
+        recv_objects.remove(object_index)
+    }
+
+    /**
+     * Removes from the receiving queue the first `TObject` of the given
+     * `ObjType` as `Process::recv_object()` does.
+     *
+     * When it is missing the given `Thread` is marked as blocked until
+     * the next `Process::send_object()`
+     */
+    pub fn recv_object_or_block(&self,
+                                obj_type: ObjType,
+                                thread: &Arc<Thread>)
+                                -> Option<Arc<dyn TObject>> {
+        let mut recv_objects = self.m_recv_objects.lock();
+        if let Some(object_index) =
+            recv_objects.iter().position(|object| object.obj_type() == obj_type)
+        {
+            return recv_objects.remove(object_index);
+        }
+
+        /* blocked under the lock, so a concurrent sender wakes it up */
+        thread.mark_as_blocked();
+        self.m_recv_waiters.lock().push(thread.clone());
+        None
+    }
+
+    /**
+     * Returns whether this `Process` is the one with the given `TaskId`
+     * or one of his descendants
+     */
+    pub fn is_or_descends_from(&self, proc_id: TaskId) -> bool {
+        if self.m_id == proc_id {
+            return true;
+        }
+
+        let mut ancestor_proc = self.parent_proc();
+        while let Some(proc) = ancestor_proc {
+            if proc.id() == proc_id {
+                return true;
+            }
+            ancestor_proc = proc.parent_proc();
+        }
+        false
+    }
+}
+
+impl Process /* Getters */ {
+    /**
+     * Returns the `TaskId` of this `Process`
+     */
+    pub fn id(&self) -> TaskId {
+        self.m_id
+    }
+
+    /**
+     * Returns the parent `Process` if any
+     */
+    pub fn parent_proc(&self) -> Option<&Arc<Process>> {
+        self.m_parent_proc.as_ref()
+    }
+
+    /**
+     * Returns the `TaskId` of the first `Process` of the session
+     */
+    pub fn session_id(&self) -> TaskId {
+        self.m_session_id
+    }
+
+    /**
+     * Returns the `PageDir` of this `Process`
+     */
+    pub fn page_dir(&self) -> &PageDir {
+        self.m_user_space.page_dir()
+    }
+
+    /**
+     * Returns the `UserSpace` of this `Process`
+     */
+    pub fn user_space(&self) -> &UserSpace {
+        &self.m_user_space
+    }
+
+    /**
+     * Returns the working directory of this `Process`
+     */
+    pub fn cwd(&self) -> &SpinMutex<VfsPath> {
+        &self.m_cwd
+    }
+
+    /**
+     * Returns the amount of `Thread`s owned by this `Process`
+     */
+    pub fn threads_count(&self) -> usize {
+        self.m_threads.lock().len()
+    }
+
+    /**
+     * Returns the `HandleTable` of this `Process`
+     */
+    pub fn handle_table(&self) -> &SpinMutex<HandleTable> {
+        &self.m_handle_table
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        /* the objects which survive this process must forget it */
+        for object in self.m_handle_table.lock().objects() {
+            object.release_from_proc(self);
+        }
+    }
 }
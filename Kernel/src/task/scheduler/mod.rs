@@ -6,39 +6,531 @@ use alloc::{
     vec::Vec
 };
 
-use crate::task::{
-    scheduler::{
-        real_time::RealTimeScheduler,
-        round_robin::RoundRobinScheduler
+use core::{
+    sync::atomic::{
+        AtomicU64,
+        Ordering
     },
-    thread::Thread
+    time::Duration
+};
+
+use api_data::task::{
+    config::{
+        RawTaskConfig,
+        TaskConfigBits,
+        TaskConfigFlags
+    },
+    exit_status::TaskExitStatus
+};
+use sync::SpinMutex;
+
+use crate::{
+    processor::Processor,
+    task::{
+        process::Process,
+        scheduler::{
+            real_time::RealTimeScheduler,
+            round_robin::RoundRobinScheduler
+        },
+        thread::{
+            THwThreadContext,
+            Thread
+        }
+    },
+    vm::user_space::UserSpace
 };
 
 pub mod real_time;
 pub mod round_robin;
 
-static mut SM_SCHEDULER: Scheduler = Scheduler { m_schedulers: Vec::new() };
+static mut SM_SCHEDULER: Scheduler = Scheduler { m_schedulers:
+                                                     SpinMutex::const_new(Vec::new()),
+                                                 m_kern_proc: None,
+                                                 m_ticks: AtomicU64::new(0) };
+
+/**
+ * Scheduling class and parameters of a `Thread`
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum SchedPolicy {
+    /**
+     * Time-sharing `Thread`, managed by the `RoundRobinScheduler`
+     */
+    RoundRobin(SchedPrio),
+
+    /**
+     * Periodic `Thread`, managed by the `RealTimeScheduler`
+     */
+    RealTime(RealTimeParams)
+}
+
+impl SchedPolicy /* Constructors */ {
+    /**
+     * Constructs the `SchedPolicy` requested by the given `RawTaskConfig`,
+     * `None` when his real-time parameters are not valid
+     */
+    pub fn from_task_config(raw_task_config: &RawTaskConfig) -> Option<Self> {
+        match raw_task_config.rt_params() {
+            Some((period, budget)) => RealTimeParams::new(period, budget).map(Self::RealTime),
+            None => Some(Self::RoundRobin(SchedPrio::from(*raw_task_config.flags())))
+        }
+    }
+}
+
+impl Default for SchedPolicy {
+    fn default() -> Self {
+        Self::RoundRobin(SchedPrio::default())
+    }
+}
+
+/**
+ * Priority levels of the time-sharing `Thread`s
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum SchedPrio {
+    High,
+    Normal,
+    Low
+}
+
+impl SchedPrio /* Getters */ {
+    /**
+     * Returns the amount of timer ticks given to a `Thread` with this
+     * priority each time it is picked
+     */
+    pub fn time_slice(&self) -> usize {
+        match self {
+            Self::High => 6,
+            Self::Normal => 4,
+            Self::Low => 2
+        }
+    }
+}
+
+impl Default for SchedPrio {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl From<TaskConfigFlags> for SchedPrio {
+    fn from(task_config_flags: TaskConfigFlags) -> Self {
+        if task_config_flags.is_enabled(TaskConfigBits::HighPrioTask) {
+            Self::High
+        } else if task_config_flags.is_enabled(TaskConfigBits::LowPrioTask) {
+            Self::Low
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/**
+ * Reservation of a real-time `Thread`.
+ *
+ * The `Thread` is guaranteed to run for `budget` ticks each `period`
+ * ticks, which is also his relative deadline
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct RealTimeParams {
+    m_period: u64,
+    m_budget: u64
+}
+
+impl RealTimeParams /* Constructors */ {
+    /**
+     * Constructs a `RealTimeParams` if `0 < budget <= period`
+     */
+    pub fn new(period: u64, budget: u64) -> Option<Self> {
+        if budget > 0 && budget <= period {
+            Some(Self { m_period: period,
+                        m_budget: budget })
+        } else {
+            None
+        }
+    }
+}
+
+impl RealTimeParams /* Getters */ {
+    /**
+     * Returns the period in timer ticks
+     */
+    pub fn period(&self) -> u64 {
+        self.m_period
+    }
+
+    /**
+     * Returns the execution budget in timer ticks for each period
+     */
+    pub fn budget(&self) -> u64 {
+        self.m_budget
+    }
+
+    /**
+     * Returns the CPU utilization reserved, in thousandths
+     */
+    pub fn utilization(&self) -> u64 {
+        self.m_budget * 1000 / self.m_period
+    }
+}
 
 pub struct Scheduler {
-    m_schedulers: Vec<Box<dyn TScheduler>>
+    m_schedulers: SpinMutex<Vec<Box<dyn TScheduler>>>,
+    m_kern_proc: Option<Arc<Process>>,
+    m_ticks: AtomicU64
+}
+
+impl Scheduler /* Constants */ {
+    /**
+     * Frequency in Hz of the timer which drives the scheduling
+     */
+    pub const TICKS_PER_SECOND: u64 = 200;
 }
 
 impl Scheduler /* Constructors */ {
     pub fn init_instance() {
         unsafe {
-            SM_SCHEDULER.m_schedulers.push(Box::new(RoundRobinScheduler::new()));
-            SM_SCHEDULER.m_schedulers.push(Box::new(RealTimeScheduler::new()));
+            /* the order of the schedulers is their precedence, so the ready
+             * real-time threads always run before the time-sharing ones
+             */
+            {
+                let mut schedulers = SM_SCHEDULER.m_schedulers.lock();
+                schedulers.push(Box::new(RealTimeScheduler::new()));
+                schedulers.push(Box::new(RoundRobinScheduler::new()));
+            }
+
+            /* the kernel process owns the idle threads and the kernel threads */
+            SM_SCHEDULER.m_kern_proc = Some(Process::new(None, UserSpace::new_kernel()));
         }
     }
 }
 
+impl Scheduler /* Methods */ {
+    /**
+     * Admits the given `Thread` into the scheduling class of his
+     * `SchedPolicy` and makes it eligible for the execution.
+     *
+     * Returns `false` when the class rejects it
+     */
+    pub fn add_thread(&self, thread: Arc<Thread>) -> bool {
+        let now = self.ticks();
+
+        /* the scheduler lock is taken by the timer interrupt too */
+        let mut is_admitted = false;
+        Processor::instance().this_core().without_interrupts(|| {
+                                             is_admitted = self.admit_thread(thread, now)
+                                         });
+        is_admitted
+    }
+
+    /**
+     * Adopts the current execution flow as the idle `Thread` of this
+     * `CpuCore` and starts to schedule the other `Thread`s
+     */
+    pub fn run_this_core(&self) -> ! {
+        let this_core = Processor::instance_mut().this_core_mut();
+
+        /* the idle thread is never added to the schedulers */
+        let idle_thread = Thread::new_idle(self.kern_proc().clone());
+        this_core.set_idle_thread(idle_thread.clone());
+        this_core.set_current_thread(idle_thread);
+
+        /* the first timer tick will preempt the idle loop */
+        loop {
+            this_core.wait_for_interrupt();
+        }
+    }
+
+    /**
+     * Called on each timer tick to account the time of the current
+     * `Thread` and to preempt it when his class requests it
+     */
+    pub fn on_timer_tick(&self) {
+        let this_core = Processor::instance().this_core();
+
+        /* the system time is advanced only by the BSP */
+        if this_core.is_bsp() {
+            self.m_ticks.fetch_add(1, Ordering::SeqCst);
+        }
+
+        /* the CpuCore could receive ticks before <run_this_core()> */
+        if !this_core.has_current_thread() {
+            return;
+        }
+
+        let current_thread = this_core.current_thread();
+        let must_preempt = if Arc::ptr_eq(&current_thread, &this_core.idle_thread()) {
+            /* the idle thread leaves the CpuCore to any ready thread */
+            true
+        } else {
+            /* each class accounts the tick and can ask for the preemption */
+            let now = self.ticks();
+            self.m_schedulers
+                .lock()
+                .iter_mut()
+                .fold(false, |must_preempt, scheduler| {
+                    scheduler.on_tick(&current_thread, now) || must_preempt
+                })
+        };
+        drop(current_thread);
+
+        /* the preemption denied by <CpuCore::without_preemption()> waits
+         * the next request of the classes
+         */
+        if must_preempt && this_core.is_preemptible() {
+            self.schedule();
+        }
+    }
+
+    /**
+     * Switches to the next eligible `Thread`, returns when the current
+     * `Thread` is scheduled again.
+     *
+     * Must be called with the interrupts disabled
+     */
+    pub fn schedule(&self) {
+        let this_core = Processor::instance_mut().this_core_mut();
+        let current_thread = this_core.current_thread();
+
+        /* the current thread continues when nothing else is ready, the idle
+         * one takes the CpuCore when the current is terminated, blocked or
+         * has exhausted his real-time budget, until the replenishment
+         */
+        let next_thread = match self.pick_next() {
+            Some(next_thread) => next_thread,
+            None if current_thread.is_alive()
+                    && !current_thread.is_blocked()
+                    && self.can_continue(&current_thread) =>
+            {
+                return
+            },
+            None => this_core.idle_thread()
+        };
+
+        if let Some(kern_stack_bottom) = next_thread.kern_stack_bottom() {
+            this_core.set_kern_stack_bottom(kern_stack_bottom);
+        }
+
+        /* switch the address space only when the process changes, the kernel
+         * half is shared by all the processes
+         */
+        if !Arc::ptr_eq(current_thread.proc(), next_thread.proc()) {
+            unsafe {
+                next_thread.proc().page_dir().activate();
+            }
+        }
+        this_core.set_tls_ptr(next_thread.tls_ptr());
+
+        /* the previous thread is given back to the schedulers only once his
+         * context is saved, by <finish_switch()>, otherwise another CpuCore
+         * could resume it before the end of the switch
+         */
+        let (prev_context, next_context) = (current_thread.hw_context() as *const _,
+                                            next_thread.hw_context() as *const _);
+        this_core.set_prev_thread(current_thread);
+        this_core.set_current_thread(next_thread);
+
+        unsafe {
+            (*prev_context).switch_to(&*next_context);
+        }
+
+        /* the thread is resumed here */
+        self.finish_switch();
+    }
+
+    /**
+     * Completes the context switch into the resumed `Thread`
+     */
+    pub fn finish_switch(&self) {
+        let this_core = Processor::instance_mut().this_core_mut();
+
+        if let Some(prev_thread) = this_core.take_prev_thread() {
+            if Arc::ptr_eq(&prev_thread, &this_core.idle_thread()) {
+                return;
+            }
+
+            let mut schedulers = self.m_schedulers.lock();
+            if let Some(scheduler) =
+                schedulers.iter_mut().find(|scheduler| scheduler.manages(&prev_thread))
+            {
+                if prev_thread.is_alive() {
+                    /* the blocked thread is given back by <wake_up_thread()> */
+                    if !prev_thread.park() {
+                        scheduler.add_thread(prev_thread);
+                    }
+                } else {
+                    /* the reservation and the stack of the thread are released here */
+                    scheduler.release_thread(&prev_thread);
+                    prev_thread.proc().remove_thread(&prev_thread);
+                }
+            }
+        }
+    }
+
+    /**
+     * Makes eligible for the execution the given `Thread` blocked with
+     * `Thread::mark_as_blocked()`
+     */
+    pub fn wake_up_thread(&self, thread: Arc<Thread>) {
+        /* a thread not yet parked is given back by <finish_switch()> */
+        if !thread.unblock() {
+            return;
+        }
+
+        /* the scheduler lock is taken by the timer interrupt too */
+        Processor::instance().this_core()
+                             .without_interrupts(|| self.enqueue_thread(thread));
+    }
+
+    /**
+     * Terminates the current `Thread` with the given `TaskExitStatus` and
+     * switches to the next one
+     */
+    pub fn exit_current_thread(&self, exit_status: TaskExitStatus) -> ! {
+        let this_core = Processor::instance().this_core();
+
+        this_core.disable_interrupts();
+        this_core.current_thread().mark_as_dead(exit_status);
+        self.schedule();
+
+        unreachable!("Scheduler::exit_current_thread(): resumed a dead thread");
+    }
+}
+
 impl Scheduler /* Getters */ {
     pub fn instance() -> &'static Self {
         unsafe { &SM_SCHEDULER }
     }
+
+    /**
+     * Returns the kernel `Process`
+     */
+    pub fn kern_proc(&self) -> &Arc<Process> {
+        self.m_kern_proc
+            .as_ref()
+            .expect("Called Scheduler::kern_proc() before Scheduler::init_instance()")
+    }
+
+    /**
+     * Returns the amount of timer ticks elapsed since the start of the
+     * scheduling
+     */
+    pub fn ticks(&self) -> u64 {
+        self.m_ticks.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Returns the time elapsed since the start of the scheduling, with the
+     * resolution of a timer tick
+     */
+    pub fn uptime(&self) -> Duration {
+        let ticks = self.ticks();
+        Duration::new(ticks / Self::TICKS_PER_SECOND,
+                      (ticks % Self::TICKS_PER_SECOND * 1_000_000_000
+                       / Self::TICKS_PER_SECOND) as u32)
+    }
+}
+
+impl Scheduler /* Privates */ {
+    /**
+     * Admits and enqueues the given `Thread` into the `TScheduler` which
+     * manages his `SchedPolicy`
+     */
+    fn admit_thread(&self, thread: Arc<Thread>, now: u64) -> bool {
+        let mut schedulers = self.m_schedulers.lock();
+        if let Some(scheduler) =
+            schedulers.iter_mut().find(|scheduler| scheduler.manages(&thread))
+        {
+            if scheduler.admit_thread(&thread, now) {
+                scheduler.add_thread(thread);
+                return true;
+            }
+        }
+        false
+    }
+
+    /**
+     * Enqueues the given already admitted `Thread` into the `TScheduler`
+     * which manages his `SchedPolicy`
+     */
+    fn enqueue_thread(&self, thread: Arc<Thread>) {
+        let mut schedulers = self.m_schedulers.lock();
+        if let Some(scheduler) =
+            schedulers.iter_mut().find(|scheduler| scheduler.manages(&thread))
+        {
+            scheduler.add_thread(thread);
+        }
+    }
+
+    /**
+     * Returns whether the `TScheduler` which manages the given `Thread`
+     * lets it continue when no other `Thread` is ready
+     */
+    fn can_continue(&self, thread: &Thread) -> bool {
+        let now = self.ticks();
+        self.m_schedulers
+            .lock()
+            .iter_mut()
+            .find(|scheduler| scheduler.manages(thread))
+            .map_or(true, |scheduler| scheduler.can_continue(thread, now))
+    }
+
+    /**
+     * Picks the next `Thread` from the first `TScheduler` which have one
+     */
+    fn pick_next(&self) -> Option<Arc<Thread>> {
+        let now = self.ticks();
+        self.m_schedulers.lock().iter_mut().find_map(|scheduler| scheduler.pick_next(now))
+    }
 }
 
+/**
+ * Interface implemented by each scheduling class
+ */
 pub trait TScheduler {
+    /**
+     * Returns whether the `SchedPolicy` of the given `Thread` belongs to
+     * this class
+     */
+    fn manages(&self, thread: &Thread) -> bool;
+
+    /**
+     * Applies the admission control of this class to the new `Thread`
+     */
+    fn admit_thread(&mut self, thread: &Arc<Thread>, now: u64) -> bool;
+
+    /**
+     * Releases what was reserved by `admit_thread()`
+     */
+    fn release_thread(&mut self, thread: &Arc<Thread>);
+
+    /**
+     * Enqueues the given admitted `Thread` as ready to run
+     */
     fn add_thread(&mut self, thread: Arc<Thread>);
-    fn pick_next(&mut self) -> Option<Arc<Thread>>;
+
+    /**
+     * Dequeues the next `Thread` to run
+     */
+    fn pick_next(&mut self, now: u64) -> Option<Arc<Thread>>;
+
+    /**
+     * Accounts a timer tick and returns whether the `current_thread` must
+     * be preempted
+     */
+    fn on_tick(&mut self, current_thread: &Thread, now: u64) -> bool;
+
+    /**
+     * Returns whether the `current_thread` can keep the `CpuCore` when no
+     * other `Thread` is ready.
+     *
+     * The default implementation always lets it continue
+     */
+    fn can_continue(&mut self, _current_thread: &Thread, _now: u64) -> bool {
+        true
+    }
 }
@@ -1,31 +1,204 @@
 /*! Kernel real-time scheduler */
 
 use alloc::{
-    collections::LinkedList,
-    sync::Arc
+    collections::BTreeMap,
+    sync::Arc,
+    vec::Vec
 };
 
+use api_data::task::TaskId;
+
 use crate::task::{
-    scheduler::TScheduler,
+    scheduler::{
+        RealTimeParams,
+        SchedPolicy,
+        TScheduler
+    },
     thread::Thread
 };
 
+/**
+ * Earliest Deadline First scheduler for the periodic real-time `Thread`s.
+ *
+ * Each admitted `Thread` receives `budget` ticks each `period` and the
+ * ready one with the earliest absolute deadline runs first. The admission
+ * control keeps the total utilization under `UTILIZATION_MAX`, which
+ * guarantees all the deadlines and leaves CPU time to the time-sharing
+ * `Thread`s
+ */
 pub struct RealTimeScheduler {
-    m_ready_to_run: LinkedList<Arc<Thread>>
+    m_reservations: BTreeMap<TaskId, Reservation>,
+    m_ready_to_run: Vec<Arc<Thread>>,
+    m_utilization: u64
+}
+
+impl RealTimeScheduler /* Constants */ {
+    /**
+     * Maximum CPU utilization which can be reserved, in thousandths
+     */
+    pub const UTILIZATION_MAX: u64 = 900;
 }
 
 impl RealTimeScheduler /* Constructors */ {
-    pub const fn new() -> Self {
-        Self { m_ready_to_run: LinkedList::new() }
+    pub fn new() -> Self {
+        Self { m_reservations: BTreeMap::new(),
+               m_ready_to_run: Vec::new(),
+               m_utilization: 0 }
+    }
+}
+
+impl RealTimeScheduler /* Getters */ {
+    /**
+     * Returns the CPU utilization currently reserved, in thousandths
+     */
+    pub fn utilization(&self) -> u64 {
+        self.m_utilization
+    }
+}
+
+impl RealTimeScheduler /* Privates */ {
+    /**
+     * Returns the `RealTimeParams` of the given `Thread` if is real-time
+     */
+    fn params_of(thread: &Thread) -> Option<RealTimeParams> {
+        if let SchedPolicy::RealTime(real_time_params) = thread.sched_policy() {
+            Some(real_time_params)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Starts the new period of the `Reservation`s which deadline is passed
+     */
+    fn replenish(&mut self, now: u64) {
+        for reservation in self.m_reservations.values_mut() {
+            reservation.replenish(now);
+        }
+    }
+
+    /**
+     * Returns the index into the ready queue of the `Thread` with budget
+     * left and the earliest deadline
+     */
+    fn earliest_ready(&self) -> Option<(usize, u64)> {
+        self.m_ready_to_run
+            .iter()
+            .enumerate()
+            .filter_map(|(index, thread)| {
+                self.m_reservations
+                    .get(&thread.id())
+                    .filter(|reservation| reservation.m_budget_left > 0)
+                    .map(|reservation| (index, reservation.m_deadline))
+            })
+            .min_by_key(|&(_, deadline)| deadline)
     }
 }
 
 impl TScheduler for RealTimeScheduler {
+    fn manages(&self, thread: &Thread) -> bool {
+        Self::params_of(thread).is_some()
+    }
+
+    fn admit_thread(&mut self, thread: &Arc<Thread>, now: u64) -> bool {
+        let real_time_params = match Self::params_of(thread) {
+            Some(real_time_params) => real_time_params,
+            None => return false
+        };
+
+        /* EDF meets all the deadlines until the utilization stays under 100% */
+        let utilization = self.m_utilization + real_time_params.utilization();
+        if utilization > Self::UTILIZATION_MAX {
+            return false;
+        }
+
+        self.m_utilization = utilization;
+        self.m_reservations.insert(thread.id(), Reservation::new(real_time_params, now));
+        true
+    }
+
+    fn release_thread(&mut self, thread: &Arc<Thread>) {
+        if let Some(reservation) = self.m_reservations.remove(&thread.id()) {
+            self.m_utilization -= reservation.m_params.utilization();
+        }
+    }
+
     fn add_thread(&mut self, thread: Arc<Thread>) {
-        self.m_ready_to_run.push_front(thread);
+        self.m_ready_to_run.push(thread);
+    }
+
+    fn pick_next(&mut self, now: u64) -> Option<Arc<Thread>> {
+        self.replenish(now);
+
+        /* the threads without budget wait for their next period */
+        let (ready_index, _) = self.earliest_ready()?;
+        Some(self.m_ready_to_run.swap_remove(ready_index))
     }
 
-    fn pick_next(&mut self) -> Option<Arc<Thread>> {
-        self.m_ready_to_run.back().map(|thread| thread.clone())
+    fn on_tick(&mut self, current_thread: &Thread, now: u64) -> bool {
+        self.replenish(now);
+
+        /* charge the tick to the current real-time thread */
+        let current_deadline = if let Some(reservation) =
+            self.m_reservations.get_mut(&current_thread.id())
+        {
+            reservation.m_budget_left = reservation.m_budget_left.saturating_sub(1);
+            if reservation.m_budget_left == 0 {
+                return true;
+            }
+            Some(reservation.m_deadline)
+        } else {
+            None
+        };
+
+        /* preempt the time-sharing threads and the later real-time ones */
+        match (self.earliest_ready(), current_deadline) {
+            (Some((_, ready_deadline)), Some(current_deadline)) => {
+                ready_deadline < current_deadline
+            },
+            (Some(_), None) => true,
+            (None, _) => false
+        }
+    }
+
+    fn can_continue(&mut self, current_thread: &Thread, now: u64) -> bool {
+        self.replenish(now);
+
+        /* the thread without budget waits his next period like the others */
+        self.m_reservations
+            .get(&current_thread.id())
+            .map_or(true, |reservation| reservation.m_budget_left > 0)
+    }
+}
+
+/**
+ * Execution reservation of an admitted real-time `Thread`
+ */
+struct Reservation {
+    m_params: RealTimeParams,
+    m_deadline: u64,
+    m_budget_left: u64
+}
+
+impl Reservation /* Constructors */ {
+    fn new(params: RealTimeParams, now: u64) -> Self {
+        Self { m_params: params,
+               m_deadline: now + params.period(),
+               m_budget_left: params.budget() }
+    }
+}
+
+impl Reservation /* Methods */ {
+    /**
+     * Moves the deadline to the end of the period which contains `now` and
+     * refills the budget, if the current deadline is passed
+     */
+    fn replenish(&mut self, now: u64) {
+        if now >= self.m_deadline {
+            let elapsed_periods = (now - self.m_deadline) / self.m_params.period() + 1;
+
+            self.m_deadline += elapsed_periods * self.m_params.period();
+            self.m_budget_left = self.m_params.budget();
+        }
     }
 }
@@ -6,26 +6,116 @@ use alloc::{
 };
 
 use crate::task::{
-    scheduler::TScheduler,
+    scheduler::{
+        SchedPolicy,
+        SchedPrio,
+        TScheduler
+    },
     thread::Thread
 };
 
+/**
+ * Multi-level round robin scheduler for the time-sharing `Thread`s.
+ *
+ * Each `SchedPrio` have his own queue, the queues are visited following
+ * `ROTATION`, so the higher priorities run more often without starving
+ * the lower ones
+ */
 pub struct RoundRobinScheduler {
-    m_ready_to_run: LinkedList<Arc<Thread>>
+    m_ready_queues: [LinkedList<Arc<Thread>>; 3],
+    m_rotation_index: usize
+}
+
+impl RoundRobinScheduler /* Constants */ {
+    /**
+     * Order in which the priority queues are visited
+     */
+    const ROTATION: [SchedPrio; 6] = [SchedPrio::High,
+                                      SchedPrio::High,
+                                      SchedPrio::High,
+                                      SchedPrio::Normal,
+                                      SchedPrio::Normal,
+                                      SchedPrio::Low];
 }
 
 impl RoundRobinScheduler /* Constructors */ {
     pub const fn new() -> Self {
-        Self { m_ready_to_run: LinkedList::new() }
+        Self { m_ready_queues: [LinkedList::new(),
+                                LinkedList::new(),
+                                LinkedList::new()],
+               m_rotation_index: 0 }
+    }
+}
+
+impl RoundRobinScheduler /* Privates */ {
+    /**
+     * Returns the `SchedPrio` of the given `Thread` if is time-sharing
+     */
+    fn prio_of(thread: &Thread) -> Option<SchedPrio> {
+        if let SchedPolicy::RoundRobin(sched_prio) = thread.sched_policy() {
+            Some(sched_prio)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Returns the index of the ready queue for the given `SchedPrio`
+     */
+    fn queue_index_of(sched_prio: SchedPrio) -> usize {
+        match sched_prio {
+            SchedPrio::High => 0,
+            SchedPrio::Normal => 1,
+            SchedPrio::Low => 2
+        }
     }
 }
 
 impl TScheduler for RoundRobinScheduler {
+    fn manages(&self, thread: &Thread) -> bool {
+        Self::prio_of(thread).is_some()
+    }
+
+    fn admit_thread(&mut self, _thread: &Arc<Thread>, _now: u64) -> bool {
+        /* the time-sharing class accepts any thread */
+        true
+    }
+
+    fn release_thread(&mut self, _thread: &Arc<Thread>) {
+        /* nothing is reserved by the time-sharing class */
+    }
+
     fn add_thread(&mut self, thread: Arc<Thread>) {
-        self.m_ready_to_run.push_back(thread);
+        let sched_prio = Self::prio_of(&thread).unwrap_or_default();
+        self.m_ready_queues[Self::queue_index_of(sched_prio)].push_back(thread);
+    }
+
+    fn pick_next(&mut self, _now: u64) -> Option<Arc<Thread>> {
+        /* follow the rotation starting from the current step, skipping the
+         * empty queues, so a lone priority level is never left waiting
+         */
+        for _ in 0..Self::ROTATION.len() {
+            let sched_prio = Self::ROTATION[self.m_rotation_index];
+            self.m_rotation_index = (self.m_rotation_index + 1) % Self::ROTATION.len();
+
+            if let Some(thread) =
+                self.m_ready_queues[Self::queue_index_of(sched_prio)].pop_front()
+            {
+                thread.set_time_slice(sched_prio.time_slice());
+                return Some(thread);
+            }
+        }
+        None
     }
 
-    fn pick_next(&mut self) -> Option<Arc<Thread>> {
-        self.m_ready_to_run.front().map(|thread| thread.clone())
+    fn on_tick(&mut self, current_thread: &Thread, _now: u64) -> bool {
+        if let Some(sched_prio) = Self::prio_of(current_thread) {
+            /* the thread continues with a new slice when nothing else is ready */
+            if current_thread.consume_time_slice() {
+                current_thread.set_time_slice(sched_prio.time_slice());
+                return true;
+            }
+        }
+        false
     }
 }
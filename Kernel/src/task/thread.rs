@@ -1,12 +1,403 @@
 /*! Thread management */
 
-use alloc::sync::Arc;
+use alloc::{
+    boxed::Box,
+    sync::Arc
+};
 
-use api_data::task::TaskId;
+use core::sync::atomic::{
+    AtomicBool,
+    AtomicUsize,
+    Ordering
+};
 
-use crate::task::process::Process;
+use api_data::task::{
+    exit_status::TaskExitStatus,
+    TaskId
+};
+use sync::SpinMutex;
+
+use crate::{
+    addr::{
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    arch::task::hw_thread_context::HwThreadContext,
+    processor::Processor,
+    task::{
+        alloc_task_id,
+        process::Process,
+        scheduler::{
+            SchedPolicy,
+            SchedPrio,
+            Scheduler
+        }
+    }
+};
+
+/**
+ * Entry point for the kernel threads
+ */
+pub type KernThreadEntry = fn(usize);
+
+/* states of the blocking, see <Thread::mark_as_blocked()> */
+const C_BLOCK_STATE_RUNNABLE: usize = 0;
+const C_BLOCK_STATE_BLOCKING: usize = 1;
+const C_BLOCK_STATE_PARKED: usize = 2;
 
 pub struct Thread {
     m_id: TaskId,
-    m_proc: Arc<Process>
+    m_proc: Arc<Process>,
+    m_hw_context: HwThreadContext,
+    m_kern_stack: Option<Box<[u8]>>,
+    m_user_start: Option<UserThreadStart>,
+    m_tls_ptr: VirtAddr,
+    m_sched_policy: SchedPolicy,
+    m_time_slice_left: AtomicUsize,
+    m_is_alive: AtomicBool,
+    m_block_state: AtomicUsize,
+    m_exit_status: SpinMutex<TaskExitStatus>
+}
+
+impl Thread /* Constants */ {
+    /**
+     * Size of the kernel stack of each `Thread`
+     */
+    pub const KERN_STACK_SIZE: usize = 32 * 1024;
+}
+
+impl Thread /* Constructors */ {
+    /**
+     * Constructs a new `Thread` which executes `thread_entry(thread_arg)`
+     * in kernel mode.
+     *
+     * The returned `Thread` is not yet known by the `Scheduler`, which
+     * will schedule it according to the given `SchedPolicy`
+     */
+    pub fn new_kernel(proc: Arc<Process>,
+                      thread_entry: KernThreadEntry,
+                      thread_arg: usize,
+                      sched_policy: SchedPolicy)
+                      -> Arc<Self> {
+        Self::new_with_kern_stack(proc, thread_entry, thread_arg, None, sched_policy)
+    }
+
+    /**
+     * Constructs a new `Thread` which jumps into the user-space of the
+     * given `Process` as described by `user_start`.
+     *
+     * The returned `Thread` is not yet known by the `Scheduler`
+     */
+    pub fn new_user(proc: Arc<Process>,
+                    user_start: UserThreadStart,
+                    sched_policy: SchedPolicy)
+                    -> Arc<Self> {
+        Self::new_with_kern_stack(proc,
+                                  Self::enter_user_space,
+                                  0,
+                                  Some(user_start),
+                                  sched_policy)
+    }
+
+    /**
+     * Constructs the idle `Thread` for the executing `CpuCore`.
+     *
+     * The idle `Thread` adopts the current execution flow and stack, so
+     * his context is filled by the first context switch
+     */
+    pub fn new_idle(proc: Arc<Process>) -> Arc<Self> {
+        Arc::new(Self { m_id: alloc_task_id(),
+                        m_proc: proc,
+                        m_hw_context: HwThreadContext::new_empty(),
+                        m_kern_stack: None,
+                        m_user_start: None,
+                        m_tls_ptr: VirtAddr::null(),
+                        m_sched_policy: SchedPolicy::RoundRobin(SchedPrio::Low),
+                        m_time_slice_left: AtomicUsize::new(0),
+                        m_is_alive: AtomicBool::new(true),
+                        m_block_state: AtomicUsize::new(C_BLOCK_STATE_RUNNABLE),
+                        m_exit_status: SpinMutex::const_new(TaskExitStatus::Success) })
+    }
+}
+
+impl Thread /* Methods */ {
+    /**
+     * Marks this `Thread` as terminated with the given `TaskExitStatus`.
+     *
+     * The `Scheduler` will not re-schedule it anymore
+     */
+    pub fn mark_as_dead(&self, exit_status: TaskExitStatus) {
+        *self.m_exit_status.lock() = exit_status;
+        self.m_is_alive.store(false, Ordering::SeqCst);
+    }
+
+    /**
+     * Marks this `Thread` as waiting for an event, the next
+     * `Scheduler::schedule()` leaves it out of the schedulers until
+     * `Scheduler::wake_up_thread()` is called
+     */
+    pub fn mark_as_blocked(&self) {
+        self.m_block_state.store(C_BLOCK_STATE_BLOCKING, Ordering::SeqCst);
+    }
+
+    /**
+     * Parks this blocked `Thread` once his context is saved.
+     *
+     * Returns `false` when it was already woken up, so it must be given
+     * back to the schedulers
+     */
+    pub fn park(&self) -> bool {
+        self.m_block_state
+            .compare_exchange(C_BLOCK_STATE_BLOCKING,
+                              C_BLOCK_STATE_PARKED,
+                              Ordering::SeqCst,
+                              Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /**
+     * Makes this `Thread` runnable again.
+     *
+     * Returns `true` when it was parked, so it must be given back to the
+     * schedulers
+     */
+    pub fn unblock(&self) -> bool {
+        self.m_block_state.swap(C_BLOCK_STATE_RUNNABLE, Ordering::SeqCst)
+        == C_BLOCK_STATE_PARKED
+    }
+
+    /**
+     * Consumes one tick of the remaining time slice.
+     *
+     * Returns `true` when the time slice is exhausted
+     */
+    pub fn consume_time_slice(&self) -> bool {
+        let prev_time_slice = self.m_time_slice_left.fetch_sub(1, Ordering::SeqCst);
+        if prev_time_slice <= 1 {
+            /* avoid the wrap-around when it was already exhausted */
+            self.m_time_slice_left.store(0, Ordering::SeqCst);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Thread /* Getters */ {
+    /**
+     * Returns the `TaskId` of this `Thread`
+     */
+    pub fn id(&self) -> TaskId {
+        self.m_id
+    }
+
+    /**
+     * Returns the `Process` which owns this `Thread`
+     */
+    pub fn proc(&self) -> &Arc<Process> {
+        &self.m_proc
+    }
+
+    /**
+     * Returns the saved hardware context of this `Thread`
+     */
+    pub fn hw_context(&self) -> &HwThreadContext {
+        &self.m_hw_context
+    }
+
+    /**
+     * Returns the bottom `VirtAddr` of the kernel stack if this `Thread`
+     * owns one
+     */
+    pub fn kern_stack_bottom(&self) -> Option<VirtAddr> {
+        self.m_kern_stack.as_ref().map(|kern_stack| Self::stack_bottom_of(kern_stack))
+    }
+
+    /**
+     * Returns the thread local storage pointer of this `Thread`, null for
+     * the kernel `Thread`s
+     */
+    pub fn tls_ptr(&self) -> VirtAddr {
+        self.m_tls_ptr
+    }
+
+    /**
+     * Returns the `SchedPolicy` of this `Thread`
+     */
+    pub fn sched_policy(&self) -> SchedPolicy {
+        self.m_sched_policy
+    }
+
+    /**
+     * Returns whether this `Thread` is not terminated
+     */
+    pub fn is_alive(&self) -> bool {
+        self.m_is_alive.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Returns whether this `Thread` is waiting for an event
+     */
+    pub fn is_blocked(&self) -> bool {
+        self.m_block_state.load(Ordering::SeqCst) != C_BLOCK_STATE_RUNNABLE
+    }
+
+    /**
+     * Returns the `TaskExitStatus` of this `Thread`, meaningful only when
+     * it is terminated
+     */
+    pub fn exit_status(&self) -> TaskExitStatus {
+        *self.m_exit_status.lock()
+    }
+}
+
+impl Thread /* Setters */ {
+    /**
+     * Gives to this `Thread` a new time slice of the given amount of ticks
+     */
+    pub fn set_time_slice(&self, time_slice: usize) {
+        self.m_time_slice_left.store(time_slice, Ordering::SeqCst);
+    }
+}
+
+impl Thread /* Privates */ {
+    /**
+     * Constructs a new `Thread` with his own kernel stack, where is
+     * executed `thread_entry(thread_arg)`
+     */
+    fn new_with_kern_stack(proc: Arc<Process>,
+                           thread_entry: KernThreadEntry,
+                           thread_arg: usize,
+                           user_start: Option<UserThreadStart>,
+                           sched_policy: SchedPolicy)
+                           -> Arc<Self> {
+        let kern_stack = vec![0; Self::KERN_STACK_SIZE].into_boxed_slice();
+
+        let hw_context = HwThreadContext::new_kernel(Self::stack_bottom_of(&kern_stack),
+                                                     thread_entry,
+                                                     thread_arg);
+        let tls_ptr = user_start.map(|user_start| user_start.m_tls_ptr)
+                                .unwrap_or_else(VirtAddr::null);
+
+        let thread = Arc::new(Self { m_id: alloc_task_id(),
+                                     m_proc: proc.clone(),
+                                     m_hw_context: hw_context,
+                                     m_kern_stack: Some(kern_stack),
+                                     m_user_start: user_start,
+                                     m_tls_ptr: tls_ptr,
+                                     m_sched_policy: sched_policy,
+                                     m_time_slice_left: AtomicUsize::new(0),
+                                     m_is_alive: AtomicBool::new(true),
+                                     m_block_state:
+                                         AtomicUsize::new(C_BLOCK_STATE_RUNNABLE),
+                                     m_exit_status:
+                                         SpinMutex::const_new(TaskExitStatus::Success) });
+        proc.add_thread(thread.clone());
+        thread
+    }
+
+    /**
+     * Kernel entry of the user `Thread`s, leaves the kernel to never return
+     */
+    fn enter_user_space(_: usize) {
+        let user_start =
+            Processor::instance().this_core()
+                                 .current_thread()
+                                 .m_user_start
+                                 .expect("Kernel thread entering user-space");
+
+        unsafe {
+            HwThreadContext::enter_user_space(user_start.m_entry_point,
+                                              user_start.m_stack_ptr,
+                                              user_start.m_args)
+        }
+    }
+
+    /**
+     * Returns the 16 bytes aligned bottom `VirtAddr` of the given stack
+     */
+    fn stack_bottom_of(stack: &[u8]) -> VirtAddr {
+        let stack_virt_addr: VirtAddr = stack.as_ptr().into();
+        stack_virt_addr.offset(stack.len()).align_down(16usize)
+    }
+}
+
+/**
+ * Describes where a user `Thread` starts his execution
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct UserThreadStart {
+    m_entry_point: VirtAddr,
+    m_stack_ptr: VirtAddr,
+    m_args: [usize; 2],
+    m_tls_ptr: VirtAddr
+}
+
+impl UserThreadStart /* Constructors */ {
+    /**
+     * Constructs a `UserThreadStart` which calls `entry_point(args[0],
+     * args[1])` with the given stack pointer and thread local storage
+     */
+    pub fn new(entry_point: VirtAddr,
+               stack_ptr: VirtAddr,
+               args: [usize; 2],
+               tls_ptr: VirtAddr)
+               -> Self {
+        Self { m_entry_point: entry_point,
+               m_stack_ptr: stack_ptr,
+               m_args: args,
+               m_tls_ptr: tls_ptr }
+    }
+}
+
+/**
+ * Rust entry-point for the new kernel threads, called by the architecture
+ * dependent context switch code
+ */
+#[no_mangle]
+extern "C" fn kern_thread_start(thread_entry: KernThreadEntry, thread_arg: usize) -> ! {
+    /* complete the switch started by the previous thread */
+    Scheduler::instance().finish_switch();
+    Processor::instance().this_core().enable_interrupts();
+
+    thread_entry(thread_arg);
+
+    /* the thread returned, so it can be terminated */
+    Scheduler::instance().exit_current_thread(TaskExitStatus::Success)
+}
+
+/**
+ * Interface on which the `Thread` relies to save and restore the hardware
+ * state of the CPU
+ */
+pub trait THwThreadContext: Sync {
+    /**
+     * Constructs an empty context, filled by the first `switch_to()`
+     */
+    fn new_empty() -> Self;
+
+    /**
+     * Constructs a context which executes `thread_entry(thread_arg)` on
+     * the given kernel stack once switched to it
+     */
+    fn new_kernel(kern_stack_bottom: VirtAddr,
+                  thread_entry: KernThreadEntry,
+                  thread_arg: usize)
+                  -> Self;
+
+    /**
+     * Saves the current CPU state into this context and resumes the
+     * `next_context`
+     */
+    unsafe fn switch_to(&self, next_context: &Self);
+
+    /**
+     * Leaves the kernel and executes `entry_point(args[0], args[1])` in
+     * user mode with the given stack pointer
+     */
+    unsafe fn enter_user_space(entry_point: VirtAddr,
+                               stack_ptr: VirtAddr,
+                               args: [usize; 2])
+                               -> !;
 }
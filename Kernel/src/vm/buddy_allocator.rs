@@ -0,0 +1,333 @@
+/*! Physical frames buddy allocator */
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use bits::bit_fields::{
+    BitFindMode,
+    TBitArray,
+    TBitFields
+};
+use helps::{
+    align::align_up,
+    dbg::{
+        C_GIB,
+        C_MIB
+    }
+};
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        TAddress
+    },
+    vm::{
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * Lists the physical memory zones, from the lowest addresses.
+ *
+ * The allocations requested for a zone fall back to the lower ones when
+ * it is exhausted, so the low memory is given only when nothing else is
+ * available
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum PhysZone {
+    /**
+     * Physical memory below 16MiB, addressable by the legacy ISA DMA
+     */
+    Dma,
+
+    /**
+     * Physical memory below 4GiB, addressable by the 32bit DMA devices
+     */
+    Dma32,
+
+    /**
+     * All the remaining physical memory
+     */
+    Normal
+}
+
+impl PhysZone /* Constants */ {
+    const COUNT: usize = 3;
+}
+
+impl PhysZone /* Getters */ {
+    /**
+     * Returns the range of physical frame indexes covered by this zone
+     */
+    fn frames_range(&self) -> Range<usize> {
+        const C_DMA_END_FRAME: usize = 16 * C_MIB / Page4KiB::SIZE;
+        const C_DMA32_END_FRAME: usize = 4 * C_GIB / Page4KiB::SIZE;
+
+        match self {
+            Self::Dma => 0..C_DMA_END_FRAME,
+            Self::Dma32 => C_DMA_END_FRAME..C_DMA32_END_FRAME,
+            Self::Normal => C_DMA32_END_FRAME..usize::MAX
+        }
+    }
+}
+
+/**
+ * Physical frames allocator which gives blocks of `2^order` contiguous
+ * frames aligned to their size.
+ *
+ * Each `PhysZone` is managed by his own `BuddyZone`, so the blocks never
+ * cross the zone boundaries
+ */
+pub struct BuddyAllocator {
+    m_zones: [BuddyZone; PhysZone::COUNT]
+}
+
+impl BuddyAllocator /* Constants */ {
+    /**
+     * Order of the blocks of the size of a `Page2MiB`
+     */
+    pub const ORDER_2MIB: usize = 9;
+
+    /**
+     * Order of the blocks of 1GiB
+     */
+    pub const ORDER_1GIB: usize = 18;
+
+    /**
+     * Biggest order which could be allocated
+     */
+    pub const ORDER_MAX: usize = Self::ORDER_1GIB;
+}
+
+impl BuddyAllocator /* Constructors */ {
+    /**
+     * Constructs an empty `BuddyAllocator` for the physical memory below
+     * the given `PhysAddr`.
+     *
+     * The available frames must be given with `BuddyAllocator::free()`
+     */
+    pub fn new(last_phys_addr: PhysAddr) -> Self {
+        let frames_count = align_up(*last_phys_addr, Page4KiB::SIZE) / Page4KiB::SIZE;
+
+        Self { m_zones: [BuddyZone::new(PhysZone::Dma, frames_count),
+                         BuddyZone::new(PhysZone::Dma32, frames_count),
+                         BuddyZone::new(PhysZone::Normal, frames_count)] }
+    }
+
+    /**
+     * Returns the order of the smallest block which contains the given
+     * size in bytes
+     */
+    pub fn order_of_size(size: usize) -> usize {
+        let frames_count = align_up(size.max(1), Page4KiB::SIZE) / Page4KiB::SIZE;
+        frames_count.next_power_of_two().trailing_zeros() as usize
+    }
+}
+
+impl BuddyAllocator /* Methods */ {
+    /**
+     * Allocates a block of `2^order` frames from the given `PhysZone`, or
+     * from the lower ones when it is exhausted
+     */
+    pub fn allocate(&mut self, order: usize, phys_zone: PhysZone) -> Option<PhysAddr> {
+        if order > Self::ORDER_MAX {
+            return None;
+        }
+
+        let frame_index =
+            self.m_zones[..=phys_zone as usize].iter_mut()
+                                               .rev()
+                                               .find_map(|buddy_zone| {
+                                                   buddy_zone.allocate(order)
+                                               })?;
+        Some(PhysAddr::from(frame_index * Page4KiB::SIZE))
+    }
+
+    /**
+     * Returns the block of `2^order` frames to the allocator, merging it
+     * with his free buddies.
+     *
+     * Returns `false` when the block was already free or it doesn't
+     * belong to the managed memory
+     */
+    pub fn free(&mut self, phys_addr: PhysAddr, order: usize) -> bool {
+        if order > Self::ORDER_MAX || !phys_addr.is_aligned(Page4KiB::SIZE << order) {
+            return false;
+        }
+
+        let block_index = phys_addr.as_page_index::<Page4KiB>() >> order;
+        self.m_zones
+            .iter_mut()
+            .find(|buddy_zone| buddy_zone.contains_block(block_index, order))
+            .map(|buddy_zone| buddy_zone.free(block_index, order))
+            .unwrap_or(false)
+    }
+}
+
+impl BuddyAllocator /* Getters */ {
+    /**
+     * Returns the amount of free frames into the given `PhysZone`
+     */
+    pub fn free_frames_of(&self, phys_zone: PhysZone) -> usize {
+        self.m_zones[phys_zone as usize].free_frames()
+    }
+}
+
+/**
+ * Buddy allocator of a single `PhysZone`.
+ *
+ * The free blocks of each order are tracked by a bitmap indexed by the
+ * block index, which is the frame index shifted by the order
+ */
+struct BuddyZone {
+    m_frames_range: Range<usize>,
+    m_free_bitmaps: Vec<Vec<u8>>,
+    m_free_blocks: [usize; BuddyAllocator::ORDER_MAX + 1]
+}
+
+impl BuddyZone /* Constructors */ {
+    /**
+     * Constructs an empty `BuddyZone` for the frames of the given
+     * `PhysZone` below `frames_count`
+     */
+    fn new(phys_zone: PhysZone, frames_count: usize) -> Self {
+        let zone_frames_range = phys_zone.frames_range();
+        let frames_range = zone_frames_range.start.min(frames_count)
+                           ..zone_frames_range.end.min(frames_count);
+
+        /* each bitmap covers the blocks which overlap the zone */
+        let free_bitmaps = (0..=BuddyAllocator::ORDER_MAX).map(|order| {
+                                                              let blocks_count =
+                                   (align_up(frames_range.end, 1 << order) >> order)
+                                   - (frames_range.start >> order);
+                                                              vec![
+                                                                  0;
+                                                                  align_up(blocks_count,
+                                                                           u8::BIT_LEN)
+                                                                  / u8::BIT_LEN
+                                                              ]
+                                                          })
+                                                          .collect();
+
+        Self { m_frames_range: frames_range,
+               m_free_bitmaps: free_bitmaps,
+               m_free_blocks: [0; BuddyAllocator::ORDER_MAX + 1] }
+    }
+}
+
+impl BuddyZone /* Methods */ {
+    /**
+     * Allocates a block of `2^order` frames and returns the index of his
+     * first frame.
+     *
+     * The smallest free block available is split until the requested
+     * order is reached
+     */
+    fn allocate(&mut self, order: usize) -> Option<usize> {
+        let source_order =
+            (order..=BuddyAllocator::ORDER_MAX).find(|source_order| {
+                                                   self.m_free_blocks[*source_order] > 0
+                                               })?;
+        let mut block_index =
+            self.m_free_bitmaps[source_order].find_bit(true, BitFindMode::Regular)?
+            + (self.m_frames_range.start >> source_order);
+        self.set_free(block_index, source_order, false);
+
+        /* keep the lower half and give back the upper one for each split */
+        for split_order in (order..source_order).rev() {
+            block_index <<= 1;
+            self.set_free(block_index + 1, split_order, true);
+        }
+        Some(block_index << order)
+    }
+
+    /**
+     * Marks as free the given block and merges it with his free buddies.
+     *
+     * Returns `false` when the block is already free
+     */
+    fn free(&mut self, block_index: usize, order: usize) -> bool {
+        /* the block is already free when it or one of his parents is free */
+        let is_already_free = (order..=BuddyAllocator::ORDER_MAX).any(|parent_order| {
+                                  let parent_index =
+                                      (block_index << order) >> parent_order;
+                                  self.is_free(parent_index, parent_order)
+                              });
+        if is_already_free {
+            return false;
+        }
+
+        let mut block_index = block_index;
+        let mut order = order;
+        while order < BuddyAllocator::ORDER_MAX {
+            let buddy_index = block_index ^ 1;
+            if !self.is_free(buddy_index, order) {
+                break;
+            }
+
+            /* the buddy is absorbed by the merged block */
+            self.set_free(buddy_index, order, false);
+            block_index >>= 1;
+            order += 1;
+        }
+        self.set_free(block_index, order, true);
+        true
+    }
+}
+
+impl BuddyZone /* Getters */ {
+    /**
+     * Returns whether the given block is entirely inside this zone
+     */
+    fn contains_block(&self, block_index: usize, order: usize) -> bool {
+        let first_frame = block_index << order;
+        first_frame >= self.m_frames_range.start
+        && first_frame + (1 << order) <= self.m_frames_range.end
+    }
+
+    /**
+     * Returns whether the given block is free
+     */
+    fn is_free(&self, block_index: usize, order: usize) -> bool {
+        self.contains_block(block_index, order)
+        && self.m_free_bitmaps[order].bit_at(self.bit_index(block_index, order))
+    }
+
+    /**
+     * Returns the amount of free frames of this zone
+     */
+    fn free_frames(&self) -> usize {
+        self.m_free_blocks
+            .iter()
+            .enumerate()
+            .map(|(order, free_blocks)| free_blocks << order)
+            .sum()
+    }
+}
+
+impl BuddyZone /* Privates */ {
+    /**
+     * Updates the free bit of the given block and the free blocks counter
+     * of his order
+     */
+    fn set_free(&mut self, block_index: usize, order: usize, is_free: bool) {
+        let bit_index = self.bit_index(block_index, order);
+        self.m_free_bitmaps[order].set_bit(bit_index, is_free);
+        if is_free {
+            self.m_free_blocks[order] += 1;
+        } else {
+            self.m_free_blocks[order] -= 1;
+        }
+    }
+
+    /**
+     * Returns the index of the bit of the given block into the bitmap of
+     * his order
+     */
+    fn bit_index(&self, block_index: usize, order: usize) -> usize {
+        block_index - (self.m_frames_range.start >> order)
+    }
+}
@@ -69,6 +69,18 @@ impl LayoutManager /* Constants */ {
      * `Kernel/linker.ld/KERNEL_VIRT_BASE`
      */
     const KERN_VIRT_BASE: usize = 0xffff_ffff_c000_0000;
+
+    /**
+     * User space begins at 4KiB, the first page is never mapped to catch
+     * null pointer dereferences
+     */
+    const USER_SPACE_BEGIN: usize = 0x1000;
+
+    /**
+     * User space ends at 190TiB. Keep this in sync with
+     * `Docs/mem_layout.md`
+     */
+    const USER_SPACE_END: usize = 0xbe00_0000_0000;
 }
 
 impl LayoutManager /* Constructor */ {
@@ -226,6 +238,16 @@ impl LayoutManager /* Getters */ {
     }
 }
 
+impl LayoutManager /* Static Functions */ {
+    /**
+     * Returns the virtual `Range` reserved to the user processes
+     */
+    pub fn user_space_range() -> Range<VirtAddr> {
+        Range { start: Self::USER_SPACE_BEGIN.into(),
+                end: Self::USER_SPACE_END.into() }
+    }
+}
+
 impl LayoutManager /* Privates */ {
     /**
      * Returns all the `LayoutComponent`s with a size
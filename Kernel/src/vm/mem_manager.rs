@@ -1,31 +1,39 @@
 /*! Kernel memory manager */
 
+use alloc::collections::BTreeMap;
 use core::{
     fmt,
     fmt::Debug,
+    ops::Range,
     sync::atomic::{
         AtomicUsize,
         Ordering
     }
 };
 
-use bits::bit_fields::{
-    BitFindMode,
-    TBitArray,
-    TBitFields
+use helps::{
+    align::{
+        align_down,
+        align_up
+    },
+    dbg::TDisplaySizePretty
 };
-use helps::dbg::TDisplaySizePretty;
 use sync::SpinMutex;
 
 use crate::{
     addr::{
         phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
         TAddress
     },
     boot_info::BootInfo,
     dbg_print::DbgLevel,
     dbg_println,
     vm::{
+        buddy_allocator::{
+            BuddyAllocator,
+            PhysZone
+        },
         layout_manager::LayoutManager,
         page_dir::PageDir,
         page_table::PageTableIndex,
@@ -47,9 +55,11 @@ static mut SM_MEM_MANAGER: Option<MemManager> = None;
  */
 pub struct MemManager {
     m_layout_manager: LayoutManager,
-    m_phys_frames_bitmap: SpinMutex<&'static mut [u8]>,
+    m_buddy_allocator: SpinMutex<BuddyAllocator>,
     m_mem_manager_stats: MemManagerStats,
-    m_kernel_page_dir: PageDir
+    m_shared_phys_frames: SpinMutex<BTreeMap<PhysAddr, usize>>,
+    m_kernel_page_dir: PageDir,
+    m_free_kern_regions: SpinMutex<Range<VirtAddr>>
 }
 
 impl MemManager /* Constructors */ {
@@ -84,13 +94,12 @@ impl MemManager /* Constructors */ {
             LayoutManager::new_randomized(*last_phys_mem_addr)
         };
 
-        /* allocate the physical frames bitmap filled with zeroes */
-        let phys_frames_bitmap_requested_pages =
-            ((*last_phys_mem_addr / Page4KiB::SIZE / u8::BIT_LEN) + Page4KiB::MASK) >> 12;
-        let mut phys_frames_bitmap =
-            vec![0; phys_frames_bitmap_requested_pages * Page4KiB::SIZE];
+        /* construct the empty physical frames allocator */
+        let mut buddy_allocator = BuddyAllocator::new(last_phys_mem_addr);
 
-        /* mark the available frames into the bitmap */
+        /* give the available frames to the allocator, which merges them into
+         * the biggest blocks allowed by their alignment
+         */
         let mem_manager_stats = MemManagerStats::new();
         for phys_addr in
             boot_info.phys_mem_ranges()
@@ -99,9 +108,19 @@ impl MemManager /* Constructors */ {
                          phys_mem_range.clone().step_by(Page4KiB::SIZE)
                      })
         {
-            /* mark as available only the frames which not store the kernel text */
-            if !layout_manager.kern_text_phys_range().contains(&phys_addr) {
-                phys_frames_bitmap.set_bit(phys_addr.as_page_index::<Page4KiB>(), true);
+            /* mark as available only the frames which not store the kernel text
+             * or the modules loaded by the bootloader, which are released later
+             */
+            let is_boot_module_frame =
+                boot_info.boot_modules().iter().any(|boot_module| {
+                    let module_phys_range = boot_module.phys_range();
+                    phys_addr >= module_phys_range.start.align_down(Page4KiB::SIZE)
+                    && phys_addr < module_phys_range.end
+                });
+            if !layout_manager.kern_text_phys_range().contains(&phys_addr)
+               && !is_boot_module_frame
+            {
+                buddy_allocator.free(phys_addr, 0);
                 mem_manager_stats.m_free_phys_frames.fetch_add(1, Ordering::Relaxed);
             } else {
                 mem_manager_stats.m_allocated_phys_frames.fetch_add(1, Ordering::Relaxed);
@@ -112,15 +131,31 @@ impl MemManager /* Constructors */ {
                      "kern_text_phys_range: {:?}",
                      layout_manager.kern_text_phys_range());
         dbg_println!(DbgLevel::Trace, "{:?}", mem_manager_stats);
+        for phys_zone in [PhysZone::Dma, PhysZone::Dma32, PhysZone::Normal] {
+            let zone_free_size =
+                buddy_allocator.free_frames_of(phys_zone) * Page4KiB::SIZE;
+            dbg_println!(DbgLevel::Trace,
+                         "{:?} zone free: {}",
+                         phys_zone,
+                         zone_free_size.display_pretty());
+        }
+
+        /* the device memory is mapped from the end of the kernel regions,
+         * the heap regions from the begin
+         */
+        let free_kern_regions = layout_manager.kern_regions_range().clone();
 
         /* initialize the global instance */
         let mm_inst = unsafe {
-            SM_MEM_MANAGER =
-                Some(Self { m_layout_manager: layout_manager,
-                            m_phys_frames_bitmap:
-                                SpinMutex::const_new(phys_frames_bitmap.leak()),
-                            m_mem_manager_stats: mem_manager_stats,
-                            m_kernel_page_dir: PageDir::pre_phys_mapping() });
+            SM_MEM_MANAGER = Some(Self { m_layout_manager: layout_manager,
+                                         m_buddy_allocator:
+                                             SpinMutex::const_new(buddy_allocator),
+                                         m_mem_manager_stats: mem_manager_stats,
+                                         m_shared_phys_frames:
+                                             SpinMutex::const_new(BTreeMap::new()),
+                                         m_kernel_page_dir: PageDir::pre_phys_mapping(),
+                                         m_free_kern_regions:
+                                             SpinMutex::const_new(free_kern_regions) });
             SM_MEM_MANAGER.as_mut().unwrap()
         };
 
@@ -129,6 +164,12 @@ impl MemManager /* Constructors */ {
         mm_inst.update_kernel_page_dir_after_phys_mapping();
         mm_inst.unmap_kernel_lower_half();
         mm_inst.protect_kernel_image();
+
+        /* the kernel regions are mapped after the creation of the processes */
+        let kern_regions_range = mm_inst.layout_manager().kern_regions_range().clone();
+        mm_inst.kernel_page_dir()
+               .ensure_kern_root_entries(kern_regions_range)
+               .expect("Failed to allocate the kernel regions page-tables");
     }
 }
 
@@ -137,7 +178,159 @@ impl MemManager /* Methods */ {
      * Allocate a physical memory frame from the kernel pool
      */
     pub fn allocate_kernel_phys_frame(&self) -> Option<PhysAddr> {
-        self.allocate_phys_frame(BitFindMode::Regular)
+        self.allocate_kernel_phys_frames(0, PhysZone::Normal)
+    }
+
+    /**
+     * Allocates `2^frames_order` contiguous physical frames, aligned to
+     * their size, from the given `PhysZone` of the kernel pool.
+     *
+     * The lower zones are used when the requested one is exhausted
+     */
+    pub fn allocate_kernel_phys_frames(&self,
+                                       frames_order: usize,
+                                       phys_zone: PhysZone)
+                                       -> Option<PhysAddr> {
+        let phys_frames =
+            self.m_buddy_allocator.lock().allocate(frames_order, phys_zone)?;
+
+        self.m_mem_manager_stats.on_allocated_phys_frames(1 << frames_order);
+        Some(phys_frames)
+    }
+
+    /**
+     * Returns the given physical memory frame to the kernel pool
+     */
+    pub fn free_kernel_phys_frame(&self, phys_frame: PhysAddr) {
+        self.free_kernel_phys_frames(phys_frame, 0);
+    }
+
+    /**
+     * Returns to the kernel pool the `2^frames_order` contiguous physical
+     * frames allocated with `MemManager::allocate_kernel_phys_frames()`
+     */
+    pub fn free_kernel_phys_frames(&self, phys_frames: PhysAddr, frames_order: usize) {
+        /* the statistics are not touched for the already free frames */
+        if self.m_buddy_allocator.lock().free(phys_frames, frames_order) {
+            self.m_mem_manager_stats.on_free_phys_frames(1 << frames_order);
+        }
+    }
+
+    /**
+     * Adds an owner to the given physical frame, which is returned to the
+     * kernel pool only by the `MemManager::release_phys_frame()` of the
+     * last owner
+     */
+    pub fn share_phys_frame(&self, phys_frame: PhysAddr) {
+        /* only the additional owners are counted, the first one is implicit */
+        *self.m_shared_phys_frames.lock().entry(phys_frame).or_insert(0) += 1;
+    }
+
+    /**
+     * Removes an owner from the given physical frame, which is freed when
+     * no other owner remains
+     */
+    pub fn release_phys_frame(&self, phys_frame: PhysAddr) {
+        let mut shared_phys_frames = self.m_shared_phys_frames.lock();
+
+        let other_owners = shared_phys_frames.get(&phys_frame).copied();
+        match other_owners {
+            Some(1) => {
+                shared_phys_frames.remove(&phys_frame);
+            },
+            Some(other_owners) => {
+                shared_phys_frames.insert(phys_frame, other_owners - 1);
+            },
+            None => {
+                drop(shared_phys_frames);
+                self.free_kernel_phys_frame(phys_frame);
+            }
+        }
+    }
+
+    /**
+     * Maps the given range of device memory as uncacheable into the kernel
+     * regions and returns the `VirtAddr` of his first byte.
+     *
+     * The mappings are permanent, the drivers map their registers once
+     */
+    pub fn map_kernel_mmio(&self, phys_addr: PhysAddr, size: usize) -> Option<VirtAddr> {
+        /* the big and aligned ranges are mapped with 2MiB pages */
+        let use_huge_pages =
+            phys_addr.is_aligned(Page2MiB::SIZE) && size >= Page2MiB::SIZE;
+        let page_size = if use_huge_pages {
+            Page2MiB::SIZE
+        } else {
+            Page4KiB::SIZE
+        };
+
+        let phys_start = phys_addr.align_down(page_size);
+        let map_size = align_up(*phys_addr - *phys_start + size, page_size);
+
+        /* reserve the virtual range below the last one mapped */
+        let virt_start = {
+            let mut free_kern_regions = self.m_free_kern_regions.lock();
+            let virt_start =
+                VirtAddr::from(align_down(*free_kern_regions.end - map_size, page_size));
+            if virt_start < free_kern_regions.start {
+                return None;
+            }
+
+            free_kern_regions.end = virt_start;
+            virt_start
+        };
+
+        for page_offset in (0..map_size).step_by(page_size) {
+            let mut page_table_mapping = if use_huge_pages {
+                self.kernel_page_dir()
+                    .ensure_page_table_entry::<Page2MiB>(virt_start.offset(page_offset))?
+            } else {
+                self.kernel_page_dir()
+                    .ensure_page_table_entry::<Page4KiB>(virt_start.offset(page_offset))?
+            };
+
+            page_table_mapping.set_phys_frame(phys_start.offset(page_offset))
+                              .set_huge_page(use_huge_pages)
+                              .set_present(true)
+                              .set_readable(true)
+                              .set_writeable(true)
+                              .set_cacheable(false)
+                              .set_global(true)
+                              .set_no_execute(true)
+                              .set_user(false);
+        }
+        Some(virt_start.offset(*phys_addr - *phys_start))
+    }
+
+    /**
+     * Maps the given amount of new writeable pages into the kernel regions
+     * and returns the `VirtAddr` of the first one.
+     *
+     * The regions are permanent, they are used to grow the kernel heap, so
+     * nothing here could allocate from it
+     */
+    pub fn allocate_kernel_region(&self, pages_count: usize) -> Option<VirtAddr> {
+        let region_size = pages_count * Page4KiB::SIZE;
+
+        /* reserve the virtual range above the last one allocated */
+        let virt_start = {
+            let mut free_kern_regions = self.m_free_kern_regions.lock();
+            let virt_start = free_kern_regions.start;
+            if *free_kern_regions.end - *virt_start < region_size {
+                return None;
+            }
+
+            free_kern_regions.start = virt_start.offset(region_size);
+            virt_start
+        };
+
+        for page_offset in (0..region_size).step_by(Page4KiB::SIZE) {
+            if self.map_kernel_region_page(virt_start.offset(page_offset)).is_none() {
+                self.release_kernel_region(virt_start, page_offset, region_size);
+                return None;
+            }
+        }
+        Some(virt_start)
     }
 }
 
@@ -152,6 +345,13 @@ impl MemManager /* Getters */ {
         }
     }
 
+    /**
+     * Returns the global `MemManager` instance, if initialized
+     */
+    pub fn try_instance() -> Option<&'static Self> {
+        unsafe { SM_MEM_MANAGER.as_ref() }
+    }
+
     /**
      * Returns the `LayoutManager` instance
      */
@@ -165,25 +365,70 @@ impl MemManager /* Getters */ {
     pub fn kernel_page_dir(&self) -> &PageDir {
         &self.m_kernel_page_dir
     }
+
+    /**
+     * Returns the `MemManagerStats` of the physical frames
+     */
+    pub fn mem_manager_stats(&self) -> &MemManagerStats {
+        &self.m_mem_manager_stats
+    }
+
+    /**
+     * Returns whether the given physical frame has more than one owner
+     */
+    pub fn is_phys_frame_shared(&self, phys_frame: PhysAddr) -> bool {
+        self.m_shared_phys_frames.lock().contains_key(&phys_frame)
+    }
 }
 
 impl MemManager /* Privates */ {
     /**
-     * Allocates the first available physical frame from the physical bitmap
+     * Maps a new writeable page at the given `VirtAddr` of the kernel
+     * regions
      */
-    fn allocate_phys_frame(&self, bit_find_mode: BitFindMode) -> Option<PhysAddr> {
-        let mut unlocked_bitmap = self.m_phys_frames_bitmap.lock();
+    fn map_kernel_region_page(&self, virt_addr: VirtAddr) -> Option<()> {
+        let phys_frame = self.allocate_kernel_phys_frame()?;
+        let mut page_table_mapping =
+            match self.kernel_page_dir().ensure_page_table_entry::<Page4KiB>(virt_addr) {
+                Some(page_table_mapping) => page_table_mapping,
+                None => {
+                    self.free_kernel_phys_frame(phys_frame);
+                    return None;
+                }
+            };
+
+        page_table_mapping.set_phys_frame(phys_frame)
+                          .set_present(true)
+                          .set_readable(true)
+                          .set_writeable(true)
+                          .set_global(true)
+                          .set_no_execute(true)
+                          .set_user(false);
+        Some(())
+    }
 
-        /* find the first bit as <true>, which means available bit */
-        if let Some(bit_index) = unlocked_bitmap.find_bit(true, bit_find_mode) {
-            /* mark the bit as no-more available and update the statistics */
-            unlocked_bitmap.set_bit(bit_index, false);
-            self.m_mem_manager_stats.on_allocated_phys_frame();
+    /**
+     * Unmaps and frees the first `mapped_size` bytes of the given kernel
+     * region, which returns free if still the last one reserved
+     */
+    fn release_kernel_region(&self,
+                             virt_start: VirtAddr,
+                             mapped_size: usize,
+                             region_size: usize) {
+        for page_offset in (0..mapped_size).step_by(Page4KiB::SIZE) {
+            if let Some(mut page_table_mapping) =
+                self.kernel_page_dir()
+                    .ensure_page_table_entry::<Page4KiB>(virt_start.offset(page_offset))
+            {
+                self.free_kernel_phys_frame(page_table_mapping.phys_frame().unwrap());
+                page_table_mapping.set_unused();
+            }
+        }
 
-            /* construct the <PhysAddr> from the frame number */
-            Some(PhysAddr::from(bit_index * Page4KiB::SIZE))
-        } else {
-            None
+        /* the regions reserved meanwhile keep this one busy */
+        let mut free_kern_regions = self.m_free_kern_regions.lock();
+        if free_kern_regions.start == virt_start.offset(region_size) {
+            free_kern_regions.start = virt_start;
         }
     }
 
@@ -288,19 +533,19 @@ impl MemManagerStats /* Getters */ {
 
 impl MemManagerStats /* Privates */ {
     /**
-     * Updates the counters when a physical frame is being allocated
+     * Updates the counters when physical frames are being allocated
      */
-    fn on_allocated_phys_frame(&self) {
-        self.m_free_phys_frames.fetch_sub(1, Ordering::SeqCst);
-        self.m_allocated_phys_frames.fetch_add(1, Ordering::SeqCst);
+    fn on_allocated_phys_frames(&self, frames_count: usize) {
+        self.m_free_phys_frames.fetch_sub(frames_count, Ordering::SeqCst);
+        self.m_allocated_phys_frames.fetch_add(frames_count, Ordering::SeqCst);
     }
 
     /**
-     * Updates the counters when a physical frame is being freed
+     * Updates the counters when physical frames are being freed
      */
-    fn on_free_phys_frame(&self) {
-        self.m_allocated_phys_frames.fetch_sub(1, Ordering::SeqCst);
-        self.m_free_phys_frames.fetch_add(1, Ordering::SeqCst);
+    fn on_free_phys_frames(&self, frames_count: usize) {
+        self.m_allocated_phys_frames.fetch_sub(frames_count, Ordering::SeqCst);
+        self.m_free_phys_frames.fetch_add(frames_count, Ordering::SeqCst);
     }
 }
 
@@ -0,0 +1,270 @@
+/*! Pageable memory objects */
+
+use alloc::{
+    collections::{
+        btree_map::Entry,
+        BTreeMap,
+        BTreeSet
+    },
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    cmp::min,
+    mem,
+    slice
+};
+
+use api_data::error::class::OsErrorClass;
+use helps::align::align_up;
+use sync::SpinMutex;
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        TAddress
+    },
+    fs::vfs::{
+        node::TVfsNode,
+        VfsResult
+    },
+    processor::Processor,
+    vm::{
+        mem_manager::MemManager,
+        user_space::UserSpaceResult,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * Memory which could be mapped into many `UserSpace`s at the same time.
+ *
+ * The frames are allocated at the first access of each page and are
+ * filled with zeroes or with the data of the backing file. When the file
+ * is kept in sync the pages written are given back to it by
+ * `MemObject::write_back()` and when the object is dropped, otherwise
+ * they remain private to the object
+ */
+pub struct MemObject {
+    m_size: usize,
+    m_file: Option<(Arc<dyn TVfsNode>, usize)>,
+    m_keep_file_sync: bool,
+    m_phys_frames: SpinMutex<BTreeMap<usize, PhysAddr>>,
+    m_dirty_pages: SpinMutex<BTreeSet<usize>>
+}
+
+impl MemObject /* Constructors */ {
+    /**
+     * Constructs a `MemObject` of the given size filled with zeroes
+     */
+    pub fn new_anonymous(size: usize) -> Self {
+        Self::new(size, None, false)
+    }
+
+    /**
+     * Constructs a `MemObject` of the given size which mirrors the data
+     * of the given file node from the given offset.
+     *
+     * The written pages are given back to the file only when
+     * `keep_file_sync` is `true`
+     */
+    pub fn new_file(size: usize,
+                    file_node: Arc<dyn TVfsNode>,
+                    file_offset: usize,
+                    keep_file_sync: bool)
+                    -> Self {
+        Self::new(size, Some((file_node, file_offset)), keep_file_sync)
+    }
+
+    /**
+     * Constructs an empty `MemObject` with the given source
+     */
+    fn new(size: usize,
+           file: Option<(Arc<dyn TVfsNode>, usize)>,
+           keep_file_sync: bool)
+           -> Self {
+        Self { m_size: size,
+               m_file: file,
+               m_keep_file_sync: keep_file_sync,
+               m_phys_frames: SpinMutex::const_new(BTreeMap::new()),
+               m_dirty_pages: SpinMutex::const_new(BTreeSet::new()) }
+    }
+}
+
+impl MemObject /* Methods */ {
+    /**
+     * Returns the frame of the given page, which is allocated and filled
+     * at the first request
+     */
+    pub fn phys_frame_at(&self, page_index: usize) -> UserSpaceResult<PhysAddr> {
+        if page_index >= self.pages_count() {
+            return Err((OsErrorClass::InvalidMemoryAccess,
+                        Some("Access beyond the end of the memory object")));
+        }
+        if let Some(phys_frame) = self.m_phys_frames.lock().get(&page_index) {
+            return Ok(*phys_frame);
+        }
+
+        /* the frame is filled without holding the lock, since the file could
+         * be read
+         */
+        let phys_frame =
+            MemManager::instance().allocate_kernel_phys_frame()
+                                  .ok_or((OsErrorClass::NotEnoughMemory, None))?;
+        let page_data = Self::frame_data(phys_frame);
+        page_data.fill(0);
+
+        /* the bytes after the end of the file remain zeroed, the file is
+         * read with the interrupts enabled, since the page faults and the
+         * system calls disable them
+         */
+        if let Some((file_node, file_offset)) = self.m_file.as_ref() {
+            let read_offset = file_offset + page_index * Page4KiB::SIZE;
+            let read_buffer = &mut page_data[..self.page_len(page_index)];
+            let this_core = Processor::instance().this_core();
+            let read_result = this_core.without_preemption(|| {
+                                           file_node.read_at(read_offset, read_buffer)
+                                       });
+            if let Err(err) = read_result {
+                MemManager::instance().free_kernel_phys_frame(phys_frame);
+                return Err(err);
+            }
+        }
+
+        /* another thread could have filled the page meanwhile */
+        match self.m_phys_frames.lock().entry(page_index) {
+            Entry::Occupied(occupied_entry) => {
+                MemManager::instance().free_kernel_phys_frame(phys_frame);
+                Ok(*occupied_entry.get())
+            },
+            Entry::Vacant(vacant_entry) => Ok(*vacant_entry.insert(phys_frame))
+        }
+    }
+
+    /**
+     * Marks the given page as written, so it will be written back to the
+     * file
+     */
+    pub fn mark_dirty(&self, page_index: usize) {
+        self.m_dirty_pages.lock().insert(page_index);
+    }
+
+    /**
+     * Clears the marks of the written pages and returns them.
+     *
+     * The `UserSpace`s must map the pages read-only again, otherwise the
+     * next writes through the writeable mappings are not marked
+     */
+    pub fn take_dirty_pages(&self) -> BTreeSet<usize> {
+        mem::take(&mut *self.m_dirty_pages.lock())
+    }
+
+    /**
+     * Writes the given pages back to the backing file, when it is kept in
+     * sync
+     */
+    pub fn write_back(&self, page_indexes: &BTreeSet<usize>) -> VfsResult<()> {
+        let (file_node, file_offset) = match self.m_file.as_ref() {
+            Some(file) if self.m_keep_file_sync => file,
+            _ => return Ok(())
+        };
+
+        /* the file is written without holding the lock, the frames are
+         * released only by the drop of the object
+         */
+        let dirty_frames: Vec<_> = {
+            let phys_frames = self.m_phys_frames.lock();
+            page_indexes.iter()
+                        .filter_map(|page_index| {
+                            phys_frames.get(page_index)
+                                       .map(|phys_frame| (*page_index, *phys_frame))
+                        })
+                        .collect()
+        };
+        for (page_index, phys_frame) in dirty_frames {
+            let page_data = Self::frame_data(phys_frame);
+            file_node.write_at(file_offset + page_index * Page4KiB::SIZE,
+                               &page_data[..self.page_len(page_index)])?;
+        }
+        Ok(())
+    }
+}
+
+impl MemObject /* Getters */ {
+    /**
+     * Returns the size in bytes of the memory
+     */
+    pub fn size(&self) -> usize {
+        self.m_size
+    }
+
+    /**
+     * Returns the amount of pages which cover the memory
+     */
+    pub fn pages_count(&self) -> usize {
+        align_up(self.m_size, Page4KiB::SIZE) / Page4KiB::SIZE
+    }
+
+    /**
+     * Returns whether the memory mirrors a file
+     */
+    pub fn is_file_backed(&self) -> bool {
+        self.m_file.is_some()
+    }
+
+    /**
+     * Returns whether the written pages are given back to the file
+     */
+    pub fn is_file_synced(&self) -> bool {
+        self.is_file_backed() && self.m_keep_file_sync
+    }
+
+    /**
+     * Returns whether the frame of the given page was already filled
+     */
+    pub fn is_page_loaded(&self, page_index: usize) -> bool {
+        self.m_phys_frames.lock().contains_key(&page_index)
+    }
+
+    /**
+     * Returns whether the given page was written
+     */
+    pub fn is_dirty(&self, page_index: usize) -> bool {
+        self.m_dirty_pages.lock().contains(&page_index)
+    }
+}
+
+impl MemObject /* Privates */ {
+    /**
+     * Returns the amount of bytes of the given page which belong to the
+     * memory, only the last page could be partial
+     */
+    fn page_len(&self, page_index: usize) -> usize {
+        min(Page4KiB::SIZE, self.m_size - page_index * Page4KiB::SIZE)
+    }
+
+    /**
+     * Returns the content of the given frame through the physical memory
+     * mapping
+     */
+    fn frame_data<'a>(phys_frame: PhysAddr) -> &'a mut [u8] {
+        let frame_virt_addr =
+            MemManager::instance().layout_manager().phys_addr_to_virt_addr(phys_frame);
+        unsafe {
+            slice::from_raw_parts_mut(frame_virt_addr.as_ptr_mut::<u8>(), Page4KiB::SIZE)
+        }
+    }
+}
+
+impl Drop for MemObject {
+    fn drop(&mut self) {
+        /* nobody could receive the error, the data is lost */
+        let dirty_pages = self.take_dirty_pages();
+        let _ = self.write_back(&dirty_pages);
+
+        for phys_frame in self.m_phys_frames.lock().values() {
+            MemManager::instance().release_phys_frame(*phys_frame);
+        }
+    }
+}
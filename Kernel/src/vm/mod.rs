@@ -12,11 +12,14 @@ use helps::dbg::{
 
 use crate::vm::page_table::PageTableLevel;
 
+pub mod buddy_allocator;
 pub mod layout_manager;
 pub mod mem_manager;
+pub mod mem_object;
 pub mod page_dir;
 pub mod page_table;
 pub mod page_table_entry;
+pub mod user_space;
 
 /**
  * Default 4KiB `PageSize`
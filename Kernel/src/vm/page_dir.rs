@@ -2,9 +2,12 @@
 
 use core::{
     fmt,
-    fmt::Debug
+    fmt::Debug,
+    ops::Range
 };
 
+use helps::dbg::C_GIB;
+
 use crate::{
     addr::{
         phys_addr::PhysAddr,
@@ -13,6 +16,7 @@ use crate::{
     },
     arch::vm::hw_page_dir::HwPageDir,
     vm::{
+        layout_manager::LayoutManager,
         mem_manager::MemManager,
         page_table::{
             PageTable,
@@ -33,6 +37,23 @@ pub struct PageDir {
     m_phys_mem_offset: VirtAddr
 }
 
+impl PageDir /* Constants */ {
+    /**
+     * Amount of entries of the root `PageTable`
+     */
+    const ROOT_ENTRIES_COUNT: usize = 512;
+
+    /**
+     * First root `PageTable` index of the kernel half
+     */
+    const KERN_ROOT_INDEX_BEGIN: usize = Self::ROOT_ENTRIES_COUNT / 2;
+
+    /**
+     * Size of the virtual memory covered by each root `PageTable` entry
+     */
+    const ROOT_ENTRY_SIZE: usize = 512 * C_GIB;
+}
+
 impl PageDir /* Constructors */ {
     pub fn current() -> Self {
         Self { m_hw_page_dir: HwPageDir::current(),
@@ -45,6 +66,34 @@ impl PageDir /* Constructors */ {
         Self { m_hw_page_dir: HwPageDir::current(),
                m_phys_mem_offset: VirtAddr::null() }
     }
+
+    /**
+     * Constructs a new `PageDir` for a user `Process`.
+     *
+     * The user-space half is empty, while the kernel half shares the
+     * `PageTable`s of the kernel `PageDir`
+     */
+    pub fn new_user() -> Option<Self> {
+        let root_phys_frame = MemManager::instance().allocate_kernel_phys_frame()?;
+        let page_dir =
+            Self { m_hw_page_dir: HwPageDir::from_phys_frame(root_phys_frame),
+                   m_phys_mem_offset: MemManager::instance().layout_manager()
+                                                            .phys_mem_mapping_range()
+                                                            .start };
+
+        /* the kernel half entries are copied, so the kernel mappings done into
+         * already existing kernel <PageTable>s are visible by the new PageDir
+         */
+        let kern_root_page_table = MemManager::instance().kernel_page_dir().root_page_table();
+        let user_root_page_table = page_dir.root_page_table();
+        user_root_page_table.clear();
+        for index in Self::KERN_ROOT_INDEX_BEGIN..Self::ROOT_ENTRIES_COUNT {
+            let page_table_index = PageTableIndex::from(index);
+            user_root_page_table[page_table_index] = kern_root_page_table[page_table_index];
+        }
+
+        Some(page_dir)
+    }
 }
 
 impl PageDir /* Methods */ {
@@ -98,6 +147,37 @@ impl PageDir /* Methods */ {
         }
     }
 
+    /**
+     * Returns the present `PageTableEntry` which maps the given `VirtAddr`
+     * without allocating any missing intermediate `PageTable`.
+     *
+     * The returned entry could map a huge 2MiB page
+     */
+    pub fn mapped_page_table_entry(&self,
+                                   virt_addr: VirtAddr)
+                                   -> Option<&PageTableEntry> {
+        let mut page_table = self.root_page_table();
+        for page_table_level in [PageTableLevel::Root,
+                                 PageTableLevel::OneGiB,
+                                 PageTableLevel::TwoMiB,
+                                 PageTableLevel::FourKiB]
+        {
+            let page_table_entry =
+                &page_table[virt_addr.page_table_index(page_table_level)];
+            if !page_table_entry.is_present() {
+                return None;
+            } else if page_table_level == PageTableLevel::FourKiB
+                      || page_table_entry.is_huge_page()
+            {
+                return Some(page_table_entry);
+            }
+
+            /* go a level deeper */
+            page_table = unsafe { self.next_page_table(page_table_entry) };
+        }
+        None
+    }
+
     pub unsafe fn next_page_table(&self,
                                   page_table_entry: &PageTableEntry)
                                   -> &mut PageTable {
@@ -106,6 +186,62 @@ impl PageDir /* Methods */ {
 
         self.frame_to_next_page_table(page_table_entry.phys_frame().unwrap())
     }
+
+    /**
+     * Allocates the missing Level3 `PageTable`s which cover the given
+     * kernel range, so the mappings done later into it are shared by the
+     * user `PageDir`s too
+     */
+    pub fn ensure_kern_root_entries(&self, virt_range: Range<VirtAddr>) -> Option<()> {
+        let root_page_table = self.root_page_table();
+        let virt_start = virt_range.start.align_down(Self::ROOT_ENTRY_SIZE);
+        for virt_addr in (virt_start..virt_range.end).step_by(Self::ROOT_ENTRY_SIZE) {
+            self.ensure_next_page_table_from_level(virt_addr,
+                                                   root_page_table,
+                                                   PageTableLevel::Root)?;
+        }
+        Some(())
+    }
+
+    /**
+     * Frees the intermediate `PageTable`s of the user-space half.
+     *
+     * The pages must be already unmapped, the kernel half is shared and
+     * never touched
+     */
+    pub fn release_user_page_tables(&self) {
+        let mem_manager = MemManager::instance();
+        let root_page_table = self.root_page_table();
+        for index in 0..Self::KERN_ROOT_INDEX_BEGIN {
+            let l4_page_table_entry = &mut root_page_table[PageTableIndex::from(index)];
+            if !l4_page_table_entry.is_present() {
+                continue;
+            }
+
+            let l3_page_table = unsafe { self.next_page_table(l4_page_table_entry) };
+            for l3_page_table_entry in l3_page_table.iter() {
+                if !l3_page_table_entry.is_present() || l3_page_table_entry.is_huge_page()
+                {
+                    continue;
+                }
+
+                /* the Level1 page-tables are referenced by the Level2 ones */
+                let l2_page_table = unsafe { self.next_page_table(l3_page_table_entry) };
+                for l2_page_table_entry in l2_page_table.iter() {
+                    if l2_page_table_entry.is_present()
+                       && !l2_page_table_entry.is_huge_page()
+                    {
+                        mem_manager.free_kernel_phys_frame(l2_page_table_entry.phys_frame()
+                                                                              .unwrap());
+                    }
+                }
+                mem_manager.free_kernel_phys_frame(l3_page_table_entry.phys_frame()
+                                                                      .unwrap());
+            }
+            mem_manager.free_kernel_phys_frame(l4_page_table_entry.phys_frame().unwrap());
+            l4_page_table_entry.set_unused();
+        }
+    }
 }
 
 impl PageDir /* Getters */ {
@@ -138,12 +274,15 @@ impl PageDir /* Privates */ {
         let new_table_created = if page_table_entry.is_unused() {
             let phys_frame = MemManager::instance().allocate_kernel_phys_frame()?;
 
-            /* fill the flags */
+            /* fill the flags, the user-space mappings need the user flag on
+             * each level
+             */
             page_table_entry.set_phys_frame(phys_frame)
                             .set_present(true)
                             .set_readable(true)
                             .set_writeable(true)
-                            .set_global(true);
+                            .set_global(true)
+                            .set_user(virt_addr < LayoutManager::user_space_range().end);
 
             true
         } else {
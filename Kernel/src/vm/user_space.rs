@@ -0,0 +1,1154 @@
+/*! User address space management */
+
+use alloc::{
+    collections::BTreeMap,
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    convert::TryFrom,
+    iter,
+    ops::Range,
+    ptr
+};
+
+use api_data::error::class::OsErrorClass;
+use bits::bit_flags::{
+    BitFlags,
+    TBitFlagsValues
+};
+use helps::align::align_up;
+use sync::{
+    mutex::{
+        data_guard::MutexDataGuard,
+        spin_mutex::RawSpinMutex
+    },
+    SpinMutex
+};
+
+use crate::{
+    addr::{
+        phys_addr::PhysAddr,
+        virt_addr::VirtAddr,
+        TAddress
+    },
+    processor::Processor,
+    vm::{
+        layout_manager::LayoutManager,
+        mem_manager::MemManager,
+        mem_object::MemObject,
+        page_dir::PageDir,
+        Page4KiB,
+        TPageSize
+    }
+};
+
+/**
+ * Locked `VmArea`s of a `UserSpace`
+ */
+type VmAreasGuard<'a> = MutexDataGuard<'a, RawSpinMutex, BTreeMap<VirtAddr, VmArea>>;
+
+/**
+ * On failure the `UserSpace` returns the `OsErrorClass` and an optional
+ * message, as the kernel functions do
+ */
+pub type UserSpaceResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
+
+/**
+ * `VmArea` protection `BitFlags`
+ */
+pub type VmProtFlags = BitFlags<usize, VmProtBits>;
+
+/**
+ * Lists the accesses allowed to the pages of a committed `VmArea`
+ */
+#[repr(usize)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum VmProtBits {
+    Readable,
+    Writeable,
+    Executable
+}
+
+impl Into<usize> for VmProtBits {
+    fn into(self) -> usize {
+        self as usize
+    }
+}
+
+impl TryFrom<usize> for VmProtBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Readable),
+            1 => Ok(Self::Writeable),
+            2 => Ok(Self::Executable),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for VmProtBits {
+}
+
+/**
+ * Lists the user memory accesses which could fault
+ */
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum VmAccess {
+    Read,
+    Write,
+    Execute
+}
+
+impl VmAccess /* Getters */ {
+    /**
+     * Returns the `VmProtBits` which allows this access
+     */
+    pub fn required_prot(&self) -> VmProtBits {
+        match self {
+            Self::Read => VmProtBits::Readable,
+            Self::Write => VmProtBits::Writeable,
+            Self::Execute => VmProtBits::Executable
+        }
+    }
+}
+
+/**
+ * Lists the memory which backs the pages of a `VmArea`
+ */
+#[derive(Clone)]
+pub enum VmBacking {
+    /**
+     * Zeroed frames owned by the `UserSpace`, allocated when the pages
+     * are committed
+     */
+    Anonymous,
+
+    /**
+     * Contiguous physical memory not owned by the `UserSpace`, like the
+     * device memory, which is mapped uncacheable.
+     *
+     * Stores the frame of the first page of the `VmArea`
+     */
+    Device(PhysAddr),
+
+    /**
+     * Frames of a `MemObject`, which could be mapped by other
+     * `UserSpace`s at the same time.
+     *
+     * Stores the index of the `MemObject` page mapped by the first page of
+     * the `VmArea`
+     */
+    Shared(Arc<MemObject>, usize)
+}
+
+/**
+ * Page aligned range of the user-space with uniform protection and
+ * backing.
+ *
+ * The reserved areas only keep the range busy, their pages become
+ * accessible once committed
+ */
+#[derive(Clone)]
+pub struct VmArea {
+    m_virt_range: Range<VirtAddr>,
+    m_prot_flags: VmProtFlags,
+    m_is_committed: bool,
+    m_backing: VmBacking
+}
+
+impl VmArea /* Constructors */ {
+    /**
+     * Constructs a `VmArea` with the given attributes
+     */
+    fn new(virt_range: Range<VirtAddr>,
+           prot_flags: VmProtFlags,
+           is_committed: bool,
+           backing: VmBacking)
+           -> Self {
+        Self { m_virt_range: virt_range,
+               m_prot_flags: prot_flags,
+               m_is_committed: is_committed,
+               m_backing: backing }
+    }
+}
+
+impl VmArea /* Getters */ {
+    /**
+     * Returns the page aligned range of this `VmArea`
+     */
+    pub fn virt_range(&self) -> &Range<VirtAddr> {
+        &self.m_virt_range
+    }
+
+    /**
+     * Returns the `VmProtFlags` of the pages
+     */
+    pub fn prot_flags(&self) -> VmProtFlags {
+        self.m_prot_flags
+    }
+
+    /**
+     * Returns whether the pages are accessible
+     */
+    pub fn is_committed(&self) -> bool {
+        self.m_is_committed
+    }
+
+    /**
+     * Returns the `VmBacking` of the pages
+     */
+    pub fn backing(&self) -> &VmBacking {
+        &self.m_backing
+    }
+}
+
+impl VmArea /* Privates */ {
+    /**
+     * Splits this `VmArea` at the given page aligned `VirtAddr` and
+     * returns the upper part
+     */
+    fn split_off(&mut self, virt_addr: VirtAddr) -> Self {
+        let lower_size = *virt_addr - *self.m_virt_range.start;
+        let upper_backing = match &self.m_backing {
+            VmBacking::Anonymous => VmBacking::Anonymous,
+            VmBacking::Device(phys_frame) => {
+                VmBacking::Device(phys_frame.offset(lower_size))
+            },
+            VmBacking::Shared(mem_object, first_page_index) => {
+                VmBacking::Shared(mem_object.clone(),
+                                  first_page_index + lower_size / Page4KiB::SIZE)
+            },
+        };
+        let upper_vm_area = Self::new(virt_addr..self.m_virt_range.end,
+                                      self.m_prot_flags,
+                                      self.m_is_committed,
+                                      upper_backing);
+
+        self.m_virt_range.end = virt_addr;
+        upper_vm_area
+    }
+
+    /**
+     * Returns whether the given `VmArea` continues this one with the same
+     * attributes
+     */
+    fn is_mergeable_with(&self, next_vm_area: &Self) -> bool {
+        let is_same_backing = match (&self.m_backing, &next_vm_area.m_backing) {
+            (VmBacking::Anonymous, VmBacking::Anonymous) => true,
+            (VmBacking::Device(phys_frame), VmBacking::Device(next_phys_frame)) => {
+                phys_frame.offset(self.size()) == *next_phys_frame
+            },
+            (VmBacking::Shared(mem_object, first_page_index),
+             VmBacking::Shared(next_mem_object, next_first_page_index)) => {
+                Arc::ptr_eq(mem_object, next_mem_object)
+                && first_page_index + self.pages_count() == *next_first_page_index
+            },
+            _ => false
+        };
+
+        is_same_backing
+        && self.m_virt_range.end == next_vm_area.m_virt_range.start
+        && self.m_prot_flags == next_vm_area.m_prot_flags
+        && self.m_is_committed == next_vm_area.m_is_committed
+    }
+
+    /**
+     * Returns the size in bytes of this `VmArea`
+     */
+    fn size(&self) -> usize {
+        *self.m_virt_range.end - *self.m_virt_range.start
+    }
+
+    /**
+     * Returns the amount of pages of this `VmArea`
+     */
+    fn pages_count(&self) -> usize {
+        self.size() / Page4KiB::SIZE
+    }
+
+    /**
+     * Returns the `MemObject` and the index of his page mapped by the
+     * given page, when this `VmArea` is `VmBacking::Shared`
+     */
+    fn mem_object_page(&self,
+                       page_virt_addr: VirtAddr)
+                       -> Option<(&Arc<MemObject>, usize)> {
+        match &self.m_backing {
+            VmBacking::Shared(mem_object, first_page_index) => {
+                let page_index = first_page_index
+                                 + (*page_virt_addr - *self.m_virt_range.start)
+                                   / Page4KiB::SIZE;
+                Some((mem_object, page_index))
+            },
+            _ => None
+        }
+    }
+
+    /**
+     * Returns an `Iterator` over the `VirtAddr` of the pages
+     */
+    fn pages(&self) -> impl Iterator<Item = VirtAddr> {
+        let virt_start = self.m_virt_range.start;
+        (0..self.size()).step_by(Page4KiB::SIZE)
+                        .map(move |page_offset| virt_start.offset(page_offset))
+    }
+}
+
+/**
+ * Address space of a `Process`.
+ *
+ * The user-space half of the `PageDir` is described by non-overlapping
+ * `VmArea`s: the ranges are first reserved, then committed to become
+ * accessible with the requested protection.
+ *
+ * The frames of the `VmBacking::Anonymous` areas are owned by the
+ * `UserSpace` and freed when unmapped, while the frames of the
+ * `VmBacking::Shared` ones are referenced until unmapped
+ */
+pub struct UserSpace {
+    m_page_dir: PageDir,
+    m_vm_areas: SpinMutex<BTreeMap<VirtAddr, VmArea>>,
+    m_is_kernel: bool
+}
+
+impl UserSpace /* Constants */ {
+    /**
+     * Unmapped gap left above the areas placed by the `UserSpace`, which
+     * catches the overflows of the areas below
+     */
+    const GUARD_SIZE: usize = Page4KiB::SIZE;
+}
+
+impl UserSpace /* Constructors */ {
+    /**
+     * Constructs an empty `UserSpace` with a new `PageDir` which shares
+     * the kernel half
+     */
+    pub fn new() -> Option<Self> {
+        Some(Self { m_page_dir: PageDir::new_user()?,
+                    m_vm_areas: SpinMutex::const_new(BTreeMap::new()),
+                    m_is_kernel: false })
+    }
+
+    /**
+     * Constructs the `UserSpace` of the kernel `Process`, which uses the
+     * current `PageDir` and never maps user memory
+     */
+    pub fn new_kernel() -> Self {
+        Self { m_page_dir: PageDir::current(),
+               m_vm_areas: SpinMutex::const_new(BTreeMap::new()),
+               m_is_kernel: true }
+    }
+}
+
+impl UserSpace /* Methods */ {
+    /**
+     * Reserves a range of the given size and returns his first
+     * `VirtAddr`.
+     *
+     * The range begins at `virt_start` when given, otherwise it is placed
+     * below the highest free range of the user-space
+     */
+    pub fn reserve(&self,
+                   virt_start: Option<VirtAddr>,
+                   size: usize)
+                   -> UserSpaceResult<VirtAddr> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let virt_range = Self::find_free_range(&vm_areas, virt_start, size)?;
+        vm_areas.insert(virt_range.start,
+                        VmArea::new(virt_range.clone(),
+                                    VmProtFlags::new_zero(),
+                                    false,
+                                    VmBacking::Anonymous));
+        Ok(virt_range.start)
+    }
+
+    /**
+     * Commits the given reserved range with the given `VmProtFlags`.
+     *
+     * The frames are allocated and zeroed at the first access of each
+     * page, the already mapped pages keep their frames and take the new
+     * protection
+     */
+    pub fn commit(&self,
+                  virt_range: Range<VirtAddr>,
+                  prot_flags: VmProtFlags)
+                  -> UserSpaceResult<()> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let area_starts = Self::isolate_range(&mut vm_areas, &virt_range)?;
+        if area_starts.iter()
+                      .any(|area_start| {
+                          !matches!(vm_areas[area_start].backing(), VmBacking::Anonymous)
+                      })
+        {
+            return Err((OsErrorClass::InvalidArgument,
+                        Some("Range not backed by anonymous memory")));
+        }
+
+        let mut is_remapped = false;
+        for area_start in area_starts {
+            let vm_area = vm_areas.get_mut(&area_start).unwrap();
+            vm_area.m_prot_flags = prot_flags;
+            vm_area.m_is_committed = true;
+
+            is_remapped |= self.remap_mapped_pages(vm_area)?;
+        }
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        drop(vm_areas);
+
+        if is_remapped {
+            self.shootdown_and_release(Vec::new());
+        }
+        Ok(())
+    }
+
+    /**
+     * Maps immediately all the pages of the given committed range, which
+     * otherwise are mapped at their first access
+     */
+    pub fn populate(&self, virt_range: Range<VirtAddr>) -> UserSpaceResult<()> {
+        let mut vm_areas = self.lock_loaded_areas(&virt_range)?;
+
+        let area_starts = Self::isolate_range(&mut vm_areas, &virt_range)?;
+        if area_starts.iter().any(|area_start| !vm_areas[area_start].is_committed()) {
+            return Err((OsErrorClass::InvalidArgument, Some("Range not committed")));
+        }
+
+        for area_start in area_starts {
+            let vm_area = &vm_areas[&area_start];
+            for page_virt_addr in vm_area.pages() {
+                if self.mapped_phys_frame(page_virt_addr).is_none() {
+                    self.map_page(vm_area, page_virt_addr)?;
+                }
+            }
+        }
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        Ok(())
+    }
+
+    /**
+     * Changes the `VmProtFlags` of the given committed range
+     */
+    pub fn protect(&self,
+                   virt_range: Range<VirtAddr>,
+                   prot_flags: VmProtFlags)
+                   -> UserSpaceResult<()> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let area_starts = Self::isolate_range(&mut vm_areas, &virt_range)?;
+        if area_starts.iter().any(|area_start| !vm_areas[area_start].is_committed()) {
+            return Err((OsErrorClass::InvalidArgument, Some("Range not committed")));
+        }
+
+        let mut is_remapped = false;
+        for area_start in area_starts {
+            let vm_area = vm_areas.get_mut(&area_start).unwrap();
+            vm_area.m_prot_flags = prot_flags;
+
+            is_remapped |= self.remap_mapped_pages(vm_area)?;
+        }
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        drop(vm_areas);
+
+        if is_remapped {
+            self.shootdown_and_release(Vec::new());
+        }
+        Ok(())
+    }
+
+    /**
+     * Maps again read-only the clean pages of the file synced `MemObject`s
+     * of the given range, so the writes which follow
+     * `MemObject::take_dirty_pages()` mark them again
+     */
+    pub fn track_clean_pages(&self, virt_range: Range<VirtAddr>) -> UserSpaceResult<()> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let area_starts = Self::isolate_range(&mut vm_areas, &virt_range)?;
+        let mut is_remapped = false;
+        for area_start in area_starts {
+            is_remapped |= self.remap_mapped_pages(&vm_areas[&area_start])?;
+        }
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        drop(vm_areas);
+
+        if is_remapped {
+            self.shootdown_and_release(Vec::new());
+        }
+        Ok(())
+    }
+
+    /**
+     * Unmaps the `VmArea`s which overlap the given range, the parts of the
+     * areas outside the range are kept.
+     *
+     * The range could include free gaps
+     */
+    pub fn unmap(&self, virt_range: Range<VirtAddr>) -> UserSpaceResult<()> {
+        Self::validate_range(&virt_range)?;
+
+        let mut vm_areas = self.m_vm_areas.lock();
+        Self::split_area_at(&mut vm_areas, virt_range.start);
+        Self::split_area_at(&mut vm_areas, virt_range.end);
+
+        let area_starts: Vec<_> = vm_areas.range(virt_range.clone())
+                                          .map(|(area_start, _)| *area_start)
+                                          .collect();
+        let mut unmapped_phys_frames = Vec::new();
+        for area_start in area_starts {
+            let vm_area = vm_areas.remove(&area_start).unwrap();
+            unmapped_phys_frames.extend(self.unmap_pages(&vm_area));
+        }
+        drop(vm_areas);
+
+        self.shootdown_and_release(unmapped_phys_frames);
+        Ok(())
+    }
+
+    /**
+     * Maps the given page aligned physical range as
+     * `VmBacking::Device` memory and returns the `VirtAddr` of his first
+     * page.
+     *
+     * The mapping begins at `virt_start` when given, otherwise the range
+     * is placed as `UserSpace::reserve()` does
+     */
+    pub fn map_device(&self,
+                      virt_start: Option<VirtAddr>,
+                      phys_range: Range<PhysAddr>)
+                      -> UserSpaceResult<VirtAddr> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let map_size = *phys_range.end - *phys_range.start;
+        let virt_range = Self::find_free_range(&vm_areas, virt_start, map_size)?;
+        let vm_area = VmArea::new(virt_range.clone(),
+                                  VmProtFlags::new_zero()
+                                  | VmProtBits::Readable
+                                  | VmProtBits::Writeable,
+                                  true,
+                                  VmBacking::Device(phys_range.start));
+
+        for page_virt_addr in vm_area.pages() {
+            self.map_page(&vm_area, page_virt_addr)?;
+        }
+        vm_areas.insert(virt_range.start, vm_area);
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        Ok(virt_range.start)
+    }
+
+    /**
+     * Maps all the pages of the given `MemObject` as `VmBacking::Shared`
+     * memory with the given `VmProtFlags` and returns the `VirtAddr` of
+     * his first page.
+     *
+     * The pages are mapped at their first access, the mapping begins at
+     * `virt_start` when given, otherwise the range is placed as
+     * `UserSpace::reserve()` does
+     */
+    pub fn map_shared(&self,
+                      virt_start: Option<VirtAddr>,
+                      mem_object: Arc<MemObject>,
+                      prot_flags: VmProtFlags)
+                      -> UserSpaceResult<VirtAddr> {
+        let mut vm_areas = self.m_vm_areas.lock();
+
+        let virt_range = Self::find_free_range(&vm_areas, virt_start, mem_object.size())?;
+        vm_areas.insert(virt_range.start,
+                        VmArea::new(virt_range.clone(),
+                                    prot_flags,
+                                    true,
+                                    VmBacking::Shared(mem_object, 0)));
+
+        Self::merge_areas(&mut vm_areas, &virt_range);
+        Ok(virt_range.start)
+    }
+
+    /**
+     * Shares the memory of this `UserSpace` with a new one, which has the
+     * same `VmArea`s.
+     *
+     * The mapped anonymous pages become read-only into both the
+     * `UserSpace`s, the first write into one of them gives to the writer
+     * his own copy of the page, while the `MemObject`s pages remain
+     * shared
+     */
+    pub fn clone_copy_on_write(&self) -> UserSpaceResult<UserSpace> {
+        let cloned_user_space = Self::new().ok_or((OsErrorClass::NotEnoughMemory, None))?;
+
+        /* the areas are given first, so the pages shared before a failure
+         * are released by the drop of the new UserSpace
+         */
+        let vm_areas = self.m_vm_areas.lock();
+        *cloned_user_space.m_vm_areas.lock() = vm_areas.clone();
+
+        let mut is_write_protected = false;
+        for vm_area in vm_areas.values() {
+            for page_virt_addr in vm_area.pages() {
+                let phys_frame = match self.mapped_phys_frame(page_virt_addr) {
+                    Some(phys_frame) => phys_frame,
+                    None => continue
+                };
+
+                match vm_area.backing() {
+                    VmBacking::Anonymous => {
+                        MemManager::instance().share_phys_frame(phys_frame);
+                        self.m_page_dir
+                            .ensure_page_table_entry::<Page4KiB>(page_virt_addr)
+                            .unwrap()
+                            .set_writeable(false);
+                        is_write_protected = true;
+                    },
+                    VmBacking::Shared(..) => {
+                        MemManager::instance().share_phys_frame(phys_frame)
+                    },
+                    VmBacking::Device(_) => {}
+                }
+                cloned_user_space.map_frame(vm_area, page_virt_addr, phys_frame)?;
+            }
+        }
+        drop(vm_areas);
+
+        /* the other threads must not write the shared frames anymore */
+        if is_write_protected {
+            self.shootdown_and_release(Vec::new());
+        }
+        Ok(cloned_user_space)
+    }
+
+    /**
+     * Resolves the page fault occurred accessing the given `VirtAddr`
+     * against the `VmArea`s.
+     *
+     * The committed pages not yet mapped are mapped with their frames,
+     * the writes into the copy-on-write pages copy them, the writes into
+     * the `MemObject`s pages mark them dirty, while the other faults are
+     * violations described by the returned message
+     */
+    pub fn resolve_page_fault(&self,
+                              fault_virt_addr: VirtAddr,
+                              vm_access: VmAccess)
+                              -> UserSpaceResult<()> {
+        let page_virt_addr = fault_virt_addr.align_down(Page4KiB::SIZE);
+        let page_range = page_virt_addr..page_virt_addr.offset(Page4KiB::SIZE);
+        let vm_areas = self.lock_loaded_areas(&page_range)?;
+
+        let vm_area = match Self::area_at(&vm_areas, fault_virt_addr) {
+            Some(vm_area) => vm_area,
+            None => {
+                return Err((OsErrorClass::InvalidMemoryAccess,
+                            Some("Access to unmapped memory")))
+            },
+        };
+        if !vm_area.is_committed() {
+            return Err((OsErrorClass::InvalidMemoryAccess,
+                        Some("Access to reserved memory")));
+        } else if !vm_area.prot_flags().is_enabled(vm_access.required_prot()) {
+            let violation_msg = match vm_access {
+                VmAccess::Read => "Read of not readable memory",
+                VmAccess::Write => "Write into read-only memory",
+                VmAccess::Execute => "Execution of not executable memory"
+            };
+            return Err((OsErrorClass::InvalidMemoryAccess, Some(violation_msg)));
+        }
+
+        /* the written pages of the file synced memory objects are mapped
+         * writeable only once marked, so they are written back
+         */
+        let mem_object_page = vm_area.mem_object_page(page_virt_addr);
+        if vm_access == VmAccess::Write {
+            if let Some((mem_object, page_index)) = mem_object_page {
+                mem_object.mark_dirty(page_index);
+            }
+        }
+
+        let page_table_entry = self.m_page_dir.mapped_page_table_entry(page_virt_addr);
+        let replaced_phys_frame = match page_table_entry {
+            Some(page_table_entry)
+                if vm_access == VmAccess::Write && !page_table_entry.is_writeable() =>
+            {
+                if mem_object_page.is_some() {
+                    self.map_page(vm_area, page_virt_addr)?;
+                    None
+                } else {
+                    self.break_copy_on_write(vm_area, page_virt_addr)?
+                }
+            },
+
+            /* the page could be already mapped by another thread of the process,
+             * otherwise the mapping doesn't allow the access and retrying it
+             * would fault again
+             */
+            Some(page_table_entry) => {
+                let is_allowed = page_table_entry.is_user()
+                                 && (vm_access != VmAccess::Execute
+                                     || !page_table_entry.is_no_execute());
+                return if is_allowed {
+                    Ok(())
+                } else {
+                    Err((OsErrorClass::InvalidMemoryAccess,
+                         Some("Access not allowed by the page mapping")))
+                };
+            },
+            None => {
+                self.map_page(vm_area, page_virt_addr)?;
+                None
+            }
+        };
+        drop(vm_areas);
+
+        /* the other threads could still read the copied frame */
+        if let Some(replaced_phys_frame) = replaced_phys_frame {
+            self.shootdown_and_release(vec![replaced_phys_frame]);
+        }
+        Ok(())
+    }
+}
+
+impl UserSpace /* Getters */ {
+    /**
+     * Returns the `PageDir` of this `UserSpace`
+     */
+    pub fn page_dir(&self) -> &PageDir {
+        &self.m_page_dir
+    }
+
+    /**
+     * Returns whether this is the `UserSpace` of the kernel `Process`
+     */
+    pub fn is_kernel(&self) -> bool {
+        self.m_is_kernel
+    }
+
+    /**
+     * Returns a copy of the `VmArea` which contains the given `VirtAddr`
+     */
+    pub fn vm_area_at(&self, virt_addr: VirtAddr) -> Option<VmArea> {
+        Self::area_at(&self.m_vm_areas.lock(), virt_addr).cloned()
+    }
+}
+
+impl UserSpace /* Privates */ {
+    /**
+     * Locks the `VmArea`s once the frames of the `MemObject`s pages of the
+     * given range are filled, so the backing files are never read while
+     * the areas are locked
+     */
+    fn lock_loaded_areas(&self,
+                         virt_range: &Range<VirtAddr>)
+                         -> UserSpaceResult<VmAreasGuard<'_>> {
+        loop {
+            let vm_areas = self.m_vm_areas.lock();
+            let unloaded_pages: Vec<_> =
+                vm_areas.range(..virt_range.end)
+                        .map(|(_, vm_area)| vm_area)
+                        .filter(|vm_area| vm_area.virt_range().end > virt_range.start)
+                        .flat_map(|vm_area| {
+                            vm_area.pages()
+                                   .filter(|page_virt_addr| {
+                                       virt_range.contains(page_virt_addr)
+                                   })
+                                   .filter_map(move |page_virt_addr| {
+                                       vm_area.mem_object_page(page_virt_addr)
+                                   })
+                        })
+                        .filter(|(mem_object, page_index)| {
+                            !mem_object.is_page_loaded(*page_index)
+                        })
+                        .map(|(mem_object, page_index)| (mem_object.clone(), page_index))
+                        .collect();
+            if unloaded_pages.is_empty() {
+                return Ok(vm_areas);
+            }
+
+            /* the areas could change meanwhile, so they are checked again */
+            drop(vm_areas);
+            for (mem_object, page_index) in unloaded_pages {
+                mem_object.phys_frame_at(page_index)?;
+            }
+        }
+    }
+
+    /**
+     * Maps the given page of the given `VmArea` with his protection,
+     * allocating the zeroed frame for the anonymous pages not yet mapped
+     * and taking a reference to the frame of the `MemObject`s pages
+     */
+    fn map_page(&self,
+                vm_area: &VmArea,
+                page_virt_addr: VirtAddr)
+                -> UserSpaceResult<()> {
+        let phys_frame = match (self.mapped_phys_frame(page_virt_addr), vm_area.backing())
+        {
+            (Some(phys_frame), _) => phys_frame,
+            (None, VmBacking::Anonymous) => Self::allocate_zeroed_frame()?,
+            (None, VmBacking::Device(phys_frame)) => {
+                phys_frame.offset(*page_virt_addr - *vm_area.virt_range().start)
+            },
+            (None, VmBacking::Shared(..)) => {
+                /* already filled by <UserSpace::lock_loaded_areas()> */
+                let (mem_object, page_index) =
+                    vm_area.mem_object_page(page_virt_addr).unwrap();
+                let phys_frame = mem_object.phys_frame_at(page_index)?;
+
+                MemManager::instance().share_phys_frame(phys_frame);
+                phys_frame
+            }
+        };
+        self.map_frame(vm_area, page_virt_addr, phys_frame)
+    }
+
+    /**
+     * Maps the given page of the given `VmArea` to the given frame.
+     *
+     * The shared anonymous frames are mapped read-only, so the first
+     * write copies them, like the clean pages of the file synced
+     * `MemObject`s, so the first write marks them dirty. The device
+     * memory is mapped uncacheable
+     */
+    fn map_frame(&self,
+                 vm_area: &VmArea,
+                 page_virt_addr: VirtAddr,
+                 phys_frame: PhysAddr)
+                 -> UserSpaceResult<()> {
+        let is_write_tracked = match vm_area.backing() {
+            VmBacking::Anonymous => {
+                MemManager::instance().is_phys_frame_shared(phys_frame)
+            },
+            VmBacking::Device(_) => false,
+            VmBacking::Shared(..) => {
+                let (mem_object, page_index) =
+                    vm_area.mem_object_page(page_virt_addr).unwrap();
+                mem_object.is_file_synced() && !mem_object.is_dirty(page_index)
+            }
+        };
+        let is_device = matches!(vm_area.backing(), VmBacking::Device(_));
+
+        let prot_flags = vm_area.prot_flags();
+        self.m_page_dir
+            .ensure_page_table_entry::<Page4KiB>(page_virt_addr)
+            .ok_or((OsErrorClass::NotEnoughMemory, None))?
+            .set_phys_frame(phys_frame)
+            .set_present(true)
+            .set_readable(prot_flags.is_enabled(VmProtBits::Readable))
+            .set_writeable(prot_flags.is_enabled(VmProtBits::Writeable)
+                           && !is_write_tracked)
+            .set_no_execute(!prot_flags.is_enabled(VmProtBits::Executable))
+            .set_cacheable(!is_device)
+            .set_user(true);
+        Ok(())
+    }
+
+    /**
+     * Applies the protection of the given `VmArea` to his already mapped
+     * pages and returns whether there was any
+     */
+    fn remap_mapped_pages(&self, vm_area: &VmArea) -> UserSpaceResult<bool> {
+        let mut is_remapped = false;
+        for page_virt_addr in vm_area.pages() {
+            if let Some(phys_frame) = self.mapped_phys_frame(page_virt_addr) {
+                self.map_frame(vm_area, page_virt_addr, phys_frame)?;
+                is_remapped = true;
+            }
+        }
+        Ok(is_remapped)
+    }
+
+    /**
+     * Gives to the given page a private copy of his shared frame, or makes
+     * it writeable when the other owners have already released the frame.
+     *
+     * Returns the shared frame replaced by the copy, which must be released
+     * once the stale TLB entries are flushed
+     */
+    fn break_copy_on_write(&self,
+                           vm_area: &VmArea,
+                           page_virt_addr: VirtAddr)
+                           -> UserSpaceResult<Option<PhysAddr>> {
+        let mem_manager = MemManager::instance();
+
+        let shared_phys_frame = self.mapped_phys_frame(page_virt_addr).unwrap();
+        if !mem_manager.is_phys_frame_shared(shared_phys_frame) {
+            self.map_frame(vm_area, page_virt_addr, shared_phys_frame)?;
+            return Ok(None);
+        }
+
+        let phys_frame = mem_manager.allocate_kernel_phys_frame()
+                                    .ok_or((OsErrorClass::NotEnoughMemory,
+                                            Some("Not enough memory to copy the page")))?;
+
+        /* copy through the physical memory mapping */
+        let layout_manager = mem_manager.layout_manager();
+        let src_virt_addr = layout_manager.phys_addr_to_virt_addr(shared_phys_frame);
+        let dest_virt_addr = layout_manager.phys_addr_to_virt_addr(phys_frame);
+        unsafe {
+            ptr::copy_nonoverlapping(src_virt_addr.as_ptr::<u8>(),
+                                     dest_virt_addr.as_ptr_mut::<u8>(),
+                                     Page4KiB::SIZE);
+        }
+        self.map_frame(vm_area, page_virt_addr, phys_frame)?;
+        Ok(Some(shared_phys_frame))
+    }
+
+    /**
+     * Unmaps the mapped pages of the given `VmArea` and returns the frames
+     * of the anonymous and `MemObject`s ones, which must be released once
+     * the stale TLB entries are flushed
+     */
+    fn unmap_pages(&self, vm_area: &VmArea) -> Vec<PhysAddr> {
+        let mut unmapped_phys_frames = Vec::new();
+        for page_virt_addr in vm_area.pages() {
+            let phys_frame = match self.mapped_phys_frame(page_virt_addr) {
+                Some(phys_frame) => phys_frame,
+                None => continue
+            };
+
+            /* the mapping invalidates the local TLB entry when dropped */
+            self.m_page_dir
+                .ensure_page_table_entry::<Page4KiB>(page_virt_addr)
+                .unwrap()
+                .set_unused();
+
+            if !matches!(vm_area.backing(), VmBacking::Device(_)) {
+                unmapped_phys_frames.push(phys_frame);
+            }
+        }
+        unmapped_phys_frames
+    }
+
+    /**
+     * Flushes the TLB entries of this `UserSpace` from the other
+     * `CpuCore`s, then releases the given unmapped frames.
+     *
+     * Must be called without holding the `VmArea`s lock, since the other
+     * `CpuCore`s could wait for it with the interrupts disabled
+     */
+    fn shootdown_and_release(&self, unmapped_phys_frames: Vec<PhysAddr>) {
+        Processor::instance().shootdown_tlb(&self.m_page_dir);
+
+        /* the shared frames are freed by their last owner */
+        for phys_frame in unmapped_phys_frames {
+            MemManager::instance().release_phys_frame(phys_frame);
+        }
+    }
+
+    /**
+     * Returns the frame which maps the given page, if any
+     */
+    fn mapped_phys_frame(&self, page_virt_addr: VirtAddr) -> Option<PhysAddr> {
+        self.m_page_dir
+            .mapped_page_table_entry(page_virt_addr)
+            .and_then(|page_table_entry| page_table_entry.phys_frame())
+    }
+
+    /**
+     * Returns the `VmArea` which contains the given `VirtAddr`
+     */
+    fn area_at(vm_areas: &BTreeMap<VirtAddr, VmArea>,
+               virt_addr: VirtAddr)
+               -> Option<&VmArea> {
+        vm_areas.range(..=virt_addr)
+                .next_back()
+                .map(|(_, vm_area)| vm_area)
+                .filter(|vm_area| vm_area.virt_range().contains(&virt_addr))
+    }
+
+    /**
+     * Returns the free page aligned range of the given size which begins
+     * at `virt_start` when given, otherwise the highest one below a guard
+     * gap
+     */
+    fn find_free_range(vm_areas: &BTreeMap<VirtAddr, VmArea>,
+                       virt_start: Option<VirtAddr>,
+                       size: usize)
+                       -> UserSpaceResult<Range<VirtAddr>> {
+        let user_space_range = LayoutManager::user_space_range();
+        let map_size = align_up(size, Page4KiB::SIZE);
+        if map_size == 0 || map_size > *user_space_range.end - *user_space_range.start {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid region size")));
+        }
+
+        if let Some(virt_start) = virt_start {
+            let virt_range = virt_start..VirtAddr::from(*virt_start + map_size);
+            Self::validate_range(&virt_range)?;
+
+            /* the last area which begins before the end must end before the
+             * start */
+            let is_overlapping = vm_areas.range(..virt_range.end)
+                                         .next_back()
+                                         .map_or(false, |(_, vm_area)| {
+                                             vm_area.virt_range().end > virt_range.start
+                                         });
+            if is_overlapping {
+                Err((OsErrorClass::IdentifierNotAvailable, Some("Region already in use")))
+            } else {
+                Ok(virt_range)
+            }
+        } else {
+            /* walk the gaps between the areas from the top of the
+             * user-space, the last gap ends at the begin of the
+             * user-space
+             */
+            let area_ranges =
+                vm_areas.values()
+                        .rev()
+                        .map(|vm_area| vm_area.virt_range().clone())
+                        .chain(iter::once(user_space_range.start
+                                          ..user_space_range.start));
+
+            let mut gap_end = *user_space_range.end;
+            for area_range in area_ranges {
+                let gap_start = *area_range.end;
+                if gap_end >= gap_start + map_size + Self::GUARD_SIZE {
+                    let virt_start = gap_end - Self::GUARD_SIZE - map_size;
+                    return Ok(VirtAddr::from(virt_start)
+                              ..VirtAddr::from(virt_start + map_size));
+                }
+                gap_end = *area_range.start;
+            }
+            Err((OsErrorClass::NotEnoughMemory, Some("No free user region")))
+        }
+    }
+
+    /**
+     * Ensures that the given range is page aligned, not empty and inside
+     * the user-space
+     */
+    fn validate_range(virt_range: &Range<VirtAddr>) -> UserSpaceResult<()> {
+        let user_space_range = LayoutManager::user_space_range();
+        if !virt_range.start.is_aligned(Page4KiB::SIZE)
+           || !virt_range.end.is_aligned(Page4KiB::SIZE)
+           || virt_range.start >= virt_range.end
+           || virt_range.start < user_space_range.start
+           || virt_range.end > user_space_range.end
+        {
+            Err((OsErrorClass::InvalidArgument, Some("Invalid user region")))
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+     * Splits the `VmArea`s at the boundaries of the given range, which must
+     * be entirely covered by areas, and returns the starts of the areas
+     * which compose it
+     */
+    fn isolate_range(vm_areas: &mut BTreeMap<VirtAddr, VmArea>,
+                     virt_range: &Range<VirtAddr>)
+                     -> UserSpaceResult<Vec<VirtAddr>> {
+        Self::validate_range(virt_range)?;
+
+        let mut covered_end = virt_range.start;
+        while covered_end < virt_range.end {
+            covered_end = Self::area_at(vm_areas, covered_end)
+                .map(|vm_area| vm_area.virt_range().end)
+                .ok_or((OsErrorClass::InvalidArgument, Some("Range not reserved")))?;
+        }
+
+        Self::split_area_at(vm_areas, virt_range.start);
+        Self::split_area_at(vm_areas, virt_range.end);
+        Ok(vm_areas.range(virt_range.clone())
+                   .map(|(area_start, _)| *area_start)
+                   .collect())
+    }
+
+    /**
+     * Splits the `VmArea` which contains the given `VirtAddr`, if it
+     * doesn't already begin there
+     */
+    fn split_area_at(vm_areas: &mut BTreeMap<VirtAddr, VmArea>, virt_addr: VirtAddr) {
+        let upper_vm_area =
+            vm_areas.range_mut(..virt_addr)
+                    .next_back()
+                    .map(|(_, vm_area)| vm_area)
+                    .filter(|vm_area| vm_area.virt_range().end > virt_addr)
+                    .map(|vm_area| vm_area.split_off(virt_addr));
+        if let Some(upper_vm_area) = upper_vm_area {
+            vm_areas.insert(virt_addr, upper_vm_area);
+        }
+    }
+
+    /**
+     * Merges the adjacent `VmArea`s with the same attributes which overlap
+     * or surround the given range, left split by the operations on it
+     */
+    fn merge_areas(vm_areas: &mut BTreeMap<VirtAddr, VmArea>,
+                   virt_range: &Range<VirtAddr>) {
+        /* the first candidate is the neighbour which precedes the range */
+        let mut area_start =
+            match vm_areas.range(..virt_range.start)
+                          .next_back()
+                          .or_else(|| vm_areas.range(virt_range.start..).next())
+            {
+                Some((area_start, _)) => *area_start,
+                None => return
+            };
+
+        /* the last candidate is the neighbour which follows the range */
+        loop {
+            let next_area_start = match vm_areas.range(area_start..).nth(1) {
+                Some((next_area_start, _)) if *next_area_start <= virt_range.end => {
+                    *next_area_start
+                },
+                _ => break
+            };
+
+            if vm_areas[&area_start].is_mergeable_with(&vm_areas[&next_area_start]) {
+                let next_vm_area = vm_areas.remove(&next_area_start).unwrap();
+                vm_areas.get_mut(&area_start).unwrap().m_virt_range.end =
+                    next_vm_area.m_virt_range.end;
+            } else {
+                area_start = next_area_start;
+            }
+        }
+    }
+
+    /**
+     * Allocates a physical frame cleared to zero
+     */
+    fn allocate_zeroed_frame() -> UserSpaceResult<PhysAddr> {
+        let phys_frame =
+            MemManager::instance().allocate_kernel_phys_frame()
+                                  .ok_or((OsErrorClass::NotEnoughMemory, None))?;
+
+        let frame_virt_addr =
+            MemManager::instance().layout_manager().phys_addr_to_virt_addr(phys_frame);
+        unsafe {
+            ptr::write_bytes(frame_virt_addr.as_ptr_mut::<u8>(), 0, Page4KiB::SIZE);
+        }
+        Ok(phys_frame)
+    }
+}
+
+impl Drop for UserSpace {
+    fn drop(&mut self) {
+        /* the kernel PageDir is shared by all the kernel threads */
+        if self.m_is_kernel {
+            return;
+        }
+
+        /* no CpuCore runs the UserSpace of a dead Process, so the frames are
+         * released without shootdown
+         */
+        for vm_area in self.m_vm_areas.lock().values() {
+            for phys_frame in self.unmap_pages(vm_area) {
+                MemManager::instance().release_phys_frame(phys_frame);
+            }
+        }
+        self.m_page_dir.release_user_page_tables();
+        MemManager::instance().free_kernel_phys_frame(self.m_page_dir.root_phys_frame());
+    }
+}
@@ -0,0 +1,536 @@
+/*! FAT filesystem host test harness
+ *
+ * Runs the scenarios which exercise the FAT driver on FAT12, FAT16 and
+ * FAT32 images. The images are created and verified with the `fatfs`
+ * crate, which writes the same layout of `mkfs.vfat`, so each scenario
+ * checks the compatibility in both the directions. The process exits
+ * with a failure status when any scenario fails
+ */
+
+use std::{
+    io::{
+        Cursor,
+        Read,
+        Write
+    },
+    process,
+    time::Duration
+};
+
+use api_data::error::class::OsErrorClass;
+use fat_fs::{
+    time::FatTimestamp,
+    volume::{
+        DirLocation,
+        EntryLocation,
+        FatVolume
+    },
+    FatFsResult
+};
+use meetix_fs::dev::MemBlockDevice;
+
+type TestVolume = FatVolume<MemBlockDevice>;
+type TestResult = Result<(), String>;
+type ScenarioFn = fn(fatfs::FatType) -> TestResult;
+
+const SECTOR_SIZE: usize = 512;
+
+/* 2021-06-15 12:34:56.78 UTC */
+const FIXED_CLOCK_SECS: u64 = 1_623_760_496;
+const FIXED_CLOCK_MILLIS: u64 = 780;
+
+fn main() {
+    let scenarios: [(&str, ScenarioFn); 6] =
+        [("read the reference tree", test_read_reference),
+         ("write visible to the reference", test_write_reference),
+         ("truncate and remove", test_truncate_remove),
+         ("timestamps", test_timestamps),
+         ("short names collisions", test_short_names),
+         ("directory growth", test_dir_growth)];
+
+    let mut failures = 0;
+    for fat_type in
+        [fatfs::FatType::Fat12, fatfs::FatType::Fat16, fatfs::FatType::Fat32].iter()
+    {
+        for (scenario_name, scenario_fn) in scenarios.iter() {
+            let result = scenario_fn(*fat_type);
+            failures += report(&format!("{:?} {}", fat_type, scenario_name), result);
+        }
+    }
+
+    if failures > 0 {
+        println!("{} scenarios failed", failures);
+        process::exit(1);
+    }
+    println!("All scenarios passed");
+}
+
+/**
+ * Reads with the driver the tree written by the reference
+ */
+fn test_read_reference(fat_type: fatfs::FatType) -> TestResult {
+    let mut image = format_image(fat_type)?;
+    {
+        let file_system = open_reference(&mut image)?;
+        let root_dir = file_system.root_dir();
+        write_reference_file(&root_dir, "README.TXT", b"MeetiX")?;
+        write_reference_file(&root_dir, "A long file name.txt", &pattern(5000, 3))?;
+        write_reference_file(&root_dir, "lower.txt", b"lower")?;
+        write_reference_file(&root_dir, "Ünïcödé.dat", &pattern(10, 5))?;
+
+        let sub_dir = root_dir.create_dir("Sub Dir").map_err(io_err)?;
+        write_reference_file(&sub_dir, "nested.bin", &pattern(70_000, 7))?;
+    }
+
+    let mut volume = mount(image)?;
+    check_eq(format!("{:?}", volume.boot_sector().fat_type()),
+             format!("{:?}", fat_type),
+             "detected FAT type")?;
+
+    let root_dir = volume.root_dir();
+    check_eq(sorted_names(&mut volume, root_dir)?,
+             strings(&["A long file name.txt",
+                       "README.TXT",
+                       "Sub Dir",
+                       "lower.txt",
+                       "Ünïcödé.dat"]),
+             "root names")?;
+
+    check_eq(read_all(&mut volume, root_dir, "readme.txt")?,
+             b"MeetiX".to_vec(),
+             "case insensitive lookup")?;
+    check_eq(read_all(&mut volume, root_dir, "A long file name.txt")?,
+             pattern(5000, 3),
+             "long name data")?;
+    check_eq(read_all(&mut volume, root_dir, "Ünïcödé.dat")?,
+             pattern(10, 5),
+             "unicode name data")?;
+
+    let sub_dir = lookup(&mut volume, root_dir, "Sub Dir")?.content_location();
+    check_eq(read_all(&mut volume, sub_dir, "nested.bin")?,
+             pattern(70_000, 7),
+             "multi cluster data")
+}
+
+/**
+ * Writes with the driver a tree which is read by the reference
+ */
+fn test_write_reference(fat_type: fatfs::FatType) -> TestResult {
+    let mut volume = mount(format_image(fat_type)?)?;
+    let root_dir = volume.root_dir();
+
+    let new_dir = fs(volume.create_node(root_dir, "New Directory", true))?;
+    let data_file =
+        fs(volume.create_node(new_dir.content_location(), "data.bin", false))?.location();
+
+    /* write the second half first, so the first one fills a hole */
+    let data = pattern(100_000, 11);
+    fs(volume.write_data(data_file, 50_000, &data[50_000..]))?;
+    check_eq(read_location(&mut volume, data_file)?[..50_000].to_vec(),
+             vec![0; 50_000],
+             "zeroed hole")?;
+    fs(volume.write_data(data_file, 0, &data[..50_000]))?;
+
+    let upper_file = fs(volume.create_node(root_dir, "UPPER.TXT", false))?.location();
+    fs(volume.write_data(upper_file, 0, b"upper"))?;
+    let mixed_file = fs(volume.create_node(root_dir, "mixed.Case", false))?.location();
+    fs(volume.write_data(mixed_file, 0, b"mixed"))?;
+
+    let free_clusters = fs(volume.free_clusters())?;
+    let mut image = unmount(volume)?;
+
+    let file_system = open_reference(&mut image)?;
+    let root_dir = file_system.root_dir();
+    check_eq(reference_names(&root_dir)?,
+             strings(&["New Directory", "UPPER.TXT", "mixed.Case"]),
+             "reference root names")?;
+    check_eq(read_reference_file(&root_dir, "New Directory/data.bin")?,
+             data,
+             "reference data")?;
+    check_eq(read_reference_file(&root_dir, "mixed.Case")?,
+             b"mixed".to_vec(),
+             "reference mixed case data")?;
+    check_eq(file_system.stats().map_err(io_err)?.free_clusters(),
+             free_clusters,
+             "free clusters")
+}
+
+/**
+ * Truncates, extends and removes files and directories
+ */
+fn test_truncate_remove(fat_type: fatfs::FatType) -> TestResult {
+    let mut volume = mount(format_image(fat_type)?)?;
+    let root_dir = volume.root_dir();
+    let initial_free_clusters = fs(volume.free_clusters())?;
+
+    let dir = fs(volume.create_node(root_dir, "Removed Dir", true))?;
+    let file = fs(volume.create_node(dir.content_location(), "truncated.bin", false))?;
+    fs(volume.write_data(file.location(), 0, &pattern(100_000, 13)))?;
+
+    fs(volume.set_data_size(file.location(), 1000))?;
+    fs(volume.set_data_size(file.location(), 5000))?;
+    let mut expected_data = pattern(1000, 13);
+    expected_data.resize(5000, 0);
+    check_eq(read_location(&mut volume, file.location())?,
+             expected_data,
+             "truncated data")?;
+
+    /* the directory could be removed only when empty */
+    let remove_result = volume.remove_node(root_dir, "removed dir");
+    check(matches!(remove_result, Err((OsErrorClass::OperationNotEnabled, _))),
+          "not empty directory removal refused")?;
+    fs(volume.remove_node(dir.content_location(), "truncated.bin"))?;
+    fs(volume.remove_node(root_dir, "removed dir"))?;
+
+    check(fs(volume.lookup(root_dir, "Removed Dir"))?.is_none(), "directory removed")?;
+    check_eq(fs(volume.free_clusters())?, initial_free_clusters, "released clusters")?;
+
+    let mut image = unmount(volume)?;
+    let file_system = open_reference(&mut image)?;
+    check_eq(reference_names(&file_system.root_dir())?,
+             Vec::<String>::new(),
+             "reference empty root")?;
+    check_eq(file_system.stats().map_err(io_err)?.free_clusters(),
+             initial_free_clusters,
+             "reference free clusters")
+}
+
+/**
+ * Checks the conversion of the timestamps in both the directions
+ */
+fn test_timestamps(fat_type: fatfs::FatType) -> TestResult {
+    let mut volume = mount(format_image(fat_type)?)?;
+    let root_dir = volume.root_dir();
+    let file = fs(volume.create_node(root_dir, "stamped.txt", false))?;
+    fs(volume.write_data(file.location(), 0, b"stamped"))?;
+
+    let short_entry = fs(volume.load_entry(file.location()))?;
+    check_eq(short_entry.creat().to_instant(),
+             fixed_clock(),
+             "creation instant round trip")?;
+    check_eq(short_entry.modify().to_instant(),
+             Duration::from_secs(FIXED_CLOCK_SECS),
+             "modification instant round trip")?;
+
+    let mut image = unmount(volume)?;
+    {
+        let file_system = open_reference(&mut image)?;
+        let root_dir = file_system.root_dir();
+        let dir_entry = root_dir.iter()
+                                .filter_map(Result::ok)
+                                .find(|dir_entry| dir_entry.file_name() == "stamped.txt")
+                                .ok_or("stamped.txt not found")?;
+
+        let modified = dir_entry.modified();
+        check_eq((modified.date.year, modified.date.month, modified.date.day),
+                 (2021, 6, 15),
+                 "reference modification date")?;
+        check_eq((modified.time.hour, modified.time.min, modified.time.sec),
+                 (12, 34, 56),
+                 "reference modification time")?;
+        check_eq(dir_entry.created().time.millis, 780, "reference creation millis")?;
+
+        /* 2000-02-29 23:59:58 */
+        let mut file = root_dir.create_file("leap.txt").map_err(io_err)?;
+        file.write_all(b"leap").map_err(io_err)?;
+        #[allow(deprecated)]
+        file.set_modified(fatfs::DateTime { date: fatfs::Date { year: 2000,
+                                                                month: 2,
+                                                                day: 29 },
+                                            time: fatfs::Time { hour: 23,
+                                                                min: 59,
+                                                                sec: 58,
+                                                                millis: 0 } });
+        file.flush().map_err(io_err)?;
+    }
+
+    let mut volume = mount(image)?;
+    let root_dir = volume.root_dir();
+    let leap_file = lookup(&mut volume, root_dir, "leap.txt")?;
+    check_eq(leap_file.short_entry().modify().to_instant(),
+             Duration::from_secs(951_868_798),
+             "reference instant")?;
+
+    /* the instants before the 1980 are clamped */
+    check_eq(FatTimestamp::from_instant(Duration::from_secs(1000)).to_instant(),
+             Duration::from_secs(315_532_800),
+             "clamped instant")
+}
+
+/**
+ * Creates many names which share the same 8.3 base name
+ */
+fn test_short_names(fat_type: fatfs::FatType) -> TestResult {
+    let mut volume = mount(format_image(fat_type)?)?;
+    let root_dir = volume.root_dir();
+
+    let mut expected_names = Vec::new();
+    for file_index in 0..12 {
+        let file_name = format!("Shared Prefix Name {}.text", file_index);
+        let file = fs(volume.create_node(root_dir, &file_name, false))?;
+        fs(volume.write_data(file.location(), 0, file_name.as_bytes()))?;
+        expected_names.push(file_name);
+    }
+    check(matches!(volume.create_node(root_dir, "SHARED prefix NAME 3.TEXT", false),
+                   Err((OsErrorClass::IdentifierNotAvailable, _))),
+          "case insensitive duplicate refused")?;
+
+    let mut short_names = fs(volume.dir_records(root_dir))?.iter()
+                                                           .map(|dir_record| {
+                                                               dir_record.short_entry()
+                                                                         .display_name()
+                                                           })
+                                                           .collect::<Vec<_>>();
+    short_names.sort();
+    short_names.dedup();
+    check_eq(short_names.len(), 12, "unique short names")?;
+
+    let mut image = unmount(volume)?;
+    let file_system = open_reference(&mut image)?;
+    let root_dir = file_system.root_dir();
+    expected_names.sort();
+    check_eq(reference_names(&root_dir)?, expected_names.clone(), "reference names")?;
+    for file_name in expected_names.iter() {
+        check_eq(read_reference_file(&root_dir, file_name)?,
+                 file_name.as_bytes().to_vec(),
+                 "reference data")?;
+    }
+    Ok(())
+}
+
+/**
+ * Fills the directories until they must grow, the FAT12/16 root is full
+ * at the end
+ */
+fn test_dir_growth(fat_type: fatfs::FatType) -> TestResult {
+    let mut volume = mount(format_image(fat_type)?)?;
+    let root_dir = volume.root_dir();
+
+    let dir = fs(volume.create_node(root_dir, "Crowded", true))?.content_location();
+    for file_index in 0..100 {
+        fs(volume.create_node(dir, &format!("Crowded File {:03}", file_index), false))?;
+    }
+    check_eq(fs(volume.dir_records(dir))?.len(), 100, "crowded children")?;
+
+    let mut fill_result = Ok(());
+    let mut created_files = 1;
+    while created_files < 2000 && fill_result.is_ok() {
+        fill_result = volume.create_node(root_dir, &format!("F{}", created_files), false)
+                            .map(|_| ());
+        created_files += 1;
+    }
+    match (fat_type, fill_result) {
+        (fatfs::FatType::Fat32, Ok(_)) => {},
+        (fatfs::FatType::Fat32, Err(err)) => return fs(Err(err)),
+        (_, result) => check(matches!(result, Err((OsErrorClass::LimitReached, _))),
+                             "fixed root directory full")?
+    }
+
+    let mut image = unmount(volume)?;
+    let file_system = open_reference(&mut image)?;
+    let crowded_dir = file_system.root_dir().open_dir("Crowded").map_err(io_err)?;
+    check_eq(reference_names(&crowded_dir)?.len(), 100, "reference crowded children")
+}
+
+/**
+ * Prints the result of the scenario and returns the failures count
+ */
+fn report(scenario_name: &str, result: TestResult) -> usize {
+    match result {
+        Ok(_) => {
+            println!("[ OK ] {}", scenario_name);
+            0
+        },
+        Err(err) => {
+            println!("[FAIL] {}: {}", scenario_name, err);
+            1
+        }
+    }
+}
+
+/**
+ * Returns an image formatted by the reference with the given FAT type
+ */
+fn format_image(fat_type: fatfs::FatType) -> Result<Vec<u8>, String> {
+    let image_size = match fat_type {
+        fatfs::FatType::Fat12 => 2 * 1024 * 1024,
+        fatfs::FatType::Fat16 => 16 * 1024 * 1024,
+        fatfs::FatType::Fat32 => 40 * 1024 * 1024
+    };
+
+    let mut image = Cursor::new(vec![0; image_size]);
+    let mut format_options = fatfs::FormatVolumeOptions::new().fat_type(fat_type);
+    if fat_type == fatfs::FatType::Fat32 {
+        format_options = format_options.bytes_per_cluster(512);
+    }
+    fatfs::format_volume(&mut image, format_options).map_err(io_err)?;
+    Ok(image.into_inner())
+}
+
+/**
+ * Opens the given image with the reference
+ */
+fn open_reference(image: &mut Vec<u8>)
+                  -> Result<fatfs::FileSystem<Cursor<&mut Vec<u8>>>, String> {
+    fatfs::FileSystem::new(Cursor::new(image), fatfs::FsOptions::new()).map_err(io_err)
+}
+
+/**
+ * Writes a new file with the reference
+ */
+fn write_reference_file<T>(dir: &fatfs::Dir<T>,
+                           file_name: &str,
+                           data: &[u8])
+                           -> TestResult
+    where T: fatfs::ReadWriteSeek {
+    dir.create_file(file_name).and_then(|mut file| file.write_all(data)).map_err(io_err)
+}
+
+/**
+ * Reads a file with the reference
+ */
+fn read_reference_file<T>(dir: &fatfs::Dir<T>,
+                          file_path: &str)
+                          -> Result<Vec<u8>, String>
+    where T: fatfs::ReadWriteSeek {
+    let mut data = Vec::new();
+    dir.open_file(file_path)
+       .and_then(|mut file| file.read_to_end(&mut data))
+       .map_err(io_err)?;
+    Ok(data)
+}
+
+/**
+ * Returns the sorted names of the given directory read by the reference
+ */
+fn reference_names<T>(dir: &fatfs::Dir<T>) -> Result<Vec<String>, String>
+    where T: fatfs::ReadWriteSeek {
+    let mut names = Vec::new();
+    for dir_entry in dir.iter() {
+        let file_name = dir_entry.map_err(io_err)?.file_name();
+        if file_name != "." && file_name != ".." {
+            names.push(file_name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/**
+ * Mounts the given image with the driver
+ */
+fn mount(image: Vec<u8>) -> Result<TestVolume, String> {
+    fs(FatVolume::mount(MemBlockDevice::from_image(SECTOR_SIZE, image), fixed_clock))
+}
+
+/**
+ * Unmounts the given volume and returns his image
+ */
+fn unmount(volume: TestVolume) -> Result<Vec<u8>, String> {
+    Ok(fs(volume.unmount())?.image().to_vec())
+}
+
+/**
+ * Returns the child of the given directory with the given name
+ */
+fn lookup(volume: &mut TestVolume,
+          dir: DirLocation,
+          name: &str)
+          -> Result<fat_fs::volume::FatDirRecord, String> {
+    fs(volume.lookup(dir, name))?.ok_or(format!("{} not found", name))
+}
+
+/**
+ * Returns the sorted names of the given directory read by the driver
+ */
+fn sorted_names(volume: &mut TestVolume,
+                dir: DirLocation)
+                -> Result<Vec<String>, String> {
+    let mut names =
+        fs(volume.dir_records(dir))?.iter()
+                                    .map(|dir_record| String::from(dir_record.name()))
+                                    .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/**
+ * Reads the whole data of the given child of the given directory
+ */
+fn read_all(volume: &mut TestVolume,
+            dir: DirLocation,
+            name: &str)
+            -> Result<Vec<u8>, String> {
+    let location = lookup(volume, dir, name)?.location();
+    read_location(volume, location)
+}
+
+/**
+ * Reads the whole data of the file at the given `EntryLocation`
+ */
+fn read_location(volume: &mut TestVolume,
+                 location: EntryLocation)
+                 -> Result<Vec<u8>, String> {
+    let file_size = fs(volume.load_entry(location))?.file_size() as usize;
+
+    let mut data = vec![0; file_size];
+    check_eq(fs(volume.read_data(location, 0, &mut data))?, file_size, "read bytes")?;
+    Ok(data)
+}
+
+/**
+ * Returns the owned copy of the given names
+ */
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| String::from(*name)).collect()
+}
+
+/**
+ * Returns a deterministic data pattern
+ */
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|byte_index| (byte_index as u8).wrapping_mul(seed).wrapping_add(seed))
+            .collect()
+}
+
+/**
+ * Returns always the same instant, to check the timestamps
+ */
+fn fixed_clock() -> Duration {
+    Duration::from_secs(FIXED_CLOCK_SECS) + Duration::from_millis(FIXED_CLOCK_MILLIS)
+}
+
+/**
+ * Converts the filesystem error to a printable one
+ */
+fn fs<T>(result: FatFsResult<T>) -> Result<T, String> {
+    result.map_err(|(err_class, err_msg)| {
+              format!("{:?}: {}", err_class, err_msg.unwrap_or(""))
+          })
+}
+
+/**
+ * Converts the reference error to a printable one
+ */
+fn io_err(err: std::io::Error) -> String {
+    format!("reference: {}", err)
+}
+
+fn check(condition: bool, what: &str) -> TestResult {
+    if condition {
+        Ok(())
+    } else {
+        Err(format!("{} failed", what))
+    }
+}
+
+fn check_eq<T>(value: T, expected: T, what: &str) -> TestResult
+    where T: PartialEq + std::fmt::Debug {
+    if value == expected {
+        Ok(())
+    } else if format!("{:?}", value).len() > 128 {
+        Err(format!("{} mismatch", what))
+    } else {
+        Err(format!("{}: {:?} != {:?}", what, value, expected))
+    }
+}
@@ -0,0 +1,690 @@
+/*! MeetiX filesystem checker
+ *
+ * Validates the consistency of a MeetiX filesystem image: the inodes, the
+ * directory tree, the links counters, the REF-CNT of the chunks against
+ * the inodes which reference them, the CRCs and the blocks bitmap.
+ *
+ * Without `--repair` the image is checked into memory and never written.
+ * The exit status follows the `e2fsck` convention: 0 when clean, 1 when
+ * all the problems were repaired, 4 when problems are left and 8 on
+ * operational errors
+ */
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet
+    },
+    env,
+    fs,
+    io::Read,
+    path::PathBuf,
+    process,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+use api_data::error::class::OsErrorClass;
+use meetix_fs::{
+    dev::{
+        ImageFileDevice,
+        MemBlockDevice,
+        TBlockDevice
+    },
+    layout::{
+        DirRecord,
+        Extent,
+        Inode,
+        NodeType,
+        SuperBlock
+    },
+    volume::{
+        default_prot_grants,
+        Volume
+    }
+};
+
+type CheckVolume = Volume<Box<dyn TBlockDevice>>;
+type CheckResult<T> = Result<T, String>;
+
+const USAGE: &str = "Usage: fsck.meetix [-y|--repair] <image>";
+
+/* exit status codes */
+const EXIT_CLEAN: i32 = 0;
+const EXIT_REPAIRED: i32 = 1;
+const EXIT_UNREPAIRED: i32 = 4;
+const EXIT_FAILURE: i32 = 8;
+
+/* name of the directory which collects the unreachable inodes */
+const LOST_FOUND_NAME: &str = "LostFound";
+
+fn main() {
+    let mut repair = false;
+    let mut image_path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-y" | "--repair" => repair = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(EXIT_CLEAN)
+            },
+            _ if arg.starts_with('-') || image_path.is_some() => {
+                eprintln!("fsck.meetix: Unexpected argument {}\n\n{}", arg, USAGE);
+                process::exit(EXIT_FAILURE)
+            },
+            _ => image_path = Some(PathBuf::from(arg))
+        }
+    }
+    let image_path = image_path.unwrap_or_else(|| {
+                                   eprintln!("fsck.meetix: Missing image path\n\n{}",
+                                             USAGE);
+                                   process::exit(EXIT_FAILURE)
+                               });
+
+    let exit_code = match FsChecker::open(&image_path, repair).and_then(FsChecker::check)
+    {
+        Ok(exit_code) => exit_code,
+        Err(err_msg) => {
+            eprintln!("fsck.meetix: {}: {}", image_path.display(), err_msg);
+            EXIT_FAILURE
+        }
+    };
+    process::exit(exit_code)
+}
+
+/**
+ * Checks and optionally repairs a mounted `Volume`
+ */
+struct FsChecker {
+    m_volume: CheckVolume,
+    m_repair: bool,
+    m_problems_found: usize,
+    m_problems_left: usize,
+    m_node_types: BTreeMap<u64, NodeType>,
+    m_references: BTreeMap<u64, u16>,
+    m_walked_dirs: BTreeSet<u64>,
+    m_lost_found_id: Option<u64>
+}
+
+impl FsChecker /* Constructors */ {
+    /**
+     * Mounts the given image, into memory when the repair is not requested
+     */
+    fn open(image_path: &PathBuf, repair: bool) -> CheckResult<Self> {
+        let block_size = read_block_size(image_path)?;
+        let device: Box<dyn TBlockDevice> = if repair {
+            Box::new(ImageFileDevice::open(image_path, block_size).map_err(|err| {
+                                                                      err.to_string()
+                                                                  })?)
+        } else {
+            let image = fs::read(image_path).map_err(|err| err.to_string())?;
+            Box::new(MemBlockDevice::from_image(block_size, image))
+        };
+
+        let volume = Volume::mount(device, host_clock).map_err(fs_err)?;
+        if !volume.was_clean() {
+            println!("The filesystem was not cleanly unmounted");
+        }
+
+        Ok(Self { m_volume: volume,
+                  m_repair: repair,
+                  m_problems_found: 0,
+                  m_problems_left: 0,
+                  m_node_types: BTreeMap::new(),
+                  m_references: BTreeMap::new(),
+                  m_walked_dirs: BTreeSet::new(),
+                  m_lost_found_id: None })
+    }
+}
+
+impl FsChecker /* Methods */ {
+    /**
+     * Runs all the check passes and returns the exit status
+     */
+    fn check(mut self) -> CheckResult<i32> {
+        println!("Pass 1: checking the inodes");
+        self.check_inodes()?;
+        if self.m_node_types.get(&Inode::ROOT_ID) != Some(&NodeType::Dir) {
+            println!("The root directory is lost, the filesystem can't be repaired");
+            return Ok(EXIT_UNREPAIRED);
+        }
+
+        println!("Pass 2: checking the directories tree");
+        self.m_references.insert(Inode::ROOT_ID, 1);
+        self.walk_dir(Inode::ROOT_ID)?;
+
+        println!("Pass 3: checking the unreachable inodes");
+        self.check_unreachable_inodes()?;
+
+        println!("Pass 4: checking the links counters");
+        self.check_links()?;
+
+        println!("Pass 5: checking the chunks and the blocks bitmap");
+        let used_blocks = self.check_chunks()?;
+        self.check_blocks_bitmap(&used_blocks);
+
+        println!("Pass 6: checking the free counters");
+        self.check_free_counters();
+
+        if self.m_repair {
+            self.m_volume.unmount().map_err(fs_err)?;
+        }
+
+        println!("{} problems found, {} repaired",
+                 self.m_problems_found,
+                 self.m_problems_found - self.m_problems_left);
+        if self.m_problems_found == 0 {
+            Ok(EXIT_CLEAN)
+        } else if self.m_problems_left == 0 {
+            Ok(EXIT_REPAIRED)
+        } else {
+            Ok(EXIT_UNREPAIRED)
+        }
+    }
+
+    /**
+     * Loads all the allocated inodes, the corrupted ones are released
+     */
+    fn check_inodes(&mut self) -> CheckResult<()> {
+        for inode_id in Inode::ROOT_ID..self.m_volume.super_block().inodes_count() {
+            match self.m_volume.load_inode(inode_id) {
+                Ok(inode) if inode.node_type() == NodeType::Free => {},
+                Ok(inode) => {
+                    self.m_node_types.insert(inode_id, inode.node_type());
+                },
+                Err(err) => {
+                    if self.problem(format!("Inode {} is corrupted ({})",
+                                            inode_id,
+                                            fs_err(err)),
+                                    true)
+                    {
+                        /* the blocks of the inode are released by the bitmap
+                         * check */
+                        let mut free_inode =
+                            Inode::new(inode_id, NodeType::Free, 0, Duration::default());
+                        free_inode.set_links(0);
+                        self.m_volume.store_inode(&mut free_inode).map_err(fs_err)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Counts the references to the children of the given directory and
+     * walks the sub-directories
+     */
+    fn walk_dir(&mut self, dir_inode_id: u64) -> CheckResult<()> {
+        let mut dirs_to_walk = vec![dir_inode_id];
+        while let Some(dir_inode_id) = dirs_to_walk.pop() {
+            self.m_walked_dirs.insert(dir_inode_id);
+
+            let dir_records = match self.m_volume.dir_records(dir_inode_id) {
+                Ok(dir_records) => dir_records,
+                Err(err) => {
+                    /* the children of the directory become unreachable */
+                    if self.problem(format!("Directory {} is unreadable ({})",
+                                            dir_inode_id,
+                                            fs_err(err)),
+                                    true)
+                    {
+                        self.m_volume.set_data_size(dir_inode_id, 0).map_err(fs_err)?;
+                    }
+                    continue;
+                }
+            };
+
+            for (record_index, dir_record) in dir_records {
+                let inode_id = dir_record.inode_id();
+                let node_type = match self.m_node_types.get(&inode_id) {
+                    Some(node_type) => *node_type,
+                    None => {
+                        if self.problem(format!("Directory {} references the free \
+                                                 inode {} as '{}'",
+                                                dir_inode_id,
+                                                inode_id,
+                                                dir_record.name()),
+                                        true)
+                        {
+                            self.m_volume
+                                .remove_dir_record(dir_inode_id, record_index)
+                                .map_err(fs_err)?;
+                        }
+                        continue;
+                    }
+                };
+
+                /* the directories can't have more than one parent */
+                if node_type == NodeType::Dir
+                   && (self.m_walked_dirs.contains(&inode_id)
+                       || dirs_to_walk.contains(&inode_id))
+                {
+                    if self.problem(format!("Directory {} is linked more times as '{}'",
+                                            inode_id,
+                                            dir_record.name()),
+                                    true)
+                    {
+                        self.m_volume
+                            .remove_dir_record(dir_inode_id, record_index)
+                            .map_err(fs_err)?;
+                    }
+                    continue;
+                }
+
+                if node_type != dir_record.node_type()
+                   && self.problem(format!("Directory {} records '{}' as {:?} but it \
+                                            is a {:?}",
+                                           dir_inode_id,
+                                           dir_record.name(),
+                                           dir_record.node_type(),
+                                           node_type),
+                                   true)
+                {
+                    self.m_volume
+                        .remove_dir_record(dir_inode_id, record_index)
+                        .map_err(fs_err)?;
+                    self.m_volume
+                        .add_dir_record(dir_inode_id,
+                                        &DirRecord::new(inode_id,
+                                                        node_type,
+                                                        dir_record.name()))
+                        .map_err(fs_err)?;
+                }
+
+                *self.m_references.entry(inode_id).or_insert(0) += 1;
+                if node_type == NodeType::Dir {
+                    dirs_to_walk.push(inode_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Reconnects the allocated inodes not referenced by any directory into
+     * the `LostFound` directory
+     */
+    fn check_unreachable_inodes(&mut self) -> CheckResult<()> {
+        let unreachable_dirs = self.unreachable_inodes(Some(NodeType::Dir));
+
+        /* the nested unreachable directories are reconnected with their
+         * parent */
+        let mut nested_dirs = BTreeSet::new();
+        for dir_inode_id in unreachable_dirs.iter() {
+            if let Ok(dir_records) = self.m_volume.dir_records(*dir_inode_id) {
+                nested_dirs.extend(dir_records.iter().map(|(_, dir_record)| {
+                                                         dir_record.inode_id()
+                                                     }));
+            }
+        }
+        for dir_inode_id in
+            unreachable_dirs.iter()
+                            .filter(|dir_inode_id| !nested_dirs.contains(dir_inode_id))
+        {
+            self.reconnect_inode(*dir_inode_id)?;
+        }
+
+        /* the remaining ones are the files, the links and the directory
+         * cycles */
+        for inode_id in self.unreachable_inodes(None) {
+            self.reconnect_inode(inode_id)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Checks that the links counter of each inode matches the directory
+     * records which reference it
+     */
+    fn check_links(&mut self) -> CheckResult<()> {
+        let inode_ids = self.m_node_types.keys().copied().collect::<Vec<_>>();
+        for inode_id in inode_ids {
+            let mut inode = self.m_volume.load_inode(inode_id).map_err(fs_err)?;
+            let references = self.m_references.get(&inode_id).copied().unwrap_or(0);
+
+            if inode.links() != references
+               && self.problem(format!("Inode {} has {} links but {} references",
+                                       inode_id,
+                                       inode.links(),
+                                       references),
+                               true)
+            {
+                inode.set_links(references);
+                self.m_volume.store_inode(&mut inode).map_err(fs_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Checks the extents of each inode and the REF-CNT and the CRC of each
+     * chunk, returns the map of the blocks referenced by the filesystem
+     */
+    fn check_chunks(&mut self) -> CheckResult<Vec<bool>> {
+        let super_block = self.m_volume.super_block().clone();
+        let blocks_count = super_block.blocks_count();
+
+        /* the metadata areas are always used */
+        let mut used_blocks = vec![false; blocks_count as usize];
+        used_blocks[..super_block.data_first_block() as usize].fill(true);
+
+        let mut chunks = BTreeMap::<u64, ChunkRefs>::new();
+        let inode_ids = self.m_node_types.keys().copied().collect::<Vec<_>>();
+        for inode_id in inode_ids {
+            let mut inode = self.m_volume.load_inode(inode_id).map_err(fs_err)?;
+
+            for extents_block in inode.extents_blocks().iter() {
+                if used_blocks[*extents_block as usize] {
+                    self.problem(format!("Inode {} extents block {} is cross-linked",
+                                         inode_id, extents_block),
+                                 false);
+                }
+                used_blocks[*extents_block as usize] = true;
+            }
+
+            let mut extents_data_len = 0;
+            for extent in inode.extents().iter() {
+                let extent_end = extent.first_block() + extent.blocks_count();
+                if !self.m_volume.is_data_block(extent.first_block())
+                   || extent_end > blocks_count
+                   || extent.data_len() > super_block.chunk_capacity(extent.blk_exp())
+                {
+                    self.problem(format!("Inode {} has an invalid extent at block {}",
+                                         inode_id,
+                                         extent.first_block()),
+                                 false);
+                    continue;
+                }
+                extents_data_len += extent.data_len();
+
+                let chunk_refs =
+                    chunks.entry(extent.first_block())
+                          .or_insert_with(|| ChunkRefs::new(extent.blk_exp()));
+                chunk_refs.m_inode_ids.push(inode_id);
+                if chunk_refs.m_blk_exp != extent.blk_exp() {
+                    self.problem(format!("Inode {} extent at block {} overlaps \
+                                          another chunk",
+                                         inode_id,
+                                         extent.first_block()),
+                                 false);
+                } else if chunk_refs.m_inode_ids.len() == 1 {
+                    let chunk_blocks = extent.first_block() as usize..extent_end as usize;
+                    if used_blocks[chunk_blocks.clone()].iter().any(|is_used| *is_used) {
+                        self.problem(format!("Inode {} chunk at block {} is \
+                                              cross-linked",
+                                             inode_id,
+                                             extent.first_block()),
+                                     false);
+                    }
+                    used_blocks[chunk_blocks].fill(true);
+                }
+            }
+
+            if inode.data_size() != extents_data_len as u64
+               && self.problem(format!("Inode {} has size {} but his extents store {} \
+                                        bytes",
+                                       inode_id,
+                                       inode.data_size(),
+                                       extents_data_len),
+                               true)
+            {
+                inode.set_data_size(extents_data_len as u64);
+                self.m_volume.store_inode(&mut inode).map_err(fs_err)?;
+            }
+        }
+
+        for (first_block, chunk_refs) in chunks.iter() {
+            self.check_chunk(*first_block, chunk_refs)?;
+        }
+        Ok(used_blocks)
+    }
+
+    /**
+     * Checks the header and the CRC of the given chunk
+     */
+    fn check_chunk(&mut self,
+                   first_block: u64,
+                   chunk_refs: &ChunkRefs)
+                   -> CheckResult<()> {
+        let mut chunk_header =
+            self.m_volume.read_chunk_header(first_block).map_err(fs_err)?;
+        if chunk_header.blk_exp() != chunk_refs.m_blk_exp {
+            self.problem(format!("Chunk at block {} has BLK-EXP {} but is referenced \
+                                  with {} by inodes {:?}",
+                                 first_block,
+                                 chunk_header.blk_exp(),
+                                 chunk_refs.m_blk_exp,
+                                 chunk_refs.m_inode_ids),
+                         false);
+            return Ok(());
+        }
+
+        /* the saturated counter is valid for more references too */
+        let references = chunk_refs.m_inode_ids.len().min(u16::MAX as usize) as u16;
+        if chunk_header.ref_count() != references
+           && self.problem(format!("Chunk at block {} has REF-CNT {} but {} references",
+                                   first_block,
+                                   chunk_header.ref_count(),
+                                   references),
+                           true)
+        {
+            chunk_header.set_ref_count(references);
+            self.m_volume
+                .write_chunk_header(first_block, &chunk_header)
+                .map_err(fs_err)?;
+        }
+
+        let extent = Extent::new(first_block, 0, chunk_refs.m_blk_exp);
+        if self.m_volume.chunk_crc_matches(&extent).map_err(fs_err)? == Some(false) {
+            self.problem(format!("Chunk at block {} of inodes {:?} has a CRC mismatch",
+                                 first_block, chunk_refs.m_inode_ids),
+                         false);
+        }
+        Ok(())
+    }
+
+    /**
+     * Compares the blocks bitmap with the blocks referenced by the
+     * filesystem, releasing the orphaned extents
+     */
+    fn check_blocks_bitmap(&mut self, used_blocks: &[bool]) {
+        let mut orphaned_blocks = Vec::new();
+        let mut unmarked_blocks = Vec::new();
+        for (block_index, is_used) in used_blocks.iter().enumerate() {
+            let block_index = block_index as u64;
+            match (self.m_volume.is_block_used(block_index), *is_used) {
+                (true, false) => orphaned_blocks.push(block_index),
+                (false, true) => unmarked_blocks.push(block_index),
+                _ => {}
+            }
+        }
+
+        if !orphaned_blocks.is_empty()
+           && self.problem(format!("{} blocks are used but not referenced (orphaned \
+                                    extents)",
+                                   orphaned_blocks.len()),
+                           true)
+        {
+            for block_index in orphaned_blocks {
+                self.m_volume.set_blocks_used(block_index, 1, false);
+            }
+        }
+        if !unmarked_blocks.is_empty()
+           && self.problem(format!("{} blocks are referenced but marked as free",
+                                   unmarked_blocks.len()),
+                           true)
+        {
+            for block_index in unmarked_blocks {
+                self.m_volume.set_blocks_used(block_index, 1, true);
+            }
+        }
+    }
+
+    /**
+     * Checks the free blocks and free inodes counters of the `SuperBlock`
+     */
+    fn check_free_counters(&mut self) {
+        let super_block = self.m_volume.super_block();
+        let free_blocks = (0..super_block.blocks_count()).filter(|block_index| {
+                              !self.m_volume.is_block_used(*block_index)
+                          })
+                          .count() as u64;
+        let free_inodes = super_block.inodes_count() - 1 - self.m_node_types.len() as u64;
+
+        let stored_free_blocks = super_block.free_blocks();
+        if stored_free_blocks != free_blocks
+           && self.problem(format!("Free blocks counter is {} but {} blocks are free",
+                                   stored_free_blocks, free_blocks),
+                           true)
+        {
+            self.m_volume.super_block_mut().set_free_blocks(free_blocks);
+        }
+
+        let stored_free_inodes = self.m_volume.super_block().free_inodes();
+        if stored_free_inodes != free_inodes
+           && self.problem(format!("Free inodes counter is {} but {} inodes are free",
+                                   stored_free_inodes, free_inodes),
+                           true)
+        {
+            self.m_volume.super_block_mut().set_free_inodes(free_inodes);
+        }
+    }
+}
+
+impl FsChecker /* Privates */ {
+    /**
+     * Reports the given problem, returns whether it must be repaired
+     */
+    fn problem(&mut self, description: String, is_repairable: bool) -> bool {
+        let will_repair = self.m_repair && is_repairable;
+
+        self.m_problems_found += 1;
+        if will_repair {
+            println!("  - {} (repaired)", description);
+        } else {
+            self.m_problems_left += 1;
+            println!("  - {}", description);
+        }
+        will_repair
+    }
+
+    /**
+     * Returns the allocated inodes of the given type not referenced by any
+     * directory
+     */
+    fn unreachable_inodes(&self, node_type: Option<NodeType>) -> Vec<u64> {
+        self.m_node_types
+            .iter()
+            .filter(|(inode_id, inode_type)| {
+                !self.m_references.contains_key(inode_id)
+                && !matches!(node_type, Some(node_type) if node_type != **inode_type)
+            })
+            .map(|(inode_id, _)| *inode_id)
+            .collect()
+    }
+
+    /**
+     * Links the given unreachable inode into the `LostFound` directory
+     */
+    fn reconnect_inode(&mut self, inode_id: u64) -> CheckResult<()> {
+        let node_type = self.m_node_types[&inode_id];
+        if self.problem(format!("{:?} inode {} is unreachable", node_type, inode_id),
+                        true)
+        {
+            let lost_found_id = self.lost_found_id()?;
+            let dir_record =
+                DirRecord::new(inode_id, node_type, &format!("Inode{}", inode_id));
+            self.m_volume.add_dir_record(lost_found_id, &dir_record).map_err(fs_err)?;
+        }
+
+        /* the reconnected directories could make reachable other inodes */
+        self.m_references.insert(inode_id, 1);
+        if node_type == NodeType::Dir {
+            self.walk_dir(inode_id)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns the `LostFound` directory, creating it when missing
+     */
+    fn lost_found_id(&mut self) -> CheckResult<u64> {
+        if let Some(lost_found_id) = self.m_lost_found_id {
+            return Ok(lost_found_id);
+        }
+
+        let lost_found_id = match self.m_volume
+                                      .lookup(Inode::ROOT_ID, LOST_FOUND_NAME)
+                                      .map_err(fs_err)?
+        {
+            Some(dir_record) if dir_record.node_type() == NodeType::Dir => {
+                dir_record.inode_id()
+            },
+            Some(_) => return Err(format!("{} is not a directory", LOST_FOUND_NAME)),
+            None => {
+                let lost_found_id = self.m_volume
+                                        .create_node(Inode::ROOT_ID,
+                                                     LOST_FOUND_NAME,
+                                                     NodeType::Dir,
+                                                     default_prot_grants())
+                                        .map_err(fs_err)?;
+                self.m_node_types.insert(lost_found_id, NodeType::Dir);
+                self.m_references.insert(lost_found_id, 1);
+                self.m_walked_dirs.insert(lost_found_id);
+                lost_found_id
+            }
+        };
+
+        self.m_lost_found_id = Some(lost_found_id);
+        Ok(lost_found_id)
+    }
+}
+
+/**
+ * Inodes which reference a chunk
+ */
+struct ChunkRefs {
+    m_blk_exp: u8,
+    m_inode_ids: Vec<u64>
+}
+
+impl ChunkRefs /* Constructors */ {
+    /**
+     * Constructs an empty `ChunkRefs` for a chunk of the given `BLK-EXP`
+     */
+    fn new(blk_exp: u8) -> Self {
+        Self { m_blk_exp: blk_exp,
+               m_inode_ids: Vec::new() }
+    }
+}
+
+/**
+ * Reads the block size from the `SuperBlock` of the image
+ */
+fn read_block_size(image_path: &PathBuf) -> CheckResult<usize> {
+    let mut raw_super_block = [0; SuperBlock::BLOCK_SIZE_MIN];
+    fs::File::open(image_path).and_then(|mut image_file| {
+                                  image_file.read_exact(&mut raw_super_block)
+                              })
+                              .map_err(|err| err.to_string())?;
+
+    let super_block = SuperBlock::decode(&raw_super_block).map_err(fs_err)?;
+    Ok(super_block.block_size())
+}
+
+/**
+ * Returns the current time since the UNIX epoch
+ */
+fn host_clock() -> Duration {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+}
+
+/**
+ * Converts the filesystem error to a printable one
+ */
+fn fs_err((err_class, err_msg): (OsErrorClass, Option<&'static str>)) -> String {
+    format!("{:?}: {}", err_class, err_msg.unwrap_or(""))
+}
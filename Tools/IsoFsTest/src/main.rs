@@ -0,0 +1,532 @@
+/*! ISO9660 filesystem host test harness
+ *
+ * Runs the scenarios which exercise the ISO9660 driver with the Rock
+ * Ridge, Joliet and plain hierarchies. The images are mastered by the
+ * `master` module with the same layout of `genisoimage`, each scenario
+ * runs over devices with both 512 and 2048 bytes blocks. The process exits
+ * with a failure status when any scenario fails
+ */
+
+use std::{
+    process,
+    time::Duration
+};
+
+use api_data::error::class::OsErrorClass;
+use iso_fs::{
+    volume::{
+        IsoDirRecord,
+        IsoNodeType,
+        IsoVolume,
+        NameKind
+    },
+    IsoFsResult
+};
+use meetix_fs::dev::MemBlockDevice;
+
+use crate::master::{
+    master_image,
+    MasterNode,
+    MasterOptions,
+    RR_DIR_MODE,
+    RR_FILE_MODE,
+    RR_GID,
+    RR_LINK_MODE,
+    RR_UID
+};
+
+mod master;
+
+type TestVolume = IsoVolume<MemBlockDevice>;
+type TestResult = Result<(), String>;
+type ScenarioFn = fn(usize) -> TestResult;
+
+/* 2021-06-15 12:34:56 UTC */
+const FIXED_CLOCK_SECS: u64 = 1_623_760_496;
+
+fn main() {
+    let scenarios: [(&str, ScenarioFn); 9] =
+        [("rock ridge tree", test_rock_ridge_tree),
+         ("rock ridge relocated directories", test_relocated_dirs),
+         ("multi-extent files", test_multi_extent),
+         ("joliet names", test_joliet_names),
+         ("plain names", test_plain_names),
+         ("rock ridge preferred to joliet", test_rock_ridge_preferred),
+         ("directory spanning sectors", test_big_dir),
+         ("timestamps", test_timestamps),
+         ("corrupted images", test_corrupted)];
+
+    let mut failures = 0;
+    for device_block_size in [512, 2048].iter() {
+        for (scenario_name, scenario_fn) in scenarios.iter() {
+            let result = scenario_fn(*device_block_size);
+            failures += report(&format!("{} bytes blocks {}",
+                                        device_block_size, scenario_name),
+                               result);
+        }
+    }
+
+    if failures > 0 {
+        println!("{} scenarios failed", failures);
+        process::exit(1);
+    }
+    println!("All scenarios passed");
+}
+
+/**
+ * Reads the names, the data, the symbolic links and the attributes of a
+ * Rock Ridge tree
+ */
+fn test_rock_ridge_tree(device_block_size: usize) -> TestResult {
+    let long_name = "l".repeat(200) + &"o".repeat(51) + ".txt";
+    let tree = dir(vec![("README.txt", file(b"MeetiX".to_vec())),
+                        (long_name.as_str(), file(pattern(5000, 3))),
+                        ("Ünïcödé.dat", file(pattern(10, 5))),
+                        ("empty", file(Vec::new())),
+                        ("Bins",
+                         dir(vec![("shell", file(pattern(3000, 7))),
+                                  ("sh", link("shell")),
+                                  ("up", link("../README.txt")),
+                                  ("abs", link("/Bins/./shell"))])),
+                        ("Apps", dir(vec![]))]);
+    let mut volume =
+        mount(master_image(&tree, &options(true, false)), device_block_size)?;
+    check_eq(volume.name_kind(), NameKind::RockRidge, "name kind")?;
+    check_eq(volume.volume_id(), "MEETIX_TEST", "volume id")?;
+
+    let root = volume.root().clone();
+    let mut names = child_names(&mut volume, &root)?;
+    names.sort();
+    let mut expected_names =
+        strings(&["Apps", "Bins", "README.txt", "empty", "Ünïcödé.dat"]);
+    expected_names.push(long_name.clone());
+    expected_names.sort();
+    check_eq(names, expected_names, "root names")?;
+
+    check_eq(read_path(&mut volume, "/README.txt")?, b"MeetiX".to_vec(), "README.txt")?;
+    check_eq(read_path(&mut volume, &format!("/{}", long_name))?,
+             pattern(5000, 3),
+             "long name data")?;
+    check_eq(read_path(&mut volume, "/Ünïcödé.dat")?, pattern(10, 5), "unicode data")?;
+    check_eq(read_path(&mut volume, "/empty")?, Vec::new(), "empty data")?;
+    check_eq(read_path(&mut volume, "/Bins/shell")?, pattern(3000, 7), "shell data")?;
+    check(fs(volume.lookup(&root, "readme.txt"))?.is_none(), "case sensitive lookup")?;
+
+    /* the symbolic links */
+    for (link_path, target) in [("/Bins/sh", "shell"),
+                                ("/Bins/up", "../README.txt"),
+                                ("/Bins/abs", "/Bins/./shell")].iter()
+    {
+        let link_record = walk(&mut volume, link_path)?;
+        check_eq(link_record.node_type(), IsoNodeType::Link, "link type")?;
+        check_eq(link_record.link_target(), Some(*target), "link target")?;
+        check_eq(rock_ridge_mode(&link_record), Some(RR_LINK_MODE), "link mode")?;
+    }
+
+    /* the POSIX attributes */
+    let shell = walk(&mut volume, "/Bins/shell")?;
+    let rock_ridge = shell.rock_ridge().ok_or("shell without Rock Ridge")?;
+    check_eq(rock_ridge.mode(), Some(RR_FILE_MODE), "shell mode")?;
+    check_eq(rock_ridge.uid(), Some(RR_UID), "shell uid")?;
+    check_eq(rock_ridge.gid(), Some(RR_GID), "shell gid")?;
+    check_eq(rock_ridge.links(), Some(1), "shell links")?;
+    let bins = walk(&mut volume, "/Bins")?;
+    check_eq(bins.node_type(), IsoNodeType::Dir, "Bins type")?;
+    check_eq(rock_ridge_mode(&bins), Some(RR_DIR_MODE), "Bins mode")?;
+    check_eq(rock_ridge_mode(&root), Some(RR_DIR_MODE), "root mode")?;
+
+    /* the read-only volume refuses the wrong node types */
+    check(volume.read_data(&bins, 0, &mut [0; 16]).is_err(), "read of a directory")?;
+    check(volume.dir_records(&shell).is_err(), "listing of a file")?;
+    let apps = walk(&mut volume, "/Apps")?;
+    check_eq(apps.node_type(), IsoNodeType::Dir, "Apps type")?;
+    check_eq(child_names(&mut volume, &apps)?, Vec::<String>::new(), "Apps names")
+}
+
+/**
+ * Walks the directories deeper than eight levels, which Rock Ridge
+ * relocates into `rr_moved`
+ */
+fn test_relocated_dirs(device_block_size: usize) -> TestResult {
+    let mut tree = dir(vec![("bottom.txt", file(pattern(700, 11)))]);
+    for level in (1..=12).rev() {
+        let level_name = format!("level{}", level);
+        tree = dir(vec![(level_name.as_str(), tree)]);
+    }
+    let mut volume =
+        mount(master_image(&tree, &options(true, false)), device_block_size)?;
+
+    let deep_path = (1..=12).map(|level| format!("/level{}", level)).collect::<String>()
+                    + "/bottom.txt";
+    check_eq(read_path(&mut volume, &deep_path)?, pattern(700, 11), "deep file data")?;
+
+    /* the relocated directories are hidden from their fake parent */
+    let relocation_dir = walk(&mut volume, "/rr_moved")?;
+    check_eq(child_names(&mut volume, &relocation_dir)?,
+             Vec::<String>::new(),
+             "rr_moved names")?;
+
+    let deep_dir =
+        walk(&mut volume, "/level1/level2/level3/level4/level5/level6/level7/level8")?;
+    check_eq(deep_dir.node_type(), IsoNodeType::Dir, "relocated type")?;
+    check_eq(child_names(&mut volume, &deep_dir)?,
+             strings(&["level9"]),
+             "relocated names")
+}
+
+/**
+ * Reads the files split into more extents across the extents boundaries
+ */
+fn test_multi_extent(device_block_size: usize) -> TestResult {
+    let data = pattern(20_000, 13);
+    let tree = dir(vec![("big.bin", file(data.clone())),
+                        ("after.txt", file(b"after".to_vec()))]);
+    let mut mastering_options = options(true, false);
+    mastering_options.m_extent_size_max = 8192;
+    let mut volume = mount(master_image(&tree, &mastering_options), device_block_size)?;
+
+    let root = volume.root().clone();
+    check_eq(child_names(&mut volume, &root)?,
+             strings(&["after.txt", "big.bin"]),
+             "multi-extent names")?;
+
+    let big_file = walk(&mut volume, "/big.bin")?;
+    check_eq(big_file.data_size(), data.len() as u64, "multi-extent size")?;
+    check_eq(read_all(&mut volume, &big_file)?, data.clone(), "multi-extent data")?;
+    for (offset, len) in [(8000, 400), (8192, 10), (16_000, 4000), (19_990, 100)].iter() {
+        let mut buffer = vec![0; *len];
+        let read_count = fs(volume.read_data(&big_file, *offset, &mut buffer))?;
+        let expected_end = (*offset + *len).min(data.len());
+        check_eq(&buffer[..read_count],
+                 &data[*offset..expected_end],
+                 &format!("read at {}", offset))?;
+    }
+    check_eq(fs(volume.read_data(&big_file, 30_000, &mut [0; 16]))?,
+             0,
+             "read past the end")?;
+    check_eq(read_path(&mut volume, "/after.txt")?, b"after".to_vec(), "after data")
+}
+
+/**
+ * Reads the UCS-2 names of the Joliet hierarchy
+ */
+fn test_joliet_names(device_block_size: usize) -> TestResult {
+    let tree = dir(vec![("A long Joliet name.text", file(pattern(100, 3))),
+                        ("Ünïcödé",
+                         dir(vec![("inner file.bin", file(pattern(4000, 9)))])),
+                        ("link", link("Ünïcödé"))]);
+    let mut volume =
+        mount(master_image(&tree, &options(false, true)), device_block_size)?;
+    check_eq(volume.name_kind(), NameKind::Joliet, "name kind")?;
+    check_eq(volume.volume_id(), "MeetiX Test", "volume id")?;
+
+    /* Joliet has no symbolic links */
+    let root = volume.root().clone();
+    check_eq(child_names(&mut volume, &root)?,
+             strings(&["A long Joliet name.text", "Ünïcödé"]),
+             "root names")?;
+    check_eq(read_path(&mut volume, "/A long Joliet name.text")?,
+             pattern(100, 3),
+             "long name data")?;
+    check_eq(read_path(&mut volume, "/Ünïcödé/inner file.bin")?,
+             pattern(4000, 9),
+             "inner data")?;
+    check(walk(&mut volume, "/Ünïcödé")?.rock_ridge().is_none(), "no Rock Ridge")
+}
+
+/**
+ * Reads the uppercase 8.3 names of the plain hierarchy
+ */
+fn test_plain_names(device_block_size: usize) -> TestResult {
+    let tree = dir(vec![("readme.txt", file(b"plain".to_vec())),
+                        ("noext", file(b"noext".to_vec())),
+                        ("longer name.data", file(pattern(50, 2))),
+                        ("longer name.dat2", file(pattern(60, 4))),
+                        ("sub dir", dir(vec![("x.y", file(b"xy".to_vec()))]))]);
+    let mut volume =
+        mount(master_image(&tree, &options(false, false)), device_block_size)?;
+    check_eq(volume.name_kind(), NameKind::Iso, "name kind")?;
+
+    let root = volume.root().clone();
+    check_eq(child_names(&mut volume, &root)?,
+             strings(&["longe001.dat",
+                       "longer_n.dat",
+                       "noext",
+                       "readme.txt",
+                       "sub_dir"]),
+             "root names")?;
+    check_eq(read_path(&mut volume, "/README.TXT")?, b"plain".to_vec(), "ignored case")?;
+    check_eq(read_path(&mut volume, "/noext")?, b"noext".to_vec(), "no extension")?;
+    check_eq(read_path(&mut volume, "/longer_n.dat")?,
+             pattern(50, 2),
+             "first collision")?;
+    check_eq(read_path(&mut volume, "/longe001.dat")?,
+             pattern(60, 4),
+             "second collision")?;
+    check_eq(read_path(&mut volume, "/Sub_Dir/X.Y")?, b"xy".to_vec(), "inner data")?;
+    check(walk(&mut volume, "/readme.txt")?.rock_ridge().is_none(), "no Rock Ridge")
+}
+
+/**
+ * Mounts the images with both Rock Ridge and Joliet, which must use the
+ * Rock Ridge hierarchy
+ */
+fn test_rock_ridge_preferred(device_block_size: usize) -> TestResult {
+    let tree = dir(vec![("Mixed Case.txt", file(b"mixed".to_vec())),
+                        ("lnk", link("Mixed Case.txt"))]);
+    let mut volume = mount(master_image(&tree, &options(true, true)), device_block_size)?;
+    check_eq(volume.name_kind(), NameKind::RockRidge, "name kind")?;
+
+    let root = volume.root().clone();
+    let mut names = child_names(&mut volume, &root)?;
+    names.sort();
+    check_eq(names, strings(&["Mixed Case.txt", "lnk"]), "root names")?;
+    check_eq(walk(&mut volume, "/lnk")?.link_target(),
+             Some("Mixed Case.txt"),
+             "link target")?;
+    check_eq(read_path(&mut volume, "/Mixed Case.txt")?, b"mixed".to_vec(), "data")
+}
+
+/**
+ * Lists a directory which spans more sectors, with records padded to the
+ * sector boundaries
+ */
+fn test_big_dir(device_block_size: usize) -> TestResult {
+    let file_names =
+        (0..300).map(|file_index| format!("file number {:03}.txt", file_index))
+                .collect::<Vec<_>>();
+    let children = file_names.iter()
+                             .enumerate()
+                             .map(|(file_index, file_name)| {
+                                 (file_name.as_str(), file(pattern(file_index, 1)))
+                             })
+                             .collect::<Vec<_>>();
+    let tree = dir(children);
+
+    for (rock_ridge, joliet) in [(true, false), (false, true)].iter() {
+        let mut volume = mount(master_image(&tree, &options(*rock_ridge, *joliet)),
+                               device_block_size)?;
+        let root = volume.root().clone();
+        check(root.data_size() > 2048 * 4, "root spans more sectors")?;
+
+        let mut names = child_names(&mut volume, &root)?;
+        names.sort();
+        check_eq(names, file_names.clone(), "big directory names")?;
+        for file_index in [0, 150, 299].iter() {
+            check_eq(read_path(&mut volume, &format!("/{}", file_names[*file_index]))?,
+                     pattern(*file_index, 1),
+                     "big directory data")?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Decodes the recording dates and the Rock Ridge timestamps with the GMT
+ * offset, in both the short and the long form
+ */
+fn test_timestamps(device_block_size: usize) -> TestResult {
+    let tree = dir(vec![("stamped", file(b"time".to_vec()))]);
+    for (long_timestamps, gmt_offset) in [(false, 8), (true, -20), (true, 0)].iter() {
+        let mut mastering_options = options(true, false);
+        mastering_options.m_long_timestamps = *long_timestamps;
+        mastering_options.m_gmt_offset = *gmt_offset;
+        let mut volume =
+            mount(master_image(&tree, &mastering_options), device_block_size)?;
+
+        let expected_inst = Duration::from_secs(FIXED_CLOCK_SECS);
+        let expected_rr_inst = if *long_timestamps {
+            expected_inst + Duration::from_millis(500)
+        } else {
+            expected_inst
+        };
+        let stamped = walk(&mut volume, "/stamped")?;
+        check_eq(stamped.recording_inst(), expected_inst, "recording instant")?;
+
+        let rock_ridge = stamped.rock_ridge().ok_or("stamped without Rock Ridge")?;
+        check_eq(rock_ridge.creat_inst(), Some(expected_rr_inst), "creation instant")?;
+        check_eq(rock_ridge.modify_inst(), Some(expected_rr_inst), "modify instant")?;
+        check_eq(rock_ridge.access_inst(), Some(expected_rr_inst), "access instant")?;
+    }
+    Ok(())
+}
+
+/**
+ * Refuses the images without descriptors, with corrupted records or
+ * truncated
+ */
+fn test_corrupted(device_block_size: usize) -> TestResult {
+    let tree = dir(vec![("data.bin", file(pattern(10_000, 5))), ("sub", dir(vec![]))]);
+    let image = master_image(&tree, &options(true, false));
+
+    /* not an ISO9660 image */
+    let blank_image = vec![0; image.len()];
+    check(mount(blank_image, device_block_size).is_err(), "blank image mounted")?;
+
+    /* the media ends before the descriptors */
+    check(mount(image[..16 * 2048].to_vec(), device_block_size).is_err(),
+          "truncated descriptors mounted")?;
+
+    /* the media ends before the data of the file */
+    let mut volume = mount(image[..image.len() - 4096].to_vec(), device_block_size)?;
+    let data_file = walk(&mut volume, "/data.bin")?;
+    match volume.read_data(&data_file, 0, &mut vec![0; 10_000]) {
+        Err((OsErrorClass::EndOfDataReached, _)) => {},
+        result => return Err(format!("read of the truncated data: {:?}", result))
+    }
+
+    /* a corrupted record length into the root directory */
+    let root_extent = volume.root().node_id() as usize;
+    let mut corrupted_image = image.clone();
+    let root_first_record_len = corrupted_image[root_extent] as usize;
+    let parent_record_len = corrupted_image[root_extent + root_first_record_len] as usize;
+    corrupted_image[root_extent + root_first_record_len + parent_record_len] = 20;
+    let mut corrupted_volume = mount(corrupted_image, device_block_size)?;
+    let root = corrupted_volume.root().clone();
+    check(corrupted_volume.dir_records(&root).is_err(), "corrupted record listed")
+}
+
+/**
+ * Prints the result of the scenario and returns the amount of failures
+ */
+fn report(scenario_name: &str, result: TestResult) -> usize {
+    match result {
+        Ok(_) => {
+            println!("[ OK ] {}", scenario_name);
+            0
+        },
+        Err(err) => {
+            println!("[FAIL] {}: {}", scenario_name, err);
+            1
+        }
+    }
+}
+
+/**
+ * Returns the mastering options with the given hierarchies
+ */
+fn options(rock_ridge: bool, joliet: bool) -> MasterOptions {
+    MasterOptions { m_rock_ridge: rock_ridge,
+                    m_joliet: joliet,
+                    m_unix_secs: FIXED_CLOCK_SECS,
+                    ..MasterOptions::default() }
+}
+
+/**
+ * Mounts the given image over a device with the given block size
+ */
+fn mount(image: Vec<u8>, device_block_size: usize) -> Result<TestVolume, String> {
+    fs(IsoVolume::mount(MemBlockDevice::from_image(device_block_size, image)))
+}
+
+/**
+ * Returns the record at the given absolute path
+ */
+fn walk(volume: &mut TestVolume, path: &str) -> Result<IsoDirRecord, String> {
+    let mut dir_record = volume.root().clone();
+    for component in path.split('/').filter(|component| !component.is_empty()) {
+        dir_record =
+            fs(volume.lookup(&dir_record, component))?.ok_or(format!("{} not found \
+                                                                      in {}",
+                                                                     component, path))?;
+    }
+    Ok(dir_record)
+}
+
+/**
+ * Returns the names of the children of the given directory
+ */
+fn child_names(volume: &mut TestVolume,
+               dir: &IsoDirRecord)
+               -> Result<Vec<String>, String> {
+    Ok(fs(volume.dir_records(dir))?.iter()
+                                   .map(|dir_record| String::from(dir_record.name()))
+                                   .collect())
+}
+
+/**
+ * Reads the whole data of the file at the given path
+ */
+fn read_path(volume: &mut TestVolume, path: &str) -> Result<Vec<u8>, String> {
+    let file_record = walk(volume, path)?;
+    read_all(volume, &file_record)
+}
+
+/**
+ * Reads the whole data of the given file with small reads
+ */
+fn read_all(volume: &mut TestVolume,
+            file_record: &IsoDirRecord)
+            -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 777];
+    loop {
+        let read_count = fs(volume.read_data(file_record, data.len(), &mut buffer))?;
+        if read_count == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..read_count]);
+    }
+    Ok(data)
+}
+
+fn rock_ridge_mode(dir_record: &IsoDirRecord) -> Option<u32> {
+    dir_record.rock_ridge().and_then(|rock_ridge| rock_ridge.mode())
+}
+
+fn dir(children: Vec<(&str, MasterNode)>) -> MasterNode {
+    MasterNode::Dir(children.into_iter()
+                            .map(|(name, node)| (String::from(name), node))
+                            .collect())
+}
+
+fn file(data: Vec<u8>) -> MasterNode {
+    MasterNode::File(data)
+}
+
+fn link(target: &str) -> MasterNode {
+    MasterNode::Link(String::from(target))
+}
+
+/**
+ * Returns owned strings from the given names
+ */
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| String::from(*name)).collect()
+}
+
+/**
+ * Returns a deterministic data pattern
+ */
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|byte_index| (byte_index as u8).wrapping_mul(seed).wrapping_add(seed))
+            .collect()
+}
+
+/**
+ * Converts the filesystem error to a printable one
+ */
+fn fs<T>(result: IsoFsResult<T>) -> Result<T, String> {
+    result.map_err(|(err_class, err_msg)| {
+              format!("{:?}: {}", err_class, err_msg.unwrap_or(""))
+          })
+}
+
+fn check(condition: bool, what: &str) -> TestResult {
+    if condition {
+        Ok(())
+    } else {
+        Err(format!("{} failed", what))
+    }
+}
+
+fn check_eq<T>(value: T, expected: T, what: &str) -> TestResult
+    where T: PartialEq + std::fmt::Debug {
+    if value == expected {
+        Ok(())
+    } else if format!("{:?}", value).len() > 128 {
+        Err(format!("{} mismatch", what))
+    } else {
+        Err(format!("{}: {:?} != {:?}", what, value, expected))
+    }
+}
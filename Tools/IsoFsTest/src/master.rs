@@ -0,0 +1,740 @@
+/*! ISO9660 images mastering
+ *
+ * Writes the images read by the scenarios with the same layout of the
+ * common tools (i.e. `genisoimage -R -J`): descriptors, directories,
+ * continuation areas and then the data of the files. The Rock Ridge deep
+ * directories are relocated into `rr_moved` like `genisoimage` does
+ */
+
+use std::collections::BTreeSet;
+
+/**
+ * Node of the tree to master
+ */
+pub enum MasterNode {
+    Dir(Vec<(String, MasterNode)>),
+    File(Vec<u8>),
+    Link(String)
+}
+
+/**
+ * Options of the mastered image
+ */
+pub struct MasterOptions {
+    pub m_rock_ridge: bool,
+    pub m_joliet: bool,
+    pub m_extent_size_max: u32,
+    pub m_long_timestamps: bool,
+    pub m_gmt_offset: i8,
+    pub m_unix_secs: u64
+}
+
+impl Default for MasterOptions {
+    fn default() -> Self {
+        Self { m_rock_ridge: true,
+               m_joliet: true,
+               m_extent_size_max: u32::MAX - (BLOCK_SIZE as u32 - 1),
+               m_long_timestamps: false,
+               m_gmt_offset: 0,
+               m_unix_secs: 0 }
+    }
+}
+
+/**
+ * POSIX attributes written into the Rock Ridge `PX` entries
+ */
+pub const RR_UID: u32 = 1000;
+pub const RR_GID: u32 = 100;
+pub const RR_FILE_MODE: u32 = 0o100644;
+pub const RR_DIR_MODE: u32 = 0o040755;
+pub const RR_LINK_MODE: u32 = 0o120777;
+
+const BLOCK_SIZE: usize = 2048;
+const FIRST_DESCRIPTOR_BLOCK: u32 = 16;
+const RECORD_LEN_MAX: usize = 254;
+const RELOCATION_DEPTH: usize = 8;
+const RELOCATION_DIR_NAME: &str = "rr_moved";
+
+const FLAG_DIRECTORY: u8 = 1 << 1;
+const FLAG_MULTI_EXTENT: u8 = 1 << 7;
+
+/**
+ * Masters the given tree into a new image
+ */
+pub fn master_image(root: &MasterNode, options: &MasterOptions) -> Vec<u8> {
+    let mut master = Master { m_options: options,
+                              m_dirs: Vec::new(),
+                              m_files: Vec::new(),
+                              m_joliet_root: None,
+                              m_relocation_dir: None,
+                              m_ce_first_block: 0,
+                              m_ce_areas: Vec::new() };
+
+    /* the primary hierarchy creates the files, the Joliet one shares them */
+    master.m_dirs.push(PlanDir::new(false, 0, None));
+    master.plan_dir(root, 0, 1, &mut 0);
+    if options.m_joliet {
+        let joliet_root = master.m_dirs.len();
+        master.m_dirs.push(PlanDir::new(true, joliet_root, None));
+        master.m_joliet_root = Some(joliet_root);
+        master.plan_dir(root, joliet_root, 1, &mut 0);
+    }
+
+    /* the sizes of the records don't depend by the extents */
+    let descriptors_count = if options.m_joliet {
+        3
+    } else {
+        2
+    };
+    let mut next_block = FIRST_DESCRIPTOR_BLOCK + descriptors_count;
+    for dir_index in 0..master.m_dirs.len() {
+        let dir_size = master.encode_dir(dir_index).len();
+        master.m_dirs[dir_index].m_extent = next_block;
+        master.m_dirs[dir_index].m_size = dir_size as u32;
+        next_block += (dir_size / BLOCK_SIZE) as u32;
+    }
+
+    master.m_ce_first_block = next_block;
+    let ce_areas_size = master.m_ce_areas.len();
+    next_block += ce_areas_size.div_ceil(BLOCK_SIZE) as u32;
+    master.m_ce_areas.clear();
+
+    for plan_file in master.m_files.iter_mut() {
+        let blocks_count = plan_file.m_data.len().div_ceil(BLOCK_SIZE);
+        plan_file.m_extent = if blocks_count > 0 {
+            next_block
+        } else {
+            0
+        };
+        next_block += blocks_count as u32;
+    }
+
+    /* write the image */
+    let mut image = vec![0; next_block as usize * BLOCK_SIZE];
+    let primary_descriptor = master.encode_descriptor(false, next_block);
+    write_block(&mut image, FIRST_DESCRIPTOR_BLOCK, &primary_descriptor);
+    if options.m_joliet {
+        let joliet_descriptor = master.encode_descriptor(true, next_block);
+        write_block(&mut image, FIRST_DESCRIPTOR_BLOCK + 1, &joliet_descriptor);
+    }
+    let mut terminator = vec![0; BLOCK_SIZE];
+    terminator[0] = 255;
+    terminator[1..6].copy_from_slice(b"CD001");
+    terminator[6] = 1;
+    write_block(&mut image, FIRST_DESCRIPTOR_BLOCK + descriptors_count - 1, &terminator);
+
+    for dir_index in 0..master.m_dirs.len() {
+        let raw_dir = master.encode_dir(dir_index);
+        write_block(&mut image, master.m_dirs[dir_index].m_extent, &raw_dir);
+    }
+    let ce_areas = master.m_ce_areas.clone();
+    write_block(&mut image, master.m_ce_first_block, &ce_areas);
+    for plan_file in master.m_files.iter() {
+        write_block(&mut image, plan_file.m_extent, &plan_file.m_data);
+    }
+    image
+}
+
+/**
+ * Directory of a hierarchy
+ */
+struct PlanDir {
+    m_joliet: bool,
+    m_parent: usize,
+    m_moved_from: Option<usize>,
+    m_records: Vec<PlanRecord>,
+    m_extent: u32,
+    m_size: u32
+}
+
+impl PlanDir {
+    fn new(joliet: bool, parent: usize, moved_from: Option<usize>) -> Self {
+        Self { m_joliet: joliet,
+               m_parent: parent,
+               m_moved_from: moved_from,
+               m_records: Vec::new(),
+               m_extent: 0,
+               m_size: 0 }
+    }
+}
+
+/**
+ * Child of a `PlanDir`
+ */
+struct PlanRecord {
+    m_name: String,
+    m_identifier: Vec<u8>,
+    m_kind: RecordKind,
+    m_relocated: bool
+}
+
+enum RecordKind {
+    Dir(usize),
+    File(usize),
+    Link(String),
+    Moved(usize)
+}
+
+/**
+ * Data of a file shared by the hierarchies
+ */
+struct PlanFile {
+    m_data: Vec<u8>,
+    m_extent: u32
+}
+
+struct Master<'a> {
+    m_options: &'a MasterOptions,
+    m_dirs: Vec<PlanDir>,
+    m_files: Vec<PlanFile>,
+    m_joliet_root: Option<usize>,
+    m_relocation_dir: Option<usize>,
+    m_ce_first_block: u32,
+    m_ce_areas: Vec<u8>
+}
+
+impl<'a> Master<'a> {
+    /**
+     * Plans the children of the given tree node into the given directory
+     */
+    fn plan_dir(&mut self,
+                master_node: &MasterNode,
+                dir_index: usize,
+                depth: usize,
+                file_index: &mut usize) {
+        let children = match master_node {
+            MasterNode::Dir(children) => children,
+            _ => unreachable!()
+        };
+        let joliet = self.m_dirs[dir_index].m_joliet;
+        let rock_ridge = self.m_options.m_rock_ridge && !joliet;
+
+        let mut used_identifiers = BTreeSet::new();
+        for (child_name, child_node) in children.iter() {
+            let is_dir = matches!(child_node, MasterNode::Dir(_));
+            let identifier = if joliet {
+                joliet_identifier(child_name, is_dir)
+            } else {
+                iso_identifier(child_name, is_dir, &mut used_identifiers)
+            };
+
+            let kind = match child_node {
+                MasterNode::File(data) => {
+                    if !joliet {
+                        self.m_files.push(PlanFile { m_data: data.clone(),
+                                                     m_extent: 0 });
+                    }
+                    *file_index += 1;
+                    RecordKind::File(*file_index - 1)
+                },
+                MasterNode::Link(target) if rock_ridge => {
+                    RecordKind::Link(target.clone())
+                },
+                MasterNode::Link(_) => continue,
+                MasterNode::Dir(_) if rock_ridge && depth >= RELOCATION_DEPTH => {
+                    let relocation_dir = self.relocation_dir();
+                    let moved_dir = self.m_dirs.len();
+                    self.m_dirs
+                        .push(PlanDir::new(false, relocation_dir, Some(dir_index)));
+                    self.m_dirs[relocation_dir].m_records
+                                               .push(PlanRecord { m_name: child_name.clone(),
+                                                                  m_identifier:
+                                                                      identifier.clone(),
+                                                                  m_kind:
+                                                                      RecordKind::Dir(moved_dir),
+                                                                  m_relocated: true });
+                    self.plan_dir(child_node, moved_dir, 2, file_index);
+                    RecordKind::Moved(moved_dir)
+                },
+                MasterNode::Dir(_) => {
+                    let child_dir = self.m_dirs.len();
+                    self.m_dirs.push(PlanDir::new(joliet, dir_index, None));
+                    self.plan_dir(child_node, child_dir, depth + 1, file_index);
+                    RecordKind::Dir(child_dir)
+                }
+            };
+            self.m_dirs[dir_index].m_records.push(PlanRecord { m_name:
+                                                                   child_name.clone(),
+                                                               m_identifier:
+                                                                   identifier,
+                                                               m_kind: kind,
+                                                               m_relocated: false });
+        }
+    }
+
+    /**
+     * Returns the directory of the relocated directories, created when
+     * needed
+     */
+    fn relocation_dir(&mut self) -> usize {
+        if let Some(relocation_dir) = self.m_relocation_dir {
+            return relocation_dir;
+        }
+
+        let relocation_dir = self.m_dirs.len();
+        self.m_dirs.push(PlanDir::new(false, 0, None));
+        self.m_dirs[0].m_records.push(PlanRecord { m_name:
+                                                       String::from(RELOCATION_DIR_NAME),
+                                                   m_identifier: b"RR_MOVED".to_vec(),
+                                                   m_kind:
+                                                       RecordKind::Dir(relocation_dir),
+                                                   m_relocated: false });
+        self.m_relocation_dir = Some(relocation_dir);
+        relocation_dir
+    }
+
+    /**
+     * Encodes the records of the given directory, the continuation areas
+     * are appended to the master ones
+     */
+    fn encode_dir(&mut self, dir_index: usize) -> Vec<u8> {
+        let dir = &self.m_dirs[dir_index];
+        let is_root = dir_index == 0 || Some(dir_index) == self.m_joliet_root;
+        let rock_ridge = self.m_options.m_rock_ridge && !dir.m_joliet;
+
+        /* the links to itself and to the parent come first */
+        let mut raw_records = Vec::new();
+        let parent_index = dir.m_parent;
+        let mut self_use = Vec::new();
+        if rock_ridge && is_root {
+            self_use.extend_from_slice(&[b'S', b'P', 7, 1, 0xBE, 0xEF, 0]);
+        }
+        let mut parent_use = Vec::new();
+        if rock_ridge {
+            self_use.extend(px_entry(RR_DIR_MODE, 2));
+            self_use.extend(self.tf_entry());
+            parent_use.extend(px_entry(RR_DIR_MODE, 2));
+            parent_use.extend(self.tf_entry());
+            if let Some(moved_from) = dir.m_moved_from {
+                parent_use.extend(location_entry(b"PL",
+                                                 self.m_dirs[moved_from].m_extent));
+            }
+        }
+        raw_records.push(self.encode_record(&[0],
+                                            dir.m_extent,
+                                            dir.m_size,
+                                            FLAG_DIRECTORY,
+                                            self_use,
+                                            Vec::new()));
+        let parent = &self.m_dirs[parent_index];
+        raw_records.push(self.encode_record(&[1],
+                                            parent.m_extent,
+                                            parent.m_size,
+                                            FLAG_DIRECTORY,
+                                            parent_use,
+                                            Vec::new()));
+
+        let mut records = std::mem::take(&mut self.m_dirs[dir_index].m_records);
+        records.sort_by(|first, second| first.m_identifier.cmp(&second.m_identifier));
+        for plan_record in records.iter() {
+            let mut inline_use = Vec::new();
+            let mut moveable_use = Vec::new();
+            if rock_ridge {
+                let (mode, links) = match plan_record.m_kind {
+                    RecordKind::Dir(_) | RecordKind::Moved(_) => (RR_DIR_MODE, 2),
+                    RecordKind::File(_) => (RR_FILE_MODE, 1),
+                    RecordKind::Link(_) => (RR_LINK_MODE, 1)
+                };
+                inline_use.extend(px_entry(mode, links));
+                inline_use.extend(self.tf_entry());
+                if plan_record.m_relocated {
+                    inline_use.extend_from_slice(&[b'R', b'E', 4, 1]);
+                }
+                if let RecordKind::Moved(moved_dir) = plan_record.m_kind {
+                    inline_use.extend(location_entry(b"CL",
+                                                     self.m_dirs[moved_dir].m_extent));
+                }
+                moveable_use.extend(nm_entries(&plan_record.m_name));
+                if let RecordKind::Link(target) = &plan_record.m_kind {
+                    moveable_use.extend(sl_entries(target));
+                }
+            }
+
+            match plan_record.m_kind {
+                RecordKind::Dir(child_dir) => {
+                    let child = &self.m_dirs[child_dir];
+                    raw_records.push(self.encode_record(&plan_record.m_identifier,
+                                                        child.m_extent,
+                                                        child.m_size,
+                                                        FLAG_DIRECTORY,
+                                                        inline_use,
+                                                        moveable_use));
+                },
+                RecordKind::File(file_index) => {
+                    /* the big files are split into more extents */
+                    let data_size = self.m_files[file_index].m_data.len();
+                    let first_extent = self.m_files[file_index].m_extent;
+                    let extent_size_max = self.m_options.m_extent_size_max as usize;
+                    let mut data_offset = 0;
+                    loop {
+                        let extent_size = (data_size - data_offset).min(extent_size_max);
+                        let is_last = data_offset + extent_size == data_size;
+                        let extent = if extent_size > 0 {
+                            first_extent + (data_offset / BLOCK_SIZE) as u32
+                        } else {
+                            0
+                        };
+                        raw_records.push(self.encode_record(&plan_record.m_identifier,
+                                                            extent,
+                                                            extent_size as u32,
+                                                            if is_last {
+                                                                0
+                                                            } else {
+                                                                FLAG_MULTI_EXTENT
+                                                            },
+                                                            inline_use.clone(),
+                                                            moveable_use.clone()));
+                        data_offset += extent_size;
+                        if is_last {
+                            break;
+                        }
+                    }
+                },
+                RecordKind::Link(_) | RecordKind::Moved(_) => {
+                    raw_records.push(self.encode_record(&plan_record.m_identifier,
+                                                        0,
+                                                        0,
+                                                        0,
+                                                        inline_use,
+                                                        moveable_use));
+                }
+            }
+        }
+
+        self.m_dirs[dir_index].m_records = records;
+
+        /* the records never cross the sectors */
+        let mut raw_dir = Vec::new();
+        for raw_record in raw_records.iter() {
+            let sector_used = raw_dir.len() % BLOCK_SIZE;
+            if sector_used + raw_record.len() > BLOCK_SIZE {
+                raw_dir.resize(raw_dir.len() + BLOCK_SIZE - sector_used, 0);
+            }
+            raw_dir.extend_from_slice(raw_record);
+        }
+        raw_dir.resize(raw_dir.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        raw_dir
+    }
+
+    /**
+     * Encodes a directory record, the `moveable_use` entries are moved
+     * into a continuation area when the record would be too big
+     */
+    fn encode_record(&mut self,
+                     identifier: &[u8],
+                     extent: u32,
+                     data_size: u32,
+                     flags: u8,
+                     mut inline_use: Vec<u8>,
+                     moveable_use: Vec<u8>)
+                     -> Vec<u8> {
+        let name_end = 33 + identifier.len();
+        let system_use_start = name_end + name_end % 2;
+        if system_use_start + inline_use.len() + moveable_use.len() > RECORD_LEN_MAX {
+            /* the continuation areas never cross the blocks */
+            let area_offset = self.m_ce_areas.len() % BLOCK_SIZE;
+            if area_offset + moveable_use.len() > BLOCK_SIZE {
+                self.m_ce_areas
+                    .resize(self.m_ce_areas.len() + BLOCK_SIZE - area_offset, 0);
+            }
+            let area_block =
+                self.m_ce_first_block + (self.m_ce_areas.len() / BLOCK_SIZE) as u32;
+            let area_offset = self.m_ce_areas.len() % BLOCK_SIZE;
+            self.m_ce_areas.extend_from_slice(&moveable_use);
+
+            let mut ce_entry = vec![b'C', b'E', 28, 1];
+            ce_entry.extend(both_endian_u32(area_block));
+            ce_entry.extend(both_endian_u32(area_offset as u32));
+            ce_entry.extend(both_endian_u32(moveable_use.len() as u32));
+            inline_use.extend(ce_entry);
+        } else {
+            inline_use.extend(moveable_use);
+        }
+
+        let record_len = system_use_start + inline_use.len();
+        let mut raw_record = vec![0; record_len + record_len % 2];
+        raw_record[0] = raw_record.len() as u8;
+        raw_record[2..10].copy_from_slice(&both_endian_u32(extent));
+        raw_record[10..18].copy_from_slice(&both_endian_u32(data_size));
+        raw_record[18..25].copy_from_slice(&self.bin_timestamp());
+        raw_record[25] = flags;
+        raw_record[28..32].copy_from_slice(&[1, 0, 0, 1]);
+        raw_record[32] = identifier.len() as u8;
+        raw_record[33..name_end].copy_from_slice(identifier);
+        raw_record[system_use_start..record_len].copy_from_slice(&inline_use);
+        raw_record
+    }
+
+    /**
+     * Encodes the primary or the Joliet volume descriptor
+     */
+    fn encode_descriptor(&mut self, joliet: bool, blocks_count: u32) -> Vec<u8> {
+        let mut raw_descriptor = vec![0; BLOCK_SIZE];
+        raw_descriptor[0] = if joliet {
+            2
+        } else {
+            1
+        };
+        raw_descriptor[1..6].copy_from_slice(b"CD001");
+        raw_descriptor[6] = 1;
+
+        let volume_id = if joliet {
+            let mut volume_id =
+                "MeetiX Test".encode_utf16()
+                             .flat_map(|code_unit| code_unit.to_be_bytes().to_vec())
+                             .collect::<Vec<_>>();
+            while volume_id.len() < 32 {
+                volume_id.extend_from_slice(&[0, b' ']);
+            }
+            raw_descriptor[88..91].copy_from_slice(b"%/E");
+            volume_id
+        } else {
+            format!("{:<32}", "MEETIX_TEST").into_bytes()
+        };
+        raw_descriptor[8..40].copy_from_slice(&[b' '; 32]);
+        raw_descriptor[40..72].copy_from_slice(&volume_id);
+        raw_descriptor[80..88].copy_from_slice(&both_endian_u32(blocks_count));
+        raw_descriptor[120..124].copy_from_slice(&[1, 0, 0, 1]);
+        raw_descriptor[124..128].copy_from_slice(&[1, 0, 0, 1]);
+        raw_descriptor[128..132].copy_from_slice(&[0x00, 0x08, 0x08, 0x00]);
+        raw_descriptor[881] = 1;
+
+        let root_index = if joliet {
+            self.m_joliet_root.unwrap()
+        } else {
+            0
+        };
+        let root_dir = &self.m_dirs[root_index];
+        let (root_extent, root_size) = (root_dir.m_extent, root_dir.m_size);
+        let root_record = self.encode_record(&[0],
+                                             root_extent,
+                                             root_size,
+                                             FLAG_DIRECTORY,
+                                             Vec::new(),
+                                             Vec::new());
+        raw_descriptor[156..156 + root_record.len()].copy_from_slice(&root_record);
+        raw_descriptor
+    }
+
+    /**
+     * Returns the recording date of all the records
+     */
+    fn bin_timestamp(&self) -> [u8; 7] {
+        let (year, month, day, hours, minutes, seconds) = self.local_date();
+        [(year - 1900) as u8,
+         month as u8,
+         day as u8,
+         hours as u8,
+         minutes as u8,
+         seconds as u8,
+         self.m_options.m_gmt_offset as u8]
+    }
+
+    /**
+     * Returns the `TF` entry with the creation, modification and access
+     * timestamps
+     */
+    fn tf_entry(&self) -> Vec<u8> {
+        let raw_timestamp = if self.m_options.m_long_timestamps {
+            let (year, month, day, hours, minutes, seconds) = self.local_date();
+            let mut raw_timestamp =
+                format!("{:04}{:02}{:02}{:02}{:02}{:02}{:02}",
+                        year, month, day, hours, minutes, seconds, 50).into_bytes();
+            raw_timestamp.push(self.m_options.m_gmt_offset as u8);
+            raw_timestamp
+        } else {
+            self.bin_timestamp().to_vec()
+        };
+
+        let flags = if self.m_options.m_long_timestamps {
+            0x87
+        } else {
+            0x07
+        };
+        let mut tf_entry =
+            vec![b'T', b'F', (5 + raw_timestamp.len() * 3) as u8, 1, flags];
+        for _ in 0..3 {
+            tf_entry.extend_from_slice(&raw_timestamp);
+        }
+        tf_entry
+    }
+
+    /**
+     * Returns the local date of the mastering instant
+     */
+    fn local_date(&self) -> (i64, i64, i64, i64, i64, i64) {
+        let local_secs = self.m_options.m_unix_secs as i64
+                         + self.m_options.m_gmt_offset as i64 * 15 * 60;
+        let (year, month, day) = civil_from_days(local_secs.div_euclid(86400));
+        let day_secs = local_secs.rem_euclid(86400);
+        (year, month, day, day_secs / 3600, day_secs / 60 % 60, day_secs % 60)
+    }
+}
+
+/**
+ * Returns the uppercase 8.3 identifier of the primary hierarchy
+ */
+fn iso_identifier(name: &str,
+                  is_dir: bool,
+                  used_identifiers: &mut BTreeSet<Vec<u8>>)
+                  -> Vec<u8> {
+    let to_d_chars = |part: &str| {
+        part.chars()
+            .map(|c| {
+                let c = c.to_ascii_uppercase();
+                if c.is_ascii_uppercase() || c.is_ascii_digit() {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>()
+    };
+    let (base, ext) = match name.rfind('.') {
+        Some(dot_index) if !is_dir && dot_index > 0 => {
+            (to_d_chars(&name[..dot_index]), to_d_chars(&name[dot_index + 1..]))
+        },
+        _ => (to_d_chars(name), String::new())
+    };
+    let base = base.chars().take(8).collect::<String>();
+    let ext = ext.chars().take(3).collect::<String>();
+
+    let mut unique_base = base.clone();
+    let mut collision_index = 0;
+    loop {
+        let identifier = if is_dir {
+            unique_base.clone()
+        } else {
+            format!("{}.{};1", unique_base, ext)
+        };
+        if used_identifiers.insert(identifier.clone().into_bytes()) {
+            return identifier.into_bytes();
+        }
+
+        collision_index += 1;
+        unique_base =
+            format!("{}{:03}", base.chars().take(5).collect::<String>(), collision_index);
+    }
+}
+
+/**
+ * Returns the UCS-2 identifier of the Joliet hierarchy
+ */
+fn joliet_identifier(name: &str, is_dir: bool) -> Vec<u8> {
+    let mut code_units = name.encode_utf16().take(64).collect::<Vec<_>>();
+    if !is_dir {
+        code_units.extend(";1".encode_utf16());
+    }
+    code_units.iter().flat_map(|code_unit| code_unit.to_be_bytes().to_vec()).collect()
+}
+
+/**
+ * Returns the `PX` entry with the given mode
+ */
+fn px_entry(mode: u32, links: u32) -> Vec<u8> {
+    let mut px_entry = vec![b'P', b'X', 36, 1];
+    for value in [mode, links, RR_UID, RR_GID].iter() {
+        px_entry.extend(both_endian_u32(*value));
+    }
+    px_entry
+}
+
+/**
+ * Returns the `NM` entries of the given name
+ */
+fn nm_entries(name: &str) -> Vec<u8> {
+    let mut nm_entries = Vec::new();
+    let name_chunks = name.as_bytes().chunks(250).collect::<Vec<_>>();
+    for (chunk_index, name_chunk) in name_chunks.iter().enumerate() {
+        let continues = chunk_index + 1 < name_chunks.len();
+        nm_entries.extend_from_slice(&[b'N',
+                                       b'M',
+                                       (5 + name_chunk.len()) as u8,
+                                       1,
+                                       continues as u8]);
+        nm_entries.extend_from_slice(name_chunk);
+    }
+    nm_entries
+}
+
+/**
+ * Returns the `SL` entries of the given link target, one for each
+ * component
+ */
+fn sl_entries(target: &str) -> Vec<u8> {
+    let mut components = Vec::new();
+    if target.starts_with('/') {
+        components.push((8, ""));
+    }
+    for component in target.split('/').filter(|component| !component.is_empty()) {
+        match component {
+            "." => components.push((2, "")),
+            ".." => components.push((4, "")),
+            _ => components.push((0, component))
+        }
+    }
+
+    let mut sl_entries = Vec::new();
+    for (component_flags, component) in components {
+        sl_entries.extend_from_slice(&[b'S',
+                                       b'L',
+                                       (7 + component.len()) as u8,
+                                       1,
+                                       0,
+                                       component_flags,
+                                       component.len() as u8]);
+        sl_entries.extend_from_slice(component.as_bytes());
+    }
+    sl_entries
+}
+
+/**
+ * Returns the `CL` or `PL` entry with the given location
+ */
+fn location_entry(signature: &[u8], location: u32) -> Vec<u8> {
+    let mut location_entry = vec![signature[0], signature[1], 12, 1];
+    location_entry.extend(both_endian_u32(location));
+    location_entry
+}
+
+fn both_endian_u32(value: u32) -> Vec<u8> {
+    let mut raw_value = value.to_le_bytes().to_vec();
+    raw_value.extend_from_slice(&value.to_be_bytes());
+    raw_value
+}
+
+fn write_block(image: &mut [u8], block: u32, data: &[u8]) {
+    let offset = block as usize * BLOCK_SIZE;
+    image[offset..offset + data.len()].copy_from_slice(data);
+}
+
+/**
+ * Returns the year, the month and the day of the given days elapsed since
+ * the UNIX epoch
+ */
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+                       - day_of_era / 146_096)
+                      / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 {
+         year + 1
+     } else {
+         year
+     },
+     month,
+     day)
+}
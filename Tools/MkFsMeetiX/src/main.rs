@@ -0,0 +1,312 @@
+/*! MeetiX filesystem image builder
+ *
+ * Formats an image file with the MeetiX filesystem and optionally
+ * populates it with the content of a host directory (usually the sysroot)
+ */
+
+use std::{
+    env,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{
+        Path,
+        PathBuf
+    },
+    process,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    object::grants::{
+        ObjGrantsBits,
+        RawObjGrants
+    }
+};
+use meetix_fs::{
+    dev::ImageFileDevice,
+    layout::NodeType,
+    volume::{
+        default_prot_grants,
+        FormatOptions,
+        Volume
+    },
+    MxFsResult
+};
+
+type ImageVolume = Volume<ImageFileDevice>;
+
+const USAGE: &str = "Usage: mkfs.meetix [options] <image>
+
+Options:
+    -s, --size <MiB>             size of the image (default 64)
+    -b, --block-size <bytes>     size of the blocks (default 512)
+    -i, --inodes <count>         amount of inodes (default a block each 16)
+    -e, --blk-exp-max <exp>      maximum BLK-EXP of the chunks (default 7)
+    -f, --frag-threshold <pct>   tolerated internal fragmentation (default 25)
+    -l, --label <label>          label of the volume (default MeetiX)
+    -r, --root <dir>             directory copied into the image";
+
+/**
+ * Command line parameters
+ */
+struct MkFsArgs {
+    m_image_path: PathBuf,
+    m_size_mib: u64,
+    m_block_size: usize,
+    m_root_dir: Option<PathBuf>,
+    m_format_options: FormatOptions
+}
+
+fn main() {
+    let mkfs_args = parse_args().unwrap_or_else(|err_msg| {
+                                    eprintln!("mkfs.meetix: {}\n\n{}", err_msg, USAGE);
+                                    process::exit(2)
+                                });
+
+    let blocks_count = mkfs_args.m_size_mib * 1024 * 1024 / mkfs_args.m_block_size as u64;
+    let device = match ImageFileDevice::create(&mkfs_args.m_image_path,
+                                               mkfs_args.m_block_size,
+                                               blocks_count)
+    {
+        Ok(device) => device,
+        Err(err) => fail(&format!("Failed to create the image: {}", err))
+    };
+    let mut volume = Volume::format(device, &mkfs_args.m_format_options, host_clock)
+        .unwrap_or_else(|err| fail(&format!("Failed to format: {}", fs_err(err))));
+
+    if let Some(root_dir) = &mkfs_args.m_root_dir {
+        if let Err(err_msg) = populate_dir(&mut volume, root_dir, 1) {
+            fail(&err_msg);
+        }
+    }
+
+    let super_block = volume.super_block();
+    println!("{}: {} blocks of {} bytes, {} free, {} inodes, {} free",
+             mkfs_args.m_image_path.display(),
+             super_block.blocks_count(),
+             super_block.block_size(),
+             super_block.free_blocks(),
+             super_block.inodes_count(),
+             super_block.free_inodes());
+
+    if let Err(err) = volume.unmount() {
+        fail(&format!("Failed to unmount: {}", fs_err(err)));
+    }
+}
+
+/**
+ * Parses the command line arguments
+ */
+fn parse_args() -> Result<MkFsArgs, String> {
+    let mut image_path = None;
+    let mut size_mib = 64;
+    let mut block_size = 512;
+    let mut root_dir = None;
+    let mut format_options = FormatOptions::new();
+    format_options.set_label("MeetiX");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut option_value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "-s" | "--size" => size_mib = parse_number(&option_value()?)?,
+            "-b" | "--block-size" => {
+                block_size = parse_number(&option_value()?)? as usize
+            },
+            "-i" | "--inodes" => {
+                format_options.set_inodes_count(parse_number(&option_value()?)?);
+            },
+            "-e" | "--blk-exp-max" => {
+                format_options.set_blk_exp_max(parse_number(&option_value()?)? as u8);
+            },
+            "-f" | "--frag-threshold" => {
+                format_options.set_frag_threshold(parse_number(&option_value()?)? as u8);
+            },
+            "-l" | "--label" => {
+                format_options.set_label(&option_value()?);
+            },
+            "-r" | "--root" => root_dir = Some(PathBuf::from(option_value()?)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0)
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ if image_path.is_none() => image_path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {}", arg))
+        }
+    }
+
+    format_options.set_serial(host_clock().as_secs() as u32);
+
+    Ok(MkFsArgs { m_image_path: image_path.ok_or("Missing image path")?,
+                  m_size_mib: size_mib,
+                  m_block_size: block_size,
+                  m_root_dir: root_dir,
+                  m_format_options: format_options })
+}
+
+/**
+ * Copies the content of the given host directory into the given directory
+ * of the volume
+ */
+fn populate_dir(volume: &mut ImageVolume,
+                host_dir: &Path,
+                dir_inode_id: u64)
+                -> Result<(), String> {
+    let read_dir = fs::read_dir(host_dir).map_err(|err| {
+                                             format!("Failed to read {}: {}",
+                                                     host_dir.display(),
+                                                     err)
+                                         })?;
+
+    /* sort the entries to obtain reproducible images */
+    let mut dir_entries = read_dir.filter_map(|dir_entry| dir_entry.ok())
+                                  .map(|dir_entry| dir_entry.path())
+                                  .collect::<Vec<_>>();
+    dir_entries.sort();
+
+    for host_path in dir_entries {
+        let name = host_path.file_name()
+                            .and_then(|name| name.to_str())
+                            .ok_or(format!("Non UTF-8 name {}", host_path.display()))?;
+        let metadata = fs::symlink_metadata(&host_path).map_err(|err| {
+                                                           format!("Failed to stat {}: \
+                                                                    {}",
+                                                                   host_path.display(),
+                                                                   err)
+                                                       })?;
+        let with_path = |err| format!("{}: {}", host_path.display(), fs_err(err));
+
+        let file_type = metadata.file_type();
+        let inode_id = if file_type.is_dir() {
+            let inode_id = volume.create_node(dir_inode_id,
+                                              name,
+                                              NodeType::Dir,
+                                              default_prot_grants())
+                                 .map_err(with_path)?;
+            populate_dir(volume, &host_path, inode_id)?;
+            inode_id
+        } else if file_type.is_file() {
+            let data = fs::read(&host_path).map_err(|err| {
+                                               format!("Failed to read {}: {}",
+                                                       host_path.display(),
+                                                       err)
+                                           })?;
+
+            let inode_id = volume.create_node(dir_inode_id,
+                                              name,
+                                              NodeType::File,
+                                              default_prot_grants())
+                                 .map_err(with_path)?;
+            volume.write_data(inode_id, 0, &data).map_err(with_path)?;
+            inode_id
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&host_path).map_err(|err| {
+                                                      format!("Failed to read link {}: \
+                                                               {}",
+                                                              host_path.display(),
+                                                              err)
+                                                  })?;
+            let str_target =
+                target.to_str()
+                      .ok_or(format!("Non UTF-8 link target {}", host_path.display()))?;
+
+            let inode_id = volume.create_node(dir_inode_id,
+                                              name,
+                                              NodeType::Link,
+                                              default_prot_grants())
+                                 .map_err(with_path)?;
+            volume.write_link(inode_id, str_target).map_err(with_path)?;
+            inode_id
+        } else {
+            eprintln!("mkfs.meetix: {} skipped, unsupported type", host_path.display());
+            continue;
+        };
+
+        apply_unix_mode(volume, inode_id, metadata.permissions().mode()).map_err(with_path)?;
+    }
+    Ok(())
+}
+
+/**
+ * Translates the <rwx> bits of the host file into the grants of the inode
+ */
+fn apply_unix_mode(volume: &mut ImageVolume,
+                   inode_id: u64,
+                   unix_mode: u32)
+                   -> MxFsResult<()> {
+    let mut prot_grants = RawObjGrants::new_zero();
+    for (shift, grant_bits) in [(6,
+                                 [ObjGrantsBits::UserCanOpenIt,
+                                  ObjGrantsBits::UserCanSeeIt,
+                                  ObjGrantsBits::UserCanReadInfo,
+                                  ObjGrantsBits::UserCanReadData,
+                                  ObjGrantsBits::UserCanWriteData,
+                                  ObjGrantsBits::UserCanExecTraversData]),
+                                (3,
+                                 [ObjGrantsBits::GroupCanOpenIt,
+                                  ObjGrantsBits::GroupCanSeeIt,
+                                  ObjGrantsBits::GroupCanReadInfo,
+                                  ObjGrantsBits::GroupCanReadData,
+                                  ObjGrantsBits::GroupCanWriteData,
+                                  ObjGrantsBits::GroupCanExecTraversData]),
+                                (0,
+                                 [ObjGrantsBits::OtherCanOpenIt,
+                                  ObjGrantsBits::OtherCanSeeIt,
+                                  ObjGrantsBits::OtherCanReadInfo,
+                                  ObjGrantsBits::OtherCanReadData,
+                                  ObjGrantsBits::OtherCanWriteData,
+                                  ObjGrantsBits::OtherCanExecTraversData])].iter()
+    {
+        let [open_bit, see_bit, read_info_bit, read_bit, write_bit, exec_bit] =
+            *grant_bits;
+        let rwx_bits = (unix_mode >> shift) & 0o7;
+
+        /* the nodes are always visible, <rwx> decides the remaining grants */
+        prot_grants.set_enabled(open_bit).set_enabled(see_bit).set_enabled(read_info_bit);
+        prot_grants.set(read_bit, rwx_bits & 0o4 != 0)
+                   .set(write_bit, rwx_bits & 0o2 != 0)
+                   .set(exec_bit, rwx_bits & 0o1 != 0);
+    }
+
+    /* the owner can always update the metadata */
+    prot_grants.set_enabled(ObjGrantsBits::UserCanWriteInfo);
+
+    let mut inode = volume.load_inode(inode_id)?;
+    inode.set_prot_grants(prot_grants.raw_bits() as u32);
+    volume.store_inode(&mut inode)
+}
+
+/**
+ * Parses a decimal number argument
+ */
+fn parse_number(str_value: &str) -> Result<u64, String> {
+    str_value.parse().map_err(|_| format!("Invalid number {}", str_value))
+}
+
+/**
+ * Returns the current time since the UNIX epoch
+ */
+fn host_clock() -> Duration {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+}
+
+/**
+ * Converts the filesystem error to a printable one
+ */
+fn fs_err((err_class, err_msg): (OsErrorClass, Option<&'static str>)) -> String {
+    format!("{:?}: {}", err_class, err_msg.unwrap_or(""))
+}
+
+/**
+ * Prints the given message and exits with failure
+ */
+fn fail(err_msg: &str) -> ! {
+    eprintln!("mkfs.meetix: {}", err_msg);
+    process::exit(1)
+}
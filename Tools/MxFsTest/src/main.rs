@@ -0,0 +1,515 @@
+/*! MeetiX filesystem host test harness
+ *
+ * Formats an image file with the MeetiX filesystem, then runs on it the
+ * scenarios which exercise the on-disk format, printing the result of each
+ * one. The process exits with a failure status when any scenario fails
+ */
+
+use std::{
+    env,
+    path::{
+        Path,
+        PathBuf
+    },
+    process,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+use meetix_fs::{
+    dev::ImageFileDevice,
+    layout::{
+        FileFlags,
+        FileFlagsBits,
+        NodeType
+    },
+    volume::{
+        default_prot_grants,
+        FormatOptions,
+        Volume
+    },
+    MxFsResult
+};
+
+type TestVolume = Volume<ImageFileDevice>;
+type TestResult = Result<(), String>;
+type ScenarioFn = fn(&mut TestVolume) -> TestResult;
+
+/* geometry of the test image (16MiB) */
+const BLOCK_SIZE: usize = 512;
+const BLOCKS_COUNT: u64 = 32 * 1024;
+
+fn main() {
+    let image_path =
+        PathBuf::from(env::args().nth(1).unwrap_or(String::from("mxfs_test.img")));
+
+    let device = ImageFileDevice::create(&image_path, BLOCK_SIZE, BLOCKS_COUNT)
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to create {}: {}", image_path.display(), err);
+            process::exit(2)
+        });
+    let mut volume = Volume::format(device,
+                                    FormatOptions::new().set_label("MxFsTest"),
+                                    host_clock).unwrap_or_else(|err| {
+                                                   eprintln!("Failed to format {}: {:?}",
+                                                             image_path.display(),
+                                                             err);
+                                                   process::exit(2)
+                                               });
+
+    let scenarios: [(&str, ScenarioFn); 11] =
+        [("write and read back", test_write_read),
+         ("overwrite", test_overwrite),
+         ("truncate and extend", test_truncate_extend),
+         ("links", test_links),
+         ("directories", test_directories),
+         ("clone with copy-on-write", test_clone_cow),
+         ("clone of NO COW files", test_clone_no_cow),
+         ("low fragment mode", test_low_fragment),
+         ("extents chain", test_extents_chain),
+         ("CRC corruption detection", test_crc_detection),
+         ("free blocks accounting", test_free_blocks)];
+
+    let mut failures = 0;
+    for (scenario_name, scenario_fn) in scenarios.iter() {
+        failures += report(scenario_name, scenario_fn(&mut volume));
+    }
+
+    /* the remount consumes the volume, so it is the last scenario */
+    failures += report("remount persistence", test_remount(volume, &image_path));
+
+    if failures > 0 {
+        println!("{} scenarios failed", failures);
+        process::exit(1);
+    }
+    println!("All scenarios passed");
+}
+
+/**
+ * Writes a file and reads it back
+ */
+fn test_write_read(volume: &mut TestVolume) -> TestResult {
+    let file_id = create_file(volume, "data.bin")?;
+    let data = pattern(100_000, 7);
+
+    check_eq(fs(volume.write_data(file_id, 0, &data))?, data.len(), "written bytes")?;
+    check_eq(read_all(volume, file_id)?, data.clone(), "read back data")?;
+
+    /* read across the end of the file */
+    let mut buffer = vec![0; 1000];
+    check_eq(fs(volume.read_data(file_id, data.len() - 100, &mut buffer))?,
+             100,
+             "bytes read at the end")
+}
+
+/**
+ * Overwrites the middle of a file spanning more chunks
+ */
+fn test_overwrite(volume: &mut TestVolume) -> TestResult {
+    let file_id = lookup_id(volume, "data.bin")?;
+    let mut data = pattern(100_000, 7);
+    let patch = pattern(5000, 13);
+
+    fs(volume.write_data(file_id, 30_000, &patch))?;
+    data[30_000..35_000].copy_from_slice(&patch);
+    check_eq(read_all(volume, file_id)?, data, "overwritten data")
+}
+
+/**
+ * Truncates a file then extends it with zeroes and writes past the end
+ */
+fn test_truncate_extend(volume: &mut TestVolume) -> TestResult {
+    let file_id = create_file(volume, "resize.bin")?;
+    fs(volume.write_data(file_id, 0, &pattern(200_000, 3)))?;
+
+    let free_blocks = volume.super_block().free_blocks();
+    fs(volume.set_data_size(file_id, 1000))?;
+    check(volume.super_block().free_blocks() > free_blocks,
+          "truncate releases the chunks")?;
+
+    fs(volume.set_data_size(file_id, 5000))?;
+    let mut expected = pattern(1000, 3);
+    expected.resize(5000, 0);
+    check_eq(read_all(volume, file_id)?, expected.clone(), "extended data")?;
+
+    /* the hole is zero filled */
+    fs(volume.write_data(file_id, 8000, b"tail"))?;
+    expected.resize(8000, 0);
+    expected.extend_from_slice(b"tail");
+    check_eq(read_all(volume, file_id)?, expected, "data written past the end")
+}
+
+/**
+ * Creates a link and reads back his target
+ */
+fn test_links(volume: &mut TestVolume) -> TestResult {
+    let link_id =
+        fs(volume.create_node(1, "data.lnk", NodeType::Link, default_prot_grants()))?;
+
+    fs(volume.write_link(link_id, "/data.bin"))?;
+    check_eq(fs(volume.read_link(link_id))?, String::from("/data.bin"), "link target")?;
+
+    fs(volume.write_link(link_id, "/resize.bin"))?;
+    check_eq(fs(volume.read_link(link_id))?,
+             String::from("/resize.bin"),
+             "updated target")
+}
+
+/**
+ * Creates and removes nested directories
+ */
+fn test_directories(volume: &mut TestVolume) -> TestResult {
+    let free_inodes = volume.super_block().free_inodes();
+
+    let dir_id = fs(volume.create_node(1, "Dir", NodeType::Dir, default_prot_grants()))?;
+    for file_index in 0..10 {
+        fs(volume.create_node(dir_id,
+                              &format!("file{}", file_index),
+                              NodeType::File,
+                              default_prot_grants()))?;
+    }
+    check_eq(fs(volume.dir_records(dir_id))?.len(), 10, "directory records")?;
+    check(fs(volume.dir_record_at(dir_id, 9))?.is_some(), "last record by index")?;
+    check(volume.create_node(dir_id, "file3", NodeType::File, 0).is_err(),
+          "duplicated names are refused")?;
+    check(volume.create_node(dir_id, "a/b", NodeType::File, 0).is_err(),
+          "names with separators are refused")?;
+
+    check(volume.remove_node(1, "Dir").is_err(), "non-empty directories are kept")?;
+    for file_index in 0..10 {
+        fs(volume.remove_node(dir_id, &format!("file{}", file_index)))?;
+    }
+    fs(volume.remove_node(1, "Dir"))?;
+
+    check(fs(volume.lookup(1, "Dir"))?.is_none(), "removed directory")?;
+    check_eq(volume.super_block().free_inodes(), free_inodes, "free inodes")
+}
+
+/**
+ * Clones a file sharing his chunks, then modifies the clone
+ */
+fn test_clone_cow(volume: &mut TestVolume) -> TestResult {
+    let src_id = lookup_id(volume, "data.bin")?;
+    let src_data = read_all(volume, src_id)?;
+
+    let src_inode = fs(volume.load_inode(src_id))?;
+    let src_blocks: u64 =
+        src_inode.extents().iter().map(|extent| extent.blocks_count()).sum();
+
+    /* only the directory record could need a new block */
+    let free_blocks = volume.super_block().free_blocks();
+    let clone_id = fs(volume.clone_file(src_id, 1, "data.clone"))?;
+    check(free_blocks - volume.super_block().free_blocks() < src_blocks,
+          "no data blocks copied")?;
+
+    for extent in src_inode.extents().iter() {
+        let chunk_header = fs(volume.read_chunk_header(extent.first_block()))?;
+        check_eq(chunk_header.ref_count(), 2, "shared chunk references")?;
+    }
+
+    /* the write copies only the touched chunk */
+    fs(volume.write_data(clone_id, 10, b"modified"))?;
+    let mut clone_data = src_data.clone();
+    clone_data[10..18].copy_from_slice(b"modified");
+    check_eq(read_all(volume, clone_id)?, clone_data, "clone data")?;
+    check_eq(read_all(volume, src_id)?, src_data.clone(), "source data")?;
+
+    let first_extent = src_inode.extents()[0];
+    let chunk_header = fs(volume.read_chunk_header(first_extent.first_block()))?;
+    check_eq(chunk_header.ref_count(), 1, "references of the copied chunk")?;
+
+    /* the removal of the clone releases only the references */
+    fs(volume.remove_node(1, "data.clone"))?;
+    check_eq(read_all(volume, src_id)?, src_data, "source after clone removal")?;
+    for extent in src_inode.extents().iter() {
+        let chunk_header = fs(volume.read_chunk_header(extent.first_block()))?;
+        check_eq(chunk_header.ref_count(), 1, "references after clone removal")?;
+    }
+    check_eq(volume.super_block().free_blocks(), free_blocks, "free blocks")
+}
+
+/**
+ * Clones a NO COW file, which never shares his chunks
+ */
+fn test_clone_no_cow(volume: &mut TestVolume) -> TestResult {
+    let src_id = create_file(volume, "nocow.bin")?;
+    fs(volume.set_file_flags(src_id, file_flags(&[FileFlagsBits::NoCow])))?;
+    let data = pattern(20_000, 5);
+    fs(volume.write_data(src_id, 0, &data))?;
+
+    let clone_id = fs(volume.clone_file(src_id, 1, "nocow.clone"))?;
+    check_eq(read_all(volume, clone_id)?, data, "clone data")?;
+
+    let src_inode = fs(volume.load_inode(src_id))?;
+    let clone_inode = fs(volume.load_inode(clone_id))?;
+    for clone_extent in clone_inode.extents().iter() {
+        check(src_inode.extents()
+                       .iter()
+                       .all(|extent| {
+                           extent.first_block() != clone_extent.first_block()
+                       }),
+              "chunks not shared")?;
+
+        let chunk_header = fs(volume.read_chunk_header(clone_extent.first_block()))?;
+        check_eq(chunk_header.ref_count(), 1, "clone chunk references")?;
+    }
+    Ok(())
+}
+
+/**
+ * Appends small pieces to two files, the low fragment one must use less
+ * extents
+ */
+fn test_low_fragment(volume: &mut TestVolume) -> TestResult {
+    let default_id = create_file(volume, "frag.default")?;
+    let low_frag_id = create_file(volume, "frag.low")?;
+    fs(volume.set_file_flags(low_frag_id, file_flags(&[FileFlagsBits::LowFragment])))?;
+
+    let piece = pattern(600, 11);
+    for piece_index in 0..100 {
+        fs(volume.write_data(default_id, piece_index * piece.len(), &piece))?;
+        fs(volume.write_data(low_frag_id, piece_index * piece.len(), &piece))?;
+    }
+    check_eq(read_all(volume, default_id)?,
+             read_all(volume, low_frag_id)?,
+             "data of the two files")?;
+
+    let default_extents = fs(volume.load_inode(default_id))?.extents().len();
+    let low_frag_extents = fs(volume.load_inode(low_frag_id))?.extents().len();
+    check(low_frag_extents < default_extents,
+          &format!("low fragment extents ({}) less than default ({})",
+                   low_frag_extents, default_extents))
+}
+
+/**
+ * Writes a file with more extents than the inline ones
+ */
+fn test_extents_chain(volume: &mut TestVolume) -> TestResult {
+    let file_id = create_file(volume, "chain.bin")?;
+    let other_id = create_file(volume, "chain.other")?;
+
+    /* interleaved writes prevent the in-place expansion of the chunks */
+    let piece = pattern(BLOCK_SIZE * 64, 17);
+    let mut expected = Vec::new();
+    for _ in 0..40 {
+        let file_size = expected.len();
+        fs(volume.write_data(file_id, file_size, &piece))?;
+        fs(volume.write_data(other_id, file_size, &piece[..BLOCK_SIZE]))?;
+        expected.extend_from_slice(&piece);
+    }
+
+    let inode = fs(volume.load_inode(file_id))?;
+    check(!inode.extents_blocks().is_empty(),
+          &format!("extents blocks used ({} extents)", inode.extents().len()))?;
+    check_eq(read_all(volume, file_id)?, expected, "chained file data")?;
+
+    /* the truncate releases the exceeding extents blocks */
+    fs(volume.set_data_size(file_id, 100))?;
+    check(fs(volume.load_inode(file_id))?.extents_blocks().is_empty(),
+          "extents blocks released")?;
+
+    fs(volume.remove_node(1, "chain.bin"))?;
+    fs(volume.remove_node(1, "chain.other"))
+}
+
+/**
+ * Corrupts the chunks of a file with CRC and of a NO CRC one
+ */
+fn test_crc_detection(volume: &mut TestVolume) -> TestResult {
+    let crc_id = create_file(volume, "crc.bin")?;
+    let no_crc_id = create_file(volume, "nocrc.bin")?;
+    fs(volume.set_file_flags(no_crc_id, file_flags(&[FileFlagsBits::NoCrc])))?;
+
+    let data = pattern(3000, 19);
+    fs(volume.write_data(crc_id, 0, &data))?;
+    fs(volume.write_data(no_crc_id, 0, &data))?;
+
+    corrupt_first_chunk(volume, crc_id)?;
+    corrupt_first_chunk(volume, no_crc_id)?;
+
+    let mut buffer = vec![0; data.len()];
+    check(volume.read_data(crc_id, 0, &mut buffer).is_err(), "corruption detected")?;
+    check(volume.read_data(no_crc_id, 0, &mut buffer).is_ok(),
+          "NO CRC files skip the check")?;
+
+    fs(volume.remove_node(1, "crc.bin"))?;
+    fs(volume.remove_node(1, "nocrc.bin"))
+}
+
+/**
+ * Creates and removes files, all the blocks must return free
+ */
+fn test_free_blocks(volume: &mut TestVolume) -> TestResult {
+    let free_blocks = volume.super_block().free_blocks();
+    let free_inodes = volume.super_block().free_inodes();
+
+    for file_index in 0..20 {
+        let file_name = format!("tmp{}", file_index);
+        let file_id = create_file(volume, &file_name)?;
+        fs(volume.write_data(file_id, 0, &pattern(1000 * (file_index + 1), 23)))?;
+    }
+    check(volume.super_block().free_blocks() < free_blocks, "blocks used")?;
+
+    for file_index in 0..20 {
+        fs(volume.remove_node(1, &format!("tmp{}", file_index)))?;
+    }
+    check_eq(volume.super_block().free_blocks(), free_blocks, "free blocks")?;
+    check_eq(volume.super_block().free_inodes(), free_inodes, "free inodes")?;
+
+    /* the counter must match the bitmap */
+    let super_block = volume.super_block();
+    let used_blocks =
+        (0..super_block.blocks_count()).filter(|block_index| {
+                                           volume.is_block_used(*block_index)
+                                       })
+                                       .count() as u64;
+    check_eq(super_block.blocks_count() - used_blocks,
+             super_block.free_blocks(),
+             "free blocks in bitmap")
+}
+
+/**
+ * Unmounts and mounts again the image, checking the stored data
+ */
+fn test_remount(volume: TestVolume, image_path: &Path) -> TestResult {
+    let free_blocks = volume.super_block().free_blocks();
+    fs(volume.unmount())?;
+
+    let device =
+        ImageFileDevice::open(image_path, BLOCK_SIZE).map_err(|err| err.to_string())?;
+    let mut volume = fs(Volume::mount(device, host_clock))?;
+    check(volume.was_clean(), "clean unmount")?;
+    check_eq(volume.super_block().label(), "MxFsTest", "label")?;
+    check_eq(volume.super_block().free_blocks(), free_blocks, "free blocks")?;
+
+    let file_id = lookup_id(&mut volume, "data.bin")?;
+    let mut data = pattern(100_000, 7);
+    data[30_000..35_000].copy_from_slice(&pattern(5000, 13));
+    check_eq(read_all(&mut volume, file_id)?, data, "file data")?;
+
+    let link_id = lookup_id(&mut volume, "data.lnk")?;
+    check_eq(fs(volume.read_link(link_id))?, String::from("/resize.bin"), "link target")?;
+
+    /* a volume not unmounted is reported as dirty */
+    fs(volume.sync())?;
+    drop(volume);
+
+    let device =
+        ImageFileDevice::open(image_path, BLOCK_SIZE).map_err(|err| err.to_string())?;
+    let volume = fs(Volume::mount(device, host_clock))?;
+    check(!volume.was_clean(), "dirty mount detected")?;
+    fs(volume.unmount()).map(|_| ())
+}
+
+/**
+ * Prints the result of the scenario and returns the failures count
+ */
+fn report(scenario_name: &str, result: TestResult) -> usize {
+    match result {
+        Ok(_) => {
+            println!("[ OK ] {}", scenario_name);
+            0
+        },
+        Err(err) => {
+            println!("[FAIL] {}: {}", scenario_name, err);
+            1
+        }
+    }
+}
+
+/**
+ * Creates an empty file into the root directory
+ */
+fn create_file(volume: &mut TestVolume, file_name: &str) -> Result<u64, String> {
+    fs(volume.create_node(1, file_name, NodeType::File, default_prot_grants()))
+}
+
+/**
+ * Returns the inode of the given child of the root directory
+ */
+fn lookup_id(volume: &mut TestVolume, file_name: &str) -> Result<u64, String> {
+    fs(volume.lookup(1, file_name))?.map(|dir_record| dir_record.inode_id())
+                                    .ok_or(format!("{} not found", file_name))
+}
+
+/**
+ * Reads the whole data of the given inode
+ */
+fn read_all(volume: &mut TestVolume, inode_id: u64) -> Result<Vec<u8>, String> {
+    let inode = fs(volume.load_inode(inode_id))?;
+
+    let mut data = vec![0; inode.data_size() as usize];
+    check_eq(fs(volume.read_data(inode_id, 0, &mut data))?, data.len(), "read bytes")?;
+    Ok(data)
+}
+
+/**
+ * Flips a data byte of the first chunk of the given file
+ */
+fn corrupt_first_chunk(volume: &mut TestVolume, inode_id: u64) -> TestResult {
+    use meetix_fs::dev::TBlockDevice;
+
+    let first_block = fs(volume.load_inode(inode_id))?.extents()[0].first_block();
+
+    let mut raw_block = vec![0; BLOCK_SIZE];
+    let device = volume.device_mut();
+    fs(device.read_blocks(first_block, &mut raw_block))?;
+    raw_block[100] ^= 0xFF;
+    fs(device.write_blocks(first_block, &raw_block))
+}
+
+/**
+ * Returns the `FileFlags` with the given bits enabled
+ */
+fn file_flags(flags_bits: &[FileFlagsBits]) -> FileFlags {
+    let mut file_flags = FileFlags::new_zero();
+    for flag_bit in flags_bits.iter() {
+        file_flags.set_enabled(*flag_bit);
+    }
+    file_flags
+}
+
+/**
+ * Returns a deterministic data pattern
+ */
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|byte_index| (byte_index as u8).wrapping_mul(seed).wrapping_add(seed))
+            .collect()
+}
+
+/**
+ * Returns the current time since the UNIX epoch
+ */
+fn host_clock() -> Duration {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+}
+
+/**
+ * Converts the filesystem error to a printable one
+ */
+fn fs<T>(result: MxFsResult<T>) -> Result<T, String> {
+    result.map_err(|(err_class, err_msg)| {
+              format!("{:?}: {}", err_class, err_msg.unwrap_or(""))
+          })
+}
+
+fn check(condition: bool, what: &str) -> TestResult {
+    if condition {
+        Ok(())
+    } else {
+        Err(format!("{} failed", what))
+    }
+}
+
+fn check_eq<T>(value: T, expected: T, what: &str) -> TestResult
+    where T: PartialEq + std::fmt::Debug {
+    if value == expected {
+        Ok(())
+    } else if format!("{:?}", value).len() > 128 {
+        Err(format!("{} mismatch", what))
+    } else {
+        Err(format!("{}: {:?} != {:?}", what, value, expected))
+    }
+}
@@ -5,6 +5,8 @@ use core::{
     fmt
 };
 
+use crate::sys::TFromSysCallPtr;
+
 /**
  * List the well-known error classes which an `OsError` can represent
  */
@@ -97,7 +99,13 @@ pub enum OsErrorClass {
      * The previous system call was failed because the running transaction
      * was interrupted by something else
      */
-    InterruptedOperation
+    InterruptedOperation,
+
+    /**
+     * The `Thread` was terminated because it accessed memory not mapped
+     * or not allowed by the protection of the region
+     */
+    InvalidMemoryAccess
 }
 
 impl Default for OsErrorClass {
@@ -131,11 +139,18 @@ impl TryFrom<u8> for OsErrorClass {
             11 => Ok(Self::OperationNotEnabled),
             12 => Ok(Self::EndOfDataReached),
             13 => Ok(Self::InterruptedOperation),
+            14 => Ok(Self::InvalidMemoryAccess),
             _ => Err(())
         }
     }
 }
 
+unsafe impl TFromSysCallPtr for OsErrorClass {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        Self::try_from((raw_ptr as *const u8).read()).is_ok()
+    }
+}
+
 impl fmt::Display for OsErrorClass {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -152,7 +167,8 @@ impl fmt::Display for OsErrorClass {
             Self::NoDataAvailable => write!(f, "Data not available"),
             Self::OperationNotEnabled => write!(f, "Operation not enabled"),
             Self::EndOfDataReached => write!(f, "End of data reached"),
-            Self::InterruptedOperation => write!(f, "Interrupted operation")
+            Self::InterruptedOperation => write!(f, "Interrupted operation"),
+            Self::InvalidMemoryAccess => write!(f, "Invalid memory access")
         }
     }
 }
@@ -1,6 +1,9 @@
 /*! Kernel call error management */
 
-use core::fmt;
+use core::{
+    fmt,
+    ptr::addr_of
+};
 
 use helps::str::{
     copy_str_to_u8_buf,
@@ -13,7 +16,9 @@ use crate::{
     sys::{
         fn_path::KernFnPath,
         RawKernHandle,
-        TAsSysCallPtr
+        RawOption,
+        TAsSysCallPtr,
+        TFromSysCallPtr
     },
     task::TaskId
 };
@@ -29,10 +34,10 @@ pub mod class;
 pub struct OsError {
     m_class: OsErrorClass,
     m_kern_fn_path: KernFnPath,
-    m_inst_handle: Option<RawKernHandle>,
+    m_inst_handle: RawOption<RawKernHandle>,
     m_proc_id: TaskId,
     m_thread_id: TaskId,
-    m_message: Option<[u8; OS_ERROR_MESSAGE_LEN_MAX]>
+    m_message: RawOption<[u8; OS_ERROR_MESSAGE_LEN_MAX]>
 }
 
 impl OsError /* Constructors */ {
@@ -48,14 +53,15 @@ impl OsError /* Constructors */ {
                -> Self {
         Self { m_class: class,
                m_kern_fn_path: kern_fn_path,
-               m_inst_handle: inst_handle,
+               m_inst_handle: inst_handle.into(),
                m_proc_id: proc_id,
                m_thread_id: thread_id,
                m_message: message.map(|str_buf| {
                                      let mut buffer = [0; OS_ERROR_MESSAGE_LEN_MAX];
                                      copy_str_to_u8_buf(&mut buffer, str_buf);
                                      buffer
-                                 }) }
+                                 })
+                                 .into() }
     }
 }
 
@@ -78,14 +84,14 @@ impl OsError /* Getters */ {
      * Returns the `KernHandle` which originates this `OsError` if any
      */
     pub fn inst_handle(&self) -> Option<RawKernHandle> {
-        self.m_inst_handle
+        self.m_inst_handle.into()
     }
 
     /**
      * Returns the formatted message of the error if any
      */
     pub fn message(&self) -> Option<&str> {
-        self.m_message.as_ref().map(|message_buf| u8_slice_to_str_slice(message_buf))
+        self.m_message.as_option().map(|message_buf| u8_slice_to_str_slice(message_buf))
     }
 }
 
@@ -93,6 +99,15 @@ impl TAsSysCallPtr for OsError {
     /* No methods to implement */
 }
 
+unsafe impl TFromSysCallPtr for OsError {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        OsErrorClass::is_valid_raw(addr_of!((*raw_ptr).m_class))
+        && KernFnPath::is_valid_raw(addr_of!((*raw_ptr).m_kern_fn_path))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_inst_handle))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_message))
+    }
+}
+
 impl fmt::Display for OsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         /* write the complete error message as follow:
@@ -1,6 +1,9 @@
 /*! `Object` configuration */
 
-use core::convert::TryFrom;
+use core::{
+    convert::TryFrom,
+    ptr::addr_of
+};
 
 use bits::bit_flags::{
     BitFlags,
@@ -13,7 +16,11 @@ use crate::{
         types::ObjType
     },
     path::PathComponent,
-    sys::TAsSysCallPtr
+    sys::{
+        RawOption,
+        TAsSysCallPtr,
+        TFromSysCallPtr
+    }
 };
 
 /**
@@ -31,7 +38,7 @@ pub struct RawObjConfig<'a> {
     m_path: Option<&'a [PathComponent]>,
     m_flags: ObjConfigFlags,
     m_grants: RawObjGrants,
-    m_data_size: Option<usize>
+    m_data_size: RawOption<usize>
 }
 
 impl<'a> RawObjConfig<'a> /* Constructors */ {
@@ -47,7 +54,7 @@ impl<'a> RawObjConfig<'a> /* Constructors */ {
         };
 
         Self { m_flags: config_flags,
-               m_data_size: None,
+               m_data_size: RawOption::None,
                m_grants: RawObjGrants::new_zero(),
                m_type: obj_type,
                m_path: None }
@@ -87,7 +94,7 @@ impl<'a> RawObjConfig<'a> /* Getters */ {
      * Returns the optional truncation size
      */
     pub fn data_size(&self) -> Option<usize> {
-        self.m_data_size
+        self.m_data_size.into()
     }
 }
 
@@ -124,7 +131,7 @@ impl<'a> RawObjConfig<'a> /* Setters */ {
      * Sets the truncation size for the `Object` to open
      */
     pub fn set_data_size(&mut self, data_size: usize) {
-        self.m_data_size = Some(data_size);
+        self.m_data_size = RawOption::Some(data_size);
     }
 }
 
@@ -132,6 +139,13 @@ impl<'a> TAsSysCallPtr for RawObjConfig<'a> {
     /* No methods to implement */
 }
 
+unsafe impl<'a> TFromSysCallPtr for RawObjConfig<'a> {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        ObjType::is_valid_raw(addr_of!((*raw_ptr).m_type))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_data_size))
+    }
+}
+
 /**
  * Lists the internal `RawOsEntityConfig` flags
  */
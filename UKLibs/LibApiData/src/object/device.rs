@@ -1,9 +1,14 @@
 /*! `Device` specific data structures */
 
-use core::convert::TryFrom;
+use core::{
+    convert::TryFrom,
+    ptr::addr_of
+};
 
 use bits::bit_fields::TBitFields;
 
+use crate::sys::TFromSysCallPtr;
+
 /**
  * `Device` identifier
  */
@@ -79,6 +84,13 @@ impl Into<usize> for DeviceId {
     }
 }
 
+unsafe impl TFromSysCallPtr for DeviceId {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        DeviceIdType::is_valid_raw(addr_of!((*raw_ptr).m_device_type))
+        && DeviceIdClass::is_valid_raw(addr_of!((*raw_ptr).m_device_class))
+    }
+}
+
 /**
  * Lists the supported `Device` sub-types
  */
@@ -142,6 +154,12 @@ impl TryFrom<u8> for DeviceIdType {
     }
 }
 
+unsafe impl TFromSysCallPtr for DeviceIdType {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        Self::try_from((raw_ptr as *const u8).read()).is_ok()
+    }
+}
+
 /**
  * Lists the supported `Device` classes
  */
@@ -283,3 +301,9 @@ impl TryFrom<u8> for DeviceIdClass {
         }
     }
 }
+
+unsafe impl TFromSysCallPtr for DeviceIdClass {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        Self::try_from((raw_ptr as *const u8).read()).is_ok()
+    }
+}
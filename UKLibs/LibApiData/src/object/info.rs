@@ -1,5 +1,7 @@
 /*! `Object` metadata information structures */
 
+use core::ptr::addr_of;
+
 use helps::str::{
     copy_str_to_u8_buf,
     u8_slice_to_str_slice
@@ -18,7 +20,10 @@ use crate::{
         types::ObjType,
         uses::ObjUseBits
     },
-    sys::TAsSysCallPtr,
+    sys::{
+        TAsSysCallPtr,
+        TFromSysCallPtr
+    },
     task::TaskId
 };
 
@@ -347,6 +352,14 @@ impl TAsSysCallPtr for RawObjInfo {
     /* No methods to implement */
 }
 
+unsafe impl TFromSysCallPtr for RawObjInfo {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        ObjType::is_valid_raw(addr_of!((*raw_ptr).m_type))
+        && DeviceId::is_valid_raw(addr_of!((*raw_ptr).m_device))
+        && bool::is_valid_raw(addr_of!((*raw_ptr).m_has_name))
+    }
+}
+
 impl Default for RawObjInfo {
     fn default() -> Self {
         Self { m_type: ObjType::default(),
@@ -0,0 +1,311 @@
+/*! `Device` input events */
+
+use core::convert::TryFrom;
+
+/**
+ * Event produced by the input `Device`s, which are read as a stream of
+ * `InputEvent`s
+ */
+#[repr(C, u8)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum InputEvent {
+    /**
+     * A key is pressed (`true`) or released (`false`), the keys kept
+     * pressed are repeated by the keyboard
+     */
+    Key(KeyCode, bool),
+
+    /**
+     * The pointer is moved by the given amounts of horizontal and vertical
+     * units, the vertical ones grow downward like the screen rows
+     */
+    MouseMove(i16, i16),
+
+    /**
+     * A `MouseButton` is pressed (`true`) or released (`false`)
+     */
+    MouseButton(MouseButton, bool),
+
+    /**
+     * The wheel is rotated by the given amount of notches, the positive
+     * ones are toward the user
+     */
+    MouseWheel(i8)
+}
+
+/**
+ * Lists the keyboard keys, independently of the keyboard layout
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+#[derive(Ord, PartialOrd)]
+#[derive(Hash)]
+pub enum KeyCode {
+    /* keys of the main block, valued as their scancode set 1 */
+    Escape         = 0x01,
+    Digit1         = 0x02,
+    Digit2         = 0x03,
+    Digit3         = 0x04,
+    Digit4         = 0x05,
+    Digit5         = 0x06,
+    Digit6         = 0x07,
+    Digit7         = 0x08,
+    Digit8         = 0x09,
+    Digit9         = 0x0a,
+    Digit0         = 0x0b,
+    Minus          = 0x0c,
+    Equal          = 0x0d,
+    Backspace      = 0x0e,
+    Tab            = 0x0f,
+    Q              = 0x10,
+    W              = 0x11,
+    E              = 0x12,
+    R              = 0x13,
+    T              = 0x14,
+    Y              = 0x15,
+    U              = 0x16,
+    I              = 0x17,
+    O              = 0x18,
+    P              = 0x19,
+    LeftBracket    = 0x1a,
+    RightBracket   = 0x1b,
+    Enter          = 0x1c,
+    LeftCtrl       = 0x1d,
+    A              = 0x1e,
+    S              = 0x1f,
+    D              = 0x20,
+    F              = 0x21,
+    G              = 0x22,
+    H              = 0x23,
+    J              = 0x24,
+    K              = 0x25,
+    L              = 0x26,
+    Semicolon      = 0x27,
+    Apostrophe     = 0x28,
+    Grave          = 0x29,
+    LeftShift      = 0x2a,
+    Backslash      = 0x2b,
+    Z              = 0x2c,
+    X              = 0x2d,
+    C              = 0x2e,
+    V              = 0x2f,
+    B              = 0x30,
+    N              = 0x31,
+    M              = 0x32,
+    Comma          = 0x33,
+    Dot            = 0x34,
+    Slash          = 0x35,
+    RightShift     = 0x36,
+    KpMultiply     = 0x37,
+    LeftAlt        = 0x38,
+    Space          = 0x39,
+    CapsLock       = 0x3a,
+    F1             = 0x3b,
+    F2             = 0x3c,
+    F3             = 0x3d,
+    F4             = 0x3e,
+    F5             = 0x3f,
+    F6             = 0x40,
+    F7             = 0x41,
+    F8             = 0x42,
+    F9             = 0x43,
+    F10            = 0x44,
+    NumLock        = 0x45,
+    ScrollLock     = 0x46,
+    Kp7            = 0x47,
+    Kp8            = 0x48,
+    Kp9            = 0x49,
+    KpMinus        = 0x4a,
+    Kp4            = 0x4b,
+    Kp5            = 0x4c,
+    Kp6            = 0x4d,
+    KpPlus         = 0x4e,
+    Kp1            = 0x4f,
+    Kp2            = 0x50,
+    Kp3            = 0x51,
+    Kp0            = 0x52,
+    KpDot          = 0x53,
+    NonUsBackslash = 0x56,
+    F11            = 0x57,
+    F12            = 0x58,
+
+    /* keys with the 0xe0 prefix, valued as 0x80 | scancode set 1 */
+    KpEnter        = 0x9c,
+    RightCtrl      = 0x9d,
+    KpSlash        = 0xb5,
+    PrintScreen    = 0xb7,
+    RightAlt       = 0xb8,
+    Pause          = 0xc5,
+    Home           = 0xc7,
+    Up             = 0xc8,
+    PageUp         = 0xc9,
+    Left           = 0xcb,
+    Right          = 0xcd,
+    End            = 0xcf,
+    Down           = 0xd0,
+    PageDown       = 0xd1,
+    Insert         = 0xd2,
+    Delete         = 0xd3,
+    LeftMeta       = 0xdb,
+    RightMeta      = 0xdc,
+    Menu           = 0xdd
+}
+
+impl Into<u8> for KeyCode {
+    fn into(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for KeyCode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Self::Escape),
+            0x02 => Ok(Self::Digit1),
+            0x03 => Ok(Self::Digit2),
+            0x04 => Ok(Self::Digit3),
+            0x05 => Ok(Self::Digit4),
+            0x06 => Ok(Self::Digit5),
+            0x07 => Ok(Self::Digit6),
+            0x08 => Ok(Self::Digit7),
+            0x09 => Ok(Self::Digit8),
+            0x0a => Ok(Self::Digit9),
+            0x0b => Ok(Self::Digit0),
+            0x0c => Ok(Self::Minus),
+            0x0d => Ok(Self::Equal),
+            0x0e => Ok(Self::Backspace),
+            0x0f => Ok(Self::Tab),
+            0x10 => Ok(Self::Q),
+            0x11 => Ok(Self::W),
+            0x12 => Ok(Self::E),
+            0x13 => Ok(Self::R),
+            0x14 => Ok(Self::T),
+            0x15 => Ok(Self::Y),
+            0x16 => Ok(Self::U),
+            0x17 => Ok(Self::I),
+            0x18 => Ok(Self::O),
+            0x19 => Ok(Self::P),
+            0x1a => Ok(Self::LeftBracket),
+            0x1b => Ok(Self::RightBracket),
+            0x1c => Ok(Self::Enter),
+            0x1d => Ok(Self::LeftCtrl),
+            0x1e => Ok(Self::A),
+            0x1f => Ok(Self::S),
+            0x20 => Ok(Self::D),
+            0x21 => Ok(Self::F),
+            0x22 => Ok(Self::G),
+            0x23 => Ok(Self::H),
+            0x24 => Ok(Self::J),
+            0x25 => Ok(Self::K),
+            0x26 => Ok(Self::L),
+            0x27 => Ok(Self::Semicolon),
+            0x28 => Ok(Self::Apostrophe),
+            0x29 => Ok(Self::Grave),
+            0x2a => Ok(Self::LeftShift),
+            0x2b => Ok(Self::Backslash),
+            0x2c => Ok(Self::Z),
+            0x2d => Ok(Self::X),
+            0x2e => Ok(Self::C),
+            0x2f => Ok(Self::V),
+            0x30 => Ok(Self::B),
+            0x31 => Ok(Self::N),
+            0x32 => Ok(Self::M),
+            0x33 => Ok(Self::Comma),
+            0x34 => Ok(Self::Dot),
+            0x35 => Ok(Self::Slash),
+            0x36 => Ok(Self::RightShift),
+            0x37 => Ok(Self::KpMultiply),
+            0x38 => Ok(Self::LeftAlt),
+            0x39 => Ok(Self::Space),
+            0x3a => Ok(Self::CapsLock),
+            0x3b => Ok(Self::F1),
+            0x3c => Ok(Self::F2),
+            0x3d => Ok(Self::F3),
+            0x3e => Ok(Self::F4),
+            0x3f => Ok(Self::F5),
+            0x40 => Ok(Self::F6),
+            0x41 => Ok(Self::F7),
+            0x42 => Ok(Self::F8),
+            0x43 => Ok(Self::F9),
+            0x44 => Ok(Self::F10),
+            0x45 => Ok(Self::NumLock),
+            0x46 => Ok(Self::ScrollLock),
+            0x47 => Ok(Self::Kp7),
+            0x48 => Ok(Self::Kp8),
+            0x49 => Ok(Self::Kp9),
+            0x4a => Ok(Self::KpMinus),
+            0x4b => Ok(Self::Kp4),
+            0x4c => Ok(Self::Kp5),
+            0x4d => Ok(Self::Kp6),
+            0x4e => Ok(Self::KpPlus),
+            0x4f => Ok(Self::Kp1),
+            0x50 => Ok(Self::Kp2),
+            0x51 => Ok(Self::Kp3),
+            0x52 => Ok(Self::Kp0),
+            0x53 => Ok(Self::KpDot),
+            0x56 => Ok(Self::NonUsBackslash),
+            0x57 => Ok(Self::F11),
+            0x58 => Ok(Self::F12),
+            0x9c => Ok(Self::KpEnter),
+            0x9d => Ok(Self::RightCtrl),
+            0xb5 => Ok(Self::KpSlash),
+            0xb7 => Ok(Self::PrintScreen),
+            0xb8 => Ok(Self::RightAlt),
+            0xc5 => Ok(Self::Pause),
+            0xc7 => Ok(Self::Home),
+            0xc8 => Ok(Self::Up),
+            0xc9 => Ok(Self::PageUp),
+            0xcb => Ok(Self::Left),
+            0xcd => Ok(Self::Right),
+            0xcf => Ok(Self::End),
+            0xd0 => Ok(Self::Down),
+            0xd1 => Ok(Self::PageDown),
+            0xd2 => Ok(Self::Insert),
+            0xd3 => Ok(Self::Delete),
+            0xdb => Ok(Self::LeftMeta),
+            0xdc => Ok(Self::RightMeta),
+            0xdd => Ok(Self::Menu),
+            _ => Err(())
+        }
+    }
+}
+
+/**
+ * Lists the mouse buttons
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+#[derive(Ord, PartialOrd)]
+#[derive(Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle
+}
+
+impl Into<u8> for MouseButton {
+    fn into(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for MouseButton {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Left),
+            1 => Ok(Self::Right),
+            2 => Ok(Self::Middle),
+            _ => Err(())
+        }
+    }
+}
@@ -5,7 +5,9 @@ pub mod device;
 pub mod dir;
 pub mod grants;
 pub mod info;
+pub mod input;
 pub mod modes;
+pub mod socket;
 pub mod types;
 pub mod uses;
 
@@ -2,7 +2,10 @@
 
 use core::convert::TryFrom;
 
-use crate::sys::TAsSysCallPtr;
+use crate::sys::{
+    TAsSysCallPtr,
+    TFromSysCallPtr
+};
 
 /**
  * Lists the available modes for `Object::recv()`
@@ -91,6 +94,7 @@ impl TryFrom<usize> for MMapPtrMode {
 /**
  * Lists the available modes for `[Device/File/Dir]::[set_]pos()`
  */
+#[repr(usize)]
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq, Eq)]
@@ -140,3 +144,10 @@ impl SeekMode /* Methods */ {
 impl TAsSysCallPtr for SeekMode {
     /* No methods to implement */
 }
+
+unsafe impl TFromSysCallPtr for SeekMode {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        /* the offsets accept any value, only the tag must be checked */
+        (raw_ptr as *const usize).read() <= Self::End.mode()
+    }
+}
@@ -0,0 +1,128 @@
+/*! `Socket` specific data structures */
+
+use core::{
+    convert::TryFrom,
+    fmt
+};
+
+use crate::sys::{
+    TAsSysCallPtr,
+    TFromSysCallPtr
+};
+
+/**
+ * Lists the transport protocols available for the `Socket`s
+ */
+#[repr(usize)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[derive(PartialOrd, Ord)]
+pub enum SocketType {
+    /**
+     * Connection-less `Socket` which exchanges datagrams with the User
+     * Datagram Protocol
+     */
+    Udp,
+
+    /**
+     * Connection oriented `Socket` which exchanges a reliable stream of
+     * bytes with the Transmission Control Protocol
+     */
+    Tcp
+}
+
+impl Into<usize> for SocketType {
+    fn into(self) -> usize {
+        self as usize
+    }
+}
+
+impl TryFrom<usize> for SocketType {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Udp),
+            1 => Ok(Self::Tcp),
+            _ => Err(())
+        }
+    }
+}
+
+impl fmt::Display for SocketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Udp => write!(f, "Udp"),
+            Self::Tcp => write!(f, "Tcp")
+        }
+    }
+}
+
+/**
+ * IPv4 address with port of a `Socket` end-point
+ */
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+#[derive(Ord, PartialOrd)]
+#[derive(Hash)]
+pub struct SocketAddr {
+    m_ip_addr: [u8; 4],
+    m_port: u16
+}
+
+impl SocketAddr /* Constructors */ {
+    /**
+     * Constructs a `SocketAddr` from the given parameters
+     */
+    pub const fn new(ip_addr: [u8; 4], port: u16) -> Self {
+        Self { m_ip_addr: ip_addr,
+               m_port: port }
+    }
+}
+
+impl SocketAddr /* Getters */ {
+    /**
+     * Returns the octets of the IPv4 address
+     */
+    pub fn ip_addr(&self) -> [u8; 4] {
+        self.m_ip_addr
+    }
+
+    /**
+     * Returns the port
+     */
+    pub fn port(&self) -> u16 {
+        self.m_port
+    }
+
+    /**
+     * Returns whether the IPv4 address is `0.0.0.0`, which means any local
+     * address
+     */
+    pub fn is_unspecified(&self) -> bool {
+        self.m_ip_addr == [0; 4]
+    }
+}
+
+impl TAsSysCallPtr for SocketAddr {
+    /* No methods to implement */
+}
+
+unsafe impl TFromSysCallPtr for SocketAddr {
+    /* No methods to implement */
+}
+
+impl fmt::Display for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,
+               "{}.{}.{}.{}:{}",
+               self.m_ip_addr[0],
+               self.m_ip_addr[1],
+               self.m_ip_addr[2],
+               self.m_ip_addr[3],
+               self.m_port)
+    }
+}
@@ -5,6 +5,8 @@ use core::{
     fmt
 };
 
+use crate::sys::TFromSysCallPtr;
+
 /**
  * Lists the available object types represented by a `Object`
  */
@@ -52,7 +54,12 @@ pub enum ObjType {
     /**
      * Identifies an `OsRawMutex` object
      */
-    OsRawMutex
+    OsRawMutex,
+
+    /**
+     * Identifies a `Socket` object
+     */
+    Socket
 }
 
 impl Default for ObjType {
@@ -80,11 +87,18 @@ impl TryFrom<usize> for ObjType {
             5 => Ok(Self::Link),
             6 => Ok(Self::MMap),
             7 => Ok(Self::OsRawMutex),
+            8 => Ok(Self::Socket),
             _ => Err(())
         }
     }
 }
 
+unsafe impl TFromSysCallPtr for ObjType {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        Self::try_from((raw_ptr as *const usize).read()).is_ok()
+    }
+}
+
 impl fmt::Display for ObjType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -95,7 +109,8 @@ impl fmt::Display for ObjType {
             Self::IpcChan => write!(f, "IpcChan"),
             Self::Link => write!(f, "Link"),
             Self::MMap => write!(f, "MMap"),
-            Self::OsRawMutex => write!(f, "OsRawMutex")
+            Self::OsRawMutex => write!(f, "OsRawMutex"),
+            Self::Socket => write!(f, "Socket")
         }
     }
 }
@@ -677,3 +677,48 @@ impl TryFrom<u16> for KernThreadFnId {
         }
     }
 }
+
+/**
+ * Lists the system call codes for the `Socket` struct
+ */
+#[repr(u16)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[derive(PartialOrd, Ord)]
+pub enum KernSocketFnId {
+    Open,
+    Bind,
+    Connect,
+    Listen,
+    Accept,
+    Send,
+    Recv,
+    SendTo,
+    RecvFrom
+}
+
+impl Into<u16> for KernSocketFnId {
+    fn into(self) -> u16 {
+        self as u16
+    }
+}
+
+impl TryFrom<u16> for KernSocketFnId {
+    type Error = ();
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Open),
+            1 => Ok(Self::Bind),
+            2 => Ok(Self::Connect),
+            3 => Ok(Self::Listen),
+            4 => Ok(Self::Accept),
+            5 => Ok(Self::Send),
+            6 => Ok(Self::Recv),
+            7 => Ok(Self::SendTo),
+            8 => Ok(Self::RecvFrom),
+            _ => Err(())
+        }
+    }
+}
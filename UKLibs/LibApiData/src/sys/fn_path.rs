@@ -1,28 +1,35 @@
 /*! Kernel function call paths */
 
-use core::fmt;
+use core::{
+    convert::TryFrom,
+    fmt
+};
 
-use crate::sys::codes::{
-    KernDeviceFnId,
-    KernDirFnId,
-    KernFileFnId,
-    KernHandleFnId,
-    KernInstantFnId,
-    KernIpcChanFnId,
-    KernLinkFnId,
-    KernMMapFnId,
-    KernMutexFnId,
-    KernObjConfigFnId,
-    KernObjectFnId,
-    KernOsEntConfigFnId,
-    KernOsEntFnId,
-    KernOsGroupFnId,
-    KernOsUserFnId,
-    KernPathFnId,
-    KernProcFnId,
-    KernTaskConfigFnId,
-    KernTaskFnId,
-    KernThreadFnId
+use crate::sys::{
+    codes::{
+        KernDeviceFnId,
+        KernDirFnId,
+        KernFileFnId,
+        KernHandleFnId,
+        KernInstantFnId,
+        KernIpcChanFnId,
+        KernLinkFnId,
+        KernMMapFnId,
+        KernMutexFnId,
+        KernObjConfigFnId,
+        KernObjectFnId,
+        KernOsEntConfigFnId,
+        KernOsEntFnId,
+        KernOsGroupFnId,
+        KernOsUserFnId,
+        KernPathFnId,
+        KernProcFnId,
+        KernSocketFnId,
+        KernTaskConfigFnId,
+        KernTaskFnId,
+        KernThreadFnId
+    },
+    TFromSysCallPtr
 };
 
 /**
@@ -33,6 +40,7 @@ use crate::sys::codes::{
  * codes for the call class, which is the secondary key of the Kernel's
  * routines table
  */
+#[repr(u16)]
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 pub enum KernFnPath {
@@ -56,9 +64,43 @@ pub enum KernFnPath {
     OsGroup(KernOsGroupFnId),
     Proc(KernProcFnId),
     Thread(KernThreadFnId),
+    Socket(KernSocketFnId),
     Invalid
 }
 
+impl KernFnPath /* Constructors */ {
+    /**
+     * Constructs the `KernFnPath` from the values returned by
+     * `KernFnPath::raw_fn_class()` and `KernFnPath::raw_fn_id()`
+     */
+    pub fn from_raw(raw_fn_class: u16, raw_fn_id: u16) -> Option<Self> {
+        match raw_fn_class {
+            0 => KernHandleFnId::try_from(raw_fn_id).map(Self::KernHandle),
+            1 => KernObjConfigFnId::try_from(raw_fn_id).map(Self::ObjConfig),
+            2 => KernTaskConfigFnId::try_from(raw_fn_id).map(Self::TaskConfig),
+            3 => KernOsEntConfigFnId::try_from(raw_fn_id).map(Self::OsEntConfig),
+            4 => KernObjectFnId::try_from(raw_fn_id).map(Self::Object),
+            5 => KernTaskFnId::try_from(raw_fn_id).map(Self::Task),
+            6 => KernDeviceFnId::try_from(raw_fn_id).map(Self::Device),
+            7 => KernDirFnId::try_from(raw_fn_id).map(Self::Dir),
+            8 => KernFileFnId::try_from(raw_fn_id).map(Self::File),
+            9 => KernIpcChanFnId::try_from(raw_fn_id).map(Self::IpcChan),
+            10 => KernLinkFnId::try_from(raw_fn_id).map(Self::Link),
+            11 => KernMMapFnId::try_from(raw_fn_id).map(Self::MMap),
+            12 => KernMutexFnId::try_from(raw_fn_id).map(Self::Mutex),
+            13 => KernInstantFnId::try_from(raw_fn_id).map(Self::Instant),
+            14 => KernPathFnId::try_from(raw_fn_id).map(Self::Path),
+            15 => KernOsEntFnId::try_from(raw_fn_id).map(Self::OsEntity),
+            16 => KernOsUserFnId::try_from(raw_fn_id).map(Self::OsUser),
+            17 => KernOsGroupFnId::try_from(raw_fn_id).map(Self::OsGroup),
+            18 => KernProcFnId::try_from(raw_fn_id).map(Self::Proc),
+            19 => KernThreadFnId::try_from(raw_fn_id).map(Self::Thread),
+            20 => KernSocketFnId::try_from(raw_fn_id).map(Self::Socket),
+            _ => Err(())
+        }.ok()
+    }
+}
+
 impl KernFnPath /* Methods */ {
     /**
      * Returns the current function class variant as `u16`
@@ -85,6 +127,7 @@ impl KernFnPath /* Methods */ {
             Self::OsGroup(_) => 17,
             Self::Proc(_) => 18,
             Self::Thread(_) => 19,
+            Self::Socket(_) => 20,
             _ => u16::MAX
         }
     }
@@ -114,6 +157,7 @@ impl KernFnPath /* Methods */ {
             Self::OsGroup(fn_id) => fn_id.into(),
             Self::Proc(fn_id) => fn_id.into(),
             Self::Thread(fn_id) => fn_id.into(),
+            Self::Socket(fn_id) => fn_id.into(),
             _ => u16::MAX
         }
     }
@@ -125,6 +169,20 @@ impl Default for KernFnPath {
     }
 }
 
+unsafe impl TFromSysCallPtr for KernFnPath {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        /* the tag is the function class, followed by the function id */
+        match (raw_ptr as *const u16).read() {
+            /* tag of KernFnPath::Invalid, which has no function id */
+            21 => true,
+            raw_fn_class => {
+                let raw_fn_id = (raw_ptr as *const u16).add(1).read();
+                Self::from_raw(raw_fn_class, raw_fn_id).is_some()
+            }
+        }
+    }
+}
+
 impl fmt::Display for KernFnPath {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -148,6 +206,7 @@ impl fmt::Display for KernFnPath {
             Self::OsGroup(fn_id) => write!(f, "KernFnPath::OsGroup({:?})", fn_id),
             Self::Proc(fn_id) => write!(f, "KernFnPath::Proc({:?})", fn_id),
             Self::Thread(fn_id) => write!(f, "KernFnPath::Thread({:?})", fn_id),
+            Self::Socket(fn_id) => write!(f, "KernFnPath::Socket({:?})", fn_id),
             Self::Invalid => write!(f, "KernFnPath::Invalid")
         }
     }
@@ -1,5 +1,13 @@
 /*! System call codes and classes */
 
+use core::{
+    mem::align_of,
+    ptr::{
+        addr_of,
+        NonNull
+    }
+};
+
 use crate::{
     error::OsError,
     limit::SYSCALL_ARGS_COUNT_MAX,
@@ -23,9 +31,10 @@ pub type RawKernHandle = u32;
  * Fixed collector of system call arguments
  */
 #[derive(Debug)]
+#[derive(Copy, Clone)]
 pub struct SysCallPayload {
     m_kern_fn_path: KernFnPath,
-    m_raw_handle: Option<RawKernHandle>, /* TODO tables with inst_required = <> */
+    m_raw_handle: RawOption<RawKernHandle>, /* TODO tables with inst_required = <> */
     m_raw_args: [usize; SYSCALL_ARGS_COUNT_MAX],
     m_error_modified: bool,
     m_error: OsError,
@@ -47,7 +56,7 @@ impl SysCallPayload /* Constructors */ {
                arg5: usize)
                -> Self {
         Self { m_kern_fn_path: kern_fn_path,
-               m_raw_handle: raw_handle,
+               m_raw_handle: raw_handle.into(),
                m_raw_args: [arg0, arg1, arg2, arg3, arg4, arg5],
                m_error_modified: false,
                m_error: OsError::default(),
@@ -69,7 +78,7 @@ impl SysCallPayload /* Getters */ {
      */
     #[inline]
     pub fn raw_handle(&self) -> Option<RawKernHandle> {
-        self.m_raw_handle
+        self.m_raw_handle.into()
     }
 
     /**
@@ -127,10 +136,29 @@ impl SysCallPayload /* Getters */ {
     }
 }
 
+impl SysCallPayload /* Setters */ {
+    /**
+     * Sets the value returned by the kernel routine
+     */
+    #[inline]
+    pub fn set_result(&mut self, result: usize) {
+        self.m_result = result;
+    }
+}
+
 impl TAsSysCallPtr for SysCallPayload {
     /* No methods to implement */
 }
 
+unsafe impl TFromSysCallPtr for SysCallPayload {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        KernFnPath::is_valid_raw(addr_of!((*raw_ptr).m_kern_fn_path))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_raw_handle))
+        && bool::is_valid_raw(addr_of!((*raw_ptr).m_error_modified))
+        && OsError::is_valid_raw(addr_of!((*raw_ptr).m_error))
+    }
+}
+
 impl Into<Result<usize, OsError>> for SysCallPayload {
     #[inline]
     fn into(self) -> Result<usize, OsError> {
@@ -163,3 +191,120 @@ pub trait TAsSysCallPtr {
         self as *mut Self as *mut u8 as usize
     }
 }
+
+/**
+ * `Option` with a well-defined layout, used by the fields of the structures
+ * which the Kernel copies from the syscall pointers
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub enum RawOption<T> {
+    None,
+    Some(T)
+}
+
+impl<T> RawOption<T> /* Getters */ {
+    /**
+     * Returns the reference to the contained value if any
+     */
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            Self::None => None,
+            Self::Some(value) => Some(value)
+        }
+    }
+}
+
+impl<T> Default for RawOption<T> {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl<T> From<Option<T>> for RawOption<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Some(value),
+            None => Self::None
+        }
+    }
+}
+
+impl<T> From<RawOption<T>> for Option<T> {
+    fn from(raw_option: RawOption<T>) -> Self {
+        match raw_option {
+            RawOption::Some(value) => Some(value),
+            RawOption::None => None
+        }
+    }
+}
+
+unsafe impl<T> TFromSysCallPtr for RawOption<T> where T: TFromSysCallPtr {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        /* the tag is followed by the value at his alignment */
+        match (raw_ptr as *const u8).read() {
+            0 => true,
+            1 => T::is_valid_raw((raw_ptr as *const u8).add(align_of::<T>()) as *const T),
+            _ => false
+        }
+    }
+}
+
+/**
+ * Interface for the types which the Kernel copies from the syscall
+ * pointers.
+ *
+ * The user could write any byte into the pointed memory, so the Kernel
+ * copies the raw bytes and checks them before reading the copy as `Self`.
+ *
+ * The default implementation suits the types without enum fields
+ *
+ * # Safety
+ * The implementation must check the discriminant of each enum field, and
+ * the value of each `bool` field, of `Self`
+ */
+pub unsafe trait TFromSysCallPtr: Copy {
+    /**
+     * Returns whether the `size_of::<Self>()` bytes at `raw_ptr` are a
+     * valid `Self`
+     *
+     * # Safety
+     * `raw_ptr` must point to `size_of::<Self>()` readable bytes aligned
+     * for `Self`
+     */
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        let _ = raw_ptr;
+        true
+    }
+}
+
+unsafe impl TFromSysCallPtr for bool {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        (raw_ptr as *const u8).read() <= 1
+    }
+}
+
+unsafe impl TFromSysCallPtr for u32 {
+    /* No methods to implement */
+}
+
+unsafe impl TFromSysCallPtr for u64 {
+    /* No methods to implement */
+}
+
+unsafe impl TFromSysCallPtr for usize {
+    /* No methods to implement */
+}
+
+unsafe impl TFromSysCallPtr for (u64, u64) {
+    /* No methods to implement */
+}
+
+unsafe impl<const N: usize> TFromSysCallPtr for [u8; N] {
+    /* No methods to implement */
+}
+
+unsafe impl<T> TFromSysCallPtr for Option<NonNull<T>> {
+    /* No methods to implement */
+}
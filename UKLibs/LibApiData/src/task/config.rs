@@ -2,7 +2,8 @@
 
 use core::{
     convert::TryFrom,
-    ptr
+    ptr,
+    ptr::addr_of
 };
 
 use bits::bit_flags::{
@@ -13,7 +14,11 @@ use bits::bit_flags::{
 use crate::{
     entity::RawOsEntityHandle,
     object::RawObjHandle,
-    sys::TAsSysCallPtr,
+    sys::{
+        RawOption,
+        TAsSysCallPtr,
+        TFromSysCallPtr
+    },
     task::{
         modes::TaskExecCpu,
         thread::{
@@ -37,16 +42,17 @@ pub type TaskConfigFlags = BitFlags<usize, TaskConfigBits>;
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 pub struct RawTaskConfig<'a> {
-    m_id: Option<TaskId>,
+    m_id: RawOption<TaskId>,
     m_task_type: TaskType,
 
     /* task execution related fields */
     m_flags: TaskConfigFlags,
     m_exec_cpu: TaskExecCpu,
+    m_rt_params: RawOption<(u64, u64)>,
 
     /* owner related fields */
-    m_os_user: Option<RawOsEntityHandle>,
-    m_os_group: Option<RawOsEntityHandle>,
+    m_os_user: RawOption<RawOsEntityHandle>,
+    m_os_group: RawOption<RawOsEntityHandle>,
 
     /* process specific parameters */
     m_file_to_exec: RawObjHandle,
@@ -70,12 +76,13 @@ impl<'a> RawTaskConfig<'a> /* Constructors */ {
             TaskConfigFlags::new_zero()
         };
 
-        Self { m_id: None,
+        Self { m_id: RawOption::None,
                m_task_type: task_type,
                m_flags: config_flags,
                m_exec_cpu: TaskExecCpu::Any,
-               m_os_user: None,
-               m_os_group: None,
+               m_rt_params: RawOption::None,
+               m_os_user: RawOption::None,
+               m_os_group: RawOption::None,
                m_file_to_exec: 0,
                m_cmdline_args: None,
                m_c_thread_entry: None,
@@ -90,7 +97,7 @@ impl<'a> RawTaskConfig<'a> /* Getters */ {
      * Returns the preferred `RawTaskId`
      */
     pub fn id(&self) -> Option<TaskId> {
-        self.m_id
+        self.m_id.into()
     }
 
     /**
@@ -121,18 +128,26 @@ impl<'a> RawTaskConfig<'a> /* Getters */ {
         self.m_exec_cpu
     }
 
+    /**
+     * Returns the period and the budget, in scheduler ticks, of the
+     * real-time reservation
+     */
+    pub fn rt_params(&self) -> Option<(u64, u64)> {
+        self.m_rt_params.into()
+    }
+
     /**
      * Returns the owner user's `RawOsEntityHandle`
      */
     pub fn os_user(&self) -> Option<RawOsEntityHandle> {
-        self.m_os_user
+        self.m_os_user.into()
     }
 
     /**
      * Returns the owner group's `RawOsEntityHandle`
      */
     pub fn os_group(&self) -> Option<RawOsEntityHandle> {
-        self.m_os_group
+        self.m_os_group.into()
     }
 
     /**
@@ -184,7 +199,7 @@ impl<'a> RawTaskConfig<'a> /* Setters */ {
      * Sets the preferred `RawTaskId`
      */
     pub fn set_id(&mut self, id: TaskId) {
-        self.m_id = Some(id);
+        self.m_id = RawOption::Some(id);
     }
 
     /**
@@ -201,18 +216,26 @@ impl<'a> RawTaskConfig<'a> /* Setters */ {
         self.m_exec_cpu = exec_cpu;
     }
 
+    /**
+     * Sets the period and the budget, in scheduler ticks, of the real-time
+     * reservation
+     */
+    pub fn set_rt_params(&mut self, period: u64, budget: u64) {
+        self.m_rt_params = RawOption::Some((period, budget));
+    }
+
     /**
      * Sets the owner user's `RawOsEntityHandle`
      */
     pub fn set_os_user(&mut self, os_user: RawOsEntityHandle) {
-        self.m_os_user = Some(os_user);
+        self.m_os_user = RawOption::Some(os_user);
     }
 
     /**
      * Sets the owner group's `RawOsEntityHandle`
      */
     pub fn set_os_group(&mut self, os_group: RawOsEntityHandle) {
-        self.m_os_group = Some(os_group);
+        self.m_os_group = RawOption::Some(os_group);
     }
 
     /**
@@ -262,6 +285,17 @@ impl<'a> TAsSysCallPtr for RawTaskConfig<'a> {
     /* No methods to implement */
 }
 
+unsafe impl<'a> TFromSysCallPtr for RawTaskConfig<'a> {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        RawOption::is_valid_raw(addr_of!((*raw_ptr).m_id))
+        && TaskType::is_valid_raw(addr_of!((*raw_ptr).m_task_type))
+        && TaskExecCpu::is_valid_raw(addr_of!((*raw_ptr).m_exec_cpu))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_rt_params))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_os_user))
+        && RawOption::is_valid_raw(addr_of!((*raw_ptr).m_os_group))
+    }
+}
+
 /**
  * Lists the internal `RawOsEntityConfig` flags
  */
@@ -2,6 +2,8 @@
 
 use core::convert::TryFrom;
 
+use crate::sys::TFromSysCallPtr;
+
 /**
  * Lists the available options for `TaskConfig::with_exec_cpu()`.
  *
@@ -9,6 +11,7 @@ use core::convert::TryFrom;
  * restricted set of CPUs in an SMP environment or can be executed
  * on any of the available CPUs
  */
+#[repr(usize)]
 #[derive(Debug)]
 #[derive(Clone, Copy)]
 #[derive(PartialEq, Eq)]
@@ -62,6 +65,13 @@ impl TaskExecCpu /* Methods */ {
     }
 }
 
+unsafe impl TFromSysCallPtr for TaskExecCpu {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        /* the mask accepts any value, only the tag must be checked */
+        (raw_ptr as *const usize).read() <= TaskExecCpu::Mask(0).option()
+    }
+}
+
 /**
  * Lists the available `Proc::mount()` modes
  */
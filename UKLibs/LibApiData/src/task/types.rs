@@ -5,6 +5,8 @@ use core::{
     fmt
 };
 
+use crate::sys::TFromSysCallPtr;
+
 /**
  * Lists the available object types represented by a `Task`
  */
@@ -55,6 +57,12 @@ impl TryFrom<usize> for TaskType {
     }
 }
 
+unsafe impl TFromSysCallPtr for TaskType {
+    unsafe fn is_valid_raw(raw_ptr: *const Self) -> bool {
+        Self::try_from((raw_ptr as *const usize).read()).is_ok()
+    }
+}
+
 impl fmt::Display for TaskType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
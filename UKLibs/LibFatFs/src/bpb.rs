@@ -0,0 +1,499 @@
+/*! FAT boot sector structures
+ *
+ * ```text
+ * ---------------------------------------------------------------------
+ * | Reserved Sectors ... | FATs ... | Root Dir (FAT12/16) | Clusters ... |
+ * ---------------------------------------------------------------------
+ * ```
+ *
+ * The first reserved sector is the `BootSector`, which describes the
+ * geometry of the filesystem, on FAT32 the reserved sectors contain the
+ * `FsInfo` sector too. The FAT12/16 root directory has a fixed size,
+ * while the FAT32 one is a cluster chain like the other directories.
+ *
+ * All the numbers are stored in little endian
+ */
+
+use alloc::string::String;
+use core::convert::TryInto;
+
+use api_data::error::class::OsErrorClass;
+
+use crate::FatFsResult;
+
+/**
+ * Lists the FAT variants, selected by the amount of clusters
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32
+}
+
+impl FatType /* Constants */ {
+    /**
+     * Maximum amount of clusters of a FAT12
+     */
+    pub const FAT12_CLUSTERS_MAX: u32 = 4084;
+
+    /**
+     * Maximum amount of clusters of a FAT16
+     */
+    pub const FAT16_CLUSTERS_MAX: u32 = 65524;
+}
+
+impl FatType /* Getters */ {
+    /**
+     * Returns the value stored into the FAT for the last cluster of a
+     * chain
+     */
+    pub fn end_of_chain(&self) -> u32 {
+        match self {
+            Self::Fat12 => 0xFFF,
+            Self::Fat16 => 0xFFFF,
+            Self::Fat32 => 0x0FFF_FFFF
+        }
+    }
+
+    /**
+     * Returns whether the given FAT entry value terminates a chain
+     */
+    pub fn is_end_of_chain(&self, fat_entry: u32) -> bool {
+        match self {
+            Self::Fat12 => fat_entry >= 0xFF8,
+            Self::Fat16 => fat_entry >= 0xFFF8,
+            Self::Fat32 => fat_entry >= 0x0FFF_FFF8
+        }
+    }
+
+    /**
+     * Returns whether the given FAT entry value marks a bad cluster
+     */
+    pub fn is_bad_cluster(&self, fat_entry: u32) -> bool {
+        match self {
+            Self::Fat12 => fat_entry == 0xFF7,
+            Self::Fat16 => fat_entry == 0xFFF7,
+            Self::Fat32 => fat_entry == 0x0FFF_FFF7
+        }
+    }
+}
+
+/**
+ * BIOS Parameter Block and the extended boot record of the first sector
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BootSector {
+    m_fat_type: FatType,
+    m_bytes_per_sector: u16,
+    m_sectors_per_cluster: u8,
+    m_reserved_sectors: u16,
+    m_fats_count: u8,
+    m_root_entries: u16,
+    m_total_sectors: u32,
+    m_fat_sectors: u32,
+    m_ext_flags: u16,
+    m_root_cluster: u32,
+    m_fs_info_sector: u16,
+    m_clusters_count: u32,
+    m_volume_id: u32,
+    m_label: [u8; 11]
+}
+
+impl BootSector /* Constants */ {
+    /**
+     * Minimum size in bytes of the decoded sector
+     */
+    pub const ENCODED_SIZE: usize = 512;
+
+    /**
+     * Signature stored at the end of the boot sector
+     */
+    const SIGNATURE: [u8; 2] = [0x55, 0xAA];
+
+    /**
+     * Value of the extended boot signature when the serial and the label
+     * are present
+     */
+    const EXT_BOOT_SIGNATURE: u8 = 0x29;
+
+    /**
+     * Bit of the FAT32 `ext_flags` which disables the FATs mirroring
+     */
+    const NO_MIRRORING_BIT: u16 = 1 << 7;
+}
+
+impl BootSector /* Constructors */ {
+    /**
+     * Decodes and validates the `BootSector` from the given buffer
+     */
+    pub fn decode(raw_sector: &[u8]) -> FatFsResult<Self> {
+        if raw_sector.len() < Self::ENCODED_SIZE
+           || raw_sector[510..512] != Self::SIGNATURE
+        {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a FAT boot sector")));
+        }
+
+        let bytes_per_sector = read_u16(raw_sector, 11);
+        let sectors_per_cluster = raw_sector[13];
+        let reserved_sectors = read_u16(raw_sector, 14);
+        let fats_count = raw_sector[16];
+        let root_entries = read_u16(raw_sector, 17);
+        if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+           || !sectors_per_cluster.is_power_of_two()
+           || reserved_sectors == 0
+           || fats_count == 0
+        {
+            return Err((OsErrorClass::TypesNotMatch, Some("Invalid FAT geometry")));
+        }
+
+        let total_sectors = match read_u16(raw_sector, 19) {
+            0 => read_u32(raw_sector, 32),
+            total_sectors16 => total_sectors16 as u32
+        };
+        let fat_sectors = match read_u16(raw_sector, 22) {
+            0 => read_u32(raw_sector, 36),
+            fat_sectors16 => fat_sectors16 as u32
+        };
+
+        /* the FAT type depends only on the amount of clusters */
+        let root_dir_sectors =
+            (root_entries as u32 * 32).div_ceil(bytes_per_sector as u32);
+        let data_first_sector = reserved_sectors as u64
+                                + fats_count as u64 * fat_sectors as u64
+                                + root_dir_sectors as u64;
+        if fat_sectors == 0 || data_first_sector >= total_sectors as u64 {
+            return Err((OsErrorClass::TypesNotMatch, Some("Invalid FAT geometry")));
+        }
+        let clusters_count = ((total_sectors as u64 - data_first_sector)
+                              / sectors_per_cluster as u64)
+                             as u32;
+        let fat_type = if clusters_count <= FatType::FAT12_CLUSTERS_MAX {
+            FatType::Fat12
+        } else if clusters_count <= FatType::FAT16_CLUSTERS_MAX {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        };
+
+        /* the extended boot record differs between FAT12/16 and FAT32 */
+        let (ext_flags, root_cluster, fs_info_sector, ext_record_offset) =
+            if fat_type == FatType::Fat32 {
+                if root_entries != 0 || read_u16(raw_sector, 42) != 0 {
+                    return Err((OsErrorClass::TypesNotMatch,
+                                Some("Unsupported FAT32 version")));
+                }
+                (read_u16(raw_sector, 40),
+                 read_u32(raw_sector, 44),
+                 read_u16(raw_sector, 48),
+                 64)
+            } else if root_entries == 0 {
+                return Err((OsErrorClass::TypesNotMatch,
+                            Some("FAT12/16 without root directory")));
+            } else {
+                (0, 0, 0, 36)
+            };
+
+        /* the FATs must describe all the clusters */
+        let fat_entries = match fat_type {
+            FatType::Fat12 => fat_sectors as u64 * bytes_per_sector as u64 * 2 / 3,
+            FatType::Fat16 => fat_sectors as u64 * bytes_per_sector as u64 / 2,
+            FatType::Fat32 => fat_sectors as u64 * bytes_per_sector as u64 / 4
+        };
+        if fat_entries < clusters_count as u64 + 2 {
+            return Err((OsErrorClass::TypesNotMatch, Some("FAT too small")));
+        }
+
+        let mut volume_id = 0;
+        let mut label = [b' '; 11];
+        if raw_sector[ext_record_offset + 2] == Self::EXT_BOOT_SIGNATURE {
+            volume_id = read_u32(raw_sector, ext_record_offset + 3);
+            label.copy_from_slice(&raw_sector
+                                      [ext_record_offset + 7..ext_record_offset + 18]);
+        }
+
+        Ok(Self { m_fat_type: fat_type,
+                  m_bytes_per_sector: bytes_per_sector,
+                  m_sectors_per_cluster: sectors_per_cluster,
+                  m_reserved_sectors: reserved_sectors,
+                  m_fats_count: fats_count,
+                  m_root_entries: root_entries,
+                  m_total_sectors: total_sectors,
+                  m_fat_sectors: fat_sectors,
+                  m_ext_flags: ext_flags,
+                  m_root_cluster: root_cluster,
+                  m_fs_info_sector: fs_info_sector,
+                  m_clusters_count: clusters_count,
+                  m_volume_id: volume_id,
+                  m_label: label })
+    }
+}
+
+impl BootSector /* Methods */ {
+    /**
+     * Returns the first sector of the given data cluster
+     */
+    pub fn cluster_first_sector(&self, cluster: u32) -> u64 {
+        self.data_first_sector()
+        + (cluster as u64 - 2) * self.m_sectors_per_cluster as u64
+    }
+
+    /**
+     * Returns whether the given cluster is a valid data cluster
+     */
+    pub fn is_data_cluster(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster < self.m_clusters_count + 2
+    }
+}
+
+impl BootSector /* Getters */ {
+    /**
+     * Returns the `FatType` of the filesystem
+     */
+    pub fn fat_type(&self) -> FatType {
+        self.m_fat_type
+    }
+
+    /**
+     * Returns the size in bytes of each sector
+     */
+    pub fn bytes_per_sector(&self) -> usize {
+        self.m_bytes_per_sector as usize
+    }
+
+    /**
+     * Returns the amount of sectors of each cluster
+     */
+    pub fn sectors_per_cluster(&self) -> u64 {
+        self.m_sectors_per_cluster as u64
+    }
+
+    /**
+     * Returns the size in bytes of each cluster
+     */
+    pub fn cluster_size(&self) -> usize {
+        self.bytes_per_sector() * self.m_sectors_per_cluster as usize
+    }
+
+    /**
+     * Returns the amount of sectors of the filesystem
+     */
+    pub fn total_sectors(&self) -> u64 {
+        self.m_total_sectors as u64
+    }
+
+    /**
+     * Returns the first sector of the first FAT
+     */
+    pub fn fat_first_sector(&self) -> u64 {
+        self.m_reserved_sectors as u64
+    }
+
+    /**
+     * Returns the amount of sectors of each FAT
+     */
+    pub fn fat_sectors(&self) -> u64 {
+        self.m_fat_sectors as u64
+    }
+
+    /**
+     * Returns the indexes of the FATs which must be updated, more than one
+     * when the mirroring is enabled
+     */
+    pub fn active_fats(&self) -> core::ops::Range<u8> {
+        if self.m_fat_type == FatType::Fat32
+           && self.m_ext_flags & Self::NO_MIRRORING_BIT != 0
+        {
+            let active_fat = (self.m_ext_flags & 0xF) as u8;
+            active_fat..active_fat + 1
+        } else {
+            0..self.m_fats_count
+        }
+    }
+
+    /**
+     * Returns the first sector of the FAT12/16 root directory
+     */
+    pub fn root_dir_first_sector(&self) -> u64 {
+        self.fat_first_sector() + self.m_fats_count as u64 * self.fat_sectors()
+    }
+
+    /**
+     * Returns the amount of entries of the FAT12/16 root directory
+     */
+    pub fn root_entries(&self) -> usize {
+        self.m_root_entries as usize
+    }
+
+    /**
+     * Returns the first sector of the data clusters
+     */
+    pub fn data_first_sector(&self) -> u64 {
+        let root_dir_sectors = (self.root_entries() * 32 + self.bytes_per_sector() - 1)
+                               / self.bytes_per_sector();
+        self.root_dir_first_sector() + root_dir_sectors as u64
+    }
+
+    /**
+     * Returns the amount of data clusters
+     */
+    pub fn clusters_count(&self) -> u32 {
+        self.m_clusters_count
+    }
+
+    /**
+     * Returns the first cluster of the FAT32 root directory
+     */
+    pub fn root_cluster(&self) -> u32 {
+        self.m_root_cluster
+    }
+
+    /**
+     * Returns the sector of the FAT32 `FsInfo`, if any
+     */
+    pub fn fs_info_sector(&self) -> Option<u64> {
+        if self.m_fat_type == FatType::Fat32
+           && self.m_fs_info_sector != 0
+           && self.m_fs_info_sector != 0xFFFF
+           && self.m_fs_info_sector < self.m_reserved_sectors
+        {
+            Some(self.m_fs_info_sector as u64)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Returns the serial number of the volume
+     */
+    pub fn volume_id(&self) -> u32 {
+        self.m_volume_id
+    }
+
+    /**
+     * Returns the label of the volume stored into the boot sector
+     */
+    pub fn label(&self) -> String {
+        self.m_label
+            .iter()
+            .map(|byte| *byte as char)
+            .collect::<String>()
+            .trim_end()
+            .into()
+    }
+}
+
+/**
+ * FAT32 free clusters hints sector.
+ *
+ * The values are only hints, `0xFFFFFFFF` means unknown
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct FsInfo {
+    m_free_count: u32,
+    m_next_free: u32
+}
+
+impl FsInfo /* Constants */ {
+    /**
+     * Value of the hints when they are unknown
+     */
+    pub const UNKNOWN: u32 = 0xFFFF_FFFF;
+
+    const LEAD_SIGNATURE: u32 = 0x4161_5252;
+    const STRUCT_SIGNATURE: u32 = 0x6141_7272;
+    const TRAIL_SIGNATURE: u32 = 0xAA55_0000;
+}
+
+impl FsInfo /* Constructors */ {
+    /**
+     * Decodes the `FsInfo` from the given sector, `None` when the
+     * signatures are not valid
+     */
+    pub fn decode(raw_sector: &[u8]) -> Option<Self> {
+        if read_u32(raw_sector, 0) != Self::LEAD_SIGNATURE
+           || read_u32(raw_sector, 484) != Self::STRUCT_SIGNATURE
+           || read_u32(raw_sector, 508) != Self::TRAIL_SIGNATURE
+        {
+            None
+        } else {
+            Some(Self { m_free_count: read_u32(raw_sector, 488),
+                        m_next_free: read_u32(raw_sector, 492) })
+        }
+    }
+}
+
+impl FsInfo /* Methods */ {
+    /**
+     * Updates the hints into the given sector, the remaining content is
+     * preserved
+     */
+    pub fn encode(&self, raw_sector: &mut [u8]) {
+        write_u32(raw_sector, 488, self.m_free_count);
+        write_u32(raw_sector, 492, self.m_next_free);
+    }
+}
+
+impl FsInfo /* Getters */ {
+    /**
+     * Returns the amount of free clusters, if known
+     */
+    pub fn free_count(&self) -> Option<u32> {
+        Some(self.m_free_count).filter(|free_count| *free_count != Self::UNKNOWN)
+    }
+
+    /**
+     * Returns the cluster from which search the next free one, if known
+     */
+    pub fn next_free(&self) -> Option<u32> {
+        Some(self.m_next_free).filter(|next_free| *next_free != Self::UNKNOWN)
+    }
+}
+
+impl FsInfo /* Setters */ {
+    /**
+     * Sets the amount of free clusters
+     */
+    pub fn set_free_count(&mut self, free_count: Option<u32>) {
+        self.m_free_count = free_count.unwrap_or(Self::UNKNOWN);
+    }
+
+    /**
+     * Sets the cluster from which search the next free one
+     */
+    pub fn set_next_free(&mut self, next_free: Option<u32>) {
+        self.m_next_free = next_free.unwrap_or(Self::UNKNOWN);
+    }
+}
+
+/**
+ * Reads a little endian `u16` at the given offset
+ */
+pub(crate) fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buffer[offset..offset + 2].try_into().unwrap())
+}
+
+/**
+ * Reads a little endian `u32` at the given offset
+ */
+pub(crate) fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+}
+
+/**
+ * Writes a little endian `u16` at the given offset
+ */
+pub(crate) fn write_u16(buffer: &mut [u8], offset: usize, value: u16) {
+    buffer[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/**
+ * Writes a little endian `u32` at the given offset
+ */
+pub(crate) fn write_u32(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,637 @@
+/*! FAT directory entries
+ *
+ * Each directory is an array of 32 bytes entries. The name of each node
+ * is stored into a `ShortDirEntry` as upper case 8.3 name, the VFAT long
+ * name, when needed, is stored in UTF-16 into the `LongDirEntry`s which
+ * precede it in reverse order
+ */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+use core::convert::TryFrom;
+
+use api_data::error::class::OsErrorClass;
+use bits::bit_flags::{
+    BitFlags,
+    TBitFlagsValues
+};
+
+use crate::{
+    bpb::{
+        read_u16,
+        read_u32,
+        write_u16,
+        write_u32
+    },
+    time::FatTimestamp,
+    FatFsResult
+};
+
+/**
+ * Attributes of a `ShortDirEntry`
+ */
+pub type FatAttrs = BitFlags<u8, FatAttrBits>;
+
+/**
+ * Lists the valid `FatAttrs` bits
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum FatAttrBits {
+    ReadOnly,
+    Hidden,
+    System,
+    VolumeId,
+    Directory,
+    Archive
+}
+
+impl From<FatAttrBits> for usize {
+    fn from(bits: FatAttrBits) -> Self {
+        bits as usize
+    }
+}
+
+impl TryFrom<usize> for FatAttrBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::ReadOnly),
+            1 => Ok(Self::Hidden),
+            2 => Ok(Self::System),
+            3 => Ok(Self::VolumeId),
+            4 => Ok(Self::Directory),
+            5 => Ok(Self::Archive),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for FatAttrBits {
+}
+
+/**
+ * 8.3 entry of a directory, stores the metadata of the node
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ShortDirEntry {
+    m_short_name: [u8; 11],
+    m_attrs: FatAttrs,
+    m_case_flags: u8,
+    m_creat: FatTimestamp,
+    m_access_date: u16,
+    m_modify: FatTimestamp,
+    m_first_cluster: u32,
+    m_file_size: u32
+}
+
+impl ShortDirEntry /* Constants */ {
+    /**
+     * Size in bytes of each encoded directory entry
+     */
+    pub const ENCODED_SIZE: usize = 32;
+
+    /**
+     * First name byte of the free entries
+     */
+    pub const FREE_MARK: u8 = 0xE5;
+
+    /**
+     * First name byte of the entry which ends the directory, the
+     * following ones are free too
+     */
+    pub const END_MARK: u8 = 0x00;
+
+    /**
+     * Short name of the self link of the sub-directories
+     */
+    pub const SELF_LINK_NAME: [u8; 11] = *b".          ";
+
+    /**
+     * Short name of the parent link of the sub-directories
+     */
+    pub const PARENT_LINK_NAME: [u8; 11] = *b"..         ";
+
+    /**
+     * First name byte which stands for the `FREE_MARK` value as first
+     * character
+     */
+    const KANJI_LEAD_MARK: u8 = 0x05;
+
+    /**
+     * Case flag of the lower case base name
+     */
+    const LOWER_BASE_FLAG: u8 = 0x08;
+
+    /**
+     * Case flag of the lower case extension
+     */
+    const LOWER_EXT_FLAG: u8 = 0x10;
+}
+
+impl ShortDirEntry /* Constructors */ {
+    /**
+     * Constructs a `ShortDirEntry` created at the given `FatTimestamp`
+     */
+    pub fn new(short_name: [u8; 11],
+               case_flags: u8,
+               attrs: FatAttrs,
+               first_cluster: u32,
+               now: FatTimestamp)
+               -> Self {
+        Self { m_short_name: short_name,
+               m_attrs: attrs,
+               m_case_flags: case_flags,
+               m_creat: now,
+               m_access_date: now.date(),
+               m_modify: FatTimestamp::new(now.date(), now.time(), 0),
+               m_first_cluster: first_cluster,
+               m_file_size: 0 }
+    }
+
+    /**
+     * Decodes the `ShortDirEntry` from the given buffer
+     */
+    pub fn decode(raw_entry: &[u8]) -> Self {
+        let mut short_name = [0; 11];
+        short_name.copy_from_slice(&raw_entry[0..11]);
+
+        Self { m_short_name: short_name,
+               m_attrs: FatAttrs::from_raw_truncate(raw_entry[11]),
+               m_case_flags: raw_entry[12],
+               m_creat: FatTimestamp::new(read_u16(raw_entry, 16),
+                                          read_u16(raw_entry, 14),
+                                          raw_entry[13]),
+               m_access_date: read_u16(raw_entry, 18),
+               m_modify: FatTimestamp::new(read_u16(raw_entry, 24),
+                                           read_u16(raw_entry, 22),
+                                           0),
+               m_first_cluster: (read_u16(raw_entry, 20) as u32) << 16
+                                | read_u16(raw_entry, 26) as u32,
+               m_file_size: read_u32(raw_entry, 28) }
+    }
+}
+
+impl ShortDirEntry /* Methods */ {
+    /**
+     * Encodes this `ShortDirEntry` into the given buffer
+     */
+    pub fn encode(&self, raw_entry: &mut [u8]) {
+        raw_entry[..Self::ENCODED_SIZE].fill(0);
+
+        raw_entry[0..11].copy_from_slice(&self.m_short_name);
+        raw_entry[11] = self.m_attrs.raw_bits();
+        raw_entry[12] = self.m_case_flags;
+        raw_entry[13] = self.m_creat.centis();
+        write_u16(raw_entry, 14, self.m_creat.time());
+        write_u16(raw_entry, 16, self.m_creat.date());
+        write_u16(raw_entry, 18, self.m_access_date);
+        write_u16(raw_entry, 20, (self.m_first_cluster >> 16) as u16);
+        write_u16(raw_entry, 22, self.m_modify.time());
+        write_u16(raw_entry, 24, self.m_modify.date());
+        write_u16(raw_entry, 26, self.m_first_cluster as u16);
+        write_u32(raw_entry, 28, self.m_file_size);
+    }
+
+    /**
+     * Returns the 8.3 name in the `NAME.EXT` form, with the case given
+     * by the case flags
+     */
+    pub fn display_name(&self) -> String {
+        let mut short_name = self.m_short_name;
+        if short_name[0] == Self::KANJI_LEAD_MARK {
+            short_name[0] = Self::FREE_MARK;
+        }
+
+        let to_char = |byte: &u8, lower_case: bool| {
+            if lower_case {
+                byte.to_ascii_lowercase() as char
+            } else {
+                *byte as char
+            }
+        };
+
+        let mut display_name =
+            short_name[0..8].iter()
+                            .map(|byte| {
+                                to_char(byte,
+                                        self.m_case_flags & Self::LOWER_BASE_FLAG != 0)
+                            })
+                            .collect::<String>();
+        display_name.truncate(display_name.trim_end_matches(' ').len());
+
+        let ext = short_name[8..11].iter()
+                                   .map(|byte| {
+                                       to_char(byte,
+                                               self.m_case_flags & Self::LOWER_EXT_FLAG
+                                               != 0)
+                                   })
+                                   .collect::<String>();
+        let ext = ext.trim_end_matches(' ');
+        if !ext.is_empty() {
+            display_name.push('.');
+            display_name.push_str(ext);
+        }
+        display_name
+    }
+
+    /**
+     * Returns the checksum of the short name stored into the
+     * `LongDirEntry`s
+     */
+    pub fn checksum(&self) -> u8 {
+        short_name_checksum(&self.m_short_name)
+    }
+
+    /**
+     * Returns whether this is the `.` or the `..` entry
+     */
+    pub fn is_dot_entry(&self) -> bool {
+        self.m_short_name == Self::SELF_LINK_NAME
+        || self.m_short_name == Self::PARENT_LINK_NAME
+    }
+}
+
+impl ShortDirEntry /* Getters */ {
+    /**
+     * Returns the raw 8.3 name
+     */
+    pub fn short_name(&self) -> &[u8; 11] {
+        &self.m_short_name
+    }
+
+    /**
+     * Returns the `FatAttrs`
+     */
+    pub fn attrs(&self) -> FatAttrs {
+        self.m_attrs
+    }
+
+    /**
+     * Returns whether the entry describes a directory
+     */
+    pub fn is_dir(&self) -> bool {
+        self.m_attrs.is_enabled(FatAttrBits::Directory)
+    }
+
+    /**
+     * Returns the first data cluster, zero when there is no data
+     */
+    pub fn first_cluster(&self) -> u32 {
+        self.m_first_cluster
+    }
+
+    /**
+     * Returns the size in bytes of the file data
+     */
+    pub fn file_size(&self) -> u32 {
+        self.m_file_size
+    }
+
+    /**
+     * Returns the creation `FatTimestamp`
+     */
+    pub fn creat(&self) -> FatTimestamp {
+        self.m_creat
+    }
+
+    /**
+     * Returns the last access `FatTimestamp`, which has only the date
+     */
+    pub fn access(&self) -> FatTimestamp {
+        FatTimestamp::new(self.m_access_date, 0, 0)
+    }
+
+    /**
+     * Returns the last modification `FatTimestamp`
+     */
+    pub fn modify(&self) -> FatTimestamp {
+        self.m_modify
+    }
+}
+
+impl ShortDirEntry /* Setters */ {
+    /**
+     * Sets the raw 8.3 name
+     */
+    pub fn set_short_name(&mut self, short_name: [u8; 11]) {
+        self.m_short_name = short_name;
+    }
+
+    /**
+     * Sets the `FatAttrs`
+     */
+    pub fn set_attrs(&mut self, attrs: FatAttrs) {
+        self.m_attrs = attrs;
+    }
+
+    /**
+     * Sets the first data cluster
+     */
+    pub fn set_first_cluster(&mut self, first_cluster: u32) {
+        self.m_first_cluster = first_cluster;
+    }
+
+    /**
+     * Sets the size in bytes of the file data
+     */
+    pub fn set_file_size(&mut self, file_size: u32) {
+        self.m_file_size = file_size;
+    }
+
+    /**
+     * Sets the creation `FatTimestamp`
+     */
+    pub fn set_creat(&mut self, creat: FatTimestamp) {
+        self.m_creat = creat;
+    }
+
+    /**
+     * Sets the last access date of the given `FatTimestamp`
+     */
+    pub fn set_access(&mut self, access: FatTimestamp) {
+        self.m_access_date = access.date();
+    }
+
+    /**
+     * Sets the last modification `FatTimestamp`, the hundredths are not
+     * stored
+     */
+    pub fn set_modify(&mut self, modify: FatTimestamp) {
+        self.m_modify = FatTimestamp::new(modify.date(), modify.time(), 0);
+    }
+}
+
+/**
+ * VFAT entry which stores 13 UTF-16 characters of a long name
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct LongDirEntry {
+    m_order: u8,
+    m_checksum: u8,
+    m_chars: [u16; 13]
+}
+
+impl LongDirEntry /* Constants */ {
+    /**
+     * Amount of UTF-16 characters stored by each entry
+     */
+    pub const CHARS_COUNT: usize = 13;
+
+    /**
+     * Maximum amount of UTF-16 characters of a long name
+     */
+    pub const NAME_LEN_MAX: usize = 255;
+
+    /**
+     * Bit of the order which marks the physically first entry, the one with
+     * the last characters
+     */
+    pub const LAST_ENTRY_BIT: u8 = 0x40;
+
+    /**
+     * Raw attributes of the long entries, an impossible combination for the
+     * `ShortDirEntry`s
+     */
+    const LONG_NAME_ATTRS: u8 = 0x0F;
+
+    /**
+     * Offsets of the characters into the encoded entry
+     */
+    const CHARS_OFFSETS: [usize; 13] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+}
+
+impl LongDirEntry /* Constructors */ {
+    /**
+     * Decodes the `LongDirEntry` from the given buffer
+     */
+    pub fn decode(raw_entry: &[u8]) -> Self {
+        let mut chars = [0; Self::CHARS_COUNT];
+        for (char_index, char_offset) in Self::CHARS_OFFSETS.iter().enumerate() {
+            chars[char_index] = read_u16(raw_entry, *char_offset);
+        }
+
+        Self { m_order: raw_entry[0],
+               m_checksum: raw_entry[13],
+               m_chars: chars }
+    }
+
+    /**
+     * Returns whether the given encoded entry is a `LongDirEntry`
+     */
+    pub fn is_long_entry(raw_entry: &[u8]) -> bool {
+        raw_entry[11] & 0x3F == Self::LONG_NAME_ATTRS
+    }
+
+    /**
+     * Constructs the `LongDirEntry`s for the given long name, in the order
+     * they are stored into the directory
+     */
+    pub fn for_long_name(long_name: &str, checksum: u8) -> Vec<Self> {
+        let mut utf16_chars = long_name.encode_utf16().collect::<Vec<_>>();
+
+        /* the name is terminated only when it doesn't fill the last entry */
+        if utf16_chars.len() % Self::CHARS_COUNT != 0 {
+            utf16_chars.push(0);
+        }
+        while utf16_chars.len() % Self::CHARS_COUNT != 0 {
+            utf16_chars.push(0xFFFF);
+        }
+
+        let entries_count = utf16_chars.len() / Self::CHARS_COUNT;
+        utf16_chars.chunks(Self::CHARS_COUNT)
+                   .enumerate()
+                   .map(|(entry_index, entry_chars)| {
+                       let mut chars = [0; Self::CHARS_COUNT];
+                       chars.copy_from_slice(entry_chars);
+
+                       let mut order = entry_index as u8 + 1;
+                       if entry_index + 1 == entries_count {
+                           order |= Self::LAST_ENTRY_BIT;
+                       }
+                       Self { m_order: order,
+                              m_checksum: checksum,
+                              m_chars: chars }
+                   })
+                   .rev()
+                   .collect()
+    }
+}
+
+impl LongDirEntry /* Methods */ {
+    /**
+     * Encodes this `LongDirEntry` into the given buffer
+     */
+    pub fn encode(&self, raw_entry: &mut [u8]) {
+        raw_entry[..ShortDirEntry::ENCODED_SIZE].fill(0);
+
+        raw_entry[0] = self.m_order;
+        raw_entry[11] = Self::LONG_NAME_ATTRS;
+        raw_entry[13] = self.m_checksum;
+        for (char_index, char_offset) in Self::CHARS_OFFSETS.iter().enumerate() {
+            write_u16(raw_entry, *char_offset, self.m_chars[char_index]);
+        }
+    }
+
+    /**
+     * Returns whether this is the physically first entry of a long name
+     */
+    pub fn is_last(&self) -> bool {
+        self.m_order & Self::LAST_ENTRY_BIT != 0
+    }
+}
+
+impl LongDirEntry /* Getters */ {
+    /**
+     * Returns the position of this entry into the long name, starting
+     * from one
+     */
+    pub fn order(&self) -> u8 {
+        self.m_order & !Self::LAST_ENTRY_BIT
+    }
+
+    /**
+     * Returns the checksum of the `ShortDirEntry` which follows the long
+     * name
+     */
+    pub fn checksum(&self) -> u8 {
+        self.m_checksum
+    }
+
+    /**
+     * Returns the UTF-16 characters of this entry, terminator and padding
+     * excluded
+     */
+    pub fn chars(&self) -> &[u16] {
+        let chars_len =
+            self.m_chars.iter().position(|utf16_char| *utf16_char == 0).unwrap_or(13);
+        &self.m_chars[..chars_len]
+    }
+}
+
+/**
+ * Validates the given long name
+ */
+pub fn validate_long_name(name: &str) -> FatFsResult<()> {
+    if name.is_empty()
+       || name.encode_utf16().count() > LongDirEntry::NAME_LEN_MAX
+       || name == "."
+       || name == ".."
+       || name.ends_with(' ')
+       || name.ends_with('.')
+       || name.chars().any(|c| c < ' ' || "\"*/:<>?\\|".contains(c))
+    {
+        Err((OsErrorClass::InvalidArgument, Some("Invalid name")))
+    } else {
+        Ok(())
+    }
+}
+
+/**
+ * Returns the 8.3 name and the case flags which store exactly the given
+ * name, `None` when a long name is needed
+ */
+pub fn exact_short_name(name: &str) -> Option<([u8; 11], u8)> {
+    let (base, ext) = match name.find('.') {
+        Some(dot_index) => (&name[..dot_index], &name[dot_index + 1..]),
+        None => (name, "")
+    };
+    if base.is_empty()
+       || base.len() > 8
+       || ext.len() > 3
+       || ext.contains('.')
+       || !base.bytes().chain(ext.bytes()).all(is_short_name_byte)
+    {
+        return None;
+    }
+
+    /* the case flags can lower only entire parts */
+    let mut case_flags = 0;
+    for (part, lower_flag) in [(base, ShortDirEntry::LOWER_BASE_FLAG),
+                               (ext, ShortDirEntry::LOWER_EXT_FLAG)].iter()
+    {
+        let has_lower = part.bytes().any(|byte| byte.is_ascii_lowercase());
+        let has_upper = part.bytes().any(|byte| byte.is_ascii_uppercase());
+        if has_lower && has_upper {
+            return None;
+        } else if has_lower {
+            case_flags |= lower_flag;
+        }
+    }
+
+    let mut short_name = [b' '; 11];
+    for (index, byte) in base.bytes().enumerate() {
+        short_name[index] = byte.to_ascii_uppercase();
+    }
+    for (index, byte) in ext.bytes().enumerate() {
+        short_name[8 + index] = byte.to_ascii_uppercase();
+    }
+    if short_name[0] == ShortDirEntry::FREE_MARK {
+        short_name[0] = ShortDirEntry::KANJI_LEAD_MARK;
+    }
+    Some((short_name, case_flags))
+}
+
+/**
+ * Returns the 8.3 name generated from the given long name with the given
+ * numeric tail (`~N`)
+ */
+pub fn generated_short_name(name: &str, numeric_tail: u32) -> [u8; 11] {
+    let to_short_bytes = |part: &str| {
+        part.chars()
+            .filter(|c| *c != ' ' && *c != '.')
+            .map(|c| {
+                if c.is_ascii() && is_short_name_byte(c as u8) {
+                    c.to_ascii_uppercase() as u8
+                } else {
+                    b'_'
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    /* the extension is after the last dot, the leading dots are ignored */
+    let trimmed_name = name.trim_start_matches('.');
+    let (base, ext) = match trimmed_name.rfind('.') {
+        Some(dot_index) => (&trimmed_name[..dot_index], &trimmed_name[dot_index + 1..]),
+        None => (trimmed_name, "")
+    };
+    let mut base_bytes = to_short_bytes(base);
+    let ext_bytes = to_short_bytes(ext);
+    if base_bytes.is_empty() {
+        base_bytes.push(b'_');
+    }
+
+    let tail = format!("~{}", numeric_tail);
+    base_bytes.truncate(8 - tail.len());
+    base_bytes.extend_from_slice(tail.as_bytes());
+
+    let mut short_name = [b' '; 11];
+    short_name[..base_bytes.len()].copy_from_slice(&base_bytes);
+    for (index, byte) in ext_bytes.iter().take(3).enumerate() {
+        short_name[8 + index] = *byte;
+    }
+    short_name
+}
+
+/**
+ * Returns the checksum of the given 8.3 name
+ */
+pub fn short_name_checksum(short_name: &[u8; 11]) -> u8 {
+    short_name.iter()
+              .fold(0u8, |checksum, byte| checksum.rotate_right(1).wrapping_add(*byte))
+}
+
+/**
+ * Returns whether the given byte is allowed into the 8.3 names
+ */
+fn is_short_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&byte)
+}
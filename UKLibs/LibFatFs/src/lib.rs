@@ -0,0 +1,26 @@
+/*! # FAT Filesystem Library
+ *
+ * Implements the `File Allocation Table` filesystem in his FAT12, FAT16
+ * and FAT32 variants, with the VFAT long names, compatible with the
+ * images created by the common tools (i.e. `mkfs.vfat`).
+ *
+ * The media is accessed through the `meetix_fs::dev::TBlockDevice`, so the
+ * same devices are shared by all the block filesystems
+ */
+
+#![no_std]
+
+#[macro_use]
+extern crate alloc;
+
+use api_data::error::class::OsErrorClass;
+
+pub mod bpb;
+pub mod dir_entry;
+pub mod time;
+pub mod volume;
+
+/**
+ * Result type returned by the filesystem operations
+ */
+pub type FatFsResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
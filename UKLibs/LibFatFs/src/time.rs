@@ -0,0 +1,186 @@
+/*! FAT timestamps
+ *
+ * The FAT stores the local date and time of the modifications, starting
+ * from the 1980, with a resolution of two seconds (ten milliseconds for
+ * the creation time). The `RawInstant`s are considered as elapsed since
+ * the UNIX epoch and the timezone is ignored
+ */
+
+use core::time::Duration;
+
+use api_data::instant::RawInstant;
+
+/**
+ * Date and time of a FAT directory entry
+ */
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub struct FatTimestamp {
+    m_date: u16,
+    m_time: u16,
+    m_centis: u8
+}
+
+impl FatTimestamp /* Constants */ {
+    /**
+     * First year representable
+     */
+    const EPOCH_YEAR: i64 = 1980;
+
+    /**
+     * Last year representable
+     */
+    const LAST_YEAR: i64 = Self::EPOCH_YEAR + 127;
+
+    const SECS_PER_DAY: u64 = 24 * 60 * 60;
+}
+
+impl FatTimestamp /* Constructors */ {
+    /**
+     * Constructs a `FatTimestamp` from the raw values of a directory
+     * entry, `centis` are the hundredths of second over the two seconds
+     * resolution of the time
+     */
+    pub fn new(date: u16, time: u16, centis: u8) -> Self {
+        Self { m_date: date,
+               m_time: time,
+               m_centis: centis }
+    }
+
+    /**
+     * Constructs a `FatTimestamp` from the given `RawInstant`, clamped to
+     * the range representable by the FAT
+     */
+    pub fn from_instant(instant: RawInstant) -> Self {
+        let first_secs =
+            days_from_civil(Self::EPOCH_YEAR, 1, 1) as u64 * Self::SECS_PER_DAY;
+        let last_secs = days_from_civil(Self::LAST_YEAR, 12, 31) as u64
+                        * Self::SECS_PER_DAY
+                        + Self::SECS_PER_DAY
+                        - 2;
+        let (secs, millis) = if instant.as_secs() < first_secs {
+            (first_secs, 0)
+        } else if instant.as_secs() > last_secs {
+            (last_secs, 0)
+        } else {
+            (instant.as_secs(), instant.subsec_millis())
+        };
+
+        let (year, month, day) = civil_from_days((secs / Self::SECS_PER_DAY) as i64);
+        let day_secs = secs % Self::SECS_PER_DAY;
+        let (hours, minutes, seconds) =
+            (day_secs / 3600, day_secs / 60 % 60, day_secs % 60);
+
+        Self { m_date: ((year - Self::EPOCH_YEAR) << 9 | month << 5 | day) as u16,
+               m_time: (hours << 11 | minutes << 5 | (seconds / 2)) as u16,
+               m_centis: ((seconds % 2) * 100 + millis as u64 / 10) as u8 }
+    }
+}
+
+impl FatTimestamp /* Methods */ {
+    /**
+     * Converts this `FatTimestamp` to a `RawInstant`, the zero date (never
+     * set) is converted to the zero instant
+     */
+    pub fn to_instant(&self) -> RawInstant {
+        if self.m_date == 0 {
+            return RawInstant::default();
+        }
+
+        let year = Self::EPOCH_YEAR + (self.m_date >> 9) as i64;
+        let month = ((self.m_date >> 5) & 0xF).clamp(1, 12) as i64;
+        let day = (self.m_date & 0x1F).max(1) as i64;
+        let day_secs = (self.m_time >> 11) as u64 * 3600
+                       + ((self.m_time >> 5) & 0x3F) as u64 * 60
+                       + (self.m_time & 0x1F) as u64 * 2;
+
+        let secs =
+            days_from_civil(year, month, day) as u64 * Self::SECS_PER_DAY + day_secs;
+        Duration::from_secs(secs)
+        + Duration::from_millis(self.m_centis.min(199) as u64 * 10)
+    }
+}
+
+impl FatTimestamp /* Getters */ {
+    /**
+     * Returns the raw FAT date
+     */
+    pub fn date(&self) -> u16 {
+        self.m_date
+    }
+
+    /**
+     * Returns the raw FAT time
+     */
+    pub fn time(&self) -> u16 {
+        self.m_time
+    }
+
+    /**
+     * Returns the hundredths of second over the two seconds resolution
+     */
+    pub fn centis(&self) -> u8 {
+        self.m_centis
+    }
+}
+
+/**
+ * Returns the days elapsed since the UNIX epoch for the given date of the
+ * proleptic Gregorian calendar
+ */
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 {
+        year - 1
+    } else {
+        year
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153
+                       * (month
+                          + if month > 2 {
+                              -3
+                          } else {
+                              9
+                          })
+                       + 2)
+                      / 5
+                      + day
+                      - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/**
+ * Returns the year, the month and the day of the given days elapsed since
+ * the UNIX epoch
+ */
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+                       - day_of_era / 146_096)
+                      / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 {
+         year + 1
+     } else {
+         year
+     },
+     month,
+     day)
+}
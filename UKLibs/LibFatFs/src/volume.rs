@@ -0,0 +1,1209 @@
+/*! FAT filesystem volume */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+use core::cmp::min;
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant
+};
+use meetix_fs::dev::TBlockDevice;
+
+use crate::{
+    bpb::{
+        BootSector,
+        FatType,
+        FsInfo
+    },
+    dir_entry::{
+        exact_short_name,
+        generated_short_name,
+        validate_long_name,
+        FatAttrBits,
+        FatAttrs,
+        LongDirEntry,
+        ShortDirEntry
+    },
+    time::FatTimestamp,
+    FatFsResult
+};
+
+/**
+ * Function which returns the current `RawInstant` for the timestamps
+ */
+pub type ClockFn = fn() -> RawInstant;
+
+/**
+ * Where the entries of a directory are stored
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum DirLocation {
+    /**
+     * Fixed size root directory of the FAT12/16
+     */
+    FixedRoot,
+
+    /**
+     * Cluster chain which starts from the given cluster
+     */
+    Chain(u32)
+}
+
+/**
+ * Position of the `ShortDirEntry` of a node into his parent directory.
+ *
+ * The entries are never moved, so the position identifies the node for
+ * all his life
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub struct EntryLocation {
+    m_dir: DirLocation,
+    m_entry_index: u32
+}
+
+impl EntryLocation /* Methods */ {
+    /**
+     * Returns an identifier of the node unique into the volume, the zero
+     * is never returned and could be used for the root directory
+     */
+    pub fn node_id(&self) -> u64 {
+        match self.m_dir {
+            DirLocation::FixedRoot => self.m_entry_index as u64 + 1,
+            DirLocation::Chain(first_cluster) => {
+                (first_cluster as u64) << 32 | self.m_entry_index as u64
+            },
+        }
+    }
+}
+
+impl EntryLocation /* Getters */ {
+    /**
+     * Returns the `DirLocation` of the parent directory
+     */
+    pub fn dir(&self) -> DirLocation {
+        self.m_dir
+    }
+
+    /**
+     * Returns the index of the `ShortDirEntry` into the parent directory
+     */
+    pub fn entry_index(&self) -> u32 {
+        self.m_entry_index
+    }
+}
+
+/**
+ * Child of a directory with his long name resolved
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct FatDirRecord {
+    m_name: String,
+    m_short_entry: ShortDirEntry,
+    m_location: EntryLocation,
+    m_first_entry_index: u32
+}
+
+impl FatDirRecord /* Getters */ {
+    /**
+     * Returns the name of the node, the long one when it exists
+     */
+    pub fn name(&self) -> &str {
+        self.m_name.as_str()
+    }
+
+    /**
+     * Returns the `ShortDirEntry` of the node
+     */
+    pub fn short_entry(&self) -> &ShortDirEntry {
+        &self.m_short_entry
+    }
+
+    /**
+     * Returns the `EntryLocation` of the `ShortDirEntry`
+     */
+    pub fn location(&self) -> EntryLocation {
+        self.m_location
+    }
+
+    /**
+     * Returns whether the node is a directory
+     */
+    pub fn is_dir(&self) -> bool {
+        self.m_short_entry.is_dir()
+    }
+
+    /**
+     * Returns the `DirLocation` of the content of this directory
+     */
+    pub fn content_location(&self) -> DirLocation {
+        DirLocation::Chain(self.m_short_entry.first_cluster())
+    }
+}
+
+/**
+ * Mounted FAT filesystem.
+ *
+ * Only one sector of the FAT is kept in memory, it is written back to all
+ * the mirrored FATs when another sector is needed or by
+ * `FatVolume::sync()`, the directory entries and the data are written
+ * through
+ */
+pub struct FatVolume<D>
+    where D: TBlockDevice {
+    m_device: D,
+    m_boot_sector: BootSector,
+    m_device_blocks_per_sector: u64,
+    m_fs_info: Option<FsInfo>,
+    m_free_clusters: Option<u32>,
+    m_next_free_cluster: u32,
+    m_fat_sector_index: Option<u64>,
+    m_fat_sector: Vec<u8>,
+    m_fat_sector_dirty: bool,
+    m_clock: ClockFn
+}
+
+impl<D> FatVolume<D> where D: TBlockDevice /* Constructors */ {
+    /**
+     * Mounts the filesystem stored into the given `TBlockDevice`
+     */
+    pub fn mount(mut device: D, clock: ClockFn) -> FatFsResult<Self> {
+        let device_block_size = device.block_size();
+        if !device_block_size.is_power_of_two() || device_block_size > 4096 {
+            return Err((OsErrorClass::InvalidArgument, Some("Unsupported block size")));
+        }
+
+        /* the boot sector is at least 512 bytes long */
+        let mut raw_boot_sector =
+            vec![0; device_block_size.max(BootSector::ENCODED_SIZE)];
+        device.read_blocks(0, &mut raw_boot_sector)?;
+        let boot_sector = BootSector::decode(&raw_boot_sector)?;
+
+        let bytes_per_sector = boot_sector.bytes_per_sector();
+        if bytes_per_sector < device_block_size {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("FAT sector smaller than the device block")));
+        }
+        let device_blocks_per_sector = (bytes_per_sector / device_block_size) as u64;
+        if boot_sector.total_sectors() * device_blocks_per_sector > device.blocks_count()
+        {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Filesystem bigger than the device")));
+        }
+
+        let mut volume = Self { m_device: device,
+                                m_device_blocks_per_sector: device_blocks_per_sector,
+                                m_fs_info: None,
+                                m_free_clusters: None,
+                                m_next_free_cluster: 2,
+                                m_fat_sector_index: None,
+                                m_fat_sector: vec![0; bytes_per_sector],
+                                m_fat_sector_dirty: false,
+                                m_clock: clock,
+                                m_boot_sector: boot_sector };
+
+        /* the FAT32 hints avoid to scan the entire FAT */
+        if let Some(fs_info_sector) = volume.m_boot_sector.fs_info_sector() {
+            let mut raw_fs_info = vec![0; bytes_per_sector];
+            volume.read_sectors(fs_info_sector, &mut raw_fs_info)?;
+            if let Some(fs_info) = FsInfo::decode(&raw_fs_info) {
+                let clusters_count = volume.m_boot_sector.clusters_count();
+                volume.m_free_clusters =
+                    fs_info.free_count()
+                           .filter(|free_count| *free_count <= clusters_count);
+                volume.m_next_free_cluster = fs_info.next_free()
+                                                    .filter(|next_free| {
+                                                        volume.m_boot_sector
+                                                              .is_data_cluster(*next_free)
+                                                    })
+                                                    .unwrap_or(2);
+                volume.m_fs_info = Some(fs_info);
+            }
+        }
+        Ok(volume)
+    }
+}
+
+impl<D> FatVolume<D> where D: TBlockDevice /* Methods */ {
+    /**
+     * Writes back the cached FAT sector and returns the `TBlockDevice`
+     */
+    pub fn unmount(mut self) -> FatFsResult<D> {
+        self.sync()?;
+        Ok(self.m_device)
+    }
+
+    /**
+     * Writes back the cached FAT sector and the FAT32 `FsInfo`
+     */
+    pub fn sync(&mut self) -> FatFsResult<()> {
+        self.flush_fat_sector()?;
+
+        if let (Some(mut fs_info), Some(fs_info_sector)) =
+            (self.m_fs_info, self.m_boot_sector.fs_info_sector())
+        {
+            fs_info.set_free_count(self.m_free_clusters);
+            fs_info.set_next_free(Some(self.m_next_free_cluster));
+
+            let mut raw_fs_info = vec![0; self.m_boot_sector.bytes_per_sector()];
+            self.read_sectors(fs_info_sector, &mut raw_fs_info)?;
+            fs_info.encode(&mut raw_fs_info);
+            self.write_sectors(fs_info_sector, &raw_fs_info)?;
+            self.m_fs_info = Some(fs_info);
+        }
+        self.m_device.flush()
+    }
+
+    /**
+     * Returns the `DirLocation` of the root directory
+     */
+    pub fn root_dir(&self) -> DirLocation {
+        match self.m_boot_sector.fat_type() {
+            FatType::Fat32 => DirLocation::Chain(self.m_boot_sector.root_cluster()),
+            _ => DirLocation::FixedRoot
+        }
+    }
+
+    /**
+     * Returns the child of the given directory with the given name, the
+     * comparison ignores the ASCII case like the other FAT drivers
+     */
+    pub fn lookup(&mut self,
+                  dir: DirLocation,
+                  name: &str)
+                  -> FatFsResult<Option<FatDirRecord>> {
+        Ok(self.dir_records(dir)?.into_iter().find(|dir_record| {
+                                                 dir_record.name()
+                                                           .eq_ignore_ascii_case(name)
+                                                 || dir_record.m_short_entry
+                                                              .display_name()
+                                                              .eq_ignore_ascii_case(name)
+                                             }))
+    }
+
+    /**
+     * Returns all the children of the given directory, the volume label and
+     * the `.` and `..` entries are excluded
+     */
+    pub fn dir_records(&mut self, dir: DirLocation) -> FatFsResult<Vec<FatDirRecord>> {
+        let raw_dir = self.read_dir(dir)?;
+
+        let mut dir_records = Vec::new();
+        let mut long_entries: Vec<LongDirEntry> = Vec::new();
+        let mut first_entry_index = 0;
+        for (entry_index, raw_entry) in
+            raw_dir.as_chunks::<{ ShortDirEntry::ENCODED_SIZE }>().0.iter().enumerate()
+        {
+            let entry_index = entry_index as u32;
+            if raw_entry[0] == ShortDirEntry::END_MARK {
+                break;
+            } else if raw_entry[0] == ShortDirEntry::FREE_MARK {
+                long_entries.clear();
+                continue;
+            } else if LongDirEntry::is_long_entry(raw_entry) {
+                let long_entry = LongDirEntry::decode(raw_entry);
+
+                /* a new long name restarts the collection */
+                if long_entry.is_last() {
+                    long_entries.clear();
+                    first_entry_index = entry_index;
+                }
+                long_entries.push(long_entry);
+                continue;
+            }
+
+            let short_entry = ShortDirEntry::decode(raw_entry);
+            let long_name = Self::assemble_long_name(&long_entries, &short_entry);
+            let has_long_name = long_name.is_some();
+            long_entries.clear();
+
+            if short_entry.attrs().is_enabled(FatAttrBits::VolumeId)
+               || short_entry.is_dot_entry()
+            {
+                continue;
+            }
+            dir_records.push(FatDirRecord { m_name: long_name.unwrap_or_else(|| {
+                                                        short_entry.display_name()
+                                                    }),
+                                            m_short_entry: short_entry,
+                                            m_location:
+                                                EntryLocation { m_dir: dir,
+                                                                m_entry_index:
+                                                                    entry_index },
+                                            m_first_entry_index: if has_long_name {
+                                                first_entry_index
+                                            } else {
+                                                entry_index
+                                            } });
+        }
+        Ok(dir_records)
+    }
+
+    /**
+     * Creates into the given directory a new empty file or directory with
+     * the given name
+     */
+    pub fn create_node(&mut self,
+                       dir: DirLocation,
+                       name: &str,
+                       is_dir: bool)
+                       -> FatFsResult<FatDirRecord> {
+        validate_long_name(name)?;
+
+        let dir_records = self.dir_records(dir)?;
+        if dir_records.iter().any(|dir_record| {
+                                 dir_record.name().eq_ignore_ascii_case(name)
+                                 || dir_record.m_short_entry
+                                              .display_name()
+                                              .eq_ignore_ascii_case(name)
+                             })
+        {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Name already in use")));
+        }
+
+        /* the long name is stored only when the 8.3 one is not enough */
+        let is_short_name_used = |short_name: &[u8; 11]| {
+            dir_records.iter().any(|dir_record| {
+                                  dir_record.m_short_entry.short_name() == short_name
+                              })
+        };
+        let (short_name, case_flags, needs_long_name) = match exact_short_name(name) {
+            Some((short_name, case_flags)) if !is_short_name_used(&short_name) => {
+                (short_name, case_flags, false)
+            },
+            _ => {
+                let short_name =
+                    (1..1_000_000).map(|numeric_tail| {
+                                      generated_short_name(name, numeric_tail)
+                                  })
+                                  .find(|short_name| !is_short_name_used(short_name))
+                                  .ok_or((OsErrorClass::LimitReached,
+                                          Some("No short names available")))?;
+                (short_name, 0, true)
+            }
+        };
+
+        /* the directories are created with their <.> and <..> entries */
+        let now = FatTimestamp::from_instant((self.m_clock)());
+        let mut attrs = FatAttrs::new_zero();
+        let first_cluster = if is_dir {
+            attrs.set_enabled(FatAttrBits::Directory);
+
+            let first_cluster = self.allocate_cluster(None)?;
+            self.zero_cluster(first_cluster)?;
+
+            let parent_cluster = match dir {
+                DirLocation::Chain(parent_cluster) if dir != self.root_dir() => {
+                    parent_cluster
+                },
+                _ => 0
+            };
+            let mut raw_dot_entries = vec![0; ShortDirEntry::ENCODED_SIZE * 2];
+            ShortDirEntry::new(ShortDirEntry::SELF_LINK_NAME,
+                               0,
+                               attrs,
+                               first_cluster,
+                               now).encode(&mut raw_dot_entries
+                                               [..ShortDirEntry::ENCODED_SIZE]);
+            ShortDirEntry::new(ShortDirEntry::PARENT_LINK_NAME,
+                               0,
+                               attrs,
+                               parent_cluster,
+                               now).encode(&mut raw_dot_entries
+                                               [ShortDirEntry::ENCODED_SIZE..]);
+            self.write_dir_entries(DirLocation::Chain(first_cluster),
+                                   0,
+                                   &raw_dot_entries)?;
+            first_cluster
+        } else {
+            attrs.set_enabled(FatAttrBits::Archive);
+            0
+        };
+
+        let short_entry =
+            ShortDirEntry::new(short_name, case_flags, attrs, first_cluster, now);
+        let long_entries = if needs_long_name {
+            LongDirEntry::for_long_name(name, short_entry.checksum())
+        } else {
+            Vec::new()
+        };
+
+        /* encode all the entries, the long ones before the short one */
+        let entries_count = long_entries.len() + 1;
+        let mut raw_entries = vec![0; entries_count * ShortDirEntry::ENCODED_SIZE];
+        for (long_entry, raw_entry) in
+            long_entries.iter()
+                        .zip(raw_entries.as_chunks_mut::<{ ShortDirEntry::ENCODED_SIZE }>().0.iter_mut())
+        {
+            long_entry.encode(raw_entry);
+        }
+        short_entry.encode(&mut raw_entries
+                               [long_entries.len() * ShortDirEntry::ENCODED_SIZE..]);
+
+        let first_entry_index = match self.reserve_dir_entries(dir, entries_count as u32)
+        {
+            Ok(first_entry_index) => first_entry_index,
+            Err(error) => {
+                if first_cluster != 0 {
+                    self.free_chain(first_cluster)?;
+                }
+                return Err(error);
+            }
+        };
+        self.write_dir_entries(dir, first_entry_index, &raw_entries)?;
+
+        Ok(FatDirRecord { m_name: String::from(name),
+                          m_short_entry: short_entry,
+                          m_location: EntryLocation { m_dir: dir,
+                                                      m_entry_index: first_entry_index
+                                                                     + entries_count
+                                                                       as u32
+                                                                     - 1 },
+                          m_first_entry_index: first_entry_index })
+    }
+
+    /**
+     * Removes the child of the given directory with the given name and
+     * releases his clusters, the directories must be empty
+     */
+    pub fn remove_node(&mut self, dir: DirLocation, name: &str) -> FatFsResult<()> {
+        let dir_record = self.lookup(dir, name)?
+                             .ok_or((OsErrorClass::ReferenceNotFound,
+                                     Some("No such name in directory")))?;
+        if dir_record.is_dir()
+           && !self.dir_records(dir_record.content_location())?.is_empty()
+        {
+            return Err((OsErrorClass::OperationNotEnabled, Some("Directory not empty")));
+        }
+
+        let first_cluster = dir_record.m_short_entry.first_cluster();
+        if self.m_boot_sector.is_data_cluster(first_cluster) {
+            self.free_chain(first_cluster)?;
+        }
+
+        /* mark as free the short entry and his long name */
+        for entry_index in
+            dir_record.m_first_entry_index..=dir_record.m_location.m_entry_index
+        {
+            let mut raw_entry = self.read_dir_entry(dir, entry_index)?;
+            raw_entry[0] = ShortDirEntry::FREE_MARK;
+            self.write_dir_entries(dir, entry_index, &raw_entry)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Reads the `ShortDirEntry` at the given `EntryLocation`
+     */
+    pub fn load_entry(&mut self, location: EntryLocation) -> FatFsResult<ShortDirEntry> {
+        let raw_entry = self.read_dir_entry(location.m_dir, location.m_entry_index)?;
+        if matches!(raw_entry[0], ShortDirEntry::END_MARK | ShortDirEntry::FREE_MARK)
+           || LongDirEntry::is_long_entry(&raw_entry)
+        {
+            return Err((OsErrorClass::ReferenceNotFound, Some("Node removed")));
+        }
+        Ok(ShortDirEntry::decode(&raw_entry))
+    }
+
+    /**
+     * Writes the given `ShortDirEntry` at the given `EntryLocation`
+     */
+    pub fn store_entry(&mut self,
+                       location: EntryLocation,
+                       short_entry: &ShortDirEntry)
+                       -> FatFsResult<()> {
+        let mut raw_entry = [0; ShortDirEntry::ENCODED_SIZE];
+        short_entry.encode(&mut raw_entry);
+        self.write_dir_entries(location.m_dir, location.m_entry_index, &raw_entry)
+    }
+
+    /**
+     * Reads the data of the given file starting from the given offset and
+     * returns the amount of bytes read
+     */
+    pub fn read_data(&mut self,
+                     location: EntryLocation,
+                     offset: usize,
+                     buffer: &mut [u8])
+                     -> FatFsResult<usize> {
+        let short_entry = self.load_entry(location)?;
+        let file_size = short_entry.file_size() as usize;
+        if offset >= file_size || buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let read_len = min(buffer.len(), file_size - offset);
+        let cluster_size = self.cluster_size();
+        let chain = self.cluster_chain(short_entry.first_cluster())?;
+        if chain.len() * cluster_size < file_size {
+            return Err((OsErrorClass::EndOfDataReached, Some("File chain too short")));
+        }
+
+        let mut cluster_buffer = vec![0; cluster_size];
+        let mut read_bytes = 0;
+        while read_bytes < read_len {
+            let data_offset = offset + read_bytes;
+            let cluster_offset = data_offset % cluster_size;
+            let copy_len = min(read_len - read_bytes, cluster_size - cluster_offset);
+
+            self.read_cluster(chain[data_offset / cluster_size], &mut cluster_buffer)?;
+            buffer[read_bytes..read_bytes + copy_len]
+                .copy_from_slice(&cluster_buffer[cluster_offset..cluster_offset + copy_len]);
+            read_bytes += copy_len;
+        }
+        Ok(read_len)
+    }
+
+    /**
+     * Writes the data of the given file starting from the given offset,
+     * the hole between the end of the file and the offset is zeroed.
+     *
+     * Returns the amount of bytes written
+     */
+    pub fn write_data(&mut self,
+                      location: EntryLocation,
+                      offset: usize,
+                      buffer: &[u8])
+                      -> FatFsResult<usize> {
+        let mut short_entry = self.load_entry(location)?;
+        if short_entry.is_dir() {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        } else if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let file_size = short_entry.file_size() as usize;
+        let end_offset =
+            offset.checked_add(buffer.len())
+                  .filter(|end_offset| *end_offset <= u32::MAX as usize)
+                  .ok_or((OsErrorClass::LimitOverflow, Some("File too big")))?;
+        if offset > file_size {
+            self.write_span(&mut short_entry, file_size, None, offset - file_size)?;
+        }
+        self.write_span(&mut short_entry, offset, Some(buffer), buffer.len())?;
+
+        short_entry.set_file_size(short_entry.file_size().max(end_offset as u32));
+        self.touch_modify(&mut short_entry);
+        self.store_entry(location, &short_entry)?;
+        Ok(buffer.len())
+    }
+
+    /**
+     * Truncates or extends with zeroes the data of the given file
+     */
+    pub fn set_data_size(&mut self,
+                         location: EntryLocation,
+                         data_size: usize)
+                         -> FatFsResult<()> {
+        let mut short_entry = self.load_entry(location)?;
+        if short_entry.is_dir() {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        } else if data_size > u32::MAX as usize {
+            return Err((OsErrorClass::LimitOverflow, Some("File too big")));
+        }
+
+        let file_size = short_entry.file_size() as usize;
+        if data_size > file_size {
+            self.write_span(&mut short_entry, file_size, None, data_size - file_size)?;
+        } else if data_size < file_size {
+            let cluster_size = self.cluster_size();
+            let chain = self.cluster_chain(short_entry.first_cluster())?;
+            let kept_clusters = data_size.div_ceil(cluster_size);
+
+            if kept_clusters == 0 {
+                if let Some(first_cluster) = chain.first() {
+                    self.free_chain(*first_cluster)?;
+                }
+                short_entry.set_first_cluster(0);
+            } else if kept_clusters < chain.len() {
+                let end_of_chain = self.m_boot_sector.fat_type().end_of_chain();
+                self.set_fat_entry(chain[kept_clusters - 1], end_of_chain)?;
+                self.free_chain(chain[kept_clusters])?;
+            }
+        }
+
+        short_entry.set_file_size(data_size as u32);
+        self.touch_modify(&mut short_entry);
+        self.store_entry(location, &short_entry)
+    }
+
+    /**
+     * Returns the clusters of the chain which starts from the given
+     * cluster, the zero cluster is the empty chain
+     */
+    pub fn cluster_chain(&mut self, first_cluster: u32) -> FatFsResult<Vec<u32>> {
+        let mut chain = Vec::new();
+        let mut next_cluster = Some(first_cluster).filter(|cluster| *cluster != 0);
+        while let Some(cluster) = next_cluster {
+            if !self.m_boot_sector.is_data_cluster(cluster)
+               || chain.len() >= self.m_boot_sector.clusters_count() as usize
+            {
+                return Err((OsErrorClass::LimitOverflow,
+                            Some("Corrupted cluster chain")));
+            }
+            chain.push(cluster);
+
+            let fat_entry = self.fat_entry(cluster)?;
+            next_cluster = if self.m_boot_sector.fat_type().is_end_of_chain(fat_entry) {
+                None
+            } else {
+                Some(fat_entry)
+            };
+        }
+        Ok(chain)
+    }
+
+    /**
+     * Returns the amount of free clusters, the FAT is scanned when it is
+     * not known
+     */
+    pub fn free_clusters(&mut self) -> FatFsResult<u32> {
+        if let Some(free_clusters) = self.m_free_clusters {
+            return Ok(free_clusters);
+        }
+
+        let mut free_clusters = 0;
+        for cluster in 2..self.m_boot_sector.clusters_count() + 2 {
+            if self.fat_entry(cluster)? == 0 {
+                free_clusters += 1;
+            }
+        }
+        self.m_free_clusters = Some(free_clusters);
+        Ok(free_clusters)
+    }
+}
+
+impl<D> FatVolume<D> where D: TBlockDevice /* Getters */ {
+    /**
+     * Returns the `BootSector` of the volume
+     */
+    pub fn boot_sector(&self) -> &BootSector {
+        &self.m_boot_sector
+    }
+
+    /**
+     * Returns the size in bytes of each cluster
+     */
+    pub fn cluster_size(&self) -> usize {
+        self.m_boot_sector.cluster_size()
+    }
+
+    /**
+     * Returns the `TBlockDevice` where the volume is stored
+     */
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.m_device
+    }
+}
+
+impl<D> FatVolume<D> where D: TBlockDevice /* Privates */ {
+    /**
+     * Returns the long name collected for the given `ShortDirEntry` when
+     * it is complete and matches the checksum
+     */
+    fn assemble_long_name(long_entries: &[LongDirEntry],
+                          short_entry: &ShortDirEntry)
+                          -> Option<String> {
+        let checksum = short_entry.checksum();
+        let entries_count = long_entries.len();
+        let is_valid = entries_count > 0
+                       && long_entries[0].is_last()
+                       && long_entries.iter().enumerate().all(|(index, long_entry)| {
+                                                             long_entry.order() as usize
+                                                             == entries_count - index
+                                                             && long_entry.checksum()
+                                                                == checksum
+                                                         });
+        if !is_valid {
+            return None;
+        }
+
+        /* the entries are stored from the last to the first */
+        let utf16_chars = long_entries.iter()
+                                      .rev()
+                                      .flat_map(|long_entry| long_entry.chars().iter())
+                                      .copied()
+                                      .collect::<Vec<_>>();
+        Some(String::from_utf16_lossy(&utf16_chars))
+    }
+
+    /**
+     * Finds, or makes room for, the given amount of consecutive free
+     * entries into the given directory and returns the index of the first
+     */
+    fn reserve_dir_entries(&mut self,
+                           dir: DirLocation,
+                           entries_count: u32)
+                           -> FatFsResult<u32> {
+        let raw_dir = self.read_dir(dir)?;
+        let dir_entries = (raw_dir.len() / ShortDirEntry::ENCODED_SIZE) as u32;
+
+        let mut free_run_start = 0;
+        let mut free_run_len = 0;
+        for (entry_index, raw_entry) in
+            raw_dir.as_chunks::<{ ShortDirEntry::ENCODED_SIZE }>().0.iter().enumerate()
+        {
+            if raw_entry[0] == ShortDirEntry::END_MARK {
+                /* all the following entries are free */
+                if free_run_len == 0 {
+                    free_run_start = entry_index as u32;
+                }
+                free_run_len += dir_entries - entry_index as u32;
+                break;
+            } else if raw_entry[0] == ShortDirEntry::FREE_MARK {
+                if free_run_len == 0 {
+                    free_run_start = entry_index as u32;
+                }
+                free_run_len += 1;
+                if free_run_len == entries_count {
+                    return Ok(free_run_start);
+                }
+            } else {
+                free_run_len = 0;
+            }
+        }
+        if free_run_len >= entries_count {
+            return Ok(free_run_start);
+        }
+
+        /* only the cluster chains could grow */
+        let first_cluster = match dir {
+            DirLocation::FixedRoot => {
+                return Err((OsErrorClass::LimitReached, Some("Root directory full")))
+            },
+            DirLocation::Chain(first_cluster) => first_cluster
+        };
+        let entries_per_cluster =
+            (self.cluster_size() / ShortDirEntry::ENCODED_SIZE) as u32;
+        let mut last_cluster = *self.cluster_chain(first_cluster)?.last().unwrap();
+        while free_run_len < entries_count {
+            let new_cluster = self.allocate_cluster(Some(last_cluster))?;
+            self.zero_cluster(new_cluster)?;
+
+            if free_run_len == 0 {
+                free_run_start = dir_entries;
+            }
+            free_run_len += entries_per_cluster;
+            last_cluster = new_cluster;
+        }
+        Ok(free_run_start)
+    }
+
+    /**
+     * Writes the given data or zeroes into the data of the file, starting
+     * from the given offset, the cluster chain is extended when needed
+     */
+    fn write_span(&mut self,
+                  short_entry: &mut ShortDirEntry,
+                  offset: usize,
+                  data: Option<&[u8]>,
+                  span_len: usize)
+                  -> FatFsResult<()> {
+        if span_len == 0 {
+            return Ok(());
+        }
+
+        let cluster_size = self.cluster_size();
+        let needed_clusters = (offset + span_len).div_ceil(cluster_size);
+        let mut chain = self.cluster_chain(short_entry.first_cluster())?;
+        while chain.len() < needed_clusters {
+            let new_cluster = self.allocate_cluster(chain.last().copied())?;
+            if chain.is_empty() {
+                short_entry.set_first_cluster(new_cluster);
+            }
+            chain.push(new_cluster);
+        }
+
+        let mut cluster_buffer = vec![0; cluster_size];
+        let mut written_bytes = 0;
+        while written_bytes < span_len {
+            let data_offset = offset + written_bytes;
+            let cluster = chain[data_offset / cluster_size];
+            let cluster_offset = data_offset % cluster_size;
+            let copy_len = min(span_len - written_bytes, cluster_size - cluster_offset);
+
+            /* the partially written clusters are read first */
+            if copy_len != cluster_size {
+                self.read_cluster(cluster, &mut cluster_buffer)?;
+            }
+            let cluster_range = cluster_offset..cluster_offset + copy_len;
+            match data {
+                Some(data) => {
+                    cluster_buffer[cluster_range].copy_from_slice(&data[written_bytes
+                                                                        ..written_bytes
+                                                                          + copy_len])
+                },
+                None => cluster_buffer[cluster_range].fill(0)
+            }
+            self.write_cluster(cluster, &cluster_buffer)?;
+            written_bytes += copy_len;
+        }
+        Ok(())
+    }
+
+    /**
+     * Updates the modification timestamp of the given `ShortDirEntry`
+     */
+    fn touch_modify(&self, short_entry: &mut ShortDirEntry) {
+        let now = FatTimestamp::from_instant((self.m_clock)());
+        short_entry.set_modify(now);
+        short_entry.set_access(now);
+
+        let mut attrs = short_entry.attrs();
+        attrs.set_enabled(FatAttrBits::Archive);
+        short_entry.set_attrs(attrs);
+    }
+
+    /**
+     * Allocates a free cluster as the end of a chain, linked to the given
+     * previous cluster
+     */
+    fn allocate_cluster(&mut self, prev_cluster: Option<u32>) -> FatFsResult<u32> {
+        let clusters_end = self.m_boot_sector.clusters_count() + 2;
+        let search_start = self.m_next_free_cluster.clamp(2, clusters_end - 1);
+
+        let free_cluster = {
+            let mut found_cluster = None;
+            for cluster in (search_start..clusters_end).chain(2..search_start) {
+                if self.fat_entry(cluster)? == 0 {
+                    found_cluster = Some(cluster);
+                    break;
+                }
+            }
+            found_cluster.ok_or((OsErrorClass::LimitReached, Some("No free clusters")))?
+        };
+
+        let end_of_chain = self.m_boot_sector.fat_type().end_of_chain();
+        self.set_fat_entry(free_cluster, end_of_chain)?;
+        if let Some(prev_cluster) = prev_cluster {
+            self.set_fat_entry(prev_cluster, free_cluster)?;
+        }
+
+        self.m_free_clusters =
+            self.m_free_clusters.map(|free_clusters| free_clusters - 1);
+        self.m_next_free_cluster = free_cluster + 1;
+        Ok(free_cluster)
+    }
+
+    /**
+     * Releases all the clusters of the chain which starts from the given
+     * cluster
+     */
+    fn free_chain(&mut self, first_cluster: u32) -> FatFsResult<()> {
+        for cluster in self.cluster_chain(first_cluster)? {
+            self.set_fat_entry(cluster, 0)?;
+            self.m_free_clusters =
+                self.m_free_clusters.map(|free_clusters| free_clusters + 1);
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns the value of the FAT entry of the given cluster
+     */
+    fn fat_entry(&mut self, cluster: u32) -> FatFsResult<u32> {
+        match self.m_boot_sector.fat_type() {
+            FatType::Fat12 => {
+                let entry_offset = cluster as u64 * 3 / 2;
+                let raw_value = self.fat_byte(entry_offset)? as u32
+                                | (self.fat_byte(entry_offset + 1)? as u32) << 8;
+                if cluster & 1 != 0 {
+                    Ok(raw_value >> 4)
+                } else {
+                    Ok(raw_value & 0xFFF)
+                }
+            },
+            FatType::Fat16 => {
+                let entry_offset = cluster as u64 * 2;
+                Ok(self.fat_byte(entry_offset)? as u32
+                   | (self.fat_byte(entry_offset + 1)? as u32) << 8)
+            },
+            FatType::Fat32 => {
+                let entry_offset = cluster as u64 * 4;
+                let mut raw_value = 0;
+                for byte_index in 0..4 {
+                    raw_value |= (self.fat_byte(entry_offset + byte_index)? as u32)
+                                 << (byte_index * 8);
+                }
+                Ok(raw_value & 0x0FFF_FFFF)
+            }
+        }
+    }
+
+    /**
+     * Sets the value of the FAT entry of the given cluster
+     */
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) -> FatFsResult<()> {
+        match self.m_boot_sector.fat_type() {
+            FatType::Fat12 => {
+                let entry_offset = cluster as u64 * 3 / 2;
+                let low_byte = self.fat_byte(entry_offset)?;
+                let high_byte = self.fat_byte(entry_offset + 1)?;
+
+                /* the odd entries use the high nibble of the first byte */
+                if cluster & 1 != 0 {
+                    self.set_fat_byte(entry_offset,
+                                      (low_byte & 0x0F) | ((value << 4) as u8 & 0xF0))?;
+                    self.set_fat_byte(entry_offset + 1, (value >> 4) as u8)
+                } else {
+                    self.set_fat_byte(entry_offset, value as u8)?;
+                    self.set_fat_byte(entry_offset + 1,
+                                      (high_byte & 0xF0) | ((value >> 8) as u8 & 0x0F))
+                }
+            },
+            FatType::Fat16 => {
+                let entry_offset = cluster as u64 * 2;
+                self.set_fat_byte(entry_offset, value as u8)?;
+                self.set_fat_byte(entry_offset + 1, (value >> 8) as u8)
+            },
+            FatType::Fat32 => {
+                /* the highest four bits are reserved and preserved */
+                let entry_offset = cluster as u64 * 4;
+                let reserved_bits = self.fat_byte(entry_offset + 3)? & 0xF0;
+                let value = value & 0x0FFF_FFFF | (reserved_bits as u32) << 24;
+                for byte_index in 0..4 {
+                    self.set_fat_byte(entry_offset + byte_index,
+                                      (value >> (byte_index * 8)) as u8)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * Returns the byte of the FAT at the given offset
+     */
+    fn fat_byte(&mut self, fat_offset: u64) -> FatFsResult<u8> {
+        let byte_index = self.load_fat_sector(fat_offset)?;
+        Ok(self.m_fat_sector[byte_index])
+    }
+
+    /**
+     * Sets the byte of the FAT at the given offset
+     */
+    fn set_fat_byte(&mut self, fat_offset: u64, value: u8) -> FatFsResult<()> {
+        let byte_index = self.load_fat_sector(fat_offset)?;
+        self.m_fat_sector[byte_index] = value;
+        self.m_fat_sector_dirty = true;
+        Ok(())
+    }
+
+    /**
+     * Loads into the cache the FAT sector which contains the given offset
+     * and returns the index of the byte into the sector
+     */
+    fn load_fat_sector(&mut self, fat_offset: u64) -> FatFsResult<usize> {
+        let bytes_per_sector = self.m_boot_sector.bytes_per_sector() as u64;
+        let fat_sector_index = fat_offset / bytes_per_sector;
+        if fat_sector_index >= self.m_boot_sector.fat_sectors() {
+            return Err((OsErrorClass::LimitOverflow, Some("Cluster out of FAT")));
+        }
+
+        if self.m_fat_sector_index != Some(fat_sector_index) {
+            self.flush_fat_sector()?;
+
+            let first_active_fat = self.m_boot_sector.active_fats().start as u64;
+            let sector = self.m_boot_sector.fat_first_sector()
+                         + first_active_fat * self.m_boot_sector.fat_sectors()
+                         + fat_sector_index;
+            let mut fat_sector = core::mem::take(&mut self.m_fat_sector);
+            let read_result = self.read_sectors(sector, &mut fat_sector);
+            self.m_fat_sector = fat_sector;
+            read_result?;
+
+            self.m_fat_sector_index = Some(fat_sector_index);
+        }
+        Ok((fat_offset % bytes_per_sector) as usize)
+    }
+
+    /**
+     * Writes back the cached FAT sector to all the active FATs
+     */
+    fn flush_fat_sector(&mut self) -> FatFsResult<()> {
+        if let (Some(fat_sector_index), true) =
+            (self.m_fat_sector_index, self.m_fat_sector_dirty)
+        {
+            let fat_sector = core::mem::take(&mut self.m_fat_sector);
+            let mut write_result = Ok(());
+            for fat_index in self.m_boot_sector.active_fats() {
+                let sector = self.m_boot_sector.fat_first_sector()
+                             + fat_index as u64 * self.m_boot_sector.fat_sectors()
+                             + fat_sector_index;
+                write_result =
+                    write_result.and_then(|_| self.write_sectors(sector, &fat_sector));
+            }
+            self.m_fat_sector = fat_sector;
+            write_result?;
+
+            self.m_fat_sector_dirty = false;
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns the content of the given directory
+     */
+    fn read_dir(&mut self, dir: DirLocation) -> FatFsResult<Vec<u8>> {
+        match dir {
+            DirLocation::FixedRoot => {
+                let bytes_per_sector = self.m_boot_sector.bytes_per_sector();
+                let root_dir_len =
+                    self.m_boot_sector.root_entries() * ShortDirEntry::ENCODED_SIZE;
+                let mut raw_dir =
+                    vec![0; root_dir_len.div_ceil(bytes_per_sector) * bytes_per_sector];
+                self.read_sectors(self.m_boot_sector.root_dir_first_sector(),
+                                  &mut raw_dir)?;
+
+                raw_dir.truncate(root_dir_len);
+                Ok(raw_dir)
+            },
+            DirLocation::Chain(first_cluster) => {
+                let cluster_size = self.cluster_size();
+                let chain = self.cluster_chain(first_cluster)?;
+
+                let mut raw_dir = vec![0; chain.len() * cluster_size];
+                for (cluster, raw_cluster) in
+                    chain.iter().zip(raw_dir.chunks_exact_mut(cluster_size))
+                {
+                    self.read_cluster(*cluster, raw_cluster)?;
+                }
+                Ok(raw_dir)
+            }
+        }
+    }
+
+    /**
+     * Reads the encoded entry at the given index of the given directory
+     */
+    fn read_dir_entry(&mut self,
+                      dir: DirLocation,
+                      entry_index: u32)
+                      -> FatFsResult<[u8; ShortDirEntry::ENCODED_SIZE]> {
+        let (sector, sector_offset) = self.dir_entry_sector(dir, entry_index)?;
+
+        let mut raw_sector = vec![0; self.m_boot_sector.bytes_per_sector()];
+        self.read_sectors(sector, &mut raw_sector)?;
+
+        let mut raw_entry = [0; ShortDirEntry::ENCODED_SIZE];
+        raw_entry.copy_from_slice(&raw_sector[sector_offset
+                                              ..sector_offset
+                                                + ShortDirEntry::ENCODED_SIZE]);
+        Ok(raw_entry)
+    }
+
+    /**
+     * Writes the given encoded entries starting from the given index of
+     * the given directory
+     */
+    fn write_dir_entries(&mut self,
+                         dir: DirLocation,
+                         first_entry_index: u32,
+                         raw_entries: &[u8])
+                         -> FatFsResult<()> {
+        let mut raw_sector = vec![0; self.m_boot_sector.bytes_per_sector()];
+        let mut loaded_sector = None;
+        for (entry_index, raw_entry) in
+            (first_entry_index..).zip(raw_entries.as_chunks::<{ ShortDirEntry::ENCODED_SIZE }>().0.iter())
+        {
+            let (sector, sector_offset) = self.dir_entry_sector(dir, entry_index)?;
+
+            /* write each touched sector only once */
+            if loaded_sector != Some(sector) {
+                if let Some(loaded_sector) = loaded_sector {
+                    self.write_sectors(loaded_sector, &raw_sector)?;
+                }
+                self.read_sectors(sector, &mut raw_sector)?;
+                loaded_sector = Some(sector);
+            }
+            raw_sector[sector_offset..sector_offset + ShortDirEntry::ENCODED_SIZE]
+                .copy_from_slice(raw_entry);
+        }
+
+        if let Some(loaded_sector) = loaded_sector {
+            self.write_sectors(loaded_sector, &raw_sector)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns the sector and the offset into it of the entry at the given
+     * index of the given directory
+     */
+    fn dir_entry_sector(&mut self,
+                        dir: DirLocation,
+                        entry_index: u32)
+                        -> FatFsResult<(u64, usize)> {
+        let bytes_per_sector = self.m_boot_sector.bytes_per_sector();
+        let entry_offset = entry_index as usize * ShortDirEntry::ENCODED_SIZE;
+
+        match dir {
+            DirLocation::FixedRoot => {
+                if entry_index as usize >= self.m_boot_sector.root_entries() {
+                    return Err((OsErrorClass::LimitOverflow,
+                                Some("Entry out of directory")));
+                }
+                Ok((self.m_boot_sector.root_dir_first_sector()
+                    + (entry_offset / bytes_per_sector) as u64,
+                    entry_offset % bytes_per_sector))
+            },
+            DirLocation::Chain(first_cluster) => {
+                let cluster_size = self.cluster_size();
+                let cluster = self.cluster_chain(first_cluster)?
+                                  .get(entry_offset / cluster_size)
+                                  .copied()
+                                  .ok_or((OsErrorClass::LimitOverflow,
+                                          Some("Entry out of directory")))?;
+                let cluster_offset = entry_offset % cluster_size;
+                Ok((self.m_boot_sector.cluster_first_sector(cluster)
+                    + (cluster_offset / bytes_per_sector) as u64,
+                    cluster_offset % bytes_per_sector))
+            }
+        }
+    }
+
+    /**
+     * Fills with zeroes the given cluster
+     */
+    fn zero_cluster(&mut self, cluster: u32) -> FatFsResult<()> {
+        let zero_cluster = vec![0; self.cluster_size()];
+        self.write_cluster(cluster, &zero_cluster)
+    }
+
+    /**
+     * Reads the given cluster into the buffer
+     */
+    fn read_cluster(&mut self, cluster: u32, buffer: &mut [u8]) -> FatFsResult<()> {
+        let sector = self.m_boot_sector.cluster_first_sector(cluster);
+        self.read_sectors(sector, buffer)
+    }
+
+    /**
+     * Writes the given cluster with the buffer
+     */
+    fn write_cluster(&mut self, cluster: u32, buffer: &[u8]) -> FatFsResult<()> {
+        let sector = self.m_boot_sector.cluster_first_sector(cluster);
+        self.write_sectors(sector, buffer)
+    }
+
+    /**
+     * Reads the sectors starting from the given one into the buffer
+     */
+    fn read_sectors(&mut self, first_sector: u64, buffer: &mut [u8]) -> FatFsResult<()> {
+        self.m_device.read_blocks(first_sector * self.m_device_blocks_per_sector, buffer)
+    }
+
+    /**
+     * Writes the sectors starting from the given one with the buffer
+     */
+    fn write_sectors(&mut self, first_sector: u64, buffer: &[u8]) -> FatFsResult<()> {
+        self.m_device.write_blocks(first_sector * self.m_device_blocks_per_sector, buffer)
+    }
+}
@@ -0,0 +1,214 @@
+/*! ISO9660 volume descriptors
+ *
+ * ```text
+ * -------------------------------------------------------------------------
+ * | System Area (16 sectors) | Descriptors ... | Terminator | Extents ... |
+ * -------------------------------------------------------------------------
+ * ```
+ *
+ * The descriptors set starts at the sixteenth logical sector and ends with
+ * the terminator descriptor. The primary descriptor describes the plain
+ * ISO9660 hierarchy, the Joliet one is a supplementary descriptor with the
+ * UCS-2 escape sequences, which references another hierarchy sharing the
+ * same file extents.
+ *
+ * The numbers are stored in both the byte orders, only the little endian
+ * one is read
+ */
+
+use alloc::string::String;
+
+use api_data::error::class::OsErrorClass;
+
+use crate::{
+    dir_record::{
+        joliet_str,
+        DirRecord
+    },
+    IsoFsResult
+};
+
+/**
+ * Lists the types of the volume descriptors
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum DescriptorType {
+    BootRecord,
+    Primary,
+    Supplementary,
+    Partition,
+    Terminator,
+    Unknown
+}
+
+impl From<u8> for DescriptorType {
+    fn from(raw_type: u8) -> Self {
+        match raw_type {
+            0 => Self::BootRecord,
+            1 => Self::Primary,
+            2 => Self::Supplementary,
+            3 => Self::Partition,
+            255 => Self::Terminator,
+            _ => Self::Unknown
+        }
+    }
+}
+
+/**
+ * Primary or supplementary volume descriptor
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct VolumeDescriptor {
+    m_type: DescriptorType,
+    m_is_joliet: bool,
+    m_volume_id: String,
+    m_volume_space_size: u32,
+    m_logical_block_size: u16,
+    m_root_record: DirRecord
+}
+
+impl VolumeDescriptor /* Constants */ {
+    /**
+     * Size of each descriptor, which is the size of a CD-ROM sector
+     */
+    pub const ENCODED_SIZE: usize = 2048;
+
+    /**
+     * Logical sector of the first descriptor
+     */
+    pub const FIRST_SECTOR: u64 = 16;
+
+    /**
+     * Maximum amount of descriptors read before the terminator
+     */
+    pub const DESCRIPTORS_MAX: u64 = 64;
+
+    const STANDARD_ID: &'static [u8] = b"CD001";
+    const ROOT_RECORD_OFFSET: usize = 156;
+    const JOLIET_ESCAPES: [&'static [u8]; 3] = [b"%/@", b"%/C", b"%/E"];
+}
+
+impl VolumeDescriptor /* Constructors */ {
+    /**
+     * Decodes the descriptor from the given buffer, `None` is returned for
+     * the descriptors which don't describe a hierarchy
+     */
+    pub fn decode(raw_descriptor: &[u8]) -> IsoFsResult<Option<Self>> {
+        if raw_descriptor.len() < Self::ENCODED_SIZE
+           || &raw_descriptor[1..6] != Self::STANDARD_ID
+        {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not an ISO9660 descriptor")));
+        }
+
+        let descriptor_type = DescriptorType::from(raw_descriptor[0]);
+        if !matches!(descriptor_type,
+                     DescriptorType::Primary | DescriptorType::Supplementary)
+        {
+            return Ok(None);
+        }
+
+        /* the supplementary descriptors are Joliet only with the UCS-2
+         * escapes */
+        let is_joliet =
+            descriptor_type == DescriptorType::Supplementary
+            && Self::JOLIET_ESCAPES.iter()
+                                   .any(|escape| &raw_descriptor[88..91] == *escape);
+        if descriptor_type == DescriptorType::Supplementary && !is_joliet {
+            return Ok(None);
+        }
+
+        let raw_volume_id = &raw_descriptor[40..72];
+        let volume_id = if is_joliet {
+            joliet_str(raw_volume_id)
+        } else {
+            String::from_utf8_lossy(raw_volume_id).into_owned()
+        };
+
+        let logical_block_size = read_u16(raw_descriptor, 128);
+        if !logical_block_size.is_power_of_two() || logical_block_size < 512 {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Invalid ISO9660 block size")));
+        }
+
+        let raw_root_record =
+            &raw_descriptor[Self::ROOT_RECORD_OFFSET..Self::ROOT_RECORD_OFFSET + 34];
+        Ok(Some(Self { m_type: descriptor_type,
+                       m_is_joliet: is_joliet,
+                       m_volume_id: String::from(volume_id.trim_end()),
+                       m_volume_space_size: read_u32(raw_descriptor, 80),
+                       m_logical_block_size: logical_block_size,
+                       m_root_record: DirRecord::decode(raw_root_record)? }))
+    }
+
+    /**
+     * Returns whether the given descriptor is the terminator of the set
+     */
+    pub fn is_terminator(raw_descriptor: &[u8]) -> bool {
+        DescriptorType::from(raw_descriptor[0]) == DescriptorType::Terminator
+        && &raw_descriptor[1..6] == Self::STANDARD_ID
+    }
+}
+
+impl VolumeDescriptor /* Getters */ {
+    /**
+     * Returns the `DescriptorType`
+     */
+    pub fn descriptor_type(&self) -> DescriptorType {
+        self.m_type
+    }
+
+    /**
+     * Returns whether this is the Joliet supplementary descriptor
+     */
+    pub fn is_joliet(&self) -> bool {
+        self.m_is_joliet
+    }
+
+    /**
+     * Returns the name of the volume
+     */
+    pub fn volume_id(&self) -> &str {
+        self.m_volume_id.as_str()
+    }
+
+    /**
+     * Returns the amount of logical blocks of the volume
+     */
+    pub fn volume_space_size(&self) -> u32 {
+        self.m_volume_space_size
+    }
+
+    /**
+     * Returns the size in bytes of the logical blocks
+     */
+    pub fn logical_block_size(&self) -> usize {
+        self.m_logical_block_size as usize
+    }
+
+    /**
+     * Returns the `DirRecord` of the root directory
+     */
+    pub fn root_record(&self) -> &DirRecord {
+        &self.m_root_record
+    }
+}
+
+/**
+ * Reads the little endian half of a both-endian `u16`
+ */
+pub(crate) fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+/**
+ * Reads the little endian half of a both-endian `u32`
+ */
+pub(crate) fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([buffer[offset],
+                        buffer[offset + 1],
+                        buffer[offset + 2],
+                        buffer[offset + 3]])
+}
@@ -0,0 +1,220 @@
+/*! ISO9660 directory records
+ *
+ * ```text
+ * -------------------------------------------------------------------------
+ * | Len | ExtAttr | Extent | DataLen | Date | Flags | ... | Name | SysUse |
+ * -------------------------------------------------------------------------
+ * ```
+ *
+ * Each directory is a sequence of variable size records, which never
+ * cross the logical sectors. The first two records of each directory are
+ * the links to itself (named `0x00`) and to his parent (named `0x01`), the
+ * system use area which follows the name contains the Rock Ridge entries
+ */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant
+};
+
+use crate::{
+    descriptor::read_u32,
+    time::bin_timestamp_instant,
+    IsoFsResult
+};
+
+/**
+ * Directory record of a file or a directory
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DirRecord {
+    m_extent: u32,
+    m_data_size: u32,
+    m_recording_inst: RawInstant,
+    m_flags: u8,
+    m_raw_name: Vec<u8>,
+    m_system_use: Vec<u8>
+}
+
+impl DirRecord /* Constants */ {
+    /**
+     * Size of the record without the name and the system use area
+     */
+    pub const FIXED_SIZE: usize = 33;
+
+    const FLAG_HIDDEN: u8 = 1 << 0;
+    const FLAG_DIRECTORY: u8 = 1 << 1;
+    const FLAG_ASSOCIATED: u8 = 1 << 2;
+    const FLAG_MULTI_EXTENT: u8 = 1 << 7;
+
+    const SELF_LINK_NAME: u8 = 0x00;
+    const PARENT_LINK_NAME: u8 = 0x01;
+}
+
+impl DirRecord /* Constructors */ {
+    /**
+     * Decodes the record from the given buffer, which starts with the
+     * length of the record
+     */
+    pub fn decode(raw_record: &[u8]) -> IsoFsResult<Self> {
+        let record_len = *raw_record.first().unwrap_or(&0) as usize;
+        if record_len <= Self::FIXED_SIZE || record_len > raw_record.len() {
+            return Err((OsErrorClass::TypesNotMatch, Some("Corrupted ISO9660 record")));
+        }
+
+        let name_len = raw_record[32] as usize;
+        let name_end = Self::FIXED_SIZE + name_len;
+        if name_end > record_len {
+            return Err((OsErrorClass::TypesNotMatch, Some("Corrupted ISO9660 record")));
+        }
+
+        /* the system use area starts at the even offset after the name */
+        let system_use_start = name_end + (name_end % 2);
+        let system_use = if system_use_start < record_len {
+            raw_record[system_use_start..record_len].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { m_extent: read_u32(raw_record, 2),
+                  m_data_size: read_u32(raw_record, 10),
+                  m_recording_inst: bin_timestamp_instant(&raw_record[18..25]),
+                  m_flags: raw_record[25],
+                  m_raw_name: raw_record[Self::FIXED_SIZE..name_end].to_vec(),
+                  m_system_use: system_use })
+    }
+}
+
+impl DirRecord /* Methods */ {
+    /**
+     * Returns the name without the version and the empty extension, in
+     * lowercase since the plain ISO9660 allows only uppercase names
+     */
+    pub fn iso_name(&self) -> String {
+        let mut iso_name = String::from_utf8_lossy(&self.m_raw_name).into_owned();
+        strip_version(&mut iso_name);
+        if iso_name.ends_with('.') {
+            iso_name.pop();
+        }
+        iso_name.to_ascii_lowercase()
+    }
+
+    /**
+     * Returns the name decoded as Joliet UCS-2, without the version
+     */
+    pub fn joliet_name(&self) -> String {
+        let mut joliet_name = joliet_str(&self.m_raw_name);
+        strip_version(&mut joliet_name);
+        joliet_name
+    }
+
+    /**
+     * Returns whether this record is the link to the directory itself
+     */
+    pub fn is_self_link(&self) -> bool {
+        self.m_raw_name == [Self::SELF_LINK_NAME]
+    }
+
+    /**
+     * Returns whether this record is the link to the parent directory
+     */
+    pub fn is_parent_link(&self) -> bool {
+        self.m_raw_name == [Self::PARENT_LINK_NAME]
+    }
+}
+
+impl DirRecord /* Getters */ {
+    /**
+     * Returns the first logical block of the data
+     */
+    pub fn extent(&self) -> u32 {
+        self.m_extent
+    }
+
+    /**
+     * Returns the size in bytes of the data of this record
+     */
+    pub fn data_size(&self) -> u32 {
+        self.m_data_size
+    }
+
+    /**
+     * Returns the recording `RawInstant`
+     */
+    pub fn recording_inst(&self) -> RawInstant {
+        self.m_recording_inst
+    }
+
+    /**
+     * Returns whether the record must not be shown to the user
+     */
+    pub fn is_hidden(&self) -> bool {
+        self.m_flags & Self::FLAG_HIDDEN != 0
+    }
+
+    /**
+     * Returns whether the record references a directory
+     */
+    pub fn is_dir(&self) -> bool {
+        self.m_flags & Self::FLAG_DIRECTORY != 0
+    }
+
+    /**
+     * Returns whether the record is an associated file (i.e. a resource
+     * fork), which is never shown
+     */
+    pub fn is_associated(&self) -> bool {
+        self.m_flags & Self::FLAG_ASSOCIATED != 0
+    }
+
+    /**
+     * Returns whether the data continues into the extent of the next
+     * record with the same name
+     */
+    pub fn is_multi_extent(&self) -> bool {
+        self.m_flags & Self::FLAG_MULTI_EXTENT != 0
+    }
+
+    /**
+     * Returns the raw identifier of the record
+     */
+    pub fn raw_name(&self) -> &[u8] {
+        self.m_raw_name.as_slice()
+    }
+
+    /**
+     * Returns the system use area, which contains the SUSP entries
+     */
+    pub fn system_use(&self) -> &[u8] {
+        self.m_system_use.as_slice()
+    }
+}
+
+/**
+ * Decodes the given big endian UCS-2 (UTF-16 for the recent tools) string
+ */
+pub fn joliet_str(raw_str: &[u8]) -> String {
+    let code_units =
+        raw_str.as_chunks::<2>().0.iter().map(|raw_unit| u16::from_be_bytes(*raw_unit));
+    char::decode_utf16(code_units).map(|decoded| {
+                                      decoded.unwrap_or(char::REPLACEMENT_CHARACTER)
+                                  })
+                                  .collect()
+}
+
+/**
+ * Removes the `;<version>` suffix of the ISO9660 file identifiers
+ */
+fn strip_version(name: &mut String) {
+    if let Some(separator_index) = name.rfind(';') {
+        if name[separator_index + 1..].bytes().all(|byte| byte.is_ascii_digit()) {
+            name.truncate(separator_index);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+/*! # ISO9660 Filesystem Library
+ *
+ * Implements the read-only ECMA-119 (ISO9660) filesystem of the CD-ROMs,
+ * with the Rock Ridge (SUSP/RRIP) and the Joliet name extensions, so the
+ * images created by the common tools (i.e. `xorriso`, `genisoimage` and
+ * `grub-mkrescue`) show the same names given on the host.
+ *
+ * The media is accessed through the `meetix_fs::dev::TBlockDevice`, so the
+ * same devices are shared by all the block filesystems
+ */
+
+#![no_std]
+
+#[macro_use]
+extern crate alloc;
+
+use api_data::error::class::OsErrorClass;
+
+pub mod descriptor;
+pub mod dir_record;
+pub mod rock_ridge;
+pub mod time;
+pub mod volume;
+
+/**
+ * Result type returned by the filesystem operations
+ */
+pub type IsoFsResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
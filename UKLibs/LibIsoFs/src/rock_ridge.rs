@@ -0,0 +1,362 @@
+/*! Rock Ridge extensions
+ *
+ * The System Use Sharing Protocol (SUSP) stores into the system use area
+ * of the directory records a sequence of entries made by a two letters
+ * signature, the length and the version. The Rock Ridge Interchange
+ * Protocol (RRIP) uses them to store the POSIX names, attributes,
+ * timestamps and symbolic links.
+ *
+ * When an area is full, the `CE` entry continues it into another block
+ */
+
+use alloc::string::String;
+
+use api_data::instant::RawInstant;
+
+use crate::{
+    descriptor::read_u32,
+    time::{
+        bin_timestamp_instant,
+        dec_timestamp_instant,
+        BIN_TIMESTAMP_SIZE,
+        DEC_TIMESTAMP_SIZE
+    }
+};
+
+/**
+ * Area of another block where the SUSP entries continue
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub struct ContinuationArea {
+    m_block: u32,
+    m_offset: u32,
+    m_len: u32
+}
+
+impl ContinuationArea /* Getters */ {
+    /**
+     * Returns the logical block of the area
+     */
+    pub fn block(&self) -> u32 {
+        self.m_block
+    }
+
+    /**
+     * Returns the offset of the area into the block
+     */
+    pub fn offset(&self) -> u32 {
+        self.m_offset
+    }
+
+    /**
+     * Returns the size in bytes of the area
+     */
+    pub fn len(&self) -> u32 {
+        self.m_len
+    }
+
+    /**
+     * Returns whether the area is empty
+     */
+    pub fn is_empty(&self) -> bool {
+        self.m_len == 0
+    }
+}
+
+/**
+ * Rock Ridge informations collected from the SUSP entries of a record
+ */
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(Clone)]
+pub struct RockRidgeInfo {
+    m_name: Option<String>,
+    m_mode: Option<u32>,
+    m_links: Option<u32>,
+    m_uid: Option<u32>,
+    m_gid: Option<u32>,
+    m_link_target: Option<String>,
+    m_link_continues: bool,
+    m_creat_inst: Option<RawInstant>,
+    m_modify_inst: Option<RawInstant>,
+    m_access_inst: Option<RawInstant>,
+    m_child_link: Option<u32>,
+    m_is_relocated: bool
+}
+
+impl RockRidgeInfo /* Constants */ {
+    /**
+     * Mask of the file type bits of the POSIX mode
+     */
+    pub const MODE_TYPE_MASK: u32 = 0o170000;
+
+    /**
+     * File type of the POSIX symbolic links
+     */
+    pub const MODE_LINK: u32 = 0o120000;
+
+    /**
+     * Minimum size of a SUSP entry, which is its header
+     */
+    const ENTRY_HEADER_SIZE: usize = 4;
+
+    /* <NM> flags */
+    const NM_CURRENT: u8 = 1 << 1;
+    const NM_PARENT: u8 = 1 << 2;
+
+    /* <SL> component flags */
+    const SL_CONTINUE: u8 = 1 << 0;
+    const SL_CURRENT: u8 = 1 << 1;
+    const SL_PARENT: u8 = 1 << 2;
+    const SL_ROOT: u8 = 1 << 3;
+
+    /* <TF> flags */
+    const TF_CREATION: u8 = 1 << 0;
+    const TF_MODIFY: u8 = 1 << 1;
+    const TF_ACCESS: u8 = 1 << 2;
+    const TF_LONG_FORM: u8 = 1 << 7;
+}
+
+impl RockRidgeInfo /* Methods */ {
+    /**
+     * Collects the entries of the given system use area and returns the
+     * `ContinuationArea` when the entries continue elsewhere
+     */
+    pub fn parse_area(&mut self, area: &[u8]) -> Option<ContinuationArea> {
+        let mut continuation_area = None;
+
+        let mut entry_offset = 0;
+        while entry_offset + Self::ENTRY_HEADER_SIZE <= area.len() {
+            let entry_len = area[entry_offset + 2] as usize;
+            if entry_len < Self::ENTRY_HEADER_SIZE
+               || entry_offset + entry_len > area.len()
+            {
+                break;
+            }
+
+            let raw_entry = &area[entry_offset..entry_offset + entry_len];
+            match &raw_entry[..2] {
+                b"ST" => break,
+                b"CE" if entry_len >= 28 => {
+                    continuation_area =
+                        Some(ContinuationArea { m_block: read_u32(raw_entry, 4),
+                                                m_offset: read_u32(raw_entry, 12),
+                                                m_len: read_u32(raw_entry, 20) })
+                },
+                b"PX" if entry_len >= 36 => {
+                    self.m_mode = Some(read_u32(raw_entry, 4));
+                    self.m_links = Some(read_u32(raw_entry, 12));
+                    self.m_uid = Some(read_u32(raw_entry, 20));
+                    self.m_gid = Some(read_u32(raw_entry, 28));
+                },
+                b"NM" if entry_len >= 5 => self.parse_name(raw_entry),
+                b"SL" if entry_len >= 5 => self.parse_link(raw_entry),
+                b"TF" if entry_len >= 5 => self.parse_timestamps(raw_entry),
+                b"CL" if entry_len >= 12 => {
+                    self.m_child_link = Some(read_u32(raw_entry, 4))
+                },
+                b"RE" => self.m_is_relocated = true,
+                _ => { /* not interesting entry */ }
+            }
+            entry_offset += entry_len;
+        }
+        continuation_area
+    }
+}
+
+impl RockRidgeInfo /* Getters */ {
+    /**
+     * Returns the POSIX name
+     */
+    pub fn name(&self) -> Option<&str> {
+        self.m_name.as_deref()
+    }
+
+    /**
+     * Returns the POSIX mode
+     */
+    pub fn mode(&self) -> Option<u32> {
+        self.m_mode
+    }
+
+    /**
+     * Returns the amount of hard links
+     */
+    pub fn links(&self) -> Option<u32> {
+        self.m_links
+    }
+
+    /**
+     * Returns the POSIX user identifier
+     */
+    pub fn uid(&self) -> Option<u32> {
+        self.m_uid
+    }
+
+    /**
+     * Returns the POSIX group identifier
+     */
+    pub fn gid(&self) -> Option<u32> {
+        self.m_gid
+    }
+
+    /**
+     * Returns whether the record is a symbolic link
+     */
+    pub fn is_link(&self) -> bool {
+        self.m_link_target.is_some()
+        || self.m_mode.is_some_and(|mode| mode & Self::MODE_TYPE_MASK == Self::MODE_LINK)
+    }
+
+    /**
+     * Returns the path referenced by the symbolic link
+     */
+    pub fn link_target(&self) -> Option<&str> {
+        self.m_link_target.as_deref()
+    }
+
+    /**
+     * Returns the creation `RawInstant`
+     */
+    pub fn creat_inst(&self) -> Option<RawInstant> {
+        self.m_creat_inst
+    }
+
+    /**
+     * Returns the last data modification `RawInstant`
+     */
+    pub fn modify_inst(&self) -> Option<RawInstant> {
+        self.m_modify_inst
+    }
+
+    /**
+     * Returns the last data access `RawInstant`
+     */
+    pub fn access_inst(&self) -> Option<RawInstant> {
+        self.m_access_inst
+    }
+
+    /**
+     * Returns the logical block of the relocated directory which this
+     * record replaces
+     */
+    pub fn child_link(&self) -> Option<u32> {
+        self.m_child_link
+    }
+
+    /**
+     * Returns whether this is the record of a relocated directory, which
+     * must be hidden from his fake parent
+     */
+    pub fn is_relocated(&self) -> bool {
+        self.m_is_relocated
+    }
+}
+
+impl RockRidgeInfo /* Privates */ {
+    /**
+     * Collects the `NM` entry, the name could be split into more entries
+     */
+    fn parse_name(&mut self, raw_entry: &[u8]) {
+        let name_flags = raw_entry[4];
+        if name_flags & (Self::NM_CURRENT | Self::NM_PARENT) != 0 {
+            return;
+        }
+
+        /* the name continues into the next <NM> entry with the continue flag */
+        let name = self.m_name.get_or_insert_with(String::new);
+        name.push_str(&String::from_utf8_lossy(&raw_entry[5..]));
+    }
+
+    /**
+     * Collects the components of the `SL` entry, the target could be split
+     * into more entries
+     */
+    fn parse_link(&mut self, raw_entry: &[u8]) {
+        let link_target = self.m_link_target.get_or_insert_with(String::new);
+
+        let mut component_offset = 5;
+        while component_offset + 2 <= raw_entry.len() {
+            let component_flags = raw_entry[component_offset];
+            let component_len = raw_entry[component_offset + 1] as usize;
+            let component_end = component_offset + 2 + component_len;
+            if component_end > raw_entry.len() {
+                break;
+            }
+
+            /* the previous component could continue into this one */
+            if !self.m_link_continues
+               && !link_target.is_empty()
+               && !link_target.ends_with('/')
+            {
+                link_target.push('/');
+            }
+
+            if component_flags & Self::SL_ROOT != 0 {
+                link_target.clear();
+                link_target.push('/');
+            } else if component_flags & Self::SL_CURRENT != 0 {
+                link_target.push('.');
+            } else if component_flags & Self::SL_PARENT != 0 {
+                link_target.push_str("..");
+            } else {
+                link_target.push_str(&String::from_utf8_lossy(&raw_entry
+                                                                  [component_offset + 2
+                                                                   ..component_end]));
+            }
+
+            self.m_link_continues = component_flags & Self::SL_CONTINUE != 0;
+            component_offset = component_end;
+        }
+    }
+
+    /**
+     * Collects the timestamps of the `TF` entry, which are stored in the
+     * order of the flags
+     */
+    fn parse_timestamps(&mut self, raw_entry: &[u8]) {
+        let timestamps_flags = raw_entry[4];
+        let (timestamp_size, decode_fn): (usize, fn(&[u8]) -> RawInstant) =
+            if timestamps_flags & Self::TF_LONG_FORM != 0 {
+                (DEC_TIMESTAMP_SIZE, dec_timestamp_instant)
+            } else {
+                (BIN_TIMESTAMP_SIZE, bin_timestamp_instant)
+            };
+
+        let mut timestamp_offset = 5;
+        for timestamp_bit in 0..7 {
+            if timestamps_flags & (1 << timestamp_bit) == 0 {
+                continue;
+            }
+            if timestamp_offset + timestamp_size > raw_entry.len() {
+                break;
+            }
+
+            let instant = decode_fn(&raw_entry[timestamp_offset
+                                               ..timestamp_offset + timestamp_size]);
+            match 1 << timestamp_bit {
+                Self::TF_CREATION => self.m_creat_inst = Some(instant),
+                Self::TF_MODIFY => self.m_modify_inst = Some(instant),
+                Self::TF_ACCESS => self.m_access_inst = Some(instant),
+                _ => { /* attributes change, backup, expiration and effective */ }
+            }
+            timestamp_offset += timestamp_size;
+        }
+    }
+}
+
+/**
+ * Returns the amount of bytes to skip at the start of each system use
+ * area when the given one (of the first record of the root directory)
+ * contains the `SP` entry, which marks the presence of the SUSP
+ */
+pub fn susp_skip_len(root_area: &[u8]) -> Option<usize> {
+    if root_area.len() >= 7 && &root_area[..2] == b"SP" && root_area[4..6] == [0xBE, 0xEF]
+    {
+        Some(root_area[6] as usize)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,122 @@
+/*! ISO9660 timestamps
+ *
+ * The directory records store the recording date in seven binary bytes,
+ * while the volume descriptors (and the long form of the Rock Ridge `TF`
+ * entries) store the dates as seventeen ASCII digits. Both the formats
+ * carry the offset from the GMT in intervals of fifteen minutes, so the
+ * `RawInstant`s returned are elapsed since the UNIX epoch in UTC
+ */
+
+use core::time::Duration;
+
+use api_data::instant::RawInstant;
+
+/**
+ * Size of the binary recording date of the directory records
+ */
+pub const BIN_TIMESTAMP_SIZE: usize = 7;
+
+/**
+ * Size of the ASCII digits date of the volume descriptors
+ */
+pub const DEC_TIMESTAMP_SIZE: usize = 17;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+const SECS_PER_GMT_OFFSET: i64 = 15 * 60;
+
+/**
+ * Decodes the binary recording date of a directory record, the zero date
+ * (never recorded) is decoded as the zero instant
+ */
+pub fn bin_timestamp_instant(raw_timestamp: &[u8]) -> RawInstant {
+    if raw_timestamp.len() < BIN_TIMESTAMP_SIZE
+       || raw_timestamp[..6].iter().all(|b| *b == 0)
+    {
+        return RawInstant::default();
+    }
+
+    utc_instant(1900 + raw_timestamp[0] as i64,
+                raw_timestamp[1] as i64,
+                raw_timestamp[2] as i64,
+                raw_timestamp[3] as i64 * 3600
+                + raw_timestamp[4] as i64 * 60
+                + raw_timestamp[5] as i64,
+                0,
+                raw_timestamp[6] as i8)
+}
+
+/**
+ * Decodes the ASCII digits date, the date made of zeroes (not specified)
+ * is decoded as the zero instant
+ */
+pub fn dec_timestamp_instant(raw_timestamp: &[u8]) -> RawInstant {
+    if raw_timestamp.len() < DEC_TIMESTAMP_SIZE
+       || !raw_timestamp[..16].iter().all(u8::is_ascii_digit)
+       || raw_timestamp[..16].iter().all(|digit| *digit == b'0')
+    {
+        return RawInstant::default();
+    }
+
+    let digits_value = |range: core::ops::Range<usize>| {
+        raw_timestamp[range].iter()
+                            .fold(0, |value, digit| value * 10 + (digit - b'0') as i64)
+    };
+    utc_instant(digits_value(0..4),
+                digits_value(4..6),
+                digits_value(6..8),
+                digits_value(8..10) * 3600
+                + digits_value(10..12) * 60
+                + digits_value(12..14),
+                digits_value(14..16) as u64 * 10,
+                raw_timestamp[16] as i8)
+}
+
+/**
+ * Returns the `RawInstant` of the given local date, the instants before
+ * the UNIX epoch are clamped to it
+ */
+fn utc_instant(year: i64,
+               month: i64,
+               day: i64,
+               day_secs: i64,
+               millis: u64,
+               gmt_offset: i8)
+               -> RawInstant {
+    let local_secs = days_from_civil(year, month.clamp(1, 12), day.clamp(1, 31))
+                     * SECS_PER_DAY
+                     + day_secs;
+    let utc_secs = local_secs - gmt_offset as i64 * SECS_PER_GMT_OFFSET;
+    if utc_secs < 0 {
+        RawInstant::default()
+    } else {
+        Duration::from_secs(utc_secs as u64) + Duration::from_millis(millis)
+    }
+}
+
+/**
+ * Returns the days elapsed since the UNIX epoch for the given date of the
+ * proleptic Gregorian calendar
+ */
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 {
+        year - 1
+    } else {
+        year
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153
+                       * (month
+                          + if month > 2 {
+                              -3
+                          } else {
+                              9
+                          })
+                       + 2)
+                      / 5
+                      + day
+                      - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
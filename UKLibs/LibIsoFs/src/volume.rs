@@ -0,0 +1,540 @@
+/*! ISO9660 volume management */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant
+};
+use meetix_fs::dev::TBlockDevice;
+
+use crate::{
+    descriptor::VolumeDescriptor,
+    dir_record::DirRecord,
+    rock_ridge::{
+        susp_skip_len,
+        RockRidgeInfo
+    },
+    IsoFsResult
+};
+
+/**
+ * Lists the hierarchies which could be read, in order of preference
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum NameKind {
+    /**
+     * Primary hierarchy with the Rock Ridge POSIX names and attributes
+     */
+    RockRidge,
+
+    /**
+     * Joliet supplementary hierarchy with the UCS-2 names
+     */
+    Joliet,
+
+    /**
+     * Primary hierarchy with the uppercase 8.3 names, shown in lowercase
+     */
+    Iso
+}
+
+/**
+ * Lists the types of the nodes of the volume
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum IsoNodeType {
+    Dir,
+    File,
+    Link
+}
+
+/**
+ * Child of a directory with his name and extents resolved
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct IsoDirRecord {
+    m_name: String,
+    m_node_type: IsoNodeType,
+    m_node_id: u64,
+    m_extents: Vec<(u32, u32)>,
+    m_data_size: u64,
+    m_recording_inst: RawInstant,
+    m_rock_ridge: Option<RockRidgeInfo>
+}
+
+impl IsoDirRecord /* Getters */ {
+    /**
+     * Returns the name of the node
+     */
+    pub fn name(&self) -> &str {
+        self.m_name.as_str()
+    }
+
+    /**
+     * Returns the `IsoNodeType`
+     */
+    pub fn node_type(&self) -> IsoNodeType {
+        self.m_node_type
+    }
+
+    /**
+     * Returns the identifier of the node, which is the position of his
+     * directory record into the volume
+     */
+    pub fn node_id(&self) -> u64 {
+        self.m_node_id
+    }
+
+    /**
+     * Returns the size in bytes of the data
+     */
+    pub fn data_size(&self) -> u64 {
+        self.m_data_size
+    }
+
+    /**
+     * Returns the recording `RawInstant`
+     */
+    pub fn recording_inst(&self) -> RawInstant {
+        self.m_recording_inst
+    }
+
+    /**
+     * Returns the `RockRidgeInfo` when the volume uses Rock Ridge
+     */
+    pub fn rock_ridge(&self) -> Option<&RockRidgeInfo> {
+        self.m_rock_ridge.as_ref()
+    }
+
+    /**
+     * Returns the path referenced by the symbolic link
+     */
+    pub fn link_target(&self) -> Option<&str> {
+        self.m_rock_ridge.as_ref().and_then(RockRidgeInfo::link_target)
+    }
+}
+
+/**
+ * Mounted ISO9660 filesystem.
+ *
+ * The volume is read-only, so nothing is cached and the records are read
+ * from the device each time
+ */
+pub struct IsoVolume<D>
+    where D: TBlockDevice {
+    m_device: D,
+    m_block_size: usize,
+    m_blocks_count: u32,
+    m_volume_id: String,
+    m_name_kind: NameKind,
+    m_susp_skip: usize,
+    m_root: IsoDirRecord
+}
+
+impl<D> IsoVolume<D> where D: TBlockDevice /* Constants */ {
+    /**
+     * Maximum amount of `CE` areas followed for each record, to stop the
+     * loops of the corrupted images
+     */
+    const CONTINUATIONS_MAX: usize = 16;
+}
+
+impl<D> IsoVolume<D> where D: TBlockDevice /* Constructors */ {
+    /**
+     * Mounts the volume stored into the given device, the Rock Ridge
+     * hierarchy is preferred to the Joliet one
+     */
+    pub fn mount(mut device: D) -> IsoFsResult<Self> {
+        let mut primary_descriptor = None;
+        let mut joliet_descriptor = None;
+
+        let mut raw_descriptor = vec![0; VolumeDescriptor::ENCODED_SIZE];
+        for descriptor_index in 0..VolumeDescriptor::DESCRIPTORS_MAX {
+            let sector = VolumeDescriptor::FIRST_SECTOR + descriptor_index;
+            read_bytes(&mut device,
+                       sector * VolumeDescriptor::ENCODED_SIZE as u64,
+                       &mut raw_descriptor)?;
+            if VolumeDescriptor::is_terminator(&raw_descriptor) {
+                break;
+            }
+
+            match VolumeDescriptor::decode(&raw_descriptor)? {
+                Some(descriptor) if descriptor.is_joliet() => {
+                    joliet_descriptor.get_or_insert(descriptor);
+                },
+                Some(descriptor) => {
+                    primary_descriptor.get_or_insert(descriptor);
+                },
+                None => { /* boot record or partition */ }
+            }
+        }
+        let primary_descriptor =
+            primary_descriptor.ok_or((OsErrorClass::TypesNotMatch,
+                                      Some("ISO9660 primary descriptor not found")))?;
+
+        let mut volume =
+            Self { m_device: device,
+                   m_block_size: primary_descriptor.logical_block_size(),
+                   m_blocks_count: primary_descriptor.volume_space_size(),
+                   m_volume_id: String::from(primary_descriptor.volume_id()),
+                   m_name_kind: NameKind::Iso,
+                   m_susp_skip: 0,
+                   m_root: IsoDirRecord { m_name: String::new(),
+                                          m_node_type: IsoNodeType::Dir,
+                                          m_node_id: 0,
+                                          m_extents: Vec::new(),
+                                          m_data_size: 0,
+                                          m_recording_inst: RawInstant::default(),
+                                          m_rock_ridge: None } };
+
+        /* the SUSP is announced by the first record of the root directory */
+        let root_record = primary_descriptor.root_record();
+        let root_self_link = volume.read_self_link(root_record.extent())?;
+        let root_descriptor =
+            if let Some(susp_skip) = susp_skip_len(root_self_link.system_use()) {
+                volume.m_name_kind = NameKind::RockRidge;
+                volume.m_susp_skip = susp_skip;
+                &primary_descriptor
+            } else if let Some(joliet_descriptor) = joliet_descriptor.as_ref() {
+                volume.m_name_kind = NameKind::Joliet;
+                volume.m_volume_id = String::from(joliet_descriptor.volume_id());
+                joliet_descriptor
+            } else {
+                &primary_descriptor
+            };
+
+        let root_record = root_descriptor.root_record();
+        let rock_ridge = if volume.m_name_kind == NameKind::RockRidge {
+            Some(volume.rock_ridge_info(root_self_link.system_use())?)
+        } else {
+            None
+        };
+        volume.m_root = IsoDirRecord { m_name: String::new(),
+                                       m_node_type: IsoNodeType::Dir,
+                                       m_node_id:
+                                           volume.block_offset(root_record.extent()),
+                                       m_extents: vec![(root_record.extent(),
+                                                        root_record.data_size())],
+                                       m_data_size: root_record.data_size() as u64,
+                                       m_recording_inst: root_record.recording_inst(),
+                                       m_rock_ridge: rock_ridge };
+        Ok(volume)
+    }
+}
+
+impl<D> IsoVolume<D> where D: TBlockDevice /* Methods */ {
+    /**
+     * Releases the volume and returns the device
+     */
+    pub fn unmount(self) -> D {
+        self.m_device
+    }
+
+    /**
+     * Returns the child of the given directory with the given name, the
+     * plain ISO9660 names are compared ignoring the case
+     */
+    pub fn lookup(&mut self,
+                  dir: &IsoDirRecord,
+                  name: &str)
+                  -> IsoFsResult<Option<IsoDirRecord>> {
+        let ignore_case = self.m_name_kind == NameKind::Iso;
+        Ok(self.dir_records(dir)?.into_iter().find(|dir_record| {
+                                                 if ignore_case {
+                                                     dir_record.name()
+                                                               .eq_ignore_ascii_case(name)
+                                                 } else {
+                                                     dir_record.name() == name
+                                                 }
+                                             }))
+    }
+
+    /**
+     * Returns the children of the given directory, without the links to
+     * itself and to the parent
+     */
+    pub fn dir_records(&mut self, dir: &IsoDirRecord) -> IsoFsResult<Vec<IsoDirRecord>> {
+        if dir.node_type() != IsoNodeType::Dir {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a directory")));
+        }
+        let (dir_extent, dir_size) = dir.m_extents[0];
+
+        let mut raw_dir = vec![0; dir_size as usize];
+        self.read_blocks_data(dir_extent, 0, &mut raw_dir)?;
+
+        let mut dir_records: Vec<IsoDirRecord> = Vec::new();
+        let mut last_continues = false;
+
+        let mut record_offset = 0;
+        while record_offset < raw_dir.len() {
+            /* the records never cross the sectors, the zero length pads them */
+            let record_len = raw_dir[record_offset] as usize;
+            if record_len == 0 {
+                record_offset =
+                    (record_offset / self.m_block_size + 1) * self.m_block_size;
+                continue;
+            }
+
+            let dir_record = DirRecord::decode(&raw_dir[record_offset..])?;
+            let node_id = self.block_offset(dir_extent) + record_offset as u64;
+            record_offset += record_len;
+
+            if dir_record.is_self_link()
+               || dir_record.is_parent_link()
+               || dir_record.is_associated()
+            {
+                continue;
+            }
+
+            /* the multi-extent files continue into the records which follow */
+            if last_continues {
+                if let Some(last_record) = dir_records.last_mut() {
+                    last_record.m_extents
+                               .push((dir_record.extent(), dir_record.data_size()));
+                    last_record.m_data_size += dir_record.data_size() as u64;
+                    last_continues = dir_record.is_multi_extent();
+                    continue;
+                }
+            }
+            last_continues = dir_record.is_multi_extent();
+
+            if let Some(iso_dir_record) = self.resolve_record(&dir_record, node_id)? {
+                dir_records.push(iso_dir_record);
+            }
+        }
+        Ok(dir_records)
+    }
+
+    /**
+     * Reads the data of the given file from the given offset, returns the
+     * amount of bytes read
+     */
+    pub fn read_data(&mut self,
+                     file: &IsoDirRecord,
+                     offset: usize,
+                     buffer: &mut [u8])
+                     -> IsoFsResult<usize> {
+        if file.node_type() != IsoNodeType::File {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        }
+        if offset as u64 >= file.data_size() {
+            return Ok(0);
+        }
+
+        let mut read_bytes_count = 0;
+        let mut extent_start = 0;
+        for (extent, extent_size) in file.m_extents.iter() {
+            let extent_end = extent_start + *extent_size as usize;
+            let read_offset = offset + read_bytes_count;
+            if read_bytes_count == buffer.len() {
+                break;
+            } else if read_offset < extent_end {
+                let read_size =
+                    (extent_end - read_offset).min(buffer.len() - read_bytes_count);
+                self.read_blocks_data(*extent,
+                                      (read_offset - extent_start) as u64,
+                                      &mut buffer[read_bytes_count
+                                                  ..read_bytes_count + read_size])?;
+                read_bytes_count += read_size;
+            }
+            extent_start = extent_end;
+        }
+        Ok(read_bytes_count)
+    }
+}
+
+impl<D> IsoVolume<D> where D: TBlockDevice /* Getters */ {
+    /**
+     * Returns the `IsoDirRecord` of the root directory
+     */
+    pub fn root(&self) -> &IsoDirRecord {
+        &self.m_root
+    }
+
+    /**
+     * Returns the `NameKind` of the hierarchy in use
+     */
+    pub fn name_kind(&self) -> NameKind {
+        self.m_name_kind
+    }
+
+    /**
+     * Returns the name of the volume
+     */
+    pub fn volume_id(&self) -> &str {
+        self.m_volume_id.as_str()
+    }
+
+    /**
+     * Returns the size in bytes of the logical blocks
+     */
+    pub fn block_size(&self) -> usize {
+        self.m_block_size
+    }
+
+    /**
+     * Returns the amount of logical blocks of the volume
+     */
+    pub fn blocks_count(&self) -> u32 {
+        self.m_blocks_count
+    }
+}
+
+impl<D> IsoVolume<D> where D: TBlockDevice /* Privates */ {
+    /**
+     * Resolves the name and the type of the given `DirRecord`, `None` is
+     * returned for the records which must be hidden
+     */
+    fn resolve_record(&mut self,
+                      dir_record: &DirRecord,
+                      node_id: u64)
+                      -> IsoFsResult<Option<IsoDirRecord>> {
+        let mut iso_dir_record =
+            IsoDirRecord { m_name: String::new(),
+                           m_node_type: if dir_record.is_dir() {
+                               IsoNodeType::Dir
+                           } else {
+                               IsoNodeType::File
+                           },
+                           m_node_id: node_id,
+                           m_extents: vec![(dir_record.extent(),
+                                            dir_record.data_size())],
+                           m_data_size: dir_record.data_size() as u64,
+                           m_recording_inst: dir_record.recording_inst(),
+                           m_rock_ridge: None };
+
+        match self.m_name_kind {
+            NameKind::RockRidge => {
+                let system_use = dir_record.system_use();
+                let rock_ridge =
+                    self.rock_ridge_info(&system_use
+                                             [self.m_susp_skip.min(system_use.len())..])?;
+                if rock_ridge.is_relocated() {
+                    return Ok(None);
+                }
+
+                /* the deep directories are relocated, the record links them */
+                if let Some(child_link) = rock_ridge.child_link() {
+                    let child_self_link = self.read_self_link(child_link)?;
+                    iso_dir_record.m_node_type = IsoNodeType::Dir;
+                    iso_dir_record.m_extents =
+                        vec![(child_link, child_self_link.data_size())];
+                    iso_dir_record.m_data_size = child_self_link.data_size() as u64;
+                } else if rock_ridge.is_link() {
+                    iso_dir_record.m_node_type = IsoNodeType::Link;
+                }
+
+                iso_dir_record.m_name = match rock_ridge.name() {
+                    Some(rock_ridge_name) => String::from(rock_ridge_name),
+                    None => dir_record.iso_name()
+                };
+                iso_dir_record.m_rock_ridge = Some(rock_ridge);
+            },
+            NameKind::Joliet => iso_dir_record.m_name = dir_record.joliet_name(),
+            NameKind::Iso => iso_dir_record.m_name = dir_record.iso_name()
+        }
+        Ok(Some(iso_dir_record))
+    }
+
+    /**
+     * Collects the `RockRidgeInfo` from the given system use area and his
+     * continuation areas
+     */
+    fn rock_ridge_info(&mut self, system_use: &[u8]) -> IsoFsResult<RockRidgeInfo> {
+        let mut rock_ridge = RockRidgeInfo::default();
+
+        let mut continuation_area = rock_ridge.parse_area(system_use);
+        for _ in 0..Self::CONTINUATIONS_MAX {
+            let area = match continuation_area {
+                Some(area) if !area.is_empty() => area,
+                _ => break
+            };
+            if area.offset() as usize + area.len() as usize > self.m_block_size {
+                return Err((OsErrorClass::TypesNotMatch, Some("Corrupted SUSP area")));
+            }
+
+            let mut raw_area = vec![0; area.len() as usize];
+            self.read_blocks_data(area.block(), area.offset() as u64, &mut raw_area)?;
+            continuation_area = rock_ridge.parse_area(&raw_area);
+        }
+        Ok(rock_ridge)
+    }
+
+    /**
+     * Reads the first record of the directory at the given logical block,
+     * which is the link to the directory itself
+     */
+    fn read_self_link(&mut self, dir_extent: u32) -> IsoFsResult<DirRecord> {
+        let mut raw_sector = vec![0; self.m_block_size];
+        self.read_blocks_data(dir_extent, 0, &mut raw_sector)?;
+
+        let self_link = DirRecord::decode(&raw_sector)?;
+        if !self_link.is_self_link() || !self_link.is_dir() {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Corrupted ISO9660 directory")));
+        }
+        Ok(self_link)
+    }
+
+    /**
+     * Reads the data which starts at the given offset from the given
+     * logical block
+     */
+    fn read_blocks_data(&mut self,
+                        first_block: u32,
+                        offset: u64,
+                        buffer: &mut [u8])
+                        -> IsoFsResult<()> {
+        let data_offset = self.block_offset(first_block) + offset;
+        read_bytes(&mut self.m_device, data_offset, buffer)
+    }
+
+    /**
+     * Returns the offset in bytes of the given logical block
+     */
+    fn block_offset(&self, block: u32) -> u64 {
+        block as u64 * self.m_block_size as u64
+    }
+}
+
+/**
+ * Reads the given amount of bytes from the given offset of the device,
+ * which could be not aligned to the blocks of the device
+ */
+fn read_bytes<D>(device: &mut D, offset: u64, buffer: &mut [u8]) -> IsoFsResult<()>
+    where D: TBlockDevice {
+    if buffer.is_empty() {
+        return Ok(());
+    }
+
+    let device_block_size = device.block_size() as u64;
+    let first_block = offset / device_block_size;
+    let last_block = (offset + buffer.len() as u64 - 1) / device_block_size;
+    if last_block >= device.blocks_count() {
+        return Err((OsErrorClass::EndOfDataReached,
+                    Some("Read out of the ISO9660 media")));
+    }
+
+    /* read directly into the buffer when aligned to the device blocks */
+    let first_block_offset = (offset % device_block_size) as usize;
+    let buffer_len = buffer.len() as u64;
+    if first_block_offset == 0 && buffer_len.is_multiple_of(device_block_size) {
+        return device.read_blocks(first_block, buffer);
+    }
+
+    let mut raw_blocks =
+        vec![0; ((last_block - first_block + 1) * device_block_size) as usize];
+    device.read_blocks(first_block, &mut raw_blocks)?;
+    buffer.copy_from_slice(&raw_blocks
+                               [first_block_offset..first_block_offset + buffer.len()]);
+    Ok(())
+}
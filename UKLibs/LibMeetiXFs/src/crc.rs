@@ -0,0 +1,51 @@
+/*! CRC-32 checksum of the chunks */
+
+/* reversed polynomial of the IEEE 802.3 CRC-32 */
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/* lookup table computed at compile time */
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/**
+ * Returns the IEEE 802.3 CRC-32 of the given data
+ */
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/**
+ * Continues the IEEE 802.3 CRC-32 of the previous data with the given one
+ */
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data.iter() {
+        crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/**
+ * Computes the lookup table for each byte value
+ */
+const fn crc32_table() -> [u32; 256] {
+    let mut crc32_table = [0; 256];
+
+    let mut byte_value = 0;
+    while byte_value < 256 {
+        let mut crc = byte_value as u32;
+
+        let mut bit_index = 0;
+        while bit_index < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit_index += 1;
+        }
+
+        crc32_table[byte_value] = crc;
+        byte_value += 1;
+    }
+    crc32_table
+}
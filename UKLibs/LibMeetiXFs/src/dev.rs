@@ -0,0 +1,252 @@
+/*! Block devices interface */
+
+use alloc::{
+    boxed::Box,
+    vec::Vec
+};
+use core::ops::Range;
+
+use api_data::error::class::OsErrorClass;
+
+use crate::MxFsResult;
+
+/**
+ * Interface to the media where the filesystem is stored.
+ *
+ * The buffers given to the read and write methods are always multiple of
+ * the `TBlockDevice::block_size()`
+ */
+pub trait TBlockDevice {
+    /**
+     * Returns the size in bytes of each block of the media, must be a
+     * power of two
+     */
+    fn block_size(&self) -> usize;
+
+    /**
+     * Returns the amount of blocks of the media
+     */
+    fn blocks_count(&self) -> u64;
+
+    /**
+     * Reads the blocks starting from the given one into the buffer
+     */
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()>;
+
+    /**
+     * Writes the blocks starting from the given one with the buffer
+     */
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()>;
+
+    /**
+     * Makes persistent the blocks written until now
+     */
+    fn flush(&mut self) -> MxFsResult<()> {
+        Ok(())
+    }
+}
+
+impl<D> TBlockDevice for Box<D> where D: TBlockDevice + ?Sized {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    fn blocks_count(&self) -> u64 {
+        (**self).blocks_count()
+    }
+
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()> {
+        (**self).read_blocks(first_block, buffer)
+    }
+
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()> {
+        (**self).write_blocks(first_block, buffer)
+    }
+
+    fn flush(&mut self) -> MxFsResult<()> {
+        (**self).flush()
+    }
+}
+
+/**
+ * RAM-backed `TBlockDevice`, used for the ramdisks
+ */
+pub struct MemBlockDevice {
+    m_block_size: usize,
+    m_data: Vec<u8>
+}
+
+impl MemBlockDevice /* Constructors */ {
+    /**
+     * Constructs a zeroed `MemBlockDevice` with the given geometry
+     */
+    pub fn new(block_size: usize, blocks_count: u64) -> Self {
+        Self { m_block_size: block_size,
+               m_data: vec![0; block_size * blocks_count as usize] }
+    }
+
+    /**
+     * Constructs a `MemBlockDevice` which uses the given image, truncated
+     * to the last complete block
+     */
+    pub fn from_image(block_size: usize, mut image: Vec<u8>) -> Self {
+        image.truncate(image.len() - image.len() % block_size);
+        Self { m_block_size: block_size,
+               m_data: image }
+    }
+}
+
+impl MemBlockDevice /* Getters */ {
+    /**
+     * Returns the content of the device
+     */
+    pub fn image(&self) -> &[u8] {
+        self.m_data.as_slice()
+    }
+}
+
+impl MemBlockDevice /* Privates */ {
+    /**
+     * Returns the byte range of the device for the given request
+     */
+    fn byte_range(&self,
+                  first_block: u64,
+                  buffer_len: usize)
+                  -> MxFsResult<Range<usize>> {
+        let start = first_block as usize * self.m_block_size;
+        match start.checked_add(buffer_len) {
+            Some(end) if end <= self.m_data.len() => Ok(start..end),
+            _ => Err((OsErrorClass::LimitOverflow, Some("Block out of device")))
+        }
+    }
+}
+
+impl TBlockDevice for MemBlockDevice {
+    fn block_size(&self) -> usize {
+        self.m_block_size
+    }
+
+    fn blocks_count(&self) -> u64 {
+        (self.m_data.len() / self.m_block_size) as u64
+    }
+
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()> {
+        let byte_range = self.byte_range(first_block, buffer.len())?;
+        buffer.copy_from_slice(&self.m_data[byte_range]);
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()> {
+        let byte_range = self.byte_range(first_block, buffer.len())?;
+        self.m_data[byte_range].copy_from_slice(buffer);
+        Ok(())
+    }
+}
+
+/**
+ * Host image file `TBlockDevice`, used by the host tools
+ */
+#[cfg(feature = "std")]
+pub struct ImageFileDevice {
+    m_file: std::fs::File,
+    m_block_size: usize,
+    m_blocks_count: u64
+}
+
+#[cfg(feature = "std")]
+impl ImageFileDevice /* Constructors */ {
+    /**
+     * Opens the given image file for read and write, the trailing
+     * incomplete block is ignored
+     */
+    pub fn open(image_path: &std::path::Path,
+                block_size: usize)
+                -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(image_path)?;
+        let blocks_count = file.metadata()?.len() / block_size as u64;
+
+        Ok(Self { m_file: file,
+                  m_block_size: block_size,
+                  m_blocks_count: blocks_count })
+    }
+
+    /**
+     * Creates, or truncates if exists, the given image file with the given
+     * geometry
+     */
+    pub fn create(image_path: &std::path::Path,
+                  block_size: usize,
+                  blocks_count: u64)
+                  -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true)
+                                              .write(true)
+                                              .create(true)
+                                              .truncate(true)
+                                              .open(image_path)?;
+        file.set_len(block_size as u64 * blocks_count)?;
+
+        Ok(Self { m_file: file,
+                  m_block_size: block_size,
+                  m_blocks_count: blocks_count })
+    }
+}
+
+#[cfg(feature = "std")]
+impl ImageFileDevice /* Privates */ {
+    /**
+     * Moves the file cursor to the given block
+     */
+    fn seek_to(&mut self, first_block: u64, buffer_len: usize) -> MxFsResult<()> {
+        use std::io::{
+            Seek,
+            SeekFrom
+        };
+
+        let blocks_len = (buffer_len / self.m_block_size) as u64;
+        if first_block.checked_add(blocks_len)
+                      .map_or(true, |end| end > self.m_blocks_count)
+        {
+            return Err((OsErrorClass::LimitOverflow, Some("Block out of device")));
+        }
+
+        self.m_file
+            .seek(SeekFrom::Start(first_block * self.m_block_size as u64))
+            .map(|_| ())
+            .map_err(|_| (OsErrorClass::Unknown, Some("Image file seek failed")))
+    }
+}
+
+#[cfg(feature = "std")]
+impl TBlockDevice for ImageFileDevice {
+    fn block_size(&self) -> usize {
+        self.m_block_size
+    }
+
+    fn blocks_count(&self) -> u64 {
+        self.m_blocks_count
+    }
+
+    fn read_blocks(&mut self, first_block: u64, buffer: &mut [u8]) -> MxFsResult<()> {
+        use std::io::Read;
+
+        self.seek_to(first_block, buffer.len())?;
+        self.m_file
+            .read_exact(buffer)
+            .map_err(|_| (OsErrorClass::Unknown, Some("Image file read failed")))
+    }
+
+    fn write_blocks(&mut self, first_block: u64, buffer: &[u8]) -> MxFsResult<()> {
+        use std::io::Write;
+
+        self.seek_to(first_block, buffer.len())?;
+        self.m_file
+            .write_all(buffer)
+            .map_err(|_| (OsErrorClass::Unknown, Some("Image file write failed")))
+    }
+
+    fn flush(&mut self) -> MxFsResult<()> {
+        self.m_file
+            .sync_all()
+            .map_err(|_| (OsErrorClass::Unknown, Some("Image file sync failed")))
+    }
+}
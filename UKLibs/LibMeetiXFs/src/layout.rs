@@ -0,0 +1,1243 @@
+/*! On-disk structures of the MeetiX filesystem
+ *
+ * ```text
+ * ---------------------------------------------------------------------
+ * | Super Block | Blocks Bitmap ... | Inodes Table ... | Chunks ...    |
+ * ---------------------------------------------------------------------
+ * ```
+ *
+ * The `SuperBlock` is stored into the first device block, it is followed by
+ * the bitmap of the used device blocks and by the table of the `Inode`s.
+ * The remaining blocks are used for the data chunks and for the blocks
+ * which store the `Extent`s exceeding the ones inlined into the `Inode`.
+ *
+ * All the numbers are stored in little endian
+ */
+
+use alloc::{
+    string::String,
+    vec::Vec
+};
+use core::{
+    convert::{
+        TryFrom,
+        TryInto
+    },
+    str,
+    time::Duration
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::types::ObjType
+};
+use bits::bit_flags::{
+    BitFlags,
+    TBitFlagsValues
+};
+
+use crate::{
+    crc::crc32,
+    MxFsResult
+};
+
+/**
+ * First block of the filesystem, describes where the other areas are
+ * stored
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SuperBlock {
+    m_block_size: u32,
+    m_blocks_count: u64,
+    m_bitmap_first_block: u64,
+    m_bitmap_blocks: u64,
+    m_inodes_first_block: u64,
+    m_inodes_blocks: u64,
+    m_inodes_count: u64,
+    m_free_blocks: u64,
+    m_free_inodes: u64,
+    m_blk_exp_max: u8,
+    m_frag_threshold: u8,
+    m_is_clean: bool,
+    m_serial: u32,
+    m_label: [u8; SuperBlock::LABEL_LEN_MAX]
+}
+
+impl SuperBlock /* Constants */ {
+    /**
+     * Identifies a MeetiX filesystem
+     */
+    pub const MAGIC: [u8; 8] = *b"MeetiXFs";
+
+    /**
+     * Version of the on-disk format described by this module
+     */
+    pub const VERSION: u32 = 1;
+
+    /**
+     * Device block which stores the `SuperBlock`
+     */
+    pub const BLOCK_INDEX: u64 = 0;
+
+    /**
+     * Minimum device block size supported
+     */
+    pub const BLOCK_SIZE_MIN: usize = 512;
+
+    /**
+     * Maximum length in bytes of the volume label
+     */
+    pub const LABEL_LEN_MAX: usize = 32;
+
+    /**
+     * Size in bytes of the encoded `SuperBlock`, CRC included
+     */
+    pub const ENCODED_SIZE: usize = 124;
+}
+
+impl SuperBlock /* Constructors */ {
+    /**
+     * Constructs a clean `SuperBlock` with the given geometry, all the
+     * blocks and the inodes are marked as free
+     */
+    pub fn new(block_size: usize,
+               blocks_count: u64,
+               inodes_count: u64,
+               blk_exp_max: u8,
+               frag_threshold: u8,
+               serial: u32,
+               label: &str)
+               -> Self {
+        let bitmap_blocks = blocks_count.div_ceil(block_size as u64 * 8);
+        let inodes_blocks =
+            (inodes_count * Inode::ENCODED_SIZE as u64).div_ceil(block_size as u64);
+
+        /* the label is truncated to the maximum length */
+        let mut raw_label = [0; Self::LABEL_LEN_MAX];
+        for (label_byte, raw_byte) in label.bytes().zip(raw_label.iter_mut()) {
+            *raw_byte = label_byte;
+        }
+
+        Self { m_block_size: block_size as u32,
+               m_blocks_count: blocks_count,
+               m_bitmap_first_block: Self::BLOCK_INDEX + 1,
+               m_bitmap_blocks: bitmap_blocks,
+               m_inodes_first_block: Self::BLOCK_INDEX + 1 + bitmap_blocks,
+               m_inodes_blocks: inodes_blocks,
+               m_inodes_count: inodes_count,
+               m_free_blocks: blocks_count,
+               m_free_inodes: inodes_count,
+               m_blk_exp_max: blk_exp_max,
+               m_frag_threshold: frag_threshold,
+               m_is_clean: true,
+               m_serial: serial,
+               m_label: raw_label }
+    }
+
+    /**
+     * Decodes the given raw `SuperBlock`, validating the magic, the
+     * version and the CRC
+     */
+    pub fn decode(raw_super_block: &[u8]) -> MxFsResult<Self> {
+        if raw_super_block.len() < Self::ENCODED_SIZE
+           || raw_super_block[0..8] != Self::MAGIC
+        {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a MeetiX filesystem")));
+        } else if read_u32(raw_super_block, 8) != Self::VERSION {
+            return Err((OsErrorClass::OperationNotEnabled,
+                        Some("Unsupported MeetiX filesystem version")));
+        } else if crc32(&raw_super_block[..Self::ENCODED_SIZE - 4])
+                  != read_u32(raw_super_block, Self::ENCODED_SIZE - 4)
+        {
+            return Err((OsErrorClass::InvalidArgument, Some("Corrupted super block")));
+        }
+
+        let mut raw_label = [0; Self::LABEL_LEN_MAX];
+        raw_label.copy_from_slice(&raw_super_block[88..88 + Self::LABEL_LEN_MAX]);
+
+        Ok(Self { m_block_size: read_u32(raw_super_block, 12),
+                  m_blocks_count: read_u64(raw_super_block, 16),
+                  m_bitmap_first_block: read_u64(raw_super_block, 24),
+                  m_bitmap_blocks: read_u64(raw_super_block, 32),
+                  m_inodes_first_block: read_u64(raw_super_block, 40),
+                  m_inodes_blocks: read_u64(raw_super_block, 48),
+                  m_inodes_count: read_u64(raw_super_block, 56),
+                  m_free_blocks: read_u64(raw_super_block, 64),
+                  m_free_inodes: read_u64(raw_super_block, 72),
+                  m_blk_exp_max: raw_super_block[80],
+                  m_frag_threshold: raw_super_block[81],
+                  m_is_clean: raw_super_block[82] != 0,
+                  m_serial: read_u32(raw_super_block, 84),
+                  m_label: raw_label })
+    }
+}
+
+impl SuperBlock /* Methods */ {
+    /**
+     * Encodes this `SuperBlock` into the given buffer
+     */
+    pub fn encode(&self, raw_super_block: &mut [u8]) {
+        raw_super_block[..Self::ENCODED_SIZE].fill(0);
+
+        raw_super_block[0..8].copy_from_slice(&Self::MAGIC);
+        write_u32(raw_super_block, 8, Self::VERSION);
+        write_u32(raw_super_block, 12, self.m_block_size);
+        write_u64(raw_super_block, 16, self.m_blocks_count);
+        write_u64(raw_super_block, 24, self.m_bitmap_first_block);
+        write_u64(raw_super_block, 32, self.m_bitmap_blocks);
+        write_u64(raw_super_block, 40, self.m_inodes_first_block);
+        write_u64(raw_super_block, 48, self.m_inodes_blocks);
+        write_u64(raw_super_block, 56, self.m_inodes_count);
+        write_u64(raw_super_block, 64, self.m_free_blocks);
+        write_u64(raw_super_block, 72, self.m_free_inodes);
+        raw_super_block[80] = self.m_blk_exp_max;
+        raw_super_block[81] = self.m_frag_threshold;
+        raw_super_block[82] = self.m_is_clean as u8;
+        write_u32(raw_super_block, 84, self.m_serial);
+        raw_super_block[88..88 + Self::LABEL_LEN_MAX].copy_from_slice(&self.m_label);
+
+        let crc = crc32(&raw_super_block[..Self::ENCODED_SIZE - 4]);
+        write_u32(raw_super_block, Self::ENCODED_SIZE - 4, crc);
+    }
+
+    /**
+     * Returns the first device block not used by the filesystem metadata
+     */
+    pub fn data_first_block(&self) -> u64 {
+        self.m_inodes_first_block + self.m_inodes_blocks
+    }
+
+    /**
+     * Returns the amount of bytes of data which a chunk of the given
+     * exponent could store
+     */
+    pub fn chunk_capacity(&self, blk_exp: u8) -> usize {
+        ((self.m_block_size as usize) << blk_exp) - ChunkHeader::ENCODED_SIZE
+    }
+
+    /**
+     * Returns the device block which stores the given `Inode` and the
+     * offset of it into the block
+     */
+    pub fn inode_location(&self, inode_id: u64) -> (u64, usize) {
+        let inode_byte_offset = inode_id * Inode::ENCODED_SIZE as u64;
+        (self.m_inodes_first_block + inode_byte_offset / self.m_block_size as u64,
+         (inode_byte_offset % self.m_block_size as u64) as usize)
+    }
+}
+
+impl SuperBlock /* Getters */ {
+    /**
+     * Returns the size in bytes of the device blocks
+     */
+    pub fn block_size(&self) -> usize {
+        self.m_block_size as usize
+    }
+
+    /**
+     * Returns the amount of device blocks of the filesystem
+     */
+    pub fn blocks_count(&self) -> u64 {
+        self.m_blocks_count
+    }
+
+    /**
+     * Returns the first device block of the blocks bitmap
+     */
+    pub fn bitmap_first_block(&self) -> u64 {
+        self.m_bitmap_first_block
+    }
+
+    /**
+     * Returns the amount of device blocks of the blocks bitmap
+     */
+    pub fn bitmap_blocks(&self) -> u64 {
+        self.m_bitmap_blocks
+    }
+
+    /**
+     * Returns the first device block of the inodes table
+     */
+    pub fn inodes_first_block(&self) -> u64 {
+        self.m_inodes_first_block
+    }
+
+    /**
+     * Returns the amount of device blocks of the inodes table
+     */
+    pub fn inodes_blocks(&self) -> u64 {
+        self.m_inodes_blocks
+    }
+
+    /**
+     * Returns the amount of inodes of the inodes table
+     */
+    pub fn inodes_count(&self) -> u64 {
+        self.m_inodes_count
+    }
+
+    /**
+     * Returns the amount of free device blocks
+     */
+    pub fn free_blocks(&self) -> u64 {
+        self.m_free_blocks
+    }
+
+    /**
+     * Returns the amount of free inodes
+     */
+    pub fn free_inodes(&self) -> u64 {
+        self.m_free_inodes
+    }
+
+    /**
+     * Returns the maximum `BLK-EXP` of the chunks
+     */
+    pub fn blk_exp_max(&self) -> u8 {
+        self.m_blk_exp_max
+    }
+
+    /**
+     * Returns the percentage of the internal fragmentation over which a
+     * growing file prefers a new chunk to the expansion of the last one
+     */
+    pub fn frag_threshold(&self) -> u8 {
+        self.m_frag_threshold
+    }
+
+    /**
+     * Returns whether the filesystem was cleanly unmounted
+     */
+    pub fn is_clean(&self) -> bool {
+        self.m_is_clean
+    }
+
+    /**
+     * Returns the serial number given at format time
+     */
+    pub fn serial(&self) -> u32 {
+        self.m_serial
+    }
+
+    /**
+     * Returns the volume label
+     */
+    pub fn label(&self) -> &str {
+        let label_len = self.m_label
+                            .iter()
+                            .position(|byte| *byte == 0)
+                            .unwrap_or(Self::LABEL_LEN_MAX);
+        str::from_utf8(&self.m_label[..label_len]).unwrap_or("")
+    }
+}
+
+impl SuperBlock /* Setters */ {
+    /**
+     * Sets the amount of free device blocks
+     */
+    pub fn set_free_blocks(&mut self, free_blocks: u64) {
+        self.m_free_blocks = free_blocks;
+    }
+
+    /**
+     * Sets the amount of free inodes
+     */
+    pub fn set_free_inodes(&mut self, free_inodes: u64) {
+        self.m_free_inodes = free_inodes;
+    }
+
+    /**
+     * Sets whether the filesystem is cleanly unmounted
+     */
+    pub fn set_clean(&mut self, is_clean: bool) {
+        self.m_is_clean = is_clean;
+    }
+}
+
+/**
+ * Lists the types of the `Inode`s
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum NodeType {
+    Free,
+    Dir,
+    File,
+    Link
+}
+
+impl NodeType /* Constructors */ {
+    /**
+     * Returns the `NodeType` for the given `ObjType` if it could be stored
+     * into the filesystem
+     */
+    pub fn from_obj_type(obj_type: ObjType) -> Option<Self> {
+        match obj_type {
+            ObjType::Dir => Some(Self::Dir),
+            ObjType::File => Some(Self::File),
+            ObjType::Link => Some(Self::Link),
+            _ => None
+        }
+    }
+}
+
+impl NodeType /* Getters */ {
+    /**
+     * Returns the `ObjType` of this `NodeType`
+     */
+    pub fn obj_type(&self) -> ObjType {
+        match self {
+            Self::Free => ObjType::Unknown,
+            Self::Dir => ObjType::Dir,
+            Self::File => ObjType::File,
+            Self::Link => ObjType::Link
+        }
+    }
+}
+
+impl TryFrom<u8> for NodeType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Free),
+            1 => Ok(Self::Dir),
+            2 => Ok(Self::File),
+            3 => Ok(Self::Link),
+            _ => Err(())
+        }
+    }
+}
+
+/**
+ * Per-file behaviour flags of an `Inode`
+ */
+pub type FileFlags = BitFlags<u8, FileFlagsBits>;
+
+/**
+ * Lists the valid `FileFlags` bits.
+ *
+ * # `NoCrc`
+ * The CRC of the chunks is neither computed nor checked, applied only on
+ * the chunks with `REF-CNT = 1`, since the other files which share the
+ * chunk could rely on it
+ *
+ * # `NoCow`
+ * The file doesn't share anymore his chunks when cloned, the chunks
+ * already shared are not duplicated until they are written
+ *
+ * # `LowFragment`
+ * When the file grows the expansion of the last chunk is always preferred
+ * to the allocation of a new chunk, trading less external fragmentation
+ * for more internal fragmentation
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum FileFlagsBits {
+    NoCrc,
+    NoCow,
+    LowFragment
+}
+
+impl From<FileFlagsBits> for usize {
+    fn from(bits: FileFlagsBits) -> Self {
+        bits as usize
+    }
+}
+
+impl TryFrom<usize> for FileFlagsBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::NoCrc),
+            1 => Ok(Self::NoCow),
+            2 => Ok(Self::LowFragment),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for FileFlagsBits {
+}
+
+/**
+ * Lists the timestamps of an `Inode`
+ */
+#[repr(usize)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum InodeInstant {
+    Creat,
+    DataAccess,
+    DataModify,
+    InfoAccess,
+    InfoModify
+}
+
+/**
+ * Node of the filesystem, stores the metadata and the `Extent`s of a
+ * directory, a file or a link
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Inode {
+    m_inode_id: u64,
+    m_node_type: NodeType,
+    m_file_flags: FileFlags,
+    m_links: u16,
+    m_os_user: u32,
+    m_os_group: u32,
+    m_prot_grants: u32,
+    m_data_size: u64,
+    m_instants: [u64; 5],
+    m_extents_count: u32,
+    m_extents: Vec<Extent>,
+    m_extents_blocks: Vec<u64>
+}
+
+impl Inode /* Constants */ {
+    /**
+     * Size in bytes of each `Inode` of the inodes table
+     */
+    pub const ENCODED_SIZE: usize = 256;
+
+    /**
+     * Amount of `Extent`s stored into the `Inode` itself
+     */
+    pub const INLINE_EXTENTS: usize = 11;
+
+    /**
+     * Identifier of the inode never used, which marks the free records of
+     * the directories
+     */
+    pub const NULL_ID: u64 = 0;
+
+    /**
+     * Identifier of the root directory
+     */
+    pub const ROOT_ID: u64 = 1;
+
+    /* offset of the inlined extents into the encoded inode */
+    const EXTENTS_OFFSET: usize = 80;
+}
+
+impl Inode /* Constructors */ {
+    /**
+     * Constructs an empty `Inode` of the given `NodeType` created at the
+     * given `RawInstant`
+     */
+    pub fn new(inode_id: u64,
+               node_type: NodeType,
+               prot_grants: u32,
+               creat_inst: RawInstant)
+               -> Self {
+        let creat_inst = creat_inst.as_nanos() as u64;
+
+        Self { m_inode_id: inode_id,
+               m_node_type: node_type,
+               m_file_flags: FileFlags::new_zero(),
+               m_links: 1,
+               m_os_user: 0,
+               m_os_group: 0,
+               m_prot_grants: prot_grants,
+               m_data_size: 0,
+               m_instants: [creat_inst; 5],
+               m_extents_count: 0,
+               m_extents: Vec::new(),
+               m_extents_blocks: Vec::new() }
+    }
+
+    /**
+     * Decodes the given raw `Inode`.
+     *
+     * Only the inlined `Extent`s are decoded, the first block of the
+     * exceeding ones is returned by `Inode::extents_blocks()`
+     */
+    pub fn decode(inode_id: u64, raw_inode: &[u8]) -> MxFsResult<Self> {
+        let node_type = NodeType::try_from(raw_inode[0]).map_err(|_| {
+                            (OsErrorClass::InvalidArgument, Some("Corrupted inode type"))
+                        })?;
+
+        let mut instants = [0; 5];
+        for (instant_index, instant) in instants.iter_mut().enumerate() {
+            *instant = read_u64(raw_inode, 24 + instant_index * 8);
+        }
+
+        let extents_count = read_u32(raw_inode, 64);
+        let extents_block = read_u64(raw_inode, 72);
+        let extents =
+            (0..Self::INLINE_EXTENTS.min(extents_count as usize)).map(|extent_index| {
+                Extent::decode(&raw_inode[Self::EXTENTS_OFFSET
+                                          + extent_index * Extent::ENCODED_SIZE..])
+            })
+            .collect();
+
+        Ok(Self { m_inode_id: inode_id,
+                  m_node_type: node_type,
+                  m_file_flags: FileFlags::from_raw_truncate(raw_inode[1]),
+                  m_links: read_u16(raw_inode, 2),
+                  m_os_user: read_u32(raw_inode, 4),
+                  m_os_group: read_u32(raw_inode, 8),
+                  m_prot_grants: read_u32(raw_inode, 12),
+                  m_data_size: read_u64(raw_inode, 16),
+                  m_instants: instants,
+                  m_extents_count: extents_count,
+                  m_extents: extents,
+                  m_extents_blocks: if extents_block != 0 {
+                      vec![extents_block]
+                  } else {
+                      Vec::new()
+                  } })
+    }
+}
+
+impl Inode /* Methods */ {
+    /**
+     * Encodes this `Inode` into the given buffer, the `Extent`s exceeding
+     * the inlined ones are stored by the caller into the
+     * `Inode::extents_blocks()`
+     */
+    pub fn encode(&self, raw_inode: &mut [u8]) {
+        raw_inode[..Self::ENCODED_SIZE].fill(0);
+
+        raw_inode[0] = self.m_node_type as u8;
+        raw_inode[1] = self.m_file_flags.raw_bits();
+        write_u16(raw_inode, 2, self.m_links);
+        write_u32(raw_inode, 4, self.m_os_user);
+        write_u32(raw_inode, 8, self.m_os_group);
+        write_u32(raw_inode, 12, self.m_prot_grants);
+        write_u64(raw_inode, 16, self.m_data_size);
+        for (instant_index, instant) in self.m_instants.iter().enumerate() {
+            write_u64(raw_inode, 24 + instant_index * 8, *instant);
+        }
+        write_u32(raw_inode, 64, self.m_extents.len() as u32);
+        write_u64(raw_inode, 72, self.m_extents_blocks.first().copied().unwrap_or(0));
+
+        for (extent_index, extent) in
+            self.m_extents.iter().take(Self::INLINE_EXTENTS).enumerate()
+        {
+            extent.encode(&mut raw_inode[Self::EXTENTS_OFFSET
+                                         + extent_index * Extent::ENCODED_SIZE..]);
+        }
+    }
+}
+
+impl Inode /* Getters */ {
+    /**
+     * Returns the identifier of this `Inode`
+     */
+    pub fn inode_id(&self) -> u64 {
+        self.m_inode_id
+    }
+
+    /**
+     * Returns the `NodeType` of this `Inode`
+     */
+    pub fn node_type(&self) -> NodeType {
+        self.m_node_type
+    }
+
+    /**
+     * Returns the `FileFlags` of this `Inode`
+     */
+    pub fn file_flags(&self) -> FileFlags {
+        self.m_file_flags
+    }
+
+    /**
+     * Returns the amount of directory records which reference this `Inode`
+     */
+    pub fn links(&self) -> u16 {
+        self.m_links
+    }
+
+    /**
+     * Returns the owner user identifier
+     */
+    pub fn os_user(&self) -> u32 {
+        self.m_os_user
+    }
+
+    /**
+     * Returns the owner group identifier
+     */
+    pub fn os_group(&self) -> u32 {
+        self.m_os_group
+    }
+
+    /**
+     * Returns the raw protection grants
+     */
+    pub fn prot_grants(&self) -> u32 {
+        self.m_prot_grants
+    }
+
+    /**
+     * Returns the size in bytes of the data
+     */
+    pub fn data_size(&self) -> u64 {
+        self.m_data_size
+    }
+
+    /**
+     * Returns the requested timestamp
+     */
+    pub fn instant(&self, inode_instant: InodeInstant) -> RawInstant {
+        Duration::from_nanos(self.m_instants[inode_instant as usize])
+    }
+
+    /**
+     * Returns the amount of `Extent`s stored into the inodes table
+     */
+    pub fn extents_count(&self) -> u32 {
+        self.m_extents_count
+    }
+
+    /**
+     * Returns the `Extent`s of the data
+     */
+    pub fn extents(&self) -> &Vec<Extent> {
+        &self.m_extents
+    }
+
+    /**
+     * Returns the mutable `Extent`s of the data
+     */
+    pub fn extents_mut(&mut self) -> &mut Vec<Extent> {
+        &mut self.m_extents
+    }
+
+    /**
+     * Returns the device blocks which store the `Extent`s exceeding the
+     * inlined ones
+     */
+    pub fn extents_blocks(&self) -> &Vec<u64> {
+        &self.m_extents_blocks
+    }
+
+    /**
+     * Returns the mutable device blocks which store the `Extent`s
+     * exceeding the inlined ones
+     */
+    pub fn extents_blocks_mut(&mut self) -> &mut Vec<u64> {
+        &mut self.m_extents_blocks
+    }
+}
+
+impl Inode /* Setters */ {
+    /**
+     * Sets the `NodeType` of this `Inode`
+     */
+    pub fn set_node_type(&mut self, node_type: NodeType) {
+        self.m_node_type = node_type;
+    }
+
+    /**
+     * Sets the `FileFlags` of this `Inode`
+     */
+    pub fn set_file_flags(&mut self, file_flags: FileFlags) {
+        self.m_file_flags = file_flags;
+    }
+
+    /**
+     * Sets the amount of directory records which reference this `Inode`
+     */
+    pub fn set_links(&mut self, links: u16) {
+        self.m_links = links;
+    }
+
+    /**
+     * Sets the owner user identifier
+     */
+    pub fn set_os_user(&mut self, os_user: u32) {
+        self.m_os_user = os_user;
+    }
+
+    /**
+     * Sets the owner group identifier
+     */
+    pub fn set_os_group(&mut self, os_group: u32) {
+        self.m_os_group = os_group;
+    }
+
+    /**
+     * Sets the raw protection grants
+     */
+    pub fn set_prot_grants(&mut self, prot_grants: u32) {
+        self.m_prot_grants = prot_grants;
+    }
+
+    /**
+     * Sets the size in bytes of the data
+     */
+    pub fn set_data_size(&mut self, data_size: u64) {
+        self.m_data_size = data_size;
+    }
+
+    /**
+     * Sets the requested timestamp
+     */
+    pub fn set_instant(&mut self, inode_instant: InodeInstant, raw_instant: RawInstant) {
+        self.m_instants[inode_instant as usize] = raw_instant.as_nanos() as u64;
+    }
+}
+
+/**
+ * Reference to a chunk of device blocks used by an `Inode`
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub struct Extent {
+    m_first_block: u64,
+    m_data_len: u32,
+    m_blk_exp: u8
+}
+
+impl Extent /* Constants */ {
+    /**
+     * Size in bytes of the encoded `Extent`
+     */
+    pub const ENCODED_SIZE: usize = 16;
+}
+
+impl Extent /* Constructors */ {
+    /**
+     * Constructs an `Extent` for the chunk at the given block
+     */
+    pub fn new(first_block: u64, data_len: u32, blk_exp: u8) -> Self {
+        Self { m_first_block: first_block,
+               m_data_len: data_len,
+               m_blk_exp: blk_exp }
+    }
+
+    /**
+     * Decodes the given raw `Extent`
+     */
+    pub fn decode(raw_extent: &[u8]) -> Self {
+        Self { m_first_block: read_u64(raw_extent, 0),
+               m_data_len: read_u32(raw_extent, 8),
+               m_blk_exp: raw_extent[12] }
+    }
+}
+
+impl Extent /* Methods */ {
+    /**
+     * Encodes this `Extent` into the given buffer
+     */
+    pub fn encode(&self, raw_extent: &mut [u8]) {
+        raw_extent[..Self::ENCODED_SIZE].fill(0);
+
+        write_u64(raw_extent, 0, self.m_first_block);
+        write_u32(raw_extent, 8, self.m_data_len);
+        raw_extent[12] = self.m_blk_exp;
+    }
+
+    /**
+     * Returns the amount of device blocks of the chunk
+     */
+    pub fn blocks_count(&self) -> u64 {
+        1 << self.m_blk_exp
+    }
+}
+
+impl Extent /* Getters */ {
+    /**
+     * Returns the first device block of the chunk
+     */
+    pub fn first_block(&self) -> u64 {
+        self.m_first_block
+    }
+
+    /**
+     * Returns the bytes of the chunk used by the `Inode`
+     */
+    pub fn data_len(&self) -> usize {
+        self.m_data_len as usize
+    }
+
+    /**
+     * Returns the `BLK-EXP` of the chunk
+     */
+    pub fn blk_exp(&self) -> u8 {
+        self.m_blk_exp
+    }
+}
+
+impl Extent /* Setters */ {
+    /**
+     * Sets the bytes of the chunk used by the `Inode`
+     */
+    pub fn set_data_len(&mut self, data_len: usize) {
+        self.m_data_len = data_len as u32;
+    }
+}
+
+/**
+ * Device block which stores the `Extent`s exceeding the ones inlined into
+ * the `Inode`, chained to the next one.
+ *
+ * ```text
+ * ------------------------------------------------------------
+ * | NEXT-BLK 64-BIT | COUNT 32-BIT | RESERVED 32-BIT | Extents...
+ * ------------------------------------------------------------
+ * ```
+ */
+pub struct ExtentsBlock;
+
+impl ExtentsBlock /* Constants */ {
+    /**
+     * Size in bytes of the header of the block
+     */
+    pub const HEADER_SIZE: usize = 16;
+}
+
+impl ExtentsBlock /* Static Functions */ {
+    /**
+     * Returns the amount of `Extent`s which a block could store
+     */
+    pub fn capacity(block_size: usize) -> usize {
+        (block_size - Self::HEADER_SIZE) / Extent::ENCODED_SIZE
+    }
+
+    /**
+     * Decodes the given raw block appending the `Extent`s to the given
+     * `Vec`, returns the next block of the chain
+     */
+    pub fn decode(raw_block: &[u8], extents: &mut Vec<Extent>) -> MxFsResult<u64> {
+        let extents_count = read_u32(raw_block, 8) as usize;
+        if extents_count > Self::capacity(raw_block.len()) {
+            return Err((OsErrorClass::InvalidArgument, Some("Corrupted extents block")));
+        }
+
+        for extent_index in 0..extents_count {
+            extents.push(Extent::decode(&raw_block[Self::HEADER_SIZE
+                                                   + extent_index
+                                                     * Extent::ENCODED_SIZE..]));
+        }
+        Ok(read_u64(raw_block, 0))
+    }
+
+    /**
+     * Encodes the given `Extent`s into the raw block chained to the given
+     * next block
+     */
+    pub fn encode(raw_block: &mut [u8], extents: &[Extent], next_block: u64) {
+        raw_block.fill(0);
+
+        write_u64(raw_block, 0, next_block);
+        write_u32(raw_block, 8, extents.len() as u32);
+        for (extent_index, extent) in extents.iter().enumerate() {
+            extent.encode(&mut raw_block[Self::HEADER_SIZE
+                                         + extent_index * Extent::ENCODED_SIZE..]);
+        }
+    }
+}
+
+/**
+ * Metadata stored into the first 64 bits of each chunk.
+ *
+ * ```text
+ * ------------------------------------------------------------------------
+ * | REF-CNT 16-BIT | BLK-EXP 4-BIT | FLAGS 4-BIT | RESERVED 8-BIT | CRC 32-BIT |
+ * ------------------------------------------------------------------------
+ * ```
+ *
+ * The CRC covers all the data area of the chunk, used or not
+ */
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub struct ChunkHeader {
+    m_ref_count: u16,
+    m_blk_exp: u8,
+    m_flags: ChunkFlags,
+    m_crc: u32
+}
+
+impl ChunkHeader /* Constants */ {
+    /**
+     * Size in bytes of the encoded `ChunkHeader`
+     */
+    pub const ENCODED_SIZE: usize = 8;
+
+    /**
+     * Maximum `BLK-EXP` representable
+     */
+    pub const BLK_EXP_MAX: u8 = 15;
+}
+
+impl ChunkHeader /* Constructors */ {
+    /**
+     * Constructs a `ChunkHeader` referenced by one `Inode`
+     */
+    pub fn new(blk_exp: u8) -> Self {
+        Self { m_ref_count: 1,
+               m_blk_exp: blk_exp,
+               m_flags: ChunkFlags::new_zero(),
+               m_crc: 0 }
+    }
+
+    /**
+     * Decodes the given raw `ChunkHeader`
+     */
+    pub fn decode(raw_header: &[u8]) -> Self {
+        Self { m_ref_count: read_u16(raw_header, 0),
+               m_blk_exp: raw_header[2] & 0x0F,
+               m_flags: ChunkFlags::from_raw_truncate(raw_header[2] >> 4),
+               m_crc: read_u32(raw_header, 4) }
+    }
+}
+
+impl ChunkHeader /* Methods */ {
+    /**
+     * Encodes this `ChunkHeader` into the given buffer
+     */
+    pub fn encode(&self, raw_header: &mut [u8]) {
+        write_u16(raw_header, 0, self.m_ref_count);
+        raw_header[2] = (self.m_blk_exp & 0x0F) | (self.m_flags.raw_bits() << 4);
+        raw_header[3] = 0;
+        write_u32(raw_header, 4, self.m_crc);
+    }
+}
+
+impl ChunkHeader /* Getters */ {
+    /**
+     * Returns the amount of `Inode`s which reference the chunk
+     */
+    pub fn ref_count(&self) -> u16 {
+        self.m_ref_count
+    }
+
+    /**
+     * Returns the `BLK-EXP` of the chunk
+     */
+    pub fn blk_exp(&self) -> u8 {
+        self.m_blk_exp
+    }
+
+    /**
+     * Returns the `ChunkFlags` of the chunk
+     */
+    pub fn flags(&self) -> ChunkFlags {
+        self.m_flags
+    }
+
+    /**
+     * Returns the CRC of the data of the chunk
+     */
+    pub fn crc(&self) -> u32 {
+        self.m_crc
+    }
+}
+
+impl ChunkHeader /* Setters */ {
+    /**
+     * Sets the amount of `Inode`s which reference the chunk
+     */
+    pub fn set_ref_count(&mut self, ref_count: u16) {
+        self.m_ref_count = ref_count;
+    }
+
+    /**
+     * Sets the `ChunkFlags` of the chunk
+     */
+    pub fn set_flags(&mut self, flags: ChunkFlags) {
+        self.m_flags = flags;
+    }
+
+    /**
+     * Sets the CRC of the data of the chunk
+     */
+    pub fn set_crc(&mut self, crc: u32) {
+        self.m_crc = crc;
+    }
+}
+
+/**
+ * Flags of a chunk
+ */
+pub type ChunkFlags = BitFlags<u8, ChunkFlagsBits>;
+
+/**
+ * Lists the valid `ChunkFlags` bits.
+ *
+ * # `HasCrc`
+ * The CRC of the chunk is valid and must be checked
+ *
+ * # `Compressed`
+ * The data of the chunk is compressed, reserved for future use
+ */
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum ChunkFlagsBits {
+    HasCrc,
+    Compressed
+}
+
+impl From<ChunkFlagsBits> for usize {
+    fn from(bits: ChunkFlagsBits) -> Self {
+        bits as usize
+    }
+}
+
+impl TryFrom<usize> for ChunkFlagsBits {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::HasCrc),
+            1 => Ok(Self::Compressed),
+            _ => Err(())
+        }
+    }
+}
+
+impl TBitFlagsValues for ChunkFlagsBits {
+}
+
+/**
+ * Record of a directory which binds a name to an `Inode`.
+ *
+ * The data of the directories is an array of records, the free ones have
+ * `Inode::NULL_ID` as inode
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DirRecord {
+    m_inode_id: u64,
+    m_node_type: NodeType,
+    m_name: String
+}
+
+impl DirRecord /* Constants */ {
+    /**
+     * Size in bytes of each encoded `DirRecord`
+     */
+    pub const ENCODED_SIZE: usize = 256;
+
+    /**
+     * Maximum length in bytes of a name
+     */
+    pub const NAME_LEN_MAX: usize = 246;
+}
+
+impl DirRecord /* Constructors */ {
+    /**
+     * Constructs a `DirRecord` with the given values
+     */
+    pub fn new(inode_id: u64, node_type: NodeType, name: &str) -> Self {
+        Self { m_inode_id: inode_id,
+               m_node_type: node_type,
+               m_name: String::from(name) }
+    }
+
+    /**
+     * Decodes the given raw `DirRecord`, returns `None` for the free ones
+     */
+    pub fn decode(raw_record: &[u8]) -> MxFsResult<Option<Self>> {
+        let inode_id = read_u64(raw_record, 0);
+        if inode_id == Inode::NULL_ID {
+            return Ok(None);
+        }
+
+        let name_len = raw_record[8] as usize;
+        let node_type = NodeType::try_from(raw_record[9]).ok();
+        let name = str::from_utf8(raw_record.get(10..10 + name_len).unwrap_or(&[])).ok();
+        match (node_type, name) {
+            (Some(node_type), Some(name)) if name_len <= Self::NAME_LEN_MAX => {
+                Ok(Some(Self::new(inode_id, node_type, name)))
+            },
+            _ => Err((OsErrorClass::InvalidArgument, Some("Corrupted directory record")))
+        }
+    }
+}
+
+impl DirRecord /* Methods */ {
+    /**
+     * Encodes this `DirRecord` into the given buffer
+     */
+    pub fn encode(&self, raw_record: &mut [u8]) {
+        raw_record[..Self::ENCODED_SIZE].fill(0);
+
+        write_u64(raw_record, 0, self.m_inode_id);
+        raw_record[8] = self.m_name.len() as u8;
+        raw_record[9] = self.m_node_type as u8;
+        raw_record[10..10 + self.m_name.len()].copy_from_slice(self.m_name.as_bytes());
+    }
+}
+
+impl DirRecord /* Getters */ {
+    /**
+     * Returns the referenced `Inode`
+     */
+    pub fn inode_id(&self) -> u64 {
+        self.m_inode_id
+    }
+
+    /**
+     * Returns the `NodeType` of the referenced `Inode`
+     */
+    pub fn node_type(&self) -> NodeType {
+        self.m_node_type
+    }
+
+    /**
+     * Returns the name of the record
+     */
+    pub fn name(&self) -> &str {
+        self.m_name.as_str()
+    }
+}
+
+/**
+ * Reads a little endian `u16` at the given offset
+ */
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buffer[offset..offset + 2].try_into().unwrap())
+}
+
+/**
+ * Reads a little endian `u32` at the given offset
+ */
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+}
+
+/**
+ * Reads a little endian `u64` at the given offset
+ */
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap())
+}
+
+/**
+ * Writes a little endian `u16` at the given offset
+ */
+fn write_u16(buffer: &mut [u8], offset: usize, value: u16) {
+    buffer[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/**
+ * Writes a little endian `u32` at the given offset
+ */
+fn write_u32(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/**
+ * Writes a little endian `u64` at the given offset
+ */
+fn write_u64(buffer: &mut [u8], offset: usize, value: u64) {
+    buffer[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
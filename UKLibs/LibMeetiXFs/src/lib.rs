@@ -0,0 +1,28 @@
+/*! # MeetiX Filesystem Library
+ *
+ * Implements the on-disk format of the standard MeetiX filesystem, an
+ * inode based filesystem with 64-bit addressing which stores the data
+ * into extents of variable size, the chunks.
+ *
+ * The library is shared by the kernel driver and by the host tools, so it
+ * accesses the media only through a `TBlockDevice`
+ */
+
+#![no_std]
+
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use api_data::error::class::OsErrorClass;
+
+pub mod crc;
+pub mod dev;
+pub mod layout;
+pub mod volume;
+
+/**
+ * Result type returned by the filesystem operations
+ */
+pub type MxFsResult<T> = Result<T, (OsErrorClass, Option<&'static str>)>;
@@ -0,0 +1,1354 @@
+/*! MeetiX filesystem volume */
+
+use alloc::{
+    collections::BTreeSet,
+    string::String,
+    vec::Vec
+};
+use core::cmp::{
+    max,
+    min
+};
+
+use api_data::{
+    error::class::OsErrorClass,
+    instant::RawInstant,
+    object::grants::{
+        ObjGrantsBits,
+        RawObjGrants
+    }
+};
+
+use crate::{
+    crc::crc32,
+    dev::TBlockDevice,
+    layout::{
+        ChunkFlagsBits,
+        ChunkHeader,
+        DirRecord,
+        Extent,
+        ExtentsBlock,
+        FileFlags,
+        FileFlagsBits,
+        Inode,
+        InodeInstant,
+        NodeType,
+        SuperBlock
+    },
+    MxFsResult
+};
+
+/**
+ * Function which returns the current `RawInstant` for the timestamps
+ */
+pub type ClockFn = fn() -> RawInstant;
+
+/**
+ * Parameters used by `Volume::format()`
+ */
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct FormatOptions {
+    m_inodes_count: Option<u64>,
+    m_blk_exp_max: u8,
+    m_frag_threshold: u8,
+    m_serial: u32,
+    m_label: String
+}
+
+impl FormatOptions /* Constants */ {
+    /**
+     * Default maximum `BLK-EXP` of the chunks
+     */
+    pub const DEFAULT_BLK_EXP_MAX: u8 = 7;
+
+    /**
+     * Default internal fragmentation percentage tolerated when a chunk is
+     * expanded
+     */
+    pub const DEFAULT_FRAG_THRESHOLD: u8 = 25;
+
+    /**
+     * Device blocks for each inode when the inodes count is not given
+     */
+    pub const BLOCKS_PER_INODE: u64 = 16;
+}
+
+impl FormatOptions /* Constructors */ {
+    /**
+     * Constructs the default `FormatOptions`
+     */
+    pub fn new() -> Self {
+        Self { m_inodes_count: None,
+               m_blk_exp_max: Self::DEFAULT_BLK_EXP_MAX,
+               m_frag_threshold: Self::DEFAULT_FRAG_THRESHOLD,
+               m_serial: 0,
+               m_label: String::new() }
+    }
+}
+
+impl FormatOptions /* Setters */ {
+    /**
+     * Sets the amount of inodes of the inodes table
+     */
+    pub fn set_inodes_count(&mut self, inodes_count: u64) -> &mut Self {
+        self.m_inodes_count = Some(inodes_count);
+        self
+    }
+
+    /**
+     * Sets the maximum `BLK-EXP` of the chunks
+     */
+    pub fn set_blk_exp_max(&mut self, blk_exp_max: u8) -> &mut Self {
+        self.m_blk_exp_max = blk_exp_max;
+        self
+    }
+
+    /**
+     * Sets the internal fragmentation percentage tolerated when a chunk is
+     * expanded
+     */
+    pub fn set_frag_threshold(&mut self, frag_threshold: u8) -> &mut Self {
+        self.m_frag_threshold = frag_threshold;
+        self
+    }
+
+    /**
+     * Sets the serial number of the volume
+     */
+    pub fn set_serial(&mut self, serial: u32) -> &mut Self {
+        self.m_serial = serial;
+        self
+    }
+
+    /**
+     * Sets the label of the volume
+     */
+    pub fn set_label(&mut self, label: &str) -> &mut Self {
+        self.m_label = String::from(label);
+        self
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * Mounted MeetiX filesystem.
+ *
+ * The blocks bitmap is kept in memory and written back by
+ * `Volume::sync()`, while the inodes and the data are written through
+ */
+pub struct Volume<D>
+    where D: TBlockDevice {
+    m_device: D,
+    m_super_block: SuperBlock,
+    m_was_clean: bool,
+    m_blocks_bitmap: Vec<u8>,
+    m_dirty_bitmap_blocks: BTreeSet<u64>,
+    m_next_free_block: u64,
+    m_next_free_inode: u64,
+    m_clock: ClockFn
+}
+
+impl<D> Volume<D> where D: TBlockDevice /* Constructors */ {
+    /**
+     * Formats the given `TBlockDevice` with an empty filesystem and
+     * mounts it
+     */
+    pub fn format(mut device: D,
+                  format_options: &FormatOptions,
+                  clock: ClockFn)
+                  -> MxFsResult<Self> {
+        let block_size = device.block_size();
+        if !block_size.is_power_of_two() || block_size < SuperBlock::BLOCK_SIZE_MIN {
+            return Err((OsErrorClass::InvalidArgument, Some("Unsupported block size")));
+        } else if format_options.m_blk_exp_max > ChunkHeader::BLK_EXP_MAX {
+            return Err((OsErrorClass::LimitOverflow, Some("BLK-EXP too big")));
+        }
+
+        let blocks_count = device.blocks_count();
+        let inodes_count =
+            format_options.m_inodes_count
+                          .unwrap_or(blocks_count / FormatOptions::BLOCKS_PER_INODE)
+                          .max(16);
+        let mut super_block = SuperBlock::new(block_size,
+                                              blocks_count,
+                                              inodes_count,
+                                              format_options.m_blk_exp_max,
+                                              format_options.m_frag_threshold,
+                                              format_options.m_serial,
+                                              &format_options.m_label);
+        let data_first_block = super_block.data_first_block();
+        if data_first_block + (1 << super_block.blk_exp_max()) > blocks_count {
+            return Err((OsErrorClass::LimitReached, Some("Device too small")));
+        }
+
+        /* the inode zero is never used */
+        super_block.set_free_inodes(inodes_count - 1);
+        super_block.set_clean(false);
+
+        /* clear the inodes table */
+        let zero_blocks = vec![0; block_size * 64];
+        let mut block_index = super_block.inodes_first_block();
+        while block_index < data_first_block {
+            let blocks_len = min(64, data_first_block - block_index);
+            device.write_blocks(block_index,
+                                &zero_blocks[..blocks_len as usize * block_size])?;
+            block_index += blocks_len;
+        }
+
+        let bitmap_bytes = super_block.bitmap_blocks() as usize * block_size;
+        let mut volume = Self { m_device: device,
+                                m_super_block: super_block,
+                                m_was_clean: true,
+                                m_blocks_bitmap: vec![0; bitmap_bytes],
+                                m_dirty_bitmap_blocks: BTreeSet::new(),
+                                m_next_free_block: data_first_block,
+                                m_next_free_inode: Inode::ROOT_ID,
+                                m_clock: clock };
+
+        /* the metadata areas are always used */
+        for bitmap_block in 0..volume.m_super_block.bitmap_blocks() {
+            volume.m_dirty_bitmap_blocks.insert(bitmap_block);
+        }
+        volume.set_blocks_used(SuperBlock::BLOCK_INDEX, data_first_block, true);
+
+        /* create the root directory */
+        let root_inode_id = volume.allocate_inode()?;
+        let mut root_inode =
+            Inode::new(root_inode_id, NodeType::Dir, default_prot_grants(), clock());
+        volume.store_inode(&mut root_inode)?;
+
+        volume.sync()?;
+        Ok(volume)
+    }
+
+    /**
+     * Mounts the filesystem stored into the given `TBlockDevice`
+     */
+    pub fn mount(mut device: D, clock: ClockFn) -> MxFsResult<Self> {
+        let block_size = device.block_size();
+        if !block_size.is_power_of_two() || block_size < SuperBlock::BLOCK_SIZE_MIN {
+            return Err((OsErrorClass::InvalidArgument, Some("Unsupported block size")));
+        }
+
+        let mut raw_super_block = vec![0; block_size];
+        device.read_blocks(SuperBlock::BLOCK_INDEX, &mut raw_super_block)?;
+        let mut super_block = SuperBlock::decode(&raw_super_block)?;
+        if super_block.block_size() != block_size
+           || super_block.blocks_count() > device.blocks_count()
+           || super_block.data_first_block() >= super_block.blocks_count()
+        {
+            return Err((OsErrorClass::TypesNotMatch,
+                        Some("Filesystem geometry not matches the device")));
+        }
+
+        /* load the blocks bitmap */
+        let mut blocks_bitmap =
+            vec![0; super_block.bitmap_blocks() as usize * block_size];
+        device.read_blocks(super_block.bitmap_first_block(), &mut blocks_bitmap)?;
+
+        /* the filesystem is dirty until it is unmounted */
+        let was_clean = super_block.is_clean();
+        super_block.set_clean(false);
+
+        let data_first_block = super_block.data_first_block();
+        let mut volume = Self { m_device: device,
+                                m_super_block: super_block,
+                                m_was_clean: was_clean,
+                                m_blocks_bitmap: blocks_bitmap,
+                                m_dirty_bitmap_blocks: BTreeSet::new(),
+                                m_next_free_block: data_first_block,
+                                m_next_free_inode: Inode::ROOT_ID,
+                                m_clock: clock };
+        volume.sync()?;
+        Ok(volume)
+    }
+}
+
+impl<D> Volume<D> where D: TBlockDevice /* Methods */ {
+    /**
+     * Marks the filesystem as clean and returns the `TBlockDevice`
+     */
+    pub fn unmount(mut self) -> MxFsResult<D> {
+        self.m_super_block.set_clean(true);
+        self.sync()?;
+        Ok(self.m_device)
+    }
+
+    /**
+     * Writes back the modified blocks bitmap and the `SuperBlock`
+     */
+    pub fn sync(&mut self) -> MxFsResult<()> {
+        let block_size = self.block_size();
+        let dirty_bitmap_blocks = core::mem::take(&mut self.m_dirty_bitmap_blocks);
+        for bitmap_block in dirty_bitmap_blocks {
+            let bitmap_range = bitmap_block as usize * block_size
+                               ..(bitmap_block as usize + 1) * block_size;
+            let device_block = self.m_super_block.bitmap_first_block() + bitmap_block;
+            self.m_device
+                .write_blocks(device_block, &self.m_blocks_bitmap[bitmap_range])?;
+        }
+
+        let mut raw_super_block = vec![0; block_size];
+        self.m_super_block.encode(&mut raw_super_block);
+        self.m_device.write_blocks(SuperBlock::BLOCK_INDEX, &raw_super_block)?;
+        self.m_device.flush()
+    }
+
+    /**
+     * Returns the child of the given directory with the given name
+     */
+    pub fn lookup(&mut self,
+                  dir_inode_id: u64,
+                  name: &str)
+                  -> MxFsResult<Option<DirRecord>> {
+        Ok(self.find_dir_record(dir_inode_id, name)?.map(|(_, dir_record)| dir_record))
+    }
+
+    /**
+     * Returns the child at the given position of the given directory
+     */
+    pub fn dir_record_at(&mut self,
+                         dir_inode_id: u64,
+                         index: usize)
+                         -> MxFsResult<Option<DirRecord>> {
+        let dir_records = self.dir_records(dir_inode_id)?;
+        Ok(dir_records.into_iter().nth(index).map(|(_, dir_record)| dir_record))
+    }
+
+    /**
+     * Returns all the children of the given directory with their record
+     * index
+     */
+    pub fn dir_records(&mut self,
+                       dir_inode_id: u64)
+                       -> MxFsResult<Vec<(usize, DirRecord)>> {
+        let dir_inode = self.load_inode(dir_inode_id)?;
+        if dir_inode.node_type() != NodeType::Dir {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a directory")));
+        }
+
+        let mut raw_records = vec![0; dir_inode.data_size() as usize];
+        self.read_inode_data(&dir_inode, 0, &mut raw_records)?;
+
+        let mut dir_records = Vec::new();
+        for (record_index, raw_record) in
+            raw_records.as_chunks::<{ DirRecord::ENCODED_SIZE }>().0.iter().enumerate()
+        {
+            if let Some(dir_record) = DirRecord::decode(raw_record)? {
+                dir_records.push((record_index, dir_record));
+            }
+        }
+        Ok(dir_records)
+    }
+
+    /**
+     * Creates into the given directory a new empty node and returns his
+     * inode
+     */
+    pub fn create_node(&mut self,
+                       dir_inode_id: u64,
+                       name: &str,
+                       node_type: NodeType,
+                       prot_grants: u32)
+                       -> MxFsResult<u64> {
+        validate_name(name)?;
+        if node_type == NodeType::Free {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid node type")));
+        } else if self.find_dir_record(dir_inode_id, name)?.is_some() {
+            return Err((OsErrorClass::IdentifierNotAvailable,
+                        Some("Name already in use")));
+        }
+
+        let inode_id = self.allocate_inode()?;
+        let mut inode = Inode::new(inode_id, node_type, prot_grants, self.now());
+        self.store_inode(&mut inode)?;
+
+        /* the inode is released when the directory can't store the record */
+        let dir_record = DirRecord::new(inode_id, node_type, name);
+        if let Err(err) = self.add_dir_record(dir_inode_id, &dir_record) {
+            self.free_inode(&mut inode)?;
+            return Err(err);
+        }
+        Ok(inode_id)
+    }
+
+    /**
+     * Removes the child with the given name from the given directory, the
+     * inode is released with his last link
+     */
+    pub fn remove_node(&mut self, dir_inode_id: u64, name: &str) -> MxFsResult<()> {
+        let (record_index, dir_record) = self.find_dir_record(dir_inode_id, name)?
+                                             .ok_or((OsErrorClass::ReferenceNotFound,
+                                                     Some("No such name in directory")))?;
+
+        let mut inode = self.load_inode(dir_record.inode_id())?;
+        if inode.node_type() == NodeType::Dir
+           && !self.dir_records(inode.inode_id())?.is_empty()
+        {
+            return Err((OsErrorClass::OperationNotEnabled, Some("Directory not empty")));
+        }
+
+        self.remove_dir_record(dir_inode_id, record_index)?;
+        if inode.links() > 1 {
+            inode.set_links(inode.links() - 1);
+            self.store_inode(&mut inode)
+        } else {
+            self.free_inode(&mut inode)
+        }
+    }
+
+    /**
+     * Creates into the given directory a copy of the given file which
+     * shares the chunks with it, unless the file is `FileFlagsBits::NoCow`
+     */
+    pub fn clone_file(&mut self,
+                      src_inode_id: u64,
+                      dir_inode_id: u64,
+                      name: &str)
+                      -> MxFsResult<u64> {
+        let src_inode = self.load_inode(src_inode_id)?;
+        if src_inode.node_type() != NodeType::File {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        }
+
+        let inode_id = self.create_node(dir_inode_id,
+                                        name,
+                                        NodeType::File,
+                                        src_inode.prot_grants())?;
+        let mut inode = self.load_inode(inode_id)?;
+        inode.set_file_flags(src_inode.file_flags());
+        inode.set_os_user(src_inode.os_user());
+        inode.set_os_group(src_inode.os_group());
+
+        if src_inode.file_flags().is_enabled(FileFlagsBits::NoCow) {
+            /* copy the data without sharing the chunks */
+            let mut copy_buffer = vec![0; self.m_super_block.chunk_capacity(0)];
+            let mut offset = 0;
+            while offset < src_inode.data_size() as usize {
+                let read_bytes =
+                    self.read_inode_data(&src_inode, offset, &mut copy_buffer)?;
+                self.append_data(&mut inode, &copy_buffer[..read_bytes])?;
+                offset += read_bytes;
+            }
+        } else {
+            for src_extent in src_inode.extents().iter() {
+                let mut chunk_header = self.read_chunk_header(src_extent.first_block())?;
+                if chunk_header.ref_count() < u16::MAX {
+                    chunk_header.set_ref_count(chunk_header.ref_count() + 1);
+                    self.write_chunk_header(src_extent.first_block(), &chunk_header)?;
+                    inode.extents_mut().push(*src_extent);
+                } else {
+                    /* the reference counter is saturated, the chunk is
+                     * copied */
+                    let raw_chunk = self.read_chunk(&src_inode, src_extent)?;
+                    let extent = self.allocate_chunk(src_extent.blk_exp())?;
+                    inode.extents_mut().push(Extent::new(extent.first_block(),
+                                                         src_extent.data_len() as u32,
+                                                         src_extent.blk_exp()));
+                    self.write_chunk(&inode, inode.extents().last().unwrap(), raw_chunk)?;
+                }
+            }
+            inode.set_data_size(src_inode.data_size());
+        }
+
+        self.store_inode(&mut inode)?;
+        Ok(inode_id)
+    }
+
+    /**
+     * Reads the data of the given inode starting from the given offset,
+     * returns the amount of bytes read
+     */
+    pub fn read_data(&mut self,
+                     inode_id: u64,
+                     offset: usize,
+                     buffer: &mut [u8])
+                     -> MxFsResult<usize> {
+        let inode = self.load_inode(inode_id)?;
+        self.read_inode_data(&inode, offset, buffer)
+    }
+
+    /**
+     * Writes the data of the given inode starting from the given offset,
+     * the hole between the end and the offset is zero filled
+     */
+    pub fn write_data(&mut self,
+                      inode_id: u64,
+                      offset: usize,
+                      buffer: &[u8])
+                      -> MxFsResult<usize> {
+        let mut inode = self.load_inode(inode_id)?;
+        self.write_inode_data(&mut inode, offset, buffer)?;
+        self.store_inode(&mut inode)?;
+        Ok(buffer.len())
+    }
+
+    /**
+     * Truncates or extends with zeroes the data of the given inode
+     */
+    pub fn set_data_size(&mut self, inode_id: u64, data_size: usize) -> MxFsResult<()> {
+        let mut inode = self.load_inode(inode_id)?;
+        self.resize_inode_data(&mut inode, data_size)?;
+        self.mark_data_modified(&mut inode);
+        self.store_inode(&mut inode)
+    }
+
+    /**
+     * Returns the path stored into the given link
+     */
+    pub fn read_link(&mut self, inode_id: u64) -> MxFsResult<String> {
+        let inode = self.load_inode(inode_id)?;
+        if inode.node_type() != NodeType::Link {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a link")));
+        }
+
+        let mut raw_target = vec![0; inode.data_size() as usize];
+        self.read_inode_data(&inode, 0, &mut raw_target)?;
+        String::from_utf8(raw_target).map_err(|_| {
+                                         (OsErrorClass::InvalidArgument,
+                                          Some("Corrupted link target"))
+                                     })
+    }
+
+    /**
+     * Stores the given path into the given link
+     */
+    pub fn write_link(&mut self, inode_id: u64, target: &str) -> MxFsResult<()> {
+        let mut inode = self.load_inode(inode_id)?;
+        if inode.node_type() != NodeType::Link {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a link")));
+        }
+
+        self.resize_inode_data(&mut inode, 0)?;
+        self.write_inode_data(&mut inode, 0, target.as_bytes())?;
+        self.store_inode(&mut inode)
+    }
+
+    /**
+     * Stores the given `DirRecord` into the first free record of the
+     * directory, the links of the referenced `Inode` are not updated
+     */
+    pub fn add_dir_record(&mut self,
+                          dir_inode_id: u64,
+                          dir_record: &DirRecord)
+                          -> MxFsResult<()> {
+        let mut dir_inode = self.load_inode(dir_inode_id)?;
+
+        let mut raw_records = vec![0; dir_inode.data_size() as usize];
+        self.read_inode_data(&dir_inode, 0, &mut raw_records)?;
+        let free_record_index =
+            raw_records.as_chunks::<{ DirRecord::ENCODED_SIZE }>()
+                       .0
+                       .iter()
+                       .position(|raw_record| {
+                           raw_record[..8] == Inode::NULL_ID.to_le_bytes()
+                       })
+                       .unwrap_or(raw_records.len() / DirRecord::ENCODED_SIZE);
+
+        let mut raw_record = [0; DirRecord::ENCODED_SIZE];
+        dir_record.encode(&mut raw_record);
+        self.write_inode_data(&mut dir_inode,
+                              free_record_index * DirRecord::ENCODED_SIZE,
+                              &raw_record)?;
+        self.store_inode(&mut dir_inode)
+    }
+
+    /**
+     * Clears the record at the given index of the directory, the trailing
+     * free records are truncated and the links of the referenced `Inode`
+     * are not updated
+     */
+    pub fn remove_dir_record(&mut self,
+                             dir_inode_id: u64,
+                             record_index: usize)
+                             -> MxFsResult<()> {
+        let mut dir_inode = self.load_inode(dir_inode_id)?;
+        self.write_inode_data(&mut dir_inode,
+                              record_index * DirRecord::ENCODED_SIZE,
+                              &[0; DirRecord::ENCODED_SIZE])?;
+
+        let mut raw_records = vec![0; dir_inode.data_size() as usize];
+        self.read_inode_data(&dir_inode, 0, &mut raw_records)?;
+        let used_records = raw_records.as_chunks::<{ DirRecord::ENCODED_SIZE }>()
+                                      .0
+                                      .iter()
+                                      .rposition(|raw_record| {
+                                          raw_record[..8] != Inode::NULL_ID.to_le_bytes()
+                                      })
+                                      .map_or(0, |last_used_index| last_used_index + 1);
+        self.resize_inode_data(&mut dir_inode, used_records * DirRecord::ENCODED_SIZE)?;
+
+        self.store_inode(&mut dir_inode)
+    }
+
+    /**
+     * Sets the `FileFlags` of the given file, which apply to the chunks
+     * written from now
+     */
+    pub fn set_file_flags(&mut self,
+                          inode_id: u64,
+                          file_flags: FileFlags)
+                          -> MxFsResult<()> {
+        let mut inode = self.load_inode(inode_id)?;
+        if inode.node_type() != NodeType::File {
+            return Err((OsErrorClass::TypesNotMatch, Some("Not a file")));
+        }
+
+        inode.set_file_flags(file_flags);
+        inode.set_instant(InodeInstant::InfoModify, self.now());
+        self.store_inode(&mut inode)
+    }
+
+    /**
+     * Loads the given `Inode` with all his `Extent`s
+     */
+    pub fn load_inode(&mut self, inode_id: u64) -> MxFsResult<Inode> {
+        if inode_id == Inode::NULL_ID || inode_id >= self.m_super_block.inodes_count() {
+            return Err((OsErrorClass::InvalidArgument, Some("Invalid inode")));
+        }
+
+        let (inode_block, inode_offset) = self.m_super_block.inode_location(inode_id);
+        let raw_block = self.read_block(inode_block)?;
+        let mut inode = Inode::decode(inode_id, &raw_block[inode_offset..])?;
+
+        /* follow the chain of the blocks of the exceeding extents */
+        let mut next_block = inode.extents_blocks().first().copied().unwrap_or(0);
+        let mut extents_blocks = Vec::new();
+        while next_block != 0 {
+            if !self.is_data_block(next_block) || extents_blocks.contains(&next_block) {
+                return Err((OsErrorClass::InvalidArgument,
+                            Some("Corrupted extents blocks chain")));
+            }
+
+            let raw_block = self.read_block(next_block)?;
+            extents_blocks.push(next_block);
+            next_block = ExtentsBlock::decode(&raw_block, inode.extents_mut())?;
+        }
+        *inode.extents_blocks_mut() = extents_blocks;
+
+        if inode.extents().len() != inode.extents_count() as usize {
+            return Err((OsErrorClass::InvalidArgument, Some("Corrupted inode extents")));
+        }
+        Ok(inode)
+    }
+
+    /**
+     * Stores the given `Inode` into the inodes table, allocating or
+     * releasing the blocks of the exceeding `Extent`s
+     */
+    pub fn store_inode(&mut self, inode: &mut Inode) -> MxFsResult<()> {
+        let block_size = self.block_size();
+        let extents_per_block = ExtentsBlock::capacity(block_size);
+        let exceeding_extents =
+            inode.extents().len().saturating_sub(Inode::INLINE_EXTENTS);
+        let needed_blocks = exceeding_extents.div_ceil(extents_per_block);
+
+        /* adapt the chain to the amount of exceeding extents */
+        while inode.extents_blocks().len() < needed_blocks {
+            let extents_block = self.allocate_blocks(1)
+                                    .ok_or((OsErrorClass::NotEnoughMemory,
+                                            Some("No space left on device")))?;
+            inode.extents_blocks_mut().push(extents_block);
+        }
+        while inode.extents_blocks().len() > needed_blocks {
+            let extents_block = inode.extents_blocks_mut().pop().unwrap();
+            self.set_blocks_used(extents_block, 1, false);
+        }
+
+        let mut raw_block = vec![0; block_size];
+        for (block_index, extents_block) in inode.extents_blocks().iter().enumerate() {
+            let first_extent = Inode::INLINE_EXTENTS + block_index * extents_per_block;
+            let last_extent =
+                min(first_extent + extents_per_block, inode.extents().len());
+            let next_block =
+                inode.extents_blocks().get(block_index + 1).copied().unwrap_or(0);
+
+            ExtentsBlock::encode(&mut raw_block,
+                                 &inode.extents()[first_extent..last_extent],
+                                 next_block);
+            self.m_device.write_blocks(*extents_block, &raw_block)?;
+        }
+
+        let (inode_block, inode_offset) =
+            self.m_super_block.inode_location(inode.inode_id());
+        let mut raw_block = self.read_block(inode_block)?;
+        inode.encode(&mut raw_block[inode_offset..]);
+        self.m_device.write_blocks(inode_block, &raw_block)
+    }
+
+    /**
+     * Reads the `ChunkHeader` of the chunk at the given block
+     */
+    pub fn read_chunk_header(&mut self, first_block: u64) -> MxFsResult<ChunkHeader> {
+        let raw_block = self.read_block(first_block)?;
+        Ok(ChunkHeader::decode(&raw_block))
+    }
+
+    /**
+     * Writes the `ChunkHeader` of the chunk at the given block, the data
+     * is untouched
+     */
+    pub fn write_chunk_header(&mut self,
+                              first_block: u64,
+                              chunk_header: &ChunkHeader)
+                              -> MxFsResult<()> {
+        let mut raw_block = self.read_block(first_block)?;
+        chunk_header.encode(&mut raw_block);
+        self.m_device.write_blocks(first_block, &raw_block)
+    }
+
+    /**
+     * Returns whether the stored CRC of the chunk matches his data,
+     * `None` if the chunk has no CRC
+     */
+    pub fn chunk_crc_matches(&mut self, extent: &Extent) -> MxFsResult<Option<bool>> {
+        let mut raw_chunk = vec![0; self.block_size() << extent.blk_exp()];
+        self.m_device.read_blocks(extent.first_block(), &mut raw_chunk)?;
+
+        let chunk_header = ChunkHeader::decode(&raw_chunk);
+        if chunk_header.flags().is_enabled(ChunkFlagsBits::HasCrc) {
+            Ok(Some(crc32(&raw_chunk[ChunkHeader::ENCODED_SIZE..]) == chunk_header.crc()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /**
+     * Returns whether the given device block is marked as used
+     */
+    pub fn is_block_used(&self, block_index: u64) -> bool {
+        self.m_blocks_bitmap[(block_index / 8) as usize] & (1 << (block_index % 8)) != 0
+    }
+
+    /**
+     * Marks the given range of device blocks as used or free, updating
+     * the free blocks counter
+     */
+    pub fn set_blocks_used(&mut self,
+                           first_block: u64,
+                           blocks_count: u64,
+                           is_used: bool) {
+        let block_bits = self.block_size() as u64 * 8;
+        let mut free_blocks = self.m_super_block.free_blocks();
+
+        for block_index in first_block..first_block + blocks_count {
+            if self.is_block_used(block_index) == is_used {
+                continue;
+            }
+
+            let bitmap_byte = &mut self.m_blocks_bitmap[(block_index / 8) as usize];
+            if is_used {
+                *bitmap_byte |= 1 << (block_index % 8);
+                free_blocks -= 1;
+            } else {
+                *bitmap_byte &= !(1 << (block_index % 8));
+                free_blocks += 1;
+
+                /* the released blocks are reused first */
+                self.m_next_free_block = min(self.m_next_free_block, block_index);
+            }
+            self.m_dirty_bitmap_blocks.insert(block_index / block_bits);
+        }
+        self.m_super_block.set_free_blocks(free_blocks);
+    }
+
+    /**
+     * Returns whether the given block is into the data area
+     */
+    pub fn is_data_block(&self, block_index: u64) -> bool {
+        block_index >= self.m_super_block.data_first_block()
+        && block_index < self.m_super_block.blocks_count()
+    }
+}
+
+impl<D> Volume<D> where D: TBlockDevice /* Getters */ {
+    /**
+     * Returns the `SuperBlock` of this volume
+     */
+    pub fn super_block(&self) -> &SuperBlock {
+        &self.m_super_block
+    }
+
+    /**
+     * Returns the mutable `SuperBlock` of this volume, used by the check
+     * tools to fix the counters
+     */
+    pub fn super_block_mut(&mut self) -> &mut SuperBlock {
+        &mut self.m_super_block
+    }
+
+    /**
+     * Returns whether the filesystem was cleanly unmounted before this
+     * mount
+     */
+    pub fn was_clean(&self) -> bool {
+        self.m_was_clean
+    }
+
+    /**
+     * Returns the size in bytes of the device blocks
+     */
+    pub fn block_size(&self) -> usize {
+        self.m_super_block.block_size()
+    }
+
+    /**
+     * Returns the underling `TBlockDevice`
+     */
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.m_device
+    }
+}
+
+impl<D> Volume<D> where D: TBlockDevice /* Privates */ {
+    /**
+     * Returns the current `RawInstant`
+     */
+    fn now(&self) -> RawInstant {
+        (self.m_clock)()
+    }
+
+    /**
+     * Reads a single device block
+     */
+    fn read_block(&mut self, block_index: u64) -> MxFsResult<Vec<u8>> {
+        let mut raw_block = vec![0; self.block_size()];
+        self.m_device.read_blocks(block_index, &mut raw_block)?;
+        Ok(raw_block)
+    }
+
+    /**
+     * Reads the data of the given `Inode` starting from the given offset
+     */
+    fn read_inode_data(&mut self,
+                       inode: &Inode,
+                       offset: usize,
+                       buffer: &mut [u8])
+                       -> MxFsResult<usize> {
+        let data_size = inode.data_size() as usize;
+        if offset >= data_size {
+            return Ok(0);
+        }
+        let read_end = min(data_size, offset + buffer.len());
+
+        let mut extent_start = 0;
+        for extent in inode.extents().iter() {
+            let extent_end = extent_start + extent.data_len();
+            if extent_end > offset && extent_start < read_end {
+                let raw_chunk = self.read_chunk(inode, extent)?;
+
+                let copy_start = max(offset, extent_start);
+                let copy_end = min(read_end, extent_end);
+                let chunk_offset = ChunkHeader::ENCODED_SIZE + copy_start - extent_start;
+                buffer[copy_start - offset..copy_end - offset]
+                    .copy_from_slice(&raw_chunk[chunk_offset
+                                                ..chunk_offset + copy_end - copy_start]);
+            }
+
+            extent_start = extent_end;
+            if extent_start >= read_end {
+                break;
+            }
+        }
+        Ok(read_end - offset)
+    }
+
+    /**
+     * Writes the data of the given `Inode` starting from the given offset
+     */
+    fn write_inode_data(&mut self,
+                        inode: &mut Inode,
+                        offset: usize,
+                        buffer: &[u8])
+                        -> MxFsResult<()> {
+        let write_end =
+            offset.checked_add(buffer.len())
+                  .ok_or((OsErrorClass::LimitOverflow, Some("File too big")))?;
+
+        /* the hole between the end and the offset is zero filled */
+        if offset > inode.data_size() as usize {
+            self.resize_inode_data(inode, offset)?;
+        }
+
+        /* overwrite the existing data then append the remaining */
+        let overwrite_end = min(write_end, inode.data_size() as usize);
+        if offset < overwrite_end {
+            self.overwrite_data(inode, offset, &buffer[..overwrite_end - offset])?;
+        }
+        if write_end > overwrite_end {
+            let append_start = max(offset, overwrite_end);
+            self.append_data(inode, &buffer[append_start - offset..])?;
+        }
+
+        self.mark_data_modified(inode);
+        Ok(())
+    }
+
+    /**
+     * Overwrites the data already stored into the chunks of the `Inode`
+     */
+    fn overwrite_data(&mut self,
+                      inode: &mut Inode,
+                      offset: usize,
+                      data: &[u8])
+                      -> MxFsResult<()> {
+        let write_end = offset + data.len();
+
+        let mut extent_start = 0;
+        for extent_index in 0..inode.extents().len() {
+            let extent_end = extent_start + inode.extents()[extent_index].data_len();
+            if extent_end > offset && extent_start < write_end {
+                let mut raw_chunk = self.own_chunk(inode, extent_index)?;
+
+                let copy_start = max(offset, extent_start);
+                let copy_end = min(write_end, extent_end);
+                let chunk_offset = ChunkHeader::ENCODED_SIZE + copy_start - extent_start;
+                raw_chunk[chunk_offset..chunk_offset + copy_end - copy_start]
+                    .copy_from_slice(&data[copy_start - offset..copy_end - offset]);
+
+                let extent = inode.extents()[extent_index];
+                self.write_chunk(inode, &extent, raw_chunk)?;
+            }
+
+            extent_start = extent_end;
+            if extent_start >= write_end {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Appends the given data at the end of the `Inode`, filling the last
+     * chunk, expanding it or allocating new chunks
+     */
+    fn append_data(&mut self, inode: &mut Inode, mut data: &[u8]) -> MxFsResult<()> {
+        while !data.is_empty() {
+            if let Some(last_extent) = inode.extents().last().copied() {
+                let chunk_capacity =
+                    self.m_super_block.chunk_capacity(last_extent.blk_exp());
+
+                /* fill the free space of the last chunk */
+                if last_extent.data_len() < chunk_capacity {
+                    let last_extent_index = inode.extents().len() - 1;
+                    let fill_len =
+                        min(chunk_capacity - last_extent.data_len(), data.len());
+
+                    let mut raw_chunk = self.own_chunk(inode, last_extent_index)?;
+                    let chunk_offset = ChunkHeader::ENCODED_SIZE + last_extent.data_len();
+                    raw_chunk[chunk_offset..chunk_offset + fill_len]
+                        .copy_from_slice(&data[..fill_len]);
+
+                    let last_extent = &mut inode.extents_mut()[last_extent_index];
+                    last_extent.set_data_len(last_extent.data_len() + fill_len);
+
+                    let last_extent = *last_extent;
+                    self.write_chunk(inode, &last_extent, raw_chunk)?;
+                    inode.set_data_size(inode.data_size() + fill_len as u64);
+                    data = &data[fill_len..];
+                    continue;
+                }
+
+                /* the last chunk is full, expand it when convenient */
+                if self.expand_last_chunk(inode, data.len())? {
+                    continue;
+                }
+            }
+
+            let extent = self.allocate_chunk(self.fitting_blk_exp(data.len()))?;
+            let raw_chunk = vec![0; self.block_size() << extent.blk_exp()];
+            self.write_chunk(inode, &extent, raw_chunk)?;
+            inode.extents_mut().push(extent);
+        }
+        Ok(())
+    }
+
+    /**
+     * Expands the last chunk of the `Inode` to fit the given amount of
+     * bytes, returns `false` when a new chunk is preferable.
+     *
+     * The expansion is refused when it wastes more than the
+     * `SuperBlock::frag_threshold()` and a new chunk wastes less, unless
+     * the file is `FileFlagsBits::LowFragment`
+     */
+    fn expand_last_chunk(&mut self,
+                         inode: &mut Inode,
+                         needed_len: usize)
+                         -> MxFsResult<bool> {
+        let last_extent_index = inode.extents().len() - 1;
+        let last_extent = inode.extents()[last_extent_index];
+        if last_extent.blk_exp() >= self.m_super_block.blk_exp_max() {
+            return Ok(false);
+        }
+
+        let used_len = last_extent.data_len();
+        let expand_blk_exp =
+            max(self.fitting_blk_exp(used_len + needed_len), last_extent.blk_exp() + 1);
+        let expand_capacity = self.m_super_block.chunk_capacity(expand_blk_exp);
+        let expand_waste = expand_capacity.saturating_sub(used_len + needed_len);
+
+        let new_blk_exp = self.fitting_blk_exp(needed_len);
+        let new_waste =
+            self.m_super_block.chunk_capacity(new_blk_exp).saturating_sub(needed_len);
+
+        if !inode.file_flags().is_enabled(FileFlagsBits::LowFragment)
+           && expand_waste * 100
+              > self.m_super_block.frag_threshold() as usize * expand_capacity
+           && new_waste < expand_waste
+        {
+            return Ok(false);
+        }
+
+        /* expand in place when the following blocks are free, otherwise move
+         * the chunk into a bigger area
+         */
+        let mut raw_chunk = self.read_chunk(inode, &last_extent)?;
+        let chunk_header = self.read_chunk_header(last_extent.first_block())?;
+        let following_block = last_extent.first_block() + last_extent.blocks_count();
+        let following_blocks = (1 << expand_blk_exp) - last_extent.blocks_count();
+        let first_block = if chunk_header.ref_count() == 1
+                             && self.are_blocks_free(following_block, following_blocks)
+        {
+            self.set_blocks_used(following_block, following_blocks, true);
+            last_extent.first_block()
+        } else if let Some(first_block) = self.allocate_blocks(1 << expand_blk_exp) {
+            self.release_chunk(&last_extent)?;
+            first_block
+        } else {
+            return Ok(false);
+        };
+
+        let expanded_extent = Extent::new(first_block, used_len as u32, expand_blk_exp);
+        raw_chunk.resize(self.block_size() << expand_blk_exp, 0);
+        self.write_chunk(inode, &expanded_extent, raw_chunk)?;
+        inode.extents_mut()[last_extent_index] = expanded_extent;
+        Ok(true)
+    }
+
+    /**
+     * Truncates or extends with zeroes the data of the `Inode`
+     */
+    fn resize_inode_data(&mut self,
+                         inode: &mut Inode,
+                         data_size: usize)
+                         -> MxFsResult<()> {
+        let current_size = inode.data_size() as usize;
+        if data_size > current_size {
+            let zero_buffer = vec![0; self.m_super_block.chunk_capacity(0)];
+
+            let mut remaining_len = data_size - current_size;
+            while remaining_len > 0 {
+                let append_len = min(remaining_len, zero_buffer.len());
+                self.append_data(inode, &zero_buffer[..append_len])?;
+                remaining_len -= append_len;
+            }
+        } else if data_size < current_size {
+            let extents = core::mem::take(inode.extents_mut());
+
+            let mut kept_len = 0;
+            for mut extent in extents.into_iter() {
+                if kept_len >= data_size {
+                    self.release_chunk(&extent)?;
+                } else {
+                    let extent_len = min(extent.data_len(), data_size - kept_len);
+                    extent.set_data_len(extent_len);
+                    inode.extents_mut().push(extent);
+                    kept_len += extent_len;
+                }
+            }
+            inode.set_data_size(data_size as u64);
+        }
+        Ok(())
+    }
+
+    /**
+     * Updates the timestamps of the `Inode` after a modification of the
+     * data
+     */
+    fn mark_data_modified(&self, inode: &mut Inode) {
+        let now = self.now();
+        inode.set_instant(InodeInstant::DataAccess, now);
+        inode.set_instant(InodeInstant::DataModify, now);
+    }
+
+    /**
+     * Reads the whole chunk of the given `Extent`, header included,
+     * checking the CRC when requested
+     */
+    fn read_chunk(&mut self, inode: &Inode, extent: &Extent) -> MxFsResult<Vec<u8>> {
+        if !self.is_data_block(extent.first_block())
+           || !self.is_data_block(extent.first_block() + extent.blocks_count() - 1)
+        {
+            return Err((OsErrorClass::InvalidArgument, Some("Corrupted extent")));
+        }
+
+        let mut raw_chunk = vec![0; self.block_size() << extent.blk_exp()];
+        self.m_device.read_blocks(extent.first_block(), &mut raw_chunk)?;
+
+        /* the files without CRC skip the check only for their own chunks */
+        let chunk_header = ChunkHeader::decode(&raw_chunk);
+        let check_crc = chunk_header.flags().is_enabled(ChunkFlagsBits::HasCrc)
+                        && (chunk_header.ref_count() > 1
+                            || !inode.file_flags().is_enabled(FileFlagsBits::NoCrc));
+        if chunk_header.blk_exp() != extent.blk_exp() {
+            return Err((OsErrorClass::InvalidArgument, Some("Corrupted chunk header")));
+        } else if check_crc
+                  && crc32(&raw_chunk[ChunkHeader::ENCODED_SIZE..]) != chunk_header.crc()
+        {
+            return Err((OsErrorClass::InvalidArgument, Some("Chunk CRC mismatch")));
+        }
+        Ok(raw_chunk)
+    }
+
+    /**
+     * Reads the chunk at the given index of the `Inode` for a
+     * modification, the shared chunk is copied first (copy-on-write)
+     */
+    fn own_chunk(&mut self,
+                 inode: &mut Inode,
+                 extent_index: usize)
+                 -> MxFsResult<Vec<u8>> {
+        let extent = inode.extents()[extent_index];
+        let raw_chunk = self.read_chunk(inode, &extent)?;
+
+        let mut chunk_header = ChunkHeader::decode(&raw_chunk);
+        if chunk_header.ref_count() > 1 {
+            let new_extent = self.allocate_chunk(extent.blk_exp())?;
+            chunk_header.set_ref_count(chunk_header.ref_count() - 1);
+            self.write_chunk_header(extent.first_block(), &chunk_header)?;
+
+            inode.extents_mut()[extent_index] = Extent::new(new_extent.first_block(),
+                                                            extent.data_len() as u32,
+                                                            extent.blk_exp());
+        }
+        Ok(raw_chunk)
+    }
+
+    /**
+     * Writes the whole chunk of the given `Extent` as referenced only by
+     * the `Inode`
+     */
+    fn write_chunk(&mut self,
+                   inode: &Inode,
+                   extent: &Extent,
+                   mut raw_chunk: Vec<u8>)
+                   -> MxFsResult<()> {
+        let mut chunk_header = ChunkHeader::new(extent.blk_exp());
+        if !inode.file_flags().is_enabled(FileFlagsBits::NoCrc) {
+            let mut chunk_flags = chunk_header.flags();
+            chunk_flags.set_enabled(ChunkFlagsBits::HasCrc);
+            chunk_header.set_flags(chunk_flags);
+            chunk_header.set_crc(crc32(&raw_chunk[ChunkHeader::ENCODED_SIZE..]));
+        }
+
+        chunk_header.encode(&mut raw_chunk);
+        self.m_device.write_blocks(extent.first_block(), &raw_chunk)
+    }
+
+    /**
+     * Decrements the reference counter of the chunk, which is released
+     * with the last reference
+     */
+    fn release_chunk(&mut self, extent: &Extent) -> MxFsResult<()> {
+        let mut chunk_header = self.read_chunk_header(extent.first_block())?;
+        if chunk_header.ref_count() > 1 {
+            chunk_header.set_ref_count(chunk_header.ref_count() - 1);
+            self.write_chunk_header(extent.first_block(), &chunk_header)
+        } else {
+            self.set_blocks_used(extent.first_block(), extent.blocks_count(), false);
+            Ok(())
+        }
+    }
+
+    /**
+     * Allocates an empty chunk of the given `BLK-EXP`, smaller chunks are
+     * tried when there is no contiguous space
+     */
+    fn allocate_chunk(&mut self, mut blk_exp: u8) -> MxFsResult<Extent> {
+        loop {
+            if let Some(first_block) = self.allocate_blocks(1 << blk_exp) {
+                return Ok(Extent::new(first_block, 0, blk_exp));
+            } else if blk_exp == 0 {
+                return Err((OsErrorClass::NotEnoughMemory,
+                            Some("No space left on device")));
+            }
+            blk_exp -= 1;
+        }
+    }
+
+    /**
+     * Returns the smallest `BLK-EXP` of a chunk which stores the given
+     * amount of bytes, the maximum one if none is enough
+     */
+    fn fitting_blk_exp(&self, data_len: usize) -> u8 {
+        (0..=self.m_super_block.blk_exp_max()).find(|blk_exp| {
+                                                  self.m_super_block
+                                                      .chunk_capacity(*blk_exp)
+                                                  >= data_len
+                                              })
+                                              .unwrap_or(self.m_super_block.blk_exp_max())
+    }
+
+    /**
+     * Allocates the first range of contiguous free blocks
+     */
+    fn allocate_blocks(&mut self, blocks_count: u64) -> Option<u64> {
+        if self.m_super_block.free_blocks() < blocks_count {
+            return None;
+        }
+
+        let data_first_block = self.m_super_block.data_first_block();
+        let blocks_end = self.m_super_block.blocks_count();
+        let search_start = max(self.m_next_free_block, data_first_block);
+
+        /* search from the hint then wrap to the start of the data area */
+        for (range_start, range_end) in
+            [(search_start, blocks_end), (data_first_block, search_start)].iter()
+        {
+            let mut free_start = *range_start;
+            let mut block_index = *range_start;
+            while block_index < min(*range_end + blocks_count, blocks_end) {
+                if self.is_block_used(block_index) {
+                    free_start = block_index + 1;
+                } else if block_index + 1 - free_start == blocks_count {
+                    self.set_blocks_used(free_start, blocks_count, true);
+                    self.m_next_free_block = free_start + blocks_count;
+                    return Some(free_start);
+                }
+                block_index += 1;
+            }
+        }
+        None
+    }
+
+    /**
+     * Returns whether all the blocks of the given range are free
+     */
+    fn are_blocks_free(&self, first_block: u64, blocks_count: u64) -> bool {
+        first_block + blocks_count <= self.m_super_block.blocks_count()
+        && (first_block..first_block + blocks_count).all(|block_index| {
+                                                        !self.is_block_used(block_index)
+                                                    })
+    }
+
+    /**
+     * Allocates a free `Inode`
+     */
+    fn allocate_inode(&mut self) -> MxFsResult<u64> {
+        if self.m_super_block.free_inodes() == 0 {
+            return Err((OsErrorClass::LimitReached, Some("No free inodes")));
+        }
+
+        let inodes_count = self.m_super_block.inodes_count();
+        let inodes_per_block = (self.block_size() / Inode::ENCODED_SIZE) as u64;
+
+        let mut inode_id = max(self.m_next_free_inode, Inode::ROOT_ID) % inodes_count;
+        let mut scanned_inodes = 0;
+        while scanned_inodes < inodes_count {
+            let (inode_block, _) = self.m_super_block.inode_location(inode_id);
+            let raw_block = self.read_block(inode_block)?;
+
+            /* check all the remaining inodes of the block before read the
+             * next */
+            loop {
+                let (_, inode_offset) = self.m_super_block.inode_location(inode_id);
+                if inode_id != Inode::NULL_ID
+                   && raw_block[inode_offset] == NodeType::Free as u8
+                {
+                    self.m_next_free_inode = inode_id + 1;
+                    self.m_super_block
+                        .set_free_inodes(self.m_super_block.free_inodes() - 1);
+                    return Ok(inode_id);
+                }
+
+                scanned_inodes += 1;
+                inode_id = (inode_id + 1) % inodes_count;
+                if inode_id.is_multiple_of(inodes_per_block)
+                   || scanned_inodes >= inodes_count
+                {
+                    break;
+                }
+            }
+        }
+        Err((OsErrorClass::LimitReached, Some("No free inodes")))
+    }
+
+    /**
+     * Releases the data and the `Inode` itself
+     */
+    fn free_inode(&mut self, inode: &mut Inode) -> MxFsResult<()> {
+        self.resize_inode_data(inode, 0)?;
+
+        let mut free_inode = Inode::new(inode.inode_id(), NodeType::Free, 0, self.now());
+        free_inode.set_links(0);
+        *free_inode.extents_blocks_mut() = core::mem::take(inode.extents_blocks_mut());
+        self.store_inode(&mut free_inode)?;
+
+        self.m_super_block.set_free_inodes(self.m_super_block.free_inodes() + 1);
+        self.m_next_free_inode = min(self.m_next_free_inode, inode.inode_id());
+        Ok(())
+    }
+
+    /**
+     * Returns the index and the `DirRecord` with the given name
+     */
+    fn find_dir_record(&mut self,
+                       dir_inode_id: u64,
+                       name: &str)
+                       -> MxFsResult<Option<(usize, DirRecord)>> {
+        let dir_records = self.dir_records(dir_inode_id)?;
+        Ok(dir_records.into_iter().find(|(_, dir_record)| dir_record.name() == name))
+    }
+}
+
+/**
+ * Returns the grants given to the new nodes, everything to the owner and
+ * only the read and the traversal to the others
+ */
+pub fn default_prot_grants() -> u32 {
+    let mut prot_grants = RawObjGrants::new_zero();
+    for grant_bit in [ObjGrantsBits::UserCanOpenIt,
+                      ObjGrantsBits::UserCanReadData,
+                      ObjGrantsBits::UserCanWriteData,
+                      ObjGrantsBits::UserCanExecTraversData,
+                      ObjGrantsBits::UserCanReadInfo,
+                      ObjGrantsBits::UserCanWriteInfo,
+                      ObjGrantsBits::UserCanSeeIt,
+                      ObjGrantsBits::GroupCanOpenIt,
+                      ObjGrantsBits::GroupCanReadData,
+                      ObjGrantsBits::GroupCanExecTraversData,
+                      ObjGrantsBits::GroupCanReadInfo,
+                      ObjGrantsBits::GroupCanSeeIt,
+                      ObjGrantsBits::OtherCanOpenIt,
+                      ObjGrantsBits::OtherCanReadData,
+                      ObjGrantsBits::OtherCanExecTraversData,
+                      ObjGrantsBits::OtherCanReadInfo,
+                      ObjGrantsBits::OtherCanSeeIt].iter()
+    {
+        prot_grants.set_enabled(*grant_bit);
+    }
+    prot_grants.raw_bits() as u32
+}
+
+/**
+ * Validates the given name of a directory record
+ */
+pub fn validate_name(name: &str) -> MxFsResult<()> {
+    if name.is_empty()
+       || name.len() > DirRecord::NAME_LEN_MAX
+       || name.contains('/')
+       || name.contains('\0')
+       || name == "."
+       || name == ".."
+    {
+        Err((OsErrorClass::InvalidArgument, Some("Invalid name")))
+    } else {
+        Ok(())
+    }
+}
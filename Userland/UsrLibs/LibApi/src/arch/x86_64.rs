@@ -11,7 +11,16 @@ use api_data::sys::{
  */
 #[inline(always)]
 pub(crate) fn do_syscall(syscall_payload: &mut SysCallPayload) {
+    /* the kernel entry uses r9 and r10 as scratch registers, while rcx and
+     * r11 are clobbered by the instruction itself
+     */
     unsafe {
-        asm!("syscall", in("rax") syscall_payload.as_syscall_ptr(), options(nostack));
+        asm!("syscall",
+             in("rax") syscall_payload.as_syscall_ptr(),
+             out("rcx") _,
+             out("r9") _,
+             out("r10") _,
+             out("r11") _,
+             options(nostack));
     }
 }
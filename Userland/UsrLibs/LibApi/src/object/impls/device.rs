@@ -71,7 +71,7 @@ impl Device /* Methods */ {
     pub fn write<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
         self.obj_handle()
             .kern_handle()
-            .inst_kern_call_2(KernFnPath::Device(KernDeviceFnId::Read),
+            .inst_kern_call_2(KernFnPath::Device(KernDeviceFnId::Write),
                               buf.as_ptr() as usize,
                               buf.len())
             .map(|written_bytes| &buf[written_bytes..])
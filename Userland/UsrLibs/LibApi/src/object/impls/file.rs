@@ -129,7 +129,7 @@ impl File /* Methods */ {
                          -> Result<MMap> {
         self.obj_handle()
             .kern_handle()
-            .inst_kern_call_4(KernFnPath::File(KernFileFnId::Move),
+            .inst_kern_call_4(KernFnPath::File(KernFileFnId::MapToMem),
                               &map_addr as *const _ as usize,
                               from_off,
                               mmap_size,
@@ -115,7 +115,8 @@ impl MMap /* Methods */ {
     pub fn is_device_backed(&self) -> Result<bool> {
         self.obj_handle()
             .info()
-            .map(|raw_obj_info| !raw_obj_info.device_id().device_class().is_storage())
+            .map(|raw_obj_info| raw_obj_info.device_id().device_class())
+            .map(|device_class| !device_class.is_storage() && !device_class.is_memory())
     }
 }
 
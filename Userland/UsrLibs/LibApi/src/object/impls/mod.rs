@@ -7,3 +7,4 @@ pub mod ipc_chan;
 pub mod link;
 pub mod mmap;
 pub mod mutex;
+pub mod socket;
@@ -0,0 +1,201 @@
+/*! Network Socket `Object` */
+
+use api_data::{
+    object::{
+        modes::ObjRecvMode,
+        socket::{
+            SocketAddr,
+            SocketType
+        },
+        types::ObjType
+    },
+    sys::{
+        codes::KernSocketFnId,
+        fn_path::KernFnPath,
+        TAsSysCallPtr
+    }
+};
+
+use crate::{
+    kern_handle::{
+        KernHandle,
+        Result
+    },
+    object::{
+        ObjHandle,
+        TObject
+    }
+};
+
+/**
+ * End-point of a network communication.
+ *
+ * The `SocketType::Udp` sockets exchange datagrams with any remote
+ * end-point, the `SocketType::Tcp` ones exchange a stream of bytes with a
+ * connected end-point
+ */
+#[repr(transparent)]
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+#[derive(Eq, PartialEq)]
+#[derive(Ord, PartialOrd)]
+#[derive(Hash)]
+pub struct Socket {
+    m_obj_handle: ObjHandle
+}
+
+impl Socket /* Constructors */ {
+    /**
+     * Opens a new unbound `Socket` of the given `SocketType`
+     */
+    pub fn new(socket_type: SocketType) -> Result<Self> {
+        KernHandle::kern_call_1(KernFnPath::Socket(KernSocketFnId::Open),
+                                socket_type.into())
+            .map(|raw_obj_handle| Self::from(ObjHandle::from_raw(raw_obj_handle)))
+    }
+}
+
+impl Socket /* Methods */ {
+    /**
+     * Binds this `Socket` to the given local `SocketAddr`.
+     *
+     * The port `0` makes the kernel select a free one
+     */
+    pub fn bind(&self, local_addr: SocketAddr) -> Result<()> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_1(KernFnPath::Socket(KernSocketFnId::Bind),
+                              local_addr.as_syscall_ptr())
+            .map(|_| ())
+    }
+
+    /**
+     * Connects this `Socket` to the given remote `SocketAddr`.
+     *
+     * For `SocketType::Tcp` waits the establishment of the connection, for
+     * `SocketType::Udp` only sets the default destination
+     */
+    pub fn connect(&self, remote_addr: SocketAddr) -> Result<()> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_1(KernFnPath::Socket(KernSocketFnId::Connect),
+                              remote_addr.as_syscall_ptr())
+            .map(|_| ())
+    }
+
+    /**
+     * Makes this bound `SocketType::Tcp` socket accept the incoming
+     * connections, at max `backlog` waiting to be accepted
+     */
+    pub fn listen(&self, backlog: usize) -> Result<()> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_1(KernFnPath::Socket(KernSocketFnId::Listen), backlog)
+            .map(|_| ())
+    }
+
+    /**
+     * Accepts, according to the given `ObjRecvMode`, an incoming
+     * connection.
+     *
+     * Returns the connected `Socket` and the remote `SocketAddr`
+     */
+    pub fn accept(&self, recv_mode: ObjRecvMode) -> Result<(Socket, SocketAddr)> {
+        let mut remote_addr = SocketAddr::default();
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_2(KernFnPath::Socket(KernSocketFnId::Accept),
+                              recv_mode.into(),
+                              remote_addr.as_syscall_ptr_mut())
+            .map(|raw_obj_handle| {
+                (Self::from(ObjHandle::from_raw(raw_obj_handle)), remote_addr)
+            })
+    }
+
+    /**
+     * Sends the given buffer to the connected end-point.
+     *
+     * Returns the unsent sub-slice of the given buffer
+     */
+    pub fn send<'a>(&self, buf: &'a [u8]) -> Result<&'a [u8]> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_2(KernFnPath::Socket(KernSocketFnId::Send),
+                              buf.as_ptr() as usize,
+                              buf.len())
+            .map(|sent_bytes| &buf[sent_bytes..])
+    }
+
+    /**
+     * Receives, according to the given `ObjRecvMode`, the data of the
+     * connected end-point.
+     *
+     * Returns the filled sub-slice of the given buffer, which is empty
+     * when the remote end-point closed the connection
+     */
+    pub fn recv<'a>(&self,
+                    recv_mode: ObjRecvMode,
+                    buf: &'a mut [u8])
+                    -> Result<&'a [u8]> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_3(KernFnPath::Socket(KernSocketFnId::Recv),
+                              recv_mode.into(),
+                              buf.as_mut_ptr() as usize,
+                              buf.len())
+            .map(move |recv_bytes| &buf[..recv_bytes])
+    }
+
+    /**
+     * Sends the given buffer as a single datagram to the given remote
+     * `SocketAddr`
+     */
+    pub fn send_to(&self, buf: &[u8], remote_addr: SocketAddr) -> Result<usize> {
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_3(KernFnPath::Socket(KernSocketFnId::SendTo),
+                              buf.as_ptr() as usize,
+                              buf.len(),
+                              remote_addr.as_syscall_ptr())
+    }
+
+    /**
+     * Receives, according to the given `ObjRecvMode`, the next datagram.
+     *
+     * Returns the filled sub-slice of the given buffer and the sender
+     * `SocketAddr`
+     */
+    pub fn recv_from<'a>(&self,
+                         recv_mode: ObjRecvMode,
+                         buf: &'a mut [u8])
+                         -> Result<(&'a [u8], SocketAddr)> {
+        let mut src_addr = SocketAddr::default();
+        self.obj_handle()
+            .kern_handle()
+            .inst_kern_call_4(KernFnPath::Socket(KernSocketFnId::RecvFrom),
+                              recv_mode.into(),
+                              buf.as_mut_ptr() as usize,
+                              buf.len(),
+                              src_addr.as_syscall_ptr_mut())
+            .map(move |recv_bytes| (&buf[..recv_bytes], src_addr))
+    }
+}
+
+impl From<ObjHandle> for Socket {
+    fn from(obj_handle: ObjHandle) -> Self {
+        Self { m_obj_handle: obj_handle }
+    }
+}
+
+impl TObject for Socket {
+    const TYPE: ObjType = ObjType::Socket;
+
+    fn obj_handle(&self) -> &ObjHandle {
+        &self.m_obj_handle
+    }
+
+    fn obj_handle_mut(&mut self) -> &mut ObjHandle {
+        &mut self.m_obj_handle
+    }
+}
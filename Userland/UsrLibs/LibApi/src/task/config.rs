@@ -172,6 +172,18 @@ impl<'a, T> TaskConfig<'a, T, CreatMode> where T: TTask /* Setters */ {
         self
     }
 
+    /**
+     * Requests for the new `Task` the real-time scheduling, which reserves
+     * `budget` ticks each `period` ticks.
+     *
+     * The kernel refuses the `Task` when the reservation could not be
+     * guaranteed
+     */
+    pub fn with_real_time(&mut self, period: u64, budget: u64) -> &mut Self {
+        self.m_raw_config.set_rt_params(period, budget);
+        self
+    }
+
     /**
      * Forces the kernel to not start as soon-as-possible the new `Task`
      */
@@ -9,6 +9,22 @@ use api_data::{
     task::exit_status::TaskExitStatus
 };
 
+extern "C" {
+    /* generated by rustc for the binaries, calls <lang_start()> */
+    fn main(argc: isize, argv: *const *const u8) -> isize;
+}
+
+/**
+ * ELF entry point, the kernel jumps here with the arguments already on
+ * the user stack of the main thread
+ */
+#[no_mangle]
+unsafe extern "C" fn _start(argc: isize, argv: *const *const u8) -> ! {
+    main(argc, argv);
+
+    unreachable!("lang_start() returned without exiting")
+}
+
 /**
  * Entry point for userspace applications
  */
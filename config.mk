@@ -69,6 +69,7 @@ MV      ?= $(shell which mv)
 MKDIR   ?= $(shell which mkdir)
 RFILT   ?= $(shell which rustfilt)
 OBJCOPY ?= $(shell which objcopy)
+TAR     ?= $(shell which tar)
 
 ifeq ($(ARCH), x86_64)
     MAKE_RESCUE ?= $(shell which grub-mkrescue)
@@ -79,7 +80,8 @@ endif
 #
 
 QEMU             ?= qemu-system-$(ARCH)
-QEMU_SHARED_ARGS ?= -m 64M $(VIRT_ACCEL) -cpu host -smp $(SMP_CORES)
+QEMU_SHARED_ARGS ?= -m 64M $(VIRT_ACCEL) -cpu host -smp $(SMP_CORES) \
+                    -netdev user,id=net0 -device virtio-net-pci,netdev=net0
 QEMU_ARGS        ?= $(QEMU_SHARED_ARGS) -serial stdio
 QEMU_GDB_ARGS    ?= $(QEMU_SHARED_ARGS)
 